@@ -37,6 +37,25 @@ pub enum Error {
     #[error("expand")]
     Expand(#[from] strategy::Error),
 
+    /// Unable to expand an archive, and [`crate::ErrorPolicy::FailFast`] is configured in [`crate::Options`].
+    #[error("expand {path:?}")]
+    ExpandArchive {
+        /// The path of the archive, as it would have been reported with a warning:
+        /// the walker reports the path of the entry, while [`crate::expand::all()`] reports the path on disk.
+        path: PathBuf,
+
+        /// The error encountered when attempting to expand the archive.
+        #[source]
+        error: strategy::Error,
+    },
+
+    /// An archive named in the pattern provided to [`crate::expand::extract()`] doesn't exist.
+    #[error("archive {path:?} not found")]
+    ArchiveNotFound {
+        /// The logical path of the archive, relative to the target.
+        path: PathBuf,
+    },
+
     /// Unable to remove a [`Destination`].
     #[error("cleanup destination {destination:?}")]
     Cleanup {
@@ -48,10 +67,55 @@ pub enum Error {
         error: io::Error,
     },
 
+    /// Unable to determine the free space on the volume containing the extraction root.
+    #[error("determine free space for extraction root {root:?}")]
+    ExtractionRootSpace {
+        /// The extraction root.
+        root: PathBuf,
+
+        /// The error encountered when attempting to determine free space.
+        #[source]
+        error: io::Error,
+    },
+
     /// Reached the recursion limit.
     #[error("recursion limit")]
     RecursionLimit,
 
+    /// A file in the archive was larger than the limit configured in [`crate::Options`], so it was removed.
+    #[error("file {path:?} in archive exceeds {limit} bytes")]
+    EntryTooLarge {
+        /// The path of the file, relative to the root of the archive.
+        path: PathBuf,
+
+        /// The configured limit.
+        limit: u64,
+    },
+
+    /// A special file in the archive was not expanded.
+    #[error("skipped {kind:?} {path:?} in archive")]
+    SpecialFile {
+        /// The path of the file, relative to the root of the archive.
+        path: PathBuf,
+
+        /// The kind of special file.
+        kind: SpecialFile,
+    },
+
+    /// The file looks like an archive, but no strategy supports expanding it.
+    ///
+    /// Only reported if enabled in [`crate::Options`].
+    #[error("unsupported archive format: {format}")]
+    UnsupportedArchive {
+        /// The format the file appears to be, identified by its magic bytes (such as `zip`)
+        /// or otherwise by its extension (such as `.rar`).
+        format: String,
+    },
+
+    /// The operation was cancelled via the cancellation token provided in [`crate::Options`].
+    #[error("cancelled")]
+    Cancelled,
+
     /// Attempted to render a path, but it could not be made relative.
     #[error("render {child:?} relative to {parent:?}")]
     RenderPathRelative {
@@ -65,6 +129,33 @@ pub enum Error {
     },
 }
 
+/// Kinds of special files in archives, which are skipped when the archive is expanded.
+///
+/// These are reported via [`Error::SpecialFile`] as warnings on the archive.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum SpecialFile {
+    /// A hard link to a file that isn't in the archive, or that appears in the archive after the link.
+    ///
+    /// Other hard links are expanded as copies of the file they reference.
+    HardLink,
+
+    /// A named pipe.
+    Fifo,
+
+    /// A character device node.
+    CharDevice,
+
+    /// A block device node.
+    BlockDevice,
+
+    /// A symlink in an archive expanded with libarchive, other than a tarball.
+    ///
+    /// libarchive doesn't report link targets when archives are unpacked entry by entry;
+    /// symlinks in tarballs are unpacked and handled according to the [`crate::SymlinkPolicy`].
+    Symlink,
+}
+
 /// Invariants expected by this library.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -83,6 +174,26 @@ pub enum Invariant {
         target: Target,
     },
 
+    /// The extraction root is not a directory.
+    #[error("extraction root {root:?} is not a directory")]
+    ExtractionRoot {
+        /// The configured extraction root.
+        root: PathBuf,
+    },
+
+    /// The volume containing the extraction root does not have the configured minimum free space.
+    #[error(
+        "extraction root {root:?} has {available} bytes free, but {required} bytes are required"
+    )]
+    ExtractionRootFull {
+        /// The configured extraction root.
+        root: PathBuf,
+        /// The free space available, in bytes.
+        available: u64,
+        /// The configured minimum free space, in bytes.
+        required: u64,
+    },
+
     /// The filter option was added for forwards compatibility, but filters were not immediately supported.
     /// This error is used when non-default filters are provided to a version of the library that doesn't support filters.
     #[error("filters are unsupported")]
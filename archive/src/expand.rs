@@ -1,7 +1,14 @@
 //! Archive expansion functionality.
 
 pub mod all;
+pub mod extract;
+pub mod stream;
 pub mod walk;
 
-pub use all::all;
-pub use walk::walk;
+pub use all::{all, all_filtered};
+pub use extract::extract;
+pub use stream::stream;
+pub use walk::{walk, walk_parallel, Walk};
+
+#[cfg(feature = "async")]
+pub use walk::walk_async;
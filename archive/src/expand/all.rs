@@ -3,6 +3,7 @@
 use log::debug;
 use std::{collections::VecDeque, path::Path};
 
+use super::walk::logical_suffix;
 use crate::{strategy::Attempt, *};
 
 /// Synchronously expand all the archives in the provided `target`.
@@ -15,6 +16,21 @@ use crate::{strategy::Attempt, *};
 /// It is recommended to use the iterator walker if possible instead, as it keeps disk space more under control
 /// by removing temporary directories after they are no longer needed instead of unarchiving all contents at once.
 pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
+    all_filtered(target, options, |_| true)
+}
+
+/// Synchronously expand all the archives in the provided `target`, pruning paths rejected by `include`.
+///
+/// This behaves the same as [`all()`], except that `include` is called with each path walked,
+/// relative to the target (paths inside archives use the archive postfix, as in [`crate::expand::walk()`]).
+/// If `include` returns `false` for a directory it is not descended into,
+/// and if it returns `false` for a file it is not considered for expansion.
+/// This allows pruning directories such as `.git` or `node_modules` that are known not to contain relevant archives.
+pub fn all_filtered(
+    target: Target,
+    options: Options,
+    include: impl Fn(&Path) -> bool,
+) -> Result<Expansion, Error> {
     debug!("Expanding {target:?} with {options:?}");
 
     // Since filters are unused today, don't let the user use anything other than the default filter set.
@@ -24,6 +40,9 @@ pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
         return invariant!(FiltersUnsupported);
     }
 
+    // Validate the extraction root before expanding anything into it.
+    options.validate_extraction_root()?;
+
     // Special case: if root is a link, error.
     if target.root.is_symlink() {
         debug!("{:?} is a symlink", target.root);
@@ -32,10 +51,10 @@ pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
 
     // Build strategies depending on the identification type.
     // Strategies can use this however they wish.
-    let strategies = strategy::List::new(options.identification);
+    let strategies = strategy::List::new(&options);
     debug!("using {strategies}");
 
-    // Queue of recursive archives to walk, and the results of the walk.
+    // Queue of recursive archives to walk, along with their depth and logical path, and the results of the walk.
     // Using a manual queue because Rust doesn't do super well with recursive function calls (it's missing TCE).
     let mut queue = VecDeque::new();
     let mut expansion = Expansion::default();
@@ -43,12 +62,17 @@ pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
     // Branch based on whether the initial path is an archive or a directory.
     if target.root.is_dir() {
         debug!("{:?} is a directory", target.root);
-        let extracted = strategies.expand_layer(&target.root, noop_filter)?;
-        queue.extend(extracted.into_iter().map(|p| (0, p)));
+        let filter = relative_filter(&include, Path::new(""), &target.root);
+        let extracted = strategies.expand_layer(&target.root, &[], filter)?;
+        queue.extend(
+            extracted
+                .into_iter()
+                .map(|p| (0, logical(Path::new(""), &target.root, &p), p)),
+        );
     } else if target.root.is_file() {
         debug!("{:?} is a file, treating as an archive", target.root);
-        let extracted = strategies.expand(&target.root);
-        queue.push_back((0, Attempt::new(target.root, extracted)));
+        let attempt = strategies.attempt(target.root, &[]);
+        queue.push_back((0, PathBuf::new(), attempt));
     } else {
         debug!("{:?} is neither directory nor file", target.root);
         return invariant!(Walkable, target);
@@ -60,10 +84,33 @@ pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
     //
     // The queue grows as new archives are discovered and expanded.
     match options.recursion {
-        Recursion::Enabled { depth: max_depth } => {
-            debug!("recursing (max depth '{max_depth}')");
-            while let Some((depth, attempt)) = queue.pop_front() {
+        Recursion::Enabled {
+            depth: default_depth,
+        } => {
+            debug!(
+                "recursing (max depth '{default_depth}', overrides {:?})",
+                options.depth_overrides
+            );
+            while let Some((depth, logical_path, attempt)) = queue.pop_front() {
+                // Recorded archives are cleaned up when `expansion` is dropped,
+                // but queued archives aren't recorded yet so they need to be removed directly.
+                if options.cancelled() {
+                    debug!("expansion cancelled");
+                    cleanup_attempts(queue.into_iter().map(|(_, _, attempt)| attempt));
+                    cleanup_attempts([attempt]);
+                    return Err(Error::Cancelled);
+                }
+
                 debug!("recording at depth '{depth}': {attempt:?}");
+                let attempt = match fail_fast(&options, attempt) {
+                    Ok(attempt) => attempt,
+                    Err(err) => {
+                        cleanup_attempts(queue.into_iter().map(|(_, _, attempt)| attempt));
+                        return Err(err);
+                    }
+                };
+
+                let max_depth = options.depth_for(&logical_path).unwrap_or_default();
                 if depth >= max_depth {
                     debug!("recursion limit reached!");
                     expansion.warn(attempt.source, Error::RecursionLimit);
@@ -71,18 +118,40 @@ pub fn all(target: Target, options: Options) -> Result<Expansion, Error> {
                 }
 
                 let expanded = attempt.result.as_ref().map(|d| d.to_owned()).ok();
+                let ancestry = attempt.ancestry.clone();
                 expansion.record(attempt);
 
                 if let Some(next_path) = expanded {
                     let depth = depth + 1;
-                    let next = strategies.expand_layer(&next_path, noop_filter)?;
-                    queue.extend(next.into_iter().map(|p| (depth, p)));
+                    if let Some(metrics) = &options.metrics {
+                        metrics.record_depth(depth);
+                    }
+                    let parent = logical_suffix(&options, &logical_path);
+                    let filter = relative_filter(&include, &parent, &next_path);
+                    let next = strategies.expand_layer(&next_path, &ancestry, filter)?;
+                    queue.extend(
+                        next.into_iter()
+                            .map(|p| (depth, logical(&parent, &next_path, &p), p)),
+                    );
+                }
+            }
+        }
+        Recursion::Disabled => {
+            while let Some((_, _, attempt)) = queue.pop_front() {
+                match fail_fast(&options, attempt) {
+                    Ok(attempt) => expansion.record(attempt),
+                    Err(err) => {
+                        cleanup_attempts(queue.into_iter().map(|(_, _, attempt)| attempt));
+                        return Err(err);
+                    }
                 }
             }
         }
-        Recursion::Disabled => expansion.record_many(queue.into_iter().map(|(_, b)| b)),
     }
 
+    if let Some(metrics) = &options.metrics {
+        metrics.record_warnings(expansion.warnings.values().map(Vec::len).sum());
+    }
     debug!("finished expanding");
     Ok(expansion)
 }
@@ -178,6 +247,10 @@ impl Expansion {
     }
 
     fn record(&mut self, attempt: Attempt) {
+        for warning in attempt.warnings {
+            self.warn(attempt.source.clone(), warning);
+        }
+
         match attempt.result {
             Ok(destination) => {
                 self.locations
@@ -193,12 +266,46 @@ impl Expansion {
         let errs = self.warnings.entry(Source::from(source)).or_default();
         errs.push(warning);
     }
+}
 
-    fn record_many(&mut self, attempts: impl IntoIterator<Item = Attempt>) {
-        attempts.into_iter().for_each(|a| self.record(a))
+/// Under [`ErrorPolicy::FailFast`], convert a failed attempt into the error to return.
+/// Files that aren't supported by any strategy or that are skip listed are never considered to have failed.
+fn fail_fast(options: &Options, attempt: Attempt) -> Result<Attempt, Error> {
+    match attempt.result {
+        Err(error) if options.on_error == ErrorPolicy::FailFast && error.is_failure() => {
+            debug!("failed to expand {:?}, stopping", attempt.source);
+            let path = attempt.source;
+            Err(Error::ExpandArchive { path, error })
+        }
+        result => Ok(Attempt { result, ..attempt }),
     }
 }
 
-fn noop_filter(_: &Path) -> bool {
-    true
+/// Remove the destinations of attempts that were never recorded into an [`Expansion`].
+fn cleanup_attempts(attempts: impl IntoIterator<Item = Attempt>) {
+    for attempt in attempts {
+        if let Ok(destination) = attempt.result {
+            let _ = fs::remove_dir_all(destination);
+        }
+    }
+}
+
+/// The logical path of an archive discovered in `dir`, the expanded contents of the archive at the logical path `parent`.
+fn logical(parent: &Path, dir: &Path, attempt: &Attempt) -> PathBuf {
+    let relative = attempt.source.strip_prefix(dir).unwrap_or(&attempt.source);
+    parent.join(relative)
+}
+
+/// Adapt `include` to the paths walked inside `dir`, the expanded contents of the archive at the logical path `parent`.
+/// `dir` itself is always included.
+fn relative_filter<'a>(
+    include: &'a impl Fn(&Path) -> bool,
+    parent: &'a Path,
+    dir: &'a Path,
+) -> impl Fn(&Path) -> bool + 'a {
+    move |path| match path.strip_prefix(dir) {
+        Ok(relative) if relative.as_os_str().is_empty() => true,
+        Ok(relative) => include(&parent.join(relative)),
+        Err(_) => true,
+    }
 }
@@ -0,0 +1,169 @@
+//! Expanding selected entries from (possibly nested) archives.
+
+use std::{
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, Read},
+    mem,
+    path::{Component, Path, PathBuf},
+};
+
+use getset::Getters;
+use log::debug;
+use tempfile::{tempdir_in, TempDir};
+
+use super::walk::logical_suffix;
+use crate::{strategy, Error, Options, Target};
+
+/// Expand only the entries matching `pattern`, which selects entries in a (possibly nested) archive in `target`.
+///
+/// `pattern` is a logical path relative to `target`, as reported by [`crate::expand::walk()`]:
+/// the archive postfix separates nested archives, and whatever follows the last postfix selects entries
+/// in the innermost archive. For example, `some.zip!_fossa.virtual_!/inner.tar!_fossa.virtual_!/lib/**`
+/// selects everything inside `lib` in `inner.tar`, itself inside `some.zip`.
+/// If `target` is an archive rather than a directory, `pattern` starts inside it (for example `lib/**`).
+///
+/// Entries are selected by path components: `*` matches any single component,
+/// `**` matches any number of components (including none), and any other component must match exactly.
+/// If nothing follows the last postfix, every file in the innermost archive is expanded.
+///
+/// Archives are streamed (see [`crate::expand::stream()`]), so for strategies that support reading entries directly
+/// only the nested archives along the path and the matching entries are written to disk,
+/// rather than expanding every archive along the path in full.
+/// Filters and recursion options are not consulted.
+///
+/// Errors with [`Error::ArchiveNotFound`] if an archive named in `pattern` doesn't exist.
+pub fn extract(
+    target: Target,
+    options: Options,
+    pattern: impl AsRef<Path>,
+) -> Result<Extraction, Error> {
+    let pattern = pattern.as_ref().to_string_lossy();
+    debug!("Extracting {pattern:?} from {target:?} with {options:?}");
+    options.validate_extraction_root()?;
+
+    let strategies = strategy::List::new(&options);
+    let temp_root = options.temp_root();
+
+    // Split the pattern into the archives along the path and the pattern inside the innermost archive.
+    let mut archives = pattern
+        .split(options.archive_postfix.as_str())
+        .map(|segment| Path::new(segment.trim_start_matches('/')))
+        .collect::<Vec<_>>();
+    let selected = archives.pop().unwrap_or_else(|| Path::new(""));
+    let mut archives = archives.into_iter();
+
+    let (mut archive, mut logical) = if target.root.is_file() {
+        (target.root.clone(), PathBuf::new())
+    } else {
+        let first = archives.next().unwrap_or_else(|| Path::new(""));
+        let archive = target.root.join(first);
+        if !archive.is_file() {
+            return Err(Error::ArchiveNotFound {
+                path: first.to_owned(),
+            });
+        }
+        (archive, first.to_owned())
+    };
+
+    // Each nested archive is streamed out of its parent into its own temporary directory,
+    // which is kept until the nested archive has been read.
+    let mut _parent: Option<TempDir> = None;
+    for nested in archives {
+        logical = logical_suffix(&options, &logical).join(nested);
+        let name = nested.file_name().unwrap_or_else(|| OsStr::new("archive"));
+        let dir = tempdir_in(&temp_root).map_err(strategy::Error::IO)?;
+        let dest = dir.path().join(name);
+
+        let mut found = false;
+        strategies.stream(&archive, &mut |path: &Path, reader: &mut dyn Read| {
+            if path == nested {
+                io::copy(reader, &mut File::create(&dest)?)?;
+                found = true;
+            }
+            Ok(())
+        })?;
+        if !found {
+            return Err(Error::ArchiveNotFound { path: logical });
+        }
+
+        debug!("streamed {logical:?} to {dest:?}");
+        archive = dest;
+        _parent = Some(dir);
+    }
+
+    let pattern = selected.iter().collect::<Vec<_>>();
+    let dir = tempdir_in(&temp_root).map_err(strategy::Error::IO)?;
+    let mut files = Vec::new();
+    strategies.stream(&archive, &mut |path: &Path, reader: &mut dyn Read| {
+        // Entries with paths that would escape the destination are never selected.
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Ok(());
+        }
+
+        let components = path.iter().collect::<Vec<_>>();
+        if pattern.is_empty() || matches_pattern(&pattern, &components) {
+            let dest = dir.path().join(path);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(reader, &mut File::create(dest)?)?;
+            files.push(path.to_owned());
+        }
+        Ok(())
+    })?;
+
+    debug!("extracted {} files from {logical:?}", files.len());
+    Ok(Extraction {
+        dir: dir.into_path(),
+        files,
+    })
+}
+
+/// The entries expanded by [`extract()`].
+///
+/// The directory containing the entries is deleted when this is dropped, unless it is persisted with [`Extraction::persist`].
+#[derive(Debug, Getters)]
+pub struct Extraction {
+    /// The directory into which the entries were expanded.
+    /// Entries are located at their paths relative to the root of the innermost archive.
+    #[getset(get = "pub")]
+    dir: PathBuf,
+
+    /// The paths of the expanded files, relative to `dir`.
+    #[getset(get = "pub")]
+    files: Vec<PathBuf>,
+}
+
+impl Extraction {
+    /// Persist the directory containing the entries, returning its path.
+    ///
+    /// This consumes the `Extraction` without deleting the directory,
+    /// meaning that it is no longer automatically deleted.
+    pub fn persist(self) -> PathBuf {
+        let mut this = mem::ManuallyDrop::new(self);
+        this.files = Vec::new();
+        mem::take(&mut this.dir)
+    }
+}
+
+impl Drop for Extraction {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Whether the path components match the pattern components.
+fn matches_pattern(pattern: &[&OsStr], path: &[&OsStr]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&first, rest)), _) if first == OsStr::new("**") => {
+            matches_pattern(rest, path)
+                || (!path.is_empty() && matches_pattern(pattern, &path[1..]))
+        }
+        (Some((&first, rest)), Some((&component, tail))) => {
+            (first == OsStr::new("*") || first == component) && matches_pattern(rest, tail)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,35 @@
+//! Streaming archive contents without expanding them.
+
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+
+use log::debug;
+
+use crate::{strategy, Error, Options};
+
+/// Stream the contents of each file in the archive at `archive` to `visit`,
+/// along with the path of the file relative to the root of the archive.
+///
+/// Unlike the other expansion functions, archives nested inside `archive` are not expanded;
+/// they are streamed like any other file. Filters and recursion options are not consulted.
+///
+/// When the strategy handling the archive supports it, entries are read directly from the archive
+/// without writing anything to disk. Otherwise the archive is expanded into a temporary directory
+/// inside the extraction root, which is deleted once all entries are streamed.
+///
+/// Contents not read by `visit` are skipped. If `visit` returns an error, streaming stops and the error is returned.
+pub fn stream(
+    archive: &Path,
+    options: Options,
+    mut visit: impl FnMut(&Path, &mut dyn Read) -> Result<(), io::Error>,
+) -> Result<(), Error> {
+    debug!("Streaming {archive:?} with {options:?}");
+    options.validate_extraction_root()?;
+
+    let strategies = strategy::List::new(&options);
+    debug!("using {strategies}");
+
+    strategies.stream(archive, &mut visit).map_err(Error::from)
+}
@@ -2,20 +2,34 @@
 
 use std::{
     collections::VecDeque,
-    fs::{self, File},
-    io,
+    fs::{self, File, FileType},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
+    time::SystemTime,
 };
 
-use crossbeam::channel::{bounded, Sender};
+use crossbeam::channel::{bounded, IntoIter, Sender};
 use derivative::Derivative;
+use fallible_iterator::FallibleIterator;
+use getset::{CopyGetters, Getters};
+use log::debug;
+use rayon::{prelude::*, Scope, ThreadPoolBuilder};
 use walkdir::{DirEntry, WalkDir};
 
+#[cfg(feature = "async")]
+use tokio::{sync::mpsc, task};
+#[cfg(feature = "async")]
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+
 use crate::{
-    strategy::{self, List},
-    Error, Options, Recursion, Target,
+    path,
+    strategy::{self, sniff, ContentDigest, Expanded, List},
+    Error, ErrorPolicy, ExpandEvent, Options, Target,
 };
 
 /// A directory entry discovered by the walker.
@@ -29,25 +43,34 @@ pub struct Entry {
     /// The actual path on disk. This is hidden from clients as an implementation detail.
     concrete: PathBuf,
 
+    /// Metadata for the file, captured when it was walked.
+    metadata: Metadata,
+
+    /// Non-fatal errors encountered while processing the entry.
+    warnings: Vec<Error>,
+
     /// The [`WalkTarget`] containing the file to which this entry points.
     /// This is needed because `WalkTarget` cleans up its directory once it finishes walking,
     /// but `Entry` may live beyond that walk operation.
     /// As long as `Entry` is around, its location on disk should be accessible.
     ///
-    /// This field is not directly used; it is merely a sentinel to keep `WalkTarget` alive.
+    /// Other than keeping `WalkTarget` alive, this is only used to report the entry's ancestors.
     #[derivative(Debug = "ignore")]
-    _target: Arc<WalkTarget>,
+    target: Arc<WalkTarget>,
 }
 
 impl Entry {
     /// Create an instance with direct ancestry.
     /// Errors if the logical entry cannot be created.
-    fn direct(target: Arc<WalkTarget>, dir: &Path, file: &Path) -> Result<Self, Error> {
-        let logical = try_make_relative(dir, file)?;
+    fn direct(target: Arc<WalkTarget>, dir: &Path, file: &DirEntry) -> Result<Self, Error> {
+        let logical = try_make_relative(dir, file.path())?;
+        let metadata = Metadata::new(file, target.expanded.is_some())?;
         Ok(Self {
-            _target: target,
+            target,
             logical: logical.to_owned(),
-            concrete: file.to_owned(),
+            concrete: file.path().to_owned(),
+            metadata,
+            warnings: Vec::new(),
         })
     }
 
@@ -58,7 +81,7 @@ impl Entry {
         target: Arc<WalkTarget>,
         parent: Option<&Path>,
         dir: &Path,
-        file: &Path,
+        file: &DirEntry,
     ) -> Result<Self, Error> {
         let entry = Self::direct(target, dir, file)?;
         Ok(match parent {
@@ -79,13 +102,69 @@ impl Entry {
         &self.logical
     }
 
+    /// Metadata for the entry, captured when it was walked.
+    ///
+    /// This is available even after the underlying file is removed from the disk,
+    /// and doesn't require any further file system access.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// The raw bytes of the entry's name in the archive containing it, if that name wasn't valid UTF-8.
+    ///
+    /// Such names are decoded according to the configured [`crate::NameEncoding`] to produce the entry's path;
+    /// this reports the original name, which may be needed to identify the entry exactly.
+    /// Only the strategies that expand zip archives and tarballs, or that are backed by libarchive, report raw names.
+    pub fn raw_name(&self) -> Option<&[u8]> {
+        let expanded = self.target.expanded.as_ref()?;
+        let relative = self.concrete.strip_prefix(&self.target.dir).ok()?;
+        expanded.raw_name(relative)
+    }
+
+    /// The archives containing the entry, outermost first.
+    ///
+    /// For example, the entry `outer.zip!_fossa.virtual_!/inner.tar!_fossa.virtual_!/file.txt`
+    /// has the ancestors `outer.zip` (at depth 1) and `outer.zip!_fossa.virtual_!/inner.tar` (at depth 2).
+    /// Entries that aren't inside an archive have no ancestors.
+    /// This allows attributing an entry to the exact archive containing it without parsing its path.
+    pub fn ancestors(&self) -> &[Ancestor] {
+        &self.target.ancestors
+    }
+
+    /// Non-fatal errors encountered while processing the entry.
+    ///
+    /// If the entry is an archive that failed to expand, the reason is reported here
+    /// (including when it was not expanded because doing so would exceed the recursion limit).
+    /// The entry itself is still walked like any other file, but its contents are not.
+    /// This mirrors the warnings reported by [`crate::expand::all::Expansion`].
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
+    /// Take the non-fatal errors encountered while processing the entry, leaving none behind.
+    ///
+    /// This allows consumers to keep the warnings after the entry is consumed by [`Entry::into_path`].
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
     /// Open a file handle for the entry.
+    ///
+    /// Prefer [`Entry::reader`], which doesn't tie consumers to the entry being backed by a file on disk.
     pub fn open(&mut self) -> Result<File, io::Error> {
         // Even though this function doesn't actually mutate `Entry` itself,
         // it _does_ allow mutation of the underlying file, so its receiver is mut.
         File::open(&self.concrete)
     }
 
+    /// Open a buffered reader for the content of the entry.
+    ///
+    /// Today entries are always backed by files on disk, but strategies may in the future read entries
+    /// directly from inside archives; this reader supports either without changes for consumers.
+    pub fn reader(&mut self) -> Result<EntryReader, io::Error> {
+        self.open().map(BufReader::new).map(EntryReader)
+    }
+
     /// Consume the `Entry` and return the canonical path relative to the expanding root.
     ///
     /// Note that this path is only usable for recording purposes, and the path is not
@@ -103,6 +182,96 @@ impl Entry {
     }
 }
 
+/// An archive containing an [`Entry`]. See [`Entry::ancestors`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters, CopyGetters)]
+pub struct Ancestor {
+    /// The logical path of the archive relative to the expanding root, as reported by [`Entry::path`] for the archive.
+    #[getset(get = "pub")]
+    path: PathBuf,
+
+    /// The depth at which the archive was expanded; archives in the walked directory are expanded at depth 1.
+    #[getset(get_copy = "pub")]
+    depth: usize,
+}
+
+/// A buffered reader for the content of an [`Entry`], returned by [`Entry::reader`].
+#[derive(Debug)]
+pub struct EntryReader(BufReader<File>);
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl BufRead for EntryReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+/// Metadata for an [`Entry`], captured when it was walked.
+///
+/// For entries inside an archive this is the metadata of the file expanded to disk;
+/// strategies generally preserve the modified time and permissions recorded in the archive,
+/// but this is not guaranteed for every format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, CopyGetters)]
+pub struct Metadata {
+    /// The size of the file, in bytes.
+    #[getset(get_copy = "pub")]
+    size: u64,
+
+    /// The last modification time of the file, if supported by the platform.
+    #[getset(get_copy = "pub")]
+    modified: Option<SystemTime>,
+
+    /// The unix permission mode of the file. Always `None` on non-unix platforms.
+    #[getset(get_copy = "pub")]
+    mode: Option<u32>,
+
+    /// The type of the file. Symlinks are not followed, so this is the type of the entry itself.
+    #[getset(get_copy = "pub")]
+    file_type: FileType,
+
+    /// Whether the file came from inside an archive, as opposed to the walked target itself.
+    #[getset(get_copy = "pub")]
+    archived: bool,
+}
+
+impl Metadata {
+    fn new(de: &DirEntry, archived: bool) -> Result<Self, walkdir::Error> {
+        let meta = de.metadata()?;
+        Ok(Self {
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            mode: mode(&meta),
+            file_type: de.file_type(),
+            archived,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn mode(_: &fs::Metadata) -> Option<u32> {
+    None
+}
+
 /// Walks `target` recursively, outputting discovered [`Entry`] items as an iterator.
 ///
 /// When an entry is found that references an archive that is supported for extraction,
@@ -132,115 +301,350 @@ impl Entry {
 /// Given this, it is important to use the [`Entry`] methods to obtain a file handle for reading the file,
 /// as attempting to read the path directly results in an error if the file is inside an archive.
 ///
-/// After an archive has been fully walked it is removed from the disk.
-pub fn walk(target: Target, options: Options) -> impl Iterator<Item = Result<Entry, Error>> {
+/// Archives that fail to expand are still output as entries; the reason they failed is available via [`Entry::warnings`].
+///
+/// After an archive has been fully walked it is removed from the disk,
+/// unless the walk is configured to persist expanded archives via [`Options`].
+pub fn walk(target: Target, options: Options) -> Walk {
     // `walk_inner` contains multiple nested iterations that need to be flattened.
     // After _much_ trial and error, this results in some _really nasty_ iterator code.
     // Instead of doing that, just use a channel and pull an iterator out of it, which keeps things much simpler.
     let (send, recv) = bounded(0);
 
     // Of course, the downside here is that this has to be in its own thread.
-    thread::spawn(move || walk_inner(send, target.root, options));
-    recv.into_iter()
+    thread::spawn(move || walk_inner(|entry| send.send(entry), target.root, options));
+    Walk(recv.into_iter())
+}
+
+/// Walks `target` recursively, outputting discovered [`Entry`] items as a stream.
+///
+/// This behaves the same as [`walk`], but is intended for async consumers:
+/// the walk is run on the tokio blocking thread pool, and its entries are output to the returned stream.
+///
+/// This function must be called from within a tokio runtime.
+#[cfg(feature = "async")]
+pub fn walk_async(target: Target, options: Options) -> impl Stream<Item = Result<Entry, Error>> {
+    // Tokio channels require a nonzero capacity; keep it as small as possible
+    // so that the walker doesn't get too far ahead of the consumer.
+    let (send, recv) = mpsc::channel(1);
+    task::spawn_blocking(move || {
+        walk_inner(|entry| send.blocking_send(entry), target.root, options)
+    });
+    ReceiverStream::new(recv)
+}
+
+/// Walks `target` recursively in parallel, outputting discovered [`Entry`] items as an iterator.
+///
+/// This behaves the same as [`walk`], except that entries are rendered and archives are expanded
+/// in parallel on a dedicated thread pool, and each expanded archive is walked as its own parallel task.
+/// Entries are therefore output in no particular order.
+///
+/// As with [`walk`], each [`Entry`] keeps the temporary directory containing it alive,
+/// and expanded archives are removed from the disk once they have been fully walked
+/// and all entries within them have been dropped.
+///
+/// A dedicated thread pool is used (rather than the global rayon pool) because walk tasks block
+/// until the consumer receives their entries; if the consumer used the same pool to receive them
+/// (for example, via `par_bridge`) the pool could deadlock.
+pub fn walk_parallel(target: Target, options: Options) -> Walk {
+    let (send, recv) = bounded(0);
+
+    thread::spawn(move || {
+        let pool = match ThreadPoolBuilder::new().build() {
+            Ok(pool) => pool,
+            Err(err) => {
+                debug!("unable to build thread pool, falling back to serial walk: {err}");
+                return walk_inner(|entry| send.send(entry), target.root, options);
+            }
+        };
+
+        if let Err(err) = options.validate_extraction_root() {
+            let _ = send.send(Err(err));
+            return;
+        }
+
+        let strategies = List::new(&options);
+        let root = WalkTarget::base(target.root);
+        let failed = AtomicBool::new(false);
+        pool.scope(|scope| walk_parallel_inner(scope, &send, &strategies, &options, &failed, root));
+
+        // Cancelled tasks exit without spawning further tasks, so once the scope completes the walk has stopped.
+        if options.cancelled() {
+            debug!("walk cancelled");
+            let _ = send.send(Err(Error::Cancelled));
+        }
+    });
+    Walk(recv.into_iter())
+}
+
+/// The entries discovered by [`walk`] or [`walk_parallel`].
+///
+/// This is both an [`Iterator`] over `Result<Entry, Error>` and a [`FallibleIterator`] over [`Entry`],
+/// so consumers can use fallible combinators without mapping over each result.
+/// Since both traits provide methods with the same names, the first fallible combinator must be called
+/// with fully qualified syntax (for example `FallibleIterator::map(walk, ...)`) if both traits are in scope;
+/// the combinators it returns are only fallible iterators.
+#[derive(Debug)]
+pub struct Walk(IntoIter<Result<Entry, Error>>);
+
+impl Iterator for Walk {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl FallibleIterator for Walk {
+    type Item = Entry;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
+        self.0.next().transpose()
+    }
 }
 
 struct WalkTarget {
     parent: Option<PathBuf>,
     depth: usize,
     dir: PathBuf,
-    temp: bool,
+
+    /// The content digests of the archives from which this target was expanded, including its own archive.
+    /// Archives with the same content as any of these contain themselves, and are not expanded.
+    ancestry: Vec<ContentDigest>,
+
+    /// The archives from which this target was expanded, outermost first, including its own archive.
+    ancestors: Vec<Ancestor>,
+
+    /// The expanded archive this target walks, if any.
+    ///
+    /// Identical archives share the same expanded directory,
+    /// which is removed once every target walking it (and every entry within it) is dropped.
+    expanded: Option<Arc<Expanded>>,
 }
 
 impl WalkTarget {
     fn base(dir: PathBuf) -> Self {
         Self {
-            dir,
+            // Entries are rendered relative to this, so this doesn't change the paths reported for them.
+            dir: path::extended_length(dir),
             depth: 0,
             parent: None,
-            temp: false,
+            ancestry: Vec::new(),
+            ancestors: Vec::new(),
+            expanded: None,
         }
     }
 
-    fn expanded(parent: PathBuf, dir: PathBuf, depth: usize) -> Self {
+    fn expanded(&self, archive: &Path, parent: PathBuf, expanded: Arc<Expanded>) -> Self {
+        let depth = self.depth + 1;
+        let mut ancestry = self.ancestry.clone();
+        ancestry.push(expanded.digest());
+        let mut ancestors = self.ancestors.clone();
+        ancestors.push(Ancestor {
+            path: archive.to_owned(),
+            depth,
+        });
         Self {
-            dir,
+            dir: expanded.path().to_owned(),
             depth,
             parent: Some(parent),
-            temp: true,
+            ancestry,
+            ancestors,
+            expanded: Some(expanded),
         }
     }
 }
 
-impl Drop for WalkTarget {
-    fn drop(&mut self) {
-        if self.temp {
-            let _ = fs::remove_dir_all(&self.dir);
-        }
+/// Walks entries in `target` according to `options`, sending them with `send`.
+/// Any error encountered is sent, and then this function exits.
+///
+/// `send` returns an error if the receiving side of the channel is closed.
+fn walk_inner<E>(
+    mut send: impl FnMut(Result<Entry, Error>) -> Result<(), E>,
+    root: PathBuf,
+    options: Options,
+) {
+    if let Err(err) = options.validate_extraction_root() {
+        let _ = send(Err(err));
+        return;
     }
-}
 
-/// Walks entries in `target` according to `options`, sending them to `tx`.
-/// Any error encountered is written to `tx`, and then this function exits.
-fn walk_inner(tx: Sender<Result<Entry, Error>>, root: PathBuf, options: Options) {
-    let strategies = List::new(options.identification);
+    let strategies = List::new(&options);
     let mut queue = VecDeque::from([WalkTarget::base(root)]);
-    let logical_suffix = |path: &Path| {
-        let mut path = path.as_os_str().to_owned();
-        path.push(&options.archive_postfix);
-        PathBuf::from(path)
-    };
 
     while let Some(target) = queue.pop_front() {
         let target = Arc::new(target);
+        let walk = walk_dir(&target)
+            .take_while(|_| !options.cancelled())
+            .filter_map(|de| {
+                visit(&strategies, &options, &target, de, |next| {
+                    queue.push_back(next)
+                })
+            })
+            .try_for_each(|entry| match entry {
+                Ok(entry) => send(Ok(entry)).map_err(|_| ()),
+                Err(err) => {
+                    let _ = send(Err(err));
+                    Err(())
+                }
+            });
 
-        // Attempt to expand the entry.
-        // If it is a supported archive, the new expanded entry is pushed onto the queue.
-        // Either way, the original entry is still returned for iteration.
-        let mut process = |entry: Entry| -> Result<Entry, Error> {
-            match options.recursion {
-                Recursion::Enabled { depth } => match strategies.expand(&entry.concrete) {
-                    Ok(expanded) => {
-                        let new_depth = target.depth + 1;
-                        if new_depth > depth {
-                            // Don't recurse further if it'd exceed the recursion depth.
-                            return Ok(entry);
-                        }
-
-                        let parent = logical_suffix(&entry.logical);
-                        queue.push_back(WalkTarget::expanded(parent, expanded, new_depth));
-                        Ok(entry)
-                    }
-                    Err(strategy::Error::NotSupported) => Ok(entry),
-                    Err(err) => Err(Error::Expand(err)),
-                },
-                Recursion::Disabled => Ok(entry),
-            }
-        };
-
-        let parent = target.parent.as_deref();
-        let render = |de: DirEntry| Entry::derived(target.clone(), parent, &target.dir, de.path());
-        let not_excludes = |e: &Entry| !options.filter.excludes(e.path());
-        let allows = |e: &Entry| options.filter.allows(e.path());
-        let walk = WalkDir::new(&target.dir)
-            .follow_links(false)
-            .into_iter()
-            .filter(|de| de.as_ref().map(|de| de.path().is_file()).unwrap_or(true))
-            .flat_map(|de| de.map(render).map_err(Error::Walk))
-            // Filter ahead of time for block list.
-            .filter(|entry| entry.as_ref().map(not_excludes).unwrap_or(true))
-            .flat_map(|entry| entry.map(&mut process))
-            // Filter after the fact for allow list.
-            // If this is filtered ahead of time, it's impossible to reach deeper filters.
-            .filter(|entry| entry.as_ref().map(allows).unwrap_or(true))
-            .try_for_each(|entry| tx.send(entry));
-
-        // If walk is error, it indicates the channel is closed; just exit.
+        // If walk is error, it indicates the channel is closed or the walk failed; just exit.
+        // Any targets remaining in the queue are cleaned up when it is dropped.
         if walk.is_err() {
             break;
         }
+
+        // Any targets remaining in the queue are cleaned up when it is dropped.
+        if options.cancelled() {
+            debug!("walk cancelled");
+            let _ = send(Err(Error::Cancelled));
+            break;
+        }
+    }
+}
+
+/// Walks entries in `target` according to `options` in parallel, sending them to `tx`.
+/// Expanded archives are walked as new tasks in `scope`.
+///
+/// Once any task sends an error it sets `failed`, and all tasks stop walking.
+fn walk_parallel_inner<'s>(
+    scope: &Scope<'s>,
+    tx: &'s Sender<Result<Entry, Error>>,
+    strategies: &'s List,
+    options: &'s Options,
+    failed: &'s AtomicBool,
+    target: WalkTarget,
+) {
+    let target = Arc::new(target);
+    let walk = walk_dir(&target)
+        .take_while(|_| !options.cancelled() && !failed.load(Ordering::Relaxed))
+        .par_bridge()
+        .filter_map(|de| {
+            visit(strategies, options, &target, de, |next| {
+                scope.spawn(move |scope| {
+                    walk_parallel_inner(scope, tx, strategies, options, failed, next)
+                })
+            })
+        })
+        .try_for_each(|entry| match entry {
+            Ok(entry) => tx.send(Ok(entry)).map_err(|_| ()),
+            Err(err) => {
+                // Only the first error is sent, even if other tasks failed concurrently.
+                if !failed.swap(true, Ordering::Relaxed) {
+                    let _ = tx.send(Err(err));
+                }
+                Err(())
+            }
+        });
+
+    // If walk is error, it indicates the channel is closed or the walk failed.
+    // Other tasks discover this the same way when they next attempt to send or walk, so just exit.
+    if walk.is_err() {
+        debug!(
+            "channel closed or walk failed, exiting walk of {:?}",
+            target.dir
+        );
     }
 }
 
+/// Walk the files in the directory for the target. Symlinks are not followed.
+fn walk_dir(target: &WalkTarget) -> impl Iterator<Item = Result<DirEntry, walkdir::Error>> {
+    WalkDir::new(&target.dir)
+        .follow_links(false)
+        .into_iter()
+        .filter(|de| de.as_ref().map(|de| de.path().is_file()).unwrap_or(true))
+}
+
+/// Render the walked directory entry into an [`Entry`], filter it, and attempt to expand it.
+///
+/// If it is a supported archive, the new expanded target is provided to `enqueue`.
+/// Either way, the original entry is still returned for iteration, unless it is filtered;
+/// if the archive could not be expanded (or was not expanded due to the recursion limit) the reason is recorded as a warning.
+///
+/// If the archive could not be expanded and [`ErrorPolicy::FailFast`] is configured, the error is returned instead,
+/// and the walk should stop.
+fn visit(
+    strategies: &List,
+    options: &Options,
+    target: &Arc<WalkTarget>,
+    de: Result<DirEntry, walkdir::Error>,
+    enqueue: impl FnOnce(WalkTarget),
+) -> Option<Result<Entry, Error>> {
+    // Entries that cannot be walked or rendered are skipped.
+    let parent = target.parent.as_deref();
+    let entry = de
+        .ok()
+        .and_then(|de| Entry::derived(target.clone(), parent, &target.dir, &de).ok())?;
+
+    // Filter ahead of time for block list.
+    if options.filter.excludes(entry.path()) {
+        return None;
+    }
+
+    let mut entry = entry;
+    match options.depth_for(&entry.logical) {
+        // Once cancelled, don't start expanding any more archives.
+        Some(_) if options.cancelled() => return None,
+        Some(depth) => match strategies.expand_shared(&entry.concrete, &target.ancestry) {
+            Ok(expanded) => {
+                let limit = options.max_entry_bytes;
+                let skipped = strategy::skipped_warnings(limit, expanded.skipped());
+                entry.warnings.extend(skipped);
+                if let Some(persist) = &options.persist {
+                    persist.record(entry.logical.clone(), expanded.path().to_owned());
+                }
+
+                // Don't recurse further if it'd exceed the recursion depth.
+                if target.depth < depth {
+                    let parent = logical_suffix(options, &entry.logical);
+                    let next = target.expanded(&entry.logical, parent, expanded);
+                    if let Some(metrics) = &options.metrics {
+                        metrics.record_depth(next.depth);
+                    }
+                    enqueue(next);
+                } else {
+                    entry.warnings.push(Error::RecursionLimit);
+                }
+            }
+            Err(strategy::Error::NotSupported) if options.report_unsupported => {
+                if let Some(format) = sniff::archive_format(&entry.concrete) {
+                    entry.warnings.push(Error::UnsupportedArchive { format });
+                }
+            }
+            Err(strategy::Error::NotSupported) => {}
+            Err(error) if options.on_error == ErrorPolicy::FailFast && error.is_failure() => {
+                let path = entry.logical;
+                return Some(Err(Error::ExpandArchive { path, error }));
+            }
+            Err(err) => entry.warnings.push(Error::Expand(err)),
+        },
+        None => {}
+    };
+
+    // Filter after the fact for allow list.
+    // If this is filtered ahead of time, it's impossible to reach deeper filters.
+    if !options.filter.allows(entry.path()) {
+        return None;
+    }
+
+    if let Some(metrics) = &options.metrics {
+        metrics.record_warnings(entry.warnings.len());
+    }
+    options.emit(|| ExpandEvent::EntryWalked {
+        path: entry.logical.clone(),
+    });
+    Some(Ok(entry))
+}
+
+/// Append the archive postfix to the logical path for an archive.
+pub(super) fn logical_suffix(options: &Options, path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(&options.archive_postfix);
+    PathBuf::from(path)
+}
+
 fn try_make_relative<'a>(parent: &'a Path, child: &'a Path) -> Result<&'a Path, Error> {
     child
         .strip_prefix(parent)
@@ -5,23 +5,33 @@
 #![warn(rust_2018_idioms)]
 
 use std::{
-    collections::{HashMap, HashSet},
-    fs, mem,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs, mem,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
 };
 
 use bimap::BiHashMap;
 use derivative::Derivative;
 use derive_more::From;
 use duplicate::duplicate_item;
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use typed_builder::TypedBuilder;
 
 mod error;
 pub mod expand;
-mod strategy;
+mod list;
+mod path;
+pub mod strategy;
 
 pub use error::*;
+pub use fallible_iterator::FallibleIterator;
+pub use list::list;
+pub use strategy::{EntryKind, Listing};
 
 /// The default archive postfix.
 pub const DEFAULT_ARCHIVE_POSTFIX: &str = "!_fossa.virtual_!";
@@ -35,10 +45,90 @@ pub struct Options {
     #[builder(default)]
     recursion: Recursion,
 
+    /// Overrides the depth set by [`Recursion::Enabled`] for archives whose paths begin with the given prefixes.
+    /// These have no effect when recursion is disabled.
+    ///
+    /// Prefixes are relative to the root and are compared by component against the logical path of each archive
+    /// (the path reported by the walker, including the archive postfix for archives nested inside other archives).
+    /// If multiple prefixes match an archive, the longest one is used.
+    ///
+    /// Overridden depths are still counted from the root; for example with the prefix `test/fixtures`
+    /// overridden to a depth of 1, archives in that directory are expanded but archives nested inside them are not.
+    /// This allows large projects to bound expansion cost where archives are known to be noise.
+    #[builder(default)]
+    depth_overrides: BTreeMap<PathBuf, usize>,
+
     /// The strategy for identifying expansion strategies for archives.
     #[builder(default)]
     identification: Identification,
 
+    /// How whiteouts are handled when expanding container image layers.
+    #[builder(default)]
+    whiteout: Whiteout,
+
+    /// How symlinks and hardlinks contained in archives are handled when they are expanded.
+    #[builder(default)]
+    symlink_policy: SymlinkPolicy,
+
+    /// Whether archives with identical content are only expanded once while walking.
+    ///
+    /// Archives are always hashed before they are expanded (so that archives containing themselves are not expanded);
+    /// when this is enabled, if an archive with the same content was already expanded during the walk and its expanded directory is still in use,
+    /// that directory is walked again under the new archive's path instead of expanding the archive again.
+    /// This is especially useful for projects that vendor the same dependency in many places.
+    ///
+    /// This only affects the walker; [`expand::all()`] always expands each archive.
+    #[builder(default = true)]
+    #[derivative(Default(value = "true"))]
+    deduplicate: bool,
+
+    /// SHA-256 digests of the content of archives that are never expanded.
+    ///
+    /// Archives are hashed before they are expanded; any archive with one of these digests is walked as a plain file
+    /// and reported with a [`strategy::Error::SkipListed`] warning instead of being expanded.
+    /// This allows scans to short-circuit the expansion of known archives (such as large base OS images)
+    /// whose content has already been catalogued.
+    ///
+    /// This affects the walker and [`expand::all()`]; archives are always expanded by [`expand::stream()`].
+    #[builder(default, setter(into))]
+    skip_digests: HashSet<[u8; 32]>,
+
+    /// Whether files that look like archives but aren't supported by any strategy are reported while walking.
+    ///
+    /// When enabled, files that have the magic bytes or extension of an archive format but could not be expanded
+    /// because no strategy supports them are reported with an [`Error::UnsupportedArchive`] warning.
+    /// This is useful for measuring gaps in format coverage.
+    /// It requires reading the start of every unsupported file, so it's disabled by default.
+    ///
+    /// This only affects the walker.
+    #[builder(default)]
+    report_unsupported: bool,
+
+    /// The maximum size in bytes of a single file expanded from an archive.
+    ///
+    /// Files in archives larger than this are removed from the expanded archive
+    /// and reported with an [`Error::EntryTooLarge`] warning on the archive,
+    /// protecting against decompression bombs consisting of a single highly compressed file.
+    ///
+    /// Strategies that decompress files themselves stop writing once a file exceeds this size,
+    /// so such files never take more than this much space on disk.
+    /// Strategies backed by libarchive write each file in full before it is removed.
+    #[builder(default, setter(strip_option))]
+    max_entry_bytes: Option<u64>,
+
+    /// How archives that fail to expand are handled.
+    ///
+    /// Archives that aren't supported by any strategy, or that are in [`Options::skip_digests`], are never considered to have failed.
+    #[builder(default)]
+    on_error: ErrorPolicy,
+
+    /// How entry names in archives that aren't valid UTF-8 are decoded.
+    ///
+    /// Names that are valid UTF-8 are always used as-is.
+    /// The original bytes of any name that isn't are reported in [`expand::Entry::raw_name`].
+    #[builder(default)]
+    name_encoding: NameEncoding,
+
     /// Filters for file walking.
     ///
     /// Currently unused but planned to be used in the future.
@@ -53,6 +143,364 @@ pub struct Options {
     #[builder(setter(into), default = String::from(DEFAULT_ARCHIVE_POSTFIX))]
     #[derivative(Default(value = "String::from(DEFAULT_ARCHIVE_POSTFIX)"))]
     archive_postfix: String,
+
+    /// The directory under which archives are expanded.
+    ///
+    /// Each expanded archive is placed into a new temporary directory inside this directory.
+    /// If not provided, the system temporary directory ([`std::env::temp_dir`]) is used.
+    ///
+    /// On Windows, archives are expanded using an extended-length (`\\?\`) form of this path
+    /// so that deeply nested archives aren't limited by `MAX_PATH`;
+    /// destinations reported for expanded archives are therefore also extended-length paths.
+    #[builder(default, setter(strip_option, into))]
+    extraction_root: Option<PathBuf>,
+
+    /// The minimum free space in bytes required on the volume containing the extraction root.
+    ///
+    /// This is validated once before expansion begins; if the volume has less free space
+    /// than this, the expansion operation fails without expanding anything.
+    /// The default of zero disables the check.
+    ///
+    /// Regardless of this setting, each archive is checked before it is expanded to ensure the volume
+    /// has room for its estimated expanded size plus this headroom.
+    #[builder(default)]
+    min_free_space: u64,
+
+    /// Called with progress events as the expansion operation runs.
+    ///
+    /// Events are reported synchronously from the thread performing the expansion,
+    /// so the handler should return quickly.
+    #[builder(default, setter(strip_option))]
+    on_event: Option<OnEvent>,
+
+    /// Allows the expansion operation to be cancelled cooperatively.
+    ///
+    /// Cancellation is checked between entries and before each archive is expanded;
+    /// once cancelled, the operation cleans up any temporary directories it has not yet handed to the caller
+    /// and reports [`Error::Cancelled`].
+    #[builder(default, setter(strip_option, into))]
+    cancel: Option<CancelToken>,
+
+    /// When provided, the walker doesn't delete the temporary directories to which archives are expanded.
+    /// Instead, each is recorded in the provided handle, which can later be used to clean them up.
+    ///
+    /// This does not affect [`expand::all()`], which instead supports [`expand::all::Expansion::persist`].
+    #[builder(default, setter(strip_option))]
+    persist: Option<Persisted>,
+
+    /// When provided, metrics about the expansion operation are collected into the provided handle.
+    #[builder(default, setter(strip_option))]
+    metrics: Option<Metrics>,
+
+    /// Custom strategies, registered via `with_strategy`.
+    #[builder(default, setter(skip))]
+    strategies: strategy::Registered,
+}
+
+impl Options {
+    /// Register a custom strategy for expanding archives.
+    ///
+    /// Custom strategies are asked whether they can expand each file before the built in strategies,
+    /// in the order in which they are registered. This allows support for formats the built in strategies don't handle
+    /// (or overriding how they handle a format) without changes to this library.
+    pub fn with_strategy(mut self, strategy: impl strategy::Strategy + 'static) -> Self {
+        self.strategies.push(Arc::new(strategy));
+        self
+    }
+
+    /// The maximum depth to which the archive at the logical path may be expanded,
+    /// or `None` if recursion is disabled.
+    pub(crate) fn depth_for(&self, path: &Path) -> Option<usize> {
+        match self.recursion {
+            Recursion::Enabled { depth } => self
+                .depth_overrides
+                .iter()
+                .filter(|(prefix, _)| path.starts_with(prefix))
+                .max_by_key(|(prefix, _)| prefix.components().count())
+                .map(|(_, depth)| *depth)
+                .or(Some(depth)),
+            Recursion::Disabled => None,
+        }
+    }
+
+    /// The directory in which temporary directories and files are created.
+    ///
+    /// On Windows this is an extended-length path, so that deeply nested archives can be expanded inside it.
+    pub(crate) fn temp_root(&self) -> PathBuf {
+        let root = self
+            .extraction_root
+            .clone()
+            .unwrap_or_else(std::env::temp_dir);
+        path::extended_length(root)
+    }
+
+    /// Validate that the extraction root is a directory with enough free space.
+    pub(crate) fn validate_extraction_root(&self) -> Result<(), Error> {
+        let root = self.temp_root();
+        if !root.is_dir() {
+            return invariant!(ExtractionRoot, root);
+        }
+
+        if self.min_free_space > 0 {
+            let available =
+                fs2::available_space(&root).map_err(|error| Error::ExtractionRootSpace {
+                    root: root.clone(),
+                    error,
+                })?;
+            if available < self.min_free_space {
+                return Err(Error::Invariant(Invariant::ExtractionRootFull {
+                    root,
+                    available,
+                    required: self.min_free_space,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the operation has been cancelled.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .map(|cancel| cancel.is_cancelled())
+            .unwrap_or(false)
+    }
+
+    /// Report an event to the `on_event` handler, if one is set.
+    ///
+    /// The event is only constructed if there is a handler to receive it.
+    pub(crate) fn emit(&self, event: impl FnOnce() -> ExpandEvent) {
+        if let Some(on_event) = &self.on_event {
+            on_event.emit(event());
+        }
+    }
+}
+
+/// Progress events reported during an expansion operation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum ExpandEvent {
+    /// An archive was discovered and is about to be expanded.
+    ///
+    /// The path is the location of the archive on disk, which may be inside a temporary directory.
+    ArchiveDiscovered {
+        /// The path to the archive.
+        path: PathBuf,
+    },
+
+    /// An archive was expanded.
+    ///
+    /// The path is the location of the archive on disk, which may be inside a temporary directory.
+    ArchiveExpanded {
+        /// The path to the archive.
+        path: PathBuf,
+
+        /// The total size in bytes of the files extracted from the archive.
+        bytes: u64,
+    },
+
+    /// An entry was walked.
+    ///
+    /// For the iterator walker, this is the logical path of the entry reported to the caller;
+    /// otherwise it is the location of the entry on disk.
+    EntryWalked {
+        /// The path to the entry.
+        path: PathBuf,
+    },
+}
+
+/// A handler for [`ExpandEvent`]s, set via [`Options`].
+#[derive(Clone)]
+pub struct OnEvent(Arc<dyn Fn(ExpandEvent) + Send + Sync>);
+
+impl OnEvent {
+    /// Create a new handler.
+    pub fn new(handler: impl Fn(ExpandEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    /// Report the event to the handler.
+    pub fn emit(&self, event: ExpandEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for OnEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OnEvent")
+    }
+}
+
+/// A cooperative cancellation token, set via [`Options`].
+///
+/// Clones share the same underlying token, so one clone can be used to cancel an operation
+/// that was provided another.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of any operation using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CancelToken")
+            .field(&self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Records the temporary directories of archives expanded by the walker when persistence is enabled via [`Options`].
+///
+/// Clones share the same underlying records, so a clone can be provided in [`Options`]
+/// and the original used to inspect and clean up the directories once the walk is complete.
+///
+/// Unlike [`expand::all::Expansion`], directories are _not_ deleted when this is dropped;
+/// it is the caller's responsibility to call `cleanup` once the directories are no longer needed.
+#[derive(Clone, Debug, Default)]
+pub struct Persisted(Arc<Mutex<HashMap<Source, Destination>>>);
+
+impl Persisted {
+    /// Create a new, empty handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locations mapping the logical path of each expanded archive (the [`Source`])
+    /// to the location on the file system to which it was expanded (the [`Destination`]).
+    ///
+    /// Archives with identical content may share a destination; see `Options::deduplicate`.
+    pub fn locations(&self) -> HashMap<Source, Destination> {
+        self.lock().clone()
+    }
+
+    /// Delete all destinations recorded so far and clear the records.
+    ///
+    /// If no errors are encountered the result is ok;
+    /// if any errors are encountered they are collected into the returned error list.
+    ///
+    /// It is supported to call `cleanup` multiple times; only destinations recorded since the last call are deleted.
+    pub fn cleanup(&self) -> Result<(), Vec<Error>> {
+        let destinations = mem::take(&mut *self.lock())
+            .into_values()
+            .collect::<HashSet<_>>();
+
+        let errors = destinations
+            .into_iter()
+            .filter_map(|dest| {
+                let destination = dest.into_inner();
+                fs::remove_dir_all(&destination)
+                    .err()
+                    .map(|error| Error::Cleanup { destination, error })
+            })
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Record an expanded archive.
+    pub(crate) fn record(&self, source: PathBuf, destination: PathBuf) {
+        self.lock().insert(source.into(), destination.into());
+    }
+
+    /// Records only ever contain complete entries, so they are still usable if another thread panicked while holding the lock.
+    fn lock(&self) -> MutexGuard<'_, HashMap<Source, Destination>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Collects metrics about expansion operations when provided via [`Options`].
+///
+/// Clones share the same underlying metrics, so a clone can be provided in [`Options`]
+/// and the original used to inspect the metrics during or after the operation.
+/// If the same handle is provided to multiple operations, their metrics are combined.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics(Arc<Mutex<MetricsReport>>);
+
+impl Metrics {
+    /// Create a new, empty handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The metrics collected so far.
+    pub fn report(&self) -> MetricsReport {
+        self.lock().clone()
+    }
+
+    /// Record an archive expanded by the named strategy.
+    pub(crate) fn record_expansion(&self, strategy: String, bytes: u64, duration: Duration) {
+        let mut report = self.lock();
+        *report.expanded.entry(strategy).or_default() += 1;
+        report.bytes_written += bytes;
+        report.duration += duration;
+    }
+
+    /// Record that an archive was expanded at the given depth.
+    pub(crate) fn record_depth(&self, depth: usize) {
+        let mut report = self.lock();
+        report.deepest = report.deepest.max(depth);
+    }
+
+    /// Record warnings reported for the operation.
+    pub(crate) fn record_warnings(&self, count: usize) {
+        if count > 0 {
+            self.lock().warnings += count;
+        }
+    }
+
+    /// Metrics are only ever updated in place, so they are still usable if another thread panicked while holding the lock.
+    fn lock(&self) -> MutexGuard<'_, MetricsReport> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Metrics collected about expansion operations. See [`Metrics`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters, CopyGetters)]
+pub struct MetricsReport {
+    /// The number of archives expanded by each strategy, by the name of the strategy.
+    #[getset(get = "pub")]
+    expanded: HashMap<String, usize>,
+
+    /// The total size of the files written to disk by expanding archives.
+    #[getset(get_copy = "pub")]
+    bytes_written: u64,
+
+    /// The total time spent expanding archives.
+    /// If archives are expanded in parallel, this may exceed the time taken by the operation.
+    #[getset(get_copy = "pub")]
+    duration: Duration,
+
+    /// The deepest recursion depth at which an archive was expanded.
+    /// Archives in the root are expanded at depth 1.
+    #[getset(get_copy = "pub")]
+    deepest: usize,
+
+    /// The number of warnings reported.
+    #[getset(get_copy = "pub")]
+    warnings: usize,
 }
 
 /// Recursion mode for expanding archives.
@@ -82,7 +530,7 @@ pub enum Recursion {
     /// Recursive archive expansion is disabled. Only the root directory is evaluated for archives.
     ///
     /// If the root provided is an archive and recursion is disabled the root's children are _not_ expanded.
-    /// An option if this is not desired is `Recursion::Enabled{ depth: 1 }`, since the initial archive
+    /// An option if this is not desired is `Recursion::Enabled { depth: 1 }`, since the initial archive
     /// does not count against the recursion limit.
     Disabled,
 }
@@ -98,6 +546,91 @@ pub enum Identification {
     MatchExtension,
 }
 
+/// Whiteout mode for container image layers.
+///
+/// Container image layers record deleted files from lower layers using "whiteout" marker files:
+/// https://github.com/opencontainers/image-spec/blob/main/layer.md#whiteouts
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Whiteout {
+    /// Files removed by a higher layer are removed from the lower layers they're in,
+    /// and the whiteout marker files themselves are removed.
+    ///
+    /// Layers may be shared between images in the same tarball,
+    /// so files are only removed from a layer if every image using it removes them.
+    #[default]
+    Apply,
+
+    /// Layers are expanded as-is: files removed by higher layers are still present in the lower layer,
+    /// and whiteout marker files are reported like any other file.
+    Ignore,
+}
+
+/// Policy for archives that fail to expand.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum ErrorPolicy {
+    /// The failure is reported as a warning and the operation continues.
+    ///
+    /// The walker reports the failure on the [`expand::Entry`] for the archive, which is still walked as a file;
+    /// [`expand::all()`] reports it in [`expand::all::Expansion::warnings`].
+    #[default]
+    WarnAndContinue,
+
+    /// The operation stops at the first failure, reporting [`Error::ExpandArchive`].
+    ///
+    /// The walker outputs the error in place of the entry for the archive and then ends;
+    /// [`expand::all()`] returns the error after cleaning up any archives it already expanded.
+    /// This is useful for scans that must not silently miss the contents of an archive.
+    FailFast,
+}
+
+/// Encoding used to decode entry names in archives that aren't valid UTF-8.
+///
+/// Many archive formats don't record the encoding of entry names:
+/// for example zip files created without the UTF-8 flag commonly use the code page of the system that created them.
+/// Whatever the encoding, names that are valid UTF-8 are used as-is.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum NameEncoding {
+    /// Invalid sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    #[default]
+    Utf8,
+
+    /// IBM code page 437, the original encoding of names in zip files.
+    Cp437,
+
+    /// Shift JIS, commonly used by archives created on Japanese systems.
+    ShiftJis,
+}
+
+/// Policy for symlinks and hardlinks contained in archives.
+///
+/// The policy is applied to each archive after it is expanded, before its contents are walked:
+/// once the policy is applied, no symlinks remain in the expanded directory.
+/// Hardlinks between files in the same archive are indistinguishable from regular files once expanded
+/// and are always kept; only hardlinks referencing files outside the expanded directory are subject to the policy.
+/// Tarballs are an exception: hardlinks in them are always expanded as copies, or reported as [`Error::SpecialFile`]
+/// if they reference a file that isn't in the archive.
+/// Symlinks in the other formats expanded with libarchive are reported as [`Error::SpecialFile`] instead,
+/// since libarchive doesn't report their targets when archives are unpacked entry by entry.
+///
+/// Symlinks in the target itself (as opposed to inside an archive) are not affected by this policy.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum SymlinkPolicy {
+    /// Links are removed from the expanded archive and are not walked.
+    Skip,
+
+    /// Expanding an archive containing links fails.
+    Error,
+
+    /// Symlinks are resolved relative to the root of the archive: absolute symlinks are treated as if the archive
+    /// were the file system root, and relative symlinks may not traverse above the archive root.
+    ///
+    /// Symlinks that resolve to a file inside the archive are replaced by a copy of that file.
+    /// All other links (symlinks to directories, symlinks that are dangling or resolve outside the archive,
+    /// and hardlinks referencing files outside the archive) are removed.
+    #[default]
+    ResolveWithinArchive,
+}
+
 /// Filters for file walking.
 ///
 /// Because everything is walked by default, the filter mechanism is
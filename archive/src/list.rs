@@ -0,0 +1,29 @@
+//! Listing archive contents without expanding them.
+
+use log::debug;
+
+use crate::{strategy, Error, Listing, Options, Target};
+
+/// List the entries in the archive at `target`, without expanding it to disk when possible.
+///
+/// This allows previewing the contents of an archive, or deciding whether it's worth expanding.
+/// Like [`crate::expand::stream()`], archives nested inside `target` are not expanded;
+/// they are listed like any other file. Filters and recursion options are not consulted.
+///
+/// When the strategy handling the archive supports it, entries are read directly from the archive.
+/// Otherwise the archive is expanded into a temporary directory inside the extraction root,
+/// which is deleted once all entries are listed.
+pub fn list(target: Target, options: Options) -> Result<Vec<Listing>, Error> {
+    debug!("Listing {target:?} with {options:?}");
+    options.validate_extraction_root()?;
+
+    if !target.root().is_file() {
+        debug!("{:?} is not a file", target.root());
+        return Err(Error::Expand(strategy::Error::NotSupported));
+    }
+
+    let strategies = strategy::List::new(&options);
+    debug!("using {strategies}");
+
+    strategies.list(target.root()).map_err(Error::from)
+}
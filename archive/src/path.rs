@@ -0,0 +1,77 @@
+//! Platform specific handling for paths at which archives are expanded.
+
+use std::path::PathBuf;
+
+/// Convert the path to an extended-length path, so that it can exceed `MAX_PATH` on Windows.
+///
+/// Deeply nested archives are expanded into directories nested just as deeply inside the extraction root,
+/// which for large projects quickly exceeds the 260 character limit Windows places on normal paths.
+/// Extended-length paths (those prefixed with `\\?\`) aren't subject to this limit,
+/// so the extraction root and walked roots are converted to them; all other paths are joined onto these.
+///
+/// Windows doesn't normalize extended-length paths, so the path is made absolute and normalized first.
+/// Paths that are already extended-length, or that can't be made absolute, are returned unchanged.
+#[cfg(windows)]
+pub(crate) fn extended_length(path: PathBuf) -> PathBuf {
+    use std::{
+        env,
+        ffi::OsString,
+        path::{Component, Prefix},
+    };
+
+    let absolute = match env::current_dir() {
+        Ok(cwd) if !path.is_absolute() => cwd.join(&path),
+        Ok(_) => path.clone(),
+        Err(_) => return path,
+    };
+
+    let mut components = absolute.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut extended = OsString::from(r"\\?\");
+                extended.push(prefix.as_os_str());
+                extended
+            }
+            Prefix::UNC(server, share) => {
+                let mut extended = OsString::from(r"\\?\UNC\");
+                extended.push(server);
+                extended.push(r"\");
+                extended.push(share);
+                extended
+            }
+            // Verbatim and device paths are already exempt from the limit.
+            _ => return path,
+        },
+        _ => return path,
+    };
+
+    let mut normal = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(name) => normal.push(name),
+            Component::ParentDir => {
+                normal.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+
+    // The root of a drive or share must end in a separator to refer to the root directory.
+    if normal.is_empty() {
+        extended.push(r"\");
+    }
+    for name in normal {
+        extended.push(r"\");
+        extended.push(name);
+    }
+    PathBuf::from(extended)
+}
+
+/// Convert the path to an extended-length path, so that it can exceed `MAX_PATH` on Windows.
+///
+/// Other platforms don't have a comparable limit, so the path is returned unchanged.
+#[cfg(not(windows))]
+pub(crate) fn extended_length(path: PathBuf) -> PathBuf {
+    path
+}
@@ -1,22 +1,66 @@
 //! Strategies for expanding archives.
+//!
+//! The built in strategies cover common archive formats;
+//! custom strategies implementing [`Strategy`] can be registered with [`crate::Options::with_strategy`].
 
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::path::PathBuf;
-use std::{fs::File, io, path::Path};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::Instant;
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
 
 use derive_more::Constructor;
+use getset::{CopyGetters, Getters};
+use sha2::{Digest, Sha256};
+use tempfile::{tempdir_in, tempfile_in, TempDir};
 
 use log::debug;
 use thiserror::Error;
 use walkdir::WalkDir;
 
-use crate::Identification;
+use crate::{ExpandEvent, Identification, NameEncoding, Options, SpecialFile};
 
+#[cfg(feature = "libarchive")]
+use self::br::Brotli;
+#[cfg(feature = "libarchive")]
+use self::compressed::Compressed;
+#[cfg(feature = "libarchive")]
+use self::container::Container;
 use self::deny::Deny;
+#[cfg(feature = "libarchive")]
+use self::gem::Gem;
+#[cfg(feature = "msi")]
+use self::installer::Installer;
+#[cfg(feature = "libarchive")]
 use self::libarchive::Libarchive;
+#[cfg(feature = "pure-rust")]
+pub(crate) use self::native::Native;
 
+#[cfg(feature = "libarchive")]
+mod br;
+#[cfg(feature = "libarchive")]
+mod compressed;
+#[cfg(feature = "libarchive")]
+mod container;
 mod deny;
+mod estimate;
+#[cfg(feature = "libarchive")]
+mod gem;
+#[cfg(feature = "msi")]
+mod installer;
+#[cfg(feature = "libarchive")]
 mod libarchive;
+mod links;
+mod names;
+#[cfg(feature = "pure-rust")]
+mod native;
+pub(crate) mod sniff;
+mod tarball;
 
 /// Errors encountered during archive expansion.
 #[derive(Debug, Error)]
@@ -35,8 +79,61 @@ pub enum Error {
     IO(#[from] io::Error),
 
     /// Libarchive expansion failed.
+    #[cfg(feature = "libarchive")]
     #[error("libarchive strategy")]
     Libarchive(#[from] compress_tools::Error),
+
+    /// Zip expansion failed.
+    #[cfg(feature = "pure-rust")]
+    #[error("zip strategy")]
+    Zip(#[from] zip::result::ZipError),
+
+    /// Unable to parse container image metadata.
+    #[error("parse container image metadata")]
+    ContainerMetadata(#[from] serde_json::Error),
+
+    /// A container image referenced a digest that could not be parsed.
+    #[error("invalid container image digest: {0}")]
+    ContainerDigest(String),
+
+    /// A container image referenced a path outside of the image.
+    #[error("container image references path outside the image: {0}")]
+    ContainerPath(String),
+
+    /// The archive contains a link, and links are disallowed by the symlink policy.
+    #[error("archive contains link at {0:?}, and links are disallowed")]
+    Link(PathBuf),
+
+    /// The volume containing the extraction root doesn't have enough free space to expand the archive.
+    #[error("insufficient space to expand archive: requires {required} bytes, {available} bytes available")]
+    InsufficientSpace {
+        /// The estimated number of bytes required to expand the archive, including the configured headroom.
+        required: u64,
+
+        /// The number of bytes available on the volume.
+        available: u64,
+    },
+
+    /// The archive has the same content as an archive it was expanded from, so expanding it would never terminate.
+    #[error("archive contains itself")]
+    SelfReferential,
+
+    /// The digest of the archive's content is in the skip list, so it isn't expanded.
+    /// See [`crate::Options::skip_digests`].
+    #[error("archive is in the skip list")]
+    SkipListed,
+
+    /// A custom strategy failed.
+    #[error("custom strategy")]
+    Custom(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Whether the error means the archive failed to expand, as opposed to the archive deliberately not being expanded.
+    /// Only failures stop the operation under [`crate::ErrorPolicy::FailFast`].
+    pub(crate) fn is_failure(&self) -> bool {
+        !matches!(self, Error::NotSupported | Error::SkipListed)
+    }
 }
 
 /// The result of attempting to extract a given path.
@@ -44,20 +141,410 @@ pub enum Error {
 pub(crate) struct Attempt {
     pub(crate) source: PathBuf,
     pub(crate) result: Result<PathBuf, Error>,
+
+    /// The content digests of the archives from which the path was expanded,
+    /// including the archive at the path itself if it was expanded.
+    pub(crate) ancestry: Vec<ContentDigest>,
+
+    /// Non-fatal errors encountered while expanding the archive.
+    pub(crate) warnings: Vec<crate::Error>,
 }
 
 /// Describes a strategy used to expand an archive.
-pub trait Strategy: Display {
-    /// Expand an archive at the provided path into a new temporary directory.
-    fn expand(&self, archive: File) -> Result<PathBuf, Error>;
+///
+/// For each file, strategies are asked in order whether they can expand it via `can_expand`;
+/// the first strategy to accept the file is used to expand it.
+/// Custom strategies registered with [`crate::Options::with_strategy`] are asked before the built in strategies.
+///
+/// The `Display` implementation names the strategy in logs.
+pub trait Strategy: Display + Send + Sync {
+    /// Expand an archive into a new temporary directory.
+    ///
+    /// `archive` is the handle returned by `can_expand`.
+    /// Temporary directories and files must be created using `ctx`.
+    /// The returned directory is owned by the caller, which deletes it once it is no longer needed.
+    fn expand(&self, archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error>;
 
     /// Check whether the archive can likely be expanded with the strategy.
+    ///
+    /// Returns [`Error::NotSupported`] if the strategy does not support the archive,
+    /// in which case the next strategy is asked. Any other error stops the search and is reported for the archive.
     fn can_expand(&self, archive: &Path) -> Result<File, Error>;
+
+    /// Estimate the total size in bytes of the files in the archive once expanded,
+    /// used to check for free space before the archive is expanded.
+    ///
+    /// By default this reads sizes from the archive's metadata for zip archives and gzip compressed files,
+    /// returning `None` for other formats. If no estimate is available, the size of the archive itself is used.
+    fn estimate_size(&self, archive: &Path) -> Option<u64> {
+        estimate::expanded_size(archive)
+    }
+
+    /// Stream the contents of each file in the archive to `visit`,
+    /// along with the path of the file relative to the root of the archive.
+    ///
+    /// `archive` is the handle returned by `can_expand`.
+    ///
+    /// By default this expands the archive into a temporary directory and then streams each file in it,
+    /// deleting the directory afterwards. Strategies able to read entries directly from the archive
+    /// should override this so that the archive is not written to disk.
+    fn stream(&self, archive: File, ctx: &Context<'_>, visit: &mut Visit<'_>) -> Result<(), Error> {
+        let dir = self.expand(archive, ctx)?;
+        let streamed = stream_dir(&dir, visit);
+        let removed = fs::remove_dir_all(&dir);
+        streamed?;
+        removed.map_err(Error::IO)
+    }
+
+    /// List the entries in the archive, with paths relative to the root of the archive.
+    ///
+    /// `archive` is the handle returned by `can_expand`.
+    ///
+    /// By default this expands the archive into a temporary directory and then lists the entries in it,
+    /// deleting the directory afterwards. Strategies able to read entry metadata directly from the archive
+    /// should override this so that the archive is not written to disk.
+    fn list(&self, archive: File, ctx: &Context<'_>) -> Result<Vec<Listing>, Error> {
+        let dir = self.expand(archive, ctx)?;
+        let listed = list_dir(&dir);
+        let removed = fs::remove_dir_all(&dir);
+        let listed = listed?;
+        removed?;
+        Ok(listed)
+    }
+}
+
+/// Called with the path and contents of each file streamed from an archive.
+///
+/// Contents not read by the visitor are skipped.
+pub type Visit<'a> = dyn FnMut(&Path, &mut dyn Read) -> Result<(), io::Error> + 'a;
+
+/// An entry in an archive, listed without expanding the archive.
+#[derive(Clone, Debug, PartialEq, Eq, Constructor, Getters, CopyGetters)]
+pub struct Listing {
+    /// The path of the entry, relative to the root of the archive.
+    #[getset(get = "pub")]
+    path: PathBuf,
+
+    /// The size of the entry in bytes, if it is a file and its size is known without reading it.
+    #[getset(get_copy = "pub")]
+    size: Option<u64>,
+
+    /// The kind of the entry.
+    #[getset(get_copy = "pub")]
+    kind: EntryKind,
+}
+
+/// The kind of an entry in an archive.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum EntryKind {
+    /// A regular file.
+    File,
+
+    /// A directory.
+    Directory,
+
+    /// A symlink.
+    Symlink,
+
+    /// A hard link to another entry in the archive.
+    HardLink,
+
+    /// Any other kind of entry, such as a named pipe or a device node.
+    Other,
+}
+
+impl EntryKind {
+    /// The kind of an entry with the unix file mode `mode`, as recorded by many archive formats.
+    pub(crate) fn from_mode(mode: u32) -> Self {
+        match mode & 0o170000 {
+            0o100000 => Self::File,
+            0o040000 => Self::Directory,
+            0o120000 => Self::Symlink,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Context provided to a [`Strategy`] when expanding an archive.
+#[derive(Debug, Clone, Copy, CopyGetters)]
+pub struct Context<'a> {
+    /// The path at which the archive being expanded is located.
+    #[getset(get_copy = "pub")]
+    path: &'a Path,
+
+    /// The directory in which temporary directories and files are created.
+    #[getset(get_copy = "pub")]
+    temp_root: &'a Path,
+
+    /// The maximum size in bytes of a single file expanded from the archive, if limited.
+    #[getset(get_copy = "pub")]
+    max_entry_bytes: Option<u64>,
+
+    /// How entry names that aren't valid UTF-8 are decoded.
+    #[getset(get_copy = "pub")]
+    name_encoding: NameEncoding,
+
+    /// Details recorded by the strategy while expanding the archive, if they're being recorded.
+    recorded: Option<&'a Mutex<Recorded>>,
+
+    /// The directory, relative to the root of the archive, under which the paths the strategy records are located.
+    prefix: Option<&'a Path>,
+}
+
+impl<'a> Context<'a> {
+    /// Create a new context for expanding the archive at `path`.
+    pub(crate) fn new(path: &'a Path, temp_root: &'a Path) -> Self {
+        Self {
+            path,
+            temp_root,
+            max_entry_bytes: None,
+            name_encoding: NameEncoding::default(),
+            recorded: None,
+            prefix: None,
+        }
+    }
+
+    /// Limit the size of files expanded from the archive.
+    pub(crate) fn with_max_entry_bytes(self, max_entry_bytes: Option<u64>) -> Self {
+        Self {
+            max_entry_bytes,
+            ..self
+        }
+    }
+
+    /// Decode entry names that aren't valid UTF-8 with the encoding.
+    pub(crate) fn with_name_encoding(self, name_encoding: NameEncoding) -> Self {
+        Self {
+            name_encoding,
+            ..self
+        }
+    }
+
+    /// Record details about the expansion into `recorded`.
+    pub(crate) fn with_recorded(self, recorded: &'a Mutex<Recorded>) -> Self {
+        Self {
+            recorded: Some(recorded),
+            ..self
+        }
+    }
+
+    /// Record paths relative to `prefix` instead of the root of the archive,
+    /// for strategies that expand parts of the archive into subdirectories.
+    pub(crate) fn within<'b>(&self, prefix: &'b Path) -> Context<'b>
+    where
+        'a: 'b,
+    {
+        Context {
+            prefix: Some(prefix),
+            ..*self
+        }
+    }
+
+    /// Record a file the strategy skipped while expanding the archive, so that it's reported as a warning.
+    pub(crate) fn skip(&self, skipped: Skipped) {
+        let skipped = match skipped {
+            Skipped::Oversized(path) => Skipped::Oversized(self.prefixed(path)),
+            Skipped::Special(path, kind) => Skipped::Special(self.prefixed(path), kind),
+        };
+        debug!("skipped {skipped:?} in {:?}", self.path);
+        self.record(|recorded| recorded.skipped.push(skipped));
+    }
+
+    /// Record the raw name of an entry whose name wasn't valid UTF-8,
+    /// along with the path relative to the root of the archive to which it was decoded.
+    pub(crate) fn record_raw_name(&self, path: PathBuf, raw: Vec<u8>) {
+        let path = self.prefixed(path);
+        debug!("decoded entry name {raw:?} to {path:?} in {:?}", self.path);
+        self.record(|recorded| {
+            recorded.raw_names.insert(path, raw);
+        });
+    }
+
+    fn prefixed(&self, path: PathBuf) -> PathBuf {
+        match self.prefix {
+            Some(prefix) => prefix.join(path),
+            None => path,
+        }
+    }
+
+    fn record(&self, record: impl FnOnce(&mut Recorded)) {
+        if let Some(recorded) = self.recorded {
+            record(
+                &mut recorded
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            );
+        }
+    }
+
+    /// Wrap a writer for a single file expanded from the archive,
+    /// so that no more than `max_entry_bytes` (plus one, to indicate that the limit was exceeded) are written to it.
+    ///
+    /// Files over the limit are removed once the archive is expanded and reported as warnings.
+    /// Strategies that decompress files themselves should wrap their destinations with this,
+    /// so that a single highly compressed file can't exhaust the disk before it's removed.
+    pub fn limit<W: Write>(&self, writer: W) -> Limited<W> {
+        Limited {
+            writer,
+            remaining: self.max_entry_bytes.map(|max| max.saturating_add(1)),
+        }
+    }
+
+    /// Create a new temporary directory inside the configured temporary root.
+    ///
+    /// The directory is deleted when the returned value is dropped;
+    /// use [`TempDir::into_path`] to keep it.
+    pub fn tempdir(&self) -> Result<TempDir, io::Error> {
+        tempdir_in(self.temp_root)
+    }
+
+    /// Create a new anonymous temporary file inside the configured temporary root.
+    ///
+    /// The file is deleted when the returned handle is closed.
+    pub fn tempfile(&self) -> Result<File, io::Error> {
+        tempfile_in(self.temp_root)
+    }
+}
+
+/// A writer which discards anything written beyond its limit. Created by [`Context::limit`].
+#[derive(Debug)]
+pub struct Limited<W> {
+    writer: W,
+    remaining: Option<u64>,
+}
+
+impl<W: Write> Write for Limited<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => return self.writer.write(buf),
+        };
+
+        // Claim the discarded bytes were written so that decompression isn't interrupted with an error.
+        let len = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(buf.len());
+        }
+        let written = self.writer.write(&buf[..len])?;
+        self.remaining = Some(remaining - written as u64);
+        Ok(if written == len { buf.len() } else { written })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A file in an archive that wasn't expanded, or that was removed once the archive was expanded.
+/// Paths are relative to the root of the archive.
+#[derive(Clone, Debug)]
+pub(crate) enum Skipped {
+    /// The file exceeded the size limit.
+    Oversized(PathBuf),
+
+    /// The file is a special file.
+    Special(PathBuf, SpecialFile),
+}
+
+impl Skipped {
+    /// The warning reporting the skipped file, given the configured size limit.
+    fn warning(&self, limit: Option<u64>) -> crate::Error {
+        match self {
+            Skipped::Oversized(path) => crate::Error::EntryTooLarge {
+                path: path.to_owned(),
+                limit: limit.unwrap_or_default(),
+            },
+            Skipped::Special(path, kind) => crate::Error::SpecialFile {
+                path: path.to_owned(),
+                kind: *kind,
+            },
+        }
+    }
+}
+
+/// Details recorded by a strategy while expanding an archive.
+#[derive(Debug, Default)]
+pub(crate) struct Recorded {
+    /// Files in the archive that weren't expanded or were removed once expanded.
+    pub(crate) skipped: Vec<Skipped>,
+
+    /// The raw names of entries whose names weren't valid UTF-8,
+    /// by the paths relative to the root of the archive to which they were decoded.
+    pub(crate) raw_names: HashMap<PathBuf, Vec<u8>>,
+}
+
+/// An archive expanded into a temporary directory, which is removed when this is dropped unless it is persisted.
+#[derive(Debug)]
+pub(crate) struct Expanded {
+    path: PathBuf,
+    digest: ContentDigest,
+    recorded: Recorded,
+    persist: bool,
+}
+
+impl Expanded {
+    /// The directory into which the archive was expanded.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The digest of the archive's content.
+    pub(crate) fn digest(&self) -> ContentDigest {
+        self.digest
+    }
+
+    /// Files in the archive that weren't expanded or were removed once expanded.
+    pub(crate) fn skipped(&self) -> &[Skipped] {
+        &self.recorded.skipped
+    }
+
+    /// The raw name of the entry at the path relative to the root of the archive, if its name wasn't valid UTF-8.
+    pub(crate) fn raw_name(&self, path: &Path) -> Option<&[u8]> {
+        self.recorded.raw_names.get(path).map(Vec::as_slice)
+    }
+}
+
+impl Drop for Expanded {
+    fn drop(&mut self) {
+        if !self.persist {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// The SHA-256 digest of an archive's content.
+pub(crate) type ContentDigest = [u8; 32];
+
+/// Custom strategies registered via [`crate::Options::with_strategy`].
+#[derive(Clone, Default)]
+pub(crate) struct Registered(Vec<Arc<dyn Strategy>>);
+
+impl Registered {
+    pub(crate) fn push(&mut self, strategy: Arc<dyn Strategy>) {
+        self.0.push(strategy);
+    }
+}
+
+impl std::fmt::Debug for Registered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|s| s.to_string()))
+            .finish()
+    }
 }
 
 /// Strategies monomorphized to the identification method used for an expand invocation.
-pub struct List {
-    strategies: Vec<Box<dyn Strategy>>,
+pub(crate) struct List {
+    strategies: Vec<Arc<dyn Strategy>>,
+    options: Options,
+    temp_root: PathBuf,
+
+    /// Archives expanded via `expand_shared`, by the digest of their content.
+    /// Entries are weak so that caching doesn't keep expanded archives on disk after they're no longer in use.
+    expanded: Mutex<HashMap<ContentDigest, Weak<Expanded>>>,
 }
 
 impl Display for List {
@@ -73,21 +560,121 @@ impl Display for List {
 }
 
 impl List {
-    /// Create a new set of strategies with the provided identification strategy.
-    pub fn new(identification: Identification) -> Self {
+    /// Create a new set of strategies configured by the provided options.
+    pub fn new(options: &Options) -> Self {
+        let identification = options.identification;
+
+        // Custom strategies take precedence, so that they're able to override the built in strategies.
+        let mut strategies = options.strategies.0.clone();
+
+        #[cfg(feature = "libarchive")]
+        strategies.extend([
+            Arc::new(Container::new(identification, options.whiteout)) as Arc<dyn Strategy>,
+            Arc::new(Gem::new(identification)),
+        ]);
+
+        #[cfg(feature = "msi")]
+        strategies.push(Arc::new(Installer::new(identification)));
+
+        #[cfg(feature = "libarchive")]
+        strategies.extend([
+            Arc::new(Libarchive::new(identification)) as Arc<dyn Strategy>,
+            Arc::new(Brotli::new(identification)),
+            Arc::new(Compressed::new(identification)),
+        ]);
+
+        // Registered after the libarchive powered strategies, so that it's only used for archives they don't support.
+        #[cfg(feature = "pure-rust")]
+        strategies.push(Arc::new(Native::new(identification)));
+
+        strategies.push(Arc::new(Deny::new(identification)));
+
         Self {
-            strategies: vec![
-                Box::new(Libarchive::new(identification)),
-                Box::new(Deny::new(identification)),
-            ],
+            strategies,
+            options: options.clone(),
+            temp_root: options.temp_root(),
+            expanded: Default::default(),
         }
     }
 
-    /// Expand the archive with one of the registered strategies.
-    pub fn expand(&self, archive: &Path) -> Result<PathBuf, Error> {
+    /// Attempt to expand the archive with one of the registered strategies.
+    ///
+    /// `ancestry` is the content digests of the archives from which this archive was expanded;
+    /// if the archive has the same content as any of them, it isn't expanded again.
+    pub(crate) fn attempt(&self, archive: PathBuf, ancestry: &[ContentDigest]) -> Attempt {
+        let mut ancestry = ancestry.to_vec();
+        let mut skipped = Vec::new();
+        let result = self.find(&archive).and_then(|(strategy, mut handle)| {
+            let digest = self.digest(&mut handle, &ancestry)?;
+            let (path, recorded) = self.expand_with(strategy, handle, &archive)?;
+            ancestry.push(digest);
+            skipped = recorded.skipped;
+            Ok(path)
+        });
+        let warnings = skipped_warnings(self.options.max_entry_bytes, &skipped).collect();
+        Attempt::new(archive, result, ancestry, warnings)
+    }
+
+    /// Expand the archive with one of the registered strategies,
+    /// reusing the expansion of an archive with identical content if one expanded by this list is still alive.
+    ///
+    /// `ancestry` is the content digests of the archives from which this archive was expanded;
+    /// if the archive has the same content as any of them, it isn't expanded again.
+    ///
+    /// If deduplication is disabled in the options, this always expands the archive.
+    pub(crate) fn expand_shared(
+        &self,
+        archive: &Path,
+        ancestry: &[ContentDigest],
+    ) -> Result<Arc<Expanded>, Error> {
+        let (strategy, mut handle) = self.find(archive)?;
+        let digest = self.digest(&mut handle, ancestry)?;
+        let expanded = |(path, recorded)| Expanded {
+            path,
+            digest,
+            recorded,
+            persist: self.options.persist.is_some(),
+        };
+        if !self.options.deduplicate {
+            let expansion = self.expand_with(strategy, handle, archive)?;
+            return Ok(Arc::new(expanded(expansion)));
+        }
+
+        let cached = self.cache().get(&digest).and_then(Weak::upgrade);
+        if let Some(expanded) = cached {
+            debug!("reusing expansion of identical archive for {archive:?}");
+            return Ok(expanded);
+        }
+
+        // The cache isn't locked during expansion so that other archives can be expanded in parallel;
+        // if an identical archive is expanded concurrently, whichever finishes last is cached.
+        let expanded = Arc::new(expanded(self.expand_with(strategy, handle, archive)?));
+        self.cache().insert(digest, Arc::downgrade(&expanded));
+        Ok(expanded)
+    }
+
+    /// Hash the content of the archive, ensuring it should be expanded.
+    ///
+    /// Errors with [`Error::SelfReferential`] if the digest is in `ancestry`,
+    /// or with [`Error::SkipListed`] if it's in the configured skip list.
+    fn digest(
+        &self,
+        handle: &mut File,
+        ancestry: &[ContentDigest],
+    ) -> Result<ContentDigest, Error> {
+        let digest = digest_unique(handle, ancestry)?;
+        if self.options.skip_digests.contains(&digest) {
+            debug!("archive is in the skip list");
+            return Err(Error::SkipListed);
+        }
+        Ok(digest)
+    }
+
+    /// Find the first registered strategy that can expand the archive.
+    fn find(&self, archive: &Path) -> Result<(&dyn Strategy, File), Error> {
         for strategy in &self.strategies {
             match strategy.can_expand(archive) {
-                Ok(handle) => return strategy.expand(handle),
+                Ok(handle) => return Ok((strategy.as_ref(), handle)),
                 Err(Error::NotSupported) => continue,
                 Err(e) => return Err(e),
             }
@@ -95,16 +682,120 @@ impl List {
         Err(Error::NotSupported)
     }
 
+    /// Expand the archive with the strategy, using the handle returned by the strategy's `can_expand`.
+    ///
+    /// Returns the expanded directory, along with the details recorded by the strategy;
+    /// files removed from the directory for exceeding the size limit are recorded as skipped.
+    fn expand_with(
+        &self,
+        strategy: &dyn Strategy,
+        handle: File,
+        archive: &Path,
+    ) -> Result<(PathBuf, Recorded), Error> {
+        self.options.emit(|| ExpandEvent::ArchiveDiscovered {
+            path: archive.to_owned(),
+        });
+        self.check_space(strategy, archive)?;
+        let recorded = Mutex::new(Recorded::default());
+        let ctx = Context::new(archive, &self.temp_root)
+            .with_max_entry_bytes(self.options.max_entry_bytes)
+            .with_name_encoding(self.options.name_encoding)
+            .with_recorded(&recorded);
+        let started = Instant::now();
+        let expanded = strategy.expand(handle, &ctx)?;
+        let oversized = links::apply_policy(&expanded, self.options.symlink_policy)
+            .and_then(|_| remove_oversized(&expanded, self.options.max_entry_bytes));
+        let oversized = match oversized {
+            Ok(oversized) => oversized,
+            Err(err) => {
+                let _ = fs::remove_dir_all(&expanded);
+                return Err(err);
+            }
+        };
+        if let Some(metrics) = &self.options.metrics {
+            let bytes = extracted_size(&expanded);
+            metrics.record_expansion(strategy.to_string(), bytes, started.elapsed());
+        }
+        self.options.emit(|| ExpandEvent::ArchiveExpanded {
+            path: archive.to_owned(),
+            bytes: extracted_size(&expanded),
+        });
+
+        let mut recorded = recorded
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let oversized = oversized.into_iter().map(Skipped::Oversized);
+        recorded.skipped.extend(oversized);
+        Ok((expanded, recorded))
+    }
+
+    /// Ensure the volume containing the temporary root has room to expand the archive,
+    /// so that expansion fails up front instead of partway through with an IO error.
+    fn check_space(&self, strategy: &dyn Strategy, archive: &Path) -> Result<(), Error> {
+        let estimate = match strategy.estimate_size(archive) {
+            Some(estimate) => estimate,
+            None => estimate::archive_size(archive).unwrap_or_default(),
+        };
+
+        let available = match fs2::available_space(&self.temp_root) {
+            Ok(available) => available,
+            Err(err) => {
+                debug!("unable to check free space in {:?}: {err}", self.temp_root);
+                return Ok(());
+            }
+        };
+
+        let required = estimate.saturating_add(self.options.min_free_space);
+        if available < required {
+            debug!("{archive:?} requires {required} bytes, {available} bytes available");
+            return Err(Error::InsufficientSpace {
+                required,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Lock the cache of expanded archives.
+    /// The cache only ever contains complete entries, so it is still usable if another thread panicked while holding it.
+    fn cache(&self) -> MutexGuard<'_, HashMap<ContentDigest, Weak<Expanded>>> {
+        self.expanded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Stream the contents of the archive with one of the registered strategies.
+    pub fn stream(&self, archive: &Path, visit: &mut Visit<'_>) -> Result<(), Error> {
+        let (strategy, handle) = self.find(archive)?;
+        self.options.emit(|| ExpandEvent::ArchiveDiscovered {
+            path: archive.to_owned(),
+        });
+        let ctx =
+            Context::new(archive, &self.temp_root).with_name_encoding(self.options.name_encoding);
+        strategy.stream(handle, &ctx, visit)
+    }
+
+    /// List the entries in the archive with one of the registered strategies.
+    pub fn list(&self, archive: &Path) -> Result<Vec<Listing>, Error> {
+        let (strategy, handle) = self.find(archive)?;
+        let ctx =
+            Context::new(archive, &self.temp_root).with_name_encoding(self.options.name_encoding);
+        strategy.list(handle, &ctx)
+    }
+
     /// Expand a single layer of archives (i.e. not recursively) in the directory
     /// using the first compatible strategy in the list.
     ///
     /// `include` determines whether a given path should be evaluated while iterating.
-    /// Paths provided to `include` are relative to `dir`.
+    /// Paths provided to `include` are the walked paths, which begin with `dir`.
     /// - If a directory is skipped (`include` returns `false`) it is not descended into.
     /// - If a file is skipped (`include` returns `false`) it is not considered for unarchiving.
+    ///
+    /// `ancestry` is the content digests of the archives from which `dir` was expanded.
     pub(crate) fn expand_layer(
         &self,
         dir: &Path,
+        ancestry: &[ContentDigest],
         include: impl Fn(&Path) -> bool,
     ) -> Result<Vec<Attempt>, Error> {
         debug!("expanding layer at {dir:?}");
@@ -127,19 +818,122 @@ impl List {
                 continue;
             }
 
-            let extracted = self.expand(entry.path());
-            if let Err(Error::NotSupported) = extracted {
-                debug!("entry is not an archive or is not supported");
-                continue;
-            }
+            self.options.emit(|| ExpandEvent::EntryWalked {
+                path: entry.path().to_owned(),
+            });
 
-            match extracted {
+            let attempt = self.attempt(entry.into_path(), ancestry);
+            match attempt.result {
+                Err(Error::NotSupported) => {
+                    debug!("entry is not an archive or is not supported");
+                    continue;
+                }
                 Ok(ref to) => debug!("expanded to {to:?}"),
                 Err(ref err) => debug!("failed to expand: {err}"),
             }
-            stack.push(Attempt::new(entry.into_path(), extracted));
+            stack.push(attempt);
         }
 
         Ok(stack)
     }
 }
+
+/// Compute the digest of the archive's content, rewinding the handle afterwards.
+///
+/// Errors with [`Error::SelfReferential`] if the digest is in `ancestry`,
+/// since the archive would then contain itself and expanding it would never terminate.
+fn digest_unique(handle: &mut File, ancestry: &[ContentDigest]) -> Result<ContentDigest, Error> {
+    let mut hasher = Sha256::new();
+    io::copy(handle, &mut hasher)?;
+    handle.seek(SeekFrom::Start(0))?;
+    let digest: ContentDigest = hasher.finalize().into();
+
+    if ancestry.contains(&digest) {
+        debug!("archive has the same content as one of its ancestors");
+        return Err(Error::SelfReferential);
+    }
+    Ok(digest)
+}
+
+/// Stream each file in the directory to `visit`, with paths relative to the directory.
+fn stream_dir(dir: &Path, visit: &mut Visit<'_>) -> Result<(), Error> {
+    for entry in WalkDir::new(dir).follow_links(false) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap_or_else(|_| entry.path());
+        let mut handle = File::open(entry.path())?;
+        visit(path, &mut handle)?;
+    }
+    Ok(())
+}
+
+/// List the entries in the directory, with paths relative to the directory.
+fn list_dir(dir: &Path) -> Result<Vec<Listing>, Error> {
+    let mut listed = Vec::new();
+    for entry in WalkDir::new(dir).min_depth(1).follow_links(false) {
+        let entry = entry?;
+        let file_type = entry.file_type();
+        let (kind, size) = if file_type.is_file() {
+            (EntryKind::File, Some(entry.metadata()?.len()))
+        } else if file_type.is_dir() {
+            (EntryKind::Directory, None)
+        } else if file_type.is_symlink() {
+            (EntryKind::Symlink, None)
+        } else {
+            (EntryKind::Other, None)
+        };
+
+        let path = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        listed.push(Listing::new(path.to_owned(), size, kind));
+    }
+    Ok(listed)
+}
+
+/// Warnings for the files skipped when expanding an archive, given the configured size limit.
+pub(crate) fn skipped_warnings(
+    limit: Option<u64>,
+    skipped: &[Skipped],
+) -> impl Iterator<Item = crate::Error> + '_ {
+    skipped.iter().map(move |skipped| skipped.warning(limit))
+}
+
+/// Remove files in the directory larger than `max_entry_bytes`, returning their paths relative to the directory.
+fn remove_oversized(dir: &Path, max_entry_bytes: Option<u64>) -> Result<Vec<PathBuf>, Error> {
+    let max = match max_entry_bytes {
+        Some(max) => max,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut removed = Vec::new();
+    for entry in WalkDir::new(dir).follow_links(false) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || entry.metadata()?.len() <= max {
+            continue;
+        }
+
+        debug!("removing {:?}, which exceeds {max} bytes", entry.path());
+        fs::remove_file(entry.path())?;
+        let path = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        removed.push(path.to_owned());
+    }
+    Ok(removed)
+}
+
+/// The total size of the files in the directory.
+/// Files that cannot be read are not counted.
+fn extracted_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|de| de.ok())
+        .filter(|de| de.file_type().is_file())
+        .filter_map(|de| de.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
@@ -0,0 +1,100 @@
+use std::{
+    io::{Seek, SeekFrom},
+    path::Path,
+};
+
+use brotli_decompressor::Decompressor;
+use compress_tools::{uncompress_archive, Ownership};
+use derive_more::Constructor;
+use log::debug;
+
+use super::*;
+
+/// The extension used by standalone brotli compressed files.
+const BROTLI_EXT: &str = ".br";
+
+/// The extension used by brotli compressed tarballs.
+const TARBALL_EXT: &str = ".tar.br";
+
+/// The buffer size used by the pure Rust brotli decoder.
+const BUFFER_SIZE: usize = 4096;
+
+/// Expands brotli compressed tarballs and decompresses standalone brotli compressed files.
+///
+/// Most builds of libarchive do not support brotli, so while libarchive is attempted first for tarballs,
+/// if it fails the tarball is decompressed with a pure Rust brotli decoder instead.
+///
+/// Standalone compressed files are always decompressed with the pure Rust brotli decoder.
+/// Like the compressed strategy, the destination contains a single file
+/// named the same as the compressed file without the `.br` extension.
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Brotli {
+    _identification: Identification,
+}
+
+impl Strategy for Brotli {
+    fn expand(&self, mut archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let file = match ctx.path().file_name().map(|file| file.to_string_lossy()) {
+            Some(file) => file.to_string(),
+            None => return Err(Error::NotSupported),
+        };
+
+        let dir = if file.ends_with(TARBALL_EXT) {
+            expand_tarball(ctx, &mut archive)?
+        } else if let Some(name) = file.strip_suffix(BROTLI_EXT) {
+            decompress_file(ctx, &mut archive, name)?
+        } else {
+            return Err(Error::NotSupported);
+        };
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir)
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        match path.file_name().map(|file| file.to_string_lossy()) {
+            Some(file) if file.len() > BROTLI_EXT.len() && file.ends_with(BROTLI_EXT) => {
+                File::open(path).map_err(Error::IO)
+            }
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for Brotli {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "brotli")
+    }
+}
+
+fn expand_tarball(ctx: &Context<'_>, archive: &mut File) -> Result<PathBuf, Error> {
+    let dir = ctx.tempdir()?;
+    match uncompress_archive(&mut *archive, dir.path(), Ownership::Ignore) {
+        Ok(_) => return Ok(dir.into_path()),
+        Err(err) => debug!("libarchive unable to expand brotli tarball, using fallback: {err}"),
+    }
+
+    // libarchive may have partially written to the original directory; start over with a new one.
+    let dir = ctx.tempdir()?;
+    let mut tarball = ctx.tempfile()?;
+    archive.seek(SeekFrom::Start(0))?;
+    io::copy(
+        &mut Decompressor::new(&mut *archive, BUFFER_SIZE),
+        &mut tarball,
+    )?;
+    tarball.seek(SeekFrom::Start(0))?;
+    uncompress_archive(&mut tarball, dir.path(), Ownership::Ignore)?;
+    Ok(dir.into_path())
+}
+
+fn decompress_file(ctx: &Context<'_>, archive: &mut File, name: &str) -> Result<PathBuf, Error> {
+    // libarchive passes through data it is unable to decompress when decompressing standalone files
+    // instead of reporting an error, so the pure Rust decoder is always used for standalone files.
+    let dir = ctx.tempdir()?;
+    let mut dest = ctx.limit(File::create(dir.path().join(name))?);
+    io::copy(
+        &mut Decompressor::new(&mut *archive, BUFFER_SIZE),
+        &mut dest,
+    )?;
+    Ok(dir.into_path())
+}
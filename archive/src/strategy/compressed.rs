@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use compress_tools::uncompress_data;
+use derive_more::Constructor;
+use lazy_static::lazy_static;
+
+use super::*;
+
+lazy_static! {
+    static ref SUPPORTED_EXTS: Vec<&'static str> = vec![".gz", ".xz", ".bz2", ".zst"];
+}
+
+/// Decompresses single compressed files which are not archives (for example `curl.1.gz` or `config.xml.xz`).
+///
+/// The destination contains a single file, named the same as the compressed file without the compression extension:
+/// ```not_rust
+/// config.xml.xz!_fossa.virtual_!/
+///   config.xml
+/// ```
+///
+/// Compressed archives (for example `.tar.gz`) are handled by the libarchive strategy, which is evaluated before this one.
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Compressed {
+    _identification: Identification,
+}
+
+impl Strategy for Compressed {
+    fn expand(&self, archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let name = decompressed_name(ctx.path()).ok_or(Error::NotSupported)?;
+        let dir = ctx.tempdir()?;
+        let mut dest = ctx.limit(File::create(dir.path().join(name))?);
+        uncompress_data(archive, &mut dest)?;
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        match decompressed_name(path) {
+            Some(_) => File::open(path).map_err(Error::IO),
+            None => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for Compressed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "compressed")
+    }
+}
+
+/// The name of the file once decompressed, if the file is a supported compressed file.
+fn decompressed_name(path: &Path) -> Option<String> {
+    let file = path.file_name()?.to_string_lossy();
+    SUPPORTED_EXTS
+        .iter()
+        .find_map(|ext| file.strip_suffix(ext))
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
@@ -0,0 +1,542 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Component, Path},
+};
+
+use compress_tools::{ArchiveContents, ArchiveIterator};
+use derive_more::Constructor;
+use serde::Deserialize;
+use tar::{Archive, EntryType};
+
+use crate::Whiteout;
+
+use super::libarchive::with_decompressed;
+use super::links::normalize;
+use super::names::enclosed;
+use super::*;
+
+/// Container images are expected to be tarballs (optionally compressed) with these extensions.
+const IMAGE_EXTS: [&str; 2] = [".tar", ".tar.gz"];
+
+/// Present at the root of `docker save` tarballs.
+const DOCKER_MANIFEST: &str = "manifest.json";
+
+/// Present at the root of OCI image layouts.
+///
+/// OCI image layouts also contain `index.json` at the root, but that name is common enough in ordinary tarballs
+/// that it isn't used to identify images.
+const OCI_LAYOUT: &str = "oci-layout";
+
+/// The entry point for OCI image layouts.
+const OCI_INDEX: &str = "index.json";
+
+/// The directory containing blobs in OCI image layouts, and in newer `docker save` tarballs.
+const BLOBS_DIR: &str = "blobs";
+
+/// Blobs larger than this aren't read as manifests: manifests and indexes are small JSON documents,
+/// while larger blobs are layers.
+const MAX_MANIFEST_BYTES: u64 = 4 * 1024 * 1024;
+
+/// The directory inside the destination containing each expanded layer.
+const LAYERS_DIR: &str = "layers";
+
+/// Files with this prefix in a layer indicate that the named file is removed from lower layers.
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Files with this name in a layer indicate that all contents of the parent directory in lower layers are removed.
+const WHITEOUT_OPAQUE: &str = ".wh..wh..opq";
+
+/// Expands container images, either created via `docker save` or stored as an OCI image layout tarball.
+///
+/// Rather than expanding the image tarball as-is (which would require another layer of expansion
+/// for each image layer and exposes image metadata), each layer is expanded into its own directory,
+/// named with the hex encoded digest of the layer blob (for legacy `docker save` tarballs this is instead the layer ID):
+///
+/// ```not_rust
+/// image.tar!_fossa.virtual_!/
+///   layers/
+///     <digest>/
+///       usr/bin/some_binary
+/// ```
+///
+/// By default whiteouts are applied (see [`Whiteout`]): files removed by a higher layer are removed from the layers
+/// below it, and the whiteout marker files themselves are removed. Layers may be shared between images,
+/// so a file is only removed from a layer if every image using that layer removes it.
+///
+/// The image tarball is read twice: once for the manifests listing the layers of each image,
+/// and once to stream each layer out of the tarball as it's expanded, so that only the expanded layers are written to disk.
+/// Layers are expanded like any other tarball, so the size limit and name encoding in the [`Context`] apply to them.
+///
+/// OCI image layouts are only supported when stored as a tarball;
+/// image layouts stored as a directory are walked as a normal directory.
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Container {
+    _identification: Identification,
+    whiteout: Whiteout,
+}
+
+impl Strategy for Container {
+    fn expand(&self, mut archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let manifests = read_image(archive.try_clone()?, read_manifests)?;
+        let images = if manifests.contains_key(Path::new(DOCKER_MANIFEST)) {
+            docker_layers(&manifests)?
+        } else {
+            oci_layers(&manifests)?
+        };
+
+        let blobs = images
+            .iter()
+            .flat_map(|image| image.layers.iter())
+            .map(|(digest, blob)| (blob.to_owned(), digest.to_owned()))
+            .collect::<HashMap<_, _>>();
+        let dir = ctx.tempdir()?;
+        let layers = dir.path().join(LAYERS_DIR);
+        archive.seek(SeekFrom::Start(0))?;
+        let expanded = read_image(archive, |image| expand_layers(image, &blobs, &layers, ctx))?;
+
+        if self.whiteout == Whiteout::Apply {
+            let images = images
+                .into_iter()
+                .map(|image| {
+                    image
+                        .layers
+                        .into_iter()
+                        .map(|(_, blob)| match expanded.get(&blob) {
+                            Some(digest) => Ok(digest.to_owned()),
+                            None => Err(io::Error::new(
+                                io::ErrorKind::NotFound,
+                                format!("layer {blob:?} is not in the image"),
+                            )),
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            apply_whiteouts(&images, &layers)?;
+        }
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        let is_tarball = match path.file_name().map(|file| file.to_string_lossy()) {
+            Some(file) => IMAGE_EXTS.iter().any(|ext| file.ends_with(ext)),
+            None => false,
+        };
+        if !is_tarball {
+            return Err(Error::NotSupported);
+        }
+
+        let mut handle = File::open(path)?;
+        if is_image(&mut handle) {
+            handle.seek(SeekFrom::Start(0))?;
+            Ok(handle)
+        } else {
+            Err(Error::NotSupported)
+        }
+    }
+}
+
+impl Display for Container {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "container")
+    }
+}
+
+/// Whether the tarball contains one of the files identifying a container image at its root.
+///
+/// Scanning stops at the first such file, so only tarballs that aren't images are read in full.
+fn is_image(handle: &mut File) -> bool {
+    let entries = match ArchiveIterator::from_read(handle) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+
+    for contents in entries {
+        match contents {
+            ArchiveContents::StartOfEntry(name, _) => {
+                let name = name.trim_start_matches("./");
+                if name == DOCKER_MANIFEST || name == OCI_LAYOUT {
+                    return true;
+                }
+            }
+            ArchiveContents::Err(_) => return false,
+            ArchiveContents::DataChunk(_) | ArchiveContents::EndOfEntry => continue,
+        }
+    }
+
+    false
+}
+
+/// An image in the tarball.
+#[derive(Debug)]
+struct Image {
+    /// The layers in the image, in order from the lowest layer to the highest.
+    /// Each layer is represented as the digest of the layer along with the path to its blob in the tarball.
+    layers: Vec<(String, PathBuf)>,
+}
+
+/// https://github.com/moby/moby/blob/master/image/spec/v1.2.md#combined-image-json--filesystem-changeset-format
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerManifest {
+    layers: Vec<String>,
+}
+
+/// https://github.com/opencontainers/image-spec/blob/main/image-index.md
+#[derive(Debug, Deserialize)]
+struct OciIndex {
+    manifests: Vec<OciDescriptor>,
+}
+
+/// https://github.com/opencontainers/image-spec/blob/main/manifest.md
+#[derive(Debug, Deserialize)]
+struct OciManifest {
+    #[serde(default)]
+    manifests: Vec<OciDescriptor>,
+
+    #[serde(default)]
+    layers: Vec<OciDescriptor>,
+}
+
+/// https://github.com/opencontainers/image-spec/blob/main/descriptor.md
+#[derive(Debug, Deserialize)]
+struct OciDescriptor {
+    digest: String,
+}
+
+impl OciDescriptor {
+    /// The path to the blob for this descriptor in the image layout, along with its hex encoded digest.
+    ///
+    /// Digests are in the form `<algorithm>:<encoded>`, where the algorithm is lowercase alphanumeric
+    /// and the encoded digest is lowercase hex; anything else is rejected,
+    /// since the digest is used to build the path to the blob.
+    fn blob(&self) -> Result<(String, PathBuf), Error> {
+        let valid = |part: &str, allowed: fn(&char) -> bool| {
+            !part.is_empty() && part.chars().all(|c| allowed(&c))
+        };
+
+        match self.digest.split_once(':') {
+            Some((algorithm, encoded))
+                if valid(algorithm, |c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                    && valid(encoded, |c| c.is_ascii_digit() || ('a'..='f').contains(c)) =>
+            {
+                Ok((
+                    encoded.to_owned(),
+                    Path::new(BLOBS_DIR).join(algorithm).join(encoded),
+                ))
+            }
+            _ => Err(Error::ContainerDigest(self.digest.clone())),
+        }
+    }
+}
+
+/// Resolve a path referenced by a `docker save` manifest to its path in the tarball.
+///
+/// Paths that could point outside the image root (absolute paths or paths containing `..`) are rejected.
+fn docker_path(path: &str) -> Result<PathBuf, Error> {
+    let relative = Path::new(path);
+    let valid = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    match enclosed(relative) {
+        Some(path) if valid => Ok(path),
+        _ => Err(Error::ContainerPath(path.to_owned())),
+    }
+}
+
+/// Read the entries of the image tarball with `read`, decompressing it first if it's compressed.
+fn read_image<T: Send>(
+    archive: File,
+    read: impl FnOnce(Archive<&mut dyn Read>) -> Result<T, Error> + Send,
+) -> Result<T, Error> {
+    let mut archive = BufReader::new(archive);
+    if sniff::compression_format(archive.fill_buf()?).is_some() {
+        with_decompressed(archive, |image| read(Archive::new(image)))
+    } else {
+        read(Archive::new(&mut archive))
+    }
+}
+
+/// Read the files in the image tarball that may be manifests, by their paths in the tarball.
+fn read_manifests(mut image: Archive<&mut dyn Read>) -> Result<HashMap<PathBuf, Vec<u8>>, Error> {
+    let mut manifests = HashMap::new();
+    for entry in image.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = match enclosed(&entry.path()?) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let is_manifest = path == Path::new(DOCKER_MANIFEST)
+            || path == Path::new(OCI_INDEX)
+            || (path.starts_with(BLOBS_DIR) && entry.size() <= MAX_MANIFEST_BYTES);
+        if is_manifest {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            manifests.insert(path, content);
+        }
+    }
+    Ok(manifests)
+}
+
+/// The content of the manifest at the path in the tarball.
+fn manifest<'a>(manifests: &'a HashMap<PathBuf, Vec<u8>>, path: &Path) -> Result<&'a [u8], Error> {
+    match manifests.get(path) {
+        Some(content) => Ok(content),
+        None => Err(Error::IO(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("manifest {path:?} is not in the image"),
+        ))),
+    }
+}
+
+/// Read the layers for each image in a `docker save` tarball.
+fn docker_layers(manifests: &HashMap<PathBuf, Vec<u8>>) -> Result<Vec<Image>, Error> {
+    let manifest = manifest(manifests, Path::new(DOCKER_MANIFEST))?;
+    let manifest = serde_json::from_slice::<Vec<DockerManifest>>(manifest)?;
+    manifest
+        .into_iter()
+        .map(|image| {
+            let layers = image
+                .layers
+                .into_iter()
+                .map(|layer| {
+                    let blob = docker_path(&layer)?;
+                    // Legacy layers are stored as `<id>/layer.tar`; newer layers are stored as `blobs/sha256/<digest>`.
+                    let digest = if blob.ends_with("layer.tar") {
+                        blob.parent().and_then(|parent| parent.file_name())
+                    } else {
+                        blob.file_name()
+                    };
+                    match digest {
+                        Some(digest) => Ok((digest.to_string_lossy().to_string(), blob)),
+                        None => Err(Error::ContainerDigest(layer)),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Image { layers })
+        })
+        .collect()
+}
+
+/// Read the layers for each image in an OCI image layout.
+fn oci_layers(manifests: &HashMap<PathBuf, Vec<u8>>) -> Result<Vec<Image>, Error> {
+    let index = manifest(manifests, Path::new(OCI_INDEX))?;
+    let index = serde_json::from_slice::<OciIndex>(index)?;
+
+    // Indexes may point to other indexes (for example, multi-platform images),
+    // so descend through them until reaching the image manifests.
+    let mut images = Vec::new();
+    let mut queue = index.manifests;
+    while let Some(descriptor) = queue.pop() {
+        let (_, blob) = descriptor.blob()?;
+        let manifest = serde_json::from_slice::<OciManifest>(manifest(manifests, &blob)?)?;
+        queue.extend(manifest.manifests);
+        if !manifest.layers.is_empty() {
+            let layers = manifest
+                .layers
+                .iter()
+                .map(OciDescriptor::blob)
+                .collect::<Result<_, _>>()?;
+            images.push(Image { layers });
+        }
+    }
+
+    Ok(images)
+}
+
+/// Expand each layer blob in `blobs` (by path in the tarball) as it's read from the image tarball,
+/// into the directory in `dest` named by the digest of the layer.
+///
+/// Returns the digest of the directory containing each expanded layer, by the path to its blob.
+/// Blobs that are links to other blobs are expanded once, under the digest of the blob they link to.
+fn expand_layers(
+    mut image: Archive<&mut dyn Read>,
+    blobs: &HashMap<PathBuf, String>,
+    dest: &Path,
+    ctx: &Context<'_>,
+) -> Result<HashMap<PathBuf, String>, Error> {
+    let mut expanded = HashMap::new();
+    let mut links = Vec::new();
+    for entry in image.entries()? {
+        let entry = entry?;
+        let path = match enclosed(&entry.path()?) {
+            Some(path) => path,
+            None => continue,
+        };
+        let digest = match blobs.get(&path) {
+            Some(digest) => digest,
+            None => continue,
+        };
+
+        // Legacy `docker save` tarballs link layers that are identical to an earlier layer.
+        let target = match entry.header().entry_type() {
+            EntryType::Symlink => entry
+                .link_name()?
+                .and_then(|target| normalize(&path.parent()?.join(target))),
+            EntryType::Link => entry.link_name()?.and_then(|target| normalize(&target)),
+            _ => {
+                expand_layer(entry, digest, dest, ctx)?;
+                expanded.insert(path, digest.to_owned());
+                continue;
+            }
+        };
+        if let Some(target) = target {
+            links.push((path, target));
+        }
+    }
+
+    for (link, target) in links {
+        if let Some(digest) = expanded.get(&target).cloned() {
+            expanded.insert(link, digest);
+        }
+    }
+    Ok(expanded)
+}
+
+/// Expand the layer blob into the directory in `dest` named by its digest, unless it was already expanded.
+fn expand_layer(
+    blob: impl Read,
+    digest: &str,
+    dest: &Path,
+    ctx: &Context<'_>,
+) -> Result<(), Error> {
+    let dest = dest.join(digest);
+    if dest.exists() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dest)?;
+    let prefix = Path::new(LAYERS_DIR).join(digest);
+    let ctx = ctx.within(&prefix);
+    let mut blob = BufReader::new(blob);
+    if sniff::compression_format(blob.fill_buf()?).is_some() {
+        with_decompressed(blob, |layer| {
+            tarball::unpack(BufReader::new(layer), &dest, &ctx)
+        })
+    } else {
+        tarball::unpack(blob, &dest, &ctx)
+    }
+}
+
+/// A whiteout marker in a layer, with paths relative to the root of the layer.
+#[derive(Debug)]
+enum Marker {
+    /// The path is removed from lower layers.
+    Removed(PathBuf),
+
+    /// All contents of the directory are removed from lower layers.
+    Opaque(PathBuf),
+}
+
+impl Marker {
+    /// Whether the marker removes the path from lower layers.
+    fn removes(&self, path: &Path) -> bool {
+        match self {
+            Marker::Removed(removed) => path.starts_with(removed),
+            Marker::Opaque(dir) => path.starts_with(dir) && path != dir,
+        }
+    }
+}
+
+/// Apply the whiteouts in the layers of each image, given as the digests of its layers in order from the lowest layer
+/// to the highest, to the expanded layers in `layers`; then remove the whiteout marker files.
+///
+/// Layers may be shared between images, so paths are only removed from a layer if every image using it removes them.
+fn apply_whiteouts(images: &[Vec<String>], layers: &Path) -> Result<(), Error> {
+    let digests = images.iter().flatten().collect::<HashSet<_>>();
+    let mut markers = HashMap::new();
+    for digest in &digests {
+        markers.insert(*digest, find_markers(&layers.join(digest))?);
+    }
+
+    for digest in digests {
+        // The markers in the layers above this one, for each image using it.
+        let above = images
+            .iter()
+            .flat_map(|image| {
+                let position = image.iter().position(|layer| layer == digest);
+                position.map(|position| {
+                    image[position + 1..]
+                        .iter()
+                        .flat_map(|layer| markers[layer].iter().map(|(_, marker)| marker))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let layer = layers.join(digest);
+        let mut removed = Vec::new();
+        let mut entries = WalkDir::new(&layer)
+            .min_depth(1)
+            .follow_links(false)
+            .into_iter();
+        while let Some(entry) = entries.next() {
+            let entry = entry?;
+            let path = entry.path().strip_prefix(&layer).unwrap_or(entry.path());
+            let removes =
+                |markers: &Vec<&Marker>| markers.iter().any(|marker| marker.removes(path));
+            if above.iter().all(removes) {
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                removed.push(entry.into_path());
+            }
+        }
+        for path in removed {
+            remove_path(&path)?;
+        }
+    }
+
+    for (_, markers) in markers {
+        for (path, _) in markers {
+            remove_path(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the whiteout markers in the expanded layer, along with the paths of the marker files.
+fn find_markers(layer: &Path) -> Result<Vec<(PathBuf, Marker)>, Error> {
+    let mut markers = Vec::new();
+    for entry in WalkDir::new(layer).min_depth(1).follow_links(false) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        let parent = entry
+            .path()
+            .parent()
+            .and_then(|parent| parent.strip_prefix(layer).ok())
+            .unwrap_or_else(|| Path::new(""));
+
+        let marker = if name == WHITEOUT_OPAQUE {
+            Marker::Opaque(parent.to_owned())
+        } else if let Some(removed) = name.strip_prefix(WHITEOUT_PREFIX) {
+            Marker::Removed(parent.join(removed))
+        } else {
+            continue;
+        };
+        markers.push((entry.path().to_owned(), marker));
+    }
+    Ok(markers)
+}
+
+/// Remove the file or directory at the path; paths that do not exist are not an error.
+fn remove_path(path: &Path) -> Result<(), io::Error> {
+    let removed = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(err) => Err(err),
+    };
+
+    match removed {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
@@ -11,7 +11,7 @@ pub struct Deny {
 }
 
 impl Strategy for Deny {
-    fn expand(&self, _: File) -> Result<PathBuf, Error> {
+    fn expand(&self, _: File, _: &Context<'_>) -> Result<PathBuf, Error> {
         Err(Error::NotSupported)
     }
 
@@ -0,0 +1,188 @@
+//! Estimates the size of archives once expanded, without expanding them.
+//!
+//! This is not a strategy; it is used to check for free space before an archive is expanded.
+//! Estimates only use metadata that is cheap to read, so they are only available for some formats.
+
+use super::*;
+
+/// Zip archives start with a local file header.
+const ZIP_MAGIC: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+
+/// Signature of the zip end of central directory record.
+const ZIP_EOCD_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x05, 0x06];
+
+/// Signature of a zip central directory file header.
+const ZIP_CDFH_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+
+/// The length of the zip end of central directory record, excluding its trailing comment.
+const ZIP_EOCD_LEN: usize = 22;
+
+/// Signature of the zip64 end of central directory locator, which immediately precedes the end of central directory record.
+const ZIP64_LOCATOR_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x06, 0x07];
+
+/// The length of the zip64 end of central directory locator.
+const ZIP64_LOCATOR_LEN: usize = 20;
+
+/// Signature of the zip64 end of central directory record.
+const ZIP64_EOCD_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x06, 0x06];
+
+/// The length of the zip64 end of central directory record, excluding its trailing extensible data.
+const ZIP64_EOCD_LEN: usize = 56;
+
+/// The header ID of the zip64 extended information extra field.
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+/// The length of a zip central directory file header, excluding its trailing variable length fields.
+const ZIP_CDFH_LEN: usize = 46;
+
+/// Gzip streams start with these bytes.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Estimate the total size in bytes of the files in the archive once it is expanded.
+///
+/// Supports zip archives (via the central directory) and gzip compressed files (via the trailer,
+/// which for gzip compressed tarballs is the size of the tarball). Returns `None` for other formats,
+/// or if the metadata can't be read.
+pub(super) fn expanded_size(archive: &Path) -> Option<u64> {
+    let mut file = File::open(archive).ok()?;
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic).ok()?;
+
+    if magic == ZIP_MAGIC {
+        zip_size(&mut file)
+    } else if magic[..2] == GZIP_MAGIC {
+        gzip_size(&mut file)
+    } else {
+        None
+    }
+}
+
+/// The size of the archive itself, used as a lower bound when the expanded size can't be estimated.
+pub(super) fn archive_size(archive: &Path) -> Option<u64> {
+    fs::metadata(archive).ok().map(|meta| meta.len())
+}
+
+/// Sum the uncompressed sizes recorded in the zip central directory.
+///
+/// Zip64 archives are supported: the location of the central directory is read from the zip64 end of central directory record
+/// if there is one, and sizes too large for the central directory file headers are read from their zip64 extra fields.
+fn zip_size(file: &mut File) -> Option<u64> {
+    let len = file.metadata().ok()?.len();
+
+    // The end of central directory record is followed by a comment of up to `u16::MAX` bytes.
+    let search = (ZIP_EOCD_LEN + u16::MAX as usize) as u64;
+    file.seek(SeekFrom::Start(len.saturating_sub(search)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+
+    let start = tail.windows(4).rposition(|w| w == ZIP_EOCD_SIGNATURE)?;
+    let eocd = tail.get(start..start + ZIP_EOCD_LEN)?;
+    let locator = start
+        .checked_sub(ZIP64_LOCATOR_LEN)
+        .and_then(|locator| tail.get(locator..start))
+        .filter(|locator| locator[..4] == ZIP64_LOCATOR_SIGNATURE);
+    let (entries, dir_len, dir_offset) = match locator {
+        Some(locator) => zip64_directory(file, locator)?,
+        None => {
+            let entries = u16_at(eocd, 10)?;
+            let dir_len = u32_at(eocd, 12)?;
+            let dir_offset = u32_at(eocd, 16)?;
+            if entries == u16::MAX || dir_len == u32::MAX || dir_offset == u32::MAX {
+                return None;
+            }
+            (
+                u64::from(entries),
+                u64::from(dir_len),
+                u64::from(dir_offset),
+            )
+        }
+    };
+    if dir_offset.checked_add(dir_len)? > len {
+        return None;
+    }
+
+    file.seek(SeekFrom::Start(dir_offset)).ok()?;
+    let mut dir = vec![0; usize::try_from(dir_len).ok()?];
+    file.read_exact(&mut dir).ok()?;
+
+    let mut total = 0u64;
+    let mut offset = 0;
+    for _ in 0..entries {
+        let header = dir.get(offset..offset + ZIP_CDFH_LEN)?;
+        if header[..4] != ZIP_CDFH_SIGNATURE {
+            return None;
+        }
+
+        let name_len = usize::from(u16_at(header, 28)?);
+        let extra_len = usize::from(u16_at(header, 30)?);
+        let comment_len = usize::from(u16_at(header, 32)?);
+
+        // Sizes that don't fit are recorded as `u32::MAX`, with the actual size in the zip64 extra field.
+        let size = match u32_at(header, 24)? {
+            u32::MAX => {
+                let extra_start = offset + ZIP_CDFH_LEN + name_len;
+                zip64_size(dir.get(extra_start..extra_start + extra_len)?)?
+            }
+            size => u64::from(size),
+        };
+        total = total.saturating_add(size);
+        offset += ZIP_CDFH_LEN + name_len + extra_len + comment_len;
+    }
+
+    Some(total)
+}
+
+/// Read the number of entries, length, and offset of the central directory
+/// from the zip64 end of central directory record referenced by the locator.
+fn zip64_directory(file: &mut File, locator: &[u8]) -> Option<(u64, u64, u64)> {
+    file.seek(SeekFrom::Start(u64_at(locator, 8)?)).ok()?;
+    let mut eocd = [0; ZIP64_EOCD_LEN];
+    file.read_exact(&mut eocd).ok()?;
+    if eocd[..4] != ZIP64_EOCD_SIGNATURE {
+        return None;
+    }
+    Some((u64_at(&eocd, 32)?, u64_at(&eocd, 40)?, u64_at(&eocd, 48)?))
+}
+
+/// Read the uncompressed size from the zip64 extended information extra field in the extra fields of a central directory file header.
+///
+/// The uncompressed size is the first value in the field, since it's only consulted if the header records the size as `u32::MAX`.
+fn zip64_size(mut extra: &[u8]) -> Option<u64> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0)?;
+        let len = usize::from(u16_at(extra, 2)?);
+        let data = extra.get(4..4 + len)?;
+        if id == ZIP64_EXTRA_ID {
+            return u64_at(data, 0);
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+/// Read the uncompressed size from the gzip trailer.
+///
+/// The trailer records the size modulo 2^32 of the final member only,
+/// so this underestimates very large or multi-member files.
+fn gzip_size(file: &mut File) -> Option<u64> {
+    file.seek(SeekFrom::End(-4)).ok()?;
+    let mut size = [0; 4];
+    file.read_exact(&mut size).ok()?;
+    Some(u64::from(u32::from_le_bytes(size)))
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buf.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> Option<u64> {
+    let bytes = buf.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
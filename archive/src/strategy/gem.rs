@@ -0,0 +1,73 @@
+use std::{
+    io::{Seek, SeekFrom},
+    path::Path,
+};
+
+use compress_tools::{uncompress_archive, uncompress_archive_file, uncompress_data, Ownership};
+use derive_more::Constructor;
+
+use super::*;
+
+/// The extension used by Ruby gems.
+const GEM_EXT: &str = ".gem";
+
+/// The gem entry containing the actual contents of the gem.
+const GEM_DATA: &str = "data.tar.gz";
+
+/// The gem entry containing the (gzip compressed) gem specification.
+const GEM_METADATA: &str = "metadata.gz";
+
+/// The name of the file to which the decompressed gem specification is written in the destination.
+const GEM_METADATA_DEST: &str = "metadata";
+
+/// Expands Ruby gems: https://guides.rubygems.org/specification-reference/
+///
+/// Gems are a plain tarball containing (among other things) a `data.tar.gz` with the actual contents of the gem
+/// and a `metadata.gz` containing the gem specification. Expanding only the outer tarball would require
+/// two more archive layers before the gem contents are reachable, so instead this strategy expands
+/// both of these at once into a single logical layer:
+///
+/// ```not_rust
+/// some.gem!_fossa.virtual_!/
+///   metadata
+///   lib/
+///     some.rb
+/// ```
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Gem {
+    _identification: Identification,
+}
+
+impl Strategy for Gem {
+    fn expand(&self, mut archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let dir = ctx.tempdir()?;
+
+        // libarchive needs to seek the inner archive, so buffer it in an anonymous temporary file.
+        let mut data = ctx.tempfile()?;
+        uncompress_archive_file(&mut archive, &mut data, GEM_DATA)?;
+        data.seek(SeekFrom::Start(0))?;
+        uncompress_archive(&mut data, dir.path(), Ownership::Ignore)?;
+
+        let mut metadata = Vec::new();
+        archive.seek(SeekFrom::Start(0))?;
+        uncompress_archive_file(&mut archive, &mut metadata, GEM_METADATA)?;
+        let mut dest = ctx.limit(File::create(dir.path().join(GEM_METADATA_DEST))?);
+        uncompress_data(metadata.as_slice(), &mut dest)?;
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        match path.file_name().map(|file| file.to_string_lossy()) {
+            Some(file) if file.ends_with(GEM_EXT) => File::open(path).map_err(Error::IO),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for Gem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gem")
+    }
+}
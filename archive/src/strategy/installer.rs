@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use derive_more::Constructor;
+use msi::Package;
+
+use super::*;
+
+/// The extension used by Windows Installer packages.
+const MSI_EXT: &str = ".msi";
+
+/// Expands Windows Installer packages: https://learn.microsoft.com/en-us/windows/win32/msi/windows-installer-portal
+///
+/// Windows Installer packages are OLE compound documents, which libarchive does not support.
+/// Each stream in the package is written as a file in the destination;
+/// notably this includes embedded cabinets (which contain the actual installed files),
+/// which are then expanded recursively like any other cabinet.
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Installer {
+    _identification: Identification,
+}
+
+impl Strategy for Installer {
+    fn expand(&self, archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let dir = ctx.tempdir()?;
+        let mut package = Package::open(archive)?;
+
+        let streams = package.streams().collect::<Vec<_>>();
+        for stream in streams {
+            let mut reader = package.read_stream(&stream)?;
+            let mut dest = ctx.limit(File::create(dir.path().join(stream_file_name(&stream)))?);
+            io::copy(&mut reader, &mut dest)?;
+        }
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        match path.file_name().map(|file| file.to_string_lossy()) {
+            Some(file) if file.ends_with(MSI_EXT) => File::open(path).map_err(Error::IO),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for Installer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "msi")
+    }
+}
+
+/// Stream names are arbitrary strings; make them safe to use as a file name.
+fn stream_file_name(stream: &str) -> String {
+    match stream {
+        "" | "." | ".." => String::from("_"),
+        stream => stream
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect(),
+    }
+}
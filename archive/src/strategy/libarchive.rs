@@ -1,38 +1,146 @@
 use std::{
-    io::{Read, Seek, SeekFrom},
+    cell::RefCell,
+    cmp,
+    io::{BufReader, Read, Seek, SeekFrom},
     path::Path,
+    thread,
+    time::{Duration, UNIX_EPOCH},
 };
 
-use compress_tools::{uncompress_archive, Ownership};
+use compress_tools::{uncompress_data, ArchiveContents, ArchiveIterator, DecodeCallback};
+use crossbeam::channel::{bounded, Receiver, Sender};
 use derive_more::Constructor;
 use lazy_static::lazy_static;
-use tempfile::tempdir;
 
+use super::names::{decode_path, enclosed};
+use super::tarball::create_dir_enclosed;
 use super::*;
 
+thread_local! {
+    /// Entry names that weren't valid UTF-8 decoded on this thread by a [`decoder`], along with their raw names.
+    ///
+    /// libarchive decodes names with a plain function pointer, so this is how decoded names are reported back to the strategy.
+    static DECODED: RefCell<Vec<(PathBuf, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+}
+
 lazy_static! {
-    static ref SUPPORTED_EXTS: Vec<&'static str> =
-        vec![".zip", ".tar", ".tar.gz", ".tar.xz", ".tar.bz2", ".rpm"];
+    static ref SUPPORTED_EXTS: Vec<&'static str> = vec![
+        ".zip", ".tar", ".tar.gz", ".tar.xz", ".tar.bz2", ".tar.zst", ".tar.lz4", ".rpm",
+        ".cpio", ".cpio.gz", ".cpgz",
+        // Java artifacts are zip archives.
+        ".jar", ".war", ".ear", ".aar",
+        // Python distributions are zip archives.
+        ".whl", ".egg",
+        // Rust crates are gzip compressed tarballs.
+        ".crate",
+        // NuGet packages (and symbol packages) are zip archives.
+        ".nupkg", ".snupkg",
+        // Android packages and bundles are zip archives.
+        // Their contents (`classes.dex`, native libraries, etc) are not archives and are walked as plain files.
+        ".apk", ".xapk", ".aab",
+        // Disk images; libarchive supports both ISO 9660 and UDF.
+        ".iso",
+        // Windows cabinets. Windows Installer packages are handled by a separate strategy, behind the `msi` feature.
+        ".cab",
+    ];
+
+    /// Initramfs images are (usually gzip-compressed) cpio archives,
+    /// but are conventionally named with a generic `.img` extension.
+    /// Only files with these name prefixes are considered to be initramfs images.
+    static ref INITRAMFS_PREFIXES: Vec<&'static str> = vec!["initramfs", "initrd"];
+
+    /// Compressed tarballs, which are decompressed by libarchive as they're unpacked by [`tarball::unpack`].
+    static ref COMPRESSED_TAR_EXTS: Vec<&'static str> = vec![
+        ".tar.gz", ".tar.xz", ".tar.bz2", ".tar.zst", ".tar.lz4", ".crate",
+    ];
 }
 
 /// The libarchive powered strategy: https://github.com/libarchive/libarchive.
 /// Formats: https://github.com/libarchive/libarchive#supported-formats
 ///
 /// Try to decompress everything that doesn't have a better strategy with this strategy.
+///
+/// Tarballs (identified by extension) are only decompressed by libarchive and are then unpacked separately,
+/// so that hard links and special files in them are handled consistently across platforms.
 #[derive(Copy, Clone, Debug, Default, Constructor)]
 pub struct Libarchive {
     identification: Identification,
 }
 
 impl Strategy for Libarchive {
-    fn expand(&self, mut path: File) -> Result<PathBuf, Error> {
-        let dir = tempdir()?;
-        uncompress_archive(&mut path, dir.path(), Ownership::Ignore)?;
+    fn expand(&self, path: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let dir = ctx.tempdir()?;
+        match tarball_kind(ctx.path()) {
+            Some(Tarball::Uncompressed) => tarball::unpack(BufReader::new(path), dir.path(), ctx)?,
+            Some(Tarball::Compressed) => unpack_compressed_tarball(path, dir.path(), ctx)?,
+            None => unpack_entries(path, dir.path(), ctx)?,
+        }
 
         // It's up to the caller to clean up temp dirs.
         Ok(dir.into_path())
     }
 
+    fn stream(&self, archive: File, ctx: &Context<'_>, visit: &mut Visit<'_>) -> Result<(), Error> {
+        let decode = decoder(ctx.name_encoding());
+        let mut entries = ArchiveIterator::from_read_with_encoding(archive, decode)?;
+        while let Some(contents) = entries.next() {
+            match contents {
+                ArchiveContents::StartOfEntry(name, _) => {
+                    let mut reader = EntryReader::new(&mut entries);
+
+                    // Directories are reported as entries with a trailing separator,
+                    // and entries with paths that would escape the archive are skipped.
+                    if !name.ends_with('/') {
+                        if let Some(path) = enclosed(Path::new(&name)) {
+                            visit(&path, &mut reader)?;
+                        }
+                    }
+
+                    // Skip whatever the visitor didn't read so the iterator is positioned at the next entry.
+                    io::copy(&mut reader, &mut io::sink())?;
+                }
+                ArchiveContents::Err(err) => return Err(err.into()),
+                ArchiveContents::DataChunk(_) | ArchiveContents::EndOfEntry => continue,
+            }
+        }
+
+        // Raw names are only reported for expanded archives.
+        take_decoded();
+        Ok(())
+    }
+
+    fn list(&self, archive: File, ctx: &Context<'_>) -> Result<Vec<Listing>, Error> {
+        let mut listed = Vec::new();
+        let decode = decoder(ctx.name_encoding());
+        let entries = ArchiveIterator::from_read_with_encoding(archive, decode)?;
+        for contents in entries {
+            match contents {
+                ArchiveContents::StartOfEntry(name, stat) => {
+                    // Entries with paths that would escape the archive are skipped, as when streaming.
+                    let path = match enclosed(Path::new(&name)) {
+                        Some(path) => path,
+                        None => continue,
+                    };
+
+                    // Mode types are narrower than `u32` on some platforms.
+                    #[allow(clippy::useless_conversion)]
+                    let kind = EntryKind::from_mode(u32::from(stat.st_mode));
+                    let size = match kind {
+                        EntryKind::File => u64::try_from(stat.st_size).ok(),
+                        _ => None,
+                    };
+                    listed.push(Listing::new(path, size, kind));
+                }
+                ArchiveContents::Err(err) => return Err(err.into()),
+                ArchiveContents::DataChunk(_) | ArchiveContents::EndOfEntry => continue,
+            }
+        }
+
+        // Raw names are only reported for expanded archives.
+        take_decoded();
+        Ok(listed)
+    }
+
     fn can_expand(&self, path: &Path) -> Result<File, Error> {
         // libarchive happily "expands" things that are not archives:
         //
@@ -61,6 +169,337 @@ impl Strategy for Libarchive {
     }
 }
 
+/// Unpack the compressed tarball into `dest` with [`tarball::unpack`].
+///
+/// The decompressed tarball is streamed to the unpacker, so it's never written to disk.
+fn unpack_compressed_tarball(archive: File, dest: &Path, ctx: &Context<'_>) -> Result<(), Error> {
+    with_decompressed(archive, |tarball| {
+        tarball::unpack(BufReader::new(tarball), dest, ctx)
+    })
+}
+
+/// Decompress `source` with libarchive, streaming the decompressed data to `consume`.
+///
+/// libarchive only decompresses into a writer, so `source` is decompressed on a separate thread
+/// and the decompressed data is passed to `consume` (on another thread) through bounded channels.
+/// `source` itself is read on the calling thread, so it doesn't need to be `Send`.
+/// Whatever `consume` doesn't read is decompressed and discarded once it returns.
+pub(super) fn with_decompressed<T: Send>(
+    mut source: impl Read,
+    consume: impl FnOnce(&mut dyn Read) -> Result<T, Error> + Send,
+) -> Result<T, Error> {
+    thread::scope(|scope| {
+        let (compressed, compressed_reader) = pipe();
+        let (decompressed, mut decompressed_reader) = pipe();
+        let decompressing = scope.spawn(move || uncompress_data(compressed_reader, decompressed));
+        let consuming = scope.spawn(move || -> Result<T, Error> {
+            let consumed = consume(&mut decompressed_reader)?;
+
+            // Drain any trailing data so that decompression finishes.
+            io::copy(&mut decompressed_reader, &mut io::sink())?;
+            Ok(consumed)
+        });
+
+        // The decompressor stops reading once it has failed or the consumer has stopped,
+        // in which case the error is reported by whichever of them stopped.
+        let fed = match io::copy(&mut source, &mut { compressed }) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(0),
+            fed => fed,
+        };
+        let consumed = join(consuming)?;
+        join(decompressing)?;
+        fed?;
+        Ok(consumed)
+    })
+}
+
+/// Wait for the thread to finish, propagating any panic.
+fn join<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Create a pipe passing data written to the [`PipeWriter`] to the [`PipeReader`].
+fn pipe() -> (PipeWriter, PipeReader) {
+    let (sender, receiver) = bounded(PIPE_CHUNKS);
+    (PipeWriter(sender), PipeReader::new(receiver))
+}
+
+/// The number of chunks buffered in each pipe.
+const PIPE_CHUNKS: usize = 16;
+
+/// Sends data written to it to a [`PipeReader`].
+struct PipeWriter(Sender<Vec<u8>>);
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "reader stopped reading"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the data written to a [`PipeWriter`], until the writer is dropped.
+struct PipeReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl PipeReader {
+    fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            chunk: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset >= self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.offset = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let remaining = &self.chunk[self.offset..];
+        let read = cmp::min(buf.len(), remaining.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+/// Unpack the archive into `dest` entry by entry, decoding entry names according to the [`NameEncoding`] in `ctx`.
+///
+/// - Files are written through [`Context::limit`], and files whose recorded size exceeds the context's
+///   `max_entry_bytes` are skipped, so that oversized files never reach the disk in full.
+/// - Symlinks, FIFOs and device nodes are skipped: libarchive doesn't report link targets when iterating entries,
+///   and the others have no content to walk.
+/// - Entries with paths that would escape the archive are skipped.
+///
+/// Skipped entries and the raw names of decoded entries are recorded in `ctx`.
+fn unpack_entries(archive: File, dest: &Path, ctx: &Context<'_>) -> Result<(), Error> {
+    let canonical_dest = dest.canonicalize()?;
+    take_decoded();
+    let decode = decoder(ctx.name_encoding());
+    let mut entries = ArchiveIterator::from_read_with_encoding(archive, decode)?;
+    while let Some(contents) = entries.next() {
+        let (name, stat) = match contents {
+            ArchiveContents::StartOfEntry(name, stat) => (name, stat),
+            ArchiveContents::Err(err) => return Err(err.into()),
+            ArchiveContents::DataChunk(_) | ArchiveContents::EndOfEntry => continue,
+        };
+
+        // Mode types are narrower than `u32` on some platforms.
+        #[allow(clippy::useless_conversion)]
+        let mode = u32::from(stat.st_mode);
+        let header = Header {
+            // Directories are reported as entries with a trailing separator.
+            kind: if name.ends_with('/') {
+                EntryKind::Directory
+            } else {
+                EntryKind::from_mode(mode)
+            },
+            mode,
+            size: u64::try_from(stat.st_size).unwrap_or_default(),
+            modified: u64::try_from(stat.st_mtime).ok(),
+        };
+
+        let mut reader = EntryReader::new(&mut entries);
+        if let Some(path) = enclosed(Path::new(&name)) {
+            unpack_entry(&mut reader, path, header, dest, &canonical_dest, ctx)?;
+        }
+
+        // Skip whatever wasn't unpacked so the iterator is positioned at the next entry.
+        io::copy(&mut reader, &mut io::sink())?;
+    }
+
+    for (path, raw) in take_decoded() {
+        if let Some(path) = enclosed(&path) {
+            ctx.record_raw_name(path, raw);
+        }
+    }
+    Ok(())
+}
+
+/// The details of an entry read from its header, used to unpack it.
+#[derive(Clone, Copy, Debug)]
+struct Header {
+    kind: EntryKind,
+    mode: u32,
+    size: u64,
+
+    /// Seconds since the unix epoch, if the entry was modified after it.
+    modified: Option<u64>,
+}
+
+/// Unpack a single entry at `path`, relative to `dest`, reading its contents from `entry`.
+fn unpack_entry(
+    entry: &mut impl Read,
+    path: PathBuf,
+    header: Header,
+    dest: &Path,
+    canonical_dest: &Path,
+    ctx: &Context<'_>,
+) -> Result<(), Error> {
+    let special = match header.kind {
+        EntryKind::Directory => {
+            create_dir_enclosed(&path, dest, canonical_dest)?;
+            return Ok(());
+        }
+        EntryKind::File => None,
+        EntryKind::Symlink => Some(SpecialFile::Symlink),
+        _ => special_file(header.mode),
+    };
+    if let Some(kind) = special {
+        ctx.skip(Skipped::Special(path, kind));
+        return Ok(());
+    }
+
+    // Most formats record the size of entries up front, so those over the limit aren't written at all.
+    // Others may record no size, so files are also written through the limit.
+    if ctx.max_entry_bytes().is_some_and(|max| header.size > max) {
+        ctx.skip(Skipped::Oversized(path));
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        if !create_dir_enclosed(parent, dest, canonical_dest)? {
+            return Ok(());
+        }
+    }
+
+    // Replace any existing entry at the path; if it were a symlink the file would be written through it.
+    let full = dest.join(&path);
+    match fs::remove_file(&full) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    let mut file = File::create(&full)?;
+    io::copy(entry, &mut ctx.limit(&mut file))?;
+    if let Some(modified) = header.modified {
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(modified))?;
+    }
+    set_permissions(&full, header.mode)
+}
+
+/// The kind of special file with the unix file mode `mode`, if it isn't a regular file, directory or symlink.
+fn special_file(mode: u32) -> Option<SpecialFile> {
+    match mode & 0o170000 {
+        0o010000 => Some(SpecialFile::Fifo),
+        0o020000 => Some(SpecialFile::CharDevice),
+        0o060000 => Some(SpecialFile::BlockDevice),
+        _ => None,
+    }
+}
+
+/// Apply the permission bits of the unix file mode to the unpacked file.
+#[cfg(unix)]
+fn set_permissions(path: &Path, mode: u32) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+    let permissions = mode & 0o7777;
+    if permissions != 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(permissions))?;
+    }
+    Ok(())
+}
+
+/// Unix permissions aren't applied on other platforms.
+#[cfg(not(unix))]
+fn set_permissions(_: &Path, _: u32) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Reads the data chunks of the current entry from an [`ArchiveIterator`] until the end of the entry.
+struct EntryReader<'a, R: Read + Seek> {
+    entries: &'a mut ArchiveIterator<R>,
+    chunk: Vec<u8>,
+    offset: usize,
+    finished: bool,
+}
+
+impl<'a, R: Read + Seek> EntryReader<'a, R> {
+    fn new(entries: &'a mut ArchiveIterator<R>) -> Self {
+        Self {
+            entries,
+            chunk: Vec::new(),
+            offset: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for EntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset >= self.chunk.len() {
+            if self.finished {
+                return Ok(0);
+            }
+
+            match self.entries.next() {
+                Some(ArchiveContents::DataChunk(chunk)) => {
+                    self.chunk = chunk;
+                    self.offset = 0;
+                }
+                Some(ArchiveContents::EndOfEntry) | None => self.finished = true,
+                Some(ArchiveContents::Err(err)) => {
+                    self.finished = true;
+                    return Err(io::Error::other(err));
+                }
+                Some(ArchiveContents::StartOfEntry(name, _)) => {
+                    self.finished = true;
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("entry {name:?} started before the previous entry ended"),
+                    ));
+                }
+            }
+        }
+
+        let remaining = &self.chunk[self.offset..];
+        let read = cmp::min(buf.len(), remaining.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+/// The function libarchive uses to decode entry names with the encoding.
+///
+/// Names that weren't valid UTF-8 are recorded for [`take_decoded`].
+fn decoder(encoding: NameEncoding) -> DecodeCallback {
+    fn decode(raw: &[u8], encoding: NameEncoding) -> compress_tools::Result<String> {
+        let (path, raw_name) = decode_path(raw, encoding);
+        if let Some(raw) = raw_name {
+            DECODED.with(|decoded| decoded.borrow_mut().push((path.clone(), raw)));
+        }
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    match encoding {
+        NameEncoding::Utf8 => |raw| decode(raw, NameEncoding::Utf8),
+        NameEncoding::Cp437 => |raw| decode(raw, NameEncoding::Cp437),
+        NameEncoding::ShiftJis => |raw| decode(raw, NameEncoding::ShiftJis),
+    }
+}
+
+/// Take the names decoded on this thread since the last call.
+fn take_decoded() -> Vec<(PathBuf, Vec<u8>)> {
+    DECODED.with(|decoded| std::mem::take(&mut *decoded.borrow_mut()))
+}
+
 /// Inspect the file to determine if it is binary.
 ///
 /// Uses the same method as git: "is there a zero byte in the first 8000 bytes of the file"
@@ -70,13 +509,38 @@ fn content_is_binary<R: Read>(stream: &mut R) -> Result<bool, io::Error> {
     Ok(buf.contains(&0))
 }
 
+/// Whether a tarball is compressed.
+enum Tarball {
+    Uncompressed,
+    Compressed,
+}
+
+fn tarball_kind(path: &Path) -> Option<Tarball> {
+    let file = path.file_name()?.to_string_lossy();
+    if file.ends_with(".tar") {
+        Some(Tarball::Uncompressed)
+    } else if COMPRESSED_TAR_EXTS.iter().any(|ext| file.ends_with(ext)) {
+        Some(Tarball::Compressed)
+    } else {
+        None
+    }
+}
+
 fn ext_is_supported(path: &Path) -> bool {
     match path.file_name().map(|file| file.to_string_lossy()) {
-        Some(file) => SUPPORTED_EXTS.iter().any(|ext| file.ends_with(ext)),
+        Some(file) => SUPPORTED_EXTS.iter().any(|ext| file.ends_with(ext)) || is_initramfs(&file),
         None => false,
     }
 }
 
+/// Initramfs images are named like `initramfs-6.1.0.img` or `initrd.img-6.1.0`.
+fn is_initramfs(file: &str) -> bool {
+    INITRAMFS_PREFIXES
+        .iter()
+        .any(|prefix| file.starts_with(prefix))
+        && file.contains(".img")
+}
+
 impl Display for Libarchive {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "libarchive")
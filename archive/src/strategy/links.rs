@@ -0,0 +1,170 @@
+//! Applies the [`SymlinkPolicy`] to expanded archives.
+//!
+//! This is not a strategy; it post-processes the directory output by any strategy
+//! so that link handling does not depend on the behavior of the underlying extraction library.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::SymlinkPolicy;
+
+use super::*;
+
+/// The maximum number of symlinks followed when resolving a single symlink, matching the Linux limit.
+const MAX_HOPS: usize = 40;
+
+/// Apply the policy to the links in the expanded archive at `root`.
+pub(super) fn apply_policy(root: &Path, policy: SymlinkPolicy) -> Result<(), Error> {
+    let links = find_links(root)?;
+    if links.is_empty() {
+        return Ok(());
+    }
+
+    match policy {
+        SymlinkPolicy::Error => Err(Error::Link(links[0].path().to_owned())),
+        SymlinkPolicy::Skip => links.iter().try_for_each(|link| remove(link.path())),
+        SymlinkPolicy::ResolveWithinArchive => {
+            // Resolve everything ahead of time, so that replacing a link doesn't affect resolution of another.
+            let canonical_root = root.canonicalize()?;
+            let resolved = links
+                .iter()
+                .map(|link| match link {
+                    Link::Symbolic(path) => (path, resolve(root, &canonical_root, path)),
+                    Link::Hard(path) => (path, None),
+                })
+                .collect::<Vec<_>>();
+
+            for (path, target) in resolved {
+                remove(path)?;
+                if let Some(target) = target {
+                    debug!("resolved link {path:?} to {target:?}");
+                    fs::copy(target, path)?;
+                } else {
+                    debug!("removed unresolvable link {path:?}");
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A link found in an expanded archive.
+enum Link {
+    /// A symlink.
+    Symbolic(PathBuf),
+
+    /// A hardlink to a file outside the expanded archive.
+    Hard(PathBuf),
+}
+
+impl Link {
+    fn path(&self) -> &Path {
+        match self {
+            Link::Symbolic(path) | Link::Hard(path) => path,
+        }
+    }
+}
+
+/// Find all symlinks in the directory, along with any hardlinks referencing files outside it.
+fn find_links(root: &Path) -> Result<Vec<Link>, Error> {
+    let mut links = Vec::new();
+    let mut hardlinks = HashMap::new();
+    for entry in WalkDir::new(root).follow_links(false) {
+        let entry = entry?;
+        if entry.path_is_symlink() {
+            links.push(Link::Symbolic(entry.into_path()));
+        } else if entry.file_type().is_file() {
+            if let Some((id, count)) = hardlink_id(&entry.metadata()?) {
+                let (paths, _) = hardlinks.entry(id).or_insert_with(|| (Vec::new(), count));
+                paths.push(entry.into_path());
+            }
+        }
+    }
+
+    // Every file in the expanded archive was created by the expansion,
+    // so if not all of the links to a file are inside it then the file is linked from outside.
+    for (paths, count) in hardlinks.into_values() {
+        if (paths.len() as u64) < count {
+            links.extend(paths.into_iter().map(Link::Hard));
+        }
+    }
+
+    Ok(links)
+}
+
+/// The device and inode identifying the file, along with its link count, if it has more than one link.
+#[cfg(unix)]
+fn hardlink_id(meta: &fs::Metadata) -> Option<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
+    if meta.nlink() > 1 {
+        Some(((meta.dev(), meta.ino()), meta.nlink()))
+    } else {
+        None
+    }
+}
+
+/// Link counts aren't available on stable Rust for non-unix platforms, so hardlinks aren't detected there.
+#[cfg(not(unix))]
+fn hardlink_id(_: &fs::Metadata) -> Option<((u64, u64), u64)> {
+    None
+}
+
+/// Resolve the symlink at `link` to the file it references inside the archive at `root`.
+///
+/// Returns `None` if the link is dangling, resolves outside the archive, resolves to something other than a file,
+/// or exceeds the maximum number of hops.
+fn resolve(root: &Path, canonical_root: &Path, link: &Path) -> Option<PathBuf> {
+    let mut current = link.to_owned();
+    for _ in 0..MAX_HOPS {
+        let meta = fs::symlink_metadata(&current).ok()?;
+        if !meta.file_type().is_symlink() {
+            // Intermediate directories may themselves be symlinks that lexical resolution doesn't account for,
+            // so ensure the final location is actually inside the archive.
+            let resolved = current.canonicalize().ok()?;
+            return if resolved.starts_with(canonical_root) && resolved.is_file() {
+                Some(resolved)
+            } else {
+                None
+            };
+        }
+
+        let target = fs::read_link(&current).ok()?;
+        let parent = current.parent()?.strip_prefix(root).ok()?;
+        let relative = if target.has_root() {
+            normalize(&target)?
+        } else {
+            normalize(&parent.join(target))?
+        };
+        current = root.join(relative);
+    }
+    None
+}
+
+/// Lexically normalize the path relative to the archive root,
+/// returning `None` if it traverses above the root.
+pub(super) fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(normalized)
+}
+
+/// Remove the link; links that no longer exist are not an error.
+fn remove(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result.map_err(Error::IO),
+    }
+}
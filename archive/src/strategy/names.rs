@@ -0,0 +1,73 @@
+//! Decodes and normalizes the names of entries in archives.
+//!
+//! This is not a strategy; it is used by the strategies that read entry names themselves,
+//! so that names are decoded according to the [`NameEncoding`] regardless of the underlying extraction library.
+
+use std::{borrow::Cow, path::Component, str};
+
+use super::*;
+
+/// The characters for bytes `0x80` through `0xFF` in code page 437; lower bytes are the same as ASCII.
+const CP437_HIGH: &str = concat!(
+    "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+/// Decode the raw name of an entry.
+///
+/// Names that are valid UTF-8 are returned as-is; all others are decoded with the encoding.
+pub(crate) fn decode(raw: &[u8], encoding: NameEncoding) -> Cow<'_, str> {
+    if let Ok(name) = str::from_utf8(raw) {
+        return Cow::Borrowed(name);
+    }
+
+    match encoding {
+        NameEncoding::Utf8 => String::from_utf8_lossy(raw),
+        NameEncoding::Cp437 => raw
+            .iter()
+            .map(|&b| match b.checked_sub(0x80) {
+                Some(high) => CP437_HIGH
+                    .chars()
+                    .nth(usize::from(high))
+                    .unwrap_or('\u{FFFD}'),
+                None => char::from(b),
+            })
+            .collect::<String>()
+            .into(),
+        NameEncoding::ShiftJis => encoding_rs::SHIFT_JIS
+            .decode_without_bom_handling(raw)
+            .0
+            .into_owned()
+            .into(),
+    }
+}
+
+/// Decode the raw name of an entry, returning the decoded path along with the raw name if it wasn't valid UTF-8.
+pub(super) fn decode_path(raw: &[u8], encoding: NameEncoding) -> (PathBuf, Option<Vec<u8>>) {
+    match decode(raw, encoding) {
+        Cow::Borrowed(name) => (PathBuf::from(name), None),
+        Cow::Owned(name) => (PathBuf::from(name), Some(raw.to_vec())),
+    }
+}
+
+/// Lexically normalize a path in the archive so that it is relative to the root of the archive,
+/// returning `None` if it traverses above the root.
+///
+/// As when unpacking other entries, absolute paths are treated as relative to the root of the archive.
+pub(super) fn enclosed(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => return None,
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use derive_more::Constructor;
+use flate2::read::MultiGzDecoder;
+use tar::{Archive, EntryType};
+use xz2::read::XzDecoder;
+use zip::{read::ZipFile, ZipArchive};
+
+use super::names::{decode_path, enclosed};
+use super::*;
+
+/// Zip archives, along with formats that are zip archives under a different extension.
+const ZIP_EXTS: [&str; 12] = [
+    ".zip", ".jar", ".war", ".ear", ".aar", ".whl", ".egg", ".nupkg", ".snupkg", ".apk", ".xapk",
+    ".aab",
+];
+
+/// Uncompressed tarballs.
+const TAR_EXTS: [&str; 1] = [".tar"];
+
+/// Gzip compressed tarballs. Rust crates are gzip compressed tarballs.
+const TAR_GZ_EXTS: [&str; 3] = [".tar.gz", ".tgz", ".crate"];
+
+/// Xz compressed tarballs.
+const TAR_XZ_EXTS: [&str; 2] = [".tar.xz", ".txz"];
+
+/// Standalone gzip compressed files.
+const GZ_EXT: &str = ".gz";
+
+/// Standalone xz compressed files.
+const XZ_EXT: &str = ".xz";
+
+/// Expands common archive formats using pure Rust implementations,
+/// for targets on which linking libarchive is impractical.
+///
+/// Formats: zip (including formats which are zip archives, such as Java and Android artifacts),
+/// tarballs (optionally gzip or xz compressed), and standalone gzip or xz compressed files.
+/// Like the compressed strategy, standalone compressed files are decompressed into a destination
+/// containing a single file named the same as the compressed file without its extension.
+///
+/// This strategy is registered after the libarchive powered strategies, so when the `libarchive` feature is enabled
+/// it's only used for the few archives they don't support.
+#[derive(Copy, Clone, Debug, Default, Constructor)]
+pub struct Native {
+    _identification: Identification,
+}
+
+impl Strategy for Native {
+    fn expand(&self, archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let format = Format::identify(ctx.path()).ok_or(Error::NotSupported)?;
+        let archive = BufReader::new(archive);
+        let dir = ctx.tempdir()?;
+
+        match format {
+            Format::Zip => unpack_zip(archive, dir.path(), ctx)?,
+            Format::Tar => tarball::unpack(archive, dir.path(), ctx)?,
+            Format::TarGz => tarball::unpack(MultiGzDecoder::new(archive), dir.path(), ctx)?,
+            Format::TarXz => tarball::unpack(XzDecoder::new(archive), dir.path(), ctx)?,
+            Format::Gz(name) => {
+                let mut dest = ctx.limit(File::create(dir.path().join(name))?);
+                io::copy(&mut MultiGzDecoder::new(archive), &mut dest)?;
+            }
+            Format::Xz(name) => {
+                let mut dest = ctx.limit(File::create(dir.path().join(name))?);
+                io::copy(&mut XzDecoder::new(archive), &mut dest)?;
+            }
+        }
+
+        // It's up to the caller to clean up temp dirs.
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, path: &Path) -> Result<File, Error> {
+        match Format::identify(path) {
+            Some(_) => File::open(path).map_err(Error::IO),
+            None => Err(Error::NotSupported),
+        }
+    }
+
+    fn stream(&self, archive: File, ctx: &Context<'_>, visit: &mut Visit<'_>) -> Result<(), Error> {
+        let format = Format::identify(ctx.path()).ok_or(Error::NotSupported)?;
+        let archive = BufReader::new(archive);
+
+        match format {
+            Format::Zip => stream_zip(archive, ctx, visit),
+            Format::Tar => stream_tar(archive, ctx, visit),
+            Format::TarGz => stream_tar(MultiGzDecoder::new(archive), ctx, visit),
+            Format::TarXz => stream_tar(XzDecoder::new(archive), ctx, visit),
+            Format::Gz(name) => {
+                visit(Path::new(&name), &mut MultiGzDecoder::new(archive)).map_err(Error::IO)
+            }
+            Format::Xz(name) => {
+                visit(Path::new(&name), &mut XzDecoder::new(archive)).map_err(Error::IO)
+            }
+        }
+    }
+
+    fn list(&self, archive: File, ctx: &Context<'_>) -> Result<Vec<Listing>, Error> {
+        let format = Format::identify(ctx.path()).ok_or(Error::NotSupported)?;
+        let archive = BufReader::new(archive);
+
+        match format {
+            Format::Zip => list_zip(archive, ctx),
+            Format::Tar => list_tar(archive, ctx),
+            Format::TarGz => list_tar(MultiGzDecoder::new(archive), ctx),
+            Format::TarXz => list_tar(XzDecoder::new(archive), ctx),
+            // The size of standalone compressed files isn't known without decompressing them.
+            Format::Gz(name) | Format::Xz(name) => {
+                Ok(vec![Listing::new(name.into(), None, EntryKind::File)])
+            }
+        }
+    }
+}
+
+impl Display for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "native")
+    }
+}
+
+/// The formats supported by this strategy.
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+
+    /// A standalone gzip compressed file, along with the name of the decompressed file.
+    Gz(String),
+
+    /// A standalone xz compressed file, along with the name of the decompressed file.
+    Xz(String),
+}
+
+impl Format {
+    fn identify(path: &Path) -> Option<Self> {
+        let file = path.file_name()?.to_string_lossy();
+        let matches = |exts: &[&str]| exts.iter().any(|ext| file.ends_with(ext));
+
+        // Tarballs are checked before standalone compressed files, since they share the compression extensions.
+        if matches(&ZIP_EXTS) {
+            Some(Self::Zip)
+        } else if matches(&TAR_EXTS) {
+            Some(Self::Tar)
+        } else if matches(&TAR_GZ_EXTS) {
+            Some(Self::TarGz)
+        } else if matches(&TAR_XZ_EXTS) {
+            Some(Self::TarXz)
+        } else if let Some(name) = decompressed_name(&file, GZ_EXT) {
+            Some(Self::Gz(name))
+        } else {
+            decompressed_name(&file, XZ_EXT).map(Self::Xz)
+        }
+    }
+}
+
+/// The name of the file once decompressed, if the file has the extension and a nonempty name without it.
+fn decompressed_name(file: &str, ext: &str) -> Option<String> {
+    file.strip_suffix(ext)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// Unpack the zip archive into `dest`, decoding entry names according to the [`NameEncoding`] in `ctx`.
+///
+/// The zip crate decodes names without the UTF-8 flag as code page 437 regardless of their content,
+/// so entries are unpacked individually from their raw names instead.
+fn unpack_zip(archive: BufReader<File>, dest: &Path, ctx: &Context<'_>) -> Result<(), Error> {
+    let mut archive = ZipArchive::new(archive)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let (path, raw_name) = decode_path(file.name_raw(), ctx.name_encoding());
+
+        // Entries with paths that would escape the archive are skipped.
+        let path = match enclosed(&path) {
+            Some(path) => path,
+            None => continue,
+        };
+        let full = dest.join(&path);
+        if file.is_dir() {
+            fs::create_dir_all(&full)?;
+        } else {
+            if let Some(parent) = full.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut ctx.limit(File::create(&full)?))?;
+            set_zip_permissions(&file, &full)?;
+        }
+
+        if let Some(raw) = raw_name {
+            ctx.record_raw_name(path, raw);
+        }
+    }
+    Ok(())
+}
+
+/// Apply the unix permissions recorded for the zip entry, if any.
+#[cfg(unix)]
+fn set_zip_permissions(file: &ZipFile<'_>, path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = file.unix_mode() {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+/// Unix permissions aren't applied on other platforms.
+#[cfg(not(unix))]
+fn set_zip_permissions(_: &ZipFile<'_>, _: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// The path of the zip entry relative to the root of the archive, decoded according to the [`NameEncoding`] in `ctx`.
+/// Returns `None` if the path would escape the archive.
+fn zip_path(file: &ZipFile<'_>, ctx: &Context<'_>) -> Option<PathBuf> {
+    let (path, _) = decode_path(file.name_raw(), ctx.name_encoding());
+    enclosed(&path)
+}
+
+fn stream_zip(
+    archive: BufReader<File>,
+    ctx: &Context<'_>,
+    visit: &mut Visit<'_>,
+) -> Result<(), Error> {
+    let mut archive = ZipArchive::new(archive)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+
+        // Entries with paths that would escape the archive are skipped.
+        if let Some(path) = zip_path(&file, ctx) {
+            visit(&path, &mut file)?;
+        }
+    }
+    Ok(())
+}
+
+fn stream_tar(archive: impl Read, ctx: &Context<'_>, visit: &mut Visit<'_>) -> Result<(), Error> {
+    let mut archive = Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        // Entries with paths that would escape the archive are skipped.
+        let (path, _) = decode_path(&entry.path_bytes(), ctx.name_encoding());
+        if let Some(path) = enclosed(&path) {
+            visit(&path, &mut entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn list_zip(archive: BufReader<File>, ctx: &Context<'_>) -> Result<Vec<Listing>, Error> {
+    let mut archive = ZipArchive::new(archive)?;
+    let mut listed = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+
+        // Entries with paths that would escape the archive are skipped, as when streaming.
+        let path = match zip_path(&file, ctx) {
+            Some(path) => path,
+            None => continue,
+        };
+        let kind = if file.is_dir() {
+            EntryKind::Directory
+        } else {
+            file.unix_mode()
+                .map(EntryKind::from_mode)
+                .unwrap_or(EntryKind::File)
+        };
+        let size = match kind {
+            EntryKind::File => Some(file.size()),
+            _ => None,
+        };
+        listed.push(Listing::new(path, size, kind));
+    }
+    Ok(listed)
+}
+
+fn list_tar(archive: impl Read, ctx: &Context<'_>) -> Result<Vec<Listing>, Error> {
+    let mut archive = Archive::new(archive);
+    let mut listed = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let kind = match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => EntryKind::File,
+            EntryType::Directory => EntryKind::Directory,
+            EntryType::Symlink => EntryKind::Symlink,
+            EntryType::Link => EntryKind::HardLink,
+            _ => EntryKind::Other,
+        };
+        let size = match kind {
+            EntryKind::File => Some(entry.size()),
+            _ => None,
+        };
+
+        // Entries with paths that would escape the archive are skipped, as when streaming.
+        let (path, _) = decode_path(&entry.path_bytes(), ctx.name_encoding());
+        if let Some(path) = enclosed(&path) {
+            listed.push(Listing::new(path, size, kind));
+        }
+    }
+    Ok(listed)
+}
@@ -0,0 +1,99 @@
+//! Identifies files that look like archives, regardless of whether any strategy supports them.
+//!
+//! This is not a strategy; it is used to report archives that no strategy was able to expand,
+//! so that gaps in format coverage can be measured.
+
+use super::*;
+
+/// Magic bytes identifying archive formats, along with their offset in the file and the name of the format.
+///
+/// Zip containers aren't included: many document formats (docx, xlsx, odt, and so on) are zip containers
+/// that are deliberately walked as plain files, and the zip based archive formats are expanded by the strategies.
+/// The first [`COMPRESSION_FORMATS`] entries are compression formats rather than archive formats.
+const MAGIC: [(usize, &[u8], &str); 16] = [
+    (0, &[0x1f, 0x8b], "gzip"),
+    (0, b"BZh", "bzip2"),
+    (0, &[0xfd, b'7', b'z', b'X', b'Z', 0x00], "xz"),
+    (0, &[0x28, 0xb5, 0x2f, 0xfd], "zstd"),
+    (0, &[0x04, 0x22, 0x4d, 0x18], "lz4"),
+    (0, &[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c], "7z"),
+    (0, b"Rar!\x1a\x07", "rar"),
+    (0, b"!<arch>\n", "ar"),
+    (0, b"MSCF", "cab"),
+    (0, &[0xed, 0xab, 0xee, 0xdb], "rpm"),
+    (0, &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1], "cfb"),
+    (0, b"070701", "cpio"),
+    (0, b"070702", "cpio"),
+    (0, b"070707", "cpio"),
+    (257, b"ustar", "tar"),
+    (0x8001, b"CD001", "iso9660"),
+];
+
+/// The number of compression formats at the start of [`MAGIC`].
+const COMPRESSION_FORMATS: usize = 5;
+
+/// Extensions of archive formats, including formats which are archives under a different extension.
+///
+/// As with [`MAGIC`], zip containers aren't included.
+const EXTENSIONS: [&str; 26] = [
+    ".tar", ".tgz", ".tbz", ".tbz2", ".txz", ".tzst", ".gz", ".bz2", ".xz", ".zst", ".lz4", ".lz",
+    ".lzma", ".br", ".7z", ".rar", ".cab", ".cpio", ".rpm", ".deb", ".gem", ".crate", ".iso",
+    ".msi", ".dmg", ".pkg",
+];
+
+/// The number of bytes read from the start of the file to check for magic bytes.
+///
+/// Magic bytes at larger offsets are read separately, and only for files long enough to contain them.
+const HEADER_LEN: usize = 257 + 5;
+
+/// If the file looks like an archive, the name of its format (by magic bytes) or its extension.
+pub(crate) fn archive_format(path: &Path) -> Option<String> {
+    if let Some(format) = magic_format(path) {
+        return Some(format.to_owned());
+    }
+
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|ext| name.ends_with(*ext))
+        .map(|ext| ext.to_string())
+}
+
+/// The name of the compression format of data starting with `header`, if it's compressed.
+pub(crate) fn compression_format(header: &[u8]) -> Option<&'static str> {
+    MAGIC[..COMPRESSION_FORMATS]
+        .iter()
+        .find(|(_, magic, _)| header.starts_with(magic))
+        .map(|(_, _, format)| *format)
+}
+
+fn magic_format(path: &Path) -> Option<&'static str> {
+    let mut file = File::open(path).ok()?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    (&mut file)
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    for (offset, magic, format) in MAGIC {
+        let found = match header.get(offset..offset + magic.len()) {
+            Some(found) => found == magic,
+            None if header.len() == HEADER_LEN => {
+                read_at(&mut file, offset, magic.len()).is_ok_and(|found| found == magic)
+            }
+            None => false,
+        };
+        if found {
+            return Some(format);
+        }
+    }
+    None
+}
+
+/// Read `len` bytes at `offset` in the file.
+fn read_at(file: &mut File, offset: usize, len: usize) -> Result<Vec<u8>, io::Error> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
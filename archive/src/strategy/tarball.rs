@@ -0,0 +1,155 @@
+//! Unpacks tarballs with explicit handling for hard links and special files.
+//!
+//! This is not a strategy; it is used by the strategies that expand tarballs,
+//! so that these entries are handled the same way regardless of the underlying extraction library or platform.
+
+use tar::{Archive, Entry, EntryType};
+
+use super::names::{decode_path, enclosed};
+use super::*;
+
+/// Unpack the tarball into `dest`.
+///
+/// - Hard links are expanded as copies of the file they reference, so that each expanded file is independent.
+///   Hard links to files that aren't in the archive (or that appear in the archive after the link) are skipped.
+/// - FIFOs and device nodes are skipped: creating them requires privileges on most platforms,
+///   and they have no content to walk.
+/// - Entries larger than the context's `max_entry_bytes` are skipped.
+/// - All other entries are unpacked as-is. Symlinks are subsequently handled according to the [`crate::SymlinkPolicy`].
+///
+/// Entry names that aren't valid UTF-8 are decoded according to the [`NameEncoding`] in `ctx`.
+/// Skipped entries and the raw names of decoded entries are recorded in `ctx`.
+pub(super) fn unpack(archive: impl Read, dest: &Path, ctx: &Context<'_>) -> Result<(), Error> {
+    let canonical_dest = dest.canonicalize()?;
+    let mut archive = Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let (path, raw_name) = decode_path(&entry.path_bytes(), ctx.name_encoding());
+        let kind = match entry.header().entry_type() {
+            EntryType::Link => {
+                if copy_link(&entry, &path, dest, &canonical_dest)? {
+                    continue;
+                }
+                SpecialFile::HardLink
+            }
+            EntryType::Fifo => SpecialFile::Fifo,
+            EntryType::Char => SpecialFile::CharDevice,
+            EntryType::Block => SpecialFile::BlockDevice,
+            _ => {
+                // Tar entries record their (expanded) size up front, so entries over the limit aren't written at all.
+                if ctx.max_entry_bytes().is_some_and(|max| entry.size() > max) {
+                    let path = enclosed(&path).unwrap_or(path);
+                    ctx.skip(Skipped::Oversized(path));
+                    continue;
+                }
+
+                match raw_name {
+                    Some(raw) => {
+                        unpack_decoded(&mut entry, &path, raw, dest, &canonical_dest, ctx)?
+                    }
+                    None => {
+                        entry.unpack_in(dest)?;
+                    }
+                }
+                continue;
+            }
+        };
+
+        let path = enclosed(&path).unwrap_or(path);
+        ctx.skip(Skipped::Special(path, kind));
+    }
+    Ok(())
+}
+
+/// Unpack an entry whose name wasn't valid UTF-8 to its decoded `path` in `dest`.
+///
+/// Entries that would be outside `dest` are not unpacked, as with [`Entry::unpack_in`].
+fn unpack_decoded<R: Read>(
+    entry: &mut Entry<'_, R>,
+    path: &Path,
+    raw: Vec<u8>,
+    dest: &Path,
+    canonical_dest: &Path,
+    ctx: &Context<'_>,
+) -> Result<(), Error> {
+    let relative = match enclosed(path) {
+        Some(relative) => relative,
+        None => return Ok(()),
+    };
+    if let Some(parent) = relative.parent() {
+        if !create_dir_enclosed(parent, dest, canonical_dest)? {
+            return Ok(());
+        }
+    }
+
+    entry.unpack(dest.join(&relative))?;
+    ctx.record_raw_name(relative, raw);
+    Ok(())
+}
+
+/// Copy the file referenced by the hard link to the link's path in `dest`.
+///
+/// Returns `false` if the link or the file it references would be outside `dest`,
+/// or if the file it references isn't a file that has already been unpacked.
+fn copy_link<R: Read>(
+    entry: &Entry<'_, R>,
+    path: &Path,
+    dest: &Path,
+    canonical_dest: &Path,
+) -> Result<bool, Error> {
+    let target = match entry.link_name()? {
+        Some(target) => target,
+        None => return Ok(false),
+    };
+    let (relative, target) = match (enclosed(path), enclosed(&target)) {
+        (Some(path), Some(target)) => (path, dest.join(target)),
+        _ => return Ok(false),
+    };
+
+    // Earlier entries may have created symlinks, so make sure both paths actually resolve inside the destination.
+    let source = match target.canonicalize() {
+        Ok(source) if source.starts_with(canonical_dest) && source.is_file() => source,
+        _ => return Ok(false),
+    };
+    if let Some(parent) = relative.parent() {
+        if !create_dir_enclosed(parent, dest, canonical_dest)? {
+            return Ok(false);
+        }
+    }
+    let path = dest.join(relative);
+
+    // Replace any existing entry at the path; if it were a symlink the copy would be written through it.
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    fs::copy(source, path)?;
+    Ok(true)
+}
+
+/// Create the directory at `relative` inside `dest`, along with any missing parents.
+///
+/// Earlier entries may have created symlinks, so each directory that already exists is checked to resolve
+/// inside the destination before anything is created in it.
+/// Returns `false`, having created nothing outside the destination, if one doesn't.
+pub(super) fn create_dir_enclosed(
+    relative: &Path,
+    dest: &Path,
+    canonical_dest: &Path,
+) -> Result<bool, Error> {
+    let mut dir = dest.to_path_buf();
+    for component in relative.components() {
+        dir.push(component);
+        match dir.canonicalize() {
+            Ok(canonical) if canonical.starts_with(canonical_dest) => {}
+            Ok(_) => return Ok(false),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => match fs::symlink_metadata(&dir) {
+                // Dangling symlinks don't resolve, but can't be created over either.
+                Ok(_) => return Ok(false),
+                Err(_) => fs::create_dir(&dir)?,
+            },
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(true)
+}
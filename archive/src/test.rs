@@ -1,6 +1,8 @@
 //! Unit tests.
 
-use crate::{expand::walk, Target, DEFAULT_ARCHIVE_POSTFIX};
+use std::path::{Path, PathBuf};
+
+use crate::{expand::walk, Options, Recursion, Target, DEFAULT_ARCHIVE_POSTFIX};
 
 #[test]
 fn walk_removes_dirs() {
@@ -72,3 +74,183 @@ fn entries_keep_dirs_alive() {
         assert!(!dir.exists(), "entry {dir:?} should now be cleaned up");
     }
 }
+
+#[cfg(feature = "pure-rust")]
+mod native {
+    use std::{
+        collections::HashMap,
+        env,
+        fs::{self, File},
+        path::{Path, PathBuf},
+    };
+
+    use crate::strategy::{Context, Native, Strategy};
+
+    fn simple_content() -> HashMap<PathBuf, Vec<u8>> {
+        HashMap::from([
+            (
+                PathBuf::from("simple/a.txt"),
+                b"6b5effe3-215a-49ec-9286-f0702f7eb529".to_vec(),
+            ),
+            (
+                PathBuf::from("simple/b.txt"),
+                b"8dea86e4-4365-4711-872b-6f652b02c8d9".to_vec(),
+            ),
+        ])
+    }
+
+    #[track_caller]
+    fn expand(archive: &str) -> HashMap<PathBuf, Vec<u8>> {
+        let path = Path::new(archive);
+        let temp_root = env::temp_dir();
+        let ctx = Context::new(path, &temp_root);
+
+        let strategy = Native::default();
+        let handle = strategy.can_expand(path).expect("must support archive");
+        let dir = strategy.expand(handle, &ctx).expect("must expand archive");
+
+        let content = walkdir::WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(|de| de.ok())
+            .filter(|de| de.file_type().is_file())
+            .map(|de| {
+                let rel = de.path().strip_prefix(&dir).expect("relative").to_owned();
+                (rel, fs::read(de.path()).expect("read file"))
+            })
+            .collect();
+
+        fs::remove_dir_all(dir).expect("remove dir");
+        content
+    }
+
+    #[track_caller]
+    fn stream(archive: &str) -> HashMap<PathBuf, Vec<u8>> {
+        let path = Path::new(archive);
+        let temp_root = env::temp_dir();
+        let ctx = Context::new(path, &temp_root);
+
+        let mut content = HashMap::new();
+        let strategy = Native::default();
+        let handle = File::open(path).expect("open archive");
+        strategy
+            .stream(handle, &ctx, &mut |path, reader| {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+                content.insert(path.to_owned(), buf);
+                Ok(())
+            })
+            .expect("must stream archive");
+        content
+    }
+
+    #[test]
+    fn expand_zip() {
+        assert_eq!(expand("testdata/simplezip/simple.zip"), simple_content());
+    }
+
+    #[test]
+    fn expand_tarballs() {
+        for archive in [
+            "testdata/simple.tar",
+            "testdata/simple.tar.gz",
+            "testdata/simple.tar.xz",
+        ] {
+            assert_eq!(expand(archive), simple_content(), "expand {archive}");
+        }
+    }
+
+    #[test]
+    fn expand_compressed_files() {
+        let content = expand("testdata/compressed/b.txt.xz");
+        assert_eq!(
+            content,
+            HashMap::from([(
+                PathBuf::from("b.txt"),
+                b"8dea86e4-4365-4711-872b-6f652b02c8d9".to_vec()
+            )])
+        );
+
+        let content = expand("testdata/compressed/a.txt.gz");
+        assert_eq!(
+            content,
+            HashMap::from([(
+                PathBuf::from("a.txt"),
+                b"6b5effe3-215a-49ec-9286-f0702f7eb529".to_vec()
+            )])
+        );
+    }
+
+    #[test]
+    fn stream_archives() {
+        for archive in [
+            "testdata/simplezip/simple.zip",
+            "testdata/simple.tar",
+            "testdata/simple.tar.gz",
+            "testdata/simple.tar.xz",
+        ] {
+            assert_eq!(stream(archive), simple_content(), "stream {archive}");
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        let strategy = Native::default();
+        assert!(strategy
+            .can_expand(Path::new("testdata/simple.cpio"))
+            .is_err());
+        assert!(strategy
+            .can_expand(Path::new("testdata/simple.tar.zst"))
+            .is_err());
+    }
+}
+
+#[test]
+fn recursion_depth_for_path() {
+    let options = Options::builder()
+        .recursion(Recursion::Enabled { depth: 10 })
+        .depth_overrides(
+            [
+                (PathBuf::from("vendor"), 20),
+                (PathBuf::from("vendor/fixtures"), 1),
+            ]
+            .into(),
+        )
+        .build();
+
+    // The longest matching prefix wins, and prefixes are compared by component.
+    assert_eq!(options.depth_for(Path::new("src/a.zip")), Some(10));
+    assert_eq!(options.depth_for(Path::new("vendor/a.zip")), Some(20));
+    assert_eq!(
+        options.depth_for(Path::new("vendor/fixtures/a.zip")),
+        Some(1)
+    );
+    assert_eq!(options.depth_for(Path::new("vendors/a.zip")), Some(10));
+
+    // Overrides have no effect when recursion is disabled.
+    let options = Options::builder()
+        .recursion(Recursion::Disabled)
+        .depth_overrides([(PathBuf::from("vendor"), 20)].into())
+        .build();
+    assert_eq!(options.depth_for(Path::new("vendor/a.zip")), None);
+}
+
+#[cfg(windows)]
+#[test]
+fn extended_length_paths() {
+    use crate::path::extended_length;
+
+    let extended = |path: &str| extended_length(PathBuf::from(path));
+    assert_eq!(extended(r"C:\foo\bar"), PathBuf::from(r"\\?\C:\foo\bar"));
+    assert_eq!(
+        extended(r"C:\foo\.\baz\..\bar"),
+        PathBuf::from(r"\\?\C:\foo\bar")
+    );
+    assert_eq!(extended("C:/foo/bar"), PathBuf::from(r"\\?\C:\foo\bar"));
+    assert_eq!(extended(r"C:\"), PathBuf::from(r"\\?\C:\"));
+    assert_eq!(
+        extended(r"\\server\share\foo"),
+        PathBuf::from(r"\\?\UNC\server\share\foo")
+    );
+    assert_eq!(extended(r"\\?\C:\foo"), PathBuf::from(r"\\?\C:\foo"));
+    assert!(extended("relative").to_string_lossy().starts_with(r"\\?\"));
+}
@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use archive::{expand::extract, Error, Options, DEFAULT_ARCHIVE_POSTFIX};
+use assert_matches::assert_matches;
+use sha2::{Digest, Sha256};
+
+use crate::testdata;
+
+/// Hash the contents of each file in the directory, by path relative to the directory.
+#[track_caller]
+fn hashed_content(dir: &Path, files: &[PathBuf]) -> HashMap<PathBuf, String> {
+    files
+        .iter()
+        .map(|file| {
+            let content = fs::read(dir.join(file)).expect("must read extracted file");
+            let hash = hex::encode(&Sha256::digest(content)[..]);
+            (file.to_owned(), hash)
+        })
+        .collect()
+}
+
+#[test]
+fn extract_nested_entries() {
+    let target = testdata::target("testdata/nested");
+    let pattern = format!(
+        "nested.zip{DEFAULT_ARCHIVE_POSTFIX}/simplezip/simple.zip{DEFAULT_ARCHIVE_POSTFIX}/simple/*"
+    );
+    let extraction = extract(target, Options::default(), pattern).expect("must extract");
+
+    let got = hashed_content(extraction.dir(), extraction.files());
+    let expected = HashMap::from([
+        (
+            PathBuf::from("simple/a.txt"),
+            String::from("a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ),
+        (
+            PathBuf::from("simple/b.txt"),
+            String::from("367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8"),
+        ),
+    ]);
+    assert_eq!(got, expected);
+
+    let dir = extraction.dir().to_owned();
+    drop(extraction);
+    assert!(!dir.exists(), "{dir:?} must be removed on drop");
+}
+
+#[test]
+fn extract_only_matching_entries() {
+    let target = testdata::target("testdata/nested");
+    let pattern = format!("nested.zip{DEFAULT_ARCHIVE_POSTFIX}/simplezip/**");
+    let extraction = extract(target, Options::default(), pattern).expect("must extract");
+
+    assert_eq!(extraction.files(), &[PathBuf::from("simplezip/simple.zip")]);
+    assert!(extraction.dir().join("simplezip/simple.zip").is_file());
+    assert!(!extraction.dir().join("inner.zip").exists());
+}
+
+#[test]
+fn extract_from_archive_target() {
+    let target = testdata::target("testdata/simplezip/simple.zip");
+    let extraction = extract(target, Options::default(), "**/b.txt").expect("must extract");
+    assert_eq!(extraction.files(), &[PathBuf::from("simple/b.txt")]);
+}
+
+#[test]
+fn extract_missing_archive() {
+    let target = testdata::target("testdata/nested");
+    let pattern =
+        format!("nested.zip{DEFAULT_ARCHIVE_POSTFIX}/missing.zip{DEFAULT_ARCHIVE_POSTFIX}/**");
+    let result = extract(target, Options::default(), pattern);
+    assert_matches!(result, Err(Error::ArchiveNotFound { path }) => {
+        assert_eq!(path, PathBuf::from(format!("nested.zip{DEFAULT_ARCHIVE_POSTFIX}/missing.zip")));
+    });
+}
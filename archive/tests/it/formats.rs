@@ -0,0 +1,420 @@
+//! Tests for archive formats beyond those supported by `fossa-cli`.
+
+use archive::*;
+
+use crate::testdata::{self, simple_content};
+
+#[test]
+fn extract_simple_cpio_newc() {
+    let target = testdata::target("testdata/simple.cpio");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn extract_simple_cpio_odc() {
+    let target = testdata::target("testdata/simple.odc.cpio");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn extract_initramfs() {
+    let target = testdata::target("testdata/initramfs");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().join("initramfs-6.1.0.img"),
+        simple_content(),
+    );
+}
+
+#[test]
+fn walk_java_archives() {
+    let target = testdata::target("testdata/java");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        (
+            "app.war",
+            "7fb4de50370c714a9db7f51816954f775a82ee3f2dd514820db1e3480882915d",
+        ),
+        (
+            "app.war!_fossa.virtual_!/META-INF/MANIFEST.MF",
+            "566ad1a80220026d05099562645ce968ff0e7c36cde22634332605bb34cc3eff",
+        ),
+        (
+            "app.war!_fossa.virtual_!/WEB-INF/lib/simple.jar",
+            "6fc7a223aae55d8b38bf3e53e3b84e6e47e0148063f747405d1d58e7bf91645e",
+        ),
+        (
+            "app.war!_fossa.virtual_!/WEB-INF/lib/simple.jar!_fossa.virtual_!/META-INF/MANIFEST.MF",
+            "566ad1a80220026d05099562645ce968ff0e7c36cde22634332605bb34cc3eff",
+        ),
+        (
+            "app.war!_fossa.virtual_!/WEB-INF/lib/simple.jar!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "app.war!_fossa.virtual_!/WEB-INF/lib/simple.jar!_fossa.virtual_!/simple/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+        (
+            "simple.jar",
+            "6fc7a223aae55d8b38bf3e53e3b84e6e47e0148063f747405d1d58e7bf91645e",
+        ),
+        (
+            "simple.jar!_fossa.virtual_!/META-INF/MANIFEST.MF",
+            "566ad1a80220026d05099562645ce968ff0e7c36cde22634332605bb34cc3eff",
+        ),
+        (
+            "simple.jar!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "simple.jar!_fossa.virtual_!/simple/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_python_distributions() {
+    let target = testdata::target("testdata/python");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        (
+            "simple-1.0-py3-none-any.whl",
+            "d6c05b7cfd0ee909a7d63674bad366ea817f4a765585583bdc5d46e5678e8000",
+        ),
+        (
+            "simple-1.0-py3-none-any.whl!_fossa.virtual_!/simple-1.0.dist-info/METADATA",
+            "cc9032d22960acee2891d7ab4b95b0f9d324bbc05b149bb79d218540a950c2bd",
+        ),
+        (
+            "simple-1.0-py3-none-any.whl!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "simple-1.0-py3-none-any.whl!_fossa.virtual_!/simple/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+        (
+            "simple-1.0-py3.9.egg",
+            "56bf4b8a8591babb9d9f67124420ad18c853401a23270e7b7d6e4802d2c4adf4",
+        ),
+        (
+            "simple-1.0-py3.9.egg!_fossa.virtual_!/EGG-INFO/PKG-INFO",
+            "cc9032d22960acee2891d7ab4b95b0f9d324bbc05b149bb79d218540a950c2bd",
+        ),
+        (
+            "simple-1.0-py3.9.egg!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "simple-1.0-py3.9.egg!_fossa.virtual_!/simple/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_ruby_gem() {
+    let target = testdata::target("testdata/ruby");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        (
+            "simple-1.0.0.gem",
+            "f2929a99a5f1535f790e20e23e141eefa313faaea5ae11dd251c0da6dc525561",
+        ),
+        (
+            "simple-1.0.0.gem!_fossa.virtual_!/metadata",
+            "18180d8433e3806b63ac5cf7c218a25e46a415cd9c6e68f860d333b8aa4fa54d",
+        ),
+        (
+            "simple-1.0.0.gem!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "simple-1.0.0.gem!_fossa.virtual_!/simple/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn extract_rust_crate() {
+    let target = testdata::target("testdata/simple-0.1.0.crate");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn walk_nested_nuget_package() {
+    let target = testdata::target("testdata/nuget");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        ("vendor.zip", "39e377022aa097a415a0911ec47bdef8c89d15197927b336479c97388efe8876"),
+        ("vendor.zip!_fossa.virtual_!/packages/simple.1.0.0.nupkg", "ce2ae5afb651e64d750cb9691ddeadee2ebc2d148c272c227326902246e5314b"),
+        ("vendor.zip!_fossa.virtual_!/packages/simple.1.0.0.nupkg!_fossa.virtual_!/Simple.nuspec", "7e58426744cf159f239db9f8f26d06710e0ae9c3e17bad0b1e020fb7a93b9d2f"),
+        ("vendor.zip!_fossa.virtual_!/packages/simple.1.0.0.nupkg!_fossa.virtual_!/simple/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ("vendor.zip!_fossa.virtual_!/packages/simple.1.0.0.nupkg!_fossa.virtual_!/simple/b.txt", "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_android_packages() {
+    let target = testdata::target("testdata/android");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        ("simple.xapk", "63540165f7f9ddbd0805b5888cf5dacbbfbc13036192baf1b0faeff800b14504"),
+        ("simple.xapk!_fossa.virtual_!/manifest.json", "679c0044a22b5051233b4b94a16ccde7d776533cb09edf8becf45f3a3221c116"),
+        ("simple.xapk!_fossa.virtual_!/com.example.simple.apk", "ec5a763c8f52fa0fa4e9f8dbc65830f4370b518dd4e8d2e4e684939ad29eb777"),
+        ("simple.xapk!_fossa.virtual_!/com.example.simple.apk!_fossa.virtual_!/AndroidManifest.xml", "9003074776db79f368110336663605b84cdba9a863a73a71ebc35df7bd7e4fdf"),
+        ("simple.xapk!_fossa.virtual_!/com.example.simple.apk!_fossa.virtual_!/classes.dex", "6bb3045f495783f8d1e47ecfd6c160935a7083ee00a232fceba1294a91b09f9b"),
+        ("simple.xapk!_fossa.virtual_!/com.example.simple.apk!_fossa.virtual_!/lib/arm64-v8a/libsimple.so", "27788eb4db15805492d6dc070216547f1a669c571b829f6e76312ce02f235832"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_docker_image() {
+    let target = testdata::target("testdata/container/docker");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        ("image.tar", "b1c58c2381be88180cff3fbc51dd27cb75b540fff962bf1356c0bf04e9500ac8"),
+        ("image.tar!_fossa.virtual_!/layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ("image.tar!_fossa.virtual_!/layers/c0614ade927db4c3f8d705ca5a78022d02558bf3cb5cd86ff3b4e9e5b9e86915/other/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_docker_image_ignore_whiteouts() {
+    let target = testdata::target("testdata/container/docker");
+    let walker = expand::walk(
+        target,
+        Options::builder().whiteout(Whiteout::Ignore).build(),
+    );
+
+    let expected = vec![
+        ("image.tar", "b1c58c2381be88180cff3fbc51dd27cb75b540fff962bf1356c0bf04e9500ac8"),
+        ("image.tar!_fossa.virtual_!/layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ("image.tar!_fossa.virtual_!/layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/b.txt", "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8"),
+        ("image.tar!_fossa.virtual_!/layers/c0614ade927db4c3f8d705ca5a78022d02558bf3cb5cd86ff3b4e9e5b9e86915/simple/.wh.b.txt", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        ("image.tar!_fossa.virtual_!/layers/c0614ade927db4c3f8d705ca5a78022d02558bf3cb5cd86ff3b4e9e5b9e86915/other/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_oci_image() {
+    let target = testdata::target("testdata/container/oci");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        ("image.tar", "c0881b374d33535eb1203404c00b99b3cfc178d78880c22d3eab9803383f50b8"),
+        ("image.tar!_fossa.virtual_!/layers/e53f5fe142c5b408880aced0acd82b93c3d8ae4eaa9633454c6b3e05f5aa1633/simple/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ("image.tar!_fossa.virtual_!/layers/73cd791ee5d4dd32bc4ad8a4287df94a27ccc013fd7b5005267a2bdb8c3ef2f3/other/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_docker_image_shared_layers() {
+    // The second image links its only layer to the lowest layer of the first image, without the whiteout above it.
+    let target = testdata::target("testdata/container/shared");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        ("image.tar", "89470d08d1058c18e3b75fb626cc50ec6a04926798eb3ab3376951d650d54351"),
+        ("image.tar!_fossa.virtual_!/layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ("image.tar!_fossa.virtual_!/layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/b.txt", "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8"),
+        ("image.tar!_fossa.virtual_!/layers/c0614ade927db4c3f8d705ca5a78022d02558bf3cb5cd86ff3b4e9e5b9e86915/other/a.txt", "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_docker_image_oversized_layer_entries() {
+    let target = testdata::target("testdata/container/docker");
+    let options = Options::builder().max_entry_bytes(10).build();
+    let entries =
+        Iterator::collect::<Result<Vec<_>, _>>(expand::walk(target, options)).expect("must walk");
+    assert_eq!(
+        entries.len(),
+        1,
+        "oversized files must not be walked: {entries:?}"
+    );
+
+    let mut oversized = entries[0]
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Error::EntryTooLarge { path, limit: 10 } => path.to_owned(),
+            other => panic!("unexpected warning: {other:?}"),
+        })
+        .collect::<Vec<_>>();
+    oversized.sort();
+    assert_eq!(
+        oversized,
+        vec![
+            std::path::Path::new("layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/a.txt"),
+            std::path::Path::new("layers/4f481c645a6fa68662ebbc9e011b839bb4fb5543731cd2def223b7d5a799718d/simple/b.txt"),
+            std::path::Path::new("layers/c0614ade927db4c3f8d705ca5a78022d02558bf3cb5cd86ff3b4e9e5b9e86915/other/a.txt"),
+        ]
+    );
+}
+
+#[test]
+fn walk_docker_image_layer_outside_image() {
+    let target = testdata::target("testdata/container/escape");
+    let walker = expand::walk(target, Default::default());
+    let entries = Iterator::collect::<Result<Vec<_>, _>>(walker).expect("must walk");
+    assert_eq!(entries.len(), 1, "image must not be expanded: {entries:?}");
+
+    let warnings = entries[0].warnings();
+    assert!(
+        matches!(
+            warnings,
+            [Error::Expand(strategy::Error::ContainerPath(path))] if path == "../../layer.tar"
+        ),
+        "unexpected warnings: {warnings:?}"
+    );
+}
+
+#[test]
+fn extract_simple_iso() {
+    let target = testdata::target("testdata/simple.iso");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn extract_simple_cab() {
+    let target = testdata::target("testdata/simple.cab");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn extract_simple_tar_zst() {
+    let target = testdata::target("testdata/simple.tar.zst");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn walk_compressed_files() {
+    let target = testdata::target("testdata/compressed");
+    let walker = expand::walk(target, Default::default());
+
+    let expected = vec![
+        (
+            "a.txt.bz2",
+            "844a3a019c8811ad7235fd0fa2d3961b9add0c5a4aa13d00fffc6aa9d82db412",
+        ),
+        (
+            "a.txt.bz2!_fossa.virtual_!/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "a.txt.gz",
+            "f15f1e8c9116de7444545e26645f900180579a2699ed6d9e0b404de60770304b",
+        ),
+        (
+            "a.txt.gz!_fossa.virtual_!/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "a.txt.zst",
+            "bb404ee6e5724d829c8e071e9b6ed2372d45632442e602416d73694302041177",
+        ),
+        (
+            "a.txt.zst!_fossa.virtual_!/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "b.txt.br",
+            "f33f26fbf66e82d465e063075e824f3f947b900cd5b008900207f18e92868a97",
+        ),
+        (
+            "b.txt.br!_fossa.virtual_!/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+        (
+            "b.txt.xz",
+            "9f31a55fa24956cb1fd1cf8b0563999b278be2335656bf72badca8b7bb4707bd",
+        ),
+        (
+            "b.txt.xz!_fossa.virtual_!/b.txt",
+            "367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8",
+        ),
+    ];
+
+    testdata::assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn extract_simple_tar_lz4() {
+    let target = testdata::target("testdata/simple.tar.lz4");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn extract_simple_tar_br() {
+    let target = testdata::target("testdata/simple.tar.br");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
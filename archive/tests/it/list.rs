@@ -0,0 +1,67 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use archive::{list, EntryKind, Error, Options};
+use assert_matches::assert_matches;
+
+use crate::testdata;
+
+/// List the archive, mapping each path to its kind and size.
+#[track_caller]
+fn listed(archive: &str) -> HashMap<PathBuf, (EntryKind, Option<u64>)> {
+    let target = testdata::target(archive);
+    list(target, Options::default())
+        .expect("must list")
+        .into_iter()
+        .map(|listing| {
+            let path = listing.path().to_owned();
+            (path, (listing.kind(), listing.size()))
+        })
+        .collect()
+}
+
+#[test]
+fn list_simple_zip() {
+    let got = listed("testdata/simplezip/simple.zip");
+    let expected = HashMap::from([
+        (PathBuf::from("simple"), (EntryKind::Directory, None)),
+        (PathBuf::from("simple/a.txt"), (EntryKind::File, Some(36))),
+        (PathBuf::from("simple/b.txt"), (EntryKind::File, Some(36))),
+    ]);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn list_simple_tarball() {
+    let got = listed("testdata/simple.tar.gz");
+    let expected = HashMap::from([
+        (PathBuf::from("simple/a.txt"), (EntryKind::File, Some(36))),
+        (PathBuf::from("simple/b.txt"), (EntryKind::File, Some(36))),
+    ]);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn list_special_files() {
+    let got = listed("testdata/special/special.tar");
+    assert_eq!(got[Path::new("simple/a.txt")], (EntryKind::File, Some(34)));
+    for special in ["simple/fifo", "simple/null", "simple/sda"] {
+        assert_eq!(
+            got[Path::new(special)],
+            (EntryKind::Other, None),
+            "{special}"
+        );
+    }
+}
+
+#[test]
+fn list_directory_unsupported() {
+    let target = testdata::target("testdata/simplezip");
+    let result = list(target, Options::default());
+    assert_matches!(
+        result,
+        Err(Error::Expand(archive::strategy::Error::NotSupported))
+    );
+}
@@ -83,3 +83,134 @@ fn filters_unsupported() {
     let result = expand::all(target, opts);
     assert_matches!(result, Err(Error::Invariant(Invariant::FiltersUnsupported)));
 }
+
+#[test]
+fn extraction_root() {
+    pretty_env_logger::init();
+
+    let root = tempfile::tempdir().expect("create extraction root");
+    let target = testdata::target("testdata/simplezip");
+    let opts = Options::builder().extraction_root(root.path()).build();
+
+    let result = expand::all(target.clone(), opts).unwrap();
+    let destination = result
+        .locations()
+        .get_by_left(&Source::from(target.root().join("simple.zip")))
+        .unwrap()
+        .inner()
+        .clone();
+
+    assert!(
+        destination.starts_with(root.path()),
+        "{destination:?} must be inside {:?}",
+        root.path()
+    );
+}
+
+#[test]
+fn extraction_root_missing() {
+    pretty_env_logger::init();
+
+    let root = tempfile::tempdir().expect("create extraction root");
+    let missing = root.path().join("missing");
+    let target = testdata::target("testdata/simplezip");
+    let opts = Options::builder().extraction_root(missing).build();
+
+    let result = expand::all(target, opts);
+    assert_matches!(
+        result,
+        Err(Error::Invariant(Invariant::ExtractionRoot { .. }))
+    );
+}
+
+#[test]
+fn extraction_root_free_space() {
+    pretty_env_logger::init();
+
+    let target = testdata::target("testdata/simplezip");
+    let opts = Options::builder().min_free_space(u64::MAX).build();
+
+    let result = expand::all(target.clone(), opts);
+    assert_matches!(
+        result,
+        Err(Error::Invariant(Invariant::ExtractionRootFull { .. }))
+    );
+
+    // `FallibleIterator` is also in scope from the glob import, so collect with `Iterator` explicitly.
+    let opts = Options::builder().min_free_space(u64::MAX).build();
+    let walked = Iterator::collect::<Vec<_>>(expand::walk(target, opts));
+    assert_matches!(
+        walked.as_slice(),
+        [Err(Error::Invariant(Invariant::ExtractionRootFull { .. }))]
+    );
+}
+
+#[test]
+fn expand_cancelled() {
+    pretty_env_logger::init();
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let target = testdata::target("testdata/nested");
+    let opts = Options::builder().cancel(cancel).build();
+
+    let result = expand::all(target, opts);
+    assert_matches!(result, Err(Error::Cancelled));
+}
+
+#[test]
+fn expand_fails_fast() {
+    pretty_env_logger::init();
+
+    let target = testdata::target("testdata/links");
+    let opts = Options::builder()
+        .symlink_policy(SymlinkPolicy::Error)
+        .on_error(ErrorPolicy::FailFast)
+        .build();
+
+    let result = expand::all(target.clone(), opts);
+    assert_matches!(result, Err(Error::ExpandArchive { path, .. }) => {
+        assert_eq!(path, target.root().join("links.tar"));
+    });
+}
+
+#[test]
+fn expand_filtered() {
+    pretty_env_logger::init();
+
+    let target = testdata::target("testdata/duplicates");
+    let include = |path: &std::path::Path| !path.starts_with("two");
+    let result = expand::all_filtered(target.clone(), Options::default(), include).unwrap();
+
+    let sources = result
+        .locations()
+        .left_values()
+        .cloned()
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        sources,
+        HashSet::from([Source::from(target.root().join("one").join("simple.zip"))])
+    );
+}
+
+#[test]
+fn expand_filtered_inside_archive() {
+    pretty_env_logger::init();
+
+    let target = testdata::target("testdata/nested");
+    let include = |path: &std::path::Path| {
+        !path.starts_with(format!("nested.zip{DEFAULT_ARCHIVE_POSTFIX}/simplezip"))
+    };
+    let result = expand::all_filtered(target, Options::default(), include).unwrap();
+
+    let expanded = result
+        .locations()
+        .left_values()
+        .filter_map(|source| source.inner().file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect::<HashSet<_>>();
+    assert!(expanded.contains("nested.zip"), "{expanded:?}");
+    assert!(expanded.contains("inner.zip"), "{expanded:?}");
+    assert!(!expanded.contains("simple.zip"), "{expanded:?}");
+}
@@ -13,7 +13,13 @@
 //!
 //! Tests in this module set up env_logger; use `RUST_LOG=debug` to see its output.
 
+mod extract;
+mod formats;
+mod list;
 mod local;
 mod ported_cli;
+mod strategy;
+mod stream;
 mod testdata;
 mod walk;
+mod zip64;
@@ -2,7 +2,7 @@
 
 use archive::*;
 
-use crate::testdata;
+use crate::testdata::{self, simple_content};
 
 /// https://github.com/fossas/fossa-cli/blob/219bdc6f38d401df2bdb7991114c54083a75f56b/test/Discovery/ArchiveSpec.hs#L30-L45
 #[test]
@@ -88,13 +88,6 @@ fn extract_fc35_rpm() {
     );
 }
 
-fn simple_content<'a>() -> Vec<(&'a str, &'a [u8])> {
-    vec![
-        ("simple/a.txt", b"6b5effe3-215a-49ec-9286-f0702f7eb529"),
-        ("simple/b.txt", b"8dea86e4-4365-4711-872b-6f652b02c8d9"),
-    ]
-}
-
 fn el7_content<'a>() -> Vec<(&'a str, &'a str)> {
     vec![
         (
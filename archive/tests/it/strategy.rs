@@ -0,0 +1,244 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use archive::{
+    expand::{all, walk},
+    strategy::{Context, Error, Strategy},
+    Options,
+};
+
+use crate::testdata::{self, assert_walked_hashed_content};
+
+/// The magic bytes at the start of the made up firmware image format.
+const FIRMWARE_MAGIC: &[u8] = b"FWIMG\x00\x01\x02";
+
+/// A made up firmware image format: the magic bytes followed by the payload.
+struct Firmware;
+
+impl Strategy for Firmware {
+    fn expand(&self, mut archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let dir = ctx.tempdir()?;
+        archive.seek(SeekFrom::Start(FIRMWARE_MAGIC.len() as u64))?;
+        let mut payload = File::create(dir.path().join("payload"))?;
+        io::copy(&mut archive, &mut payload)?;
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, archive: &Path) -> Result<File, Error> {
+        if archive.extension().map(|ext| ext != "fw").unwrap_or(true) {
+            return Err(Error::NotSupported);
+        }
+
+        let mut handle = File::open(archive)?;
+        let mut magic = [0; FIRMWARE_MAGIC.len()];
+        handle.read_exact(&mut magic)?;
+        if magic == FIRMWARE_MAGIC {
+            Ok(handle)
+        } else {
+            Err(Error::NotSupported)
+        }
+    }
+}
+
+impl Display for Firmware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "firmware")
+    }
+}
+
+/// Expands like [`Firmware`], but estimates that firmware images are too large to expand.
+struct HugeFirmware;
+
+impl Strategy for HugeFirmware {
+    fn expand(&self, archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        Firmware.expand(archive, ctx)
+    }
+
+    fn can_expand(&self, archive: &Path) -> Result<File, Error> {
+        Firmware.can_expand(archive)
+    }
+
+    fn estimate_size(&self, _: &Path) -> Option<u64> {
+        Some(u64::MAX)
+    }
+}
+
+impl Display for HugeFirmware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "huge firmware")
+    }
+}
+
+/// A made up format which expands into a copy of itself, like a quine.
+struct Mirror;
+
+impl Strategy for Mirror {
+    fn expand(&self, mut archive: File, ctx: &Context<'_>) -> Result<PathBuf, Error> {
+        let dir = ctx.tempdir()?;
+        let name = ctx.path().file_name().ok_or(Error::NotSupported)?;
+        let mut copy = File::create(dir.path().join(name))?;
+        io::copy(&mut archive, &mut copy)?;
+        Ok(dir.into_path())
+    }
+
+    fn can_expand(&self, archive: &Path) -> Result<File, Error> {
+        match archive.extension() {
+            Some(ext) if ext == "mirror" => File::open(archive).map_err(Error::IO),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for Mirror {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mirror")
+    }
+}
+
+/// Rejects every zip file with a custom error.
+struct RejectZip;
+
+impl Strategy for RejectZip {
+    fn expand(&self, _: File, _: &Context<'_>) -> Result<PathBuf, Error> {
+        Err(Error::NotSupported)
+    }
+
+    fn can_expand(&self, archive: &Path) -> Result<File, Error> {
+        match archive.extension() {
+            Some(ext) if ext == "zip" => Err(Error::Custom("zip is rejected".into())),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl Display for RejectZip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reject zip")
+    }
+}
+
+#[test]
+fn walk_custom_strategy() {
+    let target = testdata::target("testdata/custom");
+    let options = Options::default().with_strategy(Firmware);
+    let walker = walk(target, options);
+
+    let expected = vec![
+        (
+            "image.fw",
+            "71ef16a4128d0c46cc2f3234bad76206bcda2530cc9c87120f2aa3c39f36b8f7",
+        ),
+        (
+            "image.fw!_fossa.virtual_!/payload",
+            "c956d4d88813503c763780c9840a34e7df376e3a70699bc90827ec8fb090902d",
+        ),
+    ];
+    assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn custom_strategy_takes_precedence() {
+    let target = testdata::target("testdata/simplezip");
+    let options = Options::default().with_strategy(RejectZip);
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_eq!(entries.len(), 1, "zip must not be expanded: {entries:?}");
+
+    let warnings = entries[0].warnings();
+    assert!(
+        matches!(warnings, [archive::Error::Expand(Error::Custom(_))]),
+        "unexpected warnings: {warnings:?}"
+    );
+}
+
+#[test]
+fn estimate_size_default() {
+    let estimate = |path: &str| Firmware.estimate_size(Path::new(path));
+
+    // Zip archives are estimated from the central directory, gzip compressed files from the trailer.
+    assert_eq!(estimate("testdata/simplezip/simple.zip"), Some(72));
+    assert_eq!(estimate("testdata/zip64/simple.zip"), Some(72));
+    assert_eq!(estimate("testdata/simple.tar.gz"), Some(3072));
+    assert_eq!(estimate("testdata/compressed/a.txt.gz"), Some(36));
+
+    // Other formats aren't estimated.
+    assert_eq!(estimate("testdata/simple.tar"), None);
+    assert_eq!(estimate("testdata/custom/image.fw"), None);
+}
+
+#[test]
+fn insufficient_space() {
+    let target = testdata::target("testdata/custom");
+    let options = Options::default().with_strategy(HugeFirmware);
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_eq!(entries.len(), 1, "image must not be expanded: {entries:?}");
+
+    let warnings = entries[0].warnings();
+    assert!(
+        matches!(
+            warnings,
+            [archive::Error::Expand(Error::InsufficientSpace { required, .. })] if *required == u64::MAX
+        ),
+        "unexpected warnings: {warnings:?}"
+    );
+}
+
+#[test]
+fn walk_self_referential() {
+    let target = testdata::target("testdata/quine");
+    let options = Options::default().with_strategy(Mirror);
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let paths = entries
+        .iter()
+        .map(|entry| entry.path().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("self.mirror"),
+            PathBuf::from("self.mirror!_fossa.virtual_!/self.mirror"),
+        ]
+    );
+
+    // The copy inside the archive is identical to its ancestor, so it isn't expanded.
+    assert!(entries[0].warnings().is_empty());
+    let warnings = entries[1].warnings();
+    assert!(
+        matches!(warnings, [archive::Error::Expand(Error::SelfReferential)]),
+        "unexpected warnings: {warnings:?}"
+    );
+}
+
+#[test]
+fn expand_all_self_referential() {
+    let target = testdata::target("testdata/quine");
+    let options = Options::default().with_strategy(Mirror);
+
+    let expansion = all(target, options).expect("must expand");
+    assert_eq!(expansion.locations().len(), 1);
+
+    let warnings = expansion
+        .warnings()
+        .values()
+        .map(Vec::as_slice)
+        .collect::<Vec<_>>();
+    assert!(
+        matches!(
+            warnings.as_slice(),
+            [[archive::Error::Expand(Error::SelfReferential)]]
+        ),
+        "unexpected warnings: {warnings:?}"
+    );
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use archive::{expand::stream, Error, Options};
+use assert_matches::assert_matches;
+use sha2::{Digest, Sha256};
+
+/// Hash the contents of each file streamed from the archive.
+#[track_caller]
+fn streamed_hashed_content(archive: &Path) -> HashMap<PathBuf, String> {
+    let mut hashed = HashMap::new();
+    stream(archive, Options::default(), |path, reader| {
+        let mut hasher = Sha256::new();
+        io::copy(reader, &mut hasher)?;
+        hashed.insert(path.to_owned(), hex::encode(&hasher.finalize()[..]));
+        Ok(())
+    })
+    .expect("must have streamed");
+    hashed
+}
+
+fn simple_hashed_content() -> HashMap<PathBuf, String> {
+    HashMap::from([
+        (
+            PathBuf::from("simple/a.txt"),
+            String::from("a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        ),
+        (
+            PathBuf::from("simple/b.txt"),
+            String::from("367a5b6e6b67fa0c2d00dee7c91eb3f0d85a93e537335abbed7908c9f87738c8"),
+        ),
+    ])
+}
+
+#[test]
+fn stream_simple_zip() {
+    let got = streamed_hashed_content(Path::new("testdata/simplezip/simple.zip"));
+    assert_eq!(got, simple_hashed_content());
+}
+
+#[test]
+fn stream_simple_tarball() {
+    let got = streamed_hashed_content(Path::new("testdata/simple.tar.gz"));
+    assert_eq!(got, simple_hashed_content());
+}
+
+#[test]
+fn stream_skips_entries_outside_archive() {
+    let got = streamed_hashed_content(Path::new("testdata/escape/parent.tar"));
+    assert_eq!(
+        got,
+        HashMap::from([(
+            PathBuf::from("simple/a.txt"),
+            String::from("a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce"),
+        )])
+    );
+}
+
+#[test]
+fn stream_ruby_gem() {
+    // Gems are not streamed directly, but are expanded and then streamed from disk.
+    let got = streamed_hashed_content(Path::new("testdata/ruby/simple-1.0.0.gem"));
+
+    let mut expected = simple_hashed_content();
+    expected.insert(
+        PathBuf::from("metadata"),
+        String::from("18180d8433e3806b63ac5cf7c218a25e46a415cd9c6e68f860d333b8aa4fa54d"),
+    );
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn stream_skips_unread_content() {
+    let mut paths = Vec::new();
+    stream(
+        Path::new("testdata/simple.tar"),
+        Options::default(),
+        |path, _| {
+            paths.push(path.to_owned());
+            Ok(())
+        },
+    )
+    .expect("must have streamed");
+
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![PathBuf::from("simple/a.txt"), PathBuf::from("simple/b.txt")]
+    );
+}
+
+#[test]
+fn stream_stops_on_visit_error() {
+    let mut visited = 0;
+    let result = stream(
+        Path::new("testdata/simple.tar"),
+        Options::default(),
+        |_, _| {
+            visited += 1;
+            Err(io::Error::other("stop"))
+        },
+    );
+
+    assert_matches!(result, Err(Error::Expand(_)));
+    assert_eq!(visited, 1);
+}
+
+#[test]
+fn stream_unsupported() {
+    let result = stream(
+        Path::new("testdata/simplezip"),
+        Options::default(),
+        |_, _| Ok(()),
+    );
+    assert_matches!(result, Err(Error::Expand(_)));
+}
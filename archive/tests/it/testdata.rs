@@ -55,6 +55,14 @@ pub fn matches_hashed_content(
     assert_hashed_content(&destination, expected);
 }
 
+/// The content of the `simple` directory archived in the various `testdata/simple.*` archives.
+pub fn simple_content<'a>() -> Vec<(&'a str, &'a [u8])> {
+    vec![
+        ("simple/a.txt", b"6b5effe3-215a-49ec-9286-f0702f7eb529"),
+        ("simple/b.txt", b"8dea86e4-4365-4711-872b-6f652b02c8d9"),
+    ]
+}
+
 /// Get the target for a given path inside `testdata`.
 #[track_caller]
 pub fn target(path: impl Into<PathBuf>) -> Target {
@@ -69,9 +77,19 @@ pub fn assert_walked_hashed_content(
     iter: impl Iterator<Item = Result<Entry, Error>>,
     expected: Vec<(&str, &str)>,
 ) {
+    let extracted = walked_hashed_content(iter);
+    let expected = map_expected_flat(expected);
+
+    assert_eq!(extracted, expected);
+}
+
+/// Hash the contents of each entry output by the walking iterator.
+pub fn walked_hashed_content(
+    iter: impl Iterator<Item = Result<Entry, Error>>,
+) -> HashMap<PathBuf, String> {
     let contents = iter
         .filter_map(|entry| entry.ok())
-        .filter_map(|mut entry| match entry.open() {
+        .filter_map(|mut entry| match entry.reader() {
             Ok(handle) => Some((entry.path().to_owned(), handle)),
             Err(_) => None,
         })
@@ -82,10 +100,7 @@ pub fn assert_walked_hashed_content(
             (path, hex::encode(buf))
         });
 
-    let extracted = HashMap::from_iter(contents);
-    let expected = map_expected_flat(expected);
-
-    assert_eq!(extracted, expected);
+    HashMap::from_iter(contents)
 }
 
 /// Assert the contents of the archive matched the provided tree.
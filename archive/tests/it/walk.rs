@@ -1,8 +1,19 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use archive::{expand::walk, Filter, Options, Recursion};
+use archive::{
+    expand::{walk, walk_async, walk_parallel},
+    CancelToken, Error, ErrorPolicy, ExpandEvent, Filter, Metrics, NameEncoding, OnEvent, Options,
+    Persisted, Recursion, Source, SpecialFile, SymlinkPolicy, Target,
+};
+use assert_matches::assert_matches;
+use tokio_stream::StreamExt;
 
-use crate::testdata::{self, assert_walked_hashed_content};
+use crate::testdata::{self, assert_walked_hashed_content, walked_hashed_content};
 
 #[test]
 fn walks_archive() {
@@ -100,6 +111,10 @@ fn extract_nested_archives() {
             "nested.zip/inner.zip/curl-7.78.0-3.fc35.x86_64.rpm/usr/share/man/man1/curl.1.gz",
             "e3ab38e59cda834a11cee0ae4659dc6d609d8ed1f3e3c80dcd0d28cb56908d4c",
         ),
+        (
+            "nested.zip/inner.zip/curl-7.78.0-3.fc35.x86_64.rpm/usr/share/man/man1/curl.1.gz/curl.1",
+            "8e657be9798f6a76531443622dd35225f8d4450ee91ac39ebd69aa8d814a1620",
+        ),
         (
             "nested.zip/inner.zip/curl-7.78.0-3.fc35.x86_64.rpm/usr/share/doc/curl/BUGS.md",
             "c5fc32214134097232490fa9e0d3cd1f299b04f5e550c2bfc8ff081ff29f0836",
@@ -292,3 +307,778 @@ fn filters_allow_deny() {
 
     assert_walked_hashed_content(walker, expected);
 }
+
+#[test]
+fn walk_parallel_matches_walk() {
+    let options = vec![
+        Options::default(),
+        Options::builder()
+            .recursion(Recursion::Enabled { depth: 1 })
+            .build(),
+        Options::builder().recursion(Recursion::Disabled).build(),
+        Options::builder()
+            .filter(
+                Filter::builder()
+                    .exclude(HashSet::from([PathBuf::from(
+                        "nested.zip!_fossa.virtual_!/inner.zip",
+                    )]))
+                    .build(),
+            )
+            .build(),
+    ];
+
+    for options in options {
+        let serial = walk(testdata::target("testdata/nested"), options.clone());
+        let parallel = walk_parallel(testdata::target("testdata/nested"), options.clone());
+        assert_eq!(
+            walked_hashed_content(serial),
+            walked_hashed_content(parallel),
+            "options: {options:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn walk_async_matches_walk() {
+    let serial = walk(testdata::target("testdata/nested"), Default::default());
+    let stream = walk_async(testdata::target("testdata/nested"), Default::default())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(
+        walked_hashed_content(serial),
+        walked_hashed_content(stream.into_iter())
+    );
+}
+
+#[test]
+fn walk_reports_events() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let options = Options::builder()
+        .on_event(OnEvent::new(move |event| {
+            recorded.lock().expect("lock events").push(event)
+        }))
+        .build();
+
+    let target = testdata::target("testdata/simplezip");
+    let archive = target.root().join("simple.zip");
+    let entries = walk(target, options).count();
+
+    let events = events.lock().expect("lock events");
+    let walked = events
+        .iter()
+        .filter(|event| matches!(event, ExpandEvent::EntryWalked { .. }))
+        .count();
+    assert_eq!(walked, entries);
+    assert!(events.contains(&ExpandEvent::ArchiveDiscovered {
+        path: archive.clone()
+    }));
+    assert!(events.contains(&ExpandEvent::ArchiveExpanded {
+        path: archive,
+        bytes: 72,
+    }));
+}
+
+#[test]
+fn walk_reports_metadata() {
+    let target = testdata::target("testdata/simplezip");
+    let walker = walk(target, Default::default());
+
+    let got = walker
+        .map(|e| e.map(|e| (e.path().to_owned(), *e.metadata())))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must have expanded");
+    assert_eq!(got.len(), 3);
+
+    for (path, metadata) in got {
+        assert!(metadata.file_type().is_file(), "{path:?} must be a file");
+        assert!(metadata.modified().is_some(), "{path:?} must have mtime");
+        if path == Path::new("simple.zip") {
+            assert!(!metadata.archived(), "{path:?} must not be archived");
+            assert_eq!(metadata.size(), 540);
+        } else {
+            assert!(metadata.archived(), "{path:?} must be archived");
+            assert_eq!(metadata.size(), 36);
+        }
+
+        #[cfg(unix)]
+        assert!(metadata.mode().is_some(), "{path:?} must have mode");
+    }
+}
+
+#[test]
+fn walk_resolves_symlinks_within_archive() {
+    let target = testdata::target("testdata/links");
+    let walker = walk(target, Default::default());
+
+    let expected = vec![
+        (
+            "links.tar",
+            "c3ecf7ccd67e15d955058215bb325ed5d482f6478f7e4cae6fdfa3f29fac4bd9",
+        ),
+        (
+            "links.tar!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "links.tar!_fossa.virtual_!/simple/rel",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+        (
+            "links.tar!_fossa.virtual_!/simple/abs",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+    ];
+
+    assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_skips_symlinks() {
+    let target = testdata::target("testdata/links");
+    let options = Options::builder()
+        .symlink_policy(SymlinkPolicy::Skip)
+        .build();
+    let walker = walk(target, options);
+
+    let expected = vec![
+        (
+            "links.tar",
+            "c3ecf7ccd67e15d955058215bb325ed5d482f6478f7e4cae6fdfa3f29fac4bd9",
+        ),
+        (
+            "links.tar!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+    ];
+
+    assert_walked_hashed_content(walker, expected);
+}
+
+#[test]
+fn walk_tar_special_files() {
+    let target = testdata::target("testdata/special");
+    let entries = walk(target, Default::default())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+
+    let archive = entries
+        .iter()
+        .find(|entry| entry.path() == Path::new("special.tar"))
+        .expect("must walk archive");
+    let skipped = archive
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Error::SpecialFile { path, kind } => (path.to_owned(), *kind),
+            other => panic!("unexpected warning: {other:?}"),
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        skipped,
+        HashSet::from([
+            (PathBuf::from("simple/missing"), SpecialFile::HardLink),
+            (PathBuf::from("simple/escape"), SpecialFile::HardLink),
+            (PathBuf::from("simple/fifo"), SpecialFile::Fifo),
+            (PathBuf::from("simple/null"), SpecialFile::CharDevice),
+            (PathBuf::from("simple/sda"), SpecialFile::BlockDevice),
+        ])
+    );
+
+    // Hard links to files in the archive are expanded as copies.
+    let expected = vec![
+        (
+            "special.tar",
+            "aaadbdbbb4ca6a8125c3129a4ac7b9925dcd6fd6fdd267db6aec88336975f07a",
+        ),
+        (
+            "special.tar!_fossa.virtual_!/simple/a.txt",
+            "745654af9a20df673761aaed3d66e517f6b9dc4cbde9f2a819f0aa4c300d211f",
+        ),
+        (
+            "special.tar!_fossa.virtual_!/simple/hard",
+            "745654af9a20df673761aaed3d66e517f6b9dc4cbde9f2a819f0aa4c300d211f",
+        ),
+    ];
+    let target = testdata::target("testdata/special");
+    assert_walked_hashed_content(walk(target, Default::default()), expected);
+}
+
+#[test]
+fn walk_tar_creates_nothing_outside_destination() {
+    // Entries are written through a symlink to the extraction root, which is outside the destination.
+    let root = tempfile::tempdir().expect("create extraction root");
+    let target = testdata::target("testdata/escape");
+    let options = Options::builder().extraction_root(root.path()).build();
+
+    walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert!(!root.path().join("escaped").exists());
+}
+
+#[test]
+fn walk_zip_symlinks() {
+    let target = testdata::target("testdata/links-zip");
+    let entries = walk(target, Default::default())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+
+    // libarchive doesn't report link targets when unpacking zip archives, so symlinks in them are skipped.
+    let archive = entries
+        .iter()
+        .find(|entry| entry.path() == Path::new("links.zip"))
+        .expect("must walk archive");
+    assert_matches!(
+        archive.warnings(),
+        [Error::SpecialFile { path, kind: SpecialFile::Symlink }] if path == &PathBuf::from("simple/rel")
+    );
+
+    let expected = vec![
+        (
+            "links.zip",
+            "247713b614dad23ec50f6305a8afc85b189dbfc29cb3029418ffd8d37c8fad47",
+        ),
+        (
+            "links.zip!_fossa.virtual_!/simple/a.txt",
+            "a1521f679d5583c4bac29209c655c04a6cadb68a364d448d7b43224aeffd82ce",
+        ),
+    ];
+    assert_walked_hashed_content(entries.into_iter().map(Ok), expected);
+}
+
+#[test]
+fn walk_errors_on_symlinks() {
+    let target = testdata::target("testdata/links");
+    let options = Options::builder()
+        .symlink_policy(SymlinkPolicy::Error)
+        .build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_matches!(entries.as_slice(), [entry] => {
+        assert_eq!(entry.path(), PathBuf::from("links.tar"));
+        assert_matches!(entry.warnings(), [Error::Expand(_)]);
+    });
+}
+
+#[test]
+fn walk_fails_fast() {
+    let options = || {
+        Options::builder()
+            .symlink_policy(SymlinkPolicy::Error)
+            .on_error(ErrorPolicy::FailFast)
+            .build()
+    };
+
+    let serial = walk(testdata::target("testdata/links"), options()).collect::<Vec<_>>();
+    let parallel = walk_parallel(testdata::target("testdata/links"), options()).collect::<Vec<_>>();
+    for results in [serial, parallel] {
+        assert_matches!(results.as_slice(), [Err(Error::ExpandArchive { path, .. })] => {
+            assert_eq!(path, &PathBuf::from("links.tar"));
+        });
+    }
+}
+
+#[test]
+fn walk_cancelled_before_start() {
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let target = testdata::target("testdata/nested");
+    let options = Options::builder().cancel(cancel.clone()).build();
+    let got = walk(target.clone(), options.clone()).collect::<Vec<_>>();
+    assert_matches!(got.as_slice(), [Err(Error::Cancelled)]);
+
+    let got = walk_parallel(target, options).collect::<Vec<_>>();
+    assert_matches!(got.as_slice(), [Err(Error::Cancelled)]);
+}
+
+#[test]
+fn walk_cancelled_during_walk() {
+    let cancel = CancelToken::new();
+    let target = testdata::target("testdata/nested");
+    let options = Options::builder().cancel(cancel.clone()).build();
+
+    let mut walker = walk(target, options);
+    let first = walker.next();
+    assert_matches!(first, Some(Ok(_)));
+    cancel.cancel();
+
+    // The walker may have been one entry ahead of the consumer when cancelled.
+    let rest = walker.collect::<Vec<_>>();
+    assert!(rest.len() <= 2, "walk must stop promptly: {rest:?}");
+    assert_matches!(rest.last(), Some(Err(Error::Cancelled)));
+}
+
+#[test]
+fn walk_deduplicates_identical_archives() {
+    let target = testdata::target("testdata/duplicates");
+    let (paths, expansions) = walk_counting_expansions(target, true);
+
+    assert_eq!(paths, duplicates_expected());
+    assert_eq!(expansions, 1);
+}
+
+#[test]
+fn walk_without_deduplication() {
+    let target = testdata::target("testdata/duplicates");
+    let (paths, expansions) = walk_counting_expansions(target, false);
+
+    assert_eq!(paths, duplicates_expected());
+    assert_eq!(expansions, 2);
+}
+
+fn duplicates_expected() -> HashSet<PathBuf> {
+    vec![
+        "one/simple.zip",
+        "one/simple.zip!_fossa.virtual_!/simple/a.txt",
+        "one/simple.zip!_fossa.virtual_!/simple/b.txt",
+        "two/simple.zip",
+        "two/simple.zip!_fossa.virtual_!/simple/a.txt",
+        "two/simple.zip!_fossa.virtual_!/simple/b.txt",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect()
+}
+
+/// Walk the target, returning the walked paths along with the number of archives expanded.
+fn walk_counting_expansions(target: Target, deduplicate: bool) -> (HashSet<PathBuf>, usize) {
+    let expansions = Arc::new(Mutex::new(0));
+    let counter = expansions.clone();
+    let options = Options::builder()
+        .deduplicate(deduplicate)
+        .on_event(OnEvent::new(move |event| {
+            if let ExpandEvent::ArchiveExpanded { .. } = event {
+                *counter.lock().expect("lock counter") += 1;
+            }
+        }))
+        .build();
+
+    let paths = walk(target, options)
+        .map(|e| e.map(|e| e.path().to_owned()))
+        .collect::<Result<HashSet<_>, _>>()
+        .expect("must have expanded");
+    let expansions = *expansions.lock().expect("lock counter");
+    (paths, expansions)
+}
+
+#[test]
+fn walk_persists_expanded_archives() {
+    let persisted = Persisted::new();
+    let target = testdata::target("testdata/simplezip");
+    let options = Options::builder().persist(persisted.clone()).build();
+
+    // Drop all entries, which would normally remove the expanded archive.
+    let entries = walk(target, options).count();
+    assert_eq!(entries, 3);
+
+    let locations = persisted.locations();
+    let destination = locations
+        .get(&Source::new("simple.zip"))
+        .expect("must have recorded archive")
+        .inner()
+        .clone();
+    assert!(destination.join("simple").join("a.txt").is_file());
+
+    persisted.cleanup().expect("must clean up");
+    assert!(!destination.exists());
+    assert!(persisted.locations().is_empty());
+    persisted.cleanup().expect("must clean up again");
+}
+
+#[test]
+fn walk_reports_recursion_limit() {
+    let target = testdata::target("testdata/nested");
+    let options = Options::builder()
+        .recursion(Recursion::Enabled { depth: 1 })
+        .build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let warned = entries
+        .iter()
+        .filter(|entry| !entry.warnings().is_empty())
+        .map(|entry| entry.path().to_owned())
+        .collect::<HashSet<_>>();
+
+    // `nested.zip` is expanded at depth 1, but the archives inside it would be expanded at depth 2.
+    assert_eq!(
+        warned,
+        HashSet::from([
+            PathBuf::from("nested.zip!_fossa.virtual_!/inner.zip"),
+            PathBuf::from("nested.zip!_fossa.virtual_!/simple.tar.xz"),
+            PathBuf::from("nested.zip!_fossa.virtual_!/simplezip/simple.zip"),
+        ])
+    );
+    for entry in entries.iter().filter(|entry| !entry.warnings().is_empty()) {
+        assert_matches!(entry.warnings(), [Error::RecursionLimit]);
+    }
+}
+
+#[test]
+fn walk_recursion_overrides() {
+    let target = testdata::target("testdata/duplicates");
+    let options = Options::builder()
+        .recursion(Recursion::Enabled { depth: 1 })
+        .depth_overrides([(PathBuf::from("two"), 0)].into())
+        .build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let paths = entries
+        .iter()
+        .map(|entry| entry.path().to_owned())
+        .collect::<HashSet<_>>();
+
+    // Archives under `two` aren't expanded, but archives elsewhere use the default depth.
+    let expected = vec![
+        "one/simple.zip",
+        "one/simple.zip!_fossa.virtual_!/simple/a.txt",
+        "one/simple.zip!_fossa.virtual_!/simple/b.txt",
+        "two/simple.zip",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect::<HashSet<_>>();
+    assert_eq!(paths, expected);
+
+    let skipped = entries
+        .iter()
+        .find(|entry| entry.path() == Path::new("two/simple.zip"))
+        .expect("must walk archive");
+    assert_matches!(skipped.warnings(), [Error::RecursionLimit]);
+}
+
+#[test]
+fn entry_reader_seeks() {
+    let target = testdata::target("testdata/simplezip");
+    let mut entry = walk(target, Default::default())
+        .filter_map(Result::ok)
+        .find(|entry| entry.path().ends_with("a.txt"))
+        .expect("must walk file in archive");
+
+    let mut reader = entry.reader().expect("must open reader");
+    let mut first = String::new();
+    reader.read_to_string(&mut first).expect("must read");
+    assert_eq!(first, "6b5effe3-215a-49ec-9286-f0702f7eb529");
+
+    reader.seek(SeekFrom::Start(9)).expect("must seek");
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest).expect("must read");
+    assert_eq!(rest, "215a-49ec-9286-f0702f7eb529");
+}
+
+#[test]
+fn walk_fallible_iterator() {
+    use archive::FallibleIterator;
+
+    let target = testdata::target("testdata/simplezip");
+    let walker = walk(target, Default::default());
+    let paths = FallibleIterator::map(walker, |entry| Ok(entry.into_path()))
+        .collect::<HashSet<_>>()
+        .expect("must walk");
+
+    let expected = vec![
+        "simple.zip",
+        "simple.zip!_fossa.virtual_!/simple/a.txt",
+        "simple.zip!_fossa.virtual_!/simple/b.txt",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect::<HashSet<_>>();
+    assert_eq!(paths, expected);
+}
+
+#[test]
+fn walk_reports_unsupported_archives() {
+    // Map each walked path to the formats reported as unsupported for it.
+    let unsupported = |report_unsupported: bool| {
+        let target = testdata::target("testdata/unsupported");
+        let options = Options::builder()
+            .report_unsupported(report_unsupported)
+            .build();
+        walk(target, options)
+            .map(|entry| entry.expect("must walk"))
+            .map(|entry| {
+                let formats = entry
+                    .warnings()
+                    .iter()
+                    .map(|warning| match warning {
+                        Error::UnsupportedArchive { format } => format.clone(),
+                        other => panic!("unexpected warning: {other:?}"),
+                    })
+                    .collect::<Vec<_>>();
+                (entry.path().to_owned(), formats)
+            })
+            .collect::<HashMap<_, _>>()
+    };
+
+    // Files that look like archives are identified by magic bytes first, then by extension.
+    // Zip containers, such as office documents, aren't reported.
+    let expected = HashMap::from([
+        (PathBuf::from("archive.7z"), vec![String::from("7z")]),
+        (PathBuf::from("disk.dmg"), vec![String::from(".dmg")]),
+        (PathBuf::from("notes.txt"), vec![]),
+        (PathBuf::from("report.docx"), vec![]),
+    ]);
+    assert_eq!(unsupported(true), expected);
+
+    // Unsupported files are only reported when enabled.
+    let unreported = unsupported(false);
+    assert!(unreported.values().all(Vec::is_empty), "{unreported:?}");
+}
+
+#[test]
+fn walk_removes_oversized_entries() {
+    let target = testdata::target("testdata/simplezip");
+    let options = Options::builder().max_entry_bytes(10).build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let paths = entries
+        .iter()
+        .map(|entry| entry.path().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec![PathBuf::from("simple.zip")]);
+
+    let oversized = entries[0]
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Error::EntryTooLarge { path, limit: 10 } => path.to_owned(),
+            other => panic!("unexpected warning: {other:?}"),
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        oversized,
+        HashSet::from([
+            PathBuf::from("simple").join("a.txt"),
+            PathBuf::from("simple").join("b.txt"),
+        ])
+    );
+}
+
+#[test]
+fn walk_skips_oversized_tar_entries() {
+    let target = testdata::target("testdata/simple.tar");
+    let options = Options::builder().max_entry_bytes(10).build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_eq!(
+        entries.len(),
+        1,
+        "oversized files must not be walked: {entries:?}"
+    );
+
+    let oversized = entries[0]
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Error::EntryTooLarge { path, limit: 10 } => path.to_owned(),
+            other => panic!("unexpected warning: {other:?}"),
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        oversized,
+        HashSet::from([
+            PathBuf::from("simple").join("a.txt"),
+            PathBuf::from("simple").join("b.txt"),
+        ])
+    );
+}
+
+#[test]
+fn walk_skips_oversized_cpio_entries() {
+    let target = testdata::target("testdata/simple.cpio");
+    let options = Options::builder().max_entry_bytes(10).build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_eq!(
+        entries.len(),
+        1,
+        "oversized files must not be walked: {entries:?}"
+    );
+
+    let oversized = entries[0]
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Error::EntryTooLarge { path, limit: 10 } => path.to_owned(),
+            other => panic!("unexpected warning: {other:?}"),
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        oversized,
+        HashSet::from([
+            PathBuf::from("simple").join("a.txt"),
+            PathBuf::from("simple").join("b.txt"),
+        ])
+    );
+}
+
+#[test]
+fn walk_limits_decompressed_files() {
+    let target = testdata::target("testdata/compressed");
+    let options = Options::builder().max_entry_bytes(10).build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let archive = entries
+        .iter()
+        .find(|entry| entry.path() == Path::new("a.txt.gz"))
+        .expect("must walk compressed file");
+    assert_matches!(
+        archive.warnings(),
+        [Error::EntryTooLarge { path, limit: 10 }] if path == &PathBuf::from("a.txt")
+    );
+    assert!(
+        entries
+            .iter()
+            .all(|entry| !entry.path().starts_with("a.txt.gz!_fossa.virtual_!")),
+        "oversized file must not be walked: {entries:?}"
+    );
+}
+
+#[test]
+fn walk_reports_ancestors() {
+    let target = testdata::target("testdata/nested");
+    let entries = walk(target, Default::default())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let ancestors = |path: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.path() == Path::new(path))
+            .unwrap_or_else(|| panic!("must walk {path}"))
+            .ancestors()
+            .iter()
+            .map(|ancestor| (ancestor.path().to_owned(), ancestor.depth()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ancestors("nested.zip"), vec![]);
+    assert_eq!(
+        ancestors("nested.zip!_fossa.virtual_!/simplezip/simple.zip"),
+        vec![(PathBuf::from("nested.zip"), 1)]
+    );
+    assert_eq!(
+        ancestors("nested.zip!_fossa.virtual_!/simplezip/simple.zip!_fossa.virtual_!/simple/a.txt"),
+        vec![
+            (PathBuf::from("nested.zip"), 1),
+            (
+                PathBuf::from("nested.zip!_fossa.virtual_!/simplezip/simple.zip"),
+                2
+            ),
+        ]
+    );
+}
+
+#[test]
+fn walk_collects_metrics() {
+    let target = testdata::target("testdata/nested");
+    let metrics = Metrics::new();
+    let options = Options::builder()
+        .recursion(Recursion::Enabled { depth: 1 })
+        .metrics(metrics.clone())
+        .build();
+
+    walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let report = metrics.report();
+
+    // Archives beyond the recursion limit are still expanded, but their contents aren't walked.
+    assert_eq!(report.expanded().values().sum::<usize>(), 4);
+    assert_eq!(report.deepest(), 1);
+    assert_eq!(report.warnings(), 3);
+    assert!(report.bytes_written() > 0);
+}
+
+#[test]
+fn walk_decodes_entry_names() {
+    let target = testdata::target("testdata/names");
+    let options = Options::builder()
+        .name_encoding(NameEncoding::Cp437)
+        .build();
+
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let raw_names = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path().to_owned(),
+                entry.raw_name().map(<[u8]>::to_vec),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    assert_eq!(
+        raw_names,
+        HashMap::from([
+            (PathBuf::from("cp437.zip"), None),
+            (
+                PathBuf::from("cp437.zip!_fossa.virtual_!/names/café.txt"),
+                Some(b"names/caf\x82.txt".to_vec())
+            ),
+            (
+                PathBuf::from("cp437.zip!_fossa.virtual_!/names/plain.txt"),
+                None
+            ),
+        ])
+    );
+
+    let expected = vec![
+        (
+            "cp437.zip",
+            "f31b2177ebf31a463296bb703e93fa9e20817a8ff6cb11dd1e51a7e8d7b9869b",
+        ),
+        (
+            "cp437.zip!_fossa.virtual_!/names/café.txt",
+            "7b49b9e063bd91a4f9252b413261f5557b9c570aa61516989499f64a62dbcdd6",
+        ),
+        (
+            "cp437.zip!_fossa.virtual_!/names/plain.txt",
+            "dacf36547c7774a0a170806363b5d412991fbc0d6260b2c00b1d3a80a816c23f",
+        ),
+    ];
+    let target = testdata::target("testdata/names");
+    let options = Options::builder()
+        .name_encoding(NameEncoding::Cp437)
+        .build();
+    assert_walked_hashed_content(walk(target, options), expected);
+}
+
+#[test]
+fn walk_skips_listed_digests() {
+    let digest = hex::decode("65edda9e1933aa8cff1d5aeec70a8ddbd43f971454b982f101aa9beff0b72901")
+        .expect("must decode digest")
+        .try_into()
+        .expect("must be a sha256 digest");
+    let target = testdata::target("testdata/simplezip");
+    let options = Options::builder()
+        .skip_digests(HashSet::from([digest]))
+        .on_error(ErrorPolicy::FailFast)
+        .build();
+
+    // Skip listed archives are walked as plain files, and aren't failures even when failing fast.
+    let entries = walk(target, options)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    assert_matches!(entries.as_slice(), [entry] => {
+        assert_eq!(entry.path(), PathBuf::from("simple.zip"));
+        assert_matches!(entry.warnings(), [Error::Expand(archive::strategy::Error::SkipListed)]);
+    });
+}
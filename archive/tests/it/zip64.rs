@@ -0,0 +1,103 @@
+//! Tests for zip64 archives.
+//!
+//! Zip archives use the zip64 format for members or archives larger than 4GB, or with more than 65535 members.
+//! The small fixture in `testdata/zip64` forces the zip64 format for every size and offset,
+//! while the large archive test generates an archive with a member larger than 4GB.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use archive::{expand::stream, expand::walk, list, EntryKind, Options, Target};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::testdata::{self, simple_content};
+
+#[test]
+fn extract_zip64() {
+    let target = testdata::target("testdata/zip64/simple.zip");
+    testdata::matches_content(
+        target.clone(),
+        Options::default(),
+        target.root().to_owned(),
+        simple_content(),
+    );
+}
+
+#[test]
+fn list_zip64() {
+    let target = testdata::target("testdata/zip64/simple.zip");
+    let mut got = list(target, Options::default())
+        .expect("must list")
+        .into_iter()
+        .map(|listing| (listing.path().to_owned(), listing.size()))
+        .collect::<Vec<_>>();
+    got.sort();
+
+    let expected = vec![
+        (PathBuf::from("simple/a.txt"), Some(36)),
+        (PathBuf::from("simple/b.txt"), Some(36)),
+    ];
+    assert_eq!(got, expected);
+}
+
+/// The size of the member in the large archive: larger than `u32::MAX`, so it can only be recorded in zip64 fields.
+const LARGE_MEMBER_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Write a zip64 archive at `path` containing a single member of [`LARGE_MEMBER_SIZE`] zero bytes.
+/// The member compresses well, so the archive itself is only a few megabytes.
+fn write_large_archive(path: &Path) -> io::Result<()> {
+    let mut writer = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    writer.start_file("large.bin", options)?;
+
+    let chunk = vec![0; 1024 * 1024];
+    for _ in 0..LARGE_MEMBER_SIZE / chunk.len() as u64 {
+        writer.write_all(&chunk)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Expands, lists, and streams a zip64 archive containing a member larger than 4GB.
+///
+/// Expanding the archive writes the member to disk in full, so this is ignored by default:
+/// run it with `cargo test -- --ignored` on a machine with at least 6GB of free space in the temporary directory.
+#[test]
+#[ignore]
+fn zip64_large_member() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let archive = dir.path().join("large.zip");
+    write_large_archive(&archive).expect("write archive");
+    let target = Target::builder().root(dir.path().to_owned()).build();
+
+    let entries = walk(target, Options::default())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("must walk");
+    let member = entries
+        .iter()
+        .find(|entry| entry.path() == Path::new("large.zip!_fossa.virtual_!/large.bin"))
+        .expect("must walk member");
+    assert_eq!(member.metadata().size(), LARGE_MEMBER_SIZE);
+    assert!(member.warnings().is_empty(), "{:?}", member.warnings());
+    drop(entries);
+
+    let target = Target::builder().root(archive.clone()).build();
+    let listed = list(target, Options::default()).expect("must list");
+    assert_eq!(listed.len(), 1, "{listed:?}");
+    assert_eq!(listed[0].kind(), EntryKind::File);
+    assert_eq!(listed[0].size(), Some(LARGE_MEMBER_SIZE));
+
+    // Members are streamed in chunks rather than being read into memory.
+    let mut streamed = 0;
+    stream(&archive, Options::default(), |_, reader| {
+        streamed += io::copy(reader, &mut io::sink())?;
+        Ok(())
+    })
+    .expect("must stream");
+    assert_eq!(streamed, LARGE_MEMBER_SIZE);
+}
@@ -0,0 +1,349 @@
+//! Caches fingerprints on disk, so that repeated scans of the same files skip fingerprinting unchanged files.
+//!
+//! Files are identified by their path, size, and modification time;
+//! if any of these change the file is fingerprinted again.
+//! This is the same tradeoff made by tools like `git` and `make`:
+//! a file rewritten with the same size and modification time is not detected as changed.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::{Combined, Error, KindId, KindSet};
+
+/// The version of the cache file format.
+/// Cache files written with a different version are discarded.
+const VERSION: u32 = 3;
+
+/// Identifies the state of a file at the time it was fingerprinted.
+///
+/// A key also records when it was created, so it must be created before the file is read;
+/// see [`Cache::get`].
+#[derive(Clone, Debug)]
+pub struct Key {
+    path: PathBuf,
+    len: u64,
+    modified: SystemTime,
+    created: SystemTime,
+}
+
+impl Key {
+    /// Create a key for a file at the given path, with the given size and modification time.
+    pub fn new(path: impl Into<PathBuf>, len: u64, modified: SystemTime) -> Self {
+        Self {
+            path: path.into(),
+            len,
+            modified,
+            created: SystemTime::now(),
+        }
+    }
+
+    /// Create a key for the file at the given path from its metadata.
+    pub fn for_file(path: &Path) -> Result<Self, Error> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self::new(path, metadata.len(), metadata.modified()?))
+    }
+}
+
+/// A cached fingerprint, along with the state of the file when it was fingerprinted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    len: u64,
+    modified: SystemTime,
+    /// When the key was created, before the file was read to fingerprint it.
+    fingerprinted: SystemTime,
+    combined: Combined,
+}
+
+/// The contents of a cache file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Contents {
+    version: u32,
+    /// The kinds computed for each cached fingerprint.
+    /// When the kinds change, cached fingerprints don't match the requested kinds, so they are discarded.
+    kinds: Vec<KindId>,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// An on-disk cache of fingerprints.
+///
+/// The cache is loaded in full by [`Cache::open`], and written in full by [`Cache::save`].
+/// Only the fingerprints of files looked up or inserted since the cache was opened are saved,
+/// so files that no longer exist are dropped from the cache.
+///
+/// Each cache holds fingerprints computed with a single [`KindSet`].
+///
+/// The cache may be shared between threads.
+#[derive(Debug)]
+pub struct Cache {
+    path: PathBuf,
+    kinds: KindSet,
+    previous: Contents,
+    current: Mutex<HashMap<PathBuf, Entry>>,
+}
+
+impl Cache {
+    /// Open the cache stored at the given path, holding fingerprints with the default kinds;
+    /// see [`KindSet::default`].
+    ///
+    /// If the file doesn't exist, can't be parsed, or was written by an incompatible version of this crate,
+    /// the cache starts out empty; it is always safe to discard a cache.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self::open_with(KindSet::default(), path)
+    }
+
+    /// Open the cache stored at the given path, holding fingerprints with the kinds in the set.
+    ///
+    /// As with [`Cache::open`], if the file was written with a different set of kinds
+    /// the cache starts out empty.
+    pub fn open_with(kinds: KindSet, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let previous = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, Contents>(BufReader::new(file)).ok())
+            .filter(|contents| {
+                contents.version == VERSION && contents.kinds.iter().copied().eq(kinds.iter())
+            })
+            .unwrap_or_default();
+
+        Self {
+            path,
+            kinds,
+            previous,
+            current: Mutex::default(),
+        }
+    }
+
+    /// The kinds of the fingerprints held in the cache.
+    pub fn kinds(&self) -> KindSet {
+        self.kinds
+    }
+
+    /// Look up the fingerprint of a file, returning `None` if the file isn't cached or has changed since it was cached.
+    ///
+    /// A file modified in the same instant it was fingerprinted may have changed while it was read
+    /// without its modification time changing, so its fingerprint isn't trusted.
+    pub fn get(&self, key: &Key) -> Option<Combined> {
+        let entry = self.previous.entries.get(&key.path)?;
+        if entry.len != key.len || entry.modified != key.modified {
+            return None;
+        }
+
+        if entry.modified >= entry.fingerprinted {
+            return None;
+        }
+
+        self.current
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.path.clone(), entry.clone());
+        Some(entry.combined.clone())
+    }
+
+    /// Record the fingerprint of a file.
+    pub fn insert(&self, key: Key, combined: Combined) {
+        let entry = Entry {
+            len: key.len,
+            modified: key.modified,
+            fingerprinted: key.created,
+            combined,
+        };
+        self.current
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.path, entry);
+    }
+
+    /// Fingerprint the provided file with the cache's fingerprint [`crate::Kind`]s,
+    /// using the cached fingerprint if the file hasn't changed since it was cached.
+    pub fn fingerprint(&self, path: &Path) -> Result<Combined, Error> {
+        let key = Key::for_file(path)?;
+        if let Some(combined) = self.get(&key) {
+            return Ok(combined);
+        }
+
+        let combined = crate::fingerprint_with(self.kinds, path)?;
+        self.insert(key, combined.clone());
+        Ok(combined)
+    }
+
+    /// Write the cache to disk.
+    ///
+    /// The cache is written to a temporary file first and then moved into place,
+    /// so that an interrupted write doesn't corrupt the cache.
+    /// Each save writes a uniquely named temporary file that only the current user can read,
+    /// so concurrent saves to the same path don't interfere; the last save to finish wins.
+    pub fn save(self) -> Result<(), Error> {
+        let contents = Contents {
+            version: VERSION,
+            kinds: self.kinds.iter().collect(),
+            entries: self
+                .current
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+
+        // The temporary file is created next to the cache, so that it can be renamed into place.
+        let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        fs::create_dir_all(dir)?;
+        let mut writer = BufWriter::new(NamedTempFile::new_in(dir)?);
+        serde_json::to_writer(&mut writer, &contents).map_err(std::io::Error::from)?;
+        let temp = writer.into_inner().map_err(|err| err.into_error())?;
+        temp.persist(&self.path).map_err(|err| err.error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use std::time::Duration;
+
+    use super::*;
+
+    fn combined(content: &[u8]) -> Combined {
+        crate::fingerprint_stream(&mut std::io::Cursor::new(content)).expect("must fingerprint")
+    }
+
+    /// A modification time that is safely before the files are fingerprinted.
+    fn past() -> SystemTime {
+        SystemTime::now() - Duration::from_secs(60)
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        let key = Key::new("a.txt", 5, past());
+
+        let cache = Cache::open(&path);
+        assert_eq!(None, cache.get(&key));
+        cache.insert(key.clone(), combined(b"hello"));
+        cache.save().expect("must save");
+
+        let cache = Cache::open(&path);
+        assert_eq!(Some(combined(b"hello")), cache.get(&key));
+    }
+
+    #[test]
+    fn invalidated_by_change() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        let modified = past();
+
+        let cache = Cache::open(&path);
+        cache.insert(Key::new("a.txt", 5, modified), combined(b"hello"));
+        cache.save().expect("must save");
+
+        let cache = Cache::open(&path);
+        assert_eq!(None, cache.get(&Key::new("a.txt", 6, modified)));
+        let touched = modified + Duration::from_secs(1);
+        assert_eq!(None, cache.get(&Key::new("a.txt", 5, touched)));
+        assert_eq!(None, cache.get(&Key::new("b.txt", 5, modified)));
+    }
+
+    #[test]
+    fn racy_entries_not_trusted() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        let key = Key::new("a.txt", 5, SystemTime::now() + Duration::from_secs(60));
+
+        let cache = Cache::open(&path);
+        cache.insert(key.clone(), combined(b"hello"));
+        cache.save().expect("must save");
+
+        assert_eq!(None, Cache::open(&path).get(&key));
+    }
+
+    #[test]
+    fn entries_modified_while_fingerprinted_not_trusted() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+
+        // The file was modified in the same instant it was fingerprinted, well before the cache is written.
+        let mut key = Key::new("a.txt", 5, past());
+        key.created = key.modified;
+
+        let cache = Cache::open(&path);
+        cache.insert(key.clone(), combined(b"hello"));
+        cache.save().expect("must save");
+
+        assert_eq!(None, Cache::open(&path).get(&key));
+    }
+
+    #[test]
+    fn unused_entries_dropped() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        let (a, b) = (Key::new("a.txt", 5, past()), Key::new("b.txt", 5, past()));
+
+        let cache = Cache::open(&path);
+        cache.insert(a.clone(), combined(b"hello"));
+        cache.insert(b.clone(), combined(b"world"));
+        cache.save().expect("must save");
+
+        // Only `a` is looked up, so only `a` is kept.
+        let cache = Cache::open(&path);
+        assert!(cache.get(&a).is_some());
+        cache.save().expect("must save");
+
+        let cache = Cache::open(&path);
+        assert!(cache.get(&a).is_some());
+        assert_eq!(None, cache.get(&b));
+    }
+
+    #[test]
+    fn corrupt_cache_discarded() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        fs::write(&path, "not a cache").expect("must write");
+
+        let key = Key::new("a.txt", 5, past());
+        let cache = Cache::open(&path);
+        assert_eq!(None, cache.get(&key));
+        cache.insert(key.clone(), combined(b"hello"));
+        cache.save().expect("must save");
+        assert!(Cache::open(&path).get(&key).is_some());
+    }
+
+    #[test]
+    fn concurrent_saves() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let path = dir.path().join("cache.json");
+        let key = Key::new("a.txt", 5, past());
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let cache = Cache::open(&path);
+                    cache.insert(key.clone(), combined(b"hello"));
+                    cache.save().expect("must save");
+                });
+            }
+        });
+
+        assert_eq!(Some(combined(b"hello")), Cache::open(&path).get(&key));
+        let files = fs::read_dir(dir.path()).expect("must read dir").count();
+        assert_eq!(1, files, "temporary files must not be left behind");
+    }
+
+    #[test]
+    fn fingerprint_file() {
+        let dir = tempfile::tempdir().expect("must create temp dir");
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").expect("must write");
+
+        let cache = Cache::open(dir.path().join("cache.json"));
+        let fingerprinted = cache.fingerprint(&file).expect("must fingerprint");
+        assert_eq!(combined(b"hello"), fingerprinted);
+    }
+}
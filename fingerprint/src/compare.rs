@@ -0,0 +1,177 @@
+//! Compares [`Combined`] fingerprints to decide how the files they were computed from are related.
+//!
+//! Exact kinds (such as [`crate::CommentStrippedSHA256`]) match when files are the same after normalization,
+//! while fuzzy kinds ([`crate::RawSsdeep`], [`crate::RawTLSH`], and [`crate::WinnowingMinHash`]) are scored,
+//! and indicate that files are related when their scores pass the configured [`Thresholds`].
+
+use getset::CopyGetters;
+
+use crate::{Combined, Fingerprint, Kind, KindId};
+
+/// How two files are related, according to their fingerprints.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Verdict {
+    /// The files have the same content: their [`crate::RawSHA256`] fingerprints match.
+    Identical,
+
+    /// The files differ, but one is likely a modified copy of the other:
+    /// either another exact kind matches (for example, only comments or whitespace differ),
+    /// or a fuzzy kind scores the files as similar.
+    LikelyModifiedCopy,
+
+    /// None of the fingerprints indicate that the files are related.
+    Different,
+}
+
+/// The scores at which fuzzy fingerprint kinds indicate that two files are related.
+///
+/// The defaults are conservative: they favor reporting related files as [`Verdict::Different`]
+/// over reporting unrelated files as [`Verdict::LikelyModifiedCopy`].
+#[derive(Clone, Copy, PartialEq, Debug, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct Thresholds {
+    /// The minimum [`Fingerprint::similarity`] of [`crate::RawSsdeep`] fingerprints. Defaults to 50.
+    ssdeep: u8,
+
+    /// The maximum [`Fingerprint::distance`] of [`crate::RawTLSH`] fingerprints. Defaults to 100.
+    tlsh: u32,
+
+    /// The minimum [`Fingerprint::jaccard`] similarity of [`crate::WinnowingMinHash`] fingerprints. Defaults to 0.5.
+    jaccard: f64,
+}
+
+impl Thresholds {
+    /// Set the minimum [`Fingerprint::similarity`] of [`crate::RawSsdeep`] fingerprints.
+    pub fn with_ssdeep(self, ssdeep: u8) -> Self {
+        Self { ssdeep, ..self }
+    }
+
+    /// Set the maximum [`Fingerprint::distance`] of [`crate::RawTLSH`] fingerprints.
+    pub fn with_tlsh(self, tlsh: u32) -> Self {
+        Self { tlsh, ..self }
+    }
+
+    /// Set the minimum [`Fingerprint::jaccard`] similarity of [`crate::WinnowingMinHash`] fingerprints.
+    pub fn with_jaccard(self, jaccard: f64) -> Self {
+        Self { jaccard, ..self }
+    }
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            ssdeep: 50,
+            tlsh: 100,
+            jaccard: 0.5,
+        }
+    }
+}
+
+/// The result of comparing two [`Combined`] fingerprints.
+#[derive(Clone, PartialEq, Debug, CopyGetters)]
+pub struct Comparison {
+    /// How the files are related.
+    #[getset(get_copy = "pub")]
+    verdict: Verdict,
+
+    /// The exact kinds whose fingerprints match.
+    exact: Vec<KindId>,
+
+    /// The [`Fingerprint::similarity`] of the [`crate::RawSsdeep`] fingerprints, if both have one.
+    #[getset(get_copy = "pub")]
+    ssdeep: Option<u8>,
+
+    /// The [`Fingerprint::distance`] of the [`crate::RawTLSH`] fingerprints, if both have a valid one.
+    #[getset(get_copy = "pub")]
+    tlsh: Option<u32>,
+
+    /// The [`Fingerprint::jaccard`] similarity of the [`crate::WinnowingMinHash`] fingerprints, if both have a valid one.
+    #[getset(get_copy = "pub")]
+    jaccard: Option<f64>,
+}
+
+impl Comparison {
+    /// The exact kinds whose fingerprints match.
+    ///
+    /// Fuzzy kinds are never included, even if their fingerprints are the same.
+    pub fn exact(&self) -> &[KindId] {
+        &self.exact
+    }
+}
+
+/// Compare two fingerprints using the default [`Thresholds`].
+pub fn compare(a: &Combined, b: &Combined) -> Comparison {
+    compare_with(a, b, Thresholds::default())
+}
+
+/// Compare two fingerprints, using the provided thresholds for fuzzy kinds.
+///
+/// Kinds that are missing from either fingerprint (for example, text-only kinds for binary files) are ignored.
+pub fn compare_with(a: &Combined, b: &Combined, thresholds: Thresholds) -> Comparison {
+    let mut exact = Vec::new();
+    if a.raw == b.raw {
+        exact.push(KindId::RawSHA256);
+    }
+    push_exact(&mut exact, &a.comment_stripped, &b.comment_stripped);
+    push_exact(
+        &mut exact,
+        &a.normalized_whitespace,
+        &b.normalized_whitespace,
+    );
+    push_exact(
+        &mut exact,
+        &a.hash_comment_stripped,
+        &b.hash_comment_stripped,
+    );
+    push_exact(
+        &mut exact,
+        &a.literal_aware_comment_stripped,
+        &b.literal_aware_comment_stripped,
+    );
+    push_exact(&mut exact, &a.java_class, &b.java_class);
+    push_exact(&mut exact, &a.byte_exact, &b.byte_exact);
+    push_exact(&mut exact, &a.native_sections, &b.native_sections);
+    push_exact(&mut exact, &a.merkle, &b.merkle);
+
+    let ssdeep = both(&a.ssdeep, &b.ssdeep).map(|(a, b)| a.similarity(b));
+    let tlsh = both(&a.tlsh, &b.tlsh).and_then(|(a, b)| a.distance(b));
+    let jaccard = both(&a.winnowing, &b.winnowing).and_then(|(a, b)| a.jaccard(b));
+
+    let similar = matches!(ssdeep, Some(score) if score >= thresholds.ssdeep)
+        || matches!(tlsh, Some(distance) if distance <= thresholds.tlsh)
+        || matches!(jaccard, Some(similarity) if similarity >= thresholds.jaccard);
+    let verdict = if exact.contains(&KindId::RawSHA256) {
+        Verdict::Identical
+    } else if !exact.is_empty() || similar {
+        Verdict::LikelyModifiedCopy
+    } else {
+        Verdict::Different
+    };
+
+    Comparison {
+        verdict,
+        exact,
+        ssdeep,
+        tlsh,
+        jaccard,
+    }
+}
+
+/// Record the kind as an exact match if both fingerprints are present and the same.
+fn push_exact<K: Kind + PartialEq>(
+    exact: &mut Vec<KindId>,
+    a: &Option<Fingerprint<K>>,
+    b: &Option<Fingerprint<K>>,
+) {
+    if matches!(both(a, b), Some((a, b)) if a == b) {
+        exact.push(K::ID);
+    }
+}
+
+/// Pair the fingerprints if both are present.
+fn both<'a, K: Kind>(
+    a: &'a Option<Fingerprint<K>>,
+    b: &'a Option<Fingerprint<K>>,
+) -> Option<(&'a Fingerprint<K>, &'a Fingerprint<K>)> {
+    a.as_ref().zip(b.as_ref())
+}
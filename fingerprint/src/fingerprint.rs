@@ -3,7 +3,12 @@ use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
 use iter_read::IterRead;
 use sha2::{Digest, Sha256};
 
-use crate::{stream::ConvertCRLFToLF, CommentStrippedSHA256, Error, Fingerprint, RawSHA256};
+use crate::{
+    java, merkle, native, ssdeep, stream::ConvertCRLFToLF, tlsh, winnow, Blob, ByteExactSHA256,
+    CommentStrippedSHA256, Error, Fingerprint, HashCommentStrippedSHA256, JavaClassSHA256, Kind,
+    LiteralAwareCommentStrippedSHA256, MerkleSHA256, NativeSectionsSHA256,
+    NormalizedWhitespaceSHA256, RawSHA256, RawSsdeep, RawTLSH, WinnowingMinHash,
+};
 
 /// Fingerprint the file using the [`RawSHA256`] kind.
 pub fn raw<R: BufRead>(stream: &mut R) -> Result<Fingerprint<RawSHA256>, Error> {
@@ -22,6 +27,20 @@ pub fn raw<R: BufRead>(stream: &mut R) -> Result<Fingerprint<RawSHA256>, Error>
     Fingerprint::from_digest(hasher)
 }
 
+/// Fingerprint the file using the [`ByteExactSHA256`] kind.
+pub fn byte_exact<R: BufRead>(stream: &mut R) -> Result<Fingerprint<ByteExactSHA256>, Error> {
+    let mut hasher = Sha256::new();
+    content_binary(stream, &mut hasher)?;
+    Fingerprint::from_digest(hasher)
+}
+
+/// Fingerprint the file using the [`MerkleSHA256`] kind.
+///
+/// Use [`merkle::tree`] to also retain the hash of each chunk.
+pub fn merkle<R: BufRead>(stream: &mut R) -> Result<Fingerprint<MerkleSHA256>, Error> {
+    Ok(merkle::tree(stream)?.fingerprint())
+}
+
 /// Fingerprint the file using the [`CommentStrippedSHA256`] kind.
 pub fn comment_stripped<R: BufRead>(
     stream: &mut R,
@@ -49,6 +68,245 @@ pub fn comment_stripped<R: BufRead>(
     }
 }
 
+/// Fingerprint the file using the [`HashCommentStrippedSHA256`] kind.
+pub fn hash_comment_stripped<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<HashCommentStrippedSHA256>>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+    if is_binary {
+        return Ok(None);
+    }
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = Sha256::new();
+    match content_hash_stripped(&mut stream, &mut hasher) {
+        Ok(_) => Some(Fingerprint::from_digest(hasher)).transpose(),
+        Err(err) => {
+            // The `io::Error` type is opaque.
+            // Handle the case of attempting to comment strip a binary file.
+            if err.to_string().to_lowercase().contains("utf-8") {
+                Ok(None)
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Fingerprint the file using the [`LiteralAwareCommentStrippedSHA256`] kind.
+pub fn literal_aware_comment_stripped<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<LiteralAwareCommentStrippedSHA256>>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+    if is_binary {
+        return Ok(None);
+    }
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = Sha256::new();
+    match content_literal_aware_stripped(&mut stream, &mut hasher) {
+        Ok(_) => Some(Fingerprint::from_digest(hasher)).transpose(),
+        Err(err) => {
+            // The `io::Error` type is opaque.
+            // Handle the case of attempting to comment strip a binary file.
+            if err.to_string().to_lowercase().contains("utf-8") {
+                Ok(None)
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Fingerprint the file using the [`NormalizedWhitespaceSHA256`] kind.
+pub fn normalized_whitespace<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<NormalizedWhitespaceSHA256>>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+    if is_binary {
+        return Ok(None);
+    }
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = Sha256::new();
+    content_normalized_whitespace(&mut stream, &mut hasher)?;
+    Some(Fingerprint::from_digest(hasher)).transpose()
+}
+
+/// Fingerprint the file using the [`RawSsdeep`] kind.
+pub fn ssdeep<R: BufRead>(stream: &mut R) -> Result<Fingerprint<RawSsdeep>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = ssdeep::Hasher::default();
+    if is_binary {
+        content_binary(&mut stream, &mut hasher)?;
+    } else {
+        content_text(&mut stream, &mut hasher)?;
+    }
+
+    let signature = hasher.finish();
+    Ok(Fingerprint::new(Blob(signature.into_bytes())))
+}
+
+/// Fingerprint the file using the [`RawTLSH`] kind.
+///
+/// Returns `None` if the content is too short or too uniform for TLSH to produce a meaningful digest.
+pub fn tlsh<R: BufRead>(stream: &mut R) -> Result<Option<Fingerprint<RawTLSH>>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = tlsh::Hasher::default();
+    if is_binary {
+        content_binary(&mut stream, &mut hasher)?;
+    } else {
+        content_text(&mut stream, &mut hasher)?;
+    }
+
+    let digest = hasher.finish();
+    Ok(digest.map(|digest| Fingerprint::new(Blob(digest.to_vec()))))
+}
+
+/// Fingerprint the file using the [`WinnowingMinHash`] kind.
+///
+/// Returns `None` if the file is binary, or too short to contain a k-gram.
+pub fn winnowing<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<WinnowingMinHash>>, Error> {
+    // Read the start of the stream, and decide whether to treat the rest of the stream as binary based on that.
+    let BinaryCheck { read, is_binary } = content_is_binary(stream)?;
+    if is_binary {
+        return Ok(None);
+    }
+
+    // Chain the part of the stream already read to evaluate binary along with the rest of the stream.
+    let mut stream = Cursor::new(read).chain(stream);
+    let mut hasher = winnow::Hasher::default();
+    content_text(&mut stream, &mut hasher)?;
+
+    let sketch = hasher.finish();
+    Ok(sketch.map(|sketch| Fingerprint::new(Blob(sketch))))
+}
+
+/// Fingerprint the file using the [`JavaClassSHA256`] kind.
+///
+/// Returns `None` if the file isn't a Java class file.
+pub fn java_class<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<JavaClassSHA256>>, Error> {
+    read_java_class(stream)?
+        .map(|class| hash_java_class(&class))
+        .transpose()
+}
+
+/// Hash a Java class file that has already been normalized.
+pub(crate) fn hash_java_class(class: &[u8]) -> Result<Fingerprint<JavaClassSHA256>, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(class);
+    Fingerprint::from_digest(hasher)
+}
+
+/// Reads a Java class file, removing metadata recorded by the compiler.
+/// Nothing is written if the content isn't a valid class file.
+///
+/// See the `java` module for details.
+pub(crate) fn content_java_class(stream: &mut impl Read, w: &mut impl Write) -> Result<(), Error> {
+    if let Some(class) = read_java_class(stream)? {
+        w.write_all(&class)?;
+    }
+    Ok(())
+}
+
+/// Reads and normalizes a Java class file, returning `None` if the content isn't a valid class file.
+///
+/// If the start of the content doesn't look like a class file, the rest of the content isn't read.
+fn read_java_class(stream: &mut impl Read) -> Result<Option<Vec<u8>>, Error> {
+    let class = read_if_header(
+        stream,
+        java::HEADER_SIZE,
+        java::MAX_SIZE,
+        java::is_class_header,
+    )?;
+    Ok(class.and_then(|class| java::normalize(&class)))
+}
+
+/// Fingerprint the file using the [`NativeSectionsSHA256`] kind.
+///
+/// Returns `None` if the file isn't an ELF or Mach-O binary.
+pub fn native_sections<R: BufRead>(
+    stream: &mut R,
+) -> Result<Option<Fingerprint<NativeSectionsSHA256>>, Error> {
+    read_native_sections(stream)?
+        .map(|sections| hash_native_sections(&sections))
+        .transpose()
+}
+
+/// Hash the sections of a native binary that have already been normalized.
+pub(crate) fn hash_native_sections(
+    sections: &[u8],
+) -> Result<Fingerprint<NativeSectionsSHA256>, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(sections);
+    Fingerprint::from_digest(hasher)
+}
+
+/// Reads the code and data sections of a native binary, discarding metadata recorded by the linker.
+/// Nothing is written if the content isn't a valid ELF or Mach-O binary.
+///
+/// See the `native` module for details.
+pub(crate) fn content_native_sections(
+    stream: &mut impl Read,
+    w: &mut impl Write,
+) -> Result<(), Error> {
+    if let Some(sections) = read_native_sections(stream)? {
+        w.write_all(&sections)?;
+    }
+    Ok(())
+}
+
+/// Reads and normalizes a native binary, returning `None` if the content isn't a valid ELF or Mach-O binary.
+///
+/// If the start of the content doesn't look like a native binary, the rest of the content isn't read.
+fn read_native_sections(stream: &mut impl Read) -> Result<Option<Vec<u8>>, Error> {
+    let binary = read_if_header(
+        stream,
+        native::HEADER_SIZE,
+        native::MAX_SIZE,
+        native::is_native_header,
+    )?;
+    Ok(binary.and_then(|binary| native::normalize(&binary)))
+}
+
+/// Reads the content if its first `header_size` bytes satisfy `is_header`, returning `None` otherwise.
+///
+/// Content larger than `max_size` is read up to one byte past the limit, so that it is rejected rather than truncated.
+fn read_if_header(
+    stream: &mut impl Read,
+    header_size: usize,
+    max_size: usize,
+    is_header: fn(&[u8]) -> bool,
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut content = Vec::new();
+    stream.take(header_size as u64).read_to_end(&mut content)?;
+    if !is_header(&content) {
+        return Ok(None);
+    }
+
+    let limit = (max_size - content.len() + 1) as u64;
+    stream.take(limit).read_to_end(&mut content)?;
+    Ok(Some(content))
+}
+
 /// The result of checking a file for whether it is binary.
 pub(crate) struct BinaryCheck {
     pub(crate) read: Vec<u8>,
@@ -101,25 +359,257 @@ pub(crate) fn content_text(stream: &mut impl BufRead, w: &mut impl Write) -> Res
 /// - Any sequence of multiple contiguous `\n` bytes are collapsed to a single `\n` byte.
 /// - The final `\n` byte is removed from the end of the stream if present.
 pub(crate) fn content_stripped(stream: &mut impl BufRead, w: &mut impl Write) -> Result<(), Error> {
-    let mut buffered_output_line = String::new();
-    let mut is_multiline_active = false;
+    strip_lines::<CommentStripper>(stream, w)
+}
+
+/// Hashes script files while removing `#`-style comments and blank lines in a platform independent manner.
+///
+/// Specifically:
+/// - All text encodings are treated as utf8.
+/// - `git` implementations on Windows typically check out files with `\r\n` line endings,
+///   while *nix checks them out with `\n`.
+///   To be platform independent, any `\r\n` byte sequences found are converted to a single `\n`.
+/// - `#` is considered the start of a single line comment; these bytes and any other bytes until right before a `\n` are removed.
+///   This includes shebang lines (`#!`).
+///   This function does not check for `#` inside strings or escaped `#`.
+/// - Leading and trailing whitespace on each line is removed.
+/// - Any sequence of multiple contiguous `\n` bytes are collapsed to a single `\n` byte.
+/// - The final `\n` byte is removed from the end of the stream if present.
+pub(crate) fn content_hash_stripped(
+    stream: &mut impl BufRead,
+    w: &mut impl Write,
+) -> Result<(), Error> {
+    strip_lines::<HashCommentStripper>(stream, w)
+}
+
+/// Hashes code files while removing C-style comments and blank lines in a platform independent manner,
+/// leaving string and character literals intact.
+///
+/// This is the same as [`content_stripped`], except:
+/// - `"` starts a string literal, which ends at the next `"` that isn't escaped with `\`.
+///   A string literal that isn't closed by the end of the line ends there,
+///   unless the line ends with `\` (a line continuation).
+/// - `'` starts a character literal if it is followed by either a single character or an escape sequence, and then `'`.
+///   Otherwise it is treated as any other character, so that (for example) Rust lifetimes and C++ digit separators
+///   aren't mistaken for the start of a literal.
+/// - Comment markers inside literals are kept, and quotes inside comments are removed along with the comment.
+pub(crate) fn content_literal_aware_stripped(
+    stream: &mut impl BufRead,
+    w: &mut impl Write,
+) -> Result<(), Error> {
+    strip_lines::<LiteralAwareCommentStripper>(stream, w)
+}
 
+/// Reads text files while collapsing whitespace in a platform independent manner.
+///
+/// Specifically:
+/// - All text encodings are ignored; this function operates on raw bytes.
+/// - Whitespace is the ASCII bytes ` `, `\t`, `\n`, `\x0C` (form feed), and `\r`.
+///   Since `\r` and `\n` are both whitespace, line endings don't affect the output.
+/// - Each run of contiguous whitespace bytes is collapsed to a single ` ` byte.
+/// - Whitespace at the start and end of the stream is removed.
+pub(crate) fn content_normalized_whitespace(
+    stream: &mut impl BufRead,
+    w: &mut impl Write,
+) -> Result<(), Error> {
+    let mut normalizer = WhitespaceNormalizer::default();
+    let mut normalized = Vec::new();
+
+    loop {
+        let buf = stream.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+
+        normalized.clear();
+        normalizer.normalize(buf, &mut normalized);
+
+        let read = buf.len();
+        stream.consume(read);
+        w.write_all(&normalized)?;
+    }
+
+    Ok(())
+}
+
+/// Reads lines from the stream, writing each line after stripping it with `S`.
+fn strip_lines<S: StripLine>(stream: &mut impl BufRead, w: &mut impl Write) -> Result<(), Error> {
+    let mut writer = StrippedLines::<S>::default();
     for line in stream.lines() {
-        let line = line?;
+        writer.line(line?, w)?;
+    }
+    writer.finish(w)
+}
+
+/// Strips comments from a single line of text, tracking any state that carries over between lines.
+pub(crate) trait StripLine: Default {
+    /// The kind of fingerprint computed from the stripped lines.
+    type Kind: Kind;
+
+    /// Strip comments from the line, which has had its line ending removed.
+    fn strip(&mut self, line: String) -> String;
+}
+
+/// Strips C-style comments; see [`content_stripped`].
+#[derive(Default)]
+pub(crate) struct CommentStripper {
+    is_multiline_active: bool,
+}
+
+impl StripLine for CommentStripper {
+    type Kind = CommentStrippedSHA256;
+
+    fn strip(&mut self, line: String) -> String {
+        let (cleaned, is_multiline_active) = clean_line(line, self.is_multiline_active);
+        self.is_multiline_active = is_multiline_active;
+        cleaned
+    }
+}
+
+/// Strips `#`-style comments; see [`content_hash_stripped`].
+#[derive(Default)]
+pub(crate) struct HashCommentStripper;
+
+impl StripLine for HashCommentStripper {
+    type Kind = HashCommentStrippedSHA256;
+
+    fn strip(&mut self, mut line: String) -> String {
+        if let Some(start) = line.find('#') {
+            line.truncate(start);
+        }
+        line
+    }
+}
+
+/// Strips C-style comments outside of string and character literals; see [`content_literal_aware_stripped`].
+#[derive(Default)]
+pub(crate) struct LiteralAwareCommentStripper {
+    in_comment: bool,
+    in_string: bool,
+}
+
+impl StripLine for LiteralAwareCommentStripper {
+    type Kind = LiteralAwareCommentStrippedSHA256;
+
+    fn strip(&mut self, line: String) -> String {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut cleaned = String::with_capacity(line.len());
+        let mut continued = false;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if self.in_comment {
+                if c == '*' && next == Some('/') {
+                    self.in_comment = false;
+                    i += 1;
+                }
+            } else if self.in_string {
+                cleaned.push(c);
+                match (c, next) {
+                    ('\\', Some(next)) => {
+                        cleaned.push(next);
+                        i += 1;
+                    }
+                    ('\\', None) => continued = true,
+                    ('"', _) => self.in_string = false,
+                    _ => {}
+                }
+            } else {
+                match (c, next) {
+                    ('/', Some('/')) => break,
+                    ('/', Some('*')) => {
+                        self.in_comment = true;
+                        i += 1;
+                    }
+                    ('"', _) => {
+                        self.in_string = true;
+                        cleaned.push(c);
+                    }
+                    ('\'', _) => {
+                        let len = char_literal_len(&chars[i..]).unwrap_or(1);
+                        cleaned.extend(&chars[i..i + len]);
+                        i += len - 1;
+                    }
+                    _ => cleaned.push(c),
+                }
+            }
+            i += 1;
+        }
+
+        // String literals can't span lines without a line continuation.
+        self.in_string &= continued;
+        cleaned
+    }
+}
+
+/// The length of the character literal at the start of `chars`, which starts with `'`,
+/// or `None` if it isn't a character literal.
+fn char_literal_len(chars: &[char]) -> Option<usize> {
+    match chars.get(1)? {
+        // The longest escape sequence is a unicode escape, such as `'\u{10FFFF}'`.
+        '\\' => (3..chars.len().min(11))
+            .find(|&end| chars[end] == '\'')
+            .map(|end| end + 1),
+        '\'' => None,
+        _ => (chars.get(2) == Some(&'\'')).then_some(3),
+    }
+}
+
+/// Writes lines stripped by `S`, trimming each line and dropping blank lines.
+///
+/// Lines are written one at a time as they are provided, so that the same output is produced
+/// whether the content is read from a stream or provided in chunks.
+#[derive(Default)]
+pub(crate) struct StrippedLines<S> {
+    stripper: S,
+    buffered_output_line: String,
+}
 
+impl<S: StripLine> StrippedLines<S> {
+    /// Strip and buffer the next line, which has had its line ending removed.
+    pub(crate) fn line(&mut self, line: String, w: &mut impl Write) -> Result<(), Error> {
         // At this point we know we have a new line coming. If a previous line is buffered and ready to write, do so now.
         // Write it with a trailing newline because we know we'll be writing a following line.
-        if !buffered_output_line.is_empty() {
-            writeln!(w, "{buffered_output_line}")?;
+        if !self.buffered_output_line.is_empty() {
+            writeln!(w, "{}", self.buffered_output_line)?;
         }
 
-        (buffered_output_line, is_multiline_active) = clean_line(line, is_multiline_active);
-        buffered_output_line = buffered_output_line.trim().to_owned();
+        self.buffered_output_line = self.stripper.strip(line).trim().to_owned();
+        Ok(())
     }
 
-    // Now that we're done reading the input stream, if there's a buffered output line write it *without a trailing newline*.
-    write!(w, "{buffered_output_line}")?;
-    Ok(())
+    /// Finish the content, writing any buffered output line *without a trailing newline*.
+    pub(crate) fn finish(self, w: &mut impl Write) -> Result<(), Error> {
+        write!(w, "{}", self.buffered_output_line)?;
+        Ok(())
+    }
+}
+
+/// Collapses whitespace in content provided in chunks; see [`content_normalized_whitespace`].
+#[derive(Default)]
+pub(crate) struct WhitespaceNormalizer {
+    started: bool,
+    pending_space: bool,
+}
+
+impl WhitespaceNormalizer {
+    /// Normalize the next chunk of content, appending the result to `normalized`.
+    pub(crate) fn normalize(&mut self, buf: &[u8], normalized: &mut Vec<u8>) {
+        for &byte in buf {
+            if byte.is_ascii_whitespace() {
+                // Only write the space once the next non-whitespace byte is read, so that trailing whitespace is dropped.
+                self.pending_space = self.started;
+            } else {
+                if self.pending_space {
+                    normalized.push(b' ');
+                    self.pending_space = false;
+                }
+                normalized.push(byte);
+                self.started = true;
+            }
+        }
+    }
 }
 
 /// Part comment stripping, part state machine. Cleans lines of comments based on whether a previous invocation
@@ -222,6 +712,89 @@ return code;
         assert_eq!(normalize_lf(expected), String::from_utf8_lossy(&buf));
     }
 
+    #[test]
+    fn hash_comment_strip_script() {
+        let content = "#!/usr/bin/env python3\n# A placeholder script.\n\nimport sys  # for argv\n\n\ndef main():\n    return len(sys.argv)\n";
+        let expected = "import sys\ndef main():\nreturn len(sys.argv)";
+
+        let mut buf = Vec::new();
+        content_hash_stripped(&mut Cursor::new(content), &mut buf).expect("must fingerprint");
+        assert_eq!(expected, String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn hash_comment_strip_cr() {
+        let content = "key: value # comment\r\n# another comment\r\nother: value\r\n";
+        let expected = "key: value\nother: value";
+
+        let mut buf = Vec::new();
+        content_hash_stripped(&mut Cursor::new(content), &mut buf).expect("must fingerprint");
+        assert_eq!(expected, String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn literal_aware_comment_strip_strings() {
+        let content = r#"printf("http://example.com"); // print it
+s = "a \" /* b"; /* it's "quoted"
+still a comment */ call('"', '\'', '\u{2F}'); // done
+"#;
+        let expected = r#"printf("http://example.com");
+s = "a \" /* b";
+call('"', '\'', '\u{2F}');"#;
+
+        let mut buf = Vec::new();
+        content_literal_aware_stripped(&mut Cursor::new(content), &mut buf)
+            .expect("must fingerprint");
+        assert_eq!(expected, String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn literal_aware_comment_strip_unclosed_literals() {
+        // Lifetimes and digit separators aren't character literals, and strings end at the end of the line
+        // unless continued.
+        let content = "fn f<'a>(x: &'a str) {} // it's\nint n = 1'000; // n\n\"unclosed // kept\nx; // c\n\"continued \\\n// kept\"; // c\n";
+        let expected = "fn f<'a>(x: &'a str) {}\nint n = 1'000;\n\"unclosed // kept\nx;\n\"continued \\\n// kept\";";
+
+        let mut buf = Vec::new();
+        content_literal_aware_stripped(&mut Cursor::new(content), &mut buf)
+            .expect("must fingerprint");
+        assert_eq!(expected, String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn literal_aware_comment_strip_matches_without_literals() {
+        let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+        let expected = include_str!("../testdata/facebook-folly-Version.cpp.stripped");
+
+        let mut buf = Vec::new();
+        content_literal_aware_stripped(&mut Cursor::new(content), &mut buf).expect("must process");
+
+        assert_eq!(normalize_lf(expected), String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn normalize_whitespace_collapses_runs() {
+        let content = "\n\tint main() {\r\n    return  0;\n}\n\n";
+        let expected = "int main() { return 0; }";
+
+        let mut buf = Vec::new();
+        content_normalized_whitespace(&mut Cursor::new(content), &mut buf).expect("must normalize");
+        assert_eq!(expected, String::from_utf8_lossy(&buf));
+    }
+
+    #[test]
+    fn normalize_whitespace_reindented() {
+        let original = "fn main() {\n    println!(\"hello\");\n}\n";
+        let reindented = "fn main() {\r\n\tprintln!(\"hello\");\r\n}";
+
+        let mut a = Vec::new();
+        content_normalized_whitespace(&mut Cursor::new(original), &mut a).expect("must normalize");
+        let mut b = Vec::new();
+        content_normalized_whitespace(&mut Cursor::new(reindented), &mut b)
+            .expect("must normalize");
+        assert_eq!(a, b);
+    }
+
     /// Windows CI checks out CRLF. Normalize it to be LF only.
     /// This function should only be applied to testing values, not responses from the functions being tested.
     fn normalize_lf(input: impl Into<String>) -> String {
@@ -0,0 +1,511 @@
+//! Incremental fingerprinting of content provided in chunks.
+//!
+//! Each kind is defined in terms of reading the content on its own (see the `fingerprint` module).
+//! This module instead computes each kind in a single pass as content is provided,
+//! which suits content that can't be rewound, such as async streams or content being extracted from an archive.
+//! The fingerprints are the same as those computed by reading the content once for each kind.
+
+use std::io::Write;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    fingerprint::{
+        self, CommentStripper, HashCommentStripper, LiteralAwareCommentStripper, StripLine,
+        StrippedLines, WhitespaceNormalizer,
+    },
+    java, merkle, native, ssdeep, tlsh, winnow, Blob, Combined, Error, Fingerprint, KindId,
+    KindSet, Metadata,
+};
+
+/// The number of bytes at the start of the content that are checked to decide whether the content is binary.
+/// See [`crate::fingerprint::content_is_binary`].
+const BINARY_CHECK_LEN: usize = 8000;
+
+/// Computes a [`Combined`] fingerprint from content provided in chunks.
+///
+/// This is useful for callers that already stream the content for another purpose,
+/// such as while extracting it from an archive, since it computes each kind in one pass
+/// and doesn't need to read the content again.
+/// The fingerprints are the same as those computed by [`crate::fingerprint_stream_with`] for the same content and kinds.
+///
+/// ```
+/// # use fingerprint::{CombinedHasher, Error};
+/// # fn main() -> Result<(), Error> {
+/// let mut hasher = CombinedHasher::default();
+/// hasher.update(b"int main() {\r\n")?;
+/// hasher.update(b"  return 0;\r\n}\r\n")?;
+/// let combined = hasher.finalize()?;
+/// assert!(combined.comment_stripped().is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub struct CombinedHasher {
+    /// The start of the content, buffered until enough is provided to decide whether the content is binary.
+    /// Once decided, this is `None`.
+    undecided: Option<Vec<u8>>,
+    is_binary: bool,
+    /// The number of bytes provided so far.
+    size: u64,
+    /// Whether [`Metadata`] is included in the resulting [`Combined`].
+    metadata: bool,
+    crlf: CRLFToLF,
+    /// Scratch space for content after converting line endings.
+    text: Vec<u8>,
+
+    raw: Sha256,
+    byte_exact: Option<Sha256>,
+    merkle: Option<merkle::Hasher>,
+    ssdeep: Option<ssdeep::Hasher>,
+    tlsh: Option<tlsh::Hasher>,
+
+    // Text-only kinds are dropped when the content turns out to be binary,
+    // and line based kinds are dropped when the content turns out not to be valid UTF-8.
+    comment_stripped: Option<LineHasher<CommentStripper>>,
+    hash_comment_stripped: Option<LineHasher<HashCommentStripper>>,
+    literal_aware_comment_stripped: Option<LineHasher<LiteralAwareCommentStripper>>,
+    normalized_whitespace: Option<(WhitespaceNormalizer, Sha256)>,
+    winnowing: Option<winnow::Hasher>,
+
+    /// The content, buffered while it may be a Java class file; class files can only be normalized once complete.
+    /// Dropped as soon as the content can't be a class file.
+    java_class: Option<Vec<u8>>,
+    /// The content, buffered while it may be an ELF or Mach-O binary; like class files, these are normalized once complete.
+    native_sections: Option<Vec<u8>>,
+}
+
+impl CombinedHasher {
+    /// Create a hasher that computes the fingerprint [`crate::Kind`]s in the set.
+    ///
+    /// Kinds that aren't in the set are `None` in the resulting [`Combined`].
+    pub fn new(kinds: KindSet) -> Self {
+        let select = |id: KindId| kinds.contains(id);
+        Self {
+            undecided: Some(Vec::with_capacity(BINARY_CHECK_LEN)),
+            is_binary: false,
+            size: 0,
+            metadata: false,
+            crlf: CRLFToLF::default(),
+            text: Vec::new(),
+            raw: Sha256::new(),
+            byte_exact: select(KindId::ByteExactSHA256).then(Sha256::new),
+            merkle: select(KindId::MerkleSHA256).then(Default::default),
+            ssdeep: select(KindId::RawSsdeep).then(Default::default),
+            tlsh: select(KindId::RawTLSH).then(Default::default),
+            comment_stripped: select(KindId::CommentStrippedSHA256).then(Default::default),
+            hash_comment_stripped: select(KindId::HashCommentStrippedSHA256).then(Default::default),
+            literal_aware_comment_stripped: select(KindId::LiteralAwareCommentStrippedSHA256)
+                .then(Default::default),
+            normalized_whitespace: select(KindId::NormalizedWhitespaceSHA256)
+                .then(Default::default),
+            winnowing: select(KindId::WinnowingMinHash).then(Default::default),
+            java_class: select(KindId::JavaClassSHA256).then(Vec::new),
+            native_sections: select(KindId::NativeSectionsSHA256).then(Vec::new),
+        }
+    }
+
+    /// Also capture [`Metadata`] about the content in the resulting [`Combined`].
+    ///
+    /// Metadata isn't captured by default, since it isn't a fingerprint and consumers
+    /// that serialize the [`Combined`] (such as VSI uploads) would otherwise send it too.
+    pub fn with_metadata(self) -> Self {
+        Self {
+            metadata: true,
+            ..self
+        }
+    }
+
+    /// Provide the next chunk of content.
+    ///
+    /// Chunks may be any size; the fingerprints don't depend on how the content is split up.
+    pub fn update(&mut self, content: &[u8]) -> Result<(), Error> {
+        self.size += content.len() as u64;
+        if let Some(hasher) = &mut self.byte_exact {
+            hasher.update(content);
+        }
+        write_to(&mut self.merkle, content)?;
+        buffer_if_header(
+            &mut self.java_class,
+            content,
+            java::HEADER_SIZE,
+            java::MAX_SIZE,
+            java::is_class_header,
+        );
+        buffer_if_header(
+            &mut self.native_sections,
+            content,
+            native::HEADER_SIZE,
+            native::MAX_SIZE,
+            native::is_native_header,
+        );
+        let Some(undecided) = &mut self.undecided else {
+            return self.feed(content);
+        };
+
+        let (head, rest) = content.split_at(content.len().min(BINARY_CHECK_LEN - undecided.len()));
+        undecided.extend_from_slice(head);
+        if undecided.len() < BINARY_CHECK_LEN {
+            return Ok(());
+        }
+
+        self.decide()?;
+        self.feed(rest)
+    }
+
+    /// Finish the content, returning the fingerprints.
+    pub fn finalize(mut self) -> Result<Combined, Error> {
+        if self.undecided.is_some() {
+            self.decide()?;
+        }
+
+        // A `\r` at the end of the content is dropped; see `crate::stream::CRLFToLF`.
+        // Line based kinds handle this themselves, since they see the original content.
+        let raw = Fingerprint::from_digest(self.raw)?;
+        let byte_exact = self.byte_exact.map(Fingerprint::from_digest).transpose()?;
+        let merkle = self.merkle.map(|hasher| hasher.finish().fingerprint());
+        let ssdeep = self
+            .ssdeep
+            .map(|hasher| Fingerprint::new(Blob(hasher.finish().into_bytes())));
+        let tlsh = self
+            .tlsh
+            .and_then(|hasher| hasher.finish())
+            .map(|digest| Fingerprint::new(Blob(digest.to_vec())));
+        let winnowing = self
+            .winnowing
+            .and_then(|hasher| hasher.finish())
+            .map(|sketch| Fingerprint::new(Blob(sketch)));
+        let comment_stripped = self
+            .comment_stripped
+            .map(LineHasher::finish)
+            .transpose()?
+            .flatten();
+        let hash_comment_stripped = self
+            .hash_comment_stripped
+            .map(LineHasher::finish)
+            .transpose()?
+            .flatten();
+        let literal_aware_comment_stripped = self
+            .literal_aware_comment_stripped
+            .map(LineHasher::finish)
+            .transpose()?
+            .flatten();
+        let normalized_whitespace = self
+            .normalized_whitespace
+            .map(|(_, hasher)| Fingerprint::from_digest(hasher))
+            .transpose()?;
+        let java_class = self
+            .java_class
+            .and_then(|class| java::normalize(&class))
+            .map(|class| fingerprint::hash_java_class(&class))
+            .transpose()?;
+        let native_sections = self
+            .native_sections
+            .and_then(|binary| native::normalize(&binary))
+            .map(|sections| fingerprint::hash_native_sections(&sections))
+            .transpose()?;
+
+        Ok(Combined {
+            raw,
+            comment_stripped,
+            normalized_whitespace,
+            hash_comment_stripped,
+            ssdeep,
+            tlsh,
+            winnowing,
+            java_class,
+            byte_exact,
+            native_sections,
+            merkle,
+            literal_aware_comment_stripped,
+            metadata: self
+                .metadata
+                .then(|| Metadata::new(self.size, self.is_binary)),
+        })
+    }
+
+    /// Decide whether the content is binary from the buffered start of the content, then process that content.
+    fn decide(&mut self) -> Result<(), Error> {
+        let start = self.undecided.take().unwrap_or_default();
+        self.is_binary = start.contains(&0);
+        if self.is_binary {
+            self.comment_stripped = None;
+            self.hash_comment_stripped = None;
+            self.literal_aware_comment_stripped = None;
+            self.normalized_whitespace = None;
+            self.winnowing = None;
+        }
+        self.feed(&start)
+    }
+
+    /// Process content once it has been decided whether the content is binary.
+    fn feed(&mut self, content: &[u8]) -> Result<(), Error> {
+        if self.is_binary {
+            self.raw.update(content);
+            write_to(&mut self.ssdeep, content)?;
+            write_to(&mut self.tlsh, content)?;
+            return Ok(());
+        }
+
+        self.text.clear();
+        self.crlf.convert(content, &mut self.text);
+        self.raw.update(&self.text);
+        write_to(&mut self.ssdeep, &self.text)?;
+        write_to(&mut self.tlsh, &self.text)?;
+        write_to(&mut self.winnowing, &self.text)?;
+
+        if let Some((normalizer, hasher)) = &mut self.normalized_whitespace {
+            let mut normalized = Vec::with_capacity(content.len());
+            normalizer.normalize(content, &mut normalized);
+            hasher.update(&normalized);
+        }
+
+        feed_lines(&mut self.comment_stripped, content)?;
+        feed_lines(&mut self.hash_comment_stripped, content)?;
+        feed_lines(&mut self.literal_aware_comment_stripped, content)
+    }
+}
+
+impl Default for CombinedHasher {
+    /// Create a hasher that computes the default fingerprint [`crate::Kind`]s; see [`KindSet::default`].
+    fn default() -> Self {
+        Self::new(KindSet::default())
+    }
+}
+
+/// Add the content to the buffer, dropping the buffer as soon as the content so far
+/// can't be in the format recognized by `is_header` or is larger than `max_size`.
+fn buffer_if_header(
+    buffer: &mut Option<Vec<u8>>,
+    content: &[u8],
+    header_size: usize,
+    max_size: usize,
+    is_header: fn(&[u8]) -> bool,
+) {
+    if let Some(buffered) = buffer {
+        buffered.extend_from_slice(content);
+        let may_match =
+            buffered.len() <= max_size && (buffered.len() < header_size || is_header(buffered));
+        if !may_match {
+            *buffer = None;
+        }
+    }
+}
+
+/// Write the content to the hasher, if it is computing a kind.
+fn write_to(hasher: &mut Option<impl Write>, content: &[u8]) -> Result<(), Error> {
+    if let Some(hasher) = hasher {
+        hasher.write_all(content)?;
+    }
+    Ok(())
+}
+
+/// Provide the content to the line hasher, dropping it if the content isn't valid UTF-8.
+fn feed_lines<S: StripLine>(
+    hasher: &mut Option<LineHasher<S>>,
+    content: &[u8],
+) -> Result<(), Error> {
+    if let Some(lines) = hasher {
+        if !lines.update(content)? {
+            *hasher = None;
+        }
+    }
+    Ok(())
+}
+
+/// Converts `\r\n` byte pairs to a single `\n` in content provided in chunks.
+///
+/// This matches [`crate::stream::CRLFToLF`], which operates on a stream instead.
+#[derive(Default)]
+struct CRLFToLF {
+    /// Whether the last chunk ended with a `\r`, which depends on the next byte.
+    pending_cr: bool,
+}
+
+impl CRLFToLF {
+    fn convert(&mut self, content: &[u8], converted: &mut Vec<u8>) {
+        for &byte in content {
+            if self.pending_cr && byte != b'\n' {
+                converted.push(b'\r');
+            }
+            self.pending_cr = byte == b'\r';
+            if !self.pending_cr {
+                converted.push(byte);
+            }
+        }
+    }
+}
+
+/// Hashes lines stripped by `S`, splitting lines the same way as [`std::io::BufRead::lines`].
+struct LineHasher<S> {
+    /// The part of the current line provided so far.
+    partial: Vec<u8>,
+    lines: StrippedLines<S>,
+    hasher: Sha256,
+}
+
+impl<S: StripLine> Default for LineHasher<S> {
+    fn default() -> Self {
+        Self {
+            partial: Vec::new(),
+            lines: StrippedLines::default(),
+            hasher: Sha256::new(),
+        }
+    }
+}
+
+impl<S: StripLine> LineHasher<S> {
+    /// Provide the next chunk of content.
+    /// Returns `false` if a line isn't valid UTF-8, in which case this kind can't be computed.
+    fn update(&mut self, content: &[u8]) -> Result<bool, Error> {
+        let mut rest = content;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            self.partial.extend_from_slice(&rest[..end]);
+            rest = &rest[end + 1..];
+
+            if self.partial.last() == Some(&b'\r') {
+                self.partial.pop();
+            }
+            if !self.line()? {
+                return Ok(false);
+            }
+        }
+
+        self.partial.extend_from_slice(rest);
+        Ok(true)
+    }
+
+    /// Finish the content, returning `None` if a line isn't valid UTF-8.
+    fn finish(mut self) -> Result<Option<Fingerprint<S::Kind>>, Error> {
+        // Like `BufRead::lines`, a final line without a line ending is still a line, but an empty one isn't.
+        if !self.partial.is_empty() && !self.line()? {
+            return Ok(None);
+        }
+
+        self.lines.finish(&mut self.hasher)?;
+        Some(Fingerprint::from_digest(self.hasher)).transpose()
+    }
+
+    /// Process the buffered line, returning `false` if it isn't valid UTF-8.
+    fn line(&mut self) -> Result<bool, Error> {
+        let line = std::mem::take(&mut self.partial);
+        match String::from_utf8(line) {
+            Ok(line) => self.lines.line(line, &mut self.hasher).map(|_| true),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn chunked(content: &[u8], size: usize) -> Combined {
+        let mut hasher = CombinedHasher::new(KindSet::all()).with_metadata();
+        for chunk in content.chunks(size) {
+            hasher.update(chunk).expect("must hash");
+        }
+        hasher.finalize().expect("must finalize")
+    }
+
+    /// Fingerprint the content by reading it once for each kind.
+    fn read(content: &[u8]) -> Combined {
+        let stream = || Cursor::new(content);
+        let is_binary = fingerprint::content_is_binary(&mut stream())
+            .expect("must read")
+            .is_binary;
+        Combined {
+            raw: fingerprint::raw(&mut stream()).expect("must fingerprint"),
+            comment_stripped: fingerprint::comment_stripped(&mut stream())
+                .expect("must fingerprint"),
+            normalized_whitespace: fingerprint::normalized_whitespace(&mut stream())
+                .expect("must fingerprint"),
+            hash_comment_stripped: fingerprint::hash_comment_stripped(&mut stream())
+                .expect("must fingerprint"),
+            ssdeep: Some(fingerprint::ssdeep(&mut stream()).expect("must fingerprint")),
+            tlsh: fingerprint::tlsh(&mut stream()).expect("must fingerprint"),
+            winnowing: fingerprint::winnowing(&mut stream()).expect("must fingerprint"),
+            java_class: fingerprint::java_class(&mut stream()).expect("must fingerprint"),
+            byte_exact: Some(fingerprint::byte_exact(&mut stream()).expect("must fingerprint")),
+            native_sections: fingerprint::native_sections(&mut stream()).expect("must fingerprint"),
+            merkle: Some(fingerprint::merkle(&mut stream()).expect("must fingerprint")),
+            literal_aware_comment_stripped: fingerprint::literal_aware_comment_stripped(
+                &mut stream(),
+            )
+            .expect("must fingerprint"),
+            metadata: Some(Metadata::new(content.len() as u64, is_binary)),
+        }
+    }
+
+    fn assert_matches_read(content: &[u8]) {
+        let expected = read(content);
+        for size in [1, 2, 3, 7, 64, BINARY_CHECK_LEN, content.len().max(1)] {
+            assert_eq!(expected, chunked(content, size), "chunk size: {size}");
+        }
+    }
+
+    /// Deterministic content that looks like source text, with comments and mixed line endings.
+    fn text(lines: usize) -> Vec<u8> {
+        (0..lines)
+            .map(|i| match i % 5 {
+                0 => format!("// comment {i}\r\n"),
+                1 => format!("let value_{i} = {i}; /* start\n"),
+                2 => format!("   end */ call({i}); # hash {i}\r\n"),
+                3 => "\n".to_string(),
+                _ => format!("\tvalue\r_{i}\n"),
+            })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    fn matches_read_text() {
+        assert_matches_read(b"");
+        assert_matches_read(b"\r");
+        assert_matches_read(b"\r\n");
+        assert_matches_read(b"hello\r\nworld\r");
+        assert_matches_read(b"no trailing newline");
+        assert_matches_read(&text(10));
+        assert_matches_read(&text(2000));
+    }
+
+    #[test]
+    fn matches_read_binary() {
+        let mut content = text(2000);
+        content[100] = 0;
+        assert_matches_read(&content);
+
+        // The zero byte is past the portion checked for binary content.
+        let mut content = text(2000);
+        content[BINARY_CHECK_LEN + 1] = 0;
+        assert_matches_read(&content);
+    }
+
+    #[test]
+    fn matches_read_invalid_utf8() {
+        let mut content = text(2000);
+        content[BINARY_CHECK_LEN + 100] = 0xFF;
+        assert_matches_read(&content);
+    }
+
+    #[test]
+    fn matches_read_native() {
+        assert_matches_read(include_bytes!("../testdata/libanswer.elf"));
+    }
+
+    #[test]
+    fn selected_kinds() {
+        let content = text(100);
+        let mut hasher = CombinedHasher::new(KindSet::raw());
+        hasher.update(&content).expect("must hash");
+        let combined = hasher.finalize().expect("must finalize");
+
+        let expected = read(&content);
+        assert_eq!(expected.raw(), combined.raw());
+        assert_eq!(&None, combined.comment_stripped());
+        assert_eq!(&None, combined.ssdeep());
+    }
+}
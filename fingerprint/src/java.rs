@@ -0,0 +1,467 @@
+//! Normalization of Java class files, so that classes compiled from the same source by different compilers match.
+//!
+//! Compilers record metadata in class files that doesn't affect how the class behaves:
+//! the class file version, the name of the source file, line numbers, and the names of local variables.
+//! Normalization zeroes the version, removes the attributes that hold the rest of this metadata,
+//! and replaces the constant pool entries that only those attributes referenced with empty strings.
+//! Constant pool entries are replaced rather than removed so that the indexes of other entries don't change.
+//!
+//! The layout of class files is described in chapter 4 of the Java Virtual Machine Specification.
+
+use std::collections::HashSet;
+
+/// The magic number at the start of every class file.
+const MAGIC: [u8; 4] = [0xCA, 0xFE, 0xBA, 0xBE];
+
+/// The major version of the oldest class file format.
+///
+/// Mach-O universal binaries share the class file magic number, but are followed by the number of architectures
+/// in the binary; this is always far smaller than any class file major version, so it tells the two apart.
+const MIN_MAJOR_VERSION: u16 = 45;
+
+/// The number of bytes at the start of the content needed by [`is_class_header`].
+pub(crate) const HEADER_SIZE: usize = 8;
+
+/// Content larger than this isn't treated as a class file.
+/// Class files are limited in practice to a few megabytes by the limits on the constant pool and method sizes.
+pub(crate) const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Attributes that only contain debug metadata, and are removed.
+const DEBUG_ATTRIBUTES: [&[u8]; 5] = [
+    b"SourceFile",
+    b"SourceDebugExtension",
+    b"LineNumberTable",
+    b"LocalVariableTable",
+    b"LocalVariableTypeTable",
+];
+
+/// Constant pool tags, from the Java Virtual Machine Specification section 4.4.
+mod tag {
+    pub const UTF8: u8 = 1;
+    pub const INTEGER: u8 = 3;
+    pub const FLOAT: u8 = 4;
+    pub const LONG: u8 = 5;
+    pub const DOUBLE: u8 = 6;
+    pub const CLASS: u8 = 7;
+    pub const STRING: u8 = 8;
+    pub const FIELD_REF: u8 = 9;
+    pub const METHOD_REF: u8 = 10;
+    pub const INTERFACE_METHOD_REF: u8 = 11;
+    pub const NAME_AND_TYPE: u8 = 12;
+    pub const METHOD_HANDLE: u8 = 15;
+    pub const METHOD_TYPE: u8 = 16;
+    pub const DYNAMIC: u8 = 17;
+    pub const INVOKE_DYNAMIC: u8 = 18;
+    pub const MODULE: u8 = 19;
+    pub const PACKAGE: u8 = 20;
+}
+
+/// Whether the start of the content looks like a class file.
+/// Requires at least [`HEADER_SIZE`] bytes.
+pub(crate) fn is_class_header(start: &[u8]) -> bool {
+    match start {
+        [a, b, c, d, _, _, major @ ..] if [*a, *b, *c, *d] == MAGIC && major.len() >= 2 => {
+            u16::from_be_bytes([major[0], major[1]]) >= MIN_MAJOR_VERSION
+        }
+        _ => false,
+    }
+}
+
+/// Normalize the class file, returning `None` if the content isn't a valid class file.
+pub(crate) fn normalize(class: &[u8]) -> Option<Vec<u8>> {
+    if class.len() > MAX_SIZE || !is_class_header(class) {
+        return None;
+    }
+
+    let mut reader = Reader::new(class);
+    reader.bytes(HEADER_SIZE)?;
+    let pool = ConstantPool::parse(&mut reader)?;
+
+    // Parse the rest of the class, tracking which constant pool entries are referenced by what's kept.
+    let mut class = Class {
+        pool: &pool,
+        kept: pool.referenced.clone(),
+        stripped: HashSet::new(),
+    };
+    let mut body = Vec::new();
+    body.extend_from_slice(reader.bytes(6)?); // Access flags, this class, and super class.
+    let interfaces = reader.u16()?;
+    body.extend_from_slice(&interfaces.to_be_bytes());
+    body.extend_from_slice(reader.bytes(usize::from(interfaces) * 2)?);
+    for _ in 0..2 {
+        // Fields, then methods; both have the same layout.
+        let count = reader.u16()?;
+        body.extend_from_slice(&count.to_be_bytes());
+        for _ in 0..count {
+            body.extend_from_slice(reader.bytes(2)?); // Access flags.
+            for _ in 0..2 {
+                // Name, then descriptor.
+                let index = reader.u16()?;
+                class.kept.insert(index);
+                body.extend_from_slice(&index.to_be_bytes());
+            }
+            class.attributes(&mut reader, &mut body)?;
+        }
+    }
+    class.attributes(&mut reader, &mut body)?;
+    if !reader.is_empty() {
+        return None;
+    }
+
+    let mut normalized = Vec::with_capacity(HEADER_SIZE + pool.size + body.len());
+    normalized.extend_from_slice(&MAGIC);
+    normalized.extend_from_slice(&[0; 4]); // Minor and major version.
+    normalized.extend_from_slice(&pool.count.to_be_bytes());
+    for (index, entry) in pool.entries() {
+        if class.stripped.contains(&index) && !class.kept.contains(&index) {
+            normalized.extend_from_slice(&[tag::UTF8, 0, 0]);
+        } else {
+            normalized.extend_from_slice(entry);
+        }
+    }
+    normalized.extend_from_slice(&body);
+    Some(normalized)
+}
+
+/// Reads big endian values from a class file.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None;
+        }
+        let (read, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Some(read)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+/// The constant pool of a class file.
+struct ConstantPool<'a> {
+    /// The constant pool count, which is one more than the highest index.
+    count: u16,
+    /// The raw bytes of each entry, including the tag, by index.
+    /// Indexes that aren't usable (index 0, and the index after each long or double) are `None`.
+    raw: Vec<Option<&'a [u8]>>,
+    /// The contents of each UTF-8 entry, by index.
+    utf8: Vec<Option<&'a [u8]>>,
+    /// The indexes referenced by other entries in the pool.
+    referenced: HashSet<u16>,
+    /// The total size of the entries in bytes.
+    size: usize,
+}
+
+impl<'a> ConstantPool<'a> {
+    fn parse(reader: &mut Reader<'a>) -> Option<Self> {
+        let count = reader.u16()?;
+        let mut pool = Self {
+            count,
+            raw: vec![None; usize::from(count)],
+            utf8: vec![None; usize::from(count)],
+            referenced: HashSet::new(),
+            size: 0,
+        };
+
+        let mut index = 1;
+        while index < usize::from(count) {
+            let start = reader.buf;
+            let tag = reader.u8()?;
+            let mut slots = 1;
+            match tag {
+                tag::UTF8 => {
+                    let len = reader.u16()?;
+                    pool.utf8[index] = Some(reader.bytes(usize::from(len))?);
+                }
+                tag::INTEGER | tag::FLOAT => {
+                    reader.bytes(4)?;
+                }
+                tag::LONG | tag::DOUBLE => {
+                    reader.bytes(8)?;
+                    slots = 2;
+                }
+                tag::CLASS | tag::STRING | tag::METHOD_TYPE | tag::MODULE | tag::PACKAGE => {
+                    pool.referenced.insert(reader.u16()?);
+                }
+                tag::FIELD_REF
+                | tag::METHOD_REF
+                | tag::INTERFACE_METHOD_REF
+                | tag::NAME_AND_TYPE
+                | tag::DYNAMIC
+                | tag::INVOKE_DYNAMIC => {
+                    pool.referenced.insert(reader.u16()?);
+                    pool.referenced.insert(reader.u16()?);
+                }
+                tag::METHOD_HANDLE => {
+                    reader.u8()?;
+                    pool.referenced.insert(reader.u16()?);
+                }
+                _ => return None,
+            }
+
+            let len = start.len() - reader.buf.len();
+            pool.raw[index] = Some(&start[..len]);
+            pool.size += len;
+            index += slots;
+        }
+
+        // A long or double in the last slot claims an index past the end of the pool.
+        (index == usize::from(count)).then_some(pool)
+    }
+
+    /// The contents of the UTF-8 entry at the index, if the entry exists and is UTF-8.
+    fn utf8(&self, index: u16) -> Option<&'a [u8]> {
+        self.utf8.get(usize::from(index)).copied().flatten()
+    }
+
+    /// Iterate over the usable entries in the pool, with their index.
+    fn entries(&self) -> impl Iterator<Item = (u16, &'a [u8])> + '_ {
+        self.raw
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((u16::try_from(index).ok()?, (*entry)?)))
+    }
+}
+
+/// Tracks the constant pool entries referenced while normalizing the body of a class file.
+struct Class<'p, 'a> {
+    pool: &'p ConstantPool<'a>,
+    /// Entries referenced by parts of the class that are kept.
+    kept: HashSet<u16>,
+    /// Entries referenced by the debug attributes that are removed.
+    stripped: HashSet<u16>,
+}
+
+impl Class<'_, '_> {
+    /// Copy a table of attributes to the output, removing debug attributes.
+    fn attributes(&mut self, reader: &mut Reader<'_>, out: &mut Vec<u8>) -> Option<()> {
+        let count = reader.u16()?;
+        let mut kept = Vec::new();
+        let mut kept_count: u16 = 0;
+        for _ in 0..count {
+            let name_index = reader.u16()?;
+            let len = reader.u32()?;
+            let info = reader.bytes(usize::try_from(len).ok()?)?;
+            let name = self.pool.utf8(name_index)?;
+
+            if DEBUG_ATTRIBUTES.contains(&name) {
+                self.stripped.insert(name_index);
+                self.strip(name, info)?;
+                continue;
+            }
+
+            self.kept.insert(name_index);
+            kept_count += 1;
+            kept.extend_from_slice(&name_index.to_be_bytes());
+            if name == b"Code" {
+                let code = self.code(info)?;
+                kept.extend_from_slice(&u32::try_from(code.len()).ok()?.to_be_bytes());
+                kept.extend_from_slice(&code);
+            } else {
+                kept.extend_from_slice(&len.to_be_bytes());
+                kept.extend_from_slice(info);
+            }
+        }
+
+        out.extend_from_slice(&kept_count.to_be_bytes());
+        out.extend_from_slice(&kept);
+        Some(())
+    }
+
+    /// Normalize the body of a `Code` attribute, which has attributes of its own.
+    fn code(&mut self, info: &[u8]) -> Option<Vec<u8>> {
+        let mut reader = Reader::new(info);
+        let mut out = Vec::with_capacity(info.len());
+        out.extend_from_slice(reader.bytes(4)?); // Max stack and max locals.
+        let code_len = reader.u32()?;
+        out.extend_from_slice(&code_len.to_be_bytes());
+        out.extend_from_slice(reader.bytes(usize::try_from(code_len).ok()?)?);
+        let exceptions = reader.u16()?;
+        out.extend_from_slice(&exceptions.to_be_bytes());
+        out.extend_from_slice(reader.bytes(usize::from(exceptions) * 8)?);
+        self.attributes(&mut reader, &mut out)?;
+        reader.is_empty().then_some(out)
+    }
+
+    /// Record the constant pool entries referenced by a debug attribute that is removed.
+    fn strip(&mut self, name: &[u8], info: &[u8]) -> Option<()> {
+        let mut reader = Reader::new(info);
+        match name {
+            b"SourceFile" => {
+                self.stripped.insert(reader.u16()?);
+            }
+            b"LocalVariableTable" | b"LocalVariableTypeTable" => {
+                let count = reader.u16()?;
+                for _ in 0..count {
+                    reader.bytes(4)?; // Start and length of the range in which the variable is live.
+                    self.stripped.insert(reader.u16()?); // Name.
+                    self.stripped.insert(reader.u16()?); // Descriptor or signature.
+                    reader.bytes(2)?; // Local variable index.
+                }
+            }
+            _ => {}
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use super::*;
+
+    /// Builds class files for tests.
+    #[derive(Default)]
+    struct Builder {
+        pool: Vec<Vec<u8>>,
+    }
+
+    impl Builder {
+        fn utf8(&mut self, s: &str) -> u16 {
+            let mut entry = vec![tag::UTF8];
+            entry.extend_from_slice(&(s.len() as u16).to_be_bytes());
+            entry.extend_from_slice(s.as_bytes());
+            self.push(entry)
+        }
+
+        fn class(&mut self, name: &str) -> u16 {
+            let name = self.utf8(name);
+            let mut entry = vec![tag::CLASS];
+            entry.extend_from_slice(&name.to_be_bytes());
+            self.push(entry)
+        }
+
+        fn push(&mut self, entry: Vec<u8>) -> u16 {
+            self.pool.push(entry);
+            self.pool.len() as u16
+        }
+
+        fn attribute(&mut self, name: &str, info: &[u8]) -> Vec<u8> {
+            let mut attribute = self.utf8(name).to_be_bytes().to_vec();
+            attribute.extend_from_slice(&(info.len() as u32).to_be_bytes());
+            attribute.extend_from_slice(info);
+            attribute
+        }
+
+        /// Build a class with a single method, compiled with the given version and debug metadata.
+        fn build(version: u16, source: &str, line: u16, local: &str) -> Vec<u8> {
+            let mut b = Builder::default();
+            let this = b.class("Example");
+            let super_class = b.class("java/lang/Object");
+            let method_name = b.utf8("run");
+            let method_descriptor = b.utf8("()V");
+
+            let mut lines = 1u16.to_be_bytes().to_vec();
+            lines.extend_from_slice(&[0, 0]);
+            lines.extend_from_slice(&line.to_be_bytes());
+            let lines = b.attribute("LineNumberTable", &lines);
+
+            let local_name = b.utf8(local);
+            let local_descriptor = b.utf8("I");
+            let mut locals = 1u16.to_be_bytes().to_vec();
+            locals.extend_from_slice(&[0, 0, 0, 1]);
+            locals.extend_from_slice(&local_name.to_be_bytes());
+            locals.extend_from_slice(&local_descriptor.to_be_bytes());
+            locals.extend_from_slice(&[0, 1]);
+            let locals = b.attribute("LocalVariableTable", &locals);
+
+            let mut code = vec![0, 1, 0, 2]; // Max stack and max locals.
+            code.extend_from_slice(&1u32.to_be_bytes());
+            code.push(0xB1); // `return`
+            code.extend_from_slice(&[0, 0]); // Exception table.
+            code.extend_from_slice(&2u16.to_be_bytes());
+            code.extend_from_slice(&lines);
+            code.extend_from_slice(&locals);
+            let code = b.attribute("Code", &code);
+
+            let source = b.utf8(source);
+            let source = b.attribute("SourceFile", &source.to_be_bytes());
+
+            let mut class = MAGIC.to_vec();
+            class.extend_from_slice(&[0, 0]);
+            class.extend_from_slice(&version.to_be_bytes());
+            class.extend_from_slice(&(b.pool.len() as u16 + 1).to_be_bytes());
+            for entry in &b.pool {
+                class.extend_from_slice(entry);
+            }
+            class.extend_from_slice(&[0x00, 0x21]); // Public, super.
+            class.extend_from_slice(&this.to_be_bytes());
+            class.extend_from_slice(&super_class.to_be_bytes());
+            class.extend_from_slice(&[0, 0]); // Interfaces.
+            class.extend_from_slice(&[0, 0]); // Fields.
+            class.extend_from_slice(&[0, 1]); // Methods.
+            class.extend_from_slice(&[0x00, 0x01]); // Public.
+            class.extend_from_slice(&method_name.to_be_bytes());
+            class.extend_from_slice(&method_descriptor.to_be_bytes());
+            class.extend_from_slice(&[0, 1]);
+            class.extend_from_slice(&code);
+            class.extend_from_slice(&[0, 1]);
+            class.extend_from_slice(&source);
+            class
+        }
+    }
+
+    #[test]
+    fn header() {
+        let class = Builder::build(52, "Example.java", 3, "x");
+        assert!(is_class_header(&class));
+
+        // A Mach-O universal binary with two architectures.
+        assert!(!is_class_header(&[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 2]));
+        assert!(!is_class_header(b"\x7FELF\x02\x01\x01\x00"));
+        assert!(!is_class_header(&MAGIC));
+    }
+
+    #[test]
+    fn ignores_compiler_metadata() {
+        let a = normalize(&Builder::build(52, "Example.java", 3, "x")).expect("must normalize");
+        let b = normalize(&Builder::build(61, "Other.java", 10, "y")).expect("must normalize");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn removes_debug_metadata() {
+        let normalized =
+            normalize(&Builder::build(52, "Example.java", 3, "counter")).expect("must normalize");
+        let contains = |s: &[u8]| normalized.windows(s.len()).any(|w| w == s);
+        for name in DEBUG_ATTRIBUTES {
+            assert!(!contains(name), "{}", String::from_utf8_lossy(name));
+        }
+        assert!(!contains(b"Example.java"));
+        assert!(!contains(b"counter"));
+        assert!(contains(b"java/lang/Object"));
+        assert!(contains(b"Code"));
+    }
+
+    #[test]
+    fn invalid() {
+        let class = Builder::build(52, "Example.java", 3, "x");
+        assert_eq!(None, normalize(&class[..class.len() - 1]));
+        assert_eq!(None, normalize(&[class.as_slice(), &[0]].concat()));
+        assert_eq!(None, normalize(b"not a class file"));
+    }
+}
@@ -11,6 +11,41 @@
 //! which have specific rules about how to compute the fingerprint, and specific text identifiers.
 //!
 //! For more information, refer to the documentation for the types below.
+//!
+//! ## Usage
+//!
+//! - [`fingerprint`] and [`fingerprint_stream`] compute a [`Combined`] fingerprint with the default kinds
+//!   ([`RawSHA256`] and [`CommentStrippedSHA256`]);
+//!   [`fingerprint_with`] and [`fingerprint_stream_with`] compute the kinds in a [`KindSet`], such as [`KindSet::all`].
+//! - [`CombinedHasher`] computes a [`Combined`] fingerprint from content provided in chunks,
+//!   optionally capturing [`Metadata`] about the content.
+//! - [`process`] and [`process_stream`] return a [`Processed`], which also contains the content processed for each kind;
+//!   this is useful to explain why two files do or don't share a fingerprint.
+//! - [`compare::compare`] classifies how two files are related from their [`Combined`] fingerprints.
+//! - [`merkle::tree`] hashes content in fixed size chunks, retaining the hash of each chunk;
+//!   the root of the tree is the [`MerkleSHA256`] fingerprint.
+//! - [`cache::Cache`] reuses the fingerprints of files that are unchanged since they were last fingerprinted.
+//! - With the `async` feature enabled, `fingerprint_async` and `fingerprint_stream_async` fingerprint async streams;
+//!   `fingerprint_async_with` and `fingerprint_stream_async_with` compute only the kinds in a [`KindSet`].
+//! - With the `walk` feature enabled, `walk::Walker` walks a directory, expanding archives, and fingerprints each file in parallel.
+//!
+//! ## WebAssembly
+//!
+//! The crate builds for `wasm32-unknown-unknown`, computing the same fingerprints from in-memory content
+//! (for example with [`fingerprint_stream`] or [`CombinedHasher`]).
+//! Functionality that requires a filesystem isn't available on that target:
+//! the functions that open a file by path, the `cache` module, and the `walk` feature.
+//! The `sha2-asm` feature isn't supported on that target either.
+//!
+//! ## Feature flags
+//!
+//! No features are enabled by default.
+//!
+//! Name | Description | Kind
+//! ---|---|---
+//! `async` | Enables fingerprinting async streams, built on tokio | API
+//! `walk` | Enables walking directories (expanding archives) and fingerprinting each file | API
+//! `sha2-asm` | Enables hardware acceleration for SHA2 | Performance
 
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
@@ -19,23 +54,38 @@
 
 use std::{
     fmt::Display,
-    fs::File,
-    io::{self, BufRead, BufReader, Seek},
+    io::{self, Cursor, Read},
     marker::PhantomData,
-    path::Path,
+    str::FromStr,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs::File, io::BufReader, path::Path};
 
 use crate::fingerprint::BinaryCheck;
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 #[cfg(test)]
 use typed_builder::TypedBuilder;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
+pub mod compare;
 mod fingerprint;
+mod hasher;
+mod java;
+pub mod merkle;
+mod native;
 pub mod serialize;
+mod ssdeep;
 mod stream;
+mod tlsh;
+#[cfg(all(feature = "walk", not(target_arch = "wasm32")))]
+pub mod walk;
+mod winnow;
+
+pub use hasher::CombinedHasher;
 
 /// Errors that may be encountered during fingerprinting.
 #[derive(Debug, Error)]
@@ -45,6 +95,24 @@ pub enum Error {
     /// This error may be retried, but if it fails multiple times it's generally not recoverable.
     #[error("i/o error: {0}")]
     IO(#[from] io::Error),
+
+    /// The text representation of a fingerprint kind was not recognized by this version of this crate.
+    #[error("unknown fingerprint kind: {0}")]
+    UnknownKind(String),
+
+    /// Fingerprints encoded with [`serialize::binary`] could not be decoded.
+    #[error("invalid encoded fingerprint: {0}")]
+    InvalidEncoding(String),
+
+    /// An error occurred while walking a directory or expanding an archive.
+    #[cfg(all(feature = "walk", not(target_arch = "wasm32")))]
+    #[error("walk")]
+    Walk(#[from] archive::Error),
+
+    /// An error occurred while fingerprinting a file discovered while walking a directory.
+    #[cfg(all(feature = "walk", not(target_arch = "wasm32")))]
+    #[error("fingerprint {0:?}")]
+    WalkEntry(std::path::PathBuf, #[source] Box<Error>),
 }
 
 /// Fingerprint kinds MUST maintain exact implementation compatibility; once the algorithm for a given kind
@@ -65,28 +133,237 @@ pub enum Error {
 ///
 /// This trait is sealed, indicating nothing outside this module may implement it.
 ///
-/// ### Future work
+/// ### Serialization
 ///
-/// The current implementation of `Kind` causes an issue when we want to actually send kind information
-/// across a serialization boundary, because `Kind`s aren't concrete and therefore aren't
-/// generally serializable.
+/// `Kind`s aren't concrete, so they can't be sent across a serialization boundary directly;
+/// for example the `FinalizeRevision` and `CheckRevision` methods in the VSI Cloud Store
+/// send and receive the list of kinds used to fingerprint a set of files.
 ///
-/// Specifically, this is an issue for `FinalizeRevision` and `CheckRevision` methods in the VSI Cloud Store,
-/// where it's not simple to send a list of `Kind`s used to fingerprint a set of files,
-/// and it's not simple to then retreive that list from the API.
+/// Each `Kind` is identified at runtime by a [`KindId`], which is serialized as the canonical text representation
+/// of the kind. Use [`KindId`] rather than calling `to_string` on kinds to build or compare those lists.
+pub trait Kind: private::Sealed {
+    /// The runtime identifier for this kind.
+    const ID: KindId;
+}
+
+/// The runtime identifier for a fingerprint [`Kind`].
 ///
-/// Instead, for `FinalizeRevision`, clients are forced to:
-/// - Know what kinds of fingerprints are possible, separately.
-/// - Manually call `.to_string` on those kinds to get a list of kinds used.
-/// - Send them as opaque strings.
-/// And for `CheckRevision`, clients are forced to:
-/// - Manually compare the API result (which is a set of opaque strings) against known kinds, using the `to_string` method.
-/// And the server is required to treat all this as opaque strings.
+/// Identifiers are serialized as, and can be parsed from, the canonical text representation of their kind
+/// (the same text that the kind's `Display` implementation outputs).
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+#[non_exhaustive]
+pub enum KindId {
+    /// Identifies [`RawSHA256`].
+    RawSHA256,
+    /// Identifies [`CommentStrippedSHA256`].
+    CommentStrippedSHA256,
+    /// Identifies [`NormalizedWhitespaceSHA256`].
+    NormalizedWhitespaceSHA256,
+    /// Identifies [`RawSsdeep`].
+    RawSsdeep,
+    /// Identifies [`RawTLSH`].
+    RawTLSH,
+    /// Identifies [`WinnowingMinHash`].
+    WinnowingMinHash,
+    /// Identifies [`HashCommentStrippedSHA256`].
+    HashCommentStrippedSHA256,
+    /// Identifies [`JavaClassSHA256`].
+    JavaClassSHA256,
+    /// Identifies [`ByteExactSHA256`].
+    ByteExactSHA256,
+    /// Identifies [`NativeSectionsSHA256`].
+    NativeSectionsSHA256,
+    /// Identifies [`MerkleSHA256`].
+    MerkleSHA256,
+    /// Identifies [`LiteralAwareCommentStrippedSHA256`].
+    LiteralAwareCommentStrippedSHA256,
+}
+
+impl KindId {
+    const ALL: [KindId; 12] = [
+        KindId::RawSHA256,
+        KindId::CommentStrippedSHA256,
+        KindId::NormalizedWhitespaceSHA256,
+        KindId::RawSsdeep,
+        KindId::RawTLSH,
+        KindId::WinnowingMinHash,
+        KindId::HashCommentStrippedSHA256,
+        KindId::JavaClassSHA256,
+        KindId::ByteExactSHA256,
+        KindId::NativeSectionsSHA256,
+        KindId::MerkleSHA256,
+        KindId::LiteralAwareCommentStrippedSHA256,
+    ];
+
+    /// Iterate over the identifiers for all kinds supported by this version of this crate.
+    pub fn all() -> impl Iterator<Item = KindId> {
+        Self::ALL.into_iter()
+    }
+
+    /// A short, human readable description of how fingerprints of this kind are derived.
+    ///
+    /// Unlike the canonical text representation, descriptions may be reworded in later versions of this crate.
+    pub fn description(&self) -> &'static str {
+        match self {
+            KindId::RawSHA256 => "SHA256 of the file, with CRLF line endings converted to LF if the file is text",
+            KindId::CommentStrippedSHA256 => "SHA256 of a text file after removing C-style comments and blank lines",
+            KindId::NormalizedWhitespaceSHA256 => "SHA256 of a text file after collapsing each run of whitespace to a single space",
+            KindId::RawSsdeep => "ssdeep fuzzy hash of the same content as the SHA256 fingerprint",
+            KindId::RawTLSH => "TLSH locality sensitive hash of the same content as the SHA256 fingerprint",
+            KindId::WinnowingMinHash => "MinHash sketch of the k-grams of a text file, ignoring whitespace",
+            KindId::HashCommentStrippedSHA256 => "SHA256 of a text file after removing #-style comments and blank lines",
+            KindId::JavaClassSHA256 => "SHA256 of a Java class file after removing metadata recorded by the compiler",
+            KindId::ByteExactSHA256 => "SHA256 of the exact bytes of the file",
+            KindId::NativeSectionsSHA256 => "SHA256 of the code and data sections of an ELF or Mach-O binary",
+            KindId::MerkleSHA256 => "Merkle tree root of the SHA256 hashes of fixed size chunks of the exact bytes of the file",
+            KindId::LiteralAwareCommentStrippedSHA256 => "SHA256 of a text file after removing C-style comments outside of string and character literals, and blank lines",
+        }
+    }
+
+    /// Whether fingerprints of this kind may be derived for binary files.
+    ///
+    /// Kinds that don't apply to binary content (such as [`CommentStrippedSHA256`]) are never derived
+    /// for files detected as binary. Kinds that do may still be limited to particular formats,
+    /// such as [`JavaClassSHA256`].
+    pub fn applies_to_binary(&self) -> bool {
+        match self {
+            KindId::RawSHA256
+            | KindId::RawSsdeep
+            | KindId::RawTLSH
+            | KindId::JavaClassSHA256
+            | KindId::ByteExactSHA256
+            | KindId::NativeSectionsSHA256
+            | KindId::MerkleSHA256 => true,
+            KindId::CommentStrippedSHA256
+            | KindId::NormalizedWhitespaceSHA256
+            | KindId::WinnowingMinHash
+            | KindId::HashCommentStrippedSHA256
+            | KindId::LiteralAwareCommentStrippedSHA256 => false,
+        }
+    }
+}
+
+/// A set of fingerprint [`Kind`]s to compute.
 ///
-/// To make this less error prone, this is all handled in this library under the `serialize` module,
-/// and it works for now so it's not a massive problem. But if we have ideas for how to improve this for the future,
-/// we should do them.
-pub trait Kind: private::Sealed {}
+/// [`RawSHA256`] is always in the set, since it is derived for every file
+/// and services assume every file has one.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct KindSet(u32);
+
+impl KindSet {
+    /// The set of all kinds supported by this version of this crate.
+    pub fn all() -> Self {
+        KindId::all().collect()
+    }
+
+    /// The set containing only [`RawSHA256`].
+    pub fn raw() -> Self {
+        Self(Self::bit(KindId::RawSHA256))
+    }
+
+    /// The set containing [`RawSHA256`] and [`CommentStrippedSHA256`],
+    /// the kinds that existed prior to this library being created.
+    pub fn legacy() -> Self {
+        Self::raw().with(KindId::CommentStrippedSHA256)
+    }
+
+    /// Add the kind to the set.
+    pub fn with(self, id: KindId) -> Self {
+        Self(self.0 | Self::bit(id))
+    }
+
+    /// Remove the kind from the set.
+    /// [`RawSHA256`] can't be removed; attempting to remove it has no effect.
+    pub fn without(self, id: KindId) -> Self {
+        if id == KindId::RawSHA256 {
+            return self;
+        }
+        Self(self.0 & !Self::bit(id))
+    }
+
+    /// Whether the kind is in the set.
+    pub fn contains(&self, id: KindId) -> bool {
+        self.0 & Self::bit(id) != 0
+    }
+
+    /// Iterate over the kinds in the set.
+    pub fn iter(&self) -> impl Iterator<Item = KindId> + '_ {
+        KindId::all().filter(|&id| self.contains(id))
+    }
+
+    fn bit(id: KindId) -> u32 {
+        1 << id as u32
+    }
+}
+
+impl Default for KindSet {
+    /// The default set is [`KindSet::legacy`], as computed by [`fingerprint`].
+    ///
+    /// The other kinds are more expensive to compute, so they are opt-in.
+    fn default() -> Self {
+        Self::legacy()
+    }
+}
+
+impl FromIterator<KindId> for KindSet {
+    fn from_iter<T: IntoIterator<Item = KindId>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::raw(), Self::with)
+    }
+}
+
+impl std::fmt::Debug for KindSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Display for KindId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KindId::RawSHA256 => RawSHA256.fmt(f),
+            KindId::CommentStrippedSHA256 => CommentStrippedSHA256.fmt(f),
+            KindId::NormalizedWhitespaceSHA256 => NormalizedWhitespaceSHA256.fmt(f),
+            KindId::RawSsdeep => RawSsdeep.fmt(f),
+            KindId::RawTLSH => RawTLSH.fmt(f),
+            KindId::WinnowingMinHash => WinnowingMinHash.fmt(f),
+            KindId::HashCommentStrippedSHA256 => HashCommentStrippedSHA256.fmt(f),
+            KindId::JavaClassSHA256 => JavaClassSHA256.fmt(f),
+            KindId::ByteExactSHA256 => ByteExactSHA256.fmt(f),
+            KindId::NativeSectionsSHA256 => NativeSectionsSHA256.fmt(f),
+            KindId::MerkleSHA256 => MerkleSHA256.fmt(f),
+            KindId::LiteralAwareCommentStrippedSHA256 => LiteralAwareCommentStrippedSHA256.fmt(f),
+        }
+    }
+}
+
+impl FromStr for KindId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .find(|id| id.to_string() == s)
+            .ok_or_else(|| Error::UnknownKind(s.to_string()))
+    }
+}
+
+impl Serialize for KindId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KindId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
 
 /// Represents a fingerprint derived by hashing the raw contents of a file with the SHA256 algorithm.
 ///
@@ -101,7 +378,9 @@ pub trait Kind: private::Sealed {}
 pub struct RawSHA256;
 
 impl private::Sealed for RawSHA256 {}
-impl Kind for RawSHA256 {}
+impl Kind for RawSHA256 {
+    const ID: KindId = KindId::RawSHA256;
+}
 
 impl Display for RawSHA256 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -109,6 +388,28 @@ impl Display for RawSHA256 {
     }
 }
 
+/// Represents a fingerprint derived by hashing the exact bytes of a file with the SHA256 algorithm.
+///
+/// Unlike `RawSHA256`, line endings in text files are not normalized,
+/// so this matches the output of tools like `sha256sum` run against the file on disk.
+/// This means that the same text file checked out on Windows and *nix typically has different fingerprints of this kind;
+/// prefer `RawSHA256` unless the hash of the literal bytes is required.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct ByteExactSHA256;
+
+impl private::Sealed for ByteExactSHA256 {}
+impl Kind for ByteExactSHA256 {
+    const ID: KindId = KindId::ByteExactSHA256;
+}
+
+impl Display for ByteExactSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "byte_exact:sha_256")
+    }
+}
+
 /// Represents a fingerprint derived by hashing the contents of a file with the SHA256 algorithm
 /// after performing basic C-style comment stripping.
 ///
@@ -117,7 +418,9 @@ impl Display for RawSHA256 {
 pub struct CommentStrippedSHA256;
 
 impl private::Sealed for CommentStrippedSHA256 {}
-impl Kind for CommentStrippedSHA256 {}
+impl Kind for CommentStrippedSHA256 {
+    const ID: KindId = KindId::CommentStrippedSHA256;
+}
 
 impl Display for CommentStrippedSHA256 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -125,6 +428,218 @@ impl Display for CommentStrippedSHA256 {
     }
 }
 
+/// Represents a fingerprint derived by hashing the contents of a file with the SHA256 algorithm
+/// after performing basic `#`-style comment stripping.
+///
+/// This is the equivalent of `CommentStrippedSHA256` for scripting languages and configuration formats
+/// that use `#` for line comments, such as Python, Ruby, shell, and YAML.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct HashCommentStrippedSHA256;
+
+impl private::Sealed for HashCommentStrippedSHA256 {}
+impl Kind for HashCommentStrippedSHA256 {
+    const ID: KindId = KindId::HashCommentStrippedSHA256;
+}
+
+impl Display for HashCommentStrippedSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hash_comment_stripped:sha_256")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the contents of a file with the SHA256 algorithm
+/// after performing C-style comment stripping that respects string and character literals.
+///
+/// `CommentStrippedSHA256` removes anything that looks like a comment, even inside a string literal,
+/// so a line like `printf("http://example.com");` is truncated to `printf("http:`.
+/// This kind leaves literals intact, so only actual comments are removed.
+/// `CommentStrippedSHA256` is unchanged, so that its fingerprints remain comparable with those already crawled.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct LiteralAwareCommentStrippedSHA256;
+
+impl private::Sealed for LiteralAwareCommentStrippedSHA256 {}
+impl Kind for LiteralAwareCommentStrippedSHA256 {
+    const ID: KindId = KindId::LiteralAwareCommentStrippedSHA256;
+}
+
+impl Display for LiteralAwareCommentStrippedSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "literal_aware_comment_stripped:sha_256")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the contents of a file with the SHA256 algorithm
+/// after collapsing each run of whitespace to a single space.
+///
+/// This matches copies of a text file that have been re-indented or re-wrapped,
+/// which `CommentStrippedSHA256` does not since it preserves whitespace within lines.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct NormalizedWhitespaceSHA256;
+
+impl private::Sealed for NormalizedWhitespaceSHA256 {}
+impl Kind for NormalizedWhitespaceSHA256 {
+    const ID: KindId = KindId::NormalizedWhitespaceSHA256;
+}
+
+impl Display for NormalizedWhitespaceSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "normalized_whitespace:sha_256")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the raw contents of a file with context triggered piecewise hashing,
+/// in the format used by `ssdeep`.
+///
+/// Unlike the other kinds, two of these fingerprints can be compared for similarity rather than only equality
+/// with [`Fingerprint::similarity`]. This matches near-duplicate files, such as vendored sources that have been patched.
+///
+/// As with `RawSHA256`, this is derived for all kinds of files, and line endings in text files are normalized first.
+/// The content of the fingerprint is the text of the `ssdeep` signature.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct RawSsdeep;
+
+impl private::Sealed for RawSsdeep {}
+impl Kind for RawSsdeep {
+    const ID: KindId = KindId::RawSsdeep;
+}
+
+impl Display for RawSsdeep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ssdeep")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the raw contents of a file with TLSH,
+/// a locality sensitive hash.
+///
+/// Two of these fingerprints can be compared with [`Fingerprint::distance`] to cluster similar files.
+/// This is intended for binaries such as stripped shared or static libraries,
+/// which often differ only in small regions like compiler metadata.
+///
+/// As with `RawSHA256`, this is derived for all kinds of files, and line endings in text files are normalized first.
+/// However TLSH requires at least 50 bytes of sufficiently varied content, so it is not derived for all files.
+/// The content of the fingerprint is the binary TLSH digest, so its hex encoding is the TLSH digest
+/// (without the `T1` version prefix used by some TLSH implementations).
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct RawTLSH;
+
+impl private::Sealed for RawTLSH {}
+impl Kind for RawTLSH {
+    const ID: KindId = KindId::RawTLSH;
+}
+
+impl Display for RawTLSH {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tlsh")
+    }
+}
+
+/// Represents a fingerprint derived by sketching the contents of a text file with winnowing and MinHash.
+///
+/// Each k-gram of the text (ignoring whitespace) is hashed, winnowing selects representative hashes,
+/// and the smallest of those are kept as a compact sketch of the file.
+/// Two of these fingerprints can be compared with [`Fingerprint::jaccard`] to estimate how much of their text is shared,
+/// which detects files that have been partially copied into other files.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct WinnowingMinHash;
+
+impl private::Sealed for WinnowingMinHash {}
+impl Kind for WinnowingMinHash {
+    const ID: KindId = KindId::WinnowingMinHash;
+}
+
+impl Display for WinnowingMinHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "winnowing:minhash")
+    }
+}
+
+/// Represents a fingerprint derived by hashing a Java class file with the SHA256 algorithm
+/// after removing metadata recorded by the compiler.
+///
+/// The class file version is zeroed, the attributes recording the source file name, line numbers,
+/// and local variable names are removed, and constant pool entries referenced only by those attributes
+/// are replaced with empty strings.
+/// This matches classes compiled from the same source by different compilers (or different versions of the same compiler),
+/// which `RawSHA256` does not.
+///
+/// JARs are archives, so their classes are fingerprinted individually when the JAR is expanded.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct JavaClassSHA256;
+
+impl private::Sealed for JavaClassSHA256 {}
+impl Kind for JavaClassSHA256 {
+    const ID: KindId = KindId::JavaClassSHA256;
+}
+
+impl Display for JavaClassSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "java_class:sha_256")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the code and data sections of a native binary (ELF or Mach-O)
+/// with the SHA256 algorithm, ignoring metadata recorded by the linker.
+///
+/// Only the sections loaded when the binary runs are hashed, along with their names and sizes;
+/// build IDs (ELF notes), Mach-O UUIDs and timestamps, code signatures, symbol tables, and debug sections are ignored.
+/// This matches libraries rebuilt from the same source with the same toolchain and flags,
+/// or stripped of their debug information, which `RawSHA256` does not.
+///
+/// Mach-O universal binaries contain binaries for several architectures, and are not fingerprinted with this kind.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct NativeSectionsSHA256;
+
+impl private::Sealed for NativeSectionsSHA256 {}
+impl Kind for NativeSectionsSHA256 {
+    const ID: KindId = KindId::NativeSectionsSHA256;
+}
+
+impl Display for NativeSectionsSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "native_sections:sha_256")
+    }
+}
+
+/// Represents a fingerprint derived by hashing the exact bytes of a file in fixed size chunks with the SHA256 algorithm,
+/// and combining the hashes of the chunks into a Merkle tree.
+///
+/// Like `ByteExactSHA256`, line endings are not normalized, and two files have the same fingerprint of this kind
+/// only if they have the same bytes. Unlike it, the chunks of a very large file can be hashed in parallel,
+/// and the hashes of the chunks can be compared to locate the parts of two files that differ;
+/// see the [`merkle`] module for details.
+///
+/// This fingerprint kind has been finalized and may not change (except to fix a bug).
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct MerkleSHA256;
+
+impl private::Sealed for MerkleSHA256 {}
+impl Kind for MerkleSHA256 {
+    const ID: KindId = KindId::MerkleSHA256;
+}
+
+impl Display for MerkleSHA256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "merkle:sha_256")
+    }
+}
+
 /// An array of bytes representing a fingerprint's content.
 ///
 /// Must be encoded as hex to be compatible with the FOSSA backend.
@@ -207,6 +722,11 @@ impl<K> Fingerprint<K>
 where
     K: Kind,
 {
+    /// The identifier for the kind of this fingerprint.
+    pub fn kind_id(&self) -> KindId {
+        K::ID
+    }
+
     fn new(content: Blob) -> Self {
         Self {
             content,
@@ -229,6 +749,15 @@ impl Hashable for Fingerprint<RawSHA256> {
     }
 }
 
+impl Hashable for Fingerprint<ByteExactSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = ByteExactSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
 impl Hashable for Fingerprint<CommentStrippedSHA256> {
     /// Create a new hash from a fingerprint kind and a fingerprint
     fn to_hash(&self) -> Vec<u8> {
@@ -238,6 +767,122 @@ impl Hashable for Fingerprint<CommentStrippedSHA256> {
     }
 }
 
+impl Hashable for Fingerprint<HashCommentStrippedSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = HashCommentStrippedSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<LiteralAwareCommentStrippedSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = LiteralAwareCommentStrippedSHA256
+            .to_string()
+            .as_bytes()
+            .to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<NormalizedWhitespaceSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = NormalizedWhitespaceSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<RawSsdeep> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = RawSsdeep.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Fingerprint<RawSsdeep> {
+    /// Compare this fingerprint to another, returning a score from 0 (no meaningful similarity) to 100 (identical or nearly so).
+    ///
+    /// Small files produce short signatures, which can't be compared precisely, so their scores are capped.
+    /// Files whose sizes are very different can't be compared at all, and score 0.
+    pub fn similarity(&self, other: &Self) -> u8 {
+        ssdeep::compare(self.content.as_bytes(), other.content.as_bytes())
+    }
+}
+
+impl Hashable for Fingerprint<RawTLSH> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = RawTLSH.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Fingerprint<RawTLSH> {
+    /// Compute the distance between this fingerprint and another.
+    ///
+    /// A distance of 0 means the files are identical or nearly so; the distance grows without a fixed bound
+    /// as the files diverge, with distances above a few hundred indicating unrelated files.
+    /// Returns `None` if either fingerprint does not contain a valid TLSH digest.
+    pub fn distance(&self, other: &Self) -> Option<u32> {
+        tlsh::distance(self.content.as_bytes(), other.content.as_bytes())
+    }
+}
+
+impl Hashable for Fingerprint<WinnowingMinHash> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = WinnowingMinHash.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<JavaClassSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = JavaClassSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<NativeSectionsSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = NativeSectionsSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Hashable for Fingerprint<MerkleSHA256> {
+    /// Create a new hash from a fingerprint kind and a fingerprint
+    fn to_hash(&self) -> Vec<u8> {
+        let mut bs = MerkleSHA256.to_string().as_bytes().to_vec();
+        bs.extend_from_slice(self.content.as_bytes());
+        Sha256::digest(&bs).to_vec()
+    }
+}
+
+impl Fingerprint<WinnowingMinHash> {
+    /// Estimate the Jaccard similarity between the text of this fingerprint and another:
+    /// roughly, the fraction of their combined text that they share.
+    ///
+    /// Returns a value from 0.0 (nothing in common) to 1.0 (the same text, or nearly so),
+    /// or `None` if either fingerprint does not contain a valid sketch.
+    pub fn jaccard(&self, other: &Self) -> Option<f64> {
+        winnow::jaccard(self.content.as_bytes(), other.content.as_bytes())
+    }
+}
+
 impl<K> Display for Fingerprint<K>
 where
     K: Kind,
@@ -259,13 +904,95 @@ where
 #[getset(get = "pub")]
 pub struct Combined {
     /// This fingerprint is derived regardless of the kind of file.
-    // Important: if this struct is changed, update `serialize::kind::kinds_evaluated` to reflect the change.
-    // `kinds_evaluated` may be replaced by a macro in the future.
+    // Important: if a kind is added to this struct, add it to `KindId` as well;
+    // `serialize::kind::kinds_evaluated` reports the kinds in `KindId`.
     #[serde(rename = "sha_256")]
     raw: Fingerprint<RawSHA256>,
     /// The fingerprint derived when the file is a text file, and any C-style comments have been removed.
     #[serde(rename = "comment_stripped:sha_256")]
     comment_stripped: Option<Fingerprint<CommentStrippedSHA256>>,
+    /// The fingerprint derived when the file is a text file, and each run of whitespace has been collapsed to a single space.
+    #[serde(
+        rename = "normalized_whitespace:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    normalized_whitespace: Option<Fingerprint<NormalizedWhitespaceSHA256>>,
+    /// The fingerprint derived when the file is a text file, and any `#`-style comments have been removed.
+    #[serde(
+        rename = "hash_comment_stripped:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    hash_comment_stripped: Option<Fingerprint<HashCommentStrippedSHA256>>,
+    /// This fingerprint is derived regardless of the kind of file.
+    /// It is only missing from fingerprints created before this kind was added.
+    #[serde(rename = "ssdeep", default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, builder(default))]
+    ssdeep: Option<Fingerprint<RawSsdeep>>,
+    /// The fingerprint derived when the file has enough varied content for TLSH to produce a digest.
+    #[serde(rename = "tlsh", default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, builder(default))]
+    tlsh: Option<Fingerprint<RawTLSH>>,
+    /// The fingerprint derived when the file is a text file, sketching the k-grams of its text.
+    #[serde(
+        rename = "winnowing:minhash",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    winnowing: Option<Fingerprint<WinnowingMinHash>>,
+    /// The fingerprint derived when the file is a Java class file, after removing compiler metadata.
+    #[serde(
+        rename = "java_class:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    java_class: Option<Fingerprint<JavaClassSHA256>>,
+    /// This fingerprint is derived regardless of the kind of file, from the exact bytes of the file.
+    /// It is only missing from fingerprints created before this kind was added, or when it isn't selected.
+    #[serde(
+        rename = "byte_exact:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    byte_exact: Option<Fingerprint<ByteExactSHA256>>,
+    /// The fingerprint derived when the file is an ELF or Mach-O binary, from its code and data sections.
+    #[serde(
+        rename = "native_sections:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    native_sections: Option<Fingerprint<NativeSectionsSHA256>>,
+    /// This fingerprint is derived regardless of the kind of file, from the exact bytes of the file.
+    /// It is only missing from fingerprints created before this kind was added, or when it isn't selected.
+    #[serde(
+        rename = "merkle:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    merkle: Option<Fingerprint<MerkleSHA256>>,
+    /// The fingerprint derived when the file is a text file, and any C-style comments outside of string
+    /// and character literals have been removed.
+    #[serde(
+        rename = "literal_aware_comment_stripped:sha_256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(test, builder(default))]
+    literal_aware_comment_stripped: Option<Fingerprint<LiteralAwareCommentStrippedSHA256>>,
+    /// Facts about the file that was fingerprinted, captured while fingerprinting it.
+    /// This is not a fingerprint, so it isn't included in [`Combined::to_hashes`] or the `Display` output.
+    /// It is only present when requested with [`CombinedHasher::with_metadata`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, builder(default))]
+    metadata: Option<Metadata>,
 }
 
 impl Combined {
@@ -275,119 +1002,411 @@ impl Combined {
     /// For `Optional` fingerprints, a `None` value is dropped from the
     /// resulting vector.
     pub fn to_hashes(&self) -> Vec<Vec<u8>> {
-        let raw = self.raw.to_hash();
+        let mut hashes = vec![self.raw.to_hash()];
         if let Some(stripped) = &self.comment_stripped {
-            vec![raw, stripped.to_hash()]
-        } else {
-            vec![raw]
+            hashes.push(stripped.to_hash());
         }
+        if let Some(normalized) = &self.normalized_whitespace {
+            hashes.push(normalized.to_hash());
+        }
+        if let Some(stripped) = &self.hash_comment_stripped {
+            hashes.push(stripped.to_hash());
+        }
+        if let Some(ssdeep) = &self.ssdeep {
+            hashes.push(ssdeep.to_hash());
+        }
+        if let Some(tlsh) = &self.tlsh {
+            hashes.push(tlsh.to_hash());
+        }
+        if let Some(winnowing) = &self.winnowing {
+            hashes.push(winnowing.to_hash());
+        }
+        if let Some(java_class) = &self.java_class {
+            hashes.push(java_class.to_hash());
+        }
+        if let Some(byte_exact) = &self.byte_exact {
+            hashes.push(byte_exact.to_hash());
+        }
+        if let Some(native_sections) = &self.native_sections {
+            hashes.push(native_sections.to_hash());
+        }
+        if let Some(merkle) = &self.merkle {
+            hashes.push(merkle.to_hash());
+        }
+        if let Some(stripped) = &self.literal_aware_comment_stripped {
+            hashes.push(stripped.to_hash());
+        }
+        hashes
     }
 }
 
 impl Display for Combined {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", RawSHA256, self.raw)?;
         if let Some(comment_stripped) = &self.comment_stripped {
+            write!(f, "; {}({})", CommentStrippedSHA256, comment_stripped)?;
+        }
+        if let Some(normalized_whitespace) = &self.normalized_whitespace {
+            write!(
+                f,
+                "; {}({})",
+                NormalizedWhitespaceSHA256, normalized_whitespace
+            )?;
+        }
+        if let Some(hash_comment_stripped) = &self.hash_comment_stripped {
             write!(
                 f,
-                "{}({}); {}({})",
-                RawSHA256, self.raw, CommentStrippedSHA256, comment_stripped,
-            )
-        } else {
-            write!(f, "{}({})", RawSHA256, self.raw())
+                "; {}({})",
+                HashCommentStrippedSHA256, hash_comment_stripped
+            )?;
+        }
+        if let Some(ssdeep) = &self.ssdeep {
+            write!(f, "; {}({})", RawSsdeep, ssdeep)?;
+        }
+        if let Some(tlsh) = &self.tlsh {
+            write!(f, "; {}({})", RawTLSH, tlsh)?;
+        }
+        if let Some(winnowing) = &self.winnowing {
+            write!(f, "; {}({})", WinnowingMinHash, winnowing)?;
+        }
+        if let Some(java_class) = &self.java_class {
+            write!(f, "; {}({})", JavaClassSHA256, java_class)?;
         }
+        if let Some(byte_exact) = &self.byte_exact {
+            write!(f, "; {}({})", ByteExactSHA256, byte_exact)?;
+        }
+        if let Some(native_sections) = &self.native_sections {
+            write!(f, "; {}({})", NativeSectionsSHA256, native_sections)?;
+        }
+        if let Some(merkle) = &self.merkle {
+            write!(f, "; {}({})", MerkleSHA256, merkle)?;
+        }
+        if let Some(stripped) = &self.literal_aware_comment_stripped {
+            write!(f, "; {}({})", LiteralAwareCommentStrippedSHA256, stripped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Facts about a file captured while fingerprinting it, carried alongside the fingerprints in a [`Combined`].
+///
+/// This saves consumers from reading the file's metadata again after fingerprinting it.
+/// It is only captured when requested with [`CombinedHasher::with_metadata`].
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, CopyGetters, Serialize, Deserialize)]
+#[getset(get_copy = "pub")]
+pub struct Metadata {
+    /// The size of the content in bytes, before any processing.
+    size: u64,
+
+    /// Whether the content was detected to be binary, in which case text-only kinds aren't computed.
+    is_binary: bool,
+}
+
+impl Metadata {
+    /// Create an instance from the size of the content in bytes, and whether it was detected to be binary.
+    pub fn new(size: u64, is_binary: bool) -> Self {
+        Self { size, is_binary }
     }
 }
 
-/// Fingerprint the provided file with all fingerprint [`Kind`]s.
+/// Fingerprint the provided file with the default fingerprint [`Kind`]s; see [`KindSet::default`].
+#[cfg(not(target_arch = "wasm32"))]
 pub fn fingerprint(path: &Path) -> Result<Combined, Error> {
-    let mut file = BufReader::new(File::open(path)?);
-    fingerprint_stream(&mut file)
+    fingerprint_with(KindSet::default(), path)
+}
+
+/// Fingerprint the provided stream (typically a file handle) with the default fingerprint [`Kind`]s;
+/// see [`KindSet::default`].
+///
+/// The stream is read once, from its current position, computing each kind as it is read.
+pub fn fingerprint_stream<R: Read>(stream: &mut R) -> Result<Combined, Error> {
+    fingerprint_stream_with(KindSet::default(), stream)
+}
+
+/// Fingerprint the provided file with the fingerprint [`Kind`]s in the set.
+///
+/// Kinds that aren't in the set aren't computed, and are `None` in the resulting [`Combined`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn fingerprint_with(kinds: KindSet, path: &Path) -> Result<Combined, Error> {
+    let mut file = File::open(path)?;
+    fingerprint_stream_with(kinds, &mut file)
 }
 
-/// Fingerprint the provided stream (typically a file handle) with all fingerprint [`Kind`]s.
-pub fn fingerprint_stream<R: BufRead + Send + Seek + 'static>(
+/// Fingerprint the provided stream (typically a file handle) with the fingerprint [`Kind`]s in the set.
+///
+/// The stream is read once, from its current position, computing each kind as it is read.
+/// Kinds that aren't in the set aren't computed, and are `None` in the resulting [`Combined`].
+pub fn fingerprint_stream_with<R: Read>(kinds: KindSet, stream: &mut R) -> Result<Combined, Error> {
+    let mut hasher = CombinedHasher::new(kinds);
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buf[..read])?,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    hasher.finalize()
+}
+
+/// Fingerprint the provided file with the default fingerprint [`Kind`]s, reading it asynchronously.
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub async fn fingerprint_async(path: &Path) -> Result<Combined, Error> {
+    fingerprint_async_with(KindSet::default(), path).await
+}
+
+/// Fingerprint the provided file with the fingerprint [`Kind`]s in the set, reading it asynchronously.
+///
+/// Kinds that aren't in the set aren't computed, and are `None` in the resulting [`Combined`].
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub async fn fingerprint_async_with(kinds: KindSet, path: &Path) -> Result<Combined, Error> {
+    let mut file = tokio::fs::File::open(path).await?;
+    fingerprint_stream_async_with(kinds, &mut file).await
+}
+
+/// Fingerprint the provided async stream (such as an async file handle or a network stream)
+/// with the default fingerprint [`Kind`]s.
+///
+/// Like [`fingerprint_stream`], the stream is read once, from its current position, computing each kind as it is read.
+/// The fingerprints are the same as those computed by [`fingerprint_stream`] for the same content.
+///
+/// Hashing happens on the calling task between reads, in chunks of at most 64 KiB.
+#[cfg(feature = "async")]
+pub async fn fingerprint_stream_async<R: tokio::io::AsyncRead + Unpin>(
     stream: &mut R,
 ) -> Result<Combined, Error> {
-    let raw = fingerprint::raw(stream)?;
-    stream.seek(io::SeekFrom::Start(0))?;
-    let comment_stripped = fingerprint::comment_stripped(stream)?;
-    Ok(Combined {
-        raw,
-        comment_stripped,
-    })
+    fingerprint_stream_async_with(KindSet::default(), stream).await
 }
 
-/// The result of eagerly running all fingerprint [`Kind`]s on some given content.
+/// Fingerprint the provided async stream (such as an async file handle or a network stream)
+/// with the fingerprint [`Kind`]s in the set.
+///
+/// Like [`fingerprint_stream_with`], the stream is read once, from its current position, computing each kind as it is read.
+/// Kinds that aren't in the set aren't computed, and are `None` in the resulting [`Combined`].
+#[cfg(feature = "async")]
+pub async fn fingerprint_stream_async_with<R: tokio::io::AsyncRead + Unpin>(
+    kinds: KindSet,
+    stream: &mut R,
+) -> Result<Combined, Error> {
+    use tokio::io::AsyncReadExt;
+
+    let mut hasher = CombinedHasher::new(kinds);
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        match stream.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buf[..read])?,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    hasher.finalize()
+}
+
+/// The size of the chunks in which streams are read for fingerprinting.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A fingerprint, along with the content that was processed to compute it.
 ///
-/// This structure is equivalent to [`Combined`], but each fingerprint is a tuple of the computed fingerprint
-/// plus the content that was processed to make the fingerprint.
+/// The content is decoded as UTF-8, with invalid sequences replaced by `U+FFFD`;
+/// for binary files this means the content is only an approximation of what was hashed.
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ProcessedFingerprint<K: Kind> {
+    /// The computed fingerprint.
+    fingerprint: Fingerprint<K>,
+
+    /// The content from which the fingerprint was computed, after any processing required by the kind.
+    content: String,
+}
+
+impl<K: Kind> ProcessedFingerprint<K> {
+    fn new(fingerprint: Fingerprint<K>, content: Vec<u8>) -> Self {
+        Self {
+            fingerprint,
+            content: String::from_utf8_lossy(&content).to_string(),
+        }
+    }
+
+    /// Consume the instance, returning the fingerprint and the content.
+    pub fn into_parts(self) -> (Fingerprint<K>, String) {
+        (self.fingerprint, self.content)
+    }
+}
+
+/// The result of running all fingerprint [`Kind`]s on some given content,
+/// retaining the content that was processed for each kind.
+///
+/// This structure is equivalent to [`Combined`], but each fingerprint is accompanied by the content
+/// that was processed to make the fingerprint; this is useful to explain why two files have
+/// (or don't have) the same fingerprint.
 #[derive(Clone, Hash, Eq, PartialEq, Debug, Getters, Serialize, Deserialize)]
 #[getset(get = "pub")]
 pub struct Processed {
     /// Whether the file was detected to be binary.
     detected_as_binary: bool,
 
+    /// The size of the file in bytes.
+    size: u64,
+
     /// This fingerprint is derived regardless of the kind of file.
-    raw: (Fingerprint<RawSHA256>, String),
+    raw: ProcessedFingerprint<RawSHA256>,
 
     /// The fingerprint derived when the file is a text file, and any C-style comments have been removed.
-    comment_stripped: Option<(Fingerprint<CommentStrippedSHA256>, String)>,
+    comment_stripped: Option<ProcessedFingerprint<CommentStrippedSHA256>>,
+
+    /// The fingerprint derived when the file is a text file, and each run of whitespace has been collapsed to a single space.
+    normalized_whitespace: Option<ProcessedFingerprint<NormalizedWhitespaceSHA256>>,
+
+    /// The fingerprint derived when the file is a text file, and any `#`-style comments have been removed.
+    hash_comment_stripped: Option<ProcessedFingerprint<HashCommentStrippedSHA256>>,
+
+    /// This fingerprint is derived regardless of the kind of file, from the same content as `raw`.
+    ssdeep: ProcessedFingerprint<RawSsdeep>,
+
+    /// The fingerprint derived when the file has enough varied content for TLSH to produce a digest,
+    /// from the same content as `raw`.
+    tlsh: Option<ProcessedFingerprint<RawTLSH>>,
+
+    /// The fingerprint derived when the file is a text file, from the same content as `raw`;
+    /// whitespace in the content is ignored when sketching.
+    winnowing: Option<ProcessedFingerprint<WinnowingMinHash>>,
+
+    /// The fingerprint derived when the file is a Java class file, from the class after removing compiler metadata.
+    java_class: Option<ProcessedFingerprint<JavaClassSHA256>>,
+
+    /// This fingerprint is derived regardless of the kind of file, from the exact bytes of the file.
+    byte_exact: ProcessedFingerprint<ByteExactSHA256>,
+
+    /// The fingerprint derived when the file is an ELF or Mach-O binary, from its code and data sections.
+    native_sections: Option<ProcessedFingerprint<NativeSectionsSHA256>>,
+
+    /// This fingerprint is derived regardless of the kind of file, from the same content as `byte_exact`.
+    merkle: ProcessedFingerprint<MerkleSHA256>,
+
+    /// The fingerprint derived when the file is a text file, and any C-style comments outside of string
+    /// and character literals have been removed.
+    literal_aware_comment_stripped: Option<ProcessedFingerprint<LiteralAwareCommentStrippedSHA256>>,
 }
 
-/// Process the provided file with all fingerprint [`Kind`]s.
+impl Processed {
+    /// The fingerprints, without the processed content.
+    /// This is the same as the result of [`fingerprint_with`] with [`KindSet::all`] for the same content,
+    /// since every kind is processed.
+    pub fn to_combined(&self) -> Combined {
+        Combined {
+            raw: self.raw.fingerprint.clone(),
+            comment_stripped: fingerprint_of(&self.comment_stripped),
+            normalized_whitespace: fingerprint_of(&self.normalized_whitespace),
+            hash_comment_stripped: fingerprint_of(&self.hash_comment_stripped),
+            ssdeep: Some(self.ssdeep.fingerprint.clone()),
+            tlsh: fingerprint_of(&self.tlsh),
+            winnowing: fingerprint_of(&self.winnowing),
+            java_class: fingerprint_of(&self.java_class),
+            byte_exact: Some(self.byte_exact.fingerprint.clone()),
+            native_sections: fingerprint_of(&self.native_sections),
+            merkle: Some(self.merkle.fingerprint.clone()),
+            literal_aware_comment_stripped: fingerprint_of(&self.literal_aware_comment_stripped),
+            metadata: None,
+        }
+    }
+}
+
+fn fingerprint_of<K: Kind + Clone>(
+    processed: &Option<ProcessedFingerprint<K>>,
+) -> Option<Fingerprint<K>> {
+    processed
+        .as_ref()
+        .map(|processed| processed.fingerprint.clone())
+}
+
+/// Process the provided file with all fingerprint [`Kind`]s, retaining the content processed for each kind.
 ///
 /// # Performance
 ///
-/// This function is intended to be used for debugging;
-/// it outputs much more data and is much more expensive in terms of IO
-/// as compared to the standard fingerprint functions.
+/// The file is read into memory in full, and the processed content for each kind is retained,
+/// so prefer [`fingerprint`] unless the processed content is needed.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn process(path: &Path) -> Result<Processed, Error> {
     let mut file = BufReader::new(File::open(path)?);
     process_stream(&mut file)
 }
 
-/// Process the provided stream (typically a file handle) with all fingerprint [`Kind`]s.
+/// Process the provided stream (typically a file handle) with all fingerprint [`Kind`]s,
+/// retaining the content processed for each kind.
+///
+/// The stream is read once, from its current position.
 ///
 /// # Performance
 ///
-/// This function is intended to be used for debugging;
-/// it outputs much more data and is much more expensive in terms of IO
-/// as compared to the standard fingerprint functions.
-pub fn process_stream<R: BufRead + Send + Seek + 'static>(
-    stream: &mut R,
-) -> Result<Processed, Error> {
-    let BinaryCheck { is_binary, .. } = fingerprint::content_is_binary(stream)?;
-    stream.seek(io::SeekFrom::Start(0))?;
-
-    let raw = fingerprint::raw(stream)?;
-    stream.seek(io::SeekFrom::Start(0))?;
+/// The stream is read into memory in full, and the processed content for each kind is retained,
+/// so prefer [`fingerprint_stream`] unless the processed content is needed.
+pub fn process_stream<R: Read>(stream: &mut R) -> Result<Processed, Error> {
+    let mut content = Vec::new();
+    stream.read_to_end(&mut content)?;
+    let read = || Cursor::new(content.as_slice());
 
+    let BinaryCheck { is_binary, .. } = fingerprint::content_is_binary(&mut read())?;
     let mut raw_content = Vec::new();
     if is_binary {
-        fingerprint::content_binary(stream, &mut raw_content)?;
+        fingerprint::content_binary(&mut read(), &mut raw_content)?;
     } else {
-        fingerprint::content_text(stream, &mut raw_content)?;
+        fingerprint::content_text(&mut read(), &mut raw_content)?;
     }
-    stream.seek(io::SeekFrom::Start(0))?;
 
-    let comment_stripped = fingerprint::comment_stripped(stream)?;
-    stream.seek(io::SeekFrom::Start(0))?;
+    let comment_stripped = fingerprint::comment_stripped(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_stripped))
+        .transpose()?;
+    let normalized_whitespace = fingerprint::normalized_whitespace(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_normalized_whitespace))
+        .transpose()?;
+    let hash_comment_stripped = fingerprint::hash_comment_stripped(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_hash_stripped))
+        .transpose()?;
+    let literal_aware_comment_stripped = fingerprint::literal_aware_comment_stripped(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_literal_aware_stripped))
+        .transpose()?;
+
+    let ssdeep = fingerprint::ssdeep(&mut read())?;
+    let tlsh = fingerprint::tlsh(&mut read())?;
+    let winnowing = fingerprint::winnowing(&mut read())?;
+    let byte_exact = fingerprint::byte_exact(&mut read())?;
+    let merkle = fingerprint::merkle(&mut read())?;
+    let java_class = fingerprint::java_class(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_java_class))
+        .transpose()?;
+    let native_sections = fingerprint::native_sections(&mut read())?
+        .map(|fp| processed_with(fp, &content, fingerprint::content_native_sections))
+        .transpose()?;
 
     Ok(Processed {
         detected_as_binary: is_binary,
-        raw: (raw, lossy_string(raw_content)),
-        comment_stripped: if let Some(comment_stripped) = comment_stripped {
-            let mut stripped_content = Vec::new();
-            fingerprint::content_stripped(stream, &mut stripped_content)?;
-            Some((comment_stripped, lossy_string(stripped_content)))
-        } else {
-            None
-        },
+        size: content.len() as u64,
+        winnowing: winnowing.map(|fp| ProcessedFingerprint::new(fp, raw_content.clone())),
+        tlsh: tlsh.map(|fp| ProcessedFingerprint::new(fp, raw_content.clone())),
+        ssdeep: ProcessedFingerprint::new(ssdeep, raw_content.clone()),
+        raw: ProcessedFingerprint::new(fingerprint::raw(&mut read())?, raw_content),
+        comment_stripped,
+        normalized_whitespace,
+        hash_comment_stripped,
+        literal_aware_comment_stripped,
+        java_class,
+        native_sections,
+        merkle: ProcessedFingerprint::new(merkle, content.clone()),
+        byte_exact: ProcessedFingerprint::new(byte_exact, content),
     })
 }
 
-fn lossy_string(v: Vec<u8>) -> String {
-    String::from_utf8_lossy(&v).to_string()
+/// Pair the fingerprint with the content produced by running `process` on the original content.
+fn processed_with<'a, K: Kind>(
+    fingerprint: Fingerprint<K>,
+    content: &'a [u8],
+    process: impl FnOnce(&mut Cursor<&'a [u8]>, &mut Vec<u8>) -> Result<(), Error>,
+) -> Result<ProcessedFingerprint<K>, Error> {
+    let mut processed = Vec::new();
+    process(&mut Cursor::new(content), &mut processed)?;
+    Ok(ProcessedFingerprint::new(fingerprint, processed))
 }
 
 #[cfg(test)]
@@ -0,0 +1,230 @@
+//! Chunked hashing of content, combining the hash of each chunk into a Merkle tree.
+//!
+//! The content is split into chunks of [`CHUNK_SIZE`] bytes (the last chunk may be shorter),
+//! and each chunk is hashed on its own with [`leaf`]. Since chunks are independent, very large files
+//! can be hashed in parallel, and two [`Tree`]s for similar content can be compared chunk by chunk
+//! (see [`Tree::changed_chunks`]) to locate the parts of the content that differ, such as corrupted regions.
+//!
+//! The tree is built from the chunk digests as described in RFC 6962 section 2.1:
+//! leaves are hashed with a `0x00` prefix and interior nodes with a `0x01` prefix, so that they can't be confused,
+//! and the leaves are split at the largest power of two smaller than their count.
+//! Empty content is a single empty chunk.
+//!
+//! The root of the tree is the [`crate::MerkleSHA256`] fingerprint.
+//!
+//! ```
+//! # use fingerprint::{merkle::{self, Tree, CHUNK_SIZE}, Error};
+//! # fn main() -> Result<(), Error> {
+//! let content = vec![7; CHUNK_SIZE * 3];
+//!
+//! // Each chunk may be hashed on a different thread (for example with rayon's `par_chunks`).
+//! let leaves = content.chunks(CHUNK_SIZE).map(merkle::leaf).collect();
+//! let tree = Tree::from_leaves(leaves);
+//!
+//! assert_eq!(tree, merkle::tree(&mut content.as_slice())?);
+//! assert_eq!(3, tree.leaves().len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Read, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Blob, Error, Fingerprint, MerkleSHA256};
+
+/// The size of each chunk, except the last one, which may be shorter.
+///
+/// This is fixed: changing it would change every [`crate::MerkleSHA256`] fingerprint.
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The SHA256 digest of a chunk or of a node in the tree.
+pub type Digest256 = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Hash a single chunk of content, producing a leaf of the tree.
+pub fn leaf(chunk: &[u8]) -> Digest256 {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(chunk);
+    hasher.finalize().into()
+}
+
+/// Hash two child nodes, producing their parent.
+fn node(left: &Digest256, right: &Digest256) -> Digest256 {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Read the stream to its end, hashing it into a tree.
+pub fn tree(stream: &mut impl Read) -> Result<Tree, Error> {
+    let mut hasher = Hasher::default();
+    io::copy(stream, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+/// The digests of each chunk of some content, from which the root of the Merkle tree is computed.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Tree {
+    /// Always contains at least one leaf.
+    leaves: Vec<Digest256>,
+}
+
+impl Tree {
+    /// Create a tree from the digests of each chunk of the content, in order, as computed by [`leaf`].
+    ///
+    /// If there are no leaves, the content is treated as empty.
+    pub fn from_leaves(mut leaves: Vec<Digest256>) -> Self {
+        if leaves.is_empty() {
+            leaves.push(leaf(&[]));
+        }
+        Self { leaves }
+    }
+
+    /// The digests of each chunk of the content, in order.
+    pub fn leaves(&self) -> &[Digest256] {
+        &self.leaves
+    }
+
+    /// The root of the tree.
+    pub fn root(&self) -> Digest256 {
+        root(&self.leaves)
+    }
+
+    /// The fingerprint of the content, which is the root of the tree.
+    pub fn fingerprint(&self) -> Fingerprint<MerkleSHA256> {
+        Fingerprint::new(Blob(self.root().to_vec()))
+    }
+
+    /// The indexes of the chunks whose digests differ between the trees,
+    /// including chunks that are only present in one of them.
+    ///
+    /// Chunks are compared by position, so content inserted or removed near the start of a file
+    /// makes every later chunk differ.
+    pub fn changed_chunks<'a>(&'a self, other: &'a Tree) -> impl Iterator<Item = usize> + 'a {
+        let len = self.leaves.len().max(other.leaves.len());
+        (0..len).filter(|&index| self.leaves.get(index) != other.leaves.get(index))
+    }
+}
+
+/// Compute the root of the tree for the leaves.
+fn root(leaves: &[Digest256]) -> Digest256 {
+    match leaves {
+        [] => leaf(&[]),
+        [leaf] => *leaf,
+        _ => {
+            let split = leaves.len().next_power_of_two() / 2;
+            node(&root(&leaves[..split]), &root(&leaves[split..]))
+        }
+    }
+}
+
+/// Hashes content provided in chunks of any size into a [`Tree`].
+#[derive(Default)]
+pub(crate) struct Hasher {
+    leaves: Vec<Digest256>,
+    /// The hasher for the current chunk, and the number of bytes in it so far.
+    current: Option<(Sha256, usize)>,
+}
+
+impl Hasher {
+    pub(crate) fn finish(mut self) -> Tree {
+        if let Some((hasher, _)) = self.current.take() {
+            self.leaves.push(hasher.finalize().into());
+        }
+        Tree::from_leaves(self.leaves)
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let (hasher, len) = self.current.get_or_insert_with(|| {
+                let mut hasher = Sha256::new();
+                hasher.update([LEAF_PREFIX]);
+                (hasher, 0)
+            });
+
+            let (chunk, remaining) = rest.split_at(rest.len().min(CHUNK_SIZE - *len));
+            hasher.update(chunk);
+            *len += chunk.len();
+            rest = remaining;
+
+            if *len == CHUNK_SIZE {
+                if let Some((hasher, _)) = self.current.take() {
+                    self.leaves.push(hasher.finalize().into());
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use super::*;
+
+    /// Deterministic content that differs in every chunk.
+    fn content(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn chunked(content: &[u8], size: usize) -> Tree {
+        let mut hasher = Hasher::default();
+        for chunk in content.chunks(size) {
+            hasher.write_all(chunk).expect("must hash");
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn matches_leaves() {
+        for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, CHUNK_SIZE * 3 - 1] {
+            let content = content(len);
+            let expected = Tree::from_leaves(content.chunks(CHUNK_SIZE).map(leaf).collect());
+            for size in [1000, CHUNK_SIZE - 1, CHUNK_SIZE, len.max(1)] {
+                assert_eq!(expected, chunked(&content, size), "len {len}, size {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn root_follows_rfc_6962() {
+        let [a, b, c] = [leaf(b"a"), leaf(b"b"), leaf(b"c")];
+        assert_eq!(a, Tree::from_leaves(vec![a]).root());
+        assert_eq!(node(&a, &b), Tree::from_leaves(vec![a, b]).root());
+        assert_eq!(
+            node(&node(&a, &b), &c),
+            Tree::from_leaves(vec![a, b, c]).root()
+        );
+        assert_eq!(leaf(&[]), Tree::from_leaves(Vec::new()).root());
+    }
+
+    #[test]
+    fn changed_chunks() {
+        let original = content(CHUNK_SIZE * 3);
+        let mut corrupted = original.clone();
+        corrupted[CHUNK_SIZE + 10] ^= 0xFF;
+        corrupted.extend_from_slice(b"more");
+
+        let original = chunked(&original, CHUNK_SIZE);
+        let corrupted = chunked(&corrupted, CHUNK_SIZE);
+        assert_ne!(original.root(), corrupted.root());
+        assert_eq!(
+            vec![1, 3],
+            original.changed_chunks(&corrupted).collect::<Vec<_>>()
+        );
+    }
+}
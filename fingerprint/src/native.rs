@@ -0,0 +1,441 @@
+//! Normalization of native binaries (ELF and Mach-O), so that binaries rebuilt from the same source match.
+//!
+//! Linkers record metadata in native binaries that doesn't affect how the binary behaves:
+//! build IDs and UUIDs, timestamps, code signatures, symbol tables, and debug information.
+//! Normalization keeps only the sections holding code and data that are loaded when the binary runs,
+//! in the order they appear in the binary, and discards everything else (including the headers).
+//! ELF notes are discarded even though they are loaded, since that is where build IDs are recorded.
+//!
+//! Each kept section is written as its name, a zero byte, its size as a little endian `u64`, and then its content;
+//! sections that occupy no space in the file (such as `.bss`) are written without content.
+//!
+//! Mach-O universal binaries are archives of binaries for different architectures, and aren't normalized.
+//!
+//! The layout of ELF binaries is described in the System V ABI, and the layout of Mach-O binaries in `<mach-o/loader.h>`.
+
+/// The magic number at the start of every ELF binary.
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+
+/// The number of bytes at the start of the content needed by [`is_native_header`].
+pub(crate) const HEADER_SIZE: usize = 4;
+
+/// Content larger than this isn't treated as a native binary, since it must be held in memory to be normalized.
+pub(crate) const MAX_SIZE: usize = 64 * 1024 * 1024;
+
+/// ELF constants, from the System V ABI.
+mod elf {
+    pub const CLASS_32: u8 = 1;
+    pub const CLASS_64: u8 = 2;
+    pub const DATA_LITTLE: u8 = 1;
+    pub const DATA_BIG: u8 = 2;
+
+    /// The section is loaded when the binary runs.
+    pub const SHF_ALLOC: u64 = 0x2;
+    pub const SHT_NOTE: u32 = 7;
+    pub const SHT_NOBITS: u32 = 8;
+}
+
+/// Mach-O constants, from `<mach-o/loader.h>`.
+mod mach {
+    pub const MAGIC_32: [u8; 4] = [0xFE, 0xED, 0xFA, 0xCE];
+    pub const MAGIC_64: [u8; 4] = [0xFE, 0xED, 0xFA, 0xCF];
+
+    pub const LC_SEGMENT: u32 = 0x1;
+    pub const LC_SEGMENT_64: u32 = 0x19;
+
+    pub const SECTION_TYPE: u32 = 0xFF;
+    pub const S_ZEROFILL: u32 = 0x1;
+    pub const S_GB_ZEROFILL: u32 = 0xC;
+    pub const S_THREAD_LOCAL_ZEROFILL: u32 = 0x12;
+    /// The section only contains debug information.
+    pub const S_ATTR_DEBUG: u32 = 0x0200_0000;
+}
+
+/// Whether the start of the content looks like an ELF or Mach-O binary.
+/// Requires at least [`HEADER_SIZE`] bytes.
+pub(crate) fn is_native_header(start: &[u8]) -> bool {
+    let Some(magic) = start.get(..HEADER_SIZE) else {
+        return false;
+    };
+    let mut reversed = [0; HEADER_SIZE];
+    reversed.copy_from_slice(magic);
+    reversed.reverse();
+
+    magic == ELF_MAGIC
+        || [mach::MAGIC_32, mach::MAGIC_64]
+            .iter()
+            .any(|m| magic == m || &reversed == m)
+}
+
+/// Normalize the native binary, returning `None` if the content isn't a valid ELF or Mach-O binary
+/// or doesn't have any sections to keep.
+pub(crate) fn normalize(binary: &[u8]) -> Option<Vec<u8>> {
+    if binary.len() > MAX_SIZE || !is_native_header(binary) {
+        return None;
+    }
+
+    let mut out = Vec::new();
+    if binary.starts_with(&ELF_MAGIC) {
+        normalize_elf(binary, &mut out)?;
+    } else {
+        normalize_mach_o(binary, &mut out)?;
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+/// Keep the loaded sections of an ELF binary, other than notes.
+fn normalize_elf(binary: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let is_64 = match *binary.get(4)? {
+        elf::CLASS_32 => false,
+        elf::CLASS_64 => true,
+        _ => return None,
+    };
+    let endian = match *binary.get(5)? {
+        elf::DATA_LITTLE => Endian::Little,
+        elf::DATA_BIG => Endian::Big,
+        _ => return None,
+    };
+    let reader = Reader::new(binary, endian, is_64);
+
+    // Offsets of fields in the file header and section headers differ between 32 and 64 bit binaries.
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (0x28, 0x3A, 0x3C, 0x3E)
+    } else {
+        (0x20, 0x2E, 0x30, 0x32)
+    };
+    let (sh_offset, sh_size, sh_len) = if is_64 {
+        (0x18, 0x20, 0x40)
+    } else {
+        (0x10, 0x14, 0x28)
+    };
+
+    let table = reader.word(shoff)?;
+    let entry_size = u64::from(reader.u16(shentsize)?);
+    let count = reader.u16(shnum)?;
+    let names = reader.u16(shstrndx)?;
+    if entry_size < sh_len || names >= count {
+        return None;
+    }
+
+    let header = |index: u16| {
+        let offset = u64::from(index)
+            .checked_mul(entry_size)?
+            .checked_add(table)?;
+        reader.reader(offset, entry_size)
+    };
+    let names = header(names)?;
+    let names = reader.bytes(names.word(sh_offset)?, names.word(sh_size)?)?;
+
+    for index in 0..count {
+        let section = header(index)?;
+        let kind = section.u32(0x4)?;
+        let flags = section.word(0x8)?;
+        if flags & elf::SHF_ALLOC == 0 || kind == elf::SHT_NOTE {
+            continue;
+        }
+
+        let name = names.get(usize::try_from(section.u32(0x0)?).ok()?..)?;
+        let name = &name[..name.iter().position(|&b| b == 0)?];
+        let size = section.word(sh_size)?;
+        let content = match kind {
+            elf::SHT_NOBITS => &[],
+            _ => reader.bytes(section.word(sh_offset)?, size)?,
+        };
+        write_section(out, name, size, content);
+    }
+
+    Some(())
+}
+
+/// Keep the sections of a Mach-O binary, other than debug sections.
+///
+/// Load commands other than segments (such as UUIDs, dylib timestamps, and code signatures) aren't sections,
+/// so they're discarded.
+fn normalize_mach_o(binary: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let magic = binary.get(..HEADER_SIZE)?;
+    let (is_64, endian) = if magic == mach::MAGIC_32 {
+        (false, Endian::Big)
+    } else if magic == mach::MAGIC_64 {
+        (true, Endian::Big)
+    } else if magic.iter().rev().eq(&mach::MAGIC_32) {
+        (false, Endian::Little)
+    } else if magic.iter().rev().eq(&mach::MAGIC_64) {
+        (true, Endian::Little)
+    } else {
+        return None;
+    };
+    let reader = Reader::new(binary, endian, is_64);
+
+    let commands = reader.u32(16)?;
+    let mut offset: u64 = if is_64 { 32 } else { 28 };
+    for _ in 0..commands {
+        let kind = reader.u32(offset)?;
+        let size = u64::from(reader.u32(offset + 4)?);
+        if size < 8 {
+            return None;
+        }
+
+        let command = reader.reader(offset, size)?;
+        if kind == mach::LC_SEGMENT || kind == mach::LC_SEGMENT_64 {
+            normalize_segment(&reader, &command, out)?;
+        }
+        offset += size;
+    }
+
+    Some(())
+}
+
+/// Keep the sections of a Mach-O segment load command, other than debug sections.
+fn normalize_segment(reader: &Reader<'_>, command: &Reader<'_>, out: &mut Vec<u8>) -> Option<()> {
+    // Offsets of fields in segments and sections differ between 32 and 64 bit binaries.
+    let (nsects, sections, section_len) = if reader.is_64 {
+        (64, 72, 80)
+    } else {
+        (48, 56, 68)
+    };
+    let (size_at, offset_at, flags_at) = if reader.is_64 {
+        (40, 48, 64)
+    } else {
+        (36, 40, 56)
+    };
+
+    for index in 0..u64::from(command.u32(nsects)?) {
+        let section = command.reader(sections + index * section_len, section_len)?;
+        let flags = section.u32(flags_at)?;
+        if flags & mach::S_ATTR_DEBUG != 0 {
+            continue;
+        }
+
+        let name = [
+            trim_name(section.bytes(16, 16)?),
+            b",",
+            trim_name(section.bytes(0, 16)?),
+        ]
+        .concat();
+        let size = section.word(size_at)?;
+        let content = match flags & mach::SECTION_TYPE {
+            mach::S_ZEROFILL | mach::S_GB_ZEROFILL | mach::S_THREAD_LOCAL_ZEROFILL => &[],
+            _ => reader.bytes(u64::from(section.u32(offset_at)?), size)?,
+        };
+        write_section(out, &name, size, content);
+    }
+
+    Some(())
+}
+
+/// Mach-O names are padded with zero bytes to 16 bytes, unless they're exactly 16 bytes long.
+fn trim_name(name: &[u8]) -> &[u8] {
+    let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    &name[..end]
+}
+
+/// Write a kept section to the normalized content.
+fn write_section(out: &mut Vec<u8>, name: &[u8], size: u64, content: &[u8]) {
+    out.extend_from_slice(name);
+    out.push(0);
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(content);
+}
+
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+/// Reads fixed size fields at offsets in the binary, or in part of the binary.
+/// Reads return `None` if the field is out of bounds, rather than panicking.
+struct Reader<'a> {
+    buf: &'a [u8],
+    endian: Endian,
+    /// Whether address sized fields are 64 bits.
+    is_64: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8], endian: Endian, is_64: bool) -> Self {
+        Self { buf, endian, is_64 }
+    }
+
+    fn bytes(&self, offset: u64, len: u64) -> Option<&'a [u8]> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(len).ok()?)?;
+        self.buf.get(start..end)
+    }
+
+    /// A reader for part of the binary, with offsets relative to the start of that part.
+    fn reader(&self, offset: u64, len: u64) -> Option<Reader<'a>> {
+        Some(Reader::new(
+            self.bytes(offset, len)?,
+            self.endian,
+            self.is_64,
+        ))
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let bytes = self.bytes(offset, 2)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let bytes = self.bytes(offset, 4)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let bytes = self.bytes(offset, 8)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u64::from_le_bytes(bytes),
+            Endian::Big => u64::from_be_bytes(bytes),
+        })
+    }
+
+    /// Read an address sized field.
+    fn word(&self, offset: u64) -> Option<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a little endian 64 bit Mach-O binary with a single segment.
+    struct Builder {
+        sections: Vec<(&'static str, &'static str, u32, Vec<u8>)>,
+        uuid: [u8; 16],
+    }
+
+    impl Builder {
+        fn new(uuid: u8) -> Self {
+            Self {
+                sections: Vec::new(),
+                uuid: [uuid; 16],
+            }
+        }
+
+        fn section(
+            mut self,
+            segment: &'static str,
+            name: &'static str,
+            flags: u32,
+            content: &[u8],
+        ) -> Self {
+            self.sections.push((segment, name, flags, content.to_vec()));
+            self
+        }
+
+        fn build(self) -> Vec<u8> {
+            let segment_len = 72 + 80 * self.sections.len() as u32;
+            let uuid_len = 24;
+            let mut data_offset = 32 + segment_len + uuid_len;
+
+            let mut out = Vec::new();
+            for field in [
+                0xFEEDFACF,
+                0x0100_0007,
+                3,
+                6,
+                2,
+                segment_len + uuid_len,
+                0,
+                0,
+            ] {
+                out.extend_from_slice(&u32::to_le_bytes(field));
+            }
+
+            let name = |name: &str| {
+                let mut padded = name.as_bytes().to_vec();
+                padded.resize(16, 0);
+                padded
+            };
+            out.extend_from_slice(&mach::LC_SEGMENT_64.to_le_bytes());
+            out.extend_from_slice(&segment_len.to_le_bytes());
+            out.extend(name("__TEXT"));
+            out.extend_from_slice(&[0; 40]);
+            out.extend_from_slice(&(self.sections.len() as u32).to_le_bytes());
+            out.extend_from_slice(&[0; 4]);
+            for (segment, section, flags, content) in &self.sections {
+                out.extend(name(section));
+                out.extend(name(segment));
+                out.extend_from_slice(&[0; 8]);
+                out.extend_from_slice(&(content.len() as u64).to_le_bytes());
+                out.extend_from_slice(&data_offset.to_le_bytes());
+                out.extend_from_slice(&[0; 12]);
+                out.extend_from_slice(&flags.to_le_bytes());
+                out.extend_from_slice(&[0; 12]);
+                if flags & mach::SECTION_TYPE != mach::S_ZEROFILL {
+                    data_offset += content.len() as u32;
+                }
+            }
+
+            out.extend_from_slice(&0x1Bu32.to_le_bytes());
+            out.extend_from_slice(&uuid_len.to_le_bytes());
+            out.extend_from_slice(&self.uuid);
+
+            for (_, _, flags, content) in &self.sections {
+                if flags & mach::SECTION_TYPE != mach::S_ZEROFILL {
+                    out.extend_from_slice(content);
+                }
+            }
+            out
+        }
+    }
+
+    fn binary(uuid: u8, debug: &[u8]) -> Vec<u8> {
+        Builder::new(uuid)
+            .section("__TEXT", "__text", 0, &[0xC3; 4])
+            .section("__DWARF", "__debug_info", mach::S_ATTR_DEBUG, debug)
+            .section("__DATA", "__bss", mach::S_ZEROFILL, &[0; 8])
+            .build()
+    }
+
+    #[test]
+    fn header() {
+        assert!(is_native_header(&ELF_MAGIC));
+        assert!(is_native_header(&[0xCF, 0xFA, 0xED, 0xFE]));
+        assert!(is_native_header(&[0xFE, 0xED, 0xFA, 0xCE]));
+        assert!(!is_native_header(&[0xCA, 0xFE, 0xBA, 0xBE]));
+        assert!(!is_native_header(&ELF_MAGIC[..3]));
+    }
+
+    #[test]
+    fn mach_o_ignores_uuid_and_debug_sections() {
+        let a = binary(1, b"built in /home/a");
+        let b = binary(2, b"built somewhere else");
+        assert_ne!(a, b);
+        assert_eq!(normalize(&a), normalize(&b));
+    }
+
+    #[test]
+    fn mach_o_keeps_code() {
+        let normalized = normalize(&binary(1, b"")).expect("must normalize");
+        let expected = [
+            &b"__TEXT,__text\0"[..],
+            &4u64.to_le_bytes(),
+            &[0xC3; 4],
+            b"__DATA,__bss\0",
+            &8u64.to_le_bytes(),
+        ]
+        .concat();
+        assert_eq!(expected, normalized);
+    }
+
+    #[test]
+    fn invalid() {
+        let binary = binary(1, b"");
+        assert_eq!(None, normalize(&binary[..40]));
+        assert_eq!(None, normalize(&ELF_MAGIC));
+        assert_eq!(None, normalize(b"hello world"));
+    }
+}
@@ -7,9 +7,10 @@
 pub mod kind {
     use std::collections::HashSet;
 
+    use getset::{CopyGetters, Getters};
     use serde::{Deserialize, Serialize};
 
-    use crate::{CommentStrippedSHA256, RawSHA256};
+    use crate::{KindId, KindSet};
 
     /// The stringified version of a [`Kind`].
     #[derive(Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
@@ -27,10 +28,56 @@ pub mod kind {
         pub fn into_inner(self) -> String {
             self.0
         }
+
+        /// The identifier for the kind this instance represents,
+        /// or `None` if it isn't a kind supported by this version of this crate.
+        pub fn kind_id(&self) -> Option<KindId> {
+            self.0.parse().ok()
+        }
+    }
+
+    impl From<KindId> for SerializedKind {
+        fn from(id: KindId) -> Self {
+            Self::new(id)
+        }
+    }
+
+    /// Describes a fingerprint kind supported by this version of this crate; see [`kinds_supported`].
+    #[derive(Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize, Getters, CopyGetters)]
+    pub struct KindDescription {
+        /// The canonical text representation of the kind.
+        #[getset(get = "pub")]
+        kind: SerializedKind,
+
+        /// A short, human readable description of how fingerprints of the kind are derived.
+        #[getset(get = "pub")]
+        description: String,
+
+        /// Whether fingerprints of the kind may be derived for binary files.
+        #[getset(get_copy = "pub")]
+        applies_to_binary: bool,
+    }
+
+    impl From<KindId> for KindDescription {
+        fn from(id: KindId) -> Self {
+            Self {
+                kind: SerializedKind::from(id),
+                description: id.description().to_string(),
+                applies_to_binary: id.applies_to_binary(),
+            }
+        }
+    }
+
+    /// Describe every kind supported by this version of this crate, in the order of [`KindId::all`].
+    ///
+    /// This allows clients to report the kinds they support (for example, to the FOSSA API)
+    /// without hardcoding a list that can fall out of date as kinds are added.
+    pub fn kinds_supported() -> Vec<KindDescription> {
+        KindId::all().map(KindDescription::from).collect()
     }
 
     /// Return the kinds used to evaluate a [`crate::Combined`] output by
-    /// this version of this crate.
+    /// [`crate::fingerprint`] in this version of this crate.
     ///
     /// All kinds _evaluated_ for a `Combined` are included, whether the `Combined`
     /// actually included those kinds or not.
@@ -40,10 +87,16 @@ pub mod kind {
     /// something that the fingerprint algorithm _considered_ for the file that is
     /// represented by a `Combined` value.
     pub fn kinds_evaluated() -> HashSet<SerializedKind> {
-        [RawSHA256.to_string(), CommentStrippedSHA256.to_string()]
-            .into_iter()
-            .map(SerializedKind)
-            .collect()
+        kinds_evaluated_with(KindSet::default())
+    }
+
+    /// Return the kinds used to evaluate a [`crate::Combined`] output by
+    /// [`crate::fingerprint_with`] with the provided set of kinds.
+    ///
+    /// As with [`kinds_evaluated`], all kinds _evaluated_ are included,
+    /// whether the `Combined` actually included those kinds or not.
+    pub fn kinds_evaluated_with(kinds: KindSet) -> HashSet<SerializedKind> {
+        kinds.iter().map(SerializedKind::from).collect()
     }
 
     /// If the previous set of kinds contains all of the kinds we would now emit
@@ -53,3 +106,240 @@ pub mod kind {
         !would_be_evaluated.is_subset(previously_evaluated)
     }
 }
+
+/// Contains a compact binary encoding for [`crate::Combined`] fingerprints.
+///
+/// This is intended for shipping and storing large numbers of fingerprints, where JSON is too large and slow.
+/// The format is versioned and stable: fingerprints encoded by any version of this crate
+/// can be decoded by any later version.
+///
+/// ## Format
+///
+/// Each encoded [`crate::Combined`] is a self-delimiting record, so records may be concatenated:
+///
+/// - The format version, as a single byte (currently `1`).
+/// - The 32 bytes of the [`crate::RawSHA256`] fingerprint, which is always present.
+/// - The number of other entries in the record, as a single byte.
+/// - For each other entry, in ascending order of tag:
+///   - The tag identifying the entry, as a single byte.
+///   - The length of the entry in bytes, as an unsigned LEB128 varint.
+///   - The bytes of the entry.
+///
+/// Entries are either fingerprints or, for tags 128 and above, other data carried by [`crate::Combined`].
+/// Tags are assigned permanently to each kind; see [`binary::tag`].
+/// The [`crate::Metadata`] entry (tag 128) contains the size as an unsigned LEB128 varint,
+/// followed by a single byte that is `1` if the content is binary and `0` otherwise.
+///
+/// Decoders skip entries with tags they don't recognize,
+/// so records containing kinds added in later versions can still be decoded.
+pub mod binary {
+    use std::io::{self, Read, Write};
+
+    use crate::{Blob, Combined, Error, Fingerprint, Kind, KindId, Metadata};
+
+    /// The version of the format written by this version of this crate.
+    pub const VERSION: u8 = 1;
+
+    /// The length of a SHA256 digest, and therefore of the raw fingerprint.
+    const RAW_LEN: usize = 32;
+
+    /// The tag of the [`Metadata`] entry.
+    const METADATA_TAG: u8 = 128;
+
+    /// The tag identifying the kind in encoded records, or `None` for [`KindId::RawSHA256`],
+    /// which is always present at a fixed position and so has no tag.
+    ///
+    /// Tags are permanent: once assigned to a kind, a tag is never reused or changed.
+    pub fn tag(id: KindId) -> Option<u8> {
+        match id {
+            KindId::RawSHA256 => None,
+            KindId::CommentStrippedSHA256 => Some(1),
+            KindId::NormalizedWhitespaceSHA256 => Some(2),
+            KindId::RawSsdeep => Some(3),
+            KindId::RawTLSH => Some(4),
+            KindId::WinnowingMinHash => Some(5),
+            KindId::HashCommentStrippedSHA256 => Some(6),
+            KindId::JavaClassSHA256 => Some(7),
+            KindId::ByteExactSHA256 => Some(8),
+            KindId::NativeSectionsSHA256 => Some(9),
+            KindId::MerkleSHA256 => Some(10),
+            KindId::LiteralAwareCommentStrippedSHA256 => Some(11),
+        }
+    }
+
+    /// Encode the fingerprints into a new buffer.
+    ///
+    /// Returns an error if the raw fingerprint isn't a SHA256 digest,
+    /// as is the case for a default [`Combined`].
+    pub fn encode(combined: &Combined) -> Result<Vec<u8>, Error> {
+        let mut encoded = Vec::new();
+        encode_to(combined, &mut encoded)?;
+        Ok(encoded)
+    }
+
+    /// Encode the fingerprints, writing them to the writer.
+    ///
+    /// As with [`encode`], returns an error if the raw fingerprint isn't a SHA256 digest.
+    pub fn encode_to(combined: &Combined, w: &mut impl Write) -> Result<(), Error> {
+        let mut entries = Vec::new();
+        entry(&mut entries, &combined.comment_stripped);
+        entry(&mut entries, &combined.normalized_whitespace);
+        entry(&mut entries, &combined.ssdeep);
+        entry(&mut entries, &combined.tlsh);
+        entry(&mut entries, &combined.winnowing);
+        entry(&mut entries, &combined.hash_comment_stripped);
+        entry(&mut entries, &combined.java_class);
+        entry(&mut entries, &combined.byte_exact);
+        entry(&mut entries, &combined.native_sections);
+        entry(&mut entries, &combined.merkle);
+        entry(&mut entries, &combined.literal_aware_comment_stripped);
+
+        let mut metadata = Vec::new();
+        if let Some(meta) = &combined.metadata {
+            write_varint(&mut metadata, meta.size())?;
+            metadata.push(u8::from(meta.is_binary()));
+            entries.push((METADATA_TAG, &metadata));
+        }
+        entries.sort_by_key(|(tag, _)| *tag);
+
+        let raw = combined.raw.content.as_bytes();
+        if raw.len() != RAW_LEN {
+            return Err(invalid("raw fingerprint is not a SHA256 digest"));
+        }
+
+        w.write_all(&[VERSION])?;
+        w.write_all(raw)?;
+        w.write_all(&[entries.len() as u8])?;
+        for (tag, content) in entries {
+            w.write_all(&[tag])?;
+            write_varint(w, content.len() as u64)?;
+            w.write_all(content)?;
+        }
+        Ok(())
+    }
+
+    /// Decode a single record, which must make up the entire buffer.
+    pub fn decode(mut encoded: &[u8]) -> Result<Combined, Error> {
+        let combined = decode_from(&mut encoded)?.ok_or_else(|| invalid("empty record"))?;
+        if !encoded.is_empty() {
+            return Err(invalid("trailing bytes after record"));
+        }
+        Ok(combined)
+    }
+
+    /// Decode the next record from the reader.
+    ///
+    /// Returns `None` if the reader is at its end before the record starts,
+    /// which allows reading concatenated records until the end of a stream.
+    pub fn decode_from(r: &mut impl Read) -> Result<Option<Combined>, Error> {
+        let mut version = [0];
+        loop {
+            match r.read(&mut version) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        if version[0] != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+
+        let mut raw = vec![0; RAW_LEN];
+        r.read_exact(&mut raw)?;
+        let mut combined = Combined {
+            raw: Fingerprint::new(Blob(raw)),
+            ..Default::default()
+        };
+
+        let count = read_byte(r)?;
+        let mut last_tag = None;
+        for _ in 0..count {
+            let tag = read_byte(r)?;
+            if matches!(last_tag, Some(last) if tag <= last) {
+                return Err(invalid("entries are not in ascending order of tag"));
+            }
+            last_tag = Some(tag);
+
+            let len = usize::try_from(read_varint(r)?).map_err(|_| invalid("length too large"))?;
+            let mut content = Vec::new();
+            r.take(len as u64).read_to_end(&mut content)?;
+            if content.len() != len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+
+            match tag {
+                1 => combined.comment_stripped = fingerprint(content),
+                2 => combined.normalized_whitespace = fingerprint(content),
+                3 => combined.ssdeep = fingerprint(content),
+                4 => combined.tlsh = fingerprint(content),
+                5 => combined.winnowing = fingerprint(content),
+                6 => combined.hash_comment_stripped = fingerprint(content),
+                7 => combined.java_class = fingerprint(content),
+                8 => combined.byte_exact = fingerprint(content),
+                9 => combined.native_sections = fingerprint(content),
+                10 => combined.merkle = fingerprint(content),
+                11 => combined.literal_aware_comment_stripped = fingerprint(content),
+                METADATA_TAG => combined.metadata = Some(decode_metadata(&content)?),
+                // Entries added in later versions of this crate.
+                _ => {}
+            }
+        }
+
+        Ok(Some(combined))
+    }
+
+    fn fingerprint<K: Kind>(content: Vec<u8>) -> Option<Fingerprint<K>> {
+        Some(Fingerprint::new(Blob(content)))
+    }
+
+    fn decode_metadata(mut content: &[u8]) -> Result<Metadata, Error> {
+        let size = read_varint(&mut content)?;
+        match content {
+            [0] => Ok(Metadata::new(size, false)),
+            [1] => Ok(Metadata::new(size, true)),
+            _ => Err(invalid("invalid metadata")),
+        }
+    }
+
+    /// Add the fingerprint to the entries to encode, if it is present.
+    fn entry<'a, K: Kind>(entries: &mut Vec<(u8, &'a [u8])>, fp: &'a Option<Fingerprint<K>>) {
+        if let (Some(fp), Some(tag)) = (fp, tag(K::ID)) {
+            entries.push((tag, fp.content.as_bytes()));
+        }
+    }
+
+    fn invalid(reason: &str) -> Error {
+        Error::InvalidEncoding(reason.to_string())
+    }
+
+    fn read_byte(r: &mut impl Read) -> Result<u8, Error> {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn write_varint(w: &mut impl Write, mut value: u64) -> Result<(), Error> {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                w.write_all(&[byte])?;
+                return Ok(());
+            }
+            w.write_all(&[byte | 0x80])?;
+        }
+    }
+
+    fn read_varint(r: &mut impl Read) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = read_byte(r)?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+}
@@ -0,0 +1,424 @@
+//! Context triggered piecewise hashing, compatible with `ssdeep`.
+//!
+//! The content is split into pieces at points chosen by a rolling hash over a small window of bytes,
+//! and each piece is summarized as a single base64 character. Since the split points depend only on nearby content,
+//! an edit to the content only changes the characters for the pieces around it,
+//! so two signatures can be compared with an edit distance to estimate how similar their contents are.
+//!
+//! Signatures are in the `ssdeep` text format: `<block size>:<signature>:<signature at double the block size>`.
+//! Both the hashing and the comparison match `ssdeep` 2.14, so signatures can be compared with ones created by it.
+
+use std::io::{self, Write};
+
+/// The size of the window over which the rolling hash is computed.
+const ROLLING_WINDOW: usize = 7;
+
+/// The smallest block size; each larger block size is double the previous one.
+const MIN_BLOCK_SIZE: u32 = 3;
+
+/// The number of block sizes for which signatures are computed.
+const NUM_BLOCK_HASHES: usize = 31;
+
+/// The maximum length of a signature.
+const SPAMSUM_LENGTH: usize = 64;
+
+const HASH_PRIME: u32 = 0x0100_0193;
+const HASH_INIT: u32 = 0x2802_1967;
+
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn block_size(index: usize) -> u32 {
+    MIN_BLOCK_SIZE << index
+}
+
+/// The piece hash, which is a 32 bit FNV hash.
+fn sum_hash(c: u8, h: u32) -> u32 {
+    h.wrapping_mul(HASH_PRIME) ^ u32::from(c)
+}
+
+/// The rolling hash used to choose the points at which the content is split into pieces.
+#[derive(Default)]
+struct Roll {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: u32,
+}
+
+impl Roll {
+    fn update(&mut self, c: u8) {
+        let slot = self.n as usize % ROLLING_WINDOW;
+        self.h2 = self.h2.wrapping_sub(self.h1);
+        self.h2 = self.h2.wrapping_add(ROLLING_WINDOW as u32 * u32::from(c));
+        self.h1 = self.h1.wrapping_add(u32::from(c));
+        self.h1 = self.h1.wrapping_sub(u32::from(self.window[slot]));
+        self.window[slot] = c;
+        self.n = self.n.wrapping_add(1);
+        self.h3 <<= 5;
+        self.h3 ^= u32::from(c);
+    }
+
+    fn sum(&self) -> u32 {
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+/// The signature being built for a single block size.
+#[derive(Clone, Copy)]
+struct BlockHash {
+    /// The hash of the current piece.
+    h: u32,
+    /// The hash of the current piece for the signature truncated to half length,
+    /// which keeps accumulating once the truncated signature is full.
+    half_h: u32,
+    digest: [u8; SPAMSUM_LENGTH],
+    /// The character for the last piece of the truncated signature, if it has been triggered.
+    half_digest: Option<u8>,
+    len: usize,
+}
+
+impl Default for BlockHash {
+    fn default() -> Self {
+        Self {
+            h: HASH_INIT,
+            half_h: HASH_INIT,
+            digest: [0; SPAMSUM_LENGTH],
+            half_digest: None,
+            len: 0,
+        }
+    }
+}
+
+/// Computes the signature for content written to it.
+pub(crate) struct Hasher {
+    roll: Roll,
+    blocks: [BlockHash; NUM_BLOCK_HASHES],
+    /// The number of block sizes being hashed.
+    ///
+    /// A block size is only started once the block size before it is first triggered,
+    /// from a copy of the smaller block size's state. Until then they would hash identically.
+    live: usize,
+    total: u64,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self {
+            roll: Roll::default(),
+            blocks: [BlockHash::default(); NUM_BLOCK_HASHES],
+            live: 1,
+            total: 0,
+        }
+    }
+}
+
+impl Hasher {
+    fn update(&mut self, c: u8) {
+        self.roll.update(c);
+        let sum = self.roll.sum();
+
+        for block in self.blocks[..self.live].iter_mut() {
+            block.h = sum_hash(c, block.h);
+            block.half_h = sum_hash(c, block.half_h);
+        }
+
+        // The block sizes are all powers of two multiples of the smallest,
+        // so a block size is only triggered if all smaller block sizes are also triggered.
+        for index in 0..self.live {
+            let size = block_size(index);
+            if sum % size != size - 1 {
+                break;
+            }
+
+            if index == self.live - 1 && self.live < NUM_BLOCK_HASHES {
+                self.blocks[self.live] = BlockHash {
+                    h: self.blocks[index].h,
+                    half_h: self.blocks[index].half_h,
+                    ..BlockHash::default()
+                };
+                self.live += 1;
+            }
+
+            let block = &mut self.blocks[index];
+            block.digest[block.len] = B64[block.h as usize % 64];
+            block.half_digest = Some(B64[block.half_h as usize % 64]);
+            if block.len < SPAMSUM_LENGTH - 1 {
+                block.len += 1;
+                block.h = HASH_INIT;
+                if block.len < SPAMSUM_LENGTH / 2 {
+                    block.half_h = HASH_INIT;
+                    block.half_digest = None;
+                }
+            }
+        }
+    }
+
+    /// Finish hashing, returning the signature.
+    pub(crate) fn finish(self) -> String {
+        // Start with the smallest block size at which the content fits in a full signature,
+        // then move to smaller block sizes until the signature is at least half full.
+        let mut index = 0;
+        while index < self.live - 1
+            && u64::from(block_size(index)) * (SPAMSUM_LENGTH as u64) < self.total
+        {
+            index += 1;
+        }
+        while index > 0 && self.blocks[index].len < SPAMSUM_LENGTH / 2 {
+            index -= 1;
+        }
+
+        let ended_mid_piece = self.roll.sum() != 0;
+        let mut signature = format!("{}:", block_size(index)).into_bytes();
+
+        let block = &self.blocks[index];
+        signature.extend_from_slice(&block.digest[..block.len]);
+        if ended_mid_piece {
+            signature.push(B64[block.h as usize % 64]);
+        } else if block.digest[block.len] != 0 {
+            signature.push(block.digest[block.len]);
+        }
+        signature.push(b':');
+
+        // A block size that was never started would have the same state as this one.
+        match self.blocks[..self.live].get(index + 1) {
+            Some(block) => {
+                let len = block.len.min(SPAMSUM_LENGTH / 2 - 1);
+                signature.extend_from_slice(&block.digest[..len]);
+                if ended_mid_piece {
+                    signature.push(B64[block.half_h as usize % 64]);
+                } else if let Some(c) = block.half_digest {
+                    signature.push(c);
+                }
+            }
+            None if ended_mid_piece => signature.push(B64[block.h as usize % 64]),
+            None => {}
+        }
+
+        // The signature is entirely ASCII.
+        String::from_utf8_lossy(&signature).into_owned()
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &c in buf {
+            self.update(c);
+        }
+        self.total += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A parsed signature.
+struct Signature {
+    block_size: u64,
+    first: Vec<u8>,
+    second: Vec<u8>,
+}
+
+impl Signature {
+    fn parse(raw: &[u8]) -> Option<Self> {
+        let mut parts = raw.splitn(3, |&c| c == b':');
+        let block_size = std::str::from_utf8(parts.next()?).ok()?.parse().ok()?;
+        let first = eliminate_sequences(parts.next()?);
+        let second = eliminate_sequences(parts.next()?);
+        Some(Self {
+            block_size,
+            first,
+            second,
+        })
+    }
+}
+
+/// Runs of more than three identical characters carry little information, so they are shortened to three.
+fn eliminate_sequences(signature: &[u8]) -> Vec<u8> {
+    let mut eliminated = Vec::with_capacity(signature.len());
+    for (i, &c) in signature.iter().enumerate() {
+        if i < 3 || signature[i - 3..i].iter().any(|&prev| prev != c) {
+            eliminated.push(c);
+        }
+    }
+    eliminated
+}
+
+/// Compare two signatures, returning a score from 0 (no similarity) to 100 (identical or nearly so).
+///
+/// Signatures can only be compared if their block sizes are equal or differ by a factor of two;
+/// others, along with invalid signatures, score 0.
+pub(crate) fn compare(a: &[u8], b: &[u8]) -> u8 {
+    let (a, b) = match (Signature::parse(a), Signature::parse(b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return 0,
+    };
+
+    if a.block_size == b.block_size {
+        if a.first == b.first && a.second == b.second {
+            return 100;
+        }
+        score(&a.first, &b.first, a.block_size).max(score(
+            &a.second,
+            &b.second,
+            a.block_size.saturating_mul(2),
+        ))
+    } else if a.block_size == b.block_size.saturating_mul(2) {
+        score(&a.first, &b.second, a.block_size)
+    } else if b.block_size == a.block_size.saturating_mul(2) {
+        score(&a.second, &b.first, b.block_size)
+    } else {
+        0
+    }
+}
+
+/// Score the similarity of two signatures computed at the same block size.
+fn score(a: &[u8], b: &[u8], block_size: u64) -> u8 {
+    if a.len() > SPAMSUM_LENGTH || b.len() > SPAMSUM_LENGTH || !has_common_substring(a, b) {
+        return 0;
+    }
+
+    // Scale the edit distance to the length of the signatures, then to a score out of 100.
+    let distance = edit_distance(a, b) as u64;
+    let scaled = distance * SPAMSUM_LENGTH as u64 / (a.len() + b.len()) as u64;
+    let scaled = 100 * scaled / SPAMSUM_LENGTH as u64;
+    if scaled >= 100 {
+        return 0;
+    }
+    let mut score = 100 - scaled;
+
+    // Small block sizes mean short pieces, which match by chance more often;
+    // cap the score so that short signatures can't claim a high similarity.
+    let threshold =
+        (99 + ROLLING_WINDOW as u64) / ROLLING_WINDOW as u64 * u64::from(MIN_BLOCK_SIZE);
+    if block_size < threshold {
+        let cap = block_size / u64::from(MIN_BLOCK_SIZE) * a.len().min(b.len()) as u64;
+        score = score.min(cap);
+    }
+
+    score as u8
+}
+
+/// Whether the signatures share a run of at least [`ROLLING_WINDOW`] characters,
+/// which is required for them to be considered similar at all.
+fn has_common_substring(a: &[u8], b: &[u8]) -> bool {
+    if a.len() < ROLLING_WINDOW || b.len() < ROLLING_WINDOW {
+        return false;
+    }
+    a.windows(ROLLING_WINDOW)
+        .any(|window| b.windows(ROLLING_WINDOW).any(|other| window == other))
+}
+
+/// The edit distance between two signatures, where insertions and deletions cost 1 and substitutions cost 2.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = prev[j] + if ca == cb { 0 } else { 2 };
+            curr[j + 1] = substitute.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use super::*;
+
+    fn signature(content: &[u8]) -> String {
+        let mut hasher = Hasher::default();
+        hasher.write_all(content).expect("must hash");
+        hasher.finish()
+    }
+
+    /// Deterministic content that looks like source text, so it is split into many pieces.
+    fn text(lines: usize) -> Vec<u8> {
+        (0..lines)
+            .flat_map(|i| {
+                format!("let value_{i} = compute({i}, {});\n", i * 7919 % 104729).into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(signature(b""), "3::");
+    }
+
+    #[test]
+    fn format() {
+        let sig = signature(&text(200));
+        let mut parts = sig.split(':');
+        let block_size = parts
+            .next()
+            .expect("block size")
+            .parse::<u32>()
+            .expect("number");
+        assert!(block_size >= MIN_BLOCK_SIZE);
+        assert_eq!(block_size % MIN_BLOCK_SIZE, 0);
+        let first = parts.next().expect("first signature");
+        let second = parts.next().expect("second signature");
+        assert!(parts.next().is_none());
+        assert!(first.len() >= SPAMSUM_LENGTH / 2 && first.len() <= SPAMSUM_LENGTH);
+        assert!(second.len() <= SPAMSUM_LENGTH / 2);
+        assert!(first
+            .bytes()
+            .chain(second.bytes())
+            .all(|c| B64.contains(&c)));
+    }
+
+    #[test]
+    fn compare_identical() {
+        let sig = signature(&text(200));
+        assert_eq!(compare(sig.as_bytes(), sig.as_bytes()), 100);
+    }
+
+    #[test]
+    fn compare_patched() {
+        let original = text(200);
+        let mut patched = original.clone();
+        patched.splice(
+            3000..3000,
+            b"// patched for the vendored build\n".iter().copied(),
+        );
+
+        let a = signature(&original);
+        let b = signature(&patched);
+        assert_ne!(a, b);
+        let score = compare(a.as_bytes(), b.as_bytes());
+        assert!(score > 50 && score < 100, "score: {score}");
+    }
+
+    #[test]
+    fn compare_unrelated() {
+        let a = signature(&text(200));
+        let b = signature(&text(200).into_iter().rev().collect::<Vec<_>>());
+        assert_eq!(compare(a.as_bytes(), b.as_bytes()), 0);
+    }
+
+    #[test]
+    fn compare_incompatible_block_sizes() {
+        assert_eq!(compare(b"3:abcdefgh:abcd", b"12:abcdefgh:abcd"), 0);
+        assert_eq!(compare(b"not a signature", b"3:abcdefgh:abcd"), 0);
+    }
+
+    #[test]
+    fn eliminates_sequences() {
+        assert_eq!(eliminate_sequences(b"aaaaaabccccd"), b"aaabcccd");
+    }
+
+    #[test]
+    fn weighted_edit_distance() {
+        assert_eq!(edit_distance(b"abc", b"abc"), 0);
+        assert_eq!(edit_distance(b"abc", b"abd"), 2);
+        assert_eq!(edit_distance(b"abc", b"abcd"), 1);
+        assert_eq!(edit_distance(b"", b"abc"), 3);
+    }
+}
@@ -1,10 +1,13 @@
 //! Tests for the external API.
 
-use std::io::Cursor;
+use std::{collections::HashSet, io::Cursor, path::PathBuf};
 
 use sha2::{Digest, Sha256};
 
-use crate::serialize::kind::{kinds_evaluated, would_evaluate_new_kinds, SerializedKind};
+use crate::serialize::kind::{
+    kinds_evaluated, kinds_evaluated_with, kinds_supported, would_evaluate_new_kinds,
+    SerializedKind,
+};
 
 use super::*;
 
@@ -67,12 +70,16 @@ fn fingerprints_binary_file() {
 fn fingerprints_text_file() {
     let content = b"hello world";
 
-    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
     let expected_fingerprint = make_fingerprint::<RawSHA256>(content);
     assert_eq!(combined.raw, expected_fingerprint);
 
     let expected_fingerprint = make_fingerprint::<CommentStrippedSHA256>(content);
     assert_eq!(combined.comment_stripped, Some(expected_fingerprint));
+
+    let expected_fingerprint = make_fingerprint::<NormalizedWhitespaceSHA256>(content);
+    assert_eq!(combined.normalized_whitespace, Some(expected_fingerprint));
 }
 
 #[test]
@@ -93,6 +100,39 @@ fn fingerprints_text_file_stripping_cr() {
     );
 }
 
+#[test]
+fn byte_exact_does_not_strip_cr() {
+    let content = b"hello world\r\nanother line\r\na final line\n";
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    let expected_fingerprint = make_fingerprint::<ByteExactSHA256>(content);
+    assert_eq!(combined.byte_exact, Some(expected_fingerprint));
+    assert_ne!(
+        combined.raw.content,
+        make_fingerprint::<RawSHA256>(content).content
+    );
+}
+
+#[test]
+fn merkle_chunks_large_files() {
+    let content = (0..merkle::CHUNK_SIZE * 5 / 2)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(&content))
+        .expect("must fingerprint");
+    let tree = merkle::tree(&mut Cursor::new(&content)).expect("must hash");
+    assert_eq!(3, tree.leaves().len());
+    assert_eq!(combined.merkle, Some(tree.fingerprint()));
+
+    // Like `ByteExactSHA256`, line endings aren't normalized.
+    let crlf = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(b"hello\r\n"))
+        .expect("must fingerprint");
+    let lf = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(b"hello\n"))
+        .expect("must fingerprint");
+    assert_eq!(crlf.raw, lf.raw);
+    assert_ne!(crlf.merkle, lf.merkle);
+}
+
 #[test]
 fn fingerprints_binary_file_appearing_as_text() {
     // Sourced from `git@github.com:chromium/chromium.git` at `tools/origin_trials/eftest.key` on commit 49249345609d505c8bb8b0b5a42ff4b68b9e6d41.
@@ -103,6 +143,24 @@ fn fingerprints_binary_file_appearing_as_text() {
     assert_eq!(combined.comment_stripped, None);
 }
 
+#[test]
+fn normalized_whitespace_fingerprint_text_file() {
+    let content = b"  int main() {\r\n\treturn 0;\r\n}\r\n";
+    let normalized = b"int main() { return 0; }";
+
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    let expected_fingerprint = make_fingerprint::<NormalizedWhitespaceSHA256>(normalized);
+    assert_eq!(combined.normalized_whitespace, Some(expected_fingerprint));
+}
+
+#[test]
+fn normalized_whitespace_does_not_fingerprint_binary_file() {
+    let content = vec![1, 2, 3, 0, 1, 2, 3];
+    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    assert_eq!(combined.normalized_whitespace, None);
+}
+
 #[test]
 fn comment_stripped_does_not_fingerprint_binary_file() {
     let content = vec![1, 2, 3, 0, 1, 2, 3];
@@ -144,3 +202,837 @@ fn evaluate_kinds() {
     evaluated.insert(SerializedKind::new("some other kind"));
     assert!(!would_evaluate_new_kinds(&evaluated));
 }
+
+#[test]
+fn ssdeep_fingerprints_binary_file() {
+    let content = vec![1, 2, 3, 0, 1, 2, 3];
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    assert!(combined.ssdeep.is_some());
+}
+
+#[test]
+fn ssdeep_ignores_line_endings() {
+    let content = include_str!("../testdata/facebook-folly-Version.cpp").replace("\r\n", "\n");
+    let crlf = content.replace('\n', "\r\n");
+
+    let lf = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    let crlf = fingerprint_stream(&mut Cursor::new(crlf)).expect("should not error");
+    assert_eq!(lf.ssdeep, crlf.ssdeep);
+}
+
+#[test]
+fn ssdeep_similarity_patched_file() {
+    let content = include_str!("../testdata/facebook-folly-Version.cpp").replace("\r\n", "\n");
+    let patched = content.replacen(
+        "#include",
+        "// Patched for the vendored build.\n#include",
+        1,
+    );
+
+    let original = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    let patched = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(patched))
+        .expect("should not error");
+    let original = original.ssdeep.expect("must fingerprint");
+    let patched = patched.ssdeep.expect("must fingerprint");
+
+    assert_ne!(original, patched);
+    assert_eq!(original.similarity(&original), 100);
+    let score = original.similarity(&patched);
+    assert!(score > 50 && score < 100, "score: {score}");
+}
+
+#[test]
+fn tlsh_does_not_fingerprint_short_file() {
+    let content = b"hello world";
+    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    assert_eq!(combined.tlsh, None);
+}
+
+#[test]
+fn tlsh_distance_patched_file() {
+    let content = include_str!("../testdata/facebook-folly-Version.cpp").replace("\r\n", "\n");
+    let patched = content.replacen(
+        "#include",
+        "// Patched for the vendored build.\n#include",
+        1,
+    );
+
+    let original = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    let patched = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(patched))
+        .expect("should not error");
+    let original = original.tlsh.expect("must fingerprint");
+    let patched = patched.tlsh.expect("must fingerprint");
+
+    assert_eq!(original.distance(&original), Some(0));
+    let distance = original.distance(&patched).expect("must be valid");
+    assert!(distance > 0 && distance < 50, "distance: {distance}");
+}
+
+#[test]
+fn winnowing_does_not_fingerprint_binary_file() {
+    let content = vec![1, 2, 3, 0, 1, 2, 3];
+    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    assert_eq!(combined.winnowing, None);
+}
+
+#[test]
+fn winnowing_jaccard_partial_copy() {
+    let content = include_str!("../testdata/facebook-folly-Version.cpp").replace("\r\n", "\n");
+    let (head, _) = content.split_at(content.len() / 2);
+    let copied = format!("{head}\nint vendored_helper() {{ return 42; }}\n");
+
+    let original = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content.clone()))
+        .expect("should not error");
+    let copied = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(copied))
+        .expect("should not error");
+    let original = original.winnowing.expect("must fingerprint");
+    let copied = copied.winnowing.expect("must fingerprint");
+
+    assert_eq!(original.jaccard(&original), Some(1.0));
+    let similarity = original.jaccard(&copied).expect("must be valid");
+    assert!(
+        similarity > 0.2 && similarity < 1.0,
+        "similarity: {similarity}"
+    );
+}
+
+#[test]
+fn kind_id_round_trip() {
+    for id in KindId::all() {
+        let parsed = id.to_string().parse::<KindId>().expect("must parse");
+        assert_eq!(id, parsed);
+
+        let serialized = SerializedKind::from(id);
+        assert_eq!(serialized.kind_id(), Some(id));
+    }
+
+    assert!("some other kind".parse::<KindId>().is_err());
+    assert_eq!(SerializedKind::new("some other kind").kind_id(), None);
+}
+
+#[test]
+fn kind_id_matches_kind() {
+    assert_eq!(KindId::RawSHA256.to_string(), RawSHA256.to_string());
+    assert_eq!(
+        KindId::CommentStrippedSHA256.to_string(),
+        CommentStrippedSHA256.to_string()
+    );
+
+    let fp = make_fingerprint::<RawSHA256>(b"hello world");
+    assert_eq!(fp.kind_id(), KindId::RawSHA256);
+}
+
+#[test]
+fn kind_id_serde() {
+    use serde::de::{value, IntoDeserializer};
+
+    let deserializer: value::StrDeserializer<'_, value::Error> = "sha_256".into_deserializer();
+    let id = KindId::deserialize(deserializer).expect("must deserialize");
+    assert_eq!(id, KindId::RawSHA256);
+
+    let deserializer: value::StrDeserializer<'_, value::Error> =
+        "some other kind".into_deserializer();
+    assert!(KindId::deserialize(deserializer).is_err());
+}
+
+#[test]
+fn kinds_evaluated_are_default_kinds() {
+    let evaluated = kinds_evaluated()
+        .into_iter()
+        .map(|kind| kind.kind_id().expect("must be known"))
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        evaluated,
+        HashSet::from([KindId::RawSHA256, KindId::CommentStrippedSHA256])
+    );
+
+    let evaluated = kinds_evaluated_with(KindSet::all())
+        .into_iter()
+        .map(|kind| kind.kind_id().expect("must be known"))
+        .collect::<HashSet<_>>();
+    assert_eq!(evaluated, KindId::all().collect());
+}
+
+#[test]
+fn kinds_supported_describes_all_kinds() {
+    let supported = kinds_supported();
+    let kinds = supported
+        .iter()
+        .map(|description| description.kind().kind_id().expect("must be known"))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, KindId::all().collect::<Vec<_>>());
+    assert!(supported.iter().all(|kind| !kind.description().is_empty()));
+
+    let json = serde_json::to_value(&supported[1]).expect("must serialize");
+    assert_eq!(json["kind"], "comment_stripped:sha_256");
+    assert_eq!(json["applies_to_binary"], false);
+}
+
+#[test]
+fn kinds_not_applying_to_binary_are_not_derived() {
+    let binary = [&[0, 1, 2, 3][..], b"int main() { return 0; } // comment"].concat();
+    let binary = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(binary))
+        .expect("must fingerprint");
+    let binary = serde_json::to_value(binary).expect("must serialize");
+
+    let text = b"int main() {\n  return 0; // comment\n}\n";
+    let text =
+        fingerprint_stream_with(KindSet::all(), &mut Cursor::new(text)).expect("must fingerprint");
+    let text = serde_json::to_value(text).expect("must serialize");
+
+    for kind in KindId::all().filter(|kind| !kind.applies_to_binary()) {
+        let kind = kind.to_string();
+        assert!(binary[&kind].is_null(), "{kind} derived for binary file");
+        assert!(!text[&kind].is_null(), "{kind} not derived for text file");
+    }
+}
+
+#[test]
+fn kinds_not_computed_are_not_serialized() {
+    let combined = fingerprint_stream(&mut Cursor::new(b"hello world")).expect("must fingerprint");
+    let json = serde_json::to_value(combined).expect("must serialize");
+    let json = json.as_object().expect("must be object");
+
+    // The legacy kinds are serialized as before; other kinds are left out rather than serialized as `null`.
+    assert!(json.contains_key("sha_256"));
+    assert!(json.contains_key("comment_stripped:sha_256"));
+    for kind in KindId::all().filter(|&kind| !KindSet::default().contains(kind)) {
+        assert!(!json.contains_key(&kind.to_string()), "{kind} serialized");
+    }
+}
+
+#[test]
+fn kind_set_always_has_raw() {
+    let kinds = KindSet::raw();
+    assert_eq!(kinds.iter().collect::<Vec<_>>(), vec![KindId::RawSHA256]);
+
+    let kinds = KindSet::all().without(KindId::RawSHA256);
+    assert!(kinds.contains(KindId::RawSHA256));
+
+    let kinds = [KindId::RawSsdeep].into_iter().collect::<KindSet>();
+    assert!(kinds.contains(KindId::RawSHA256));
+    assert!(kinds.contains(KindId::RawSsdeep));
+    assert!(!kinds.contains(KindId::CommentStrippedSHA256));
+}
+
+#[test]
+fn fingerprints_selected_kinds() {
+    let content = b"hello world";
+    let all = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+
+    let raw = fingerprint_stream_with(KindSet::raw(), &mut Cursor::new(content))
+        .expect("should not error");
+    assert_eq!(raw.raw, all.raw);
+    assert_eq!(raw.comment_stripped, None);
+    assert_eq!(raw.normalized_whitespace, None);
+    assert_eq!(raw.ssdeep, None);
+
+    let kinds = KindSet::raw().with(KindId::CommentStrippedSHA256);
+    let stripped =
+        fingerprint_stream_with(kinds, &mut Cursor::new(content)).expect("should not error");
+    assert_eq!(stripped.comment_stripped, all.comment_stripped);
+    assert_eq!(stripped.normalized_whitespace, None);
+}
+
+#[test]
+fn evaluate_selected_kinds() {
+    let evaluated = kinds_evaluated_with(KindSet::raw());
+    assert_eq!(
+        evaluated,
+        HashSet::from([SerializedKind::new(RawSHA256.to_string())])
+    );
+    assert!(would_evaluate_new_kinds(&evaluated));
+}
+
+#[test]
+fn hash_comment_stripped_fingerprint_text_file() {
+    let content = b"#!/bin/sh\n# Print a greeting.\n\necho hello # to stdout\r\n";
+    let stripped = b"echo hello";
+
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("should not error");
+    let expected_fingerprint = make_fingerprint::<HashCommentStrippedSHA256>(stripped);
+    assert_eq!(combined.hash_comment_stripped, Some(expected_fingerprint));
+}
+
+#[test]
+fn hash_comment_stripped_does_not_fingerprint_binary_file() {
+    let content = vec![1, 2, 3, 0, 1, 2, 3];
+    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("should not error");
+    assert_eq!(combined.hash_comment_stripped, None);
+}
+
+#[tokio::test]
+async fn fingerprint_stream_async_matches_sync() {
+    let text = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let binary = include_bytes!("../testdata/eftest.key");
+    for content in [&text[..], &binary[..]] {
+        let expected =
+            fingerprint_stream(&mut Cursor::new(content.to_vec())).expect("must fingerprint");
+        let actual = fingerprint_stream_async(&mut Cursor::new(content))
+            .await
+            .expect("must fingerprint");
+        assert_eq!(expected, actual);
+    }
+}
+
+#[tokio::test]
+async fn fingerprint_stream_async_selected_kinds() {
+    let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    for kinds in [KindSet::raw(), KindSet::all()] {
+        let expected = fingerprint_stream_with(kinds, &mut Cursor::new(content.to_vec()))
+            .expect("must fingerprint");
+        let actual = fingerprint_stream_async_with(kinds, &mut Cursor::new(content))
+            .await
+            .expect("must fingerprint");
+        assert_eq!(expected, actual);
+    }
+}
+
+/// An async reader that is interrupted before each successful read.
+struct InterruptingAsync<R> {
+    inner: R,
+    interrupt: bool,
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for InterruptingAsync<R> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return std::task::Poll::Ready(Err(std::io::ErrorKind::Interrupted.into()));
+        }
+        std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+#[tokio::test]
+async fn fingerprint_stream_async_retries_interrupted_reads() {
+    let content = b"hello world";
+    let expected = fingerprint_stream(&mut Cursor::new(content)).expect("must fingerprint");
+    let mut stream = InterruptingAsync {
+        inner: Cursor::new(content),
+        interrupt: false,
+    };
+    let actual = fingerprint_stream_async(&mut stream)
+        .await
+        .expect("must fingerprint");
+    assert_eq!(expected, actual);
+}
+
+#[tokio::test]
+async fn fingerprint_async_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/facebook-folly-Version.cpp");
+    let expected = fingerprint(&path).expect("must fingerprint");
+    let actual = fingerprint_async(&path).await.expect("must fingerprint");
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn combined_hasher_matches_fingerprint_stream() {
+    let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let expected = fingerprint_stream(&mut Cursor::new(content)).expect("must fingerprint");
+
+    let mut hasher = CombinedHasher::default();
+    for chunk in content.chunks(1000) {
+        hasher.update(chunk).expect("must hash");
+    }
+    assert_eq!(expected, hasher.finalize().expect("must finalize"));
+}
+
+#[test]
+fn fingerprint_stream_without_seek() {
+    let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let (head, tail) = content.split_at(content.len() / 2);
+
+    // Chained readers can't seek, but are read in a single pass.
+    let mut stream = head.chain(tail);
+    let combined = fingerprint_stream(&mut stream).expect("must fingerprint");
+    let expected = fingerprint_stream(&mut Cursor::new(content)).expect("must fingerprint");
+    assert_eq!(expected, combined);
+}
+
+#[test]
+fn process_text_file() {
+    let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let stripped = include_str!("../testdata/facebook-folly-Version.cpp.stripped");
+    let processed = process_stream(&mut Cursor::new(content)).expect("must process");
+    assert!(!processed.detected_as_binary());
+
+    let expected = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("must fingerprint");
+    assert_eq!(expected, processed.to_combined());
+
+    let raw = processed.raw().content();
+    assert_eq!(String::from_utf8_lossy(content), raw.as_str());
+    let comment_stripped = processed.comment_stripped().as_ref().expect("must strip");
+    assert_eq!(stripped, comment_stripped.content());
+}
+
+#[test]
+fn process_binary_file() {
+    let content = [&[0, 1, 2, 3][..], b"int main() { return 0; }"].concat();
+    let processed = process_stream(&mut Cursor::new(&content)).expect("must process");
+    assert!(processed.detected_as_binary());
+    assert_eq!(&None, processed.comment_stripped());
+
+    let expected = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(&content))
+        .expect("must fingerprint");
+    assert_eq!(expected, processed.to_combined());
+}
+
+#[test]
+fn java_class_ignores_compiler_metadata() {
+    // The same class, compiled for Java 8 from `Greeter.java` and for Java 17 from `Copy.java`,
+    // with the class moved down a few lines in the source.
+    let java8 = include_bytes!("../testdata/Greeter.java8.class");
+    let java17 = include_bytes!("../testdata/Greeter.java17.class");
+
+    let java8 =
+        fingerprint_stream_with(KindSet::all(), &mut Cursor::new(java8)).expect("must fingerprint");
+    let java17 = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(java17))
+        .expect("must fingerprint");
+    assert_ne!(java8.raw(), java17.raw());
+    assert!(java8.java_class().is_some());
+    assert_eq!(java8.java_class(), java17.java_class());
+}
+
+#[test]
+fn java_class_does_not_fingerprint_other_files() {
+    let text = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let combined = fingerprint_stream(&mut Cursor::new(text)).expect("must fingerprint");
+    assert_eq!(&None, combined.java_class());
+
+    // Mach-O universal binaries share the class file magic number.
+    let content = [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 2, 0, 0, 0, 7];
+    let combined = fingerprint_stream(&mut Cursor::new(content)).expect("must fingerprint");
+    assert_eq!(&None, combined.java_class());
+}
+
+#[test]
+fn process_java_class() {
+    let content = include_bytes!("../testdata/Greeter.java8.class");
+    let processed = process_stream(&mut Cursor::new(content)).expect("must process");
+    let expected = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("must fingerprint");
+    assert_eq!(expected, processed.to_combined());
+
+    let java_class = processed.java_class().as_ref().expect("must be a class");
+    assert!(!java_class.content().contains("Greeter.java"));
+}
+
+#[test]
+fn native_sections_ignore_build_metadata() {
+    // The same shared library, linked with a different build ID and with debug information.
+    let release = include_bytes!("../testdata/libanswer.elf");
+    let debug = include_bytes!("../testdata/libanswer.debug.elf");
+
+    let release = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(release))
+        .expect("must fingerprint");
+    let debug =
+        fingerprint_stream_with(KindSet::all(), &mut Cursor::new(debug)).expect("must fingerprint");
+    assert_ne!(release.raw(), debug.raw());
+    assert!(release.native_sections().is_some());
+    assert_eq!(release.native_sections(), debug.native_sections());
+}
+
+#[test]
+fn native_sections_do_not_fingerprint_other_files() {
+    let text = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let combined = fingerprint_stream(&mut Cursor::new(text)).expect("must fingerprint");
+    assert_eq!(&None, combined.native_sections());
+
+    let class = include_bytes!("../testdata/Greeter.java8.class");
+    let combined = fingerprint_stream(&mut Cursor::new(class)).expect("must fingerprint");
+    assert_eq!(&None, combined.native_sections());
+}
+
+#[test]
+fn process_native_sections() {
+    let content = include_bytes!("../testdata/libanswer.debug.elf");
+    let processed = process_stream(&mut Cursor::new(content)).expect("must process");
+    let expected = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("must fingerprint");
+    assert_eq!(expected, processed.to_combined());
+
+    let sections = processed
+        .native_sections()
+        .as_ref()
+        .expect("must be a native binary");
+    assert!(sections.content().contains("hello from a shared library"));
+    assert!(!sections.content().contains(".debug_info"));
+}
+
+#[test]
+fn walk_fingerprints_files() {
+    let dir = tempfile::tempdir().expect("must create temp dir");
+    std::fs::create_dir(dir.path().join("nested")).expect("must create dir");
+    std::fs::write(dir.path().join("a.txt"), "hello\r\n").expect("must write");
+    std::fs::write(dir.path().join("nested").join("b.txt"), "world\n").expect("must write");
+
+    let walked = std::sync::Mutex::new(Vec::new());
+    let count = walk::Walker::new()
+        .parallelism(std::num::NonZeroUsize::new(1).expect("must be nonzero"))
+        .walk(dir.path(), |entry| {
+            walked.lock().expect("must lock").push(entry.into_parts());
+            Ok::<_, Error>(())
+        })
+        .expect("must walk");
+
+    let mut walked = walked.into_inner().expect("must lock");
+    walked.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let expected = vec![
+        (
+            PathBuf::from("a.txt"),
+            fingerprint_stream(&mut Cursor::new(b"hello\r\n")).expect("must fingerprint"),
+        ),
+        (
+            Path::new("nested").join("b.txt"),
+            fingerprint_stream(&mut Cursor::new(b"world\n")).expect("must fingerprint"),
+        ),
+    ];
+    assert_eq!(count, 2);
+    assert_eq!(walked, expected);
+}
+
+#[test]
+fn walk_selected_kinds_with_cache() {
+    let dir = tempfile::tempdir().expect("must create temp dir");
+    let file = dir.path().join("a.txt");
+    std::fs::write(&file, "hello").expect("must write");
+    let cache_dir = tempfile::tempdir().expect("must create temp dir");
+    let cache_path = cache_dir.path().join("cache.json");
+    let key = cache::Key::for_file(&file).expect("must create key");
+
+    // The cache holds the default kinds, so it isn't used when other kinds are selected.
+    let cache = cache::Cache::open(&cache_path);
+    walk::Walker::new()
+        .kinds(KindSet::raw())
+        .cache(&cache)
+        .walk(dir.path(), |entry| {
+            assert_eq!(&None, entry.combined().comment_stripped());
+            Ok::<_, Error>(())
+        })
+        .expect("must walk");
+    cache.save().expect("must save");
+    assert_eq!(None, cache::Cache::open(&cache_path).get(&key));
+
+    let cache = cache::Cache::open(&cache_path);
+    walk::Walker::new()
+        .cache(&cache)
+        .walk(dir.path(), |_| Ok::<_, Error>(()))
+        .expect("must walk");
+    cache.save().expect("must save");
+    assert!(cache::Cache::open(&cache_path).get(&key).is_some());
+}
+
+#[test]
+fn walk_does_not_cache_archived_files() {
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/reproducible");
+    let dir = tempfile::tempdir().expect("must create temp dir");
+    let archive = dir.path().join("archive.tar");
+    let cache_dir = tempfile::tempdir().expect("must create temp dir");
+    let cache_path = cache_dir.path().join("cache.json");
+
+    // Both archives hold `inner.txt` with the same size and modification time, but different content,
+    // as a reproducible build would after the content changes.
+    let walk = |tarball: &str| {
+        std::fs::copy(testdata.join(tarball), &archive).expect("must copy");
+        let cache = cache::Cache::open(&cache_path);
+        let walked = std::sync::Mutex::new(Vec::new());
+        walk::Walker::new()
+            .cache(&cache)
+            .walk(dir.path(), |entry| {
+                walked.lock().expect("must lock").push(entry.into_parts());
+                Ok::<_, Error>(())
+            })
+            .expect("must walk");
+        cache.save().expect("must save");
+        walked.into_inner().expect("must lock")
+    };
+    let inner = |walked: Vec<(PathBuf, Combined)>| {
+        walked
+            .into_iter()
+            .find(|(path, _)| path.ends_with("inner.txt"))
+            .map(|(_, combined)| combined)
+            .expect("must walk inner file")
+    };
+
+    let hello = fingerprint_stream(&mut Cursor::new(b"hello")).expect("must fingerprint");
+    let world = fingerprint_stream(&mut Cursor::new(b"world")).expect("must fingerprint");
+    assert_eq!(inner(walk("a.tar")), hello);
+    assert_eq!(inner(walk("b.tar")), world);
+}
+
+#[test]
+fn walk_stops_on_error() {
+    let dir = tempfile::tempdir().expect("must create temp dir");
+    std::fs::write(dir.path().join("a.txt"), "hello").expect("must write");
+
+    let result = walk::Walker::new().walk(dir.path(), |_| {
+        Err(Error::UnknownKind(String::from("stop")))
+    });
+    assert!(matches!(result, Err(Error::UnknownKind(_))));
+}
+
+#[test]
+fn compare_identical() {
+    let content = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let combined = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(content))
+        .expect("must fingerprint");
+
+    let comparison = compare::compare(&combined, &combined);
+    assert_eq!(comparison.verdict(), compare::Verdict::Identical);
+    assert!(comparison.exact().contains(&KindId::RawSHA256));
+    assert_eq!(comparison.ssdeep(), Some(100));
+}
+
+#[test]
+fn compare_modified_copy() {
+    let content = include_str!("../testdata/facebook-folly-Version.cpp").replace("\r\n", "\n");
+    let original = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(&content))
+        .expect("must fingerprint");
+
+    // Only comments differ, so the comment stripped fingerprints match exactly.
+    let commented = content.replacen("#include", "// Vendored.\n#include", 1);
+    let commented = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(commented))
+        .expect("must fingerprint");
+    let comparison = compare::compare(&original, &commented);
+    assert_eq!(comparison.verdict(), compare::Verdict::LikelyModifiedCopy);
+    assert!(comparison.exact().contains(&KindId::CommentStrippedSHA256));
+    assert!(!comparison.exact().contains(&KindId::RawSHA256));
+
+    // Code differs, so only the fuzzy kinds relate the files.
+    let patched = content.replacen("#include", "int vendored = 1;\n#include", 1);
+    let patched = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(patched))
+        .expect("must fingerprint");
+    let comparison = compare::compare(&original, &patched);
+    assert_eq!(comparison.verdict(), compare::Verdict::LikelyModifiedCopy);
+    assert!(comparison.exact().is_empty());
+
+    let strict = compare::Thresholds::default()
+        .with_ssdeep(101)
+        .with_tlsh(0)
+        .with_jaccard(1.1);
+    let comparison = compare::compare_with(&original, &patched, strict);
+    assert_eq!(comparison.verdict(), compare::Verdict::Different);
+}
+
+#[test]
+fn compare_different() {
+    let text = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let binary = include_bytes!("../testdata/eftest.key");
+    let text = fingerprint_stream(&mut Cursor::new(text)).expect("must fingerprint");
+    let binary = fingerprint_stream(&mut Cursor::new(binary)).expect("must fingerprint");
+
+    let comparison = compare::compare(&text, &binary);
+    assert_eq!(comparison.verdict(), compare::Verdict::Different);
+    assert!(comparison.exact().is_empty());
+}
+
+/// A fingerprint whose content is the byte repeated `len` times.
+fn repeated<K: Kind>(byte: u8, len: usize) -> Fingerprint<K> {
+    Fingerprint::builder()
+        .content(Blob(vec![byte; len]))
+        .kind(PhantomData {})
+        .build()
+}
+
+#[test]
+fn binary_encoding_golden_raw_only() {
+    let combined = Combined::builder()
+        .raw(repeated(0x11, 32))
+        .comment_stripped(None)
+        .build();
+    let golden = [&[1][..], &[0x11; 32], &[0]].concat();
+
+    assert_eq!(
+        serialize::binary::encode(&combined).expect("must encode"),
+        golden
+    );
+    assert_eq!(
+        serialize::binary::decode(&golden).expect("must decode"),
+        combined
+    );
+}
+
+#[test]
+fn binary_encoding_golden() {
+    let combined = Combined::builder()
+        .raw(repeated(0x11, 32))
+        .comment_stripped(Some(repeated(0x22, 32)))
+        .ssdeep(Some(repeated(0x33, 3)))
+        .winnowing(Some(repeated(0x55, 200)))
+        .byte_exact(Some(repeated(0x88, 32)))
+        .build();
+    let golden = [
+        &[1][..],
+        &[0x11; 32],
+        &[4],
+        &[1, 32],
+        &[0x22; 32],
+        &[3, 3],
+        &[0x33; 3],
+        // 200 is encoded as a two byte varint.
+        &[5, 0xC8, 0x01],
+        &[0x55; 200],
+        &[8, 32],
+        &[0x88; 32],
+    ]
+    .concat();
+
+    assert_eq!(
+        serialize::binary::encode(&combined).expect("must encode"),
+        golden
+    );
+    assert_eq!(
+        serialize::binary::decode(&golden).expect("must decode"),
+        combined
+    );
+}
+
+#[test]
+fn binary_encoding_golden_metadata() {
+    let combined = Combined::builder()
+        .raw(repeated(0x11, 32))
+        .comment_stripped(None)
+        .metadata(Some(Metadata::new(300, true)))
+        .build();
+    // 300 is encoded as a two byte varint, followed by the binary flag.
+    let golden = [&[1][..], &[0x11; 32], &[1], &[128, 3, 0xAC, 0x02, 1]].concat();
+
+    assert_eq!(
+        serialize::binary::encode(&combined).expect("must encode"),
+        golden
+    );
+    assert_eq!(
+        serialize::binary::decode(&golden).expect("must decode"),
+        combined
+    );
+}
+
+#[test]
+fn binary_encoding_round_trip_stream() {
+    let text = include_bytes!("../testdata/facebook-folly-Version.cpp");
+    let binary = include_bytes!("../testdata/eftest.key");
+    let text =
+        fingerprint_stream_with(KindSet::all(), &mut Cursor::new(text)).expect("must fingerprint");
+    let binary = fingerprint_stream_with(KindSet::all(), &mut Cursor::new(binary))
+        .expect("must fingerprint");
+
+    let mut encoded = Vec::new();
+    for combined in [&text, &binary] {
+        serialize::binary::encode_to(combined, &mut encoded).expect("must encode");
+    }
+
+    let mut stream = Cursor::new(encoded);
+    let decode =
+        |stream: &mut Cursor<Vec<u8>>| serialize::binary::decode_from(stream).expect("must decode");
+    assert_eq!(decode(&mut stream), Some(text));
+    assert_eq!(decode(&mut stream), Some(binary));
+    assert_eq!(decode(&mut stream), None);
+}
+
+/// A reader that is interrupted before each successful read.
+struct Interrupting<R> {
+    inner: R,
+    interrupt: bool,
+}
+
+impl<R: std::io::Read> std::io::Read for Interrupting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+        self.inner.read(buf)
+    }
+}
+
+#[test]
+fn binary_encoding_retries_interrupted_reads() {
+    let combined = fingerprint_stream(&mut Cursor::new(b"hello world")).expect("must fingerprint");
+    let encoded = serialize::binary::encode(&combined).expect("must encode");
+
+    let mut stream = Interrupting {
+        inner: Cursor::new(encoded),
+        interrupt: false,
+    };
+    let decoded = serialize::binary::decode_from(&mut stream).expect("must decode");
+    assert_eq!(decoded, Some(combined));
+    let decoded = serialize::binary::decode_from(&mut stream).expect("must decode");
+    assert_eq!(decoded, None);
+}
+
+#[test]
+fn binary_encoding_rejects_invalid_raw() {
+    let combined = Combined::builder()
+        .raw(repeated(0x11, 20))
+        .comment_stripped(None)
+        .build();
+    assert!(serialize::binary::encode(&combined).is_err());
+    assert!(serialize::binary::encode(&Combined::default()).is_err());
+}
+
+#[test]
+fn binary_encoding_skips_unknown_kinds() {
+    // A record from a later version, with a kind (tag 200) this version doesn't know.
+    let encoded = [&[1][..], &[0x11; 32], &[2], &[3, 1, 0x33], &[200, 2, 0, 0]].concat();
+    let combined = serialize::binary::decode(&encoded).expect("must decode");
+    assert_eq!(combined.ssdeep(), &Some(repeated(0x33, 1)));
+}
+
+#[test]
+fn binary_encoding_rejects_invalid() {
+    let valid = [&[1][..], &[0x11; 32], &[0]].concat();
+    let invalid = [
+        [&[2][..], &valid[1..]].concat(),
+        valid[..20].to_vec(),
+        [&valid[..], &[0]].concat(),
+        // Tags out of order.
+        [&[1][..], &[0x11; 32], &[2], &[3, 1, 0x33], &[1, 1, 0x22]].concat(),
+    ];
+    for encoded in invalid {
+        assert!(serialize::binary::decode(&encoded).is_err(), "{encoded:?}");
+    }
+}
+
+#[test]
+fn metadata_not_captured_by_default() {
+    let combined = fingerprint_stream(&mut Cursor::new(b"hello world")).expect("must fingerprint");
+    assert_eq!(combined.metadata(), &None);
+
+    let json = serde_json::to_value(&combined).expect("must serialize");
+    assert!(json.get("metadata").is_none());
+}
+
+#[test]
+fn metadata_captured() {
+    let capture = |content: &[u8]| {
+        let mut hasher = CombinedHasher::default().with_metadata();
+        hasher.update(content).expect("must hash");
+        hasher.finalize().expect("must finalize")
+    };
+
+    let text = b"hello world\r\n";
+    let combined = capture(text);
+    assert_eq!(combined.metadata(), &Some(Metadata::new(13, false)));
+
+    let binary = vec![1, 2, 3, 0, 1, 2, 3];
+    let combined = capture(&binary);
+    assert_eq!(combined.metadata(), &Some(Metadata::new(7, true)));
+
+    // Fingerprints serialized before metadata was added still deserialize.
+    let mut json = serde_json::to_value(&combined).expect("must serialize");
+    json.as_object_mut()
+        .expect("must be object")
+        .remove("metadata");
+    let combined = serde_json::from_value::<Combined>(json).expect("must deserialize");
+    assert_eq!(combined.metadata(), &None);
+}
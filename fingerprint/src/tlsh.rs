@@ -0,0 +1,308 @@
+//! Trend Micro locality sensitive hashing (TLSH).
+//!
+//! Each window of five bytes in the content adds to a histogram of triplets of those bytes,
+//! and the digest records which quartile each bucket of the histogram falls in,
+//! along with the length of the content and the shape of the histogram.
+//! Content that differs only in a few places has a nearly identical histogram,
+//! so the distance between two digests estimates how different their contents are.
+//!
+//! This is the standard TLSH configuration of 128 buckets and a 1 byte checksum.
+//! Digests are laid out as TLSH lays them out before encoding them as hex,
+//! so the hex encoding of a digest is the same as the TLSH digest (without the `T1` version prefix).
+
+use std::io::{self, Write};
+
+/// The number of buckets in the histogram that are used in the digest.
+const BUCKETS: usize = 128;
+
+/// The number of bytes in the digest body; each byte records the quartiles of four buckets.
+const CODE_SIZE: usize = BUCKETS / 4;
+
+/// The number of bytes in the digest header: the checksum, the length, and the quartile ratios.
+const HEADER_SIZE: usize = 3;
+
+/// The number of bytes in a digest.
+pub(crate) const DIGEST_SIZE: usize = HEADER_SIZE + CODE_SIZE;
+
+/// The size of the sliding window over the content.
+const WINDOW: usize = 5;
+
+/// Content shorter than this doesn't produce a meaningful histogram.
+const MIN_LENGTH: u64 = 50;
+
+/// The permutation used for Pearson hashing of byte triplets.
+const PEARSON: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163, 14, 197, 213, 181, 161,
+    85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200, 110, 177, 104, 103, 141, 253, 255, 50, 77,
+    101, 81, 18, 45, 96, 31, 222, 25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227,
+    149, 235, 97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248, 174, 169,
+    211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243, 132, 56, 148, 75, 128, 133,
+    158, 100, 130, 126, 91, 13, 153, 246, 216, 219, 119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92,
+    32, 136, 114, 52, 10, 138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+    170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131, 125, 173, 15, 238, 79,
+    95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123, 118, 73, 2, 157, 46, 116, 9, 145, 134, 228,
+    207, 212, 202, 215, 69, 229, 27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39,
+    203, 233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76, 140, 36, 210,
+    172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120, 51, 65, 28, 144, 254, 221, 93, 189,
+    194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+fn pearson(salt: u8, a: u8, b: u8, c: u8) -> u8 {
+    let h = PEARSON[usize::from(salt)];
+    let h = PEARSON[usize::from(h ^ a)];
+    let h = PEARSON[usize::from(h ^ b)];
+    PEARSON[usize::from(h ^ c)]
+}
+
+fn swap_nibbles(b: u8) -> u8 {
+    b.rotate_left(4)
+}
+
+/// Computes the digest for content written to it.
+pub(crate) struct Hasher {
+    window: [u8; WINDOW],
+    buckets: [u32; 256],
+    checksum: u8,
+    total: u64,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self {
+            window: [0; WINDOW],
+            buckets: [0; 256],
+            checksum: 0,
+            total: 0,
+        }
+    }
+}
+
+impl Hasher {
+    fn update(&mut self, c: u8) {
+        let position = (self.total % WINDOW as u64) as usize;
+        self.window[position] = c;
+        self.total += 1;
+        if self.total < WINDOW as u64 {
+            return;
+        }
+
+        // `w[n]` is the byte read `n` bytes before the current one.
+        let w = |n: usize| self.window[(position + WINDOW - n) % WINDOW];
+        let (w0, w1, w2, w3, w4) = (w(0), w(1), w(2), w(3), w(4));
+
+        self.checksum = pearson(0, w0, w1, self.checksum);
+        for (salt, a, b) in [
+            (2, w1, w2),
+            (3, w1, w3),
+            (5, w2, w3),
+            (7, w2, w4),
+            (11, w1, w4),
+            (13, w3, w4),
+        ] {
+            let bucket = pearson(salt, w0, a, b);
+            self.buckets[usize::from(bucket)] += 1;
+        }
+    }
+
+    /// Finish hashing, returning the digest.
+    ///
+    /// Returns `None` if the content is too short or too uniform to produce a meaningful digest.
+    pub(crate) fn finish(self) -> Option<[u8; DIGEST_SIZE]> {
+        if self.total < MIN_LENGTH {
+            return None;
+        }
+
+        let buckets = &self.buckets[..BUCKETS];
+        let nonzero = buckets.iter().filter(|&&count| count > 0).count();
+        if nonzero <= BUCKETS / 2 {
+            return None;
+        }
+
+        let mut sorted = buckets.to_vec();
+        sorted.sort_unstable();
+        let quarter = BUCKETS / 4;
+        let q1 = sorted[quarter - 1];
+        let q2 = sorted[2 * quarter - 1];
+        let q3 = sorted[3 * quarter - 1];
+        if q3 == 0 {
+            return None;
+        }
+
+        let mut digest = [0; DIGEST_SIZE];
+        digest[0] = swap_nibbles(self.checksum);
+        digest[1] = swap_nibbles(length_code(self.total));
+        let q1_ratio = (u64::from(q1) * 100 / u64::from(q3) % 16) as u8;
+        let q2_ratio = (u64::from(q2) * 100 / u64::from(q3) % 16) as u8;
+        digest[2] = (q2_ratio << 4) | q1_ratio;
+
+        // The body is written in reverse, so the last four buckets are encoded by the first byte.
+        for (i, group) in buckets.chunks(4).enumerate() {
+            let code = group.iter().enumerate().fold(0, |code, (j, &count)| {
+                let quartile = if count > q3 {
+                    3
+                } else if count > q2 {
+                    2
+                } else if count > q1 {
+                    1
+                } else {
+                    0
+                };
+                code | quartile << (j * 2)
+            });
+            digest[DIGEST_SIZE - 1 - i] = code;
+        }
+
+        Some(digest)
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &c in buf {
+            self.update(c);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encode the length of the content on a logarithmic scale, which is finer for shorter content.
+fn length_code(len: u64) -> u8 {
+    let log = (len as f64).ln();
+    let code = if len <= 656 {
+        log / 1.5_f64.ln()
+    } else if len <= 3199 {
+        log / 1.3_f64.ln() - 8.72777
+    } else {
+        log / 1.1_f64.ln() - 62.5472
+    };
+    (code.floor() as i64 & 0xFF) as u8
+}
+
+/// The distance between two values on a ring of the given size.
+fn ring_distance(a: u8, b: u8, size: u32) -> u32 {
+    let (a, b) = (u32::from(a), u32::from(b));
+    let direct = a.abs_diff(b);
+    direct.min(size - direct)
+}
+
+/// Compute the distance between two digests, returning `None` if either is not a valid digest.
+///
+/// A distance of 0 means the contents are identical or nearly so; the distance grows without a fixed bound
+/// as the contents diverge, with distances above a few hundred indicating unrelated contents.
+pub(crate) fn distance(a: &[u8], b: &[u8]) -> Option<u32> {
+    let a: &[u8; DIGEST_SIZE] = a.try_into().ok()?;
+    let b: &[u8; DIGEST_SIZE] = b.try_into().ok()?;
+    let mut distance = 0;
+
+    // The length of the content.
+    distance += match ring_distance(swap_nibbles(a[1]), swap_nibbles(b[1]), 256) {
+        d @ (0 | 1) => d,
+        d => d * 12,
+    };
+
+    // The shape of the histogram.
+    for shift in [0, 4] {
+        let ratio = |digest: &[u8; DIGEST_SIZE]| (digest[2] >> shift) & 0x0F;
+        distance += match ring_distance(ratio(a), ratio(b), 16) {
+            d @ (0 | 1) => d,
+            d => (d - 1) * 12,
+        };
+    }
+
+    if a[0] != b[0] {
+        distance += 1;
+    }
+
+    // The body, in which buckets that are in quartiles at opposite ends are penalized more heavily.
+    for (&x, &y) in a[HEADER_SIZE..].iter().zip(&b[HEADER_SIZE..]) {
+        for shift in (0..8).step_by(2) {
+            distance += match ((x >> shift) & 0b11).abs_diff((y >> shift) & 0b11) {
+                3 => 6,
+                d => u32::from(d),
+            };
+        }
+    }
+
+    Some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use super::*;
+
+    fn digest(content: &[u8]) -> Option<[u8; DIGEST_SIZE]> {
+        let mut hasher = Hasher::default();
+        hasher.write_all(content).expect("must hash");
+        hasher.finish()
+    }
+
+    /// Deterministic content that looks like a binary, so that it fills the histogram.
+    fn binary(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pearson_is_permutation() {
+        let mut sorted = PEARSON.to_vec();
+        sorted.sort_unstable();
+        assert!(sorted.iter().enumerate().all(|(i, &b)| usize::from(b) == i));
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(digest(&binary(49, 1)), None);
+        assert!(digest(&binary(512, 1)).is_some());
+    }
+
+    #[test]
+    fn too_uniform() {
+        assert_eq!(digest(&[0; 4096]), None);
+    }
+
+    #[test]
+    fn length_codes() {
+        assert_eq!(length_code(50), 9);
+        assert_eq!(length_code(656), 15);
+        assert_eq!(length_code(3199), 22);
+        assert_eq!(length_code(1 << 20), 82);
+    }
+
+    #[test]
+    fn distance_identical() {
+        let a = digest(&binary(8192, 1)).expect("must digest");
+        assert_eq!(distance(&a, &a), Some(0));
+    }
+
+    #[test]
+    fn distance_similar_and_unrelated() {
+        let original = binary(8192, 1);
+        let mut patched = original.clone();
+        patched[4000..4032].copy_from_slice(&binary(32, 2));
+
+        let original = digest(&original).expect("must digest");
+        let patched = digest(&patched).expect("must digest");
+        let unrelated = digest(&binary(8192, 3)).expect("must digest");
+
+        let near = distance(&original, &patched).expect("valid digests");
+        let far = distance(&original, &unrelated).expect("valid digests");
+        assert!(near > 0 && near < far, "near: {near}, far: {far}");
+    }
+
+    #[test]
+    fn distance_invalid() {
+        let a = digest(&binary(8192, 1)).expect("must digest");
+        assert_eq!(distance(&a, &a[1..]), None);
+    }
+}
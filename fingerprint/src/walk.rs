@@ -0,0 +1,183 @@
+//! Walks a directory, expanding any archives it contains, and fingerprints each file found.
+//!
+//! This composes [`archive::expand::walk()`] with fingerprinting, so that consumers
+//! (such as VSI scans) don't each need to reimplement the glue between the two.
+
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use getset::Getters;
+use rayon::{prelude::*, ThreadPoolBuilder};
+
+use crate::{
+    cache::{Cache, Key},
+    fingerprint_stream_with, Combined, Error, KindSet,
+};
+
+/// A file discovered and fingerprinted by a [`Walker`].
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct Walked {
+    /// The logical path of the file, relative to the walked directory.
+    /// Files inside archives are reported as though the archive was a directory; see [`archive::expand::walk()`].
+    path: PathBuf,
+
+    /// The fingerprints of the file.
+    combined: Combined,
+
+    /// Non-fatal errors encountered while walking the file,
+    /// such as the reason an archive failed to expand; see [`archive::expand::walk::Entry::warnings`].
+    warnings: Vec<archive::Error>,
+}
+
+impl Walked {
+    /// Consume the instance, returning the logical path and the fingerprints.
+    pub fn into_parts(self) -> (PathBuf, Combined) {
+        (self.path, self.combined)
+    }
+}
+
+/// Walks a directory, expanding archives, and fingerprints each file found in parallel.
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use fingerprint::{walk::Walker, Error};
+/// # fn main() -> Result<(), Error> {
+/// let walked = Walker::new().walk(Path::new("vendor"), |walked| {
+///     let (path, combined) = walked.into_parts();
+///     println!("{path:?}: {combined}");
+///     Ok::<_, Error>(())
+/// })?;
+/// println!("fingerprinted {walked} files");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Walker<'a> {
+    archive: archive::Options,
+    kinds: KindSet,
+    parallelism: Option<NonZeroUsize>,
+    cache: Option<&'a Cache>,
+}
+
+impl<'a> Walker<'a> {
+    /// Create a walker that computes the default fingerprint [`crate::Kind`]s (see [`KindSet::default`]),
+    /// using the default archive options and the global rayon thread pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the options used to walk the directory and expand archives.
+    ///
+    /// In particular, a cancellation token provided here stops the walk promptly once cancelled.
+    pub fn archive_options(self, archive: archive::Options) -> Self {
+        Self { archive, ..self }
+    }
+
+    /// Set the fingerprint [`crate::Kind`]s to compute.
+    pub fn kinds(self, kinds: KindSet) -> Self {
+        Self { kinds, ..self }
+    }
+
+    /// Set the maximum number of files fingerprinted at once.
+    ///
+    /// Files are fingerprinted on a dedicated thread pool of this size.
+    /// If not set, files are fingerprinted on the global rayon thread pool.
+    pub fn parallelism(self, parallelism: NonZeroUsize) -> Self {
+        Self {
+            parallelism: Some(parallelism),
+            ..self
+        }
+    }
+
+    /// Reuse fingerprints from the cache for files that are unchanged since they were cached,
+    /// and record the fingerprints of other files in the cache.
+    ///
+    /// Files are identified in the cache by their path, size, and modification time.
+    /// Files inside archives aren't cached: their modification time comes from the archive,
+    /// which reproducible builds set to a fixed value, so a changed file could look unchanged.
+    /// The cache is only used when its kinds (see [`Cache::kinds`]) are the kinds computed by the walker.
+    pub fn cache(self, cache: &'a Cache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Walk the directory, calling `f` with each file as it is fingerprinted.
+    /// Returns the number of files fingerprinted.
+    ///
+    /// Files are fingerprinted in parallel, so `f` is called concurrently and in no particular order.
+    /// The walk stops at the first error, whether encountered while walking, fingerprinting, or returned by `f`,
+    /// and that error is returned; `f` may still be called for files that were being fingerprinted at the time.
+    pub fn walk<F, E>(&self, root: &Path, f: F) -> Result<usize, E>
+    where
+        F: Fn(Walked) -> Result<(), E> + Sync + Send,
+        E: From<Error> + Send,
+    {
+        let walked = AtomicUsize::new(0);
+        let run = || {
+            archive::expand::walk(root.to_path_buf().into(), self.archive.clone())
+                .par_bridge()
+                .try_for_each(|entry| {
+                    let mut entry = entry.map_err(Error::from)?;
+                    let combined = self.fingerprint_entry(root, &mut entry).map_err(|err| {
+                        Error::WalkEntry(entry.path().to_path_buf(), Box::new(err))
+                    })?;
+                    let warnings = entry.take_warnings();
+
+                    walked.fetch_add(1, Ordering::Relaxed);
+                    f(Walked {
+                        path: entry.into_path(),
+                        combined,
+                        warnings,
+                    })
+                })
+        };
+
+        // If the dedicated pool can't be built, fall back to the global pool rather than failing the walk.
+        let pool = self.parallelism.and_then(|parallelism| {
+            ThreadPoolBuilder::new()
+                .num_threads(parallelism.get())
+                .build()
+                .ok()
+        });
+        match pool {
+            Some(pool) => pool.install(run)?,
+            None => run()?,
+        }
+
+        Ok(walked.into_inner())
+    }
+
+    /// Fingerprint the entry, using the cached fingerprint if the entry is unchanged since it was cached.
+    fn fingerprint_entry(
+        &self,
+        root: &Path,
+        entry: &mut archive::expand::walk::Entry,
+    ) -> Result<Combined, Error> {
+        let cache = self.cache.filter(|cache| cache.kinds() == self.kinds);
+        let metadata = entry.metadata();
+        let key = metadata
+            .modified()
+            .filter(|_| !metadata.archived())
+            .map(|modified| Key::new(root.join(entry.path()), metadata.size(), modified));
+        if let Some(combined) = cache
+            .zip(key.as_ref())
+            .and_then(|(cache, key)| cache.get(key))
+        {
+            return Ok(combined);
+        }
+
+        // Entries inside archives may not exist at their logical path, so they must be read through the entry.
+        let combined = fingerprint_stream_with(self.kinds, &mut entry.reader()?)?;
+        if let (Some(cache), Some(key)) = (cache, key) {
+            cache.insert(key, combined.clone());
+        }
+
+        Ok(combined)
+    }
+}
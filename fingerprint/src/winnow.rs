@@ -0,0 +1,241 @@
+//! Winnowing sketches of source text, for detecting partial copies.
+//!
+//! Every k-gram of the text (ignoring whitespace) is hashed, and winnowing selects the minimum hash
+//! from each window of consecutive k-grams; any run of text at least as long as a window plus a k-gram
+//! that is shared by two texts is guaranteed to select at least one hash in common.
+//! The sketch keeps a bounded number of the smallest selected hashes (a "bottom-k" MinHash sketch),
+//! which is enough to estimate the Jaccard similarity of the full sets of selected hashes.
+//!
+//! Sketches are encoded as the selected hashes in ascending order, each as 8 big endian bytes.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    io::{self, Write},
+};
+
+/// The number of (non-whitespace) bytes in each k-gram.
+const K: usize = 16;
+
+/// The number of consecutive k-grams in each winnowing window.
+const WINDOW: usize = 8;
+
+/// The maximum number of hashes kept in a sketch.
+const SKETCH_SIZE: usize = 64;
+
+/// The base of the polynomial rolling hash over k-grams.
+const BASE: u64 = 0x100_0000_01b3;
+
+/// Mix the bits of a rolling hash, so that the smallest hashes are a uniform sample of the k-grams.
+fn mix(mut h: u64) -> u64 {
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+/// Computes the sketch for content written to it.
+pub(crate) struct Hasher {
+    /// The bytes of the current k-gram.
+    gram: VecDeque<u8>,
+    /// The rolling hash of the current k-gram.
+    rolling: u64,
+    /// `BASE` raised to `K - 1`, used to remove the oldest byte from the rolling hash.
+    leading: u64,
+    /// The hashes of the k-grams in the current window, along with their position.
+    window: VecDeque<(u64, u64)>,
+    /// The number of k-grams hashed.
+    grams: u64,
+    /// The position of the most recently selected hash, so that a hash is only selected once.
+    selected: Option<u64>,
+    sketch: BTreeSet<u64>,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self {
+            gram: VecDeque::with_capacity(K),
+            rolling: 0,
+            leading: (1..K).fold(1, |acc: u64, _| acc.wrapping_mul(BASE)),
+            window: VecDeque::with_capacity(WINDOW),
+            grams: 0,
+            selected: None,
+            sketch: BTreeSet::new(),
+        }
+    }
+}
+
+impl Hasher {
+    fn update(&mut self, c: u8) {
+        if c.is_ascii_whitespace() {
+            return;
+        }
+
+        if self.gram.len() == K {
+            let oldest = self.gram.pop_front().unwrap_or_default();
+            self.rolling = self
+                .rolling
+                .wrapping_sub(u64::from(oldest).wrapping_mul(self.leading));
+        }
+        self.gram.push_back(c);
+        self.rolling = self.rolling.wrapping_mul(BASE).wrapping_add(u64::from(c));
+        if self.gram.len() < K {
+            return;
+        }
+
+        let position = self.grams;
+        self.grams += 1;
+        if self.window.len() == WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back((mix(self.rolling), position));
+        if self.window.len() == WINDOW {
+            self.select();
+        }
+    }
+
+    /// Select the minimum hash in the current window, preferring the rightmost on ties.
+    fn select(&mut self) {
+        let minimum = self
+            .window
+            .iter()
+            .copied()
+            .reduce(|min, next| if next.0 <= min.0 { next } else { min });
+        if let Some((hash, position)) = minimum {
+            if self.selected != Some(position) {
+                self.selected = Some(position);
+                self.insert(hash);
+            }
+        }
+    }
+
+    fn insert(&mut self, hash: u64) {
+        self.sketch.insert(hash);
+        if self.sketch.len() > SKETCH_SIZE {
+            self.sketch.pop_last();
+        }
+    }
+
+    /// Finish hashing, returning the encoded sketch.
+    ///
+    /// Returns `None` if the content is too short to contain a k-gram.
+    pub(crate) fn finish(mut self) -> Option<Vec<u8>> {
+        // Content shorter than a window still has k-grams worth sketching; treat it as a single window.
+        if self.grams > 0 && self.window.len() < WINDOW {
+            self.select();
+        }
+        if self.sketch.is_empty() {
+            return None;
+        }
+        Some(self.sketch.iter().flat_map(|h| h.to_be_bytes()).collect())
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &c in buf {
+            self.update(c);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decode an encoded sketch, returning `None` if it is not valid.
+fn decode(sketch: &[u8]) -> Option<BTreeSet<u64>> {
+    let chunks = sketch.chunks_exact(8);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks
+        .map(|chunk| chunk.try_into().ok().map(u64::from_be_bytes))
+        .collect()
+}
+
+/// Estimate the Jaccard similarity of the texts from which two sketches were created,
+/// from 0.0 (nothing in common) to 1.0 (the same, or nearly so).
+///
+/// Returns `None` if either sketch is not valid.
+pub(crate) fn jaccard(a: &[u8], b: &[u8]) -> Option<f64> {
+    let a = decode(a)?;
+    let b = decode(b)?;
+
+    // The smallest hashes of the union are a uniform sample of the union,
+    // and since each sketch holds the smallest hashes of its own set,
+    // whether each sampled hash is in both sets can be determined exactly.
+    let union = a.union(&b).take(SKETCH_SIZE).collect::<Vec<_>>();
+    if union.is_empty() {
+        return None;
+    }
+    let shared = union
+        .iter()
+        .filter(|h| a.contains(h) && b.contains(h))
+        .count();
+    Some(shared as f64 / union.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for internal logic.
+
+    use super::*;
+
+    fn sketch(content: &[u8]) -> Option<Vec<u8>> {
+        let mut hasher = Hasher::default();
+        hasher.write_all(content).expect("must hash");
+        hasher.finish()
+    }
+
+    /// Deterministic content that looks like source text.
+    fn text(from: usize, to: usize) -> String {
+        (from..to)
+            .map(|i| format!("let value_{i} = compute({i}, {});\n", i * 7919 % 104729))
+            .collect()
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(sketch(b"int x;"), None);
+        assert!(sketch(b"int main() { return 0; }").is_some());
+    }
+
+    #[test]
+    fn bounded() {
+        let encoded = sketch(text(0, 1000).as_bytes()).expect("must sketch");
+        assert_eq!(encoded.len(), SKETCH_SIZE * 8);
+        let decoded = decode(&encoded).expect("must decode");
+        assert_eq!(decoded.len(), SKETCH_SIZE);
+    }
+
+    #[test]
+    fn ignores_whitespace() {
+        let a = text(0, 100);
+        let b = a.replace('\n', "\n    ").replace(' ', "  ");
+        assert_eq!(sketch(a.as_bytes()), sketch(b.as_bytes()));
+    }
+
+    #[test]
+    fn jaccard_identical() {
+        let a = sketch(text(0, 500).as_bytes()).expect("must sketch");
+        assert_eq!(jaccard(&a, &a), Some(1.0));
+    }
+
+    #[test]
+    fn jaccard_partial_copy() {
+        let original = sketch(text(0, 500).as_bytes()).expect("must sketch");
+        let half = sketch(text(250, 750).as_bytes()).expect("must sketch");
+        let unrelated = sketch(text(1000, 1500).as_bytes()).expect("must sketch");
+
+        let partial = jaccard(&original, &half).expect("valid sketches");
+        assert!(partial > 0.15 && partial < 0.6, "partial: {partial}");
+        let none = jaccard(&original, &unrelated).expect("valid sketches");
+        assert!(none < 0.05, "none: {none}");
+    }
+
+    #[test]
+    fn jaccard_invalid() {
+        let a = sketch(text(0, 500).as_bytes()).expect("must sketch");
+        assert_eq!(jaccard(&a, &a[1..]), None);
+    }
+}
@@ -1,6 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use sha2::{Digest, Sha256};
-use snippets::text::ConvertCRLFToLF;
+use snippets::text::{ConvertCRLFToLF, FingerprintAlgorithm};
 
 const INPUT: &[u8] = b"Iste nam laboriosam \r\n voluptatem \n distinctio.";
 
@@ -56,6 +56,18 @@ fn hash_transform_vec() {
     black_box(digest);
 }
 
+fn fingerprint_sha256() {
+    let digest = FingerprintAlgorithm::Sha256.fingerprint(INPUT);
+
+    black_box(digest);
+}
+
+fn fingerprint_blake3() {
+    let digest = FingerprintAlgorithm::Blake3.fingerprint(INPUT);
+
+    black_box(digest);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("hash_slice_update", |b| b.iter(hash_slice_update));
     c.bench_function("hash_slice_digest", |b| b.iter(hash_slice_digest));
@@ -63,6 +75,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("hash_vec", |b| b.iter(hash_vec));
     c.bench_function("hash_transform_iter", |b| b.iter(hash_transform_iter));
     c.bench_function("hash_transform_vec", |b| b.iter(hash_transform_vec));
+    c.bench_function("fingerprint_sha256", |b| b.iter(fingerprint_sha256));
+    c.bench_function("fingerprint_blake3", |b| b.iter(fingerprint_blake3));
 }
 
 criterion_group!(benches, criterion_benchmark);
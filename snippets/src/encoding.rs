@@ -0,0 +1,105 @@
+//! Detects the character encoding of source code, transcoding it to UTF-8 for extraction.
+//!
+//! The parsers used by this library read source code as UTF-8,
+//! but a lot of older source code (especially C code vendored into other projects) is not UTF-8.
+//! Source code in other encodings parses incorrectly; for example every other byte of UTF-16 text is usually `0`.
+//!
+//! Encodings are detected as follows:
+//!
+//! Content | Encoding
+//! ---|---
+//! Starts with a UTF-16 byte order mark | [`SourceEncoding::Utf16Le`] or [`SourceEncoding::Utf16Be`]
+//! Otherwise, valid UTF-8 (including ASCII) | [`SourceEncoding::Utf8`]
+//! Otherwise | [`SourceEncoding::Latin1`]
+//!
+//! Extractors transcode content before parsing it if [`Options::with_transcoding`] is set.
+//! In that case, the locations of snippets are reported in the transcoded content,
+//! which differ from locations in the original content for any non-ASCII text.
+//!
+//! [`Options::with_transcoding`]: crate::Options::with_transcoding
+
+use std::borrow::Cow;
+
+use strum::Display;
+
+/// The character encoding of a unit of source code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+#[strum(serialize_all = "snake_case")]
+#[non_exhaustive]
+pub enum SourceEncoding {
+    /// UTF-8, which includes ASCII.
+    Utf8,
+
+    /// Little endian UTF-16, detected by its byte order mark.
+    Utf16Le,
+
+    /// Big endian UTF-16, detected by its byte order mark.
+    Utf16Be,
+
+    /// Latin-1, which is assumed for any content that is not valid UTF-8.
+    ///
+    /// This is decoded as [windows-1252], which is a superset of the printable characters in ISO-8859-1;
+    /// this is also how web browsers decode content labeled as Latin-1.
+    ///
+    /// [windows-1252]: https://encoding.spec.whatwg.org/#windows-1252
+    Latin1,
+}
+
+impl SourceEncoding {
+    /// Detect the encoding of the content.
+    ///
+    /// ```
+    /// # use snippets::encoding::SourceEncoding;
+    /// assert_eq!(SourceEncoding::detect(b"int main() {}"), SourceEncoding::Utf8);
+    /// assert_eq!(SourceEncoding::detect(b"\xFF\xFEi\0n\0t\0"), SourceEncoding::Utf16Le);
+    /// assert_eq!(SourceEncoding::detect(b"/* caf\xE9 */"), SourceEncoding::Latin1);
+    /// ```
+    pub fn detect(content: &[u8]) -> Self {
+        match encoding_rs::Encoding::for_bom(content) {
+            Some((encoding, _)) if encoding == encoding_rs::UTF_16LE => Self::Utf16Le,
+            Some((encoding, _)) if encoding == encoding_rs::UTF_16BE => Self::Utf16Be,
+            _ if std::str::from_utf8(content).is_ok() => Self::Utf8,
+            _ => Self::Latin1,
+        }
+    }
+
+    fn encoding(self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::Utf8 => encoding_rs::UTF_8,
+            Self::Utf16Le => encoding_rs::UTF_16LE,
+            Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::Latin1 => encoding_rs::WINDOWS_1252,
+        }
+    }
+}
+
+/// Detect the encoding of the content and transcode it to UTF-8.
+///
+/// UTF-8 content is returned as-is, without copying it.
+/// The byte order mark of UTF-16 content is not included in the transcoded content.
+/// Any sequence that is invalid in the detected encoding (such as an unpaired UTF-16 surrogate)
+/// is replaced with the replacement character (`U+FFFD`).
+///
+/// ```
+/// # use snippets::encoding::{transcode, SourceEncoding};
+/// let (encoding, content) = transcode(b"\xFE\xFF\0i\0n\0t");
+/// assert_eq!(encoding, SourceEncoding::Utf16Be);
+/// assert_eq!(content.as_ref(), b"int");
+///
+/// let (encoding, content) = transcode(b"/* caf\xE9 */");
+/// assert_eq!(encoding, SourceEncoding::Latin1);
+/// assert_eq!(content.as_ref(), "/* café */".as_bytes());
+/// ```
+pub fn transcode(content: &[u8]) -> (SourceEncoding, Cow<'_, [u8]>) {
+    let detected = SourceEncoding::detect(content);
+    if detected == SourceEncoding::Utf8 {
+        return (detected, Cow::Borrowed(content));
+    }
+
+    let (decoded, _) = detected.encoding().decode_with_bom_removal(content);
+    let transcoded = match decoded {
+        Cow::Borrowed(decoded) => Cow::Borrowed(decoded.as_bytes()),
+        Cow::Owned(decoded) => Cow::Owned(decoded.into_bytes()),
+    };
+    (detected, transcoded)
+}
@@ -2,6 +2,9 @@
 //!
 //! Any consumer can build their own language support with the [`super::Extractor`] trait,
 //! but this module provides prebuilt language support.
+//!
+//! The prebuilt extractors keep a parser for each language on each thread,
+//! reusing it for every extraction on that thread instead of creating a new parser each time.
 
 #[cfg(feature = "lang-c99-tc3")]
 pub mod c99_tc3;
@@ -9,9 +12,55 @@ pub mod c99_tc3;
 #[cfg(feature = "lang-cpp-98")]
 pub mod cpp_98;
 
+#[cfg(feature = "lang-java")]
+pub mod java;
+
+#[cfg(feature = "lang-javascript")]
+pub mod javascript;
+
+#[cfg(feature = "lang-php")]
+pub mod php;
+
+#[cfg(feature = "lang-swift")]
+pub mod swift;
+
+#[cfg(feature = "lang-typescript")]
+pub mod typescript;
+
+#[cfg(feature = "lang-typescript")]
+pub mod tsx;
+
 pub use normalize_code::*;
 pub use normalize_comments::*;
+pub use normalize_literals::*;
 
+pub(crate) mod methods;
 mod normalize_code;
 mod normalize_comments;
-mod snippet_context;
+mod normalize_literals;
+pub(crate) mod snippet_context;
+pub(crate) mod traversal;
+pub(crate) mod winnow;
+
+#[cfg(any(feature = "lang-c99-tc3", feature = "lang-cpp-98"))]
+mod preprocessor;
+
+#[cfg(any(
+    feature = "lang-c99-tc3",
+    feature = "lang-cpp-98",
+    feature = "lang-java",
+    feature = "lang-javascript",
+    feature = "lang-php",
+    feature = "lang-swift"
+))]
+mod parser_pool;
+
+#[cfg(any(
+    feature = "lang-c99-tc3",
+    feature = "lang-cpp-98",
+    feature = "lang-java",
+    feature = "lang-javascript",
+    feature = "lang-php",
+    feature = "lang-swift"
+))]
+mod scope;
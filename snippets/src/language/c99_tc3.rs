@@ -17,8 +17,17 @@
 //!
 //! That being said, this extractor should generally support newer versions
 //! of the C programming language. This is because this extractor is only
-//! concerned with functions, and a review of the later C standards
-//! does not imply that function parsing has changed.
+//! concerned with functions and macros, and a review of the later C standards
+//! does not imply that parsing them has changed.
+//!
+//! # Targets
+//! This extractor supports extracting the following as snippets from C files:
+//!
+//! * Function Signatures, Bodies, and Full Declarations
+//! * Macro Signatures, Bodies, and Full Definitions
+//!
+//! "Macro" means an object-like (`#define MAX 10`) or function-like (`#define SQUARE(x) ((x) * (x))`)
+//! macro definition with a value. Macros without a value, such as include guards, are not extracted.
 //!
 //! [`Extractor`]: crate::Extractor
 //! [standards]: https://en.wikipedia.org/wiki/C_(programming_language)#History
@@ -28,21 +37,25 @@
 //! [`tree-sitter-c`]: https://github.com/tree-sitter/tree-sitter-c
 //! [`iso-9899-tc3`]: https://github.com/slebok/zoo/tree/master/zoo/c/c99/iso-9899-tc3
 
-use std::borrow::Cow;
+use std::time::Duration;
 
-use tap::{Pipe, Tap};
+use itertools::Itertools;
+use tap::Pipe;
 use tracing::{debug, warn};
 use tree_sitter::Node;
-use tree_sitter_traversal::{traverse, traverse_tree, Order};
 
 use crate::debugging::ToDisplayEscaped;
-use crate::text::normalize_space;
-use crate::tree_sitter_consts::{NODE_KIND_FUNC_DEF, NODE_KIND_OPEN_BRACE};
+use crate::tree_sitter_consts::{
+    NODE_KIND_FUNC_DECLARATOR, NODE_KIND_FUNC_DEF, NODE_KIND_OPEN_BRACE,
+};
 use crate::{impl_language, impl_prelude::*};
 
-use super::normalize_code::normalize_code;
-use super::normalize_comments::normalize_comments;
+use super::methods;
+use super::parser_pool;
+use super::preprocessor;
+use super::scope;
 use super::snippet_context::SnippetContext;
+use super::traversal::{traverse, traverse_tree};
 
 /// This module implements support for C99 TC3.
 ///
@@ -70,18 +83,39 @@ impl SnippetExtractor for Extractor {
         opts: &SnippetOptions,
         content: impl AsRef<[u8]>,
     ) -> Result<Vec<Snippet<Self::Language>>, ExtractorError> {
-        let mut parser = init_parser()?;
+        let content = opts.prepare(content.as_ref());
+        let parsed = Self::parse_guarded(opts, &content)?;
+        Self::extract_lazy(&parsed, opts).collect()
+    }
+}
+
+impl LazySnippetExtractor for Extractor {
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, ExtractorError> {
+        parse_within(content, None)
+    }
 
-        let content = content.as_ref();
-        let Some(tree) = parser.parse(content, None) else {
-            warn!("provided content did not parse to a tree");
-            return Vec::new().pipe(Ok);
+    fn parse_guarded<'a>(
+        opts: &SnippetOptions,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, ExtractorError> {
+        opts.check_size(content)?;
+        parse_within(content, opts.timeout())
+    }
+
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, Self::Language> {
+        let Some(tree) = parsed.tree() else {
+            return Snippets::empty();
         };
+        let content = parsed.content();
+        let lines = parsed.lines();
 
-        traverse_tree(&tree, Order::Pre)
+        traverse_tree(tree)
             // Report syntax errors as warnings.
             // Always write a debugging line for each node, regardless of the kind of node.
-            .inspect(|node| inspect_node(node, content))
+            .inspect(move |node| inspect_node(node, content))
             // Nodes that are not "named" are syntax,
             // which this function currently ignores.
             //
@@ -90,63 +124,83 @@ impl SnippetExtractor for Extractor {
             .filter(|node| node.is_named())
             // Hand each node off to be processed into possibly many snippets,
             // based on the provided options.
-            .flat_map(|node| {
-                let loc = node.byte_range().pipe(SnippetLocation::from);
-                opts.cartesian_product()
-                    .filter(move |(target, _, _)| matches_target(*target, node))
-                    .map(move |(t, kind, method)| (t, SnippetMetadata::new(kind, method, loc)))
-                    .filter_map(move |(target, meta)| extract(target, meta, node, content))
+            .flat_map(move |node| {
+                opts.targets_and_kinds()
+                    .filter(move |(target, _)| matches_target(*target, node))
+                    .filter_map(move |(target, kind)| {
+                        extract(target, kind, node, content, lines, opts)
+                    })
             })
-            // Then just collect all the produced snippets and done!
-            // `From<Iterator> for Result<T, E>` stops iteration on the first error as well.
-            .collect()
+            // Each combination of target and kind extracts a snippet for each method.
+            .flatten_ok()
+            // Snippets are only extracted as the caller asks for them.
+            .pipe(Snippets::new)
+    }
+}
+
+impl QuerySnippetExtractor for Extractor {
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError> {
+        Query::new(&tree_sitter_c::LANGUAGE.into(), source)
     }
 }
 
-#[tracing::instrument(skip_all, fields(%target, kind = %meta.kind(), method = %meta.method(), location = %meta.location()))]
+#[tracing::instrument(skip_all, fields(%target, %kind, location = %SnippetLocation::from(node.byte_range())))]
 fn extract<L>(
     target: SnippetTarget,
-    meta: SnippetMetadata,
+    kind: SnippetKind,
     node: Node<'_>,
     content: &[u8],
-) -> Option<Result<Snippet<L>, ExtractorError>> {
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
     match target {
-        SnippetTarget::Function => extract_function(meta, node, content),
+        SnippetTarget::Macro | SnippetTarget::Function => {
+            extract_target(target, kind, node, content, lines, opts)
+        }
+        // This extractor does not match classes; see `matches_target`.
+        SnippetTarget::Class => None,
     }
 }
 
 #[tracing::instrument(skip_all)]
-fn extract_function<L>(
-    meta: SnippetMetadata,
+fn extract_target<L>(
+    target: SnippetTarget,
+    kind: SnippetKind,
     node: Node<'_>,
     content: &[u8],
-) -> Option<Result<Snippet<L>, ExtractorError>> {
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
     // The raw content here is just extracted for debugging.
-    let raw = meta.location().extract_from(content);
+    let raw = &content[node.byte_range()];
     debug!(raw = %raw.display_escaped());
 
     // The actual context, the part the snippet scanner cares about, is extracted here.
     // It also returns a new location so FOSSA can report a more accurate range for the snippet.
-    let context = extract_context(meta, node, content)?;
+    // Every method shares this context, so it is only extracted once for each kind of snippet.
+    let context = extract_context(target, kind, node, content)?;
+    // Full snippets optionally also cover the comments documenting the item.
+    let context = match kind {
+        SnippetKind::Full if opts.leading_comments() => context.with_leading_comments(node),
+        _ => context,
+    };
     debug!(context = %context.content().display_escaped());
 
-    // Transformations are applied on text extraction from the context.
-    // A new location is _not_ generated by this function because the transformed text
-    // won't have any more real of a relation to the original "range of text"
-    // than the context's range.
-    let text = extract_text(meta.method(), &context);
-    debug!(text = %text.display_escaped());
-
-    // The more exact location generated above overwrites the overall node location,
-    // otherwise users would just always see the whole node.
-    let meta = SnippetMetadata::new(meta.kind(), meta.method(), context.location());
-    Snippet::from(meta, text)
-        .tap(|snippet| debug!(fingerprint = %snippet.fingerprint()))
+    // Trivially small snippets are skipped before any transform is applied,
+    // so that every method for this context is kept or skipped together.
+    if !context.meets_minimum(opts) {
+        debug!("context is smaller than the configured minimum");
+        return None;
+    }
+
+    let name = extract_name(target, node, content);
+    let scope = scope::enclosing(node, |ancestor| scope_of(ancestor, content));
+    methods::extract(kind, name.as_deref(), &scope, &context, lines, opts)
         .pipe(Ok)
         .pipe(Some)
 }
 
-/// Extracts the "context" of a node with the provided metadata.
+/// Extracts the "context" of a node for the provided kind of snippet.
 ///
 /// This consists of:
 /// - A vector of parsed [`Node`]s that make up the part of the subtree considered relevant.
@@ -158,16 +212,22 @@ fn extract_function<L>(
 /// - [`SnippetTransform::Comment`] needs both; to find comments and then slice them out.
 /// - [`SnippetTransform::Space`] needs the parsed form so it normalizes spaces.
 /// - [`SnippetTransform::Code`] needs the parsed form so it normalizes spaces and slices comments.
+/// - [`SnippetTransform::Literal`] needs the parsed form so it also blanks literals.
 #[tracing::instrument(skip_all)]
 fn extract_context<'a>(
-    meta: SnippetMetadata,
+    target: SnippetTarget,
+    kind: SnippetKind,
     node: Node<'a>,
     content: &'a [u8],
 ) -> Option<SnippetContext<'a>> {
-    match meta.kind() {
-        SnippetKind::Full => Some(SnippetContext::new(node, meta.location(), content)),
+    if target == SnippetTarget::Macro {
+        return preprocessor::extract_context(kind, node, content);
+    }
+
+    match kind {
+        SnippetKind::Full => Some(SnippetContext::new(node, node.byte_range().into(), content)),
         SnippetKind::Body => {
-            let parts = FunctionParts::from(meta, node, content);
+            let parts = FunctionParts::from(kind, node, content);
 
             // Unless a delimiter was found, not much for this extractor to do.
             if parts.body.is_empty() {
@@ -204,7 +264,7 @@ fn extract_context<'a>(
             SnippetContext::from_nodes(parts.body, report_as, content).pipe(Some)
         }
         SnippetKind::Signature => {
-            let parts = FunctionParts::from(meta, node, content);
+            let parts = FunctionParts::from(kind, node, content);
 
             // Unless a delimiter was found, not much for this extractor to do.
             if parts.signature.is_empty() {
@@ -231,8 +291,8 @@ fn extract_context<'a>(
             // would be reported to have come from the whole function
             // instead of just this small part.
             let report_as = SnippetLocation::builder()
-                .byte_offset(meta.location().start_byte())
-                .byte_len(offset - meta.location().start_byte())
+                .byte_offset(node.start_byte())
+                .byte_len(offset - node.start_byte())
                 .build();
 
             // The context reports the nodes that made up this extracted snippet,
@@ -242,25 +302,6 @@ fn extract_context<'a>(
     }
 }
 
-#[tracing::instrument(skip_all)]
-fn extract_text<'a>(method: SnippetMethod, context: &'a SnippetContext) -> Cow<'a, [u8]> {
-    match method {
-        // For the happy path, raw snippets, no extra allocations!
-        SnippetMethod::Raw => Cow::from(context.content()),
-        // Any modification will require a new vector.
-        SnippetMethod::Normalized(tf) => transform(tf, context),
-    }
-}
-
-#[tracing::instrument(skip_all)]
-fn transform<'a>(transform: SnippetTransform, context: &'a SnippetContext) -> Cow<'a, [u8]> {
-    match transform {
-        SnippetTransform::Code => normalize_code(context),
-        SnippetTransform::Comment => normalize_comments(context).into(),
-        SnippetTransform::Space => normalize_space(context.content()),
-    }
-}
-
 /// Report whether the given treesitter node kind is a valid entrypoint for the target.
 ///
 /// Defined here instead of on [`SnippetTarget`] because that type should be generic across
@@ -268,8 +309,63 @@ fn transform<'a>(transform: SnippetTransform, context: &'a SnippetContext) -> Co
 #[tracing::instrument(level = "DEBUG", skip_all, fields(%target, node_kind = %node.kind()), ret)]
 fn matches_target(target: SnippetTarget, node: Node<'_>) -> bool {
     match target {
+        SnippetTarget::Macro => preprocessor::is_macro_definition(node),
         SnippetTarget::Function => node.kind() == NODE_KIND_FUNC_DEF,
+        SnippetTarget::Class => false,
+    }
+}
+
+/// Report the name declared by a node matched by [`matches_target`], if it has one.
+fn extract_name(target: SnippetTarget, node: Node<'_>, content: &[u8]) -> Option<String> {
+    let name = match target {
+        SnippetTarget::Macro => preprocessor::macro_name(node)?,
+        SnippetTarget::Function => function_declarator(node)?
+            .child_by_field_name(FIELD_DECLARATOR)
+            .and_then(unparenthesized)?,
+        SnippetTarget::Class => return None,
+    };
+    String::from_utf8_lossy(&content[name.byte_range()])
+        .into_owned()
+        .pipe(Some)
+}
+
+/// Report the scope declared by a node enclosing an item, if it declares one.
+///
+/// C has no namespaces or classes, so the only scopes are functions;
+/// functions are only nested in C code using compiler extensions, such as GCC's nested functions.
+fn scope_of(node: Node<'_>, content: &[u8]) -> Option<SnippetScope> {
+    if !matches_target(SnippetTarget::Function, node) {
+        return None;
+    }
+
+    let name = extract_name(SnippetTarget::Function, node, content);
+    SnippetScope::new(SnippetScopeKind::Function)
+        .with_name(name)
+        .pipe(Some)
+}
+
+/// Find the declarator of a function definition that holds its name and parameters.
+///
+/// It may be wrapped in other declarators; for example `char *name(void)`
+/// wraps it in a pointer declarator.
+fn function_declarator(node: Node<'_>) -> Option<Node<'_>> {
+    let mut declarator = node.child_by_field_name(FIELD_DECLARATOR)?;
+    while declarator.kind() != NODE_KIND_FUNC_DECLARATOR {
+        // Parenthesized declarators hold the declarator they wrap as a child instead of a field.
+        declarator = declarator
+            .child_by_field_name(FIELD_DECLARATOR)
+            .or_else(|| declarator.named_child(0))?;
+    }
+    Some(declarator)
+}
+
+/// Unwrap any parentheses around a declarator, as in `int (name)(void)`,
+/// which is sometimes used to prevent a function-like macro of the same name from expanding.
+fn unparenthesized(mut declarator: Node<'_>) -> Option<Node<'_>> {
+    while declarator.kind() == NODE_KIND_PARENTHESIZED_DECLARATOR {
+        declarator = declarator.named_child(0)?;
     }
+    Some(declarator)
 }
 
 #[tracing::instrument(skip_all)]
@@ -296,13 +392,28 @@ fn inspect_node(node: &Node<'_>, content: &[u8]) {
     }
 }
 
+/// Parse the content, abandoning the parse if it doesn't complete within the timeout.
+fn parse_within(
+    content: &[u8],
+    timeout: Option<Duration>,
+) -> Result<Parsed<'_, Language>, ExtractorError> {
+    let tree = parser_pool::parse::<Language>(content, timeout, init_parser)?;
+    if tree.is_none() {
+        warn!("provided content did not parse to a tree");
+    }
+    Ok(Parsed::new(tree, content))
+}
+
 #[tracing::instrument]
 fn init_parser() -> Result<tree_sitter::Parser, ExtractorError> {
     let mut parser = tree_sitter::Parser::new();
-    parser.set_language(tree_sitter_c::language())?;
+    parser.set_language(&tree_sitter_c::LANGUAGE.into())?;
     Ok(parser)
 }
 
+const NODE_KIND_PARENTHESIZED_DECLARATOR: &str = "parenthesized_declarator";
+const FIELD_DECLARATOR: &str = "declarator";
+
 #[derive(Debug, Clone)]
 struct FunctionParts<'a> {
     delimit_byte: usize,
@@ -313,11 +424,11 @@ struct FunctionParts<'a> {
 impl<'a> FunctionParts<'a> {
     /// Break a function into the nodes in the body and signature.
     ///
-    /// As a performance optimization, if the metadata only asks for the signature,
+    /// As a performance optimization, if only the signature is asked for,
     /// body nodes are not stored. They are still traversed, in case treesitter
     /// iterates over nodes out of order.
-    fn from(meta: SnippetMetadata, node: Node<'a>, content: &'a [u8]) -> Self {
-        let nodes = traverse(node.walk(), Order::Pre).inspect(|node| inspect_node(node, content));
+    fn from(kind: SnippetKind, node: Node<'a>, content: &'a [u8]) -> Self {
+        let nodes = traverse(node.walk()).inspect(|node| inspect_node(node, content));
 
         let mut signature = Vec::new();
         let mut body = Vec::new();
@@ -328,7 +439,7 @@ impl<'a> FunctionParts<'a> {
                 // Just delimit on first open brace, this is C.
                 (NODE_KIND_OPEN_BRACE, None) => {
                     delimit_byte = node.start_byte().pipe(Some);
-                    if meta.kind == SnippetKind::Signature {
+                    if kind == SnippetKind::Signature {
                         break;
                     } else {
                         body.push(node);
@@ -339,7 +450,7 @@ impl<'a> FunctionParts<'a> {
                 (_, Some(delimit_byte)) => {
                     if node.start_byte() < delimit_byte {
                         signature.push(node);
-                    } else if meta.kind != SnippetKind::Signature {
+                    } else if kind != SnippetKind::Signature {
                         body.push(node);
                     }
                 }
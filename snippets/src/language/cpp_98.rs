@@ -4,8 +4,6 @@
 //!
 //! C++ has evolved over the years via different [standards].
 //! This implementation primarily targets parsing C++ 98.
-//! Initially, this extractor will only support extracting bare functions from C++.
-//! Methods and/or classes are planned for implementation.
 //!
 //! This is because we are using the grammar maintained by the [`tree-sitter`]
 //! project for C++, [`tree-sitter-cpp`].
@@ -17,8 +15,8 @@
 //!
 //! That being said, this extractor should generally support newer versions
 //! of the C++ programming language. This is because this extractor is only
-//! concerned with functions, and a review of the later C++ standards
-//! does not imply that function parsing has changed.
+//! concerned with functions, classes, and macros, and a review of the later C++ standards
+//! does not imply that parsing them has changed.
 //!
 //! # Targets
 //! This extractor supports extracting the following as snippets from C++ files:
@@ -26,8 +24,38 @@
 //! * Function Signatures
 //! * Function Bodies
 //! * Full Functions Declarations
+//! * Class Signatures
+//! * Class Bodies
+//! * Full Class Declarations
+//! * Macro Signatures
+//! * Macro Bodies
+//! * Full Macro Definitions
 //!
-//! "Function" means functions that are not a method definition as part of a class.
+//! "Function" means any function definition with a body, which includes:
+//!
+//! * Bare functions, such as `int main() { ... }`.
+//! * Member functions defined inside their class, including constructors and destructors.
+//! * Member functions defined outside their class, such as `int Foo::bar() { ... }`.
+//! * Operator overloads and conversion operators, such as `bool operator==(const Foo&) const { ... }`.
+//! * Function templates, such as `template <typename T> T max(T a, T b) { ... }`,
+//!   including member templates and explicit specializations (e.g. `template <> int max<int>(int a, int b) { ... }`).
+//!
+//! The signature of a function is everything before its body,
+//! including any default arguments (e.g. `void sort(int* begin, int* end, bool descending = false)`)
+//! and any constructor initializer list (e.g. `Foo::Foo() : bar(0)`).
+//! Defaulted and deleted functions (e.g. `Foo() = default;`) have no body, so they only have a signature.
+//!
+//! "Class" means any class, struct, or union specifier with a body;
+//! forward declarations such as `class Foo;` are not extracted.
+//! The signature of a class is its header (e.g. `class Foo : public Bar`),
+//! and its body is the brace-delimited list of its members.
+//!
+//! "Macro" means an object-like (`#define MAX 10`) or function-like (`#define SQUARE(x) ((x) * (x))`)
+//! macro definition with a value. Macros without a value, such as include guards, are not extracted.
+//!
+//! For templates, the template header (e.g. `template <typename T, typename Compare = std::less<T> >`)
+//! is considered part of the signature of the function or class it declares,
+//! and so is also part of the full declaration.
 //!
 //! [`Extractor`]: crate::Extractor
 //! [`iso-14882:1998`]: https://www.externsoft.ch/download/cpp-iso.html
@@ -35,21 +63,23 @@
 //! [`tree-sitter`]: https://github.com/tree-sitter/tree-sitter
 //! [`tree-sitter-cpp`]: https://github.com/tree-sitter/tree-sitter-cpp
 //! [standards]: https://en.wikipedia.org/wiki/C%2B%2B#History
-use std::borrow::Cow;
+use std::{ops::Range, time::Duration};
 
-use tap::{Pipe, Tap};
+use itertools::Itertools;
+use tap::Pipe;
 use tracing::{debug, warn};
 use tree_sitter::Node;
-use tree_sitter_traversal::{traverse, traverse_tree, Order};
 
 use crate::debugging::ToDisplayEscaped;
-use crate::text::normalize_space;
-use crate::tree_sitter_consts::{NODE_KIND_FUNC_DEF, NODE_KIND_OPEN_BRACE};
+use crate::tree_sitter_consts::{NODE_KIND_COMMENT, NODE_KIND_FUNC_DECLARATOR, NODE_KIND_FUNC_DEF};
 use crate::{impl_language, impl_prelude::*};
 
-use super::normalize_code::normalize_code;
-use super::normalize_comments::normalize_comments;
+use super::methods;
+use super::parser_pool;
+use super::preprocessor;
+use super::scope;
 use super::snippet_context::SnippetContext;
+use super::traversal::{traverse, traverse_tree};
 
 /// This module implements support for CPP 98.
 ///
@@ -67,7 +97,8 @@ impl_language!(Language);
 /// Supports extracting snippets for CPP 98 source code.
 pub struct Extractor;
 
-// This extractor is basically a copy-paste of c99_tc3 extractor.
+// This extractor started as a copy-paste of c99_tc3 extractor,
+// but locates functions using the C++ grammar (e.g. their `body` field and enclosing templates).
 // If you make changes to this extractor, consider if they should also be made to the c99_tc3 extractor
 // or if the functionality makes sense to be shared.
 impl SnippetExtractor for Extractor {
@@ -77,18 +108,39 @@ impl SnippetExtractor for Extractor {
         opts: &SnippetOptions,
         content: impl AsRef<[u8]>,
     ) -> Result<Vec<Snippet<Self::Language>>, ExtractorError> {
-        let mut parser = init_parser()?;
+        let content = opts.prepare(content.as_ref());
+        let parsed = Self::parse_guarded(opts, &content)?;
+        Self::extract_lazy(&parsed, opts).collect()
+    }
+}
+
+impl LazySnippetExtractor for Extractor {
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, ExtractorError> {
+        parse_within(content, None)
+    }
 
-        let content = content.as_ref();
-        let Some(tree) = parser.parse(content, None) else {
-            warn!("provided content did not parse to a tree");
-            return Vec::new().pipe(Ok);
+    fn parse_guarded<'a>(
+        opts: &SnippetOptions,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, ExtractorError> {
+        opts.check_size(content)?;
+        parse_within(content, opts.timeout())
+    }
+
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, Self::Language> {
+        let Some(tree) = parsed.tree() else {
+            return Snippets::empty();
         };
+        let content = parsed.content();
+        let lines = parsed.lines();
 
-        traverse_tree(&tree, Order::Pre)
+        traverse_tree(tree)
             // Report syntax errors as warnings.
             // Always write a debugging line for each node, regardless of the kind of node.
-            .inspect(|node| inspect_node(node, content))
+            .inspect(move |node| inspect_node(node, content))
             // Nodes that are not "named" are syntax,
             // which this function currently ignores.
             //
@@ -97,63 +149,81 @@ impl SnippetExtractor for Extractor {
             .filter(|node| node.is_named())
             // Hand each node off to be processed into possibly many snippets,
             // based on the provided options.
-            .flat_map(|node| {
-                let loc = node.byte_range().pipe(SnippetLocation::from);
-                opts.cartesian_product()
-                    .filter(move |(target, _, _)| matches_target(*target, node))
-                    .map(move |(t, kind, method)| (t, SnippetMetadata::new(kind, method, loc)))
-                    .filter_map(move |(target, meta)| extract(target, meta, node, content))
+            .flat_map(move |node| {
+                opts.targets_and_kinds()
+                    .filter(move |(target, _)| matches_target(*target, node))
+                    .filter_map(move |(target, kind)| {
+                        extract(target, kind, node, content, lines, opts)
+                    })
             })
-            // Then just collect all the produced snippets and done!
-            // `From<Iterator> for Result<T, E>` stops iteration on the first error as well.
-            .collect()
+            // Each combination of target and kind extracts a snippet for each method.
+            .flatten_ok()
+            // Snippets are only extracted as the caller asks for them.
+            .pipe(Snippets::new)
+    }
+}
+
+impl QuerySnippetExtractor for Extractor {
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError> {
+        Query::new(&tree_sitter_cpp::LANGUAGE.into(), source)
     }
 }
 
-#[tracing::instrument(skip_all, fields(%target, kind = %meta.kind(), method = %meta.method(), location = %meta.location()))]
+#[tracing::instrument(skip_all, fields(%target, %kind, location = %SnippetLocation::from(node.byte_range())))]
 fn extract<L>(
     target: SnippetTarget,
-    meta: SnippetMetadata,
+    kind: SnippetKind,
     node: Node<'_>,
     content: &[u8],
-) -> Option<Result<Snippet<L>, ExtractorError>> {
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
     match target {
-        SnippetTarget::Function => extract_function(meta, node, content),
+        SnippetTarget::Macro | SnippetTarget::Function | SnippetTarget::Class => {
+            extract_target(target, kind, node, content, lines, opts)
+        }
     }
 }
 
 #[tracing::instrument(skip_all)]
-fn extract_function<L>(
-    meta: SnippetMetadata,
+fn extract_target<L>(
+    target: SnippetTarget,
+    kind: SnippetKind,
     node: Node<'_>,
     content: &[u8],
-) -> Option<Result<Snippet<L>, ExtractorError>> {
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
     // The raw content here is just extracted for debugging.
-    let raw = meta.location().extract_from(content);
+    let raw = &content[node.byte_range()];
     debug!(raw = %raw.display_escaped());
 
     // The actual context, the part the snippet scanner cares about, is extracted here.
     // It also returns a new location so FOSSA can report a more accurate range for the snippet.
-    let context = extract_context(meta, node, content)?;
+    // Every method shares this context, so it is only extracted once for each kind of snippet.
+    let context = extract_context(target, kind, node, content)?;
+    // Full snippets optionally also cover the comments documenting the item.
+    let context = match kind {
+        SnippetKind::Full if opts.leading_comments() => context.with_leading_comments(node),
+        _ => context,
+    };
     debug!(context = %context.content().display_escaped());
 
-    // Transformations are applied on text extraction from the context.
-    // A new location is _not_ generated by this function because the transformed text
-    // won't have any more real of a relation to the original "range of text"
-    // than the context's range.
-    let text = extract_text(meta.method(), &context);
-    debug!(text = %text.display_escaped());
-
-    // The more exact location generated above overwrites the overall node location,
-    // otherwise users would just always see the whole node.
-    let meta = SnippetMetadata::new(meta.kind(), meta.method(), context.location());
-    Snippet::from(meta, text)
-        .tap(|snippet| debug!(fingerprint = %snippet.fingerprint()))
+    // Trivially small snippets are skipped before any transform is applied,
+    // so that every method for this context is kept or skipped together.
+    if !context.meets_minimum(opts) {
+        debug!("context is smaller than the configured minimum");
+        return None;
+    }
+
+    let name = extract_name(target, node, content);
+    let scope = scope::enclosing(node, |ancestor| scope_of(ancestor, content));
+    methods::extract(kind, name.as_deref(), &scope, &context, lines, opts)
         .pipe(Ok)
         .pipe(Some)
 }
 
-/// Extracts the "context" of a node with the provided metadata.
+/// Extracts the "context" of a node for the provided kind of snippet.
 ///
 /// This consists of:
 /// - A vector of parsed [`Node`]s that make up the part of the subtree considered relevant.
@@ -165,27 +235,34 @@ fn extract_function<L>(
 /// - [`SnippetTransform::Comment`] needs both; to find comments and then slice them out.
 /// - [`SnippetTransform::Space`] needs the parsed form so it normalizes spaces.
 /// - [`SnippetTransform::Code`] needs the parsed form so it normalizes spaces and slices comments.
+/// - [`SnippetTransform::Literal`] needs the parsed form so it also blanks literals.
 #[tracing::instrument(skip_all)]
 fn extract_context<'a>(
-    meta: SnippetMetadata,
+    target: SnippetTarget,
+    kind: SnippetKind,
     node: Node<'a>,
     content: &'a [u8],
 ) -> Option<SnippetContext<'a>> {
-    match meta.kind() {
-        SnippetKind::Full => Some(SnippetContext::new(node, meta.location(), content)),
+    if target == SnippetTarget::Macro {
+        return preprocessor::extract_context(kind, node, content);
+    }
+
+    match kind {
+        SnippetKind::Full => Some(SnippetContext::new(node, node.byte_range().into(), content)),
         SnippetKind::Body => {
-            let parts = FunctionParts::from(meta, node, content);
+            let parts = Parts::from(kind, node, content);
 
             // Unless a delimiter was found, not much for this extractor to do.
             if parts.body.is_empty() {
-                warn!("function body not found");
+                warn!("{target} body not found");
                 return None;
             }
 
-            // This node ends at the end of the function.
+            // The body ends at the end of the function or class, not including any trailing
+            // syntax of the declaration that contains it (such as the `;` after a class template).
             // Since the end of the delimiter signifies the start, anything between is the body.
             let mut offset = parts.delimit_byte;
-            let end = node.end_byte();
+            let end = parts.end_byte;
 
             // Spaces between the delimiter end and start of the actual
             // body content should not be significant.
@@ -193,7 +270,7 @@ fn extract_context<'a>(
                 offset += 1;
             }
             if offset == end {
-                warn!("function body appears to be made up entirely of spaces");
+                warn!("{target} body appears to be made up entirely of spaces");
                 offset = parts.delimit_byte;
             }
 
@@ -211,11 +288,11 @@ fn extract_context<'a>(
             SnippetContext::from_nodes(parts.body, report_as, content).pipe(Some)
         }
         SnippetKind::Signature => {
-            let parts = FunctionParts::from(meta, node, content);
+            let parts = Parts::from(kind, node, content);
 
             // Unless a delimiter was found, not much for this extractor to do.
             if parts.signature.is_empty() {
-                warn!("function signature not found");
+                warn!("{target} signature not found");
                 return None;
             }
 
@@ -229,7 +306,7 @@ fn extract_context<'a>(
                 offset -= 1;
             }
             if offset == start {
-                warn!("function signature appears to be made up entirely of spaces");
+                warn!("{target} signature appears to be made up entirely of spaces");
                 offset = parts.delimit_byte;
             }
 
@@ -238,8 +315,8 @@ fn extract_context<'a>(
             // would be reported to have come from the whole function
             // instead of just this small part.
             let report_as = SnippetLocation::builder()
-                .byte_offset(meta.location().start_byte())
-                .byte_len(offset - meta.location().start_byte())
+                .byte_offset(node.start_byte())
+                .byte_len(offset - node.start_byte())
                 .build();
 
             // The context reports the nodes that made up this extracted snippet,
@@ -249,25 +326,6 @@ fn extract_context<'a>(
     }
 }
 
-#[tracing::instrument(skip_all)]
-fn extract_text<'a>(method: SnippetMethod, context: &'a SnippetContext) -> Cow<'a, [u8]> {
-    match method {
-        // For the happy path, raw snippets, no extra allocations!
-        SnippetMethod::Raw => Cow::from(context.content()),
-        // Any modification will require a new vector.
-        SnippetMethod::Normalized(tf) => transform(tf, context),
-    }
-}
-
-#[tracing::instrument(skip_all)]
-fn transform<'a>(transform: SnippetTransform, context: &'a SnippetContext) -> Cow<'a, [u8]> {
-    match transform {
-        SnippetTransform::Code => normalize_code(context),
-        SnippetTransform::Comment => normalize_comments(context).into(),
-        SnippetTransform::Space => normalize_space(context.content()),
-    }
-}
-
 /// Report whether the given treesitter node kind is a valid entrypoint for the target.
 ///
 /// Defined here instead of on [`SnippetTarget`] because that type should be generic across
@@ -275,10 +333,144 @@ fn transform<'a>(transform: SnippetTransform, context: &'a SnippetContext) -> Co
 #[tracing::instrument(level = "DEBUG", skip_all, fields(%target, node_kind = %node.kind()), ret)]
 fn matches_target(target: SnippetTarget, node: Node<'_>) -> bool {
     match target {
-        SnippetTarget::Function => node.kind() == NODE_KIND_FUNC_DEF,
+        SnippetTarget::Macro => preprocessor::is_macro_definition(node),
+        SnippetTarget::Function => {
+            declaration(node).is_some_and(|decl| decl.kind() == NODE_KIND_FUNC_DEF)
+        }
+        SnippetTarget::Class => declaration(node).is_some_and(|decl| {
+            matches!(
+                decl.kind(),
+                NODE_KIND_CLASS_SPEC | NODE_KIND_STRUCT_SPEC | NODE_KIND_UNION_SPEC
+            ) && decl.child_by_field_name(FIELD_BODY).is_some()
+        }),
     }
 }
 
+/// Resolve the declaration that a node represents as a possible snippet entrypoint.
+///
+/// Templates are entrypoints for the function or class they declare,
+/// so that the template header is included in the snippet.
+/// For nested templates (e.g. a member template of a class template defined outside the class)
+/// only the outermost template is an entrypoint.
+/// A declaration directly inside a template is not an entrypoint on its own,
+/// since it is handled by its template.
+fn declaration(node: Node<'_>) -> Option<Node<'_>> {
+    let in_template = node
+        .parent()
+        .map(|parent| parent.kind() == NODE_KIND_TEMPLATE_DECL)
+        .unwrap_or_default();
+    if in_template {
+        return None;
+    }
+
+    let mut decl = node;
+    while decl.kind() == NODE_KIND_TEMPLATE_DECL {
+        // The declared item follows the template parameters,
+        // so it is the last named child that isn't a comment.
+        let mut cursor = decl.walk();
+        let inner = decl
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() != NODE_KIND_COMMENT)
+            .last()?;
+        decl = inner;
+    }
+    Some(decl)
+}
+
+/// Report the name declared by a node matched by [`matches_target`], if it has one.
+///
+/// Names are reported as written, so a member function defined outside its class
+/// (e.g. `int Foo::bar() { ... }`) is named `Foo::bar`, and an operator overload is named e.g. `operator==`.
+/// Anonymous classes, such as `struct { int x; } point;`, have no name.
+fn extract_name(target: SnippetTarget, node: Node<'_>, content: &[u8]) -> Option<String> {
+    let range = match target {
+        SnippetTarget::Macro => preprocessor::macro_name(node)?.byte_range(),
+        SnippetTarget::Function => function_name(declaration(node)?)?,
+        SnippetTarget::Class => declaration(node)?
+            .child_by_field_name(FIELD_NAME)?
+            .byte_range(),
+    };
+    String::from_utf8_lossy(&content[range])
+        .trim_end()
+        .to_owned()
+        .pipe(Some)
+}
+
+/// Report the scope declared by a node enclosing an item, if it declares one.
+///
+/// Classes and functions are scopes when they would be matched by [`matches_target`],
+/// so a class template is reported once for its template declaration.
+/// Namespaces and lambdas are also scopes; anonymous namespaces and lambdas have no name.
+fn scope_of(node: Node<'_>, content: &[u8]) -> Option<SnippetScope> {
+    let (kind, name) = match node.kind() {
+        NODE_KIND_NAMESPACE_DEF => (
+            SnippetScopeKind::Namespace,
+            node.child_by_field_name(FIELD_NAME)
+                .map(|name| String::from_utf8_lossy(&content[name.byte_range()]).into_owned()),
+        ),
+        NODE_KIND_LAMBDA_EXPR => (SnippetScopeKind::Function, None),
+        _ if matches_target(SnippetTarget::Class, node) => (
+            SnippetScopeKind::Class,
+            extract_name(SnippetTarget::Class, node, content),
+        ),
+        _ if matches_target(SnippetTarget::Function, node) => (
+            SnippetScopeKind::Function,
+            extract_name(SnippetTarget::Function, node, content),
+        ),
+        _ => return None,
+    };
+    SnippetScope::new(kind).with_name(name).pipe(Some)
+}
+
+/// Find the range of the name of a function definition in its declarator.
+///
+/// The declarator holding the name and parameters may be wrapped in other declarators;
+/// for example `int &name()` wraps it in a reference declarator.
+///
+/// Conversion operators (e.g. `operator bool() const`) are declared differently:
+/// their name is everything before their parameters.
+fn function_name(node: Node<'_>) -> Option<Range<usize>> {
+    let mut declarator = node.child_by_field_name(FIELD_DECLARATOR)?;
+    loop {
+        match declarator.kind() {
+            NODE_KIND_FUNC_DECLARATOR => {
+                return declarator
+                    .child_by_field_name(FIELD_DECLARATOR)
+                    .and_then(unparenthesized)
+                    .map(|name| name.byte_range())
+            }
+            NODE_KIND_OPERATOR_CAST | NODE_KIND_QUALIFIED_ID => {
+                let params = operator_cast(declarator)?.child_by_field_name(FIELD_DECLARATOR)?;
+                return Some(declarator.start_byte()..params.start_byte());
+            }
+            // Reference and parenthesized declarators hold the declarator they wrap
+            // as a child instead of a field.
+            _ => {
+                declarator = declarator
+                    .child_by_field_name(FIELD_DECLARATOR)
+                    .or_else(|| declarator.named_child(0))?
+            }
+        }
+    }
+}
+
+/// Find the conversion operator named by a (possibly qualified) declarator, e.g. `Foo::operator bool() const`.
+fn operator_cast(mut node: Node<'_>) -> Option<Node<'_>> {
+    while node.kind() == NODE_KIND_QUALIFIED_ID {
+        node = node.child_by_field_name(FIELD_NAME)?;
+    }
+    (node.kind() == NODE_KIND_OPERATOR_CAST).then_some(node)
+}
+
+/// Unwrap any parentheses around a declarator, as in `int (name)()`,
+/// which is sometimes used to prevent a function-like macro of the same name from expanding.
+fn unparenthesized(mut declarator: Node<'_>) -> Option<Node<'_>> {
+    while declarator.kind() == NODE_KIND_PARENTHESIZED_DECLARATOR {
+        declarator = declarator.named_child(0)?;
+    }
+    Some(declarator)
+}
+
 #[tracing::instrument(skip_all)]
 fn inspect_node(node: &Node<'_>, content: &[u8]) {
     let location = node.byte_range().pipe(SnippetLocation::from);
@@ -303,64 +495,80 @@ fn inspect_node(node: &Node<'_>, content: &[u8]) {
     }
 }
 
+/// Parse the content, abandoning the parse if it doesn't complete within the timeout.
+fn parse_within(
+    content: &[u8],
+    timeout: Option<Duration>,
+) -> Result<Parsed<'_, Language>, ExtractorError> {
+    let tree = parser_pool::parse::<Language>(content, timeout, init_parser)?;
+    if tree.is_none() {
+        warn!("provided content did not parse to a tree");
+    }
+    Ok(Parsed::new(tree, content))
+}
+
 #[tracing::instrument]
 fn init_parser() -> Result<tree_sitter::Parser, ExtractorError> {
     let mut parser = tree_sitter::Parser::new();
-    parser.set_language(tree_sitter_cpp::language())?;
+    parser.set_language(&tree_sitter_cpp::LANGUAGE.into())?;
     Ok(parser)
 }
 
+const NODE_KIND_CLASS_SPEC: &str = "class_specifier";
+const NODE_KIND_STRUCT_SPEC: &str = "struct_specifier";
+const NODE_KIND_UNION_SPEC: &str = "union_specifier";
+const NODE_KIND_TEMPLATE_DECL: &str = "template_declaration";
+const NODE_KIND_NAMESPACE_DEF: &str = "namespace_definition";
+const NODE_KIND_LAMBDA_EXPR: &str = "lambda_expression";
+const NODE_KIND_OPERATOR_CAST: &str = "operator_cast";
+const NODE_KIND_PARENTHESIZED_DECLARATOR: &str = "parenthesized_declarator";
+const NODE_KIND_QUALIFIED_ID: &str = "qualified_identifier";
+const FIELD_BODY: &str = "body";
+const FIELD_DECLARATOR: &str = "declarator";
+const FIELD_NAME: &str = "name";
+
 #[derive(Debug, Clone)]
-struct FunctionParts<'a> {
+struct Parts<'a> {
     delimit_byte: usize,
+    end_byte: usize,
     signature: Vec<Node<'a>>,
     body: Vec<Node<'a>>,
 }
 
-impl<'a> FunctionParts<'a> {
-    /// Break a function into the nodes in the body and signature.
+impl<'a> Parts<'a> {
+    /// Break a node matching a target into the nodes in the body and signature.
+    ///
+    /// Both functions and classes delimit their body with the `body` field of the declaration,
+    /// which is correct regardless of what the signature contains
+    /// (for example, braces in a constructor initializer list or a class header).
+    /// If there is no body, such as for a defaulted function, the whole node is the signature.
     ///
-    /// As a performance optimization, if the metadata only asks for the signature,
-    /// body nodes are not stored. They are still traversed, in case treesitter
-    /// iterates over nodes out of order.
-    fn from(meta: SnippetMetadata, node: Node<'a>, content: &'a [u8]) -> Self {
-        let nodes = traverse(node.walk(), Order::Pre).inspect(|node| inspect_node(node, content));
+    /// As a performance optimization, if only the signature is asked for,
+    /// body nodes are not stored.
+    fn from(kind: SnippetKind, node: Node<'a>, content: &'a [u8]) -> Self {
+        let (delimit_byte, end_byte) = declaration(node)
+            .and_then(|decl| decl.child_by_field_name(FIELD_BODY))
+            .map(|body| (body.start_byte(), body.end_byte()))
+            .unwrap_or_else(|| (node.end_byte(), node.end_byte()));
+        let nodes = traverse(node.walk()).inspect(|node| inspect_node(node, content));
 
         let mut signature = Vec::new();
         let mut body = Vec::new();
-        let mut delimit_byte = None;
-        let mut last_byte = node.start_byte();
         for node in nodes {
-            match (node.kind(), delimit_byte) {
-                // Just delimit on first open brace, this is C.
-                (NODE_KIND_OPEN_BRACE, None) => {
-                    delimit_byte = node.start_byte().pipe(Some);
-                    if meta.kind == SnippetKind::Signature {
-                        break;
-                    } else {
-                        body.push(node);
-                    }
-                }
-                // If a delimiter has been found, push the node into body or signature,
-                // depending on which side of the delimiter the node falls.
-                (_, Some(delimit_byte)) => {
-                    if node.start_byte() < delimit_byte {
-                        signature.push(node);
-                    } else if meta.kind != SnippetKind::Signature {
-                        body.push(node);
-                    }
-                }
-                // For no delimiter, default to signature, since this function starts
-                // at the start of the function definition.
-                (_, None) => signature.push(node),
+            if node.start_byte() < delimit_byte {
+                signature.push(node);
+            } else if kind == SnippetKind::Signature {
+                break;
+            } else if node.end_byte() <= end_byte {
+                body.push(node);
             }
-            last_byte = node.end_byte();
         }
 
-        FunctionParts {
+        Parts {
+            delimit_byte,
+            end_byte,
             signature,
             body,
-            delimit_byte: delimit_byte.unwrap_or(last_byte),
         }
     }
 }
@@ -0,0 +1,507 @@
+//! Implements an [`Extractor`] for the JavaScript programming language.
+//!
+//! # Standard
+//!
+//! JavaScript is standardized as [ECMAScript], which has evolved over the years via yearly editions.
+//! This implementation uses the grammar maintained by the [`tree-sitter`]
+//! project for JavaScript, [`tree-sitter-javascript`], which targets the latest
+//! edition of ECMAScript along with JSX.
+//!
+//! # Targets
+//!
+//! This extractor supports extracting the following as snippets from JavaScript files:
+//!
+//! * Function Signatures
+//! * Function Bodies
+//! * Full Function Declarations
+//!
+//! "Function" means any of:
+//! * Function declarations, including generator functions: `function add(a, b) { return a + b; }`
+//! * Class methods: `class Adder { add(a, b) { return a + b; } }`
+//! * Arrow functions assigned to an identifier: `const add = (a, b) => a + b;`
+//!
+//! For arrow functions, the snippet includes the identifier to which it is assigned,
+//! so the signature of `const add = (a, b) => a + b;` is `add = (a, b) =>`.
+//! Arrow functions that are not assigned to an identifier (for example callbacks)
+//! are not extracted on their own, but are included in the snippets of the function containing them.
+//!
+//! [`Extractor`]: crate::Extractor
+//! [ECMAScript]: https://en.wikipedia.org/wiki/ECMAScript
+//! [`tree-sitter`]: https://github.com/tree-sitter/tree-sitter
+//! [`tree-sitter-javascript`]: https://github.com/tree-sitter/tree-sitter-javascript
+
+use std::time::Duration;
+
+use itertools::Itertools;
+use tap::Pipe;
+use tracing::{debug, warn};
+use tree_sitter::Node;
+
+use crate::debugging::ToDisplayEscaped;
+use crate::{impl_language, impl_prelude::*};
+
+use super::methods;
+use super::parser_pool;
+use super::scope;
+use super::snippet_context::SnippetContext;
+use super::traversal::{traverse, traverse_tree};
+
+/// This module implements support for JavaScript.
+///
+/// Review module documentation for more details.
+#[derive(Copy, Clone)]
+pub struct Language;
+
+impl SnippetLanguage for Language {
+    const NAME: &'static str = "javascript";
+    const STRATEGY: LanguageStrategy = LanguageStrategy::Static;
+}
+impl_language!(Language);
+
+/// Supports extracting snippets from JavaScript source code.
+pub struct Extractor;
+
+// This extractor is largely a copy-paste of the java extractor,
+// differing mainly in how nodes are matched and how functions are split into signature and body.
+// If you make changes to this extractor, consider if they should also be made to the other extractors
+// or if the functionality makes sense to be shared.
+//
+// The typescript and tsx extractors are implemented in terms of this one; see `extract_lazy_with`.
+impl SnippetExtractor for Extractor {
+    type Language = Language;
+
+    #[tracing::instrument(skip_all, fields(kinds = %opts.kinds(), transforms = %opts.transforms(), content_len = content.as_ref().len()))]
+    fn extract(
+        opts: &SnippetOptions,
+        content: impl AsRef<[u8]>,
+    ) -> Result<Vec<Snippet<Self::Language>>, ExtractorError> {
+        let content = opts.prepare(content.as_ref());
+        let parsed = Self::parse_guarded(opts, &content)?;
+        Self::extract_lazy(&parsed, opts).collect()
+    }
+}
+
+impl LazySnippetExtractor for Extractor {
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, ExtractorError> {
+        parse_with::<Self::Language>(&tree_sitter_javascript::LANGUAGE.into(), content, None)
+    }
+
+    fn parse_guarded<'a>(
+        opts: &SnippetOptions,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, ExtractorError> {
+        opts.check_size(content)?;
+        parse_with::<Self::Language>(
+            &tree_sitter_javascript::LANGUAGE.into(),
+            content,
+            opts.timeout(),
+        )
+    }
+
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, Self::Language> {
+        extract_lazy_with(parsed, opts)
+    }
+}
+
+impl QuerySnippetExtractor for Extractor {
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError> {
+        Query::new(&tree_sitter_javascript::LANGUAGE.into(), source)
+    }
+}
+
+/// Parse the content for the language `L` using the provided grammar,
+/// abandoning the parse if it doesn't complete within the timeout.
+pub(super) fn parse_with<'a, L: 'static>(
+    language: &tree_sitter::Language,
+    content: &'a [u8],
+    timeout: Option<Duration>,
+) -> Result<Parsed<'a, L>, ExtractorError> {
+    let tree = parser_pool::parse::<L>(content, timeout, || init_parser(language))?;
+    if tree.is_none() {
+        warn!("provided content did not parse to a tree");
+    }
+    Ok(Parsed::new(tree, content))
+}
+
+/// Lazily extract snippets from content parsed with [`parse_with`].
+///
+/// The TypeScript grammars are extensions of the JavaScript grammar,
+/// so this is shared with the TypeScript and TSX extractors: the same node kinds and fields
+/// describe functions in all three languages.
+/// Since signatures are made up of everything preceding the body of the function,
+/// TypeScript type parameters and annotations are included in signature snippets.
+pub(super) fn extract_lazy_with<'p, L: 'p>(
+    parsed: &'p Parsed<'_, L>,
+    opts: &'p SnippetOptions,
+) -> Snippets<'p, L> {
+    let Some(tree) = parsed.tree() else {
+        return Snippets::empty();
+    };
+    let content = parsed.content();
+    let lines = parsed.lines();
+
+    traverse_tree(tree)
+        // Report syntax errors as warnings.
+        // Always write a debugging line for each node, regardless of the kind of node.
+        .inspect(move |node| inspect_node(node, content))
+        // Nodes that are not "named" are syntax,
+        // which this function currently ignores.
+        //
+        // Reference:
+        // https://tree-sitter.github.io/tree-sitter/using-parsers#named-vs-anonymous-nodes
+        .filter(|node| node.is_named())
+        // Hand each node off to be processed into possibly many snippets,
+        // based on the provided options.
+        .flat_map(move |node| {
+            opts.targets_and_kinds()
+                .filter(move |(target, _)| matches_target(*target, node))
+                .filter_map(move |(target, kind)| extract(target, kind, node, content, lines, opts))
+        })
+        // Each combination of target and kind extracts a snippet for each method.
+        .flatten_ok()
+        // Snippets are only extracted as the caller asks for them.
+        .pipe(Snippets::new)
+}
+
+#[tracing::instrument(skip_all, fields(%target, %kind, location = %SnippetLocation::from(node.byte_range())))]
+fn extract<L>(
+    target: SnippetTarget,
+    kind: SnippetKind,
+    node: Node<'_>,
+    content: &[u8],
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
+    match target {
+        SnippetTarget::Function => extract_function(kind, node, content, lines, opts),
+        // This extractor does not match classes or macros; see `matches_target`.
+        SnippetTarget::Macro | SnippetTarget::Class => None,
+    }
+}
+
+#[tracing::instrument(skip_all)]
+fn extract_function<L>(
+    kind: SnippetKind,
+    node: Node<'_>,
+    content: &[u8],
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
+    // The raw content here is just extracted for debugging.
+    let raw = &content[node.byte_range()];
+    debug!(raw = %raw.display_escaped());
+
+    // The actual context, the part the snippet scanner cares about, is extracted here.
+    // It also returns a new location so FOSSA can report a more accurate range for the snippet.
+    // Every method shares this context, so it is only extracted once for each kind of snippet.
+    let context = extract_context(kind, node, content)?;
+    // Full snippets optionally also cover the comments documenting the item.
+    let context = match kind {
+        SnippetKind::Full if opts.leading_comments() => context.with_leading_comments(node),
+        _ => context,
+    };
+    debug!(context = %context.content().display_escaped());
+
+    // Trivially small snippets are skipped before any transform is applied,
+    // so that every method for this context is kept or skipped together.
+    if !context.meets_minimum(opts) {
+        debug!("context is smaller than the configured minimum");
+        return None;
+    }
+
+    let name = extract_name(node, content);
+    let scope = scope::enclosing(node, |ancestor| scope_of(ancestor, content));
+    methods::extract(kind, name.as_deref(), &scope, &context, lines, opts)
+        .pipe(Ok)
+        .pipe(Some)
+}
+
+/// Extracts the "context" of a node for the provided kind of snippet.
+///
+/// This consists of:
+/// - A vector of parsed [`Node`]s that make up the part of the subtree considered relevant.
+/// - A [`SnippetLocation`] selecting the (usually constrained) span that makes up the context.
+///
+/// Both are returned instead of just one or the other because different text extractors care
+/// about different sets of data. Specifically:
+/// - [`SnippetMethod::Raw`] needs the text _as written_.
+/// - [`SnippetTransform::Comment`] needs both; to find comments and then slice them out.
+/// - [`SnippetTransform::Space`] needs the parsed form so it normalizes spaces.
+/// - [`SnippetTransform::Code`] needs the parsed form so it normalizes spaces and slices comments.
+/// - [`SnippetTransform::Literal`] needs the parsed form so it also blanks literals.
+#[tracing::instrument(skip_all)]
+fn extract_context<'a>(
+    kind: SnippetKind,
+    node: Node<'a>,
+    content: &'a [u8],
+) -> Option<SnippetContext<'a>> {
+    match kind {
+        SnippetKind::Full => Some(SnippetContext::new(node, node.byte_range().into(), content)),
+        SnippetKind::Body => {
+            let parts = FunctionParts::from(kind, node, content);
+
+            // Unless a delimiter was found, not much for this extractor to do.
+            if parts.body.is_empty() {
+                warn!("function body not found");
+                return None;
+            }
+
+            // This node ends at the end of the function.
+            // Since the end of the delimiter signifies the start, anything between is the body.
+            let mut offset = parts.delimit_byte;
+            let end = node.end_byte();
+
+            // Spaces between the delimiter end and start of the actual
+            // body content should not be significant.
+            while offset < end && content[offset].is_ascii_whitespace() {
+                offset += 1;
+            }
+            if offset == end {
+                warn!("function body appears to be made up entirely of spaces");
+                offset = parts.delimit_byte;
+            }
+
+            // The new location is meant to enable a more precise report of where
+            // exactly the snippet was found in the file; otherwise the snippet
+            // would be reported to have come from the whole function
+            // instead of just this small part.
+            let report_as = SnippetLocation::builder()
+                .byte_offset(offset)
+                .byte_len(end - offset)
+                .build();
+
+            // The context reports the nodes that made up this extracted snippet,
+            // for future pipeline operations to use.
+            SnippetContext::from_nodes(parts.body, report_as, content).pipe(Some)
+        }
+        SnippetKind::Signature => {
+            let parts = FunctionParts::from(kind, node, content);
+
+            // Unless a delimiter was found, not much for this extractor to do.
+            if parts.signature.is_empty() {
+                warn!("function signature not found");
+                return None;
+            }
+
+            // This node starts at the start of the function.
+            // Since the start of the delimiter signifies the end, anything between is the signature.
+            let mut offset = parts.delimit_byte;
+            let start = node.start_byte();
+
+            // Spaces between the signature and delimiter are not significant.
+            while offset > start && content[offset - 1].is_ascii_whitespace() {
+                offset -= 1;
+            }
+            if offset == start {
+                warn!("function signature appears to be made up entirely of spaces");
+                offset = parts.delimit_byte;
+            }
+
+            // The new location is meant to enable a more precise report of where
+            // exactly the snippet was found in the file; otherwise the snippet
+            // would be reported to have come from the whole function
+            // instead of just this small part.
+            let report_as = SnippetLocation::builder()
+                .byte_offset(node.start_byte())
+                .byte_len(offset - node.start_byte())
+                .build();
+
+            // The context reports the nodes that made up this extracted snippet,
+            // for future pipeline operations to use.
+            SnippetContext::from_nodes(parts.signature, report_as, content).pipe(Some)
+        }
+    }
+}
+
+/// Report whether the given treesitter node kind is a valid entrypoint for the target.
+///
+/// Defined here instead of on [`SnippetTarget`] because that type should be generic across
+/// language parse strategies instead of being tied to treesitter-specific implementations.
+#[tracing::instrument(level = "DEBUG", skip_all, fields(%target, node_kind = %node.kind()), ret)]
+fn matches_target(target: SnippetTarget, node: Node<'_>) -> bool {
+    match target {
+        SnippetTarget::Function => match node.kind() {
+            NODE_KIND_FUNC_DECL | NODE_KIND_GENERATOR_FUNC_DECL | NODE_KIND_METHOD_DEF => true,
+            NODE_KIND_VARIABLE_DECLARATOR => is_arrow_assignment(node, FIELD_NAME, FIELD_VALUE),
+            NODE_KIND_ASSIGNMENT_EXPR => is_arrow_assignment(node, FIELD_LEFT, FIELD_RIGHT),
+            _ => false,
+        },
+        SnippetTarget::Macro | SnippetTarget::Class => false,
+    }
+}
+
+/// Report whether the node assigns an arrow function to an identifier,
+/// given the names of the fields holding the assigned identifier and the assigned value.
+fn is_arrow_assignment(node: Node<'_>, target_field: &str, value_field: &str) -> bool {
+    let is_kind = |field: &str, kind: &str| {
+        node.child_by_field_name(field)
+            .map(|child| child.kind() == kind)
+            .unwrap_or_default()
+    };
+    is_kind(target_field, NODE_KIND_IDENTIFIER) && is_kind(value_field, NODE_KIND_ARROW_FUNC)
+}
+
+/// Report the name declared by a node matched by [`matches_target`].
+///
+/// Arrow functions are anonymous, so they are named by the identifier they are assigned to.
+fn extract_name(node: Node<'_>, content: &[u8]) -> Option<String> {
+    let field = match node.kind() {
+        NODE_KIND_ASSIGNMENT_EXPR => FIELD_LEFT,
+        _ => FIELD_NAME,
+    };
+    let name = node.child_by_field_name(field)?;
+    String::from_utf8_lossy(&content[name.byte_range()])
+        .into_owned()
+        .pipe(Some)
+}
+
+/// Report the scope declared by a node enclosing an item, if it declares one.
+///
+/// Functions are scopes when they would be matched by [`matches_target`].
+/// Other function expressions are anonymous functions, unless they are named in the expression
+/// (e.g. `function retry() { ... }` passed as a callback).
+/// Classes (including class expressions) and TypeScript namespaces and modules are also scopes.
+fn scope_of(node: Node<'_>, content: &[u8]) -> Option<SnippetScope> {
+    let kind = match node.kind() {
+        NODE_KIND_CLASS_DECL | NODE_KIND_ABSTRACT_CLASS_DECL | NODE_KIND_CLASS => {
+            SnippetScopeKind::Class
+        }
+        NODE_KIND_INTERNAL_MODULE | NODE_KIND_MODULE => SnippetScopeKind::Namespace,
+        // Arrow functions assigned to an identifier are matched as their assignment,
+        // which is the next ancestor; this avoids reporting the same function twice.
+        NODE_KIND_ARROW_FUNC | NODE_KIND_FUNC_EXPR | NODE_KIND_GENERATOR_FUNC => {
+            let assigned = node
+                .parent()
+                .is_some_and(|parent| matches_target(SnippetTarget::Function, parent));
+            if assigned {
+                return None;
+            }
+            SnippetScopeKind::Function
+        }
+        _ if matches_target(SnippetTarget::Function, node) => SnippetScopeKind::Function,
+        _ => return None,
+    };
+
+    let name = extract_name(node, content);
+    SnippetScope::new(kind).with_name(name).pipe(Some)
+}
+
+/// Find the body of a node matched by [`matches_target`].
+fn function_body(node: Node<'_>) -> Option<Node<'_>> {
+    match node.kind() {
+        NODE_KIND_VARIABLE_DECLARATOR => node.child_by_field_name(FIELD_VALUE)?,
+        NODE_KIND_ASSIGNMENT_EXPR => node.child_by_field_name(FIELD_RIGHT)?,
+        _ => node,
+    }
+    .child_by_field_name(FIELD_BODY)
+}
+
+#[tracing::instrument(skip_all)]
+fn inspect_node(node: &Node<'_>, content: &[u8]) {
+    let location = node.byte_range().pipe(SnippetLocation::from);
+    if node.is_error() {
+        let start = node.start_position();
+        let end = node.end_position();
+        warn!(
+            %location,
+            content = %location.extract_from(content).display_escaped(),
+            kind = %"syntax_error",
+            line_start = start.row,
+            line_end = end.row,
+            col_start = start.column,
+            col_end = end.column,
+        );
+    } else {
+        debug!(
+            %location,
+            content = %location.extract_from(content).display_escaped(),
+            kind = %node.kind(),
+        );
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(super) fn init_parser(
+    language: &tree_sitter::Language,
+) -> Result<tree_sitter::Parser, ExtractorError> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(language)?;
+    Ok(parser)
+}
+
+const NODE_KIND_FUNC_DECL: &str = "function_declaration";
+const NODE_KIND_GENERATOR_FUNC_DECL: &str = "generator_function_declaration";
+const NODE_KIND_METHOD_DEF: &str = "method_definition";
+const NODE_KIND_VARIABLE_DECLARATOR: &str = "variable_declarator";
+const NODE_KIND_ASSIGNMENT_EXPR: &str = "assignment_expression";
+const NODE_KIND_ARROW_FUNC: &str = "arrow_function";
+const NODE_KIND_IDENTIFIER: &str = "identifier";
+const NODE_KIND_FUNC_EXPR: &str = "function_expression";
+const NODE_KIND_GENERATOR_FUNC: &str = "generator_function";
+const NODE_KIND_CLASS_DECL: &str = "class_declaration";
+const NODE_KIND_ABSTRACT_CLASS_DECL: &str = "abstract_class_declaration";
+const NODE_KIND_CLASS: &str = "class";
+const NODE_KIND_INTERNAL_MODULE: &str = "internal_module";
+const NODE_KIND_MODULE: &str = "module";
+const FIELD_BODY: &str = "body";
+const FIELD_NAME: &str = "name";
+const FIELD_VALUE: &str = "value";
+const FIELD_LEFT: &str = "left";
+const FIELD_RIGHT: &str = "right";
+
+#[derive(Debug, Clone)]
+struct FunctionParts<'a> {
+    delimit_byte: usize,
+    signature: Vec<Node<'a>>,
+    body: Vec<Node<'a>>,
+}
+
+impl<'a> FunctionParts<'a> {
+    /// Break a function into the nodes in the body and signature.
+    ///
+    /// Unlike C, the first open brace can't be used as the delimiter:
+    /// parameters may contain braces (e.g. destructuring `function f({ a, b }) {}`),
+    /// and arrow functions may not have braces at all (e.g. `const add = (a, b) => a + b;`).
+    /// Instead, the delimiter is the start of the body of the function.
+    ///
+    /// As a performance optimization, if only the signature is asked for,
+    /// body nodes are not stored.
+    fn from(kind: SnippetKind, node: Node<'a>, content: &'a [u8]) -> Self {
+        let delimit_byte = function_body(node).map(|body| body.start_byte());
+        let nodes = traverse(node.walk()).inspect(|node| inspect_node(node, content));
+
+        let mut signature = Vec::new();
+        let mut body = Vec::new();
+        for node in nodes {
+            match delimit_byte {
+                Some(delimit_byte) if node.start_byte() >= delimit_byte => {
+                    if kind == SnippetKind::Signature {
+                        break;
+                    }
+                    body.push(node);
+                }
+                _ => signature.push(node),
+            }
+        }
+
+        FunctionParts {
+            signature,
+            body,
+            delimit_byte: delimit_byte.unwrap_or_else(|| node.end_byte()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_language_compatible() {
+        let _ = init_parser(&tree_sitter_javascript::LANGUAGE.into())
+            .expect("parser language must be compatible");
+    }
+}
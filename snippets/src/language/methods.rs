@@ -0,0 +1,178 @@
+//! Extracts a snippet for each configured [`SnippetMethod`] from the context of an item.
+//!
+//! Extractors find the context for each kind of snippet of an item once,
+//! then hand it off here; every method then shares that context,
+//! and the intermediate buffers that several transforms have in common are only built once.
+
+use std::borrow::Cow;
+
+use once_cell::unsync::OnceCell;
+use tap::{Pipe, Tap};
+use tracing::debug;
+
+use crate::debugging::ToDisplayEscaped;
+use crate::impl_prelude::*;
+use crate::text::normalize_space;
+
+use super::normalize_comments::normalize_comments;
+use super::normalize_literals::normalize_literals;
+use super::snippet_context::SnippetContext;
+use super::winnow;
+
+/// Extract a snippet of the given kind for each method configured in the options.
+///
+/// Snippets are reported at the location of the context rather than the location of the item,
+/// since the context is a more precise report of where the snippet was found.
+#[tracing::instrument(skip_all, fields(%kind, location = %context.location()))]
+pub(crate) fn extract<L>(
+    kind: SnippetKind,
+    name: Option<&str>,
+    scope: &[SnippetScope],
+    context: &SnippetContext<'_>,
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Vec<Snippet<L>> {
+    let location = context.location();
+    let span = lines.span(location);
+    let texts = Texts::new(context);
+
+    let mut snippets = Vec::new();
+    for method in opts.methods() {
+        let meta = SnippetMetadata::new(kind, method, location)
+            .with_name(name)
+            .with_scope(scope.iter().cloned());
+
+        // Winnowing reports a snippet for each k-gram of tokens it selects,
+        // rather than a single snippet for the text of the whole context.
+        if let (SnippetMethod::Winnowed, Some(winnowing)) = (method, opts.winnowing()) {
+            snippets.extend(winnow::extract(&meta, context, winnowing, opts, lines));
+            continue;
+        }
+
+        // Transformations are applied on text extraction from the context.
+        // A new location is _not_ generated here because the transformed text
+        // won't have any more real of a relation to the original "range of text"
+        // than the context's range.
+        let Some(text) = texts.text(method) else {
+            continue;
+        };
+        debug!(%method, text = %text.display_escaped());
+
+        Snippet::from_options(meta, text, opts)
+            .with_lines(span)
+            .tap(|snippet| debug!(%method, fingerprint = %snippet.fingerprint()))
+            .pipe(|snippet| snippets.push(snippet));
+    }
+
+    snippets
+}
+
+/// The text of a context for each method, sharing work between transforms.
+///
+/// [`SnippetTransform::Comment`] and [`SnippetTransform::Code`] both start by removing comments,
+/// so the content without comments is built at most once and reused by both.
+struct Texts<'a> {
+    context: &'a SnippetContext<'a>,
+    without_comments: OnceCell<Cow<'a, [u8]>>,
+}
+
+impl<'a> Texts<'a> {
+    fn new(context: &'a SnippetContext<'a>) -> Self {
+        Self {
+            context,
+            without_comments: OnceCell::new(),
+        }
+    }
+
+    /// The text for the method, or `None` for methods that don't extract the text of the whole context.
+    fn text(&self, method: SnippetMethod) -> Option<Cow<'_, [u8]>> {
+        match method {
+            // For the happy path, raw snippets, no extra allocations!
+            SnippetMethod::Raw => Cow::from(self.context.content()).pipe(Some),
+            SnippetMethod::Normalized(transform) => self.transform(transform).pipe(Some),
+            // Winnowed snippets are made from k-grams of tokens instead; see `winnow::extract`.
+            SnippetMethod::Winnowed => None,
+        }
+    }
+
+    fn transform(&self, transform: SnippetTransform) -> Cow<'_, [u8]> {
+        match transform {
+            SnippetTransform::Literal => normalize_literals(self.context),
+            SnippetTransform::Code => normalize_space(self.without_comments()),
+            SnippetTransform::Comment => Cow::from(self.without_comments()),
+            SnippetTransform::Space => normalize_space(self.context.content()),
+        }
+    }
+
+    fn without_comments(&self) -> &[u8] {
+        self.without_comments
+            .get_or_init(|| normalize_comments(self.context))
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::impl_prelude::*;
+    use crate::language::snippet_context::SnippetContext;
+    use crate::language::traversal::traverse_tree;
+    use crate::language::{normalize_code, normalize_comments, normalize_literals};
+    use crate::text::normalize_space;
+
+    use super::Texts;
+
+    #[cfg(feature = "lang-c99-tc3")]
+    #[test]
+    fn shared_texts_match_transforms() {
+        //! The shared buffers must not change the text of any method,
+        //! compared to applying each transform on its own.
+        let text = r#"int main() {
+  printf("Hello, world!"); // comment
+  /* A longer comment */
+}"#
+        .as_bytes();
+
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_c::LANGUAGE.into())
+            .expect("Could not set language");
+
+        let tree = parser.parse(text, None).expect("Couldn't parse test text");
+        let context = SnippetContext::from_nodes(
+            traverse_tree(&tree),
+            SnippetLocation::builder()
+                .byte_offset(0)
+                .byte_len(text.len())
+                .build(),
+            text,
+        );
+
+        let texts = Texts::new(&context);
+        let text_for = |transform| {
+            texts
+                .text(SnippetMethod::Normalized(transform))
+                .expect("normalized methods have text")
+                .into_owned()
+        };
+
+        assert_eq!(
+            text_for(SnippetTransform::Code),
+            normalize_code(&context).as_ref()
+        );
+        assert_eq!(
+            text_for(SnippetTransform::Comment),
+            normalize_comments(&context).as_ref()
+        );
+        assert_eq!(
+            text_for(SnippetTransform::Literal),
+            normalize_literals(&context).as_ref()
+        );
+        assert_eq!(
+            text_for(SnippetTransform::Space),
+            normalize_space(text).as_ref()
+        );
+        assert_eq!(texts.text(SnippetMethod::Raw).as_deref(), Some(text));
+        assert_eq!(texts.text(SnippetMethod::Winnowed), None);
+    }
+}
@@ -1,7 +1,5 @@
 use std::borrow::Cow;
 
-use tap::Pipe;
-
 use super::{normalize_comments, snippet_context::SnippetContext};
 use crate::text::normalize_space;
 
@@ -10,10 +8,12 @@ use crate::text::normalize_space;
 /// Uses [`super::normalize_comments`] and [`crate::text::normalize_space`].
 #[tracing::instrument(skip_all)]
 pub fn normalize_code<'a>(context: &'a SnippetContext) -> Cow<'a, [u8]> {
-    normalize_comments(context)
-        .pipe_borrow(normalize_space)
-        .pipe(Vec::from)
-        .into()
+    let text = normalize_comments(context);
+    match normalize_space(&text) {
+        // Nothing to normalize, so the text without comments is reused as-is.
+        Cow::Borrowed(_) => text,
+        Cow::Owned(normalized) => Cow::Owned(normalized),
+    }
 }
 
 #[cfg(test)]
@@ -21,7 +21,7 @@ mod tests {
 
     use crate::impl_prelude::SnippetLocation;
     use crate::language::snippet_context::SnippetContext;
-    use tree_sitter_traversal::{traverse_tree, Order};
+    use crate::language::traversal::traverse_tree;
 
     #[cfg(feature = "lang-c99-tc3")]
     #[test]
@@ -38,12 +38,12 @@ mod tests {
 
         let mut parser = tree_sitter::Parser::new();
         parser
-            .set_language(tree_sitter_c::language())
+            .set_language(&tree_sitter_c::LANGUAGE.into())
             .expect("Could not set language");
 
         let tree = parser.parse(text, None).expect("Couldn't parse test text");
         let context = SnippetContext::from_nodes(
-            traverse_tree(&tree, Order::Pre),
+            traverse_tree(&tree),
             SnippetLocation::builder()
                 .byte_offset(0)
                 .byte_len(text.len())
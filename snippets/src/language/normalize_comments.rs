@@ -1,20 +1,33 @@
-use crate::tree_sitter_consts::NODE_KIND_COMMENT;
+use std::borrow::Cow;
+
+use crate::tree_sitter_consts::{
+    NODE_KIND_BLOCK_COMMENT, NODE_KIND_COMMENT, NODE_KIND_LINE_COMMENT, NODE_KIND_MULTILINE_COMMENT,
+};
 
 use super::snippet_context::SnippetContext;
 
 /// Remove all comment node text from the given content.
-/// In general, this function should work in any language for which treesitter produces nodes whose `kind` equals [`NODE_KIND_COMMENT`].
+/// In general, this function should work in any language for which treesitter produces nodes whose `kind` equals
+/// [`NODE_KIND_COMMENT`], or [`NODE_KIND_LINE_COMMENT`] and [`NODE_KIND_BLOCK_COMMENT`] (as for example Java does),
+/// or [`NODE_KIND_COMMENT`] and [`NODE_KIND_MULTILINE_COMMENT`] (as for example Swift does).
 ///
 /// Because tree sitter parsers can define their own node types
-/// it's possible a comment node may have a kind not equal to [`NODE_KIND_COMMENT`].
+/// it's possible a comment node may have some other kind.
 /// Extractors that use this function should test each language-specific comment syntax at least once.
+///
+/// If the context contains no comments, its content is returned without copying.
 #[tracing::instrument(skip_all)]
-pub fn normalize_comments<'a>(context: &'a SnippetContext) -> Vec<u8> {
-    let comment_nodes = context
-        .nodes()
-        .iter()
-        .filter(|n| n.kind() == NODE_KIND_COMMENT);
-    context.content_around(comment_nodes)
+pub fn normalize_comments<'a>(context: &'a SnippetContext) -> Cow<'a, [u8]> {
+    let comment_nodes = context.nodes().iter().filter(|n| {
+        matches!(
+            n.kind(),
+            NODE_KIND_COMMENT
+                | NODE_KIND_LINE_COMMENT
+                | NODE_KIND_BLOCK_COMMENT
+                | NODE_KIND_MULTILINE_COMMENT
+        )
+    });
+    context.text_around_nodes(comment_nodes)
 }
 
 #[cfg(test)]
@@ -22,7 +35,7 @@ mod tests {
 
     use crate::impl_prelude::SnippetLocation;
     use crate::language::snippet_context::SnippetContext;
-    use tree_sitter_traversal::{traverse_tree, Order};
+    use crate::language::traversal::traverse_tree;
 
     #[cfg(feature = "lang-c99-tc3")]
     #[test]
@@ -42,12 +55,12 @@ mod tests {
 
         let mut parser = tree_sitter::Parser::new();
         parser
-            .set_language(tree_sitter_c::language())
+            .set_language(&tree_sitter_c::LANGUAGE.into())
             .expect("Could not set language");
 
         let tree = parser.parse(text, None).expect("Couldn't parse test text");
         let context = SnippetContext::from_nodes(
-            traverse_tree(&tree, Order::Pre),
+            traverse_tree(&tree),
             SnippetLocation::builder()
                 .byte_offset(0)
                 .byte_len(text.len())
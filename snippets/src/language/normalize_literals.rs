@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+use crate::text::normalize_space;
+use crate::tree_sitter_consts::{
+    NODE_KINDS_CHAR_LITERAL, NODE_KINDS_NUMBER_LITERAL, NODE_KINDS_STRING_LITERAL,
+    NODE_KIND_BLOCK_COMMENT, NODE_KIND_COMMENT, NODE_KIND_LINE_COMMENT,
+    NODE_KIND_MULTILINE_COMMENT,
+};
+
+use super::snippet_context::SnippetContext;
+
+const BLANK_STRING: &[u8] = b"\"\"";
+const BLANK_CHAR: &[u8] = b"''";
+const BLANK_NUMBER: &[u8] = b"0";
+
+/// Perform code normalization, and also blank any literal values.
+///
+/// Comments are removed as in [`super::normalize_comments`], and literals are replaced
+/// with a placeholder for their kind: strings become `""`, characters become `''`, and numbers become `0`.
+/// Then whitespace is normalized with [`crate::text::normalize_space`].
+///
+/// Literals are recognized by the kind of their node, using the kinds
+/// of the grammars for the languages supported by this library.
+/// Extractors that use this function should test each language-specific literal syntax at least once.
+#[tracing::instrument(skip_all)]
+pub fn normalize_literals<'a>(context: &'a SnippetContext) -> Cow<'a, [u8]> {
+    let replacements = context
+        .nodes()
+        .iter()
+        // Keywords are anonymous nodes whose kind is their text,
+        // which may coincide with the kind of a literal (such as the `string` type in PHP).
+        .filter(|node| node.is_named())
+        .filter_map(|node| replacement(node.kind()).map(|replacement| (node, replacement)));
+
+    let text = context.masked_text(replacements);
+    match normalize_space(&text) {
+        // Nothing to normalize, so the masked text is reused as-is.
+        Cow::Borrowed(_) => text,
+        Cow::Owned(normalized) => Cow::Owned(normalized),
+    }
+}
+
+/// The content with which to replace a node of the given kind, if it is replaced.
+fn replacement(kind: &str) -> Option<&'static [u8]> {
+    match kind {
+        NODE_KIND_COMMENT
+        | NODE_KIND_LINE_COMMENT
+        | NODE_KIND_BLOCK_COMMENT
+        | NODE_KIND_MULTILINE_COMMENT => Some(b""),
+        _ if NODE_KINDS_STRING_LITERAL.contains(&kind) => Some(BLANK_STRING),
+        _ if NODE_KINDS_CHAR_LITERAL.contains(&kind) => Some(BLANK_CHAR),
+        _ if NODE_KINDS_NUMBER_LITERAL.contains(&kind) => Some(BLANK_NUMBER),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::impl_prelude::SnippetLocation;
+    use crate::language::snippet_context::SnippetContext;
+    use crate::language::traversal::traverse_tree;
+
+    #[cfg(feature = "lang-c99-tc3")]
+    #[test]
+    fn normalizes_literals() {
+        //! Technically, `[normalize_literals]` has applications beyond C.
+        //! This is meant to be a very basic test and uses C.
+        //! Language specific tests should also be done against this as they are implemented.
+        let text = r#"int main() {
+  printf("Hello, " "world!"); // comment
+  /* A longer comment */
+  putchar('\n');
+  return 42 + 0x1F;
+}"#
+        .as_bytes();
+        let expected_text = r#"int main() { printf(""); putchar(''); return 0 + 0; }"#;
+
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_c::LANGUAGE.into())
+            .expect("Could not set language");
+
+        let tree = parser.parse(text, None).expect("Couldn't parse test text");
+        let context = SnippetContext::from_nodes(
+            traverse_tree(&tree),
+            SnippetLocation::builder()
+                .byte_offset(0)
+                .byte_len(text.len())
+                .build(),
+            text,
+        );
+
+        let out_text = super::normalize_literals(&context);
+        assert_eq!(
+            std::str::from_utf8(out_text.as_ref()).expect("Could not parse out text"),
+            expected_text
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Reuses parsers across extractions on the same thread.
+//!
+//! Creating a parser and assigning it a language is a meaningful fraction of the time spent
+//! extracting snippets from small files, so each thread keeps a parser for each language it has parsed.
+
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    time::{Duration, Instant},
+};
+
+use tree_sitter::{ParseOptions, Parser, Tree};
+
+use crate::impl_prelude::*;
+use crate::Skipped;
+
+thread_local! {
+    static PARSERS: RefCell<HashMap<TypeId, Parser>> = RefCell::new(HashMap::new());
+}
+
+/// Parse the content with the parser for the language `L`,
+/// creating the parser with `init` if this thread hasn't yet parsed that language.
+///
+/// If a timeout is provided and parsing doesn't complete within it,
+/// parsing is abandoned and [`Skipped::TimedOut`] is reported.
+pub(super) fn parse<L: 'static>(
+    content: &[u8],
+    timeout: Option<Duration>,
+    init: impl FnOnce() -> Result<Parser, ExtractorError>,
+) -> Result<Option<Tree>, ExtractorError> {
+    PARSERS.with(|parsers| {
+        let mut parsers = parsers.borrow_mut();
+        let parser = match parsers.entry(TypeId::of::<L>()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(init()?),
+        };
+
+        // Reset so that the parse starts fresh,
+        // even if the previous parse on this parser didn't complete.
+        parser.reset();
+
+        // The parser periodically reports its progress, and abandons the parse if the callback returns true.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut expired = |_: &_| deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let options = ParseOptions::new().progress_callback(&mut expired);
+        let mut read = |offset: usize, _| content.get(offset..).unwrap_or_default();
+        let tree = parser.parse_with_options(&mut read, None, Some(options));
+
+        match (tree, timeout) {
+            (None, Some(timeout)) => Err(Skipped::TimedOut { timeout }.into()),
+            (tree, _) => Ok(tree),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counted;
+
+    #[test]
+    fn reuses_parser() {
+        let mut inits = 0;
+        for _ in 0..3 {
+            let tree = parse::<Counted>(b"int main() {}", None, || {
+                inits += 1;
+                let mut parser = Parser::new();
+                parser.set_language(&tree_sitter_c::LANGUAGE.into())?;
+                Ok(parser)
+            })
+            .expect("must parse");
+            assert!(tree.is_some());
+        }
+        assert_eq!(inits, 1);
+    }
+}
@@ -0,0 +1,501 @@
+//! Implements an [`Extractor`] for the PHP programming language.
+//!
+//! # Standard
+//!
+//! PHP has no formal standard; the language is defined by its reference implementation.
+//! This implementation uses the grammar maintained by the [`tree-sitter`]
+//! project for PHP, [`tree-sitter-php`], which targets the syntax of PHP 8.
+//!
+//! PHP files are templates: PHP code is written in `<?php ... ?>` blocks,
+//! and everything outside of those blocks is text (usually HTML) that is output as-is.
+//! This extractor parses both, so functions are extracted from files that mix PHP and HTML,
+//! including functions whose body closes the PHP block to output HTML.
+//! The text outside of PHP blocks is never extracted on its own.
+//!
+//! # Targets
+//!
+//! This extractor supports extracting the following as snippets from PHP files:
+//!
+//! * Function Signatures
+//! * Function Bodies
+//! * Full Function Declarations
+//!
+//! "Function" means any of:
+//! * Function declarations: `function add($a, $b) { return $a + $b; }`
+//! * Methods of classes, interfaces, traits, and enums: `class Adder { public function add($a, $b) { ... } }`
+//!
+//! Methods without a body (abstract methods or methods declared in an interface)
+//! only produce signature and full snippets.
+//! Anonymous functions and arrow functions are not extracted on their own,
+//! but are included in the snippets of the function containing them.
+//!
+//! Namespaces are reported in the scope of each snippet (see [`Metadata::scope`]),
+//! whether the namespace encloses its declarations in braces (`namespace App { ... }`)
+//! or applies to the rest of the file (`namespace App;`).
+//!
+//! [`Extractor`]: crate::Extractor
+//! [`Metadata::scope`]: crate::Metadata::scope
+//! [`tree-sitter`]: https://github.com/tree-sitter/tree-sitter
+//! [`tree-sitter-php`]: https://github.com/tree-sitter/tree-sitter-php
+
+use std::time::Duration;
+
+use itertools::Itertools;
+use tap::Pipe;
+use tracing::{debug, warn};
+use tree_sitter::Node;
+
+use crate::debugging::ToDisplayEscaped;
+use crate::{impl_language, impl_prelude::*};
+
+use super::methods;
+use super::parser_pool;
+use super::scope;
+use super::snippet_context::SnippetContext;
+use super::traversal::{traverse, traverse_tree};
+
+/// This module implements support for PHP.
+///
+/// Review module documentation for more details.
+#[derive(Copy, Clone)]
+pub struct Language;
+
+impl SnippetLanguage for Language {
+    const NAME: &'static str = "php";
+    const STRATEGY: LanguageStrategy = LanguageStrategy::Static;
+}
+impl_language!(Language);
+
+/// Supports extracting snippets from PHP source code.
+pub struct Extractor;
+
+// This extractor is largely a copy-paste of the java extractor,
+// differing mainly in how nodes are matched and how scopes are reported.
+// If you make changes to this extractor, consider if they should also be made to the other extractors
+// or if the functionality makes sense to be shared.
+impl SnippetExtractor for Extractor {
+    type Language = Language;
+
+    #[tracing::instrument(skip_all, fields(kinds = %opts.kinds(), transforms = %opts.transforms(), content_len = content.as_ref().len()))]
+    fn extract(
+        opts: &SnippetOptions,
+        content: impl AsRef<[u8]>,
+    ) -> Result<Vec<Snippet<Self::Language>>, ExtractorError> {
+        let content = opts.prepare(content.as_ref());
+        let parsed = Self::parse_guarded(opts, &content)?;
+        Self::extract_lazy(&parsed, opts).collect()
+    }
+}
+
+impl LazySnippetExtractor for Extractor {
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, ExtractorError> {
+        parse_within(content, None)
+    }
+
+    fn parse_guarded<'a>(
+        opts: &SnippetOptions,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, ExtractorError> {
+        opts.check_size(content)?;
+        parse_within(content, opts.timeout())
+    }
+
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, Self::Language> {
+        let Some(tree) = parsed.tree() else {
+            return Snippets::empty();
+        };
+        let content = parsed.content();
+        let lines = parsed.lines();
+
+        traverse_tree(tree)
+            // Report syntax errors as warnings.
+            // Always write a debugging line for each node, regardless of the kind of node.
+            .inspect(move |node| inspect_node(node, content))
+            // Nodes that are not "named" are syntax,
+            // which this function currently ignores.
+            //
+            // Reference:
+            // https://tree-sitter.github.io/tree-sitter/using-parsers#named-vs-anonymous-nodes
+            .filter(|node| node.is_named())
+            // Hand each node off to be processed into possibly many snippets,
+            // based on the provided options.
+            .flat_map(move |node| {
+                opts.targets_and_kinds()
+                    .filter(move |(target, _)| matches_target(*target, node))
+                    .filter_map(move |(target, kind)| {
+                        extract(target, kind, node, content, lines, opts)
+                    })
+            })
+            // Each combination of target and kind extracts a snippet for each method.
+            .flatten_ok()
+            // Snippets are only extracted as the caller asks for them.
+            .pipe(Snippets::new)
+    }
+}
+
+impl QuerySnippetExtractor for Extractor {
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError> {
+        Query::new(&tree_sitter_php::LANGUAGE_PHP.into(), source)
+    }
+}
+
+#[tracing::instrument(skip_all, fields(%target, %kind, location = %SnippetLocation::from(node.byte_range())))]
+fn extract<L>(
+    target: SnippetTarget,
+    kind: SnippetKind,
+    node: Node<'_>,
+    content: &[u8],
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
+    match target {
+        SnippetTarget::Function => extract_target(target, kind, node, content, lines, opts),
+        // See `matches_target`.
+        SnippetTarget::Macro | SnippetTarget::Class => None,
+    }
+}
+
+#[tracing::instrument(skip_all)]
+fn extract_target<L>(
+    target: SnippetTarget,
+    kind: SnippetKind,
+    node: Node<'_>,
+    content: &[u8],
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
+    // The raw content here is just extracted for debugging.
+    let raw = &content[node.byte_range()];
+    debug!(raw = %raw.display_escaped());
+
+    // The actual context, the part the snippet scanner cares about, is extracted here.
+    // It also returns a new location so FOSSA can report a more accurate range for the snippet.
+    // Every method shares this context, so it is only extracted once for each kind of snippet.
+    let context = extract_context(target, kind, node, content)?;
+    // Full snippets optionally also cover the comments documenting the item.
+    let context = match kind {
+        SnippetKind::Full if opts.leading_comments() => context.with_leading_comments(node),
+        _ => context,
+    };
+    debug!(context = %context.content().display_escaped());
+
+    // Trivially small snippets are skipped before any transform is applied,
+    // so that every method for this context is kept or skipped together.
+    if !context.meets_minimum(opts) {
+        debug!("context is smaller than the configured minimum");
+        return None;
+    }
+
+    let name = extract_name(node, content);
+    let scope = file_namespace(node, content)
+        .into_iter()
+        .chain(scope::enclosing(node, |ancestor| {
+            scope_of(ancestor, content)
+        }))
+        .collect_vec();
+    methods::extract(kind, name.as_deref(), &scope, &context, lines, opts)
+        .pipe(Ok)
+        .pipe(Some)
+}
+
+/// Extracts the "context" of a node for the provided kind of snippet.
+///
+/// This consists of:
+/// - A vector of parsed [`Node`]s that make up the part of the subtree considered relevant.
+/// - A [`SnippetLocation`] selecting the (usually constrained) span that makes up the context.
+///
+/// Both are returned instead of just one or the other because different text extractors care
+/// about different sets of data. Specifically:
+/// - [`SnippetMethod::Raw`] needs the text _as written_.
+/// - [`SnippetTransform::Comment`] needs both; to find comments and then slice them out.
+/// - [`SnippetTransform::Space`] needs the parsed form so it normalizes spaces.
+/// - [`SnippetTransform::Code`] needs the parsed form so it normalizes spaces and slices comments.
+/// - [`SnippetTransform::Literal`] needs the parsed form so it also blanks literals.
+#[tracing::instrument(skip_all)]
+fn extract_context<'a>(
+    target: SnippetTarget,
+    kind: SnippetKind,
+    node: Node<'a>,
+    content: &'a [u8],
+) -> Option<SnippetContext<'a>> {
+    match kind {
+        SnippetKind::Full => Some(SnippetContext::new(node, node.byte_range().into(), content)),
+        SnippetKind::Body => {
+            let parts = Parts::from(kind, node, content);
+
+            // Unless a delimiter was found, not much for this extractor to do.
+            if parts.body.is_empty() {
+                warn!("{target} body not found");
+                return None;
+            }
+
+            // This node ends at the end of the function.
+            // Since the end of the delimiter signifies the start, anything between is the body.
+            let mut offset = parts.delimit_byte;
+            let end = node.end_byte();
+
+            // Spaces between the delimiter end and start of the actual
+            // body content should not be significant.
+            while offset < end && content[offset].is_ascii_whitespace() {
+                offset += 1;
+            }
+            if offset == end {
+                warn!("{target} body appears to be made up entirely of spaces");
+                offset = parts.delimit_byte;
+            }
+
+            // The new location is meant to enable a more precise report of where
+            // exactly the snippet was found in the file; otherwise the snippet
+            // would be reported to have come from the whole function
+            // instead of just this small part.
+            let report_as = SnippetLocation::builder()
+                .byte_offset(offset)
+                .byte_len(end - offset)
+                .build();
+
+            // The context reports the nodes that made up this extracted snippet,
+            // for future pipeline operations to use.
+            SnippetContext::from_nodes(parts.body, report_as, content).pipe(Some)
+        }
+        SnippetKind::Signature => {
+            let parts = Parts::from(kind, node, content);
+
+            // Unless a delimiter was found, not much for this extractor to do.
+            if parts.signature.is_empty() {
+                warn!("{target} signature not found");
+                return None;
+            }
+
+            // This node starts at the start of the function.
+            // Since the start of the delimiter signifies the end, anything between is the signature.
+            let mut offset = parts.delimit_byte;
+            let start = node.start_byte();
+
+            // Spaces between the signature and delimiter are not significant.
+            while offset > start && content[offset - 1].is_ascii_whitespace() {
+                offset -= 1;
+            }
+            if offset == start {
+                warn!("{target} signature appears to be made up entirely of spaces");
+                offset = parts.delimit_byte;
+            }
+
+            // The new location is meant to enable a more precise report of where
+            // exactly the snippet was found in the file; otherwise the snippet
+            // would be reported to have come from the whole function
+            // instead of just this small part.
+            let report_as = SnippetLocation::builder()
+                .byte_offset(node.start_byte())
+                .byte_len(offset - node.start_byte())
+                .build();
+
+            // The context reports the nodes that made up this extracted snippet,
+            // for future pipeline operations to use.
+            SnippetContext::from_nodes(parts.signature, report_as, content).pipe(Some)
+        }
+    }
+}
+
+/// Report whether the given treesitter node kind is a valid entrypoint for the target.
+///
+/// Defined here instead of on [`SnippetTarget`] because that type should be generic across
+/// language parse strategies instead of being tied to treesitter-specific implementations.
+#[tracing::instrument(level = "DEBUG", skip_all, fields(%target, node_kind = %node.kind()), ret)]
+fn matches_target(target: SnippetTarget, node: Node<'_>) -> bool {
+    match target {
+        SnippetTarget::Function => {
+            matches!(node.kind(), NODE_KIND_FUNC_DEF | NODE_KIND_METHOD_DECL)
+        }
+        SnippetTarget::Macro | SnippetTarget::Class => false,
+    }
+}
+
+/// Report the name declared by a node in its `name` field.
+///
+/// Every function matched by [`matches_target`] is named,
+/// as are the classes and namespaces reported as scopes, except for anonymous classes.
+fn extract_name(node: Node<'_>, content: &[u8]) -> Option<String> {
+    let name = node.child_by_field_name(FIELD_NAME)?;
+    String::from_utf8_lossy(&content[name.byte_range()])
+        .into_owned()
+        .pipe(Some)
+}
+
+/// Report the scope declared by a node enclosing an item, if it declares one.
+///
+/// Functions are scopes when they would be matched by [`matches_target`].
+/// Classes, interfaces, traits, and enums are class scopes; anonymous classes are too, without a name.
+/// Anonymous functions and arrow functions are function scopes without a name.
+/// Namespaces are only ancestors of the items they enclose when declared with braces;
+/// see [`file_namespace`] for namespaces that apply to the rest of the file.
+fn scope_of(node: Node<'_>, content: &[u8]) -> Option<SnippetScope> {
+    let kind = match node.kind() {
+        NODE_KIND_CLASS_DECL
+        | NODE_KIND_INTERFACE_DECL
+        | NODE_KIND_TRAIT_DECL
+        | NODE_KIND_ENUM_DECL
+        | NODE_KIND_ANONYMOUS_CLASS => SnippetScopeKind::Class,
+        // Namespaces without a name declare the global namespace, which isn't a scope.
+        NODE_KIND_NAMESPACE_DEF if node.child_by_field_name(FIELD_NAME).is_some() => {
+            SnippetScopeKind::Namespace
+        }
+        NODE_KIND_ANONYMOUS_FUNC | NODE_KIND_ARROW_FUNC => SnippetScopeKind::Function,
+        _ if matches_target(SnippetTarget::Function, node) => SnippetScopeKind::Function,
+        _ => return None,
+    };
+
+    let name = extract_name(node, content);
+    SnippetScope::new(kind).with_name(name).pipe(Some)
+}
+
+/// Report the namespace that applies to the node because it was declared earlier in the file
+/// without braces (`namespace App;`), if any.
+///
+/// Such a namespace applies to every statement until the next namespace declaration,
+/// but the statements are siblings of the declaration rather than its children,
+/// so the namespace isn't an ancestor of the node.
+fn file_namespace(node: Node<'_>, content: &[u8]) -> Option<SnippetScope> {
+    let mut statement = node;
+    while let Some(parent) = statement.parent() {
+        if parent.kind() == NODE_KIND_PROGRAM {
+            break;
+        }
+        statement = parent;
+    }
+
+    let mut sibling = statement.prev_named_sibling();
+    while let Some(node) = sibling {
+        if node.kind() == NODE_KIND_NAMESPACE_DEF {
+            // Namespaces declared with braces end with their braces;
+            // they don't apply to the statements after them.
+            if node.child_by_field_name(FIELD_BODY).is_some() {
+                return None;
+            }
+            let name = extract_name(node, content)?;
+            return SnippetScope::new(SnippetScopeKind::Namespace)
+                .with_name(Some(name))
+                .pipe(Some);
+        }
+        sibling = node.prev_named_sibling();
+    }
+    None
+}
+
+#[tracing::instrument(skip_all)]
+fn inspect_node(node: &Node<'_>, content: &[u8]) {
+    let location = node.byte_range().pipe(SnippetLocation::from);
+    if node.is_error() {
+        let start = node.start_position();
+        let end = node.end_position();
+        warn!(
+            %location,
+            content = %location.extract_from(content).display_escaped(),
+            kind = %"syntax_error",
+            line_start = start.row,
+            line_end = end.row,
+            col_start = start.column,
+            col_end = end.column,
+        );
+    } else {
+        debug!(
+            %location,
+            content = %location.extract_from(content).display_escaped(),
+            kind = %node.kind(),
+        );
+    }
+}
+
+/// Parse the content, abandoning the parse if it doesn't complete within the timeout.
+fn parse_within(
+    content: &[u8],
+    timeout: Option<Duration>,
+) -> Result<Parsed<'_, Language>, ExtractorError> {
+    let tree = parser_pool::parse::<Language>(content, timeout, init_parser)?;
+    if tree.is_none() {
+        warn!("provided content did not parse to a tree");
+    }
+    Ok(Parsed::new(tree, content))
+}
+
+/// Create a parser for PHP files, which may mix PHP blocks with HTML.
+///
+/// The grammar for PHP without the surrounding HTML (`LANGUAGE_PHP_ONLY`) is not used,
+/// since it can't parse files that contain text outside of PHP blocks.
+#[tracing::instrument]
+fn init_parser() -> Result<tree_sitter::Parser, ExtractorError> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_php::LANGUAGE_PHP.into())?;
+    Ok(parser)
+}
+
+const NODE_KIND_FUNC_DEF: &str = "function_definition";
+const NODE_KIND_METHOD_DECL: &str = "method_declaration";
+const NODE_KIND_CLASS_DECL: &str = "class_declaration";
+const NODE_KIND_INTERFACE_DECL: &str = "interface_declaration";
+const NODE_KIND_TRAIT_DECL: &str = "trait_declaration";
+const NODE_KIND_ENUM_DECL: &str = "enum_declaration";
+const NODE_KIND_ANONYMOUS_CLASS: &str = "anonymous_class";
+const NODE_KIND_ANONYMOUS_FUNC: &str = "anonymous_function";
+const NODE_KIND_ARROW_FUNC: &str = "arrow_function";
+const NODE_KIND_NAMESPACE_DEF: &str = "namespace_definition";
+const NODE_KIND_PROGRAM: &str = "program";
+const FIELD_BODY: &str = "body";
+const FIELD_NAME: &str = "name";
+
+#[derive(Debug, Clone)]
+struct Parts<'a> {
+    delimit_byte: usize,
+    signature: Vec<Node<'a>>,
+    body: Vec<Node<'a>>,
+}
+
+impl<'a> Parts<'a> {
+    /// Break a function or method into the nodes in the body and signature.
+    ///
+    /// As in Java, the delimiter is the start of the `body` field of the declaration:
+    /// attributes in the signature may contain braces (e.g. `#[Route(options: ['a' => 1])]`),
+    /// so the first open brace can't be used.
+    ///
+    /// As a performance optimization, if only the signature is asked for,
+    /// body nodes are not stored.
+    fn from(kind: SnippetKind, node: Node<'a>, content: &'a [u8]) -> Self {
+        // Methods without a body (abstract and interface methods) are made up entirely of signature.
+        let delimit_byte = node
+            .child_by_field_name(FIELD_BODY)
+            .map(|body| body.start_byte());
+        let nodes = traverse(node.walk()).inspect(|node| inspect_node(node, content));
+
+        let mut signature = Vec::new();
+        let mut body = Vec::new();
+        for node in nodes {
+            match delimit_byte {
+                Some(delimit_byte) if node.start_byte() >= delimit_byte => {
+                    if kind == SnippetKind::Signature {
+                        break;
+                    }
+                    body.push(node);
+                }
+                _ => signature.push(node),
+            }
+        }
+
+        Parts {
+            signature,
+            body,
+            delimit_byte: delimit_byte.unwrap_or_else(|| node.end_byte()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_language_compatible() {
+        let _ = init_parser().expect("parser language must be compatible");
+    }
+}
@@ -0,0 +1,86 @@
+//! Extraction of preprocessor macro definitions, shared by the C and C++ extractors,
+//! whose grammars parse `#define` directives the same way.
+
+use tap::Pipe;
+use tracing::warn;
+use tree_sitter::Node;
+
+use crate::impl_prelude::*;
+
+use super::snippet_context::SnippetContext;
+use super::traversal::traverse;
+
+const NODE_KIND_PREPROC_DEF: &str = "preproc_def";
+const NODE_KIND_PREPROC_FUNCTION_DEF: &str = "preproc_function_def";
+const FIELD_NAME: &str = "name";
+const FIELD_VALUE: &str = "value";
+
+/// Report whether the node is an object-like or function-like macro definition with a value.
+///
+/// Macros without a value (such as include guards, e.g. `#define FOO_H`) are not matched,
+/// since they are too short to be meaningful snippets.
+pub(super) fn is_macro_definition(node: Node<'_>) -> bool {
+    matches!(
+        node.kind(),
+        NODE_KIND_PREPROC_DEF | NODE_KIND_PREPROC_FUNCTION_DEF
+    ) && node.child_by_field_name(FIELD_VALUE).is_some()
+}
+
+/// Find the name of the macro defined by the node, e.g. `SQUARE` for `#define SQUARE(x) ((x) * (x))`.
+pub(super) fn macro_name(node: Node<'_>) -> Option<Node<'_>> {
+    node.child_by_field_name(FIELD_NAME)
+}
+
+/// Extracts the "context" of a macro definition for the provided kind of snippet.
+///
+/// The signature of a macro is the directive up to its value (e.g. `#define SQUARE(x)`),
+/// and the body is its value (e.g. `((x) * (x))`).
+///
+/// The grammar includes the newline ending the directive in the node,
+/// so trailing whitespace is not included in any context.
+pub(super) fn extract_context<'a>(
+    kind: SnippetKind,
+    node: Node<'a>,
+    content: &'a [u8],
+) -> Option<SnippetContext<'a>> {
+    let Some(value) = node.child_by_field_name(FIELD_VALUE) else {
+        warn!("macro value not found");
+        return None;
+    };
+
+    let start = node.start_byte();
+    let end = trim_end(content, start, node.end_byte());
+    let nodes = traverse(node.walk());
+    let (offset, end, nodes) = match kind {
+        SnippetKind::Full => (start, end, nodes.collect::<Vec<_>>()),
+        SnippetKind::Body => (
+            value.start_byte(),
+            end,
+            nodes
+                .filter(|node| node.start_byte() >= value.start_byte())
+                .collect(),
+        ),
+        SnippetKind::Signature => (
+            start,
+            trim_end(content, start, value.start_byte()),
+            nodes
+                .take_while(|node| node.start_byte() < value.start_byte())
+                .collect(),
+        ),
+    };
+
+    let report_as = SnippetLocation::builder()
+        .byte_offset(offset)
+        .byte_len(end - offset)
+        .build();
+
+    SnippetContext::from_nodes(nodes, report_as, content).pipe(Some)
+}
+
+/// Move `end` backwards over any whitespace, stopping at `start`.
+fn trim_end(content: &[u8], start: usize, mut end: usize) -> usize {
+    while end > start && content[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    end
+}
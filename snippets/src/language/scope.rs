@@ -0,0 +1,24 @@
+//! Finds the scopes enclosing an item, shared by the prebuilt extractors.
+
+use tree_sitter::Node;
+
+use crate::impl_prelude::*;
+
+/// Report the scopes enclosing the node, from outermost to innermost.
+///
+/// Each ancestor of the node is classified by `scope_of`, which reports the scope the ancestor declares (if any)
+/// according to the grammar of the language.
+pub(crate) fn enclosing<'a>(
+    node: Node<'a>,
+    scope_of: impl Fn(Node<'a>) -> Option<SnippetScope>,
+) -> Vec<SnippetScope> {
+    let mut scopes = Vec::new();
+    let mut ancestor = node.parent();
+    while let Some(node) = ancestor {
+        scopes.extend(scope_of(node));
+        ancestor = node.parent();
+    }
+
+    scopes.reverse();
+    scopes
+}
@@ -1,7 +1,13 @@
-use crate::impl_prelude::SnippetLocation;
+use std::borrow::Cow;
+
+use crate::impl_prelude::{SnippetLocation, SnippetOptions};
+use crate::tree_sitter_consts::{
+    NODE_KIND_BLOCK_COMMENT, NODE_KIND_COMMENT, NODE_KIND_LINE_COMMENT, NODE_KIND_MULTILINE_COMMENT,
+};
 use getset::{CopyGetters, Getters};
 use tree_sitter::Node;
-use tree_sitter_traversal::{traverse, Order};
+
+use super::traversal::traverse;
 
 /// This structure represents a view into a larger piece of parsed text.
 /// For snippet scanning, we generally look at just parts of a larger piece of text for each snippet.
@@ -25,7 +31,7 @@ impl<'a> SnippetContext<'a> {
     /// Ensure that the content provided is the same as the content used to extract the parent node;
     /// byte offsets must line up for operations on this type to make sense.
     pub fn new(parent: Node<'a>, location: SnippetLocation, content: &'a [u8]) -> Self {
-        Self::from_nodes(traverse(parent.walk(), Order::Pre), location, content)
+        Self::from_nodes(traverse(parent.walk()), location, content)
     }
 
     /// Make a new instance from a set of nodes and their location within the original parsed text.
@@ -49,19 +55,239 @@ impl<'a> SnippetContext<'a> {
         self.location.extract_from(self.content)
     }
 
-    /// Get content from the snippet which is not in ranges covered by the provided nodes.
-    pub fn content_around(&self, nodes: impl Iterator<Item = &'a Node<'a>>) -> Vec<u8> {
-        let mut slices = Vec::new();
-        let mut start_byte = self.location.start_byte();
+    /// The syntax tokens in the snippet, in the order in which they appear:
+    /// the leaf nodes inside its location, not counting comments.
+    ///
+    /// Zero-width nodes (such as those inserted by the parser to recover from a syntax error)
+    /// are not tokens in the source code, so they aren't included either.
+    pub fn tokens(&self) -> impl Iterator<Item = &Node<'a>> {
+        let range = self.location.as_range();
+        self.nodes
+            .iter()
+            .filter(|node| node.child_count() == 0)
+            .filter(|node| node.start_byte() < node.end_byte())
+            .filter(move |node| range.start <= node.start_byte() && node.end_byte() <= range.end)
+            .filter(|node| !is_comment(node))
+    }
+
+    /// Count the syntax tokens in the snippet; see [`SnippetContext::tokens`].
+    pub fn token_count(&self) -> usize {
+        self.tokens().count()
+    }
+
+    /// Extract the text of a node from the content in which this snippet resides.
+    pub fn text_of(&self, node: &Node<'_>) -> &'a [u8] {
+        &self.content[node.byte_range()]
+    }
+
+    /// Report whether the snippet meets the minimum size configured in the options.
+    pub fn meets_minimum(&self, opts: &SnippetOptions) -> bool {
+        let bytes = self.location.as_range().len();
+        // Counting tokens walks the nodes, so skip it if it isn't needed.
+        let tokens = if opts.min_tokens() > 0 {
+            self.token_count()
+        } else {
+            0
+        };
+        opts.meets_minimum(bytes, tokens)
+    }
 
-        for node in nodes {
-            let node_start_byte = node.start_byte();
-            slices.push(&self.content[start_byte..node_start_byte]);
+    /// Extend the snippet to include the block of comments immediately preceding it,
+    /// such as a doc comment or license header.
+    ///
+    /// Provide the node from which the snippet was extracted; comments are found among the siblings
+    /// of that node (or of its outermost ancestor starting at the same position as the snippet).
+    /// A comment is included if it starts its own line and no blank line separates it from the snippet
+    /// or from the comment after it; so a comment trailing code on the line before the snippet is not included.
+    pub fn with_leading_comments(self, node: Node<'a>) -> Self {
+        let start_byte = self.location.start_byte();
+        let mut outer = node;
+        while let Some(parent) = outer.parent().filter(|p| p.start_byte() >= start_byte) {
+            outer = parent;
+        }
+
+        let mut comments = Vec::new();
+        let mut next_byte = start_byte;
+        let mut sibling = outer.prev_sibling();
+        while let Some(comment) = sibling.filter(is_comment) {
+            if !self.is_contiguous(comment.end_byte(), next_byte)
+                || !self.starts_line(comment.start_byte())
+            {
+                break;
+            }
+            next_byte = comment.start_byte();
+            comments.push(comment);
+            sibling = comment.prev_sibling();
+        }
+
+        if comments.is_empty() {
+            return self;
+        }
+
+        let location = SnippetLocation::from(next_byte..self.location.as_range().end);
+        let nodes = comments
+            .into_iter()
+            .rev()
+            .flat_map(|comment| traverse(comment.walk()))
+            .chain(self.nodes);
+        Self::from_nodes(nodes, location, self.content)
+    }
+
+    /// Report whether only whitespace, without a blank line, separates the two positions.
+    fn is_contiguous(&self, end_byte: usize, start_byte: usize) -> bool {
+        let between = &self.content[end_byte..start_byte];
+        between.iter().all(u8::is_ascii_whitespace)
+            && between.iter().filter(|&&b| b == b'\n').count() <= 1
+    }
+
+    /// Report whether only whitespace precedes the position on its line.
+    fn starts_line(&self, byte: usize) -> bool {
+        self.content[..byte]
+            .iter()
+            .rev()
+            .take_while(|&&b| b != b'\n')
+            .all(u8::is_ascii_whitespace)
+    }
+
+    /// Get the text of the snippet, without the text covered by the provided nodes.
+    ///
+    /// Nodes are handled as in [`SnippetContext::masked_text`].
+    pub fn text_around_nodes<'n, 't: 'n>(
+        &self,
+        nodes: impl IntoIterator<Item = &'n Node<'t>>,
+    ) -> Cow<'a, [u8]> {
+        self.masked_text(nodes.into_iter().map(|node| (node, [].as_slice())))
+    }
+
+    /// Get the text of the snippet, replacing the text covered by each provided node
+    /// with the mask provided for that node.
+    ///
+    /// Nodes must be provided in the order in which they appear in the content.
+    /// Nodes inside the range of a node that was already masked, or which extend outside the snippet,
+    /// are ignored.
+    ///
+    /// The text is only copied if a node is actually masked;
+    /// otherwise the text of the snippet is borrowed from the content as-is.
+    pub fn masked_text<'n, 't: 'n, 'm>(
+        &self,
+        masks: impl IntoIterator<Item = (&'n Node<'t>, &'m [u8])>,
+    ) -> Cow<'a, [u8]> {
+        let range = self.location.as_range();
+        let mut masked: Option<Vec<u8>> = None;
+        let mut start_byte = range.start;
+
+        for (node, mask) in masks {
+            if node.start_byte() < start_byte || node.end_byte() > range.end {
+                continue;
+            }
+            // Masking an empty range with nothing wouldn't change the text.
+            if node.start_byte() == node.end_byte() && mask.is_empty() {
+                continue;
+            }
+
+            let text = masked.get_or_insert_with(|| Vec::with_capacity(range.len()));
+            text.extend_from_slice(&self.content[start_byte..node.start_byte()]);
+            text.extend_from_slice(mask);
             start_byte = node.end_byte();
         }
 
-        // `Location::end_byte` is inclusive.
-        slices.push(&self.content[start_byte..=self.location.end_byte()]);
-        slices.concat()
+        match masked {
+            Some(mut text) => {
+                text.extend_from_slice(&self.content[start_byte..range.end]);
+                Cow::Owned(text)
+            }
+            None => Cow::Borrowed(&self.content[range]),
+        }
+    }
+}
+
+fn is_comment(node: &Node<'_>) -> bool {
+    matches!(
+        node.kind(),
+        NODE_KIND_COMMENT
+            | NODE_KIND_LINE_COMMENT
+            | NODE_KIND_BLOCK_COMMENT
+            | NODE_KIND_MULTILINE_COMMENT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::impl_prelude::SnippetLocation;
+    use crate::language::traversal::traverse_tree;
+    use tree_sitter::Node;
+
+    use super::SnippetContext;
+
+    #[cfg(feature = "lang-c99-tc3")]
+    fn with_context(text: &[u8], location: SnippetLocation, f: impl FnOnce(&SnippetContext)) {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_c::LANGUAGE.into())
+            .expect("Could not set language");
+
+        let tree = parser.parse(text, None).expect("Couldn't parse test text");
+        let context = SnippetContext::from_nodes(traverse_tree(&tree), location, text);
+        f(&context)
+    }
+
+    #[cfg(feature = "lang-c99-tc3")]
+    fn kind<'a>(context: &'a SnippetContext, kind: &'a str) -> impl Iterator<Item = &'a Node<'a>> {
+        context
+            .nodes()
+            .iter()
+            .filter(move |node| node.kind() == kind)
+    }
+
+    #[cfg(feature = "lang-c99-tc3")]
+    #[test]
+    fn masked_text_borrows_without_masks() {
+        let text = b"int main() { return 0; }";
+        let location = SnippetLocation::from(4..10);
+
+        with_context(text, location, |context| {
+            let masked =
+                context.masked_text(kind(context, "comment").map(|node| (node, &b"x"[..])));
+            assert!(matches!(masked, Cow::Borrowed(b"main()")));
+
+            let around = context.text_around_nodes(kind(context, "comment"));
+            assert!(matches!(around, Cow::Borrowed(b"main()")));
+        });
+    }
+
+    #[cfg(feature = "lang-c99-tc3")]
+    #[test]
+    fn masked_text_masks_nodes() {
+        let text = b"int add(int a) { return a + 1; }";
+        let location = SnippetLocation::from(15..32);
+
+        with_context(text, location, |context| {
+            // The parameter is outside the snippet, so only the identifier in the body is masked.
+            let masked =
+                context.masked_text(kind(context, "identifier").map(|node| (node, &b"_"[..])));
+            assert_eq!(masked.as_ref(), b"{ return _ + 1; }");
+
+            // Nodes inside a node that was already masked are ignored.
+            let nodes = ["return_statement", "number_literal"]
+                .into_iter()
+                .flat_map(|k| kind(context, k))
+                .map(|node| (node, &b"ret;"[..]));
+            let masked = context.masked_text(nodes);
+            assert_eq!(masked.as_ref(), b"{ ret; }");
+        });
+    }
+
+    #[cfg(feature = "lang-c99-tc3")]
+    #[test]
+    fn text_around_nodes_removes_nodes() {
+        let text = b"int main() { /* a */ return 0; // b\n}";
+        let location = SnippetLocation::from(0..text.len());
+
+        with_context(text, location, |context| {
+            let around = context.text_around_nodes(kind(context, "comment"));
+            assert_eq!(around.as_ref(), b"int main() {  return 0; \n}");
+        });
     }
 }
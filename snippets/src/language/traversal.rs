@@ -0,0 +1,100 @@
+//! Walks the nodes of a parsed syntax tree, shared by the prebuilt extractors and normalizations.
+
+use std::iter::FusedIterator;
+
+use tree_sitter::{Node, Tree, TreeCursor};
+
+/// Iterate over the node under the cursor and all of its descendants, in pre-order:
+/// each node is reported before its children, and children are reported in the order in which they appear.
+pub(crate) fn traverse(cursor: TreeCursor<'_>) -> impl FusedIterator<Item = Node<'_>> {
+    PreOrder {
+        cursor: Some(cursor),
+        started: false,
+    }
+}
+
+/// Iterate over all the nodes in the tree, in pre-order; see [`traverse`].
+pub(crate) fn traverse_tree(tree: &Tree) -> impl FusedIterator<Item = Node<'_>> {
+    traverse(tree.walk())
+}
+
+struct PreOrder<'a> {
+    /// The cursor is dropped once the traversal has finished.
+    cursor: Option<TreeCursor<'a>>,
+    started: bool,
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cursor = self.cursor.as_mut()?;
+        if !self.started {
+            self.started = true;
+            return Some(cursor.node());
+        }
+
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            return Some(cursor.node());
+        }
+
+        // Climb back up until an ancestor has a sibling that hasn't been visited;
+        // the cursor can't climb above the node it started on, so this ends the traversal there.
+        while cursor.goto_parent() {
+            if cursor.goto_next_sibling() {
+                return Some(cursor.node());
+            }
+        }
+
+        self.cursor = None;
+        None
+    }
+}
+
+impl FusedIterator for PreOrder<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::Parser;
+
+    use super::*;
+
+    #[test]
+    fn visits_parents_before_children() {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_c::LANGUAGE.into())
+            .expect("must set language");
+        let tree = parser.parse("int a; int b;", None).expect("must parse");
+
+        let kinds = traverse_tree(&tree)
+            .filter(|node| node.is_named())
+            .map(|node| node.kind())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "translation_unit",
+                "declaration",
+                "primitive_type",
+                "identifier",
+                "declaration",
+                "primitive_type",
+                "identifier",
+            ]
+        );
+    }
+
+    #[test]
+    fn stays_within_starting_node() {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_c::LANGUAGE.into())
+            .expect("must set language");
+        let tree = parser.parse("int a; int b;", None).expect("must parse");
+
+        let first = tree.root_node().child(0).expect("must have a declaration");
+        let count = traverse(first.walk()).count();
+        assert_eq!(count, 4, "declaration, type, identifier, and semicolon");
+    }
+}
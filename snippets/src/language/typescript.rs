@@ -0,0 +1,107 @@
+//! Implements an [`Extractor`] for the TypeScript programming language.
+//!
+//! # Standard
+//!
+//! TypeScript is a superset of JavaScript which adds static types,
+//! and has evolved over the years via different [releases].
+//! This implementation uses the TypeScript grammar maintained by the [`tree-sitter`]
+//! project, [`tree-sitter-typescript`], which extends the JavaScript grammar.
+//!
+//! For files containing JSX (typically `.tsx` files), use [`super::tsx`] instead:
+//! the two dialects are not compatible, since type assertions like `<T>value`
+//! are ambiguous with JSX elements.
+//!
+//! # Targets
+//!
+//! Since the TypeScript grammar extends the JavaScript grammar, this extractor shares its implementation
+//! with [`super::javascript`], and supports extracting the same targets.
+//! Review that module for details.
+//!
+//! Type parameters and annotations are part of the signature of a function,
+//! so for example the signature of `function id<T>(value: T): T { return value; }`
+//! is `function id<T>(value: T): T`.
+//! Declarations without a body, such as overload signatures and abstract methods, are not extracted.
+//!
+//! [`Extractor`]: crate::Extractor
+//! [releases]: https://github.com/microsoft/TypeScript/wiki/Roadmap
+//! [`tree-sitter`]: https://github.com/tree-sitter/tree-sitter
+//! [`tree-sitter-typescript`]: https://github.com/tree-sitter/tree-sitter-typescript
+
+use crate::{impl_language, impl_prelude::*};
+
+use super::javascript::{extract_lazy_with, parse_with};
+
+/// This module implements support for TypeScript.
+///
+/// Review module documentation for more details.
+#[derive(Copy, Clone)]
+pub struct Language;
+
+impl SnippetLanguage for Language {
+    const NAME: &'static str = "typescript";
+    const STRATEGY: LanguageStrategy = LanguageStrategy::Static;
+}
+impl_language!(Language);
+
+/// Supports extracting snippets from TypeScript source code.
+pub struct Extractor;
+
+impl SnippetExtractor for Extractor {
+    type Language = Language;
+
+    #[tracing::instrument(skip_all, fields(kinds = %opts.kinds(), transforms = %opts.transforms(), content_len = content.as_ref().len()))]
+    fn extract(
+        opts: &SnippetOptions,
+        content: impl AsRef<[u8]>,
+    ) -> Result<Vec<Snippet<Self::Language>>, ExtractorError> {
+        let content = opts.prepare(content.as_ref());
+        let parsed = Self::parse_guarded(opts, &content)?;
+        Self::extract_lazy(&parsed, opts).collect()
+    }
+}
+
+impl LazySnippetExtractor for Extractor {
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, ExtractorError> {
+        parse_with::<Self::Language>(
+            &tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            content,
+            None,
+        )
+    }
+
+    fn parse_guarded<'a>(
+        opts: &SnippetOptions,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, ExtractorError> {
+        opts.check_size(content)?;
+        parse_with::<Self::Language>(
+            &tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            content,
+            opts.timeout(),
+        )
+    }
+
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, Self::Language> {
+        extract_lazy_with(parsed, opts)
+    }
+}
+
+impl QuerySnippetExtractor for Extractor {
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError> {
+        Query::new(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(), source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::javascript::init_parser;
+
+    #[test]
+    fn parser_language_compatible() {
+        let _ = init_parser(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
+            .expect("parser language must be compatible");
+    }
+}
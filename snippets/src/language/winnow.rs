@@ -0,0 +1,122 @@
+//! Extraction of [`SnippetMethod::Winnowed`] snippets, shared by all extractors.
+
+use tracing::debug;
+
+use crate::debugging::ToDisplayEscaped;
+use crate::impl_prelude::*;
+use crate::Winnowing;
+
+use super::snippet_context::SnippetContext;
+
+/// Extract a snippet for each k-gram of tokens in the context selected by winnowing.
+///
+/// The metadata provides the kind, method, and name of each snippet;
+/// the location of each snippet is the span of the tokens in its k-gram.
+#[tracing::instrument(skip_all, fields(k = %winnowing.k(), window = %winnowing.window()))]
+pub(crate) fn extract<L>(
+    meta: &SnippetMetadata,
+    context: &SnippetContext<'_>,
+    winnowing: Winnowing,
+    opts: &SnippetOptions,
+    lines: &LineIndex,
+) -> Vec<Snippet<L>> {
+    let k = winnowing.k().get();
+    let tokens = context.tokens().collect::<Vec<_>>();
+    if tokens.len() < k {
+        debug!(tokens = tokens.len(), "too few tokens to winnow");
+        return Vec::new();
+    }
+
+    let kgrams = tokens
+        .windows(k)
+        .map(|kgram| {
+            let content = kgram
+                .iter()
+                .map(|token| context.text_of(token))
+                .collect::<Vec<_>>()
+                .join(&b' ');
+            let location = SnippetLocation::from(kgram[0].start_byte()..kgram[k - 1].end_byte());
+            let fingerprint = opts.fingerprint_algorithm().fingerprint(&content);
+            (location, content, fingerprint)
+        })
+        .collect::<Vec<_>>();
+
+    let hashes = kgrams
+        .iter()
+        .map(|(_, _, fingerprint)| hash(fingerprint.as_bytes()))
+        .collect::<Vec<_>>();
+
+    select(&hashes, winnowing.window().get())
+        .into_iter()
+        .map(|index| {
+            let (location, content, _) = &kgrams[index];
+            debug!(%location, kgram = %content.display_escaped());
+
+            let meta = SnippetMetadata::new(meta.kind(), meta.method(), *location)
+                .with_name(meta.name())
+                .with_scope(meta.scope().iter().cloned());
+            Snippet::from_options(meta, content, opts).with_lines(lines.span(*location))
+        })
+        .collect()
+}
+
+/// Select the indexes of the minimum hash in each window of hashes, in order.
+///
+/// Ties are broken by selecting the rightmost minimum hash, and each index is only selected once,
+/// as described in the paper; see [`Winnowing`].
+/// If there are fewer hashes than the size of a window, the minimum of all of them is selected.
+fn select(hashes: &[u64], window: usize) -> Vec<usize> {
+    let window = window.min(hashes.len());
+    if window == 0 {
+        return Vec::new();
+    }
+
+    let mut selected = Vec::new();
+    for start in 0..=hashes.len() - window {
+        // `min_by_key` reports the first minimum it finds, so iterate in reverse for the rightmost.
+        let minimum = hashes[start..start + window]
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|(_, hash)| **hash)
+            .map(|(offset, _)| start + offset);
+        if let Some(index) = minimum {
+            if selected.last() != Some(&index) {
+                selected.push(index);
+            }
+        }
+    }
+    selected
+}
+
+/// Reduce a fingerprint to the value compared by winnowing.
+fn hash(fingerprint: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let len = fingerprint.len().min(bytes.len());
+    bytes[..len].copy_from_slice(&fingerprint[..len]);
+    u64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_rightmost_minimum_once() {
+        // Example from the paper: "77 74 42 17 98 50 17 98 8 88 67 39 77 74 42 17 98"
+        // with a window of 4 selects "17 17 8 39 17".
+        let hashes = [
+            77, 74, 42, 17, 98, 50, 17, 98, 8, 88, 67, 39, 77, 74, 42, 17, 98,
+        ];
+        let selected = select(&hashes, 4);
+        let values = selected.iter().map(|&i| hashes[i]).collect::<Vec<_>>();
+        assert_eq!(values, vec![17, 17, 8, 39, 17]);
+        assert_eq!(selected, vec![3, 6, 8, 11, 15]);
+    }
+
+    #[test]
+    fn selects_minimum_of_short_input() {
+        assert_eq!(select(&[5, 3, 9], 4), vec![1]);
+        assert_eq!(select(&[], 4), Vec::<usize>::new());
+    }
+}
@@ -14,22 +14,34 @@
 //!
 //! Name | Description | Kind
 //! ---|---|---
+//! `blake3` | Enables fingerprinting snippets with BLAKE3; see [`text::FingerprintAlgorithm`] | API
+//! `encoding` | Enables detecting the encoding of source code and transcoding it to UTF-8 before extraction | API
 //! `lang-all` | Enables all features that are of the kind "Language" | Language
 //! `lang-c99-tc3` | Enables support for C99 TC3 | Language
 //! `lang-cpp` | Enables support for C++ 98. | Language
+//! `lang-java` | Enables support for Java | Language
+//! `lang-javascript` | Enables support for JavaScript | Language
+//! `lang-php` | Enables support for PHP | Language
+//! `lang-swift` | Enables support for Swift | Language
+//! `lang-typescript` | Enables support for TypeScript and TSX; implies `lang-javascript` | Language
 //! `sha2-asm` | Enables hardware acceleration for SHA2 | Performance
+//! `walk` | Enables walking directories and extracting snippets from each file in parallel | API
 
 #![deny(clippy::invalid_regex)]
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     marker::PhantomData,
+    num::NonZeroUsize,
     ops::{Range, RangeInclusive},
     str::Utf8Error,
+    sync::Mutex,
+    time::Duration,
 };
 
 use derivative::Derivative;
-use derive_more::{Constructor, Deref, Index};
+use derive_more::{Deref, Index};
 pub use fallible_iterator::FallibleIterator;
 use flagset::{flags, FlagSet};
 use getset::{CopyGetters, Getters};
@@ -40,11 +52,22 @@ use tap::Conv;
 use thiserror::Error;
 use typed_builder::TypedBuilder;
 
+use crate::text::FingerprintAlgorithm;
+
 pub mod debugging;
 pub mod language;
+pub mod matching;
+pub mod preprocess;
+pub mod query;
 pub mod text;
 mod tree_sitter_consts;
 
+#[cfg(feature = "encoding")]
+pub mod encoding;
+
+#[cfg(feature = "walk")]
+pub mod walk;
+
 /// Convenience import for all types that
 /// an implementation of [`Extractor`] would likely need.
 ///
@@ -55,12 +78,15 @@ mod tree_sitter_consts;
 /// [`Error`]: crate::Error
 pub mod impl_prelude {
     pub use super::{
-        Error as ExtractorError, Extractor as SnippetExtractor, Kind as SnippetKind,
-        Kinds as SnippetKinds, Language as SnippetLanguage, LanguageError,
-        Location as SnippetLocation, Metadata as SnippetMetadata, Method as SnippetMethod,
-        Options as SnippetOptions, Snippet, Strategy as LanguageStrategy, Target as SnippetTarget,
+        Error as ExtractorError, Extractor as SnippetExtractor, FallibleIterator,
+        Kind as SnippetKind, Kinds as SnippetKinds, Language as SnippetLanguage, LanguageError,
+        LazyExtractor as LazySnippetExtractor, LineIndex, Location as SnippetLocation,
+        Metadata as SnippetMetadata, Method as SnippetMethod, Options as SnippetOptions, Parsed,
+        Scope as SnippetScope, ScopeKind as SnippetScopeKind, Skipped as SkippedExtraction,
+        Snippet, Snippets, Strategy as LanguageStrategy, Target as SnippetTarget,
         Transform as SnippetTransform, Transforms as SnippetTransforms,
     };
+    pub use crate::query::{Query, QueryExtractor as QuerySnippetExtractor};
 }
 
 /// Errors reported by [`Extractor`].
@@ -72,6 +98,28 @@ pub enum Error {
 
     #[error("read input as utf8")]
     DecodeUTF8(#[from] Utf8Error),
+
+    #[error("compile query")]
+    Query(#[from] QueryError),
+
+    /// Extraction was skipped by one of the guards configured in [`Options`].
+    #[error("skipped extraction: {0}")]
+    Skipped(#[from] Skipped),
+
+    /// A generic IO error occurred while reading a file.
+    #[cfg(feature = "walk")]
+    #[error("i/o error: {0}")]
+    IO(#[from] std::io::Error),
+
+    /// An error occurred while walking a directory.
+    #[cfg(feature = "walk")]
+    #[error("walk")]
+    Walk(#[from] walkdir::Error),
+
+    /// An error occurred while extracting snippets from a file discovered while walking a directory.
+    #[cfg(feature = "walk")]
+    #[error("extract snippets from {0:?}")]
+    WalkEntry(std::path::PathBuf, #[source] Box<Error>),
 }
 
 impl From<tree_sitter::LanguageError> for Error {
@@ -80,6 +128,32 @@ impl From<tree_sitter::LanguageError> for Error {
     }
 }
 
+/// The reason extraction of a unit of source code was skipped;
+/// see [`Options::with_max_file_bytes`] and [`Options::with_timeout`].
+///
+/// Skipping is reported as [`Error::Skipped`] so that a single pathological input
+/// (such as a large minified file) doesn't stall extraction of everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum Skipped {
+    /// The content is larger than the configured maximum size.
+    #[error("content is {bytes} bytes, larger than the maximum of {max_bytes} bytes")]
+    TooLarge {
+        /// The size of the content, in bytes.
+        bytes: usize,
+
+        /// The configured maximum size, in bytes.
+        max_bytes: usize,
+    },
+
+    /// Parsing the content took longer than the configured timeout.
+    #[error("parsing did not complete within {timeout:?}")]
+    TimedOut {
+        /// The configured timeout.
+        timeout: Duration,
+    },
+}
+
 /// An error that occurs when trying to assign an incompatible language to a parser.
 // Note: Implementing it this way allows us to keep `tree_sitter` out of the public API.
 //       More details: https://docs.rs/thiserror/latest/thiserror/
@@ -87,6 +161,12 @@ impl From<tree_sitter::LanguageError> for Error {
 #[error(transparent)]
 pub struct LanguageError(#[from] tree_sitter::LanguageError);
 
+/// An error that occurs when a tree-sitter query fails to compile; see [`query`].
+// Note: Like `LanguageError`, this keeps `tree_sitter` out of the public API.
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct QueryError(#[from] tree_sitter::QueryError);
+
 /// An implementation of [`Extractor`] enables snippets to be extracted
 /// from a given unit of source code (typically a file).
 pub trait Extractor {
@@ -107,6 +187,187 @@ pub trait Extractor {
     ) -> Result<Vec<Snippet<Self::Language>>, Error>;
 }
 
+/// An object-safe form of [`Extractor`], so that extractors for different languages
+/// can be chosen at runtime, for example from configuration.
+///
+/// Every [`Extractor`] implements this trait, reporting its language at runtime
+/// and erasing the language from the snippets it extracts into [`DynSnippet`]s.
+///
+/// ```
+/// # use snippets::*;
+/// # #[cfg(all(feature = "lang-c99-tc3", feature = "lang-java"))]
+/// # fn main() -> Result<(), Error> {
+/// let extractors: Vec<Box<dyn DynExtractor>> = vec![
+///     Box::new(language::c99_tc3::Extractor),
+///     Box::new(language::java::Extractor),
+/// ];
+///
+/// let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+/// let c = extractors
+///     .iter()
+///     .find(|extractor| extractor.language_name() == "c99_tc3")
+///     .expect("c extractor is configured");
+///
+/// let snippets = c.extract_dyn(&opts, b"int main() { return 0; }")?;
+/// assert_eq!(snippets.len(), 1);
+/// assert_eq!(snippets[0].language(), "c99_tc3/static");
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "lang-c99-tc3", feature = "lang-java")))]
+/// # fn main() {}
+/// ```
+pub trait DynExtractor: Send + Sync {
+    /// The name of the source language supported by the extractor; see [`Language::NAME`].
+    fn language_name(&self) -> &'static str;
+
+    /// The strategy used for parsing the language; see [`Language::STRATEGY`].
+    fn language_strategy(&self) -> Strategy;
+
+    /// The display name of the language; see [`Language::display`].
+    fn language_display(&self) -> &'static str;
+
+    /// Reads the provided unit of source code for snippets, according to the provided options;
+    /// see [`Extractor::extract`].
+    fn extract_dyn(&self, opts: &Options, content: &[u8]) -> Result<Vec<DynSnippet>, Error>;
+}
+
+impl<E: Extractor + Send + Sync> DynExtractor for E {
+    fn language_name(&self) -> &'static str {
+        E::Language::NAME
+    }
+
+    fn language_strategy(&self) -> Strategy {
+        E::Language::STRATEGY
+    }
+
+    fn language_display(&self) -> &'static str {
+        E::Language::display()
+    }
+
+    fn extract_dyn(&self, opts: &Options, content: &[u8]) -> Result<Vec<DynSnippet>, Error> {
+        E::extract(opts, content)
+            .map(|snippets| snippets.into_iter().map(Snippet::into_dyn).collect())
+    }
+}
+
+/// An [`Extractor`] that can also extract snippets lazily, as the parsed source code is traversed,
+/// instead of collecting every snippet up front.
+///
+/// This is useful for very large units of source code, such as generated files,
+/// since each snippet can be processed (and dropped) before the next one is extracted.
+///
+/// Implementations of this trait are provided for the languages in [`language`];
+/// since [`Parsed`] wraps the parse tree, it can't be implemented outside this library.
+///
+/// ```
+/// # use snippets::*;
+/// # #[cfg(feature = "lang-c99-tc3")]
+/// # fn main() -> Result<(), Error> {
+/// use snippets::language::c99_tc3;
+///
+/// let content = b"int one() { return 1; }\nint two() { return 2; }";
+/// let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+///
+/// let parsed = c99_tc3::Extractor::parse(content)?;
+/// let mut snippets = c99_tc3::Extractor::extract_lazy(&parsed, &opts);
+/// while let Some(snippet) = snippets.next()? {
+///     println!("{snippet}");
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "lang-c99-tc3"))]
+/// # fn main() {}
+/// ```
+pub trait LazyExtractor: Extractor {
+    /// Parse the provided unit of source code,
+    /// so that snippets can be extracted from it with [`LazyExtractor::extract_lazy`].
+    fn parse(content: &[u8]) -> Result<Parsed<'_, Self::Language>, Error>;
+
+    /// Parse the provided unit of source code like [`LazyExtractor::parse`],
+    /// applying the guards configured in the options:
+    /// content larger than [`Options::max_file_bytes`] is skipped without being parsed,
+    /// and parsing is abandoned once it exceeds [`Options::timeout`].
+    ///
+    /// The default implementation only applies the size guard;
+    /// the extractors in this crate override it to also apply the timeout.
+    fn parse_guarded<'a>(
+        opts: &Options,
+        content: &'a [u8],
+    ) -> Result<Parsed<'a, Self::Language>, Error> {
+        opts.check_size(content)?;
+        Self::parse(content)
+    }
+
+    /// Lazily extract snippets from the parsed source code, according to the provided options.
+    ///
+    /// Snippets are yielded in the same order in which [`Extractor::extract`] reports them.
+    fn extract_lazy<'p>(
+        parsed: &'p Parsed<'_, Self::Language>,
+        opts: &'p Options,
+    ) -> Snippets<'p, Self::Language>;
+}
+
+/// A unit of source code parsed by a [`LazyExtractor`].
+pub struct Parsed<'a, L> {
+    /// The parse tree; `None` if the parser produced no tree.
+    tree: Option<tree_sitter::Tree>,
+    content: &'a [u8],
+    lines: LineIndex,
+    language: PhantomData<L>,
+}
+
+impl<'a, L> Parsed<'a, L> {
+    pub(crate) fn new(tree: Option<tree_sitter::Tree>, content: &'a [u8]) -> Self {
+        Self {
+            tree,
+            content,
+            lines: LineIndex::new(content),
+            language: PhantomData,
+        }
+    }
+
+    pub(crate) fn tree(&self) -> Option<&tree_sitter::Tree> {
+        self.tree.as_ref()
+    }
+
+    pub(crate) fn lines(&self) -> &LineIndex {
+        &self.lines
+    }
+
+    /// The unit of source code that was parsed.
+    pub fn content(&self) -> &'a [u8] {
+        self.content
+    }
+}
+
+/// Snippets extracted lazily by a [`LazyExtractor`].
+///
+/// Iteration stops at the first error.
+pub struct Snippets<'p, L> {
+    inner: Box<dyn Iterator<Item = Result<Snippet<L>, Error>> + 'p>,
+}
+
+impl<'p, L: 'p> Snippets<'p, L> {
+    pub(crate) fn new(inner: impl Iterator<Item = Result<Snippet<L>, Error>> + 'p) -> Self {
+        Self {
+            inner: Box::new(inner),
+        }
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self::new(std::iter::empty())
+    }
+}
+
+impl<L> FallibleIterator for Snippets<'_, L> {
+    type Item = Snippet<L>;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
+        self.inner.next().transpose()
+    }
+}
+
 /// Options for extracting snippets.
 /// Options are constructed via the `Options::builder` method.
 ///
@@ -177,6 +438,14 @@ pub trait Extractor {
 /// let options = Options::new(Target::Function, Kinds::full(), Transforms::none());
 /// assert!(options.transforms().is_empty());
 /// ```
+///
+/// Skip trivially small snippets, such as getters and one-liners:
+/// ```
+/// # use snippets::*;
+/// let options = Options::default().with_min_bytes(64).with_min_tokens(16);
+/// assert_eq!(options.min_bytes(), 64);
+/// assert_eq!(options.min_tokens(), 16);
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct Options {
@@ -192,6 +461,38 @@ pub struct Options {
     /// Include the `raw` method.
     /// Recommended for general use; disabling is mainly intended for tests.
     include_raw: bool,
+
+    /// The minimum size, in bytes, of the source code a snippet covers.
+    /// Snippets covering less source code are not extracted.
+    min_bytes: usize,
+
+    /// The minimum number of syntax tokens (excluding comments) in the source code a snippet covers.
+    /// Snippets covering fewer tokens are not extracted.
+    min_tokens: usize,
+
+    /// The configuration for [`Method::Winnowed`] snippets, if they are extracted.
+    winnowing: Option<Winnowing>,
+
+    /// Include the block of comments immediately preceding an item in its [`Kind::Full`] snippets.
+    leading_comments: bool,
+
+    /// The algorithm used to fingerprint snippets.
+    fingerprint_algorithm: FingerprintAlgorithm,
+
+    /// Retain the content that generated the fingerprint of each snippet.
+    retain_content: bool,
+
+    /// The maximum size, in bytes, of content to extract snippets from.
+    /// Larger content is skipped.
+    max_file_bytes: Option<usize>,
+
+    /// The maximum wall-clock time to spend parsing content.
+    /// Parsing that takes longer is abandoned and the content is skipped.
+    timeout: Option<Duration>,
+
+    /// Detect the encoding of content, transcoding it to UTF-8 before extraction.
+    #[cfg(feature = "encoding")]
+    transcoding: bool,
 }
 
 impl Options {
@@ -206,6 +507,16 @@ impl Options {
             kinds: kinds.conv::<Kinds>().default_if_empty(),
             transforms: transforms.into(),
             include_raw: true,
+            min_bytes: 0,
+            min_tokens: 0,
+            winnowing: None,
+            leading_comments: false,
+            fingerprint_algorithm: FingerprintAlgorithm::default(),
+            retain_content: false,
+            max_file_bytes: None,
+            timeout: None,
+            #[cfg(feature = "encoding")]
+            transcoding: false,
         }
     }
 
@@ -217,20 +528,235 @@ impl Options {
         }
     }
 
+    /// Skip snippets covering fewer than `min_bytes` bytes of source code.
+    ///
+    /// The size is measured on the source code the snippet covers (as reported by its location),
+    /// before any [`Transform`] is applied, so every [`Method`] for a given snippet is kept or skipped together.
+    pub fn with_min_bytes(self, min_bytes: usize) -> Self {
+        Self { min_bytes, ..self }
+    }
+
+    /// Skip snippets covering fewer than `min_tokens` syntax tokens.
+    ///
+    /// Tokens are the leaves of the syntax tree within the snippet, not counting comments;
+    /// for example `int x = 1;` is five tokens.
+    /// Like [`Options::with_min_bytes`], this is measured before any [`Transform`] is applied.
+    pub fn with_min_tokens(self, min_tokens: usize) -> Self {
+        Self { min_tokens, ..self }
+    }
+
+    /// Also generate [`Method::Winnowed`] snippets, using the provided configuration.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// let options = Options::default().with_winnowing(Winnowing::default());
+    /// assert!(options.methods().any(|method| method == Method::Winnowed));
+    /// ```
+    pub fn with_winnowing(self, winnowing: Winnowing) -> Self {
+        Self {
+            winnowing: Some(winnowing),
+            ..self
+        }
+    }
+
+    /// Include the block of comments immediately preceding an item, such as a doc comment
+    /// or license header, in its [`Kind::Full`] snippets.
+    ///
+    /// Only comments that start their own line are included, and a blank line ends the block;
+    /// the [`Kind::Signature`] and [`Kind::Body`] snippets of the item are unaffected.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// # #[cfg(feature = "lang-c99-tc3")]
+    /// # fn main() -> Result<(), Error> {
+    /// # use snippets::language::c99_tc3;
+    /// let content = b"int x; // unrelated\n\n/* Adds two numbers. */\nint add(int a, int b) { return a + b; }";
+    /// let options = Options::new(Target::Function, Kind::Full, Transforms::none())
+    ///     .with_leading_comments()
+    ///     .with_content();
+    ///
+    /// let snippets = c99_tc3::Extractor::extract(&options, content)?;
+    /// assert_eq!(
+    ///     snippets[0].content().expect("content is retained").as_bytes(),
+    ///     b"/* Adds two numbers. */\nint add(int a, int b) { return a + b; }",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "lang-c99-tc3"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_leading_comments(self) -> Self {
+        Self {
+            leading_comments: true,
+            ..self
+        }
+    }
+
+    /// Fingerprint snippets with the provided algorithm, instead of the default SHA-256;
+    /// see [`FingerprintAlgorithm`] for details.
+    ///
+    /// ```
+    /// # use snippets::{*, text::FingerprintAlgorithm};
+    /// let options = Options::default();
+    /// assert_eq!(options.fingerprint_algorithm(), FingerprintAlgorithm::Sha256);
+    /// # #[cfg(feature = "blake3")]
+    /// # {
+    ///
+    /// let options = options.with_fingerprint_algorithm(FingerprintAlgorithm::Blake3);
+    /// assert_eq!(options.fingerprint_algorithm(), FingerprintAlgorithm::Blake3);
+    /// # }
+    /// ```
+    pub fn with_fingerprint_algorithm(self, fingerprint_algorithm: FingerprintAlgorithm) -> Self {
+        Self {
+            fingerprint_algorithm,
+            ..self
+        }
+    }
+
+    /// Retain the content that generated the fingerprint of each snippet
+    /// (after any [`Transform`] is applied); see [`Snippet::content`].
+    ///
+    /// Content is not retained by default, since most consumers only need the fingerprint
+    /// and copying the content of every snippet is expensive; it is mainly useful for debugging
+    /// and for matching workflows that inspect the matched code.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// # #[cfg(feature = "lang-c99-tc3")]
+    /// # fn main() -> Result<(), Error> {
+    /// # use snippets::language::c99_tc3;
+    /// let content = b"int main() { return 0; }";
+    /// let options = Options::new(Target::Function, Kind::Signature, Transforms::none());
+    ///
+    /// let snippets = c99_tc3::Extractor::extract(&options, content)?;
+    /// assert_eq!(snippets[0].content(), None);
+    ///
+    /// let snippets = c99_tc3::Extractor::extract(&options.with_content(), content)?;
+    /// let retained = snippets[0].content().expect("content is retained");
+    /// assert_eq!(retained.as_bytes(), b"int main()");
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "lang-c99-tc3"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_content(self) -> Self {
+        Self {
+            retain_content: true,
+            ..self
+        }
+    }
+
+    /// Skip content larger than `max_file_bytes` bytes, reporting [`Skipped::TooLarge`]
+    /// instead of extracting snippets from it.
+    ///
+    /// The size is measured on the content as it is parsed, after [`Options::prepare`];
+    /// when walking a directory, files are also checked before they are read.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// # #[cfg(feature = "lang-c99-tc3")]
+    /// # fn main() {
+    /// # use snippets::language::c99_tc3;
+    /// let content = b"int main() { return 0; }";
+    /// let options = Options::default().with_max_file_bytes(16);
+    ///
+    /// let err = c99_tc3::Extractor::extract(&options, content).expect_err("content is too large");
+    /// assert!(matches!(
+    ///     err,
+    ///     Error::Skipped(Skipped::TooLarge { bytes: 24, max_bytes: 16 })
+    /// ));
+    /// # }
+    /// # #[cfg(not(feature = "lang-c99-tc3"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_max_file_bytes(self, max_file_bytes: usize) -> Self {
+        Self {
+            max_file_bytes: Some(max_file_bytes),
+            ..self
+        }
+    }
+
+    /// Abandon parsing content after `timeout` has elapsed, reporting [`Skipped::TimedOut`]
+    /// instead of extracting snippets from it.
+    ///
+    /// Parsing is where pathological inputs (such as minified code) spend their time,
+    /// so the timeout applies to parsing; the time spent extracting snippets from
+    /// a completed parse is proportional to the number of snippets extracted.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Detect the encoding of content, transcoding it to UTF-8 before extraction;
+    /// see [`encoding`] for details.
+    #[cfg(feature = "encoding")]
+    pub fn with_transcoding(self) -> Self {
+        Self {
+            transcoding: true,
+            ..self
+        }
+    }
+
+    /// Prepare content for extraction according to these options.
+    ///
+    /// Content is returned as-is unless [`Options::with_transcoding`] is set,
+    /// in which case it is transcoded to UTF-8.
+    /// Extractors call this before parsing content in [`Extractor::extract`];
+    /// callers of [`LazyExtractor::parse`] should call it before parsing.
+    pub fn prepare<'a>(&self, content: &'a [u8]) -> Cow<'a, [u8]> {
+        #[cfg(feature = "encoding")]
+        if self.transcoding {
+            let (detected, transcoded) = encoding::transcode(content);
+            tracing::debug!(encoding = %detected, "transcoded content");
+            return transcoded;
+        }
+
+        Cow::Borrowed(content)
+    }
+
+    /// Report whether the content is within the configured maximum size;
+    /// see [`Options::with_max_file_bytes`].
+    pub fn check_size(&self, content: &[u8]) -> Result<(), Skipped> {
+        match self.max_file_bytes {
+            Some(max_bytes) if content.len() > max_bytes => Err(Skipped::TooLarge {
+                bytes: content.len(),
+                max_bytes,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Report whether a snippet covering `bytes` bytes and `tokens` tokens of source code
+    /// meets the configured minimum size.
+    pub fn meets_minimum(&self, bytes: usize, tokens: usize) -> bool {
+        bytes >= self.min_bytes && tokens >= self.min_tokens
+    }
+
+    /// Report the cartesian product of the configured [`Target`]s and [`Kind`]s of snippets to extract.
+    ///
+    /// Extractors find the context of each combination once,
+    /// then apply each configured [`Method`] (see [`Options::methods`]) to that context.
+    pub fn targets_and_kinds(&self) -> impl Iterator<Item = (Target, Kind)> {
+        itertools::iproduct!(self.targets.iter(), self.kinds.iter())
+    }
+
     /// Report the cartesian product of the configured [`Kind`]s of snippets to extract
     /// with configured [`Method`]s to apply.
     pub fn cartesian_product(&self) -> impl Iterator<Item = (Target, Kind, Method)> {
+        itertools::iproduct!(self.targets.iter(), self.kinds.iter(), self.methods())
+    }
+
+    /// Report the configured [`Method`]s to apply.
+    pub fn methods(&self) -> impl Iterator<Item = Method> + Clone {
         let include_raw = self.include_raw;
-        itertools::iproduct!(
-            self.targets.iter(),
-            self.kinds.iter(),
-            Method::iter(self.transforms).filter(move |method| {
-                match method {
-                    Method::Raw => include_raw,
-                    _ => true,
-                }
+        Method::iter(self.transforms)
+            .filter(move |method| match method {
+                Method::Raw => include_raw,
+                _ => true,
             })
-        )
+            .chain(self.winnowing.map(|_| Method::Winnowed))
     }
 }
 
@@ -241,6 +767,16 @@ impl Default for Options {
             kinds: Kinds::full(),
             transforms: Transforms::full(),
             include_raw: true,
+            min_bytes: 0,
+            min_tokens: 0,
+            winnowing: None,
+            leading_comments: false,
+            fingerprint_algorithm: FingerprintAlgorithm::default(),
+            retain_content: false,
+            max_file_bytes: None,
+            timeout: None,
+            #[cfg(feature = "encoding")]
+            transcoding: false,
         }
     }
 }
@@ -291,8 +827,20 @@ pub trait Language {
 
     /// Override the display of the language name if desired.
     fn display() -> &'static str {
-        static DISPLAY: OnceCell<String> = OnceCell::new();
-        DISPLAY.get_or_init(|| format!("{}/{}", Self::NAME, Self::STRATEGY))
+        // A static in a generic function is shared by every implementation,
+        // so the display names are cached by name and strategy.
+        // Each distinct language leaks its display name once.
+        static DISPLAY: OnceCell<Mutex<HashMap<(&'static str, Strategy), &'static str>>> =
+            OnceCell::new();
+        let mut cache = DISPLAY
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache
+            .entry((Self::NAME, Self::STRATEGY))
+            .or_insert_with(|| {
+                Box::leak(format!("{}/{}", Self::NAME, Self::STRATEGY).into_boxed_str())
+            })
     }
 }
 
@@ -400,6 +948,11 @@ pub enum Strategy {
     /// The extractor statically analyzes the code.
     /// No compile time metaprogramming is evaluated.
     Static,
+
+    /// The extractor statically analyzes the code after a lightweight preprocessing pass,
+    /// which evaluates conditional compilation and expands simple macros;
+    /// see [`preprocess`] for details.
+    Preprocessed,
 }
 
 /// An extracted snippet from the given unit of source code.
@@ -407,7 +960,7 @@ pub enum Strategy {
 #[derivative(Ord, PartialEq, Eq)]
 pub struct Snippet<L> {
     /// Metadata for the extracted snippet.
-    #[getset(get_copy = "pub")]
+    #[getset(get = "pub")]
     metadata: Metadata,
 
     /// The bytes of the snippet fingerprint.
@@ -417,10 +970,18 @@ pub struct Snippet<L> {
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     fingerprint: text::Buffer,
 
-    /// Reports the content that actually generated the fingerprint.
-    #[getset(get = "pub")]
+    /// The content that actually generated the fingerprint, if it was retained.
     #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
-    content: text::Buffer,
+    content: Option<text::Buffer>,
+
+    /// The lines and columns spanned by the snippet, if known.
+    ///
+    /// This is derived from the [`Location`] in the metadata,
+    /// so it is not considered for ordering or equality.
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
+    lines: Option<LineSpan>,
 
     /// Used to disambiguate snippets by source language.
     ///
@@ -437,14 +998,88 @@ impl<L> PartialOrd for Snippet<L> {
 }
 
 impl<L> Snippet<L> {
-    /// Create a new snippet from the provided data.
+    /// Create a new snippet from the provided data, as configured by the default [`Options`]:
+    /// the content is fingerprinted with the default [`FingerprintAlgorithm`], and is not retained.
+    ///
+    /// Use [`Snippet::from_options`] with [`Options::with_content`] to retain the content.
     pub fn from(meta: Metadata, content: impl AsRef<[u8]>) -> Self {
+        Self::from_options(meta, content, &Options::default())
+    }
+
+    /// Create a new snippet from the provided data, as configured by the options:
+    /// the content is fingerprinted with [`Options::fingerprint_algorithm`],
+    /// and is only retained if [`Options::with_content`] is set.
+    pub fn from_options(meta: Metadata, content: impl AsRef<[u8]>, opts: &Options) -> Self {
+        let content = content.as_ref();
         Self::builder()
-            .content(text::Buffer::new(content.as_ref()))
-            .fingerprint(text::fingerprint(content))
+            .content(opts.retain_content.then(|| text::Buffer::new(content)))
+            .fingerprint(opts.fingerprint_algorithm.fingerprint(content))
             .metadata(meta)
             .build()
     }
+
+    /// The content that actually generated the fingerprint, if it was retained;
+    /// see [`Options::with_content`].
+    pub fn content(&self) -> Option<&text::Buffer> {
+        self.content.as_ref()
+    }
+
+    /// Set the lines and columns spanned by the snippet.
+    ///
+    /// Extractors set this using a [`LineIndex`] over the unit of source code
+    /// from which the snippet was extracted:
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// # struct L;
+    /// let content = b"#include <stdio.h>\n\nint main() {}";
+    /// let location = Location::from(20..30);
+    /// let meta = Metadata::new(Kind::Signature, Method::Raw, location);
+    ///
+    /// let lines = LineIndex::new(content);
+    /// let snippet = Snippet::<L>::from(meta, location.extract_from(content))
+    ///     .with_lines(lines.span(location));
+    ///
+    /// let span = snippet.lines().expect("lines were set");
+    /// assert_eq!(span.to_string(), "3:1-3:10");
+    /// ```
+    pub fn with_lines(self, lines: LineSpan) -> Self {
+        Self {
+            lines: Some(lines),
+            ..self
+        }
+    }
+
+    /// Report the snippet as extracted from the language `M`,
+    /// for extractors that wrap another extractor (such as [`preprocess::Preprocessor`]).
+    pub(crate) fn with_language<M>(self) -> Snippet<M> {
+        Snippet {
+            metadata: self.metadata,
+            fingerprint: self.fingerprint,
+            content: self.content,
+            lines: self.lines,
+            language: PhantomData,
+        }
+    }
+}
+
+impl<L: Language> Snippet<L> {
+    /// Erase the language of the snippet, recording its display name instead.
+    pub fn into_dyn(self) -> DynSnippet {
+        DynSnippet {
+            language: L::display(),
+            metadata: self.metadata,
+            fingerprint: self.fingerprint,
+            content: self.content,
+            lines: self.lines,
+        }
+    }
+}
+
+impl<L: Language> From<Snippet<L>> for DynSnippet {
+    fn from(snippet: Snippet<L>) -> Self {
+        snippet.into_dyn()
+    }
 }
 
 impl<L: Language> std::fmt::Display for Snippet<L> {
@@ -460,22 +1095,237 @@ impl<L: Language> std::fmt::Debug for Snippet<L> {
             .field("metadata", &self.metadata)
             .field("fingerprint", &self.fingerprint)
             .field("content", &self.content)
+            .field("lines", &self.lines)
+            .finish()
+    }
+}
+
+/// An extracted snippet whose language is only known at runtime,
+/// as extracted by a [`DynExtractor`] or converted from a [`Snippet`].
+///
+/// Like [`Snippet`], only the language, metadata, and fingerprint are considered for ordering and equality.
+#[derive(Clone, Getters, CopyGetters, Derivative)]
+#[derivative(Ord, PartialEq, Eq)]
+pub struct DynSnippet {
+    /// The display name of the language from which the snippet was extracted; see [`Language::display`].
+    #[getset(get_copy = "pub")]
+    language: &'static str,
+
+    /// Metadata for the extracted snippet.
+    #[getset(get = "pub")]
+    metadata: Metadata,
+
+    /// The bytes of the snippet fingerprint.
+    #[getset(get = "pub")]
+    #[derivative(PartialOrd = "ignore", Ord = "ignore")]
+    fingerprint: text::Buffer,
+
+    /// The content that actually generated the fingerprint, if it was retained.
+    #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
+    content: Option<text::Buffer>,
+
+    /// The lines and columns spanned by the snippet, if known.
+    #[getset(get_copy = "pub")]
+    #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
+    lines: Option<LineSpan>,
+}
+
+impl DynSnippet {
+    /// The content that actually generated the fingerprint, if it was retained;
+    /// see [`Options::with_content`].
+    pub fn content(&self) -> Option<&text::Buffer> {
+        self.content.as_ref()
+    }
+}
+
+impl PartialOrd for DynSnippet {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for DynSnippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.language, self.metadata)
+    }
+}
+
+impl std::fmt::Debug for DynSnippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynSnippet")
+            .field("language", &self.language)
+            .field("metadata", &self.metadata)
+            .field("fingerprint", &self.fingerprint)
+            .field("content", &self.content)
+            .field("lines", &self.lines)
             .finish()
     }
 }
 
 /// The metadata for an extracted snippet.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, CopyGetters, Constructor)]
-#[getset(get_copy = "pub")]
+///
+/// Only the kind, method, and location are considered for ordering and equality;
+/// the name is descriptive, since the location already identifies the snippet.
+#[derive(Debug, Clone, Getters, CopyGetters, Derivative)]
+#[derivative(Ord, PartialEq, Eq)]
 pub struct Metadata {
     /// The kind of item this snippet represents.
+    #[getset(get_copy = "pub")]
     kind: Kind,
 
     /// The method used to extract this snippet.
+    #[getset(get_copy = "pub")]
     method: Method,
 
     /// The location at which the snippet was found.
+    #[getset(get_copy = "pub")]
     location: Location,
+
+    /// The name declared by the item this snippet represents, if it has one.
+    ///
+    /// This is the name as written in the declaration,
+    /// so it is qualified only if the declaration itself is qualified
+    /// (for example the C++ method definition `void Greeter::greet() {}` is named `Greeter::greet`).
+    #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
+    name: Option<String>,
+
+    /// The scopes enclosing the item this snippet represents, from outermost to innermost.
+    #[derivative(PartialOrd = "ignore", Ord = "ignore", PartialEq = "ignore")]
+    scope: Vec<Scope>,
+}
+
+impl Metadata {
+    /// Create new metadata for a snippet, without a name or enclosing scopes.
+    pub fn new(kind: Kind, method: Method, location: Location) -> Self {
+        Self {
+            kind,
+            method,
+            location,
+            name: None,
+            scope: Vec::new(),
+        }
+    }
+
+    /// Set the name declared by the item this snippet represents.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// let meta = Metadata::new(Kind::Full, Method::Raw, Location::from(0..10));
+    /// assert_eq!(meta.name(), None);
+    ///
+    /// let meta = meta.with_name(Some("curl_easy_init"));
+    /// assert_eq!(meta.name(), Some("curl_easy_init"));
+    /// ```
+    pub fn with_name(self, name: Option<impl Into<String>>) -> Self {
+        Self {
+            name: name.map(Into::into),
+            ..self
+        }
+    }
+
+    /// The name declared by the item this snippet represents, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Set the scopes enclosing the item this snippet represents, from outermost to innermost.
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// let meta = Metadata::new(Kind::Full, Method::Raw, Location::from(0..10));
+    /// assert_eq!(meta.depth(), 0);
+    ///
+    /// let meta = meta.with_scope([
+    ///     Scope::new(ScopeKind::Namespace).with_name(Some("net")),
+    ///     Scope::new(ScopeKind::Class).with_name(Some("Socket")),
+    /// ]);
+    /// assert_eq!(meta.depth(), 2);
+    /// assert_eq!(meta.scope()[1].name(), Some("Socket"));
+    /// ```
+    pub fn with_scope(self, scope: impl IntoIterator<Item = Scope>) -> Self {
+        Self {
+            scope: scope.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// The scopes enclosing the item this snippet represents, from outermost to innermost.
+    ///
+    /// Top-level items have no enclosing scopes.
+    /// Extractors report scopes for the namespaces, classes, and functions (including anonymous functions,
+    /// such as lambdas) that contain the item, which distinguishes nested items from top-level definitions.
+    pub fn scope(&self) -> &[Scope] {
+        &self.scope
+    }
+
+    /// The number of scopes enclosing the item this snippet represents; `0` for top-level items.
+    pub fn depth(&self) -> usize {
+        self.scope.len()
+    }
+}
+
+/// A scope enclosing the item a snippet represents; see [`Metadata::scope`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, CopyGetters)]
+pub struct Scope {
+    /// The kind of scope.
+    #[getset(get_copy = "pub")]
+    kind: ScopeKind,
+
+    /// The name declared by the scope, if it has one.
+    name: Option<String>,
+}
+
+impl Scope {
+    /// Create a new scope, without a name.
+    pub fn new(kind: ScopeKind) -> Self {
+        Self { kind, name: None }
+    }
+
+    /// Set the name declared by the scope.
+    pub fn with_name(self, name: Option<impl Into<String>>) -> Self {
+        Self {
+            name: name.map(Into::into),
+            ..self
+        }
+    }
+
+    /// The name declared by the scope, if it has one.
+    ///
+    /// Like [`Metadata::name`], this is the name as written in the declaration.
+    /// Anonymous scopes, such as lambdas or anonymous namespaces, have no name.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} {name}", self.kind),
+            None => write!(f, "anonymous {}", self.kind),
+        }
+    }
+}
+
+/// The kind of a [`Scope`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+#[strum(serialize_all = "snake_case")]
+#[non_exhaustive]
+pub enum ScopeKind {
+    /// A namespace or module, such as a C++ `namespace`.
+    Namespace,
+
+    /// A class or similar type declaration with members, such as a struct, interface, or enum.
+    Class,
+
+    /// A function, method, or anonymous function such as a lambda.
+    Function,
+}
+
+impl PartialOrd for Metadata {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl std::fmt::Display for Metadata {
@@ -655,6 +1505,102 @@ impl ByteLen {
     }
 }
 
+/// A position in a unit of source code, as displayed to users.
+///
+/// Both the line and column are 1-based, meaning that the first byte of the file
+/// is at line `1`, column `1`.
+/// Columns count bytes from the start of the line, not characters,
+/// so that they line up with the byte offsets in a [`Location`] regardless of the encoding of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct Position {
+    /// The 1-based line number.
+    line: usize,
+
+    /// The 1-based byte column within the line.
+    column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The lines and columns spanned by a [`Location`].
+///
+/// Like [`Location::end_byte`], the end is inclusive: it is the position of the last byte in the snippet.
+/// A snippet with no content starts and ends at the same position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct LineSpan {
+    /// The position of the first byte of the snippet.
+    start: Position,
+
+    /// The position of the last byte of the snippet.
+    end: Position,
+}
+
+impl std::fmt::Display for LineSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Indexes the start of each line in a unit of source code,
+/// so that [`Location`]s in it can be converted to [`LineSpan`]s without rescanning the content.
+///
+/// Lines are delimited by `\n`, so content with `\r\n` line endings
+/// reports the `\r` as the last column of each line.
+///
+/// ```
+/// # use snippets::*;
+/// let lines = LineIndex::new("int a;\nint main() {\n}\n");
+///
+/// let span = lines.span(Location::from(7..21));
+/// assert_eq!((span.start().line(), span.start().column()), (2, 1));
+/// assert_eq!((span.end().line(), span.end().column()), (3, 1));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// The byte offset at which each line starts, in order.
+    /// Always contains at least the first line, which starts at `0`.
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Index the lines in the content.
+    pub fn new(content: impl AsRef<[u8]>) -> Self {
+        let newlines = content
+            .as_ref()
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .map(|(offset, _)| offset + 1);
+        Self {
+            starts: std::iter::once(0).chain(newlines).collect(),
+        }
+    }
+
+    /// The position of the byte at the offset.
+    pub fn position(&self, offset: usize) -> Position {
+        // The line is the last one that starts at or before the offset.
+        let line = self.starts.partition_point(|start| *start <= offset);
+        Position {
+            line,
+            column: offset - self.starts[line - 1] + 1,
+        }
+    }
+
+    /// The lines and columns spanned by the location.
+    pub fn span(&self, location: Location) -> LineSpan {
+        LineSpan {
+            start: self.position(location.start_byte()),
+            end: self.position(location.end_byte().max(location.start_byte())),
+        }
+    }
+}
+
 flags! {
     /// The kind of item this snippet represents.
     ///
@@ -826,16 +1772,27 @@ impl std::fmt::Display for Kinds {
 ///
 /// Items with higher "specificity order" are sorted _higher_; meaning that a
 /// [`Method::Raw`] variant would be sorted later in a vector
-/// than a [`Method::Normalized`] variant:
+/// than a [`Method::Normalized`] variant,
+/// which in turn would be sorted later than a [`Method::Winnowed`] variant:
 ///
 /// ```
 /// # use snippets::*;
 /// # let arbitrary = Transform::Space;
 /// assert!(Method::Raw > Method::Normalized(arbitrary));
+/// assert!(Method::Normalized(arbitrary) > Method::Winnowed);
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
 pub enum Method {
+    /// Generated from a sequence of tokens selected by winnowing the tokens in the text;
+    /// see [`Winnowing`] for details.
+    ///
+    /// Unlike other methods, this generates many snippets for each item:
+    /// one for each k-gram of tokens selected by winnowing.
+    /// This enables matching a part of an item that was copied verbatim
+    /// (for example a few lines pasted into a larger function), at the cost of specificity.
+    Winnowed,
+
     /// Generated from the text with the specified normalizations applied.
     Normalized(Transform),
 
@@ -870,6 +1827,65 @@ impl Method {
     }
 }
 
+/// The configuration for [`Method::Winnowed`] snippets.
+///
+/// Winnowing, as described in [Winnowing: Local Algorithms for Document Fingerprinting],
+/// hashes each k-gram (each run of `k` consecutive tokens) in the text,
+/// then selects the minimum hash from each window of `window` consecutive k-gram hashes.
+///
+/// Tokens are the leaves of the syntax tree, not counting comments,
+/// so winnowed snippets are insensitive to formatting and comments.
+/// Each selected k-gram is reported as a snippet whose content is its tokens separated by a space,
+/// and whose location spans its tokens in the original text.
+///
+/// This guarantees that any run of at least `k + window - 1` tokens shared between two items
+/// results in at least one shared snippet, while no run of fewer than `k` tokens does.
+/// Items with fewer than `k` tokens have no winnowed snippets.
+///
+/// ```
+/// # use snippets::*;
+/// # use std::num::NonZeroUsize;
+/// let winnowing = Winnowing::default();
+/// assert_eq!(winnowing.k().get(), 8);
+/// assert_eq!(winnowing.window().get(), 8);
+///
+/// let k = NonZeroUsize::new(5).expect("nonzero");
+/// let window = NonZeroUsize::new(4).expect("nonzero");
+/// let winnowing = Winnowing::new(k, window);
+/// assert_eq!(winnowing.guarantee(), 8);
+/// ```
+///
+/// [Winnowing: Local Algorithms for Document Fingerprinting]: https://theory.stanford.edu/~aiken/publications/papers/sigmod03.pdf
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct Winnowing {
+    /// The number of tokens in each k-gram.
+    k: NonZeroUsize,
+
+    /// The number of consecutive k-grams from which one is selected.
+    window: NonZeroUsize,
+}
+
+impl Winnowing {
+    /// Create a new configuration for winnowing.
+    pub fn new(k: NonZeroUsize, window: NonZeroUsize) -> Self {
+        Self { k, window }
+    }
+
+    /// The minimum number of consecutive tokens that two items must share
+    /// to be guaranteed to share a winnowed snippet.
+    pub fn guarantee(&self) -> usize {
+        self.k.get() + self.window.get() - 1
+    }
+}
+
+impl Default for Winnowing {
+    fn default() -> Self {
+        let eight = NonZeroUsize::new(8).expect("8 is nonzero");
+        Self::new(eight, eight)
+    }
+}
+
 impl<I: IntoIterator<Item = Transform>> From<I> for Method {
     fn from(value: I) -> Self {
         let mut value = value.into_iter();
@@ -884,6 +1900,7 @@ impl<I: IntoIterator<Item = Transform>> From<I> for Method {
 impl std::fmt::Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Method::Winnowed => write!(f, "winnowed"),
             Method::Normalized(transforms) => write!(f, "normalized({transforms})"),
             Method::Raw => write!(f, "raw"),
         }
@@ -897,7 +1914,8 @@ flags! {
     ///
     /// Specificity is in the order specified by the implementation of [`Ord`] for this type,
     /// meaning that a [`Transform::Space`] variant is considered a more exact match
-    /// than a [`Transform::Comment`] variant.
+    /// than a [`Transform::Comment`] variant, which is a more exact match than a [`Transform::Code`] variant,
+    /// which is a more exact match than a [`Transform::Literal`] variant.
     ///
     /// Items with higher "specificity order" are sorted _higher_; meaning that a
     /// [`Transform::Space`] variant would be sorted later in a vector
@@ -906,11 +1924,38 @@ flags! {
     /// ```
     /// # use snippets::*;
     /// assert!(Transform::Space > Transform::Comment);
+    /// assert!(Transform::Comment > Transform::Code);
+    /// assert!(Transform::Code > Transform::Literal);
     /// ```
     #[derive(Hash, PartialOrd, Ord, EnumIter, Display)]
     #[strum(serialize_all = "snake_case")]
     #[non_exhaustive]
     pub enum Transform: u8 {
+        /// Transform the text as in [`Transform::Code`], and also replace any literal values
+        /// with a placeholder: strings become `""`, characters become `''`, and numbers become `0`.
+        ///
+        /// This allows copies of code whose constants have been changed to still match,
+        /// at the cost of also matching unrelated code that differs only in its constants.
+        /// Exactly what constitutes a literal is up to the implementation
+        /// of the [`Extractor`] for the language being analyzed.
+        ///
+        /// # Example
+        ///
+        /// The original input:
+        /// ```ignore
+        /// fn say_happy_birthday(age: usize) -> String {
+        ///   // TODO: make 'years' smart plural.
+        ///   println!("Happy birthday! You're {age} years old!");
+        ///   age + 1
+        /// }
+        /// ```
+        ///
+        /// Is normalized to this:
+        /// ```ignore
+        /// fn say_happy_birthday(age: usize) -> String { println!(""); age + 0 }
+        /// ```
+        Literal,
+
         /// Transform the text to have any comments removed and whitespace normalized.
         /// Equivalent to [`Transform::Comment`] followed by [`Transform::Space`].
         ///
@@ -1101,18 +2146,58 @@ flags! {
     /// # Specificity order
     ///
     /// Specificity is in the order specified by the implementation of [`Ord`] for this type.
-    /// Currently only one variant exists, but the idea is similar to that of [`Kind`] or [`Transform`]:
+    /// The idea is similar to that of [`Kind`] or [`Transform`]:
     /// the more exact and meaningful the snippet target, the higher specificity.
+    /// A [`Target::Class`] contains its methods, so matching it is considered a more exact match
+    /// than matching a [`Target::Function`].
+    /// A [`Target::Macro`] is usually short and made up of fragments of code rather than whole statements,
+    /// so matching it is considered less exact than matching a [`Target::Function`].
     ///
     /// Items with higher "specificity order" are sorted _higher_; meaning that a
     /// higher specificity variant is sorted later in a vector
-    /// than a lower specificity variant.
+    /// than a lower specificity variant:
+    ///
+    /// ```
+    /// # use snippets::*;
+    /// assert!(Target::Class > Target::Function);
+    /// assert!(Target::Function > Target::Macro);
+    /// ```
     #[derive(Hash, PartialOrd, Ord, EnumIter, Display)]
     #[strum(serialize_all = "snake_case")]
     #[non_exhaustive]
     pub enum Target: u8 {
+        /// Targets preprocessor macro definitions (`#define`) as snippets.
+        ///
+        /// For this target, the [`Kind::Signature`] of a macro is the directive up to its value,
+        /// and the [`Kind::Body`] is its value:
+        ///
+        /// ```ignore
+        /// #define SQUARE(x)  // <- signature
+        ///   ((x) * (x))      // <- body
+        /// ```
+        ///
+        /// Only languages with a preprocessor support this target;
+        /// review the documentation for each [`Extractor`] for details.
+        Macro,
+
         /// Targets function defintions as snippets.
         Function,
+
+        /// Targets class, struct, and similar type declarations as snippets.
+        ///
+        /// For this target, the [`Kind::Signature`] of a class is its header,
+        /// and the [`Kind::Body`] is the declaration of its members:
+        ///
+        /// ```ignore
+        /// class Greeter : public Base   // <- signature
+        /// {                             // <- body
+        ///   std::string greet() const;  // <- body
+        /// }                             // <- body
+        /// ```
+        ///
+        /// Support for this target varies by language;
+        /// review the documentation for each [`Extractor`] for details.
+        Class,
     }
 }
 
@@ -1250,6 +2335,13 @@ mod tests {
         assert_eq!(input, vec![Kind::Signature, Kind::Body, Kind::Full]);
     }
 
+    #[test]
+    fn specificity_order_target() {
+        let mut input = vec![Target::Class, Target::Function, Target::Macro];
+        input.sort_unstable();
+        assert_eq!(input, vec![Target::Macro, Target::Function, Target::Class]);
+    }
+
     #[test]
     fn specificity_order_method() {
         let arbitrary = Transform::Space;
@@ -1273,8 +2365,10 @@ mod tests {
             Method::Normalized(Transform::Comment),
             Method::Normalized(Transform::Code),
             Method::Normalized(Transform::Space),
+            Method::Normalized(Transform::Literal),
         ];
         let expected = vec![
+            Method::Normalized(Transform::Literal),
             Method::Normalized(Transform::Code),
             Method::Normalized(Transform::Comment),
             Method::Normalized(Transform::Space),
@@ -1355,4 +2449,42 @@ mod tests {
 
         assert_eq!(location.extract_from(input.as_bytes()), b"helloworld");
     }
+
+    #[test]
+    fn line_index_positions() {
+        let lines = LineIndex::new("ab\r\ncd\n\n");
+        let positions = [0, 1, 2, 3, 4, 6, 7, 8]
+            .into_iter()
+            .map(|offset| lines.position(offset).to_string())
+            .collect_vec();
+        assert_eq!(
+            positions,
+            vec!["1:1", "1:2", "1:3", "1:4", "2:1", "2:3", "3:1", "4:1"]
+        );
+    }
+
+    #[test]
+    fn line_index_empty() {
+        let lines = LineIndex::new("");
+        let span = lines.span(Location::from(0..0));
+        assert_eq!(span.to_string(), "1:1-1:1");
+    }
+
+    #[test]
+    fn language_display_is_per_language() {
+        struct First;
+        impl Language for First {
+            const NAME: &'static str = "first";
+            const STRATEGY: Strategy = Strategy::Static;
+        }
+
+        struct Second;
+        impl Language for Second {
+            const NAME: &'static str = "second";
+            const STRATEGY: Strategy = Strategy::Static;
+        }
+
+        assert_eq!(First::display(), "first/static");
+        assert_eq!(Second::display(), "second/static");
+    }
 }
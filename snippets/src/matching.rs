@@ -0,0 +1,270 @@
+//! Matches the snippets extracted from a project against a corpus of known snippets,
+//! such as an export of a knowledge base.
+//!
+//! Snippets match when they have the same fingerprint and were extracted
+//! with the same [`Kind`] and [`Method`]. The language and location of the snippets don't matter,
+//! so code copied between files, or between closely related languages such as C and C++, still matches.
+//!
+//! ```
+//! # use snippets::*;
+//! # #[cfg(feature = "lang-c99-tc3")]
+//! # fn main() -> Result<(), Error> {
+//! use snippets::{language::c99_tc3, matching::{self, Corpus}};
+//!
+//! let opts = Options::new(Targets::full(), Kinds::full(), Transforms::none());
+//! let upstream = b"int add(int a, int b) { return a + b; }";
+//! let project = b"int sub(int a, int b) { return a - b; }\nint add(int a, int b) { return a + b; }";
+//!
+//! let known = c99_tc3::Extractor::extract(&opts, upstream)?
+//!     .into_iter()
+//!     .map(|snippet| ("upstream/math.c", snippet.into_dyn()))
+//!     .collect::<Corpus<_>>();
+//! let extracted = c99_tc3::Extractor::extract(&opts, project)?
+//!     .into_iter()
+//!     .map(|snippet| ("src/math.c", snippet.into_dyn()));
+//!
+//! // `add` matches in full; its signature and body are covered by that match.
+//! let matches = matching::find(extracted, &known);
+//! assert_eq!(matches.len(), 1);
+//!
+//! let found = &matches.iter().next().expect("has a match");
+//! assert_eq!(found.project().snippet().metadata().kind(), Kind::Full);
+//! assert_eq!(found.project().snippet().metadata().name(), Some("add"));
+//! assert_eq!(found.known()[0].source(), &"upstream/math.c");
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "lang-c99-tc3"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Specificity and overlap resolution
+//!
+//! A copied function usually matches several times: in full, by its signature and body,
+//! and with each configured [`Method`]. Reporting all of these would bury the meaningful
+//! matches, so matches are considered in specificity order (first by [`Kind`], then by [`Method`]),
+//! and a match is dropped if its location in the project is entirely within the location
+//! of a more specific match already accepted for the same source.
+//!
+//! Matches that only partially overlap, such as adjacent [`Method::Winnowed`] k-grams,
+//! are all reported.
+//!
+//! [`Kind`]: crate::Kind
+//! [`Method`]: crate::Method
+//! [`Method::Winnowed`]: crate::Method::Winnowed
+
+use std::{cmp::Reverse, collections::HashMap, hash::Hash};
+
+use getset::Getters;
+
+use crate::{text, DynSnippet, Kind, Location, Method};
+
+/// A snippet along with the source from which it was extracted.
+///
+/// The source is whatever identifies the snippet for the caller,
+/// such as the path of the file in a project or the identifier of a package in a knowledge base.
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct Entry<S> {
+    /// The source from which the snippet was extracted.
+    source: S,
+
+    /// The extracted snippet.
+    snippet: DynSnippet,
+}
+
+impl<S> Entry<S> {
+    /// Create a new entry for a snippet extracted from the source.
+    pub fn new(source: S, snippet: impl Into<DynSnippet>) -> Self {
+        Self {
+            source,
+            snippet: snippet.into(),
+        }
+    }
+}
+
+/// A set of known snippets, indexed by fingerprint so that they can be matched efficiently.
+#[derive(Debug, Clone)]
+pub struct Corpus<S> {
+    index: HashMap<text::Buffer, Vec<Entry<S>>>,
+    len: usize,
+}
+
+impl<S> Corpus<S> {
+    /// Create an empty corpus.
+    pub fn new() -> Self {
+        Self {
+            index: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Add a snippet extracted from the source to the corpus.
+    pub fn insert(&mut self, source: S, snippet: impl Into<DynSnippet>) {
+        let entry = Entry::new(source, snippet);
+        self.index
+            .entry(entry.snippet.fingerprint().clone())
+            .or_default()
+            .push(entry);
+        self.len += 1;
+    }
+
+    /// The number of snippets in the corpus.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the corpus contains no snippets.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the entries in the corpus that match the snippet.
+    pub fn matching<'a>(&'a self, snippet: &DynSnippet) -> impl Iterator<Item = &'a Entry<S>> + 'a {
+        let kind = snippet.metadata().kind();
+        let method = snippet.metadata().method();
+        self.index
+            .get(snippet.fingerprint())
+            .into_iter()
+            .flatten()
+            .filter(move |entry| {
+                let meta = entry.snippet.metadata();
+                meta.kind() == kind && meta.method() == method
+            })
+    }
+}
+
+impl<S> Default for Corpus<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, D: Into<DynSnippet>> Extend<(S, D)> for Corpus<S> {
+    fn extend<T: IntoIterator<Item = (S, D)>>(&mut self, iter: T) {
+        for (source, snippet) in iter {
+            self.insert(source, snippet);
+        }
+    }
+}
+
+impl<S, D: Into<DynSnippet>> FromIterator<(S, D)> for Corpus<S> {
+    fn from_iter<T: IntoIterator<Item = (S, D)>>(iter: T) -> Self {
+        let mut corpus = Self::new();
+        corpus.extend(iter);
+        corpus
+    }
+}
+
+/// A snippet from the project, along with the known snippets it matches.
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct Match<'k, P, K> {
+    /// The snippet in the project.
+    project: Entry<P>,
+
+    /// The known snippets that match the project snippet; there is always at least one.
+    known: Vec<&'k Entry<K>>,
+}
+
+impl<P, K> Match<'_, P, K> {
+    fn specificity(&self) -> (Kind, Method) {
+        let meta = self.project.snippet.metadata();
+        (meta.kind(), meta.method())
+    }
+}
+
+/// The matches found by [`find`], ordered from most to least specific.
+#[derive(Debug, Clone)]
+pub struct Matches<'k, P, K> {
+    matches: Vec<Match<'k, P, K>>,
+}
+
+impl<'k, P, K> Matches<'k, P, K> {
+    /// The number of matches.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Whether no matches were found.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Iterate over the matches, from most to least specific.
+    pub fn iter(&self) -> impl Iterator<Item = &Match<'k, P, K>> {
+        self.matches.iter()
+    }
+
+    /// Iterate over the matches grouped by the [`Kind`] and [`Method`] of the project snippets,
+    /// from most to least specific.
+    ///
+    /// Within each group, matches are in the order in which their project snippets were provided.
+    pub fn groups(&self) -> impl Iterator<Item = (Kind, Method, &[Match<'k, P, K>])> {
+        self.matches
+            .chunk_by(|a, b| a.specificity() == b.specificity())
+            .map(|group| {
+                let (kind, method) = group[0].specificity();
+                (kind, method, group)
+            })
+    }
+}
+
+impl<'k, P, K> IntoIterator for Matches<'k, P, K> {
+    type Item = Match<'k, P, K>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.matches.into_iter()
+    }
+}
+
+/// Match the snippets extracted from a project against the known snippets in the corpus.
+///
+/// Project snippets are provided along with their source, which is used to resolve overlaps;
+/// see the module documentation for details.
+pub fn find<'k, P, K, D>(
+    project: impl IntoIterator<Item = (P, D)>,
+    known: &'k Corpus<K>,
+) -> Matches<'k, P, K>
+where
+    P: Clone + Eq + Hash,
+    D: Into<DynSnippet>,
+{
+    let mut candidates = project
+        .into_iter()
+        .map(|(source, snippet)| Entry::new(source, snippet))
+        .filter_map(|project| {
+            let known = known.matching(&project.snippet).collect::<Vec<_>>();
+            if known.is_empty() {
+                None
+            } else {
+                Some(Match { project, known })
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Stable, so that matches of equal specificity stay in the order they were provided.
+    candidates.sort_by_key(|candidate| Reverse(candidate.specificity()));
+
+    let mut accepted = HashMap::<P, Vec<Location>>::new();
+    let matches = candidates
+        .into_iter()
+        .filter(|candidate| {
+            let location = candidate.project.snippet.metadata().location();
+            let covered = accepted
+                .entry(candidate.project.source.clone())
+                .or_default();
+            if covered.iter().any(|outer| contains(*outer, location)) {
+                return false;
+            }
+            covered.push(location);
+            true
+        })
+        .collect();
+
+    Matches { matches }
+}
+
+fn contains(outer: Location, inner: Location) -> bool {
+    outer.start_byte() <= inner.start_byte() && inner.end_byte() <= outer.end_byte()
+}
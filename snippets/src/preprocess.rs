@@ -0,0 +1,930 @@
+//! Runs a lightweight C preprocessor pass over source code before it is parsed,
+//! extracting snippets with the [`Strategy::Preprocessed`] strategy.
+//!
+//! The prebuilt C and C++ extractors statically analyze the code as written.
+//! In codebases that use conditional compilation heavily, the parser sees every branch of each `#ifdef` at once,
+//! so functions are missed, or extracted with code mixed from several configurations.
+//! Preprocessing first resolves the conditionals for a provided set of defines,
+//! the way a compiler would for a given build configuration:
+//!
+//! ```
+//! # use snippets::*;
+//! # #[cfg(feature = "lang-c99-tc3")]
+//! # fn main() -> Result<(), Error> {
+//! use snippets::{language::c99_tc3, preprocess::{Preprocessed, Preprocessor}};
+//!
+//! let content = br#"
+//! #ifdef _WIN32
+//! int open_file(const char *path) {
+//! #else
+//! int open_file(const char *path, int mode) {
+//! #endif
+//!   return BUFFER_SIZE;
+//! }
+//! "#;
+//!
+//! let opts = Options::new(Target::Function, Kind::Signature, Transforms::none()).with_content();
+//! let preprocessor = Preprocessor::new().define("BUFFER_SIZE", "4096");
+//! let snippets = preprocessor.extract::<c99_tc3::Extractor>(&opts, content)?;
+//!
+//! assert_eq!(snippets.len(), 1);
+//! assert_eq!(Preprocessed::<c99_tc3::Language>::display(), "c99_tc3/preprocessed");
+//! let signature = snippets[0].content().expect("content is retained");
+//! assert_eq!(signature.as_bytes(), b"int open_file(const char *path, int mode)");
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "lang-c99-tc3"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Supported preprocessing
+//!
+//! This is not a complete C preprocessor; it only does what is needed to expose code to the parser.
+//!
+//! Directive | Handling
+//! ---|---
+//! `#if`, `#ifdef`, `#ifndef`, `#elif`, `#elifdef`, `#elifndef`, `#else`, `#endif` | Resolved, keeping only the active groups.
+//! `#define` | Object-like macros are expanded in the code that follows; function-like macros are only recorded as defined.
+//! `#undef` | Removes the definition.
+//! Anything else, such as `#include` | Left as-is.
+//!
+//! `#if` and `#elif` expressions support integer literals, `defined`, and the arithmetic, comparison, and logical
+//! operators. As in C, identifiers that aren't object-like macros evaluate to `0`.
+//! Expressions that can't be evaluated, such as those invoking function-like macros, are treated as false.
+//!
+//! `#define` directives in active groups are kept, so that macros are still extracted by [`Target::Macro`].
+//! Macros are not expanded in directives, comments, or string and character literals.
+//!
+//! Macros expand into at most 256 levels of nested macros, and expressions nest at most 256 levels deep;
+//! deeper macros are left unexpanded, and deeper expressions are treated as false.
+//! Since macros can expand exponentially, [`Preprocessor::extract`] stops preprocessing
+//! once the output grows past [`Options::max_file_bytes`].
+//!
+//! # Locations
+//!
+//! Like transcoding (see [`Options::with_transcoding`]), snippet locations are reported in the preprocessed content.
+//! Conditional directives and inactive groups are replaced by whitespace rather than removed,
+//! so lines and byte offsets are unchanged by conditional compilation;
+//! only the expansion of macros moves the code that follows it on the same line.
+//!
+//! [`Strategy::Preprocessed`]: crate::Strategy::Preprocessed
+//! [`Target::Macro`]: crate::Target::Macro
+//! [`Options::with_transcoding`]: crate::Options::with_transcoding
+//! [`Options::max_file_bytes`]: crate::Options::max_file_bytes
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use tracing::debug;
+
+use crate::{
+    Error, FallibleIterator, Language, LazyExtractor, Options, Skipped, Snippet, Strategy,
+};
+
+/// The maximum depth of nested macro expansions, and of nested `#if` subexpressions.
+/// This keeps crafted input from overflowing the stack.
+const MAX_NESTING: usize = 256;
+
+/// Preprocesses C and C++ source code with a set of defines before it is parsed;
+/// see the module documentation for details.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    defines: HashMap<Vec<u8>, Macro>,
+}
+
+impl Preprocessor {
+    /// Create a preprocessor without any defines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define an object-like macro, like `-DNAME=VALUE` on a compiler command line.
+    ///
+    /// Compilers define `-DNAME` as `1`; use an empty value for `-DNAME=`.
+    pub fn define(mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.defines.insert(
+            name.as_ref().as_bytes().to_vec(),
+            Macro::Object(value.as_ref().as_bytes().to_vec()),
+        );
+        self
+    }
+
+    /// Report whether the macro is defined.
+    pub fn is_defined(&self, name: impl AsRef<str>) -> bool {
+        self.defines.contains_key(name.as_ref().as_bytes())
+    }
+
+    /// Preprocess the content, resolving conditional compilation and expanding object-like macros.
+    ///
+    /// The size of the output isn't limited; use [`Preprocessor::extract`]
+    /// with [`Options::with_max_file_bytes`] to bound it.
+    ///
+    /// ```
+    /// # use snippets::preprocess::Preprocessor;
+    /// let content = b"#if VERSION >= 2\nint v2;\n#else\nint v1;\n#endif\nint max = MAX;\n";
+    /// let preprocessor = Preprocessor::new().define("VERSION", "2").define("MAX", "10");
+    ///
+    /// let preprocessed = preprocessor.preprocess(content);
+    /// let expected = b"                \nint v2;\n     \n       \n      \nint max = 10;\n";
+    /// assert_eq!(preprocessed, expected);
+    /// ```
+    pub fn preprocess(&self, content: &[u8]) -> Vec<u8> {
+        self.preprocess_within(content, None)
+            .unwrap_or_else(|skipped| {
+                unreachable!("preprocessing without a maximum size: {skipped}")
+            })
+    }
+
+    /// Preprocess the content, reporting [`Skipped::TooLarge`] as soon as the output
+    /// grows past `max_bytes`, rather than once it is complete.
+    fn preprocess_within(
+        &self,
+        content: &[u8],
+        max_bytes: Option<usize>,
+    ) -> Result<Vec<u8>, Skipped> {
+        let mut state = State {
+            macros: self.defines.clone(),
+            conditionals: Vec::new(),
+            in_comment: false,
+            max_bytes,
+        };
+
+        let mut preprocessed = Vec::with_capacity(content.len());
+        for line in logical_lines(content) {
+            state.line(line, &mut preprocessed)?;
+        }
+        if !state.conditionals.is_empty() {
+            debug!(
+                unterminated = state.conditionals.len(),
+                "conditional without #endif"
+            );
+        }
+
+        Ok(preprocessed)
+    }
+
+    /// Preprocess the content, then extract snippets from it with the extractor `E`
+    /// according to the provided options.
+    ///
+    /// The content is prepared (see [`Options::prepare`]) before it is preprocessed.
+    /// Both the content and the preprocessed output are limited to [`Options::max_file_bytes`];
+    /// preprocessing stops as soon as the output grows past it.
+    pub fn extract<E: LazyExtractor>(
+        &self,
+        opts: &Options,
+        content: impl AsRef<[u8]>,
+    ) -> Result<Vec<Snippet<Preprocessed<E::Language>>>, Error> {
+        let content = opts.prepare(content.as_ref());
+        opts.check_size(&content)?;
+        let content = self.preprocess_within(&content, opts.max_file_bytes())?;
+        let parsed = E::parse_guarded(opts, &content)?;
+        E::extract_lazy(&parsed, opts)
+            .map(|snippet| Ok(snippet.with_language()))
+            .collect()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Preprocessor {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |preprocessor, (name, value)| {
+                preprocessor.define(name, value)
+            })
+    }
+}
+
+/// The language `L`, extracted after preprocessing with a [`Preprocessor`].
+///
+/// Its name is the same as `L`, but its strategy is [`Strategy::Preprocessed`],
+/// so that snippets extracted from preprocessed content are distinguished from those extracted statically.
+pub struct Preprocessed<L>(PhantomData<L>);
+
+impl<L: Language> Language for Preprocessed<L> {
+    const NAME: &'static str = L::NAME;
+    const STRATEGY: Strategy = Strategy::Preprocessed;
+}
+
+// Derived implementations would require `L` to implement these traits too.
+impl<L> Clone for Preprocessed<L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L> Copy for Preprocessed<L> {}
+
+impl<L: Language> std::fmt::Display for Preprocessed<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::display())
+    }
+}
+
+impl<L: Language> std::fmt::Debug for Preprocessed<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::display())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Macro {
+    /// An object-like macro with its replacement text, e.g. `#define MAX 10`.
+    Object(Vec<u8>),
+
+    /// A function-like macro, e.g. `#define SQUARE(x) ((x) * (x))`.
+    /// These are never expanded, but are reported as defined.
+    Function,
+}
+
+/// A conditional group, opened by `#if` (or a variant) and closed by `#endif`.
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+    /// Whether the group containing this conditional is active.
+    parent_active: bool,
+
+    /// Whether any branch of this conditional has been active so far.
+    taken: bool,
+
+    /// Whether the current branch of this conditional is active.
+    active: bool,
+}
+
+struct State {
+    macros: HashMap<Vec<u8>, Macro>,
+    conditionals: Vec<Conditional>,
+    in_comment: bool,
+
+    /// The maximum size of the output, if any.
+    max_bytes: Option<usize>,
+}
+
+impl State {
+    fn active(&self) -> bool {
+        self.conditionals.last().is_none_or(|c| c.active)
+    }
+
+    /// Report whether the output is within the maximum size.
+    fn check_size(&self, out: &[u8]) -> Result<(), Skipped> {
+        match self.max_bytes {
+            Some(max_bytes) if out.len() > max_bytes => Err(Skipped::TooLarge {
+                bytes: out.len(),
+                max_bytes,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Preprocess a logical line (with any line continuations) into the output.
+    fn line(&mut self, line: &[u8], out: &mut Vec<u8>) -> Result<(), Skipped> {
+        let directive = if self.in_comment {
+            None
+        } else {
+            Directive::parse(line)
+        };
+
+        // Comments may start in any line, and continue onto the next.
+        let mut tokens = Tokens::new(line, self.in_comment);
+        match directive {
+            Some(directive) if directive.is_conditional() => {
+                tokens.by_ref().for_each(drop);
+                self.conditional(directive)?;
+                blank(line, out);
+            }
+            Some(directive) if self.active() => {
+                tokens.by_ref().for_each(drop);
+                self.define(directive);
+                out.extend_from_slice(line);
+            }
+            None if self.active() => {
+                for (token, text) in tokens.by_ref() {
+                    match token {
+                        Token::Identifier => self.expand(text, out, &mut Vec::new())?,
+                        _ => out.extend_from_slice(text),
+                    }
+                }
+            }
+            _ => {
+                tokens.by_ref().for_each(drop);
+                blank(line, out);
+            }
+        }
+        self.in_comment = tokens.in_comment;
+        self.check_size(out)
+    }
+
+    fn conditional(&mut self, directive: Directive) -> Result<(), Skipped> {
+        let parent_active = self.active();
+        match directive.name.as_slice() {
+            b"if" | b"ifdef" | b"ifndef" => {
+                let condition = parent_active && self.evaluate(&directive)?;
+                self.conditionals.push(Conditional {
+                    parent_active,
+                    taken: condition,
+                    active: condition,
+                });
+            }
+            b"elif" | b"elifdef" | b"elifndef" => {
+                let Some(&Conditional {
+                    parent_active,
+                    taken,
+                    ..
+                }) = self.conditionals.last()
+                else {
+                    debug!("#elif without #if");
+                    return Ok(());
+                };
+                let condition = parent_active && !taken && self.evaluate(&directive)?;
+                if let Some(conditional) = self.conditionals.last_mut() {
+                    conditional.active = condition;
+                    conditional.taken |= condition;
+                }
+            }
+            b"else" => match self.conditionals.last_mut() {
+                Some(conditional) => {
+                    conditional.active = conditional.parent_active && !conditional.taken;
+                    conditional.taken = true;
+                }
+                None => debug!("#else without #if"),
+            },
+            _ => {
+                if self.conditionals.pop().is_none() {
+                    debug!("#endif without #if");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, directive: &Directive) -> Result<bool, Skipped> {
+        let defined = |name: &[u8]| self.macros.contains_key(name);
+        Ok(match directive.name.as_slice() {
+            b"ifdef" | b"elifdef" => defined(&directive.argument),
+            b"ifndef" | b"elifndef" => !defined(&directive.argument),
+            _ => Expression::evaluate(&directive.argument, &self.macros, self.max_bytes)?
+                .unwrap_or_else(|| {
+                    debug!(expression = %String::from_utf8_lossy(&directive.argument), "unsupported expression");
+                    false
+                }),
+        })
+    }
+
+    fn define(&mut self, directive: Directive) {
+        match directive.name.as_slice() {
+            b"define" => {
+                let argument = directive.argument.as_slice();
+                let name_len = identifier_len(argument);
+                if name_len == 0 {
+                    return;
+                }
+                let (name, rest) = argument.split_at(name_len);
+                let definition = if rest.first() == Some(&b'(') {
+                    Macro::Function
+                } else {
+                    Macro::Object(rest.trim_ascii().to_vec())
+                };
+                self.macros.insert(name.to_vec(), definition);
+            }
+            b"undef" => {
+                self.macros.remove(directive.argument.as_slice());
+            }
+            _ => {}
+        }
+    }
+
+    /// Expand the identifier into the output if it names an object-like macro,
+    /// recursively expanding the macros in its replacement text.
+    ///
+    /// Macros currently being expanded are not expanded again, which (as in C)
+    /// stops self-referential macros such as `#define errno errno` from recursing forever.
+    /// The output is checked against the maximum size as it grows,
+    /// since a few nested macros can expand exponentially.
+    fn expand<'a>(
+        &'a self,
+        name: &'a [u8],
+        out: &mut Vec<u8>,
+        expanding: &mut Vec<&'a [u8]>,
+    ) -> Result<(), Skipped> {
+        let replacement = match self.macros.get(name) {
+            Some(Macro::Object(replacement))
+                if !expanding.contains(&name) && expanding.len() < MAX_NESTING =>
+            {
+                replacement
+            }
+            _ => {
+                out.extend_from_slice(name);
+                return self.check_size(out);
+            }
+        };
+
+        expanding.push(name);
+        for (token, text) in Tokens::new(replacement, false) {
+            match token {
+                Token::Identifier => self.expand(text, out, expanding)?,
+                _ => {
+                    out.extend_from_slice(text);
+                    self.check_size(out)?;
+                }
+            }
+        }
+        expanding.pop();
+        Ok(())
+    }
+}
+
+/// A preprocessor directive, with line continuations joined and comments removed.
+struct Directive {
+    name: Vec<u8>,
+    argument: Vec<u8>,
+}
+
+impl Directive {
+    fn parse(line: &[u8]) -> Option<Self> {
+        let line = line.trim_ascii_start().strip_prefix(b"#")?;
+        let joined = join_continuations(line);
+        let text = strip_comments(&joined);
+        let text = text.trim_ascii_start();
+
+        let (name, argument) = text.split_at(identifier_len(text));
+        Some(Self {
+            name: name.to_vec(),
+            argument: argument.trim_ascii().to_vec(),
+        })
+    }
+
+    fn is_conditional(&self) -> bool {
+        matches!(
+            self.name.as_slice(),
+            b"if" | b"ifdef" | b"ifndef" | b"elif" | b"elifdef" | b"elifndef" | b"else" | b"endif"
+        )
+    }
+}
+
+/// Evaluates the constant expression of an `#if` or `#elif` directive.
+struct Expression<'m> {
+    tokens: Vec<Operand>,
+    position: usize,
+    macros: &'m HashMap<Vec<u8>, Macro>,
+
+    /// The depth of the subexpression currently being parsed.
+    depth: usize,
+
+    /// The maximum number of bytes of macro replacement text to expand, if any.
+    max_bytes: Option<usize>,
+
+    /// The number of bytes of macro replacement text expanded so far.
+    expanded: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Number(i64),
+    Operator(Vec<u8>),
+}
+
+impl<'m> Expression<'m> {
+    /// Evaluate the expression, or `None` if it can't be evaluated.
+    ///
+    /// Reports [`Skipped::TooLarge`] if expanding the macros in the expression
+    /// would take more than `max_bytes` bytes.
+    fn evaluate(
+        text: &[u8],
+        macros: &'m HashMap<Vec<u8>, Macro>,
+        max_bytes: Option<usize>,
+    ) -> Result<Option<bool>, Skipped> {
+        let mut expression = Self {
+            tokens: Vec::new(),
+            position: 0,
+            macros,
+            depth: 0,
+            max_bytes,
+            expanded: 0,
+        };
+        let tokens = expression.tokenize(text, &mut Vec::new());
+        if let Some(max_bytes) = max_bytes.filter(|&max_bytes| expression.expanded > max_bytes) {
+            return Err(Skipped::TooLarge {
+                bytes: expression.expanded,
+                max_bytes,
+            });
+        }
+        let Some(tokens) = tokens else {
+            return Ok(None);
+        };
+        expression.tokens = tokens;
+
+        let Some(value) = expression.ternary() else {
+            return Ok(None);
+        };
+        if expression.position != expression.tokens.len() {
+            return Ok(None);
+        }
+        Ok(Some(value != 0))
+    }
+
+    /// Split the expression into numbers and operators,
+    /// evaluating `defined` and expanding macros along the way.
+    ///
+    /// Stops (returning `None`) once the expanded replacement text grows past the maximum size.
+    fn tokenize<'a>(
+        &mut self,
+        text: &'a [u8],
+        expanding: &mut Vec<&'a [u8]>,
+    ) -> Option<Vec<Operand>>
+    where
+        'm: 'a,
+    {
+        let macros = self.macros;
+        let mut operands = Vec::new();
+        let mut tokens = Tokens::new(text, false).filter(|(token, text)| {
+            !matches!(token, Token::Comment) && !text.iter().all(u8::is_ascii_whitespace)
+        });
+
+        while let Some((token, text)) = tokens.next() {
+            match token {
+                Token::Number => operands.push(Operand::Number(parse_number(text)?)),
+                Token::Identifier if text == b"defined" => {
+                    let name = match tokens.next()? {
+                        (Token::Identifier, name) => name,
+                        (_, b"(") => match (tokens.next()?, tokens.next()?) {
+                            ((Token::Identifier, name), (_, b")")) => name,
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                    let defined = macros.contains_key(name);
+                    operands.push(Operand::Number(defined.into()));
+                }
+                Token::Identifier => match macros.get(text) {
+                    Some(Macro::Object(replacement)) if !expanding.contains(&text) => {
+                        self.expanded += replacement.len();
+                        let exceeded = self
+                            .max_bytes
+                            .is_some_and(|max_bytes| self.expanded > max_bytes);
+                        if exceeded || expanding.len() >= MAX_NESTING {
+                            return None;
+                        }
+                        expanding.push(text);
+                        operands.extend(self.tokenize(replacement, expanding)?);
+                        expanding.pop();
+                    }
+                    _ if text == b"true" => operands.push(Operand::Number(1)),
+                    _ => operands.push(Operand::Number(0)),
+                },
+                Token::Other => operands.push(Operand::Operator(text.to_vec())),
+                Token::Literal | Token::Comment => return None,
+            }
+        }
+
+        Some(operands)
+    }
+
+    fn peek(&self) -> Option<&[u8]> {
+        match self.tokens.get(self.position)? {
+            Operand::Operator(operator) => Some(operator),
+            Operand::Number(_) => None,
+        }
+    }
+
+    fn expect(&mut self, operator: &[u8]) -> Option<()> {
+        (self.peek()? == operator).then(|| self.position += 1)
+    }
+
+    /// Parse a subexpression with `parse`, unless that would nest too deeply.
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Option<i64>) -> Option<i64> {
+        if self.depth >= MAX_NESTING {
+            return None;
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn ternary(&mut self) -> Option<i64> {
+        self.nested(|expression| {
+            let condition = expression.binary(0)?;
+            if expression.expect(b"?").is_none() {
+                return Some(condition);
+            }
+            let then = expression.ternary()?;
+            expression.expect(b":")?;
+            let otherwise = expression.ternary()?;
+            Some(if condition != 0 { then } else { otherwise })
+        })
+    }
+
+    /// Parse binary operators by precedence climbing,
+    /// only consuming operators that bind at least as tightly as `min_precedence`.
+    fn binary(&mut self, min_precedence: u8) -> Option<i64> {
+        let mut lhs = self.unary()?;
+        while let Some(precedence) = self.peek().and_then(precedence) {
+            if precedence < min_precedence {
+                break;
+            }
+            let operator = self.peek()?.to_vec();
+            self.position += 1;
+
+            let rhs = self.binary(precedence + 1)?;
+            lhs = apply(&operator, lhs, rhs)?;
+        }
+        Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<i64> {
+        self.nested(
+            |expression| match expression.tokens.get(expression.position)?.clone() {
+                Operand::Number(value) => {
+                    expression.position += 1;
+                    Some(value)
+                }
+                Operand::Operator(operator) => {
+                    expression.position += 1;
+                    match operator.as_slice() {
+                        b"(" => {
+                            let value = expression.ternary()?;
+                            expression.expect(b")")?;
+                            Some(value)
+                        }
+                        b"!" => Some((expression.unary()? == 0).into()),
+                        b"~" => Some(!expression.unary()?),
+                        b"-" => Some(expression.unary()?.wrapping_neg()),
+                        b"+" => expression.unary(),
+                        _ => None,
+                    }
+                }
+            },
+        )
+    }
+}
+
+fn precedence(operator: &[u8]) -> Option<u8> {
+    Some(match operator {
+        b"||" => 0,
+        b"&&" => 1,
+        b"|" => 2,
+        b"^" => 3,
+        b"&" => 4,
+        b"==" | b"!=" => 5,
+        b"<" | b">" | b"<=" | b">=" => 6,
+        b"<<" | b">>" => 7,
+        b"+" | b"-" => 8,
+        b"*" | b"/" | b"%" => 9,
+        _ => return None,
+    })
+}
+
+fn apply(operator: &[u8], lhs: i64, rhs: i64) -> Option<i64> {
+    Some(match operator {
+        b"||" => (lhs != 0 || rhs != 0).into(),
+        b"&&" => (lhs != 0 && rhs != 0).into(),
+        b"|" => lhs | rhs,
+        b"^" => lhs ^ rhs,
+        b"&" => lhs & rhs,
+        b"==" => (lhs == rhs).into(),
+        b"!=" => (lhs != rhs).into(),
+        b"<" => (lhs < rhs).into(),
+        b">" => (lhs > rhs).into(),
+        b"<=" => (lhs <= rhs).into(),
+        b">=" => (lhs >= rhs).into(),
+        b"<<" => lhs.checked_shl(u32::try_from(rhs).ok()?)?,
+        b">>" => lhs.checked_shr(u32::try_from(rhs).ok()?)?,
+        b"+" => lhs.wrapping_add(rhs),
+        b"-" => lhs.wrapping_sub(rhs),
+        b"*" => lhs.wrapping_mul(rhs),
+        b"/" => lhs.checked_div(rhs)?,
+        b"%" => lhs.checked_rem(rhs)?,
+        _ => return None,
+    })
+}
+
+/// Parse an integer literal, such as `10`, `0x1F`, `017`, or `0b11`, ignoring any suffix (e.g. `10UL`).
+fn parse_number(text: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(text).ok()?;
+    let text = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(binary) = text.strip_prefix("0b").or(text.strip_prefix("0B")) {
+        (binary, 2)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (&text[1..], 8)
+    } else {
+        (text, 10)
+    };
+    u64::from_str_radix(digits, radix)
+        .ok()
+        .map(|value| value as i64)
+}
+
+/// The kinds of token that matter to the preprocessor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// A block or line comment, or the part of a block comment in the text.
+    Comment,
+
+    /// A string or character literal.
+    Literal,
+
+    /// A preprocessing number, e.g. `10`, `0x1F`, or `1.5e-3f`.
+    Number,
+
+    /// An identifier or keyword.
+    Identifier,
+
+    /// Anything else: whitespace, or a punctuator such as `&&`.
+    Other,
+}
+
+/// Splits text into [`Token`]s.
+///
+/// Every byte of the text is part of exactly one token,
+/// so concatenating the tokens reproduces the text.
+struct Tokens<'a> {
+    text: &'a [u8],
+    position: usize,
+
+    /// Whether the text ends inside a block comment, once iteration is complete.
+    in_comment: bool,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a [u8], in_comment: bool) -> Self {
+        Self {
+            text,
+            position: 0,
+            in_comment,
+        }
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        &self.text[self.position..]
+    }
+
+    /// Report the length of the next token.
+    fn next_len(&mut self) -> (Token, usize) {
+        let rest = self.remaining();
+        if self.in_comment || rest.starts_with(b"/*") {
+            let start = if self.in_comment { 0 } else { 2 };
+            return match find(&rest[start..], b"*/") {
+                Some(end) => {
+                    self.in_comment = false;
+                    (Token::Comment, start + end + 2)
+                }
+                None => {
+                    self.in_comment = true;
+                    (Token::Comment, rest.len())
+                }
+            };
+        }
+        if rest.starts_with(b"//") {
+            // Line comments continue to the end of the logical line, but not its final newline.
+            return (Token::Comment, rest.trim_ascii_end().len());
+        }
+
+        match rest[0] {
+            quote @ (b'"' | b'\'') => {
+                let mut len = 1;
+                while len < rest.len() {
+                    match rest[len] {
+                        b'\\' => len += 2,
+                        b'\n' => break,
+                        byte if byte == quote => {
+                            len += 1;
+                            break;
+                        }
+                        _ => len += 1,
+                    }
+                }
+                (Token::Literal, len.min(rest.len()))
+            }
+            byte if byte.is_ascii_digit()
+                || (byte == b'.' && rest.get(1).is_some_and(u8::is_ascii_digit)) =>
+            {
+                let mut len = 1;
+                while let Some(&byte) = rest.get(len) {
+                    let exponent = matches!(rest[len - 1], b'e' | b'E' | b'p' | b'P');
+                    if is_identifier(byte)
+                        || byte == b'.'
+                        || (exponent && matches!(byte, b'+' | b'-'))
+                    {
+                        len += 1;
+                    } else {
+                        break;
+                    }
+                }
+                (Token::Number, len)
+            }
+            _ if identifier_len(rest) > 0 => (Token::Identifier, identifier_len(rest)),
+            _ => {
+                const PUNCTUATORS: [&[u8]; 8] =
+                    [b"||", b"&&", b"==", b"!=", b"<=", b">=", b"<<", b">>"];
+                let len = if PUNCTUATORS.iter().any(|p| rest.starts_with(p)) {
+                    2
+                } else {
+                    1
+                };
+                (Token::Other, len)
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (Token, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.text.len() {
+            return None;
+        }
+        let (token, len) = self.next_len();
+        let text = &self.remaining()[..len];
+        self.position += len;
+        Some((token, text))
+    }
+}
+
+/// Split the content into logical lines: physical lines joined by line continuations (`\` before a newline).
+/// Each line includes its final newline, if any.
+fn logical_lines(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = content;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut end = 0;
+        let len = loop {
+            match rest[end..].iter().position(|&b| b == b'\n') {
+                Some(newline) => {
+                    end += newline + 1;
+                    let line = rest[..end - 1]
+                        .strip_suffix(b"\r")
+                        .unwrap_or(&rest[..end - 1]);
+                    if !line.ends_with(b"\\") {
+                        break end;
+                    }
+                }
+                None => break rest.len(),
+            }
+        };
+
+        let (line, remaining) = rest.split_at(len);
+        rest = remaining;
+        Some(line)
+    })
+}
+
+/// Replace the line with whitespace, keeping its newlines so that lines and byte offsets are unchanged.
+fn blank(line: &[u8], out: &mut Vec<u8>) {
+    out.extend(line.iter().map(|&b| match b {
+        b'\n' | b'\r' => b,
+        _ => b' ',
+    }));
+}
+
+fn join_continuations(text: &[u8]) -> Vec<u8> {
+    let mut joined = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(backslash) = rest.iter().position(|&b| b == b'\\') {
+        joined.extend_from_slice(&rest[..backslash]);
+        let after = &rest[backslash + 1..];
+        let after = after.strip_prefix(b"\r").unwrap_or(after);
+        rest = match after.strip_prefix(b"\n") {
+            Some(after) => after,
+            None => {
+                joined.push(b'\\');
+                &rest[backslash + 1..]
+            }
+        };
+    }
+    joined.extend_from_slice(rest);
+    joined
+}
+
+/// Replace each comment in the text with a single space, as the C preprocessor does.
+fn strip_comments(text: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(text.len());
+    for (token, text) in Tokens::new(text, false) {
+        match token {
+            Token::Comment => stripped.push(b' '),
+            _ => stripped.extend_from_slice(text),
+        }
+    }
+    stripped
+}
+
+fn is_identifier(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Report the length of the identifier at the start of the text, or `0` if the text doesn't start with one.
+fn identifier_len(text: &[u8]) -> usize {
+    match text.first() {
+        Some(byte) if !byte.is_ascii_digit() => {
+            text.iter().take_while(|&&b| is_identifier(b)).count()
+        }
+        _ => 0,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
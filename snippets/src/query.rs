@@ -0,0 +1,166 @@
+//! Extracts snippets for each capture of a user-supplied [tree-sitter query].
+//!
+//! The prebuilt extractors only extract the [`Target`]s this library supports.
+//! Queries make it possible to extract other constructs, such as SQL strings or test functions,
+//! without implementing a new [`Extractor`]:
+//!
+//! ```
+//! # use snippets::*;
+//! # #[cfg(feature = "lang-java")]
+//! # fn main() -> Result<(), Error> {
+//! use snippets::{language::java, query::QueryExtractor};
+//!
+//! let content = br#"class Repo { String find() { return "SELECT * FROM users"; } }"#;
+//! let query = java::Extractor::query(r#"((string_literal) @sql (#match? @sql "^\"SELECT "))"#)?;
+//!
+//! let parsed = java::Extractor::parse(content)?;
+//! let opts = Options::new(Targets::full(), Kind::Full, Transforms::none()).with_content();
+//! let snippets = query.extract(&parsed, &opts).collect::<Vec<_>>()?;
+//!
+//! assert_eq!(snippets.len(), 1);
+//! assert_eq!(snippets[0].metadata().name(), Some("sql"));
+//! let content = snippets[0].content().expect("content is retained");
+//! assert_eq!(content.as_bytes(), br#""SELECT * FROM users""#);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "lang-java"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Captures as snippets
+//!
+//! Each node captured by the query is extracted as a [`Kind::Full`] snippet,
+//! named (see [`Metadata::name`]) with the name of the capture, without the leading `@`.
+//! Captures don't have a signature or body, so other [`Kind`]s are not extracted;
+//! similarly the [`Target`]s in the [`Options`] don't apply, since the query selects what to extract.
+//! All other options, such as the [`Method`]s and the minimum size of snippets, apply as usual.
+//!
+//! Queries aren't aware of the structure of the language, so captures don't report their enclosing scopes
+//! (see [`Metadata::scope`]); use the query itself to select captures by their ancestors instead.
+//!
+//! Captures whose name starts with an underscore (e.g. `@_name`) are not extracted,
+//! so that they can be used in predicates without producing snippets.
+//!
+//! [tree-sitter query]: https://tree-sitter.github.io/tree-sitter/using-parsers#query-syntax
+//! [`Target`]: crate::Target
+//! [`Kind`]: crate::Kind
+//! [`Kind::Full`]: crate::Kind::Full
+//! [`Metadata::name`]: crate::Metadata::name
+//! [`Metadata::scope`]: crate::Metadata::scope
+
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use tap::Pipe;
+use tree_sitter::{Node, QueryCursor, StreamingIterator};
+
+use crate::impl_prelude::*;
+use crate::language::{methods, snippet_context::SnippetContext};
+use crate::QueryError;
+
+/// An [`Extractor`] whose language can be queried with a user-supplied tree-sitter query.
+///
+/// Implementations of this trait are provided for the languages in [`crate::language`].
+///
+/// [`Extractor`]: crate::Extractor
+pub trait QueryExtractor: LazySnippetExtractor {
+    /// Compile the tree-sitter query for the language of this extractor.
+    ///
+    /// The query is compiled once, and can then be used to extract snippets
+    /// from any number of units of source code parsed by [`LazySnippetExtractor::parse`].
+    fn query(source: &str) -> Result<Query<Self::Language>, ExtractorError>;
+}
+
+/// A compiled tree-sitter query for the language `L`; see the module documentation for details.
+pub struct Query<L> {
+    inner: tree_sitter::Query,
+    names: Vec<String>,
+    language: PhantomData<L>,
+}
+
+impl<L> Query<L> {
+    pub(crate) fn new(
+        grammar: &tree_sitter::Language,
+        source: &str,
+    ) -> Result<Self, ExtractorError> {
+        let inner = tree_sitter::Query::new(grammar, source).map_err(QueryError)?;
+        let names = inner
+            .capture_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        Ok(Self {
+            inner,
+            names,
+            language: PhantomData,
+        })
+    }
+
+    /// The names of the captures in the query, without the leading `@`.
+    pub fn capture_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Lazily extract snippets for each capture of the query in the parsed source code,
+    /// according to the provided options.
+    ///
+    /// The query is run over the whole unit of source code up front,
+    /// but snippets are only extracted as the caller asks for them.
+    /// Snippets are reported in the order in which their captures appear in the source code.
+    pub fn extract<'p>(
+        &'p self,
+        parsed: &'p Parsed<'_, L>,
+        opts: &'p SnippetOptions,
+    ) -> Snippets<'p, L>
+    where
+        L: 'p,
+    {
+        let Some(tree) = parsed.tree() else {
+            return Snippets::empty();
+        };
+        if !opts.kinds().contains(SnippetKind::Full) {
+            return Snippets::empty();
+        }
+        let content = parsed.content();
+        let lines = parsed.lines();
+
+        let names = &self.names;
+        let mut cursor = QueryCursor::new();
+        let captures = cursor
+            .captures(&self.inner, tree.root_node(), content)
+            .map_deref(|(found, index)| found.captures[*index])
+            .filter(|capture| !names[capture.index as usize].starts_with('_'))
+            .map(|capture| (capture.node, names[capture.index as usize].as_str()))
+            .collect::<Vec<_>>();
+
+        captures
+            .into_iter()
+            .filter_map(move |(node, name)| extract(name, node, content, lines, opts))
+            // Each capture extracts a snippet for each method.
+            .flatten_ok()
+            .pipe(Snippets::new)
+    }
+}
+
+#[tracing::instrument(skip_all, fields(%capture, location = %SnippetLocation::from(node.byte_range())))]
+fn extract<L>(
+    capture: &str,
+    node: Node<'_>,
+    content: &[u8],
+    lines: &LineIndex,
+    opts: &SnippetOptions,
+) -> Option<Result<Vec<Snippet<L>>, ExtractorError>> {
+    let context = SnippetContext::new(node, node.byte_range().into(), content);
+    let context = if opts.leading_comments() {
+        context.with_leading_comments(node)
+    } else {
+        context
+    };
+    if !context.meets_minimum(opts) {
+        return None;
+    }
+
+    methods::extract(SnippetKind::Full, Some(capture), &[], &context, lines, opts)
+        .pipe(Ok)
+        .pipe(Some)
+}
@@ -2,6 +2,7 @@
 
 use base64::Engine;
 use sha2::{Digest, Sha256};
+use strum::Display;
 use tap::Pipe;
 
 pub use buffer::Buffer;
@@ -12,15 +13,69 @@ pub mod buffer;
 mod normalize_lines;
 mod normalize_space;
 
-/// Given a buffer, produce a fingerprint of its contents.
+/// Given a buffer, produce a fingerprint of its contents using the default [`FingerprintAlgorithm`].
 pub fn fingerprint(input: impl AsRef<[u8]>) -> Buffer {
-    // Using an iterator here because according to the rough benchmarks it's much faster than cloning into vec.
-    let normalized_line_endings = input.as_ref().iter().copied().convert_crlf_lf();
-    let mut hasher = Sha256::new();
-    for c in normalized_line_endings {
-        hasher.update([c]);
+    FingerprintAlgorithm::default().fingerprint(input)
+}
+
+/// The hashing algorithm used to fingerprint the contents of a buffer.
+///
+/// Fingerprints made with different algorithms never match,
+/// so every party comparing fingerprints (for example a project and a knowledge base)
+/// must use the same algorithm.
+/// SHA-256 is the default, and is the algorithm to use for compatibility with other tooling;
+/// BLAKE3 is much faster, for high-volume pipelines that control both sides of the comparison.
+///
+/// Line endings are normalized before hashing with any algorithm,
+/// so that `\r\n` and `\n` line endings produce the same fingerprint.
+///
+/// ```
+/// # use snippets::text::{fingerprint, FingerprintAlgorithm};
+/// let input = b"int main() {\r\n  return 0;\r\n}";
+/// assert_eq!(FingerprintAlgorithm::Sha256.fingerprint(input), fingerprint(input));
+/// assert_eq!(FingerprintAlgorithm::Sha256.fingerprint(input).as_bytes().len(), 32);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Display)]
+#[strum(serialize_all = "snake_case")]
+#[non_exhaustive]
+pub enum FingerprintAlgorithm {
+    /// Fingerprint with SHA-256.
+    #[default]
+    Sha256,
+
+    /// Fingerprint with BLAKE3, using its default 32 byte output.
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl FingerprintAlgorithm {
+    /// Given a buffer, produce a fingerprint of its contents using this algorithm.
+    pub fn fingerprint(self, input: impl AsRef<[u8]>) -> Buffer {
+        match self {
+            Self::Sha256 => {
+                // Using an iterator here because according to the rough benchmarks it's much faster than cloning into vec.
+                let normalized_line_endings = input.as_ref().iter().copied().convert_crlf_lf();
+                let mut hasher = Sha256::new();
+                for c in normalized_line_endings {
+                    hasher.update([c]);
+                }
+                hasher.finalize().as_slice().pipe(Buffer::new)
+            }
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => {
+                // BLAKE3 is fastest when it hashes long slices at a time,
+                // so hash the runs of bytes between line endings instead of each byte.
+                let mut hasher = blake3::Hasher::new();
+                for line in input.as_ref().split_inclusive(|&b| b == b'\n') {
+                    match line.strip_suffix(b"\r\n") {
+                        Some(line) => hasher.update(line).update(b"\n"),
+                        None => hasher.update(line),
+                    };
+                }
+                hasher.finalize().as_bytes().pipe(Buffer::new)
+            }
+        }
     }
-    hasher.finalize().as_slice().pipe(Buffer::new)
 }
 
 /// Given a buffer, produce a base64 representation of its contents.
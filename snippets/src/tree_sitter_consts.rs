@@ -1,3 +1,44 @@
 pub const NODE_KIND_COMMENT: &str = "comment";
+pub const NODE_KIND_LINE_COMMENT: &str = "line_comment";
+pub const NODE_KIND_BLOCK_COMMENT: &str = "block_comment";
+pub const NODE_KIND_MULTILINE_COMMENT: &str = "multiline_comment";
 pub const NODE_KIND_FUNC_DEF: &str = "function_definition";
+pub const NODE_KIND_FUNC_DECLARATOR: &str = "function_declarator";
 pub const NODE_KIND_OPEN_BRACE: &str = "{";
+
+/// Node kinds for string literals across the supported grammars.
+pub const NODE_KINDS_STRING_LITERAL: &[&str] = &[
+    "string_literal",
+    "raw_string_literal",
+    "concatenated_string",
+    "text_block",
+    "string",
+    "template_string",
+    "encapsed_string",
+    "heredoc",
+    "nowdoc",
+    "line_string_literal",
+    "multi_line_string_literal",
+];
+
+/// Node kinds for character literals across the supported grammars.
+pub const NODE_KINDS_CHAR_LITERAL: &[&str] = &["char_literal", "character_literal"];
+
+/// Node kinds for numeric literals across the supported grammars.
+pub const NODE_KINDS_NUMBER_LITERAL: &[&str] = &[
+    "number_literal",
+    "number",
+    "decimal_integer_literal",
+    "hex_integer_literal",
+    "octal_integer_literal",
+    "binary_integer_literal",
+    "decimal_floating_point_literal",
+    "hex_floating_point_literal",
+    "integer",
+    "float",
+    "integer_literal",
+    "real_literal",
+    "hex_literal",
+    "oct_literal",
+    "bin_literal",
+];
@@ -0,0 +1,402 @@
+//! Walks a directory and extracts snippets from each file in a supported language.
+//!
+//! Files are routed to a language [`crate::Extractor`] by their extension;
+//! only languages enabled via feature flags are extracted, and other files are skipped.
+//! This composes directory walking with extraction, so that consumers
+//! don't each need to reimplement the glue between the two.
+//!
+//! Extension | Language
+//! ---|---
+//! `.c`, `.h` | C99 TC3
+//! `.cc`, `.cpp`, `.cxx`, `.c++`, `.hh`, `.hpp`, `.hxx`, `.h++` | C++ 98
+//! `.java` | Java
+//! `.js`, `.mjs`, `.cjs`, `.jsx` | JavaScript
+//! `.php` | PHP
+//! `.swift` | Swift
+//! `.ts`, `.mts`, `.cts` | TypeScript
+//! `.tsx` | TSX
+//!
+//! Files skipped by the guards configured in the [`Options`]
+//! (see [`Options::with_max_file_bytes`] and [`Options::with_timeout`])
+//! are reported as [`Extracted::Skipped`] rather than stopping the walk.
+
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use getset::Getters;
+use rayon::{prelude::*, ThreadPoolBuilder};
+use walkdir::WalkDir;
+
+// Some of these are unused when no languages are enabled.
+#[allow(unused_imports)]
+use crate::{DynSnippet, Error, Extractor, Options, Skipped, Snippet};
+
+#[cfg(feature = "lang-c99-tc3")]
+use crate::language::c99_tc3;
+#[cfg(feature = "lang-cpp-98")]
+use crate::language::cpp_98;
+#[cfg(feature = "lang-java")]
+use crate::language::java;
+#[cfg(feature = "lang-javascript")]
+use crate::language::javascript;
+#[cfg(feature = "lang-php")]
+use crate::language::php;
+#[cfg(feature = "lang-swift")]
+use crate::language::swift;
+#[cfg(feature = "lang-typescript")]
+use crate::language::{tsx, typescript};
+
+/// Snippets extracted from a single file, in the language to which the file was routed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Extracted {
+    /// Snippets extracted from a C file.
+    #[cfg(feature = "lang-c99-tc3")]
+    C99Tc3(Vec<Snippet<c99_tc3::Language>>),
+
+    /// Snippets extracted from a C++ file.
+    #[cfg(feature = "lang-cpp-98")]
+    Cpp98(Vec<Snippet<cpp_98::Language>>),
+
+    /// Snippets extracted from a Java file.
+    #[cfg(feature = "lang-java")]
+    Java(Vec<Snippet<java::Language>>),
+
+    /// Snippets extracted from a JavaScript file.
+    #[cfg(feature = "lang-javascript")]
+    JavaScript(Vec<Snippet<javascript::Language>>),
+
+    /// Snippets extracted from a PHP file.
+    #[cfg(feature = "lang-php")]
+    Php(Vec<Snippet<php::Language>>),
+
+    /// Snippets extracted from a Swift file.
+    #[cfg(feature = "lang-swift")]
+    Swift(Vec<Snippet<swift::Language>>),
+
+    /// Snippets extracted from a TypeScript file.
+    #[cfg(feature = "lang-typescript")]
+    TypeScript(Vec<Snippet<typescript::Language>>),
+
+    /// Snippets extracted from a TSX file.
+    #[cfg(feature = "lang-typescript")]
+    Tsx(Vec<Snippet<tsx::Language>>),
+
+    /// The file was skipped instead of extracted, for the provided reason.
+    Skipped(Skipped),
+}
+
+impl Extracted {
+    /// Extract snippets from the content, choosing the language by the extension of the path.
+    ///
+    /// Returns `None` if the extension doesn't correspond to an enabled language.
+    /// Content skipped by the guards configured in the options is reported as [`Extracted::Skipped`].
+    pub fn from_path(
+        path: &Path,
+        opts: &Options,
+        content: impl AsRef<[u8]>,
+    ) -> Option<Result<Self, Error>> {
+        Route::from_path(path).map(|route| or_skipped(route.extract(opts, content.as_ref())))
+    }
+
+    /// The number of snippets extracted.
+    pub fn len(&self) -> usize {
+        match *self {
+            #[cfg(feature = "lang-c99-tc3")]
+            Self::C99Tc3(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-cpp-98")]
+            Self::Cpp98(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-java")]
+            Self::Java(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-javascript")]
+            Self::JavaScript(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-php")]
+            Self::Php(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-swift")]
+            Self::Swift(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-typescript")]
+            Self::TypeScript(ref snippets) => snippets.len(),
+            #[cfg(feature = "lang-typescript")]
+            Self::Tsx(ref snippets) => snippets.len(),
+            Self::Skipped(_) => 0,
+        }
+    }
+
+    /// Erase the language of the snippets; see [`Snippet::into_dyn`].
+    pub fn into_dyn(self) -> Vec<DynSnippet> {
+        match self {
+            #[cfg(feature = "lang-c99-tc3")]
+            Self::C99Tc3(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-cpp-98")]
+            Self::Cpp98(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-java")]
+            Self::Java(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-javascript")]
+            Self::JavaScript(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-php")]
+            Self::Php(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-swift")]
+            Self::Swift(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-typescript")]
+            Self::TypeScript(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            #[cfg(feature = "lang-typescript")]
+            Self::Tsx(snippets) => snippets.into_iter().map(Snippet::into_dyn).collect(),
+            Self::Skipped(_) => Vec::new(),
+        }
+    }
+
+    /// Whether no snippets were extracted.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The language extractor to which a file is routed.
+#[derive(Debug, Clone, Copy)]
+enum Route {
+    #[cfg(feature = "lang-c99-tc3")]
+    C99Tc3,
+    #[cfg(feature = "lang-cpp-98")]
+    Cpp98,
+    #[cfg(feature = "lang-java")]
+    Java,
+    #[cfg(feature = "lang-javascript")]
+    JavaScript,
+    #[cfg(feature = "lang-php")]
+    Php,
+    #[cfg(feature = "lang-swift")]
+    Swift,
+    #[cfg(feature = "lang-typescript")]
+    TypeScript,
+    #[cfg(feature = "lang-typescript")]
+    Tsx,
+}
+
+impl Route {
+    /// Choose the language by the extension of the path, if it corresponds to an enabled language.
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            #[cfg(feature = "lang-c99-tc3")]
+            "c" | "h" => Some(Self::C99Tc3),
+            #[cfg(feature = "lang-cpp-98")]
+            "cc" | "cpp" | "cxx" | "c++" | "hh" | "hpp" | "hxx" | "h++" => Some(Self::Cpp98),
+            #[cfg(feature = "lang-java")]
+            "java" => Some(Self::Java),
+            #[cfg(feature = "lang-javascript")]
+            "js" | "mjs" | "cjs" | "jsx" => Some(Self::JavaScript),
+            #[cfg(feature = "lang-php")]
+            "php" => Some(Self::Php),
+            #[cfg(feature = "lang-swift")]
+            "swift" => Some(Self::Swift),
+            #[cfg(feature = "lang-typescript")]
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            #[cfg(feature = "lang-typescript")]
+            "tsx" => Some(Self::Tsx),
+            _ => None,
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn extract(self, opts: &Options, content: &[u8]) -> Result<Extracted, Error> {
+        match self {
+            #[cfg(feature = "lang-c99-tc3")]
+            Self::C99Tc3 => c99_tc3::Extractor::extract(opts, content).map(Extracted::C99Tc3),
+            #[cfg(feature = "lang-cpp-98")]
+            Self::Cpp98 => cpp_98::Extractor::extract(opts, content).map(Extracted::Cpp98),
+            #[cfg(feature = "lang-java")]
+            Self::Java => java::Extractor::extract(opts, content).map(Extracted::Java),
+            #[cfg(feature = "lang-javascript")]
+            Self::JavaScript => {
+                javascript::Extractor::extract(opts, content).map(Extracted::JavaScript)
+            }
+            #[cfg(feature = "lang-php")]
+            Self::Php => php::Extractor::extract(opts, content).map(Extracted::Php),
+            #[cfg(feature = "lang-swift")]
+            Self::Swift => swift::Extractor::extract(opts, content).map(Extracted::Swift),
+            #[cfg(feature = "lang-typescript")]
+            Self::TypeScript => {
+                typescript::Extractor::extract(opts, content).map(Extracted::TypeScript)
+            }
+            #[cfg(feature = "lang-typescript")]
+            Self::Tsx => tsx::Extractor::extract(opts, content).map(Extracted::Tsx),
+        }
+    }
+}
+
+/// A file discovered by a [`Walker`], and the snippets extracted from it.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct Walked {
+    /// The path of the file, relative to the walked directory.
+    path: PathBuf,
+
+    /// The snippets extracted from the file.
+    snippets: Extracted,
+}
+
+impl Walked {
+    /// Consume the instance, returning the path and the snippets.
+    pub fn into_parts(self) -> (PathBuf, Extracted) {
+        (self.path, self.snippets)
+    }
+}
+
+/// Walks a directory, extracting snippets from each file in a supported language in parallel.
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use snippets::{walk::Walker, Error, Options, Target, Kinds, Transforms};
+/// # fn main() -> Result<(), Error> {
+/// let opts = Options::new(Target::Function, Kinds::full(), Transforms::full());
+/// let walked = Walker::new(opts).walk(Path::new("vendor"), |walked| {
+///     let (path, snippets) = walked.into_parts();
+///     println!("{path:?}: {} snippets", snippets.len());
+///     Ok::<_, Error>(())
+/// })?;
+/// println!("extracted snippets from {walked} files");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Walker {
+    opts: Options,
+    parallelism: Option<NonZeroUsize>,
+}
+
+impl Walker {
+    /// Create a walker that extracts snippets with the provided options,
+    /// using the global rayon thread pool.
+    pub fn new(opts: Options) -> Self {
+        Self {
+            opts,
+            parallelism: None,
+        }
+    }
+
+    /// Set the maximum number of files from which snippets are extracted at once.
+    ///
+    /// Files are extracted on a dedicated thread pool of this size.
+    /// If not set, files are extracted on the global rayon thread pool.
+    pub fn parallelism(self, parallelism: NonZeroUsize) -> Self {
+        Self {
+            parallelism: Some(parallelism),
+            ..self
+        }
+    }
+
+    /// Walk the directory, calling `f` with each file in a supported language as its snippets are extracted.
+    /// Returns the number of files from which snippets were extracted.
+    ///
+    /// Symbolic links are not followed, and files in unsupported languages are skipped.
+    /// Files skipped by the guards configured in the options are passed to `f` as [`Extracted::Skipped`],
+    /// but aren't counted in the returned number of files.
+    ///
+    /// Files are extracted in parallel, so `f` is called concurrently and in no particular order.
+    /// The walk stops at the first error, whether encountered while walking, extracting, or returned by `f`,
+    /// and that error is returned; `f` may still be called for files that were being extracted at the time.
+    pub fn walk<F, E>(&self, root: &Path, f: F) -> Result<usize, E>
+    where
+        F: Fn(Walked) -> Result<(), E> + Sync + Send,
+        E: From<Error> + Send,
+    {
+        let walked = AtomicUsize::new(0);
+        let run = || {
+            WalkDir::new(root)
+                .into_iter()
+                .par_bridge()
+                .try_for_each(|entry| {
+                    let entry = entry.map_err(Error::from)?;
+                    if !entry.file_type().is_file() {
+                        return Ok(());
+                    }
+
+                    let Some(snippets) = self.extract_entry(entry.path()) else {
+                        return Ok(());
+                    };
+                    let snippets = snippets.map_err(|err| {
+                        Error::WalkEntry(entry.path().to_path_buf(), Box::new(err))
+                    })?;
+
+                    let path = entry.path().strip_prefix(root).unwrap_or(entry.path());
+                    if !matches!(snippets, Extracted::Skipped(_)) {
+                        walked.fetch_add(1, Ordering::Relaxed);
+                    }
+                    f(Walked {
+                        path: path.to_path_buf(),
+                        snippets,
+                    })
+                })
+        };
+
+        // If the dedicated pool can't be built, fall back to the global pool rather than failing the walk.
+        let pool = self.parallelism.and_then(|parallelism| {
+            ThreadPoolBuilder::new()
+                .num_threads(parallelism.get())
+                .build()
+                .ok()
+        });
+        match pool {
+            Some(pool) => pool.install(run)?,
+            None => run()?,
+        }
+
+        Ok(walked.into_inner())
+    }
+
+    /// Extract snippets from the file, if it is in a supported language.
+    ///
+    /// The file is only read once it is known to be in a supported language,
+    /// and only if it is within the configured maximum size.
+    fn extract_entry(&self, path: &Path) -> Option<Result<Extracted, Error>> {
+        let route = Route::from_path(path)?;
+        let extracted = self
+            .read(path)
+            .and_then(|content| route.extract(&self.opts, &content));
+        Some(or_skipped(extracted))
+    }
+
+    /// Read the file, checking its size against the configured maximum before reading it.
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        if let Some(max_bytes) = self.opts.max_file_bytes() {
+            let bytes = std::fs::metadata(path)?.len();
+            let bytes = usize::try_from(bytes).unwrap_or(usize::MAX);
+            if bytes > max_bytes {
+                return Err(Skipped::TooLarge { bytes, max_bytes }.into());
+            }
+        }
+        Ok(std::fs::read(path)?)
+    }
+}
+
+/// Report content skipped by a guard as [`Extracted::Skipped`] instead of an error.
+fn or_skipped(extracted: Result<Extracted, Error>) -> Result<Extracted, Error> {
+    match extracted {
+        Err(Error::Skipped(skipped)) => Ok(Extracted::Skipped(skipped)),
+        extracted => extracted,
+    }
+}
+
+/// Walk the directory, extracting snippets from each file in a supported language.
+///
+/// This is a convenience for [`Walker::walk`] which collects every file,
+/// sorted by path, into memory.
+pub fn extract_dir(root: &Path, opts: &Options) -> Result<Vec<Walked>, Error> {
+    let walked = std::sync::Mutex::new(Vec::new());
+    Walker::new(*opts).walk(root, |file| {
+        walked
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(file);
+        Ok::<_, Error>(())
+    })?;
+
+    let mut walked = walked
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    walked.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(walked)
+}
@@ -0,0 +1,41 @@
+use snippets::{
+    language::{c99_tc3, java},
+    DynExtractor, DynSnippet, Extractor, Kind, Options, Target, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn dispatch_by_language_name() {
+    crate::tracing::setup();
+
+    let extractors: Vec<Box<dyn DynExtractor>> =
+        vec![Box::new(c99_tc3::Extractor), Box::new(java::Extractor)];
+    let names = extractors
+        .iter()
+        .map(|extractor| extractor.language_display())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["c99_tc3/static", "java/static"]);
+
+    let content = include_str_lf!("language/testdata/java/HelloWorld.java");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let java = extractors
+        .iter()
+        .find(|extractor| extractor.language_name() == "java")
+        .expect("java extractor is configured");
+
+    let extracted = java
+        .extract_dyn(&opts, content.as_bytes())
+        .expect("extract snippets");
+    let expected = java::Extractor::extract(&opts, &content)
+        .expect("extract snippets")
+        .into_iter()
+        .map(DynSnippet::from)
+        .collect::<Vec<_>>();
+
+    assert!(!extracted.is_empty(), "must have extracted snippets");
+    assert_eq!(extracted, expected);
+    assert!(extracted
+        .iter()
+        .all(|snippet| snippet.language() == "java/static"));
+}
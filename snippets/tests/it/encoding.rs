@@ -0,0 +1,81 @@
+use snippets::{
+    encoding::{transcode, SourceEncoding},
+    language::c99_tc3,
+    Extractor, Kind, LazyExtractor, Options, Target, Transform, Transforms,
+};
+
+const CONTENT: &str = "/* Grüße */\nint main() {\n  return 0;\n}\n";
+
+fn utf16le(content: &str) -> Vec<u8> {
+    let mut encoded = vec![0xFF, 0xFE];
+    encoded.extend(content.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    encoded
+}
+
+fn utf16be(content: &str) -> Vec<u8> {
+    let mut encoded = vec![0xFE, 0xFF];
+    encoded.extend(content.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+    encoded
+}
+
+fn latin1(content: &str) -> Vec<u8> {
+    content
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)).expect("content must be latin-1"))
+        .collect()
+}
+
+#[test]
+fn transcode_detects_encoding() {
+    crate::tracing::setup();
+
+    let cases = [
+        (CONTENT.as_bytes().to_vec(), SourceEncoding::Utf8),
+        (utf16le(CONTENT), SourceEncoding::Utf16Le),
+        (utf16be(CONTENT), SourceEncoding::Utf16Be),
+        (latin1(CONTENT), SourceEncoding::Latin1),
+    ];
+    for (content, expected) in cases {
+        let (detected, transcoded) = transcode(&content);
+        assert_eq!(detected, expected);
+        assert_eq!(transcoded.as_ref(), CONTENT.as_bytes(), "{expected}");
+    }
+}
+
+#[test]
+fn extract_transcoded() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transform::Space);
+    let expected = c99_tc3::Extractor::extract(&opts, CONTENT).expect("extract snippets");
+    assert!(!expected.is_empty(), "must have extracted snippets");
+
+    let opts = opts.with_transcoding();
+    for content in [utf16le(CONTENT), utf16be(CONTENT), latin1(CONTENT)] {
+        let extract = c99_tc3::Extractor::extract(&opts, &content).expect("extract snippets");
+        assert_eq!(extract, expected);
+    }
+}
+
+#[test]
+fn extract_untranscoded_utf16() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let expected = c99_tc3::Extractor::extract(&opts, CONTENT).expect("extract snippets");
+
+    let extract = c99_tc3::Extractor::extract(&opts, utf16le(CONTENT)).expect("extract snippets");
+    assert_ne!(extract, expected);
+}
+
+#[test]
+fn prepare_for_lazy_extraction() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none()).with_transcoding();
+    let content = utf16le(CONTENT);
+    let prepared = opts.prepare(&content);
+    let parsed = c99_tc3::Extractor::parse(&prepared).expect("parse content");
+
+    assert_eq!(parsed.content(), CONTENT.as_bytes());
+}
@@ -0,0 +1,66 @@
+use snippets::{
+    language::c99_tc3,
+    text::{fingerprint, FingerprintAlgorithm},
+    Extractor, Kinds, Options, Target, Transforms,
+};
+
+const CONTENT: &str = r#"int add(int a, int b) {
+  return a + b;
+}
+"#;
+
+#[test]
+fn default_is_sha256() {
+    crate::tracing::setup();
+
+    let input = CONTENT.as_bytes();
+    assert_eq!(
+        FingerprintAlgorithm::default(),
+        FingerprintAlgorithm::Sha256
+    );
+    assert_eq!(
+        FingerprintAlgorithm::Sha256.fingerprint(input),
+        fingerprint(input)
+    );
+}
+
+#[test]
+fn blake3_normalizes_line_endings() {
+    crate::tracing::setup();
+
+    let lf = CONTENT;
+    let crlf = CONTENT.replace('\n', "\r\n");
+    assert_eq!(
+        FingerprintAlgorithm::Blake3.fingerprint(lf),
+        FingerprintAlgorithm::Blake3.fingerprint(crlf),
+    );
+
+    // A lone carriage return is not a line ending.
+    assert_ne!(
+        FingerprintAlgorithm::Blake3.fingerprint("a\rb"),
+        FingerprintAlgorithm::Blake3.fingerprint("a\nb"),
+    );
+}
+
+#[test]
+fn extract_with_blake3() {
+    crate::tracing::setup();
+
+    let sha256 = Options::new(Target::Function, Kinds::full(), Transforms::full()).with_content();
+    let blake3 = sha256.with_fingerprint_algorithm(FingerprintAlgorithm::Blake3);
+
+    let expected = c99_tc3::Extractor::extract(&sha256, CONTENT).expect("extract snippets");
+    let extracted = c99_tc3::Extractor::extract(&blake3, CONTENT).expect("extract snippets");
+    assert_eq!(extracted.len(), expected.len());
+
+    for (snippet, expected) in extracted.iter().zip(&expected) {
+        assert_eq!(snippet.metadata(), expected.metadata());
+        assert_eq!(snippet.content(), expected.content());
+        assert_ne!(snippet.fingerprint(), expected.fingerprint());
+        assert_eq!(
+            snippet.fingerprint(),
+            &FingerprintAlgorithm::Blake3
+                .fingerprint(snippet.content().expect("content is retained").as_bytes()),
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use snippets::{
+    language::{c99_tc3, java, javascript, typescript},
+    preprocess::Preprocessor,
+    Error, Extractor, Kind, LazyExtractor, Options, Skipped, Target, Transforms,
+};
+
+/// A single line of minified JavaScript, large enough that parsing it takes a while.
+fn minified() -> String {
+    (0..20_000)
+        .map(|i| format!("function f{i}(a,b){{return a?b+{i}:[a,b,{{c:a}}];}}"))
+        .collect()
+}
+
+fn too_large(err: Error) -> (usize, usize) {
+    match err {
+        Error::Skipped(Skipped::TooLarge { bytes, max_bytes }) => (bytes, max_bytes),
+        err => panic!("expected content to be too large, got: {err:?}"),
+    }
+}
+
+#[test]
+fn max_file_bytes() {
+    crate::tracing::setup();
+
+    let content = "int main() { return 0; }";
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+
+    let extract = c99_tc3::Extractor::extract(&opts.with_max_file_bytes(content.len()), content)
+        .expect("content within the maximum is extracted");
+    assert_eq!(extract.len(), 1);
+
+    let opts = opts.with_max_file_bytes(content.len() - 1);
+    let err = c99_tc3::Extractor::extract(&opts, content).expect_err("content is too large");
+    assert_eq!(too_large(err), (content.len(), content.len() - 1));
+
+    let err = java::Extractor::extract(&opts, content).expect_err("content is too large");
+    assert_eq!(too_large(err), (content.len(), content.len() - 1));
+
+    let err = Preprocessor::new()
+        .extract::<c99_tc3::Extractor>(&opts, content)
+        .expect_err("content is too large");
+    assert_eq!(too_large(err), (content.len(), content.len() - 1));
+}
+
+#[test]
+fn max_file_bytes_lazy() {
+    crate::tracing::setup();
+
+    let content = b"function add(a, b) { return a + b; }";
+    let opts = Options::default().with_max_file_bytes(8);
+
+    let err = javascript::Extractor::parse_guarded(&opts, content)
+        .err()
+        .expect("content is too large");
+    assert_eq!(too_large(err), (content.len(), 8));
+
+    // Parsing without the guards is unaffected by them.
+    assert!(javascript::Extractor::parse(content).is_ok());
+}
+
+#[test]
+fn timeout() {
+    crate::tracing::setup();
+
+    let content = minified();
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+
+    let timeout = Duration::from_micros(1);
+    let err = javascript::Extractor::extract(&opts.with_timeout(timeout), &content)
+        .expect_err("parsing times out");
+    assert!(
+        matches!(err, Error::Skipped(Skipped::TimedOut { timeout: reported }) if reported == timeout),
+        "expected parsing to time out, got: {err:?}"
+    );
+
+    // The parser is reused for the next parse, which must not inherit the timeout.
+    let extract = typescript::Extractor::extract(&opts, &content).expect("extract snippets");
+    assert_eq!(extract.len(), 20_000);
+    let extract =
+        javascript::Extractor::extract(&opts.with_timeout(Duration::from_secs(600)), &content)
+            .expect("parsing completes within the timeout");
+    assert_eq!(extract.len(), 20_000);
+}
@@ -1,2 +1,8 @@
 pub mod c99_tc3;
 pub mod cpp_98;
+pub mod java;
+pub mod javascript;
+pub mod php;
+pub mod swift;
+pub mod tsx;
+pub mod typescript;
@@ -151,6 +151,102 @@ fn full_space_hello_world() {
     assert_eq!(extract, expected);
 }
 
+#[test]
+fn full_space_hello_world_crlf_lf() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Space);
+    let span_lf = Location::from(21..74);
+    let span_crlf = Location::from(24..80);
+
+    // This test runs on both Windows and other platforms, so it normalizes
+    // to \n regardless of the actual example file and then expands that back to \r\n.
+    //
+    // On non-Windows the first replace will just effectively do nothing.
+    let content_lf = include_str!("testdata/c99_tc3/hello_world.c").replace("\r\n", "\n");
+    let content_crlf = content_lf.replace('\n', "\r\n");
+
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract_lf = c99_tc3::Extractor::extract(&opts, &content_lf).unwrap();
+    let extract_crlf = c99_tc3::Extractor::extract(&opts, &content_crlf).unwrap();
+
+    // Both line endings are whitespace, so they collapse to the same normalized text.
+    let expected_content = br#"int main() { printf("hello world\n"); return 0; }"#;
+    let expected_lf = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span_lf),
+        expected_content,
+    )];
+    let expected_crlf = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span_crlf),
+        expected_content,
+    )];
+
+    assert_eq!(extract_lf.clone(), expected_lf);
+    assert_eq!(extract_crlf.clone(), expected_crlf);
+
+    let fingerprints_lf = extract_lf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    let fingerprints_crlf = extract_crlf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    assert_eq!(fingerprints_lf, fingerprints_crlf);
+}
+
+#[test]
+fn full_space_unicode_whitespace() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Space);
+
+    // Tabs, vertical tabs, form feeds, and non-ASCII whitespace (here a no-break space)
+    // are all `White_Space=yes`, and so are normalized like any other space.
+    let content = "int main()\t{\u{a0}\x0b\x0creturn 0;\n}\n";
+    let span = Location::from(0..content.len() - 1);
+
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        b"int main() { return 0; }",
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_comment_space_hello_world_comment() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transforms = Transform::Comment | Transform::Space;
+    let span = Location::from(84..224);
+
+    let content = include_str_lf!("testdata/c99_tc3/hello_world_comment.c");
+    let opts = Options::new(Target::Function, kind, transforms).disable_raw();
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("must set up parser");
+
+    // Each transform is applied on its own, producing one snippet per transform.
+    // Applying both at once is `Transform::Code`.
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, Method::Normalized(Transform::Comment), span),
+            b"int  main  () ",
+        ),
+        Snippet::from(
+            Metadata::new(kind, Method::Normalized(Transform::Space), span),
+            br#"int /* the classic starting point */ main /* who needs params? */ (/* no params, because let's keep this "simple" */) // and end with a bang"#,
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
 #[test]
 fn full_raw_hello_world_comment() {
     crate::tracing::setup();
@@ -402,6 +498,61 @@ fn body_code_hello_world_comment() {
     assert_eq!(extract, expected);
 }
 
+#[test]
+fn full_literal_hello_world_comment() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Literal);
+    let span = Location::from(84..1336);
+
+    let content = include_str_lf!("testdata/c99_tc3/hello_world_comment.c");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let expected_content = r#"int main () { printf("" ); return 0 ; }"#;
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        expected_content.as_bytes(),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn literal_ignores_changed_constants() {
+    crate::tracing::setup();
+
+    let original = r#"int check(char *s) { return s[0] == 'y' ? printf("yes %d", 1) : 10; }"#;
+    let modified = r#"int check(char *s) { return s[0] == 'n' ? printf("no: %d\n", 2) : 0x20; }"#;
+
+    let opts = Options::new(Target::Function, Kind::Full, Transform::Literal)
+        .disable_raw()
+        .with_content();
+    let original = c99_tc3::Extractor::extract(&opts, original).expect("must set up parser");
+    let modified = c99_tc3::Extractor::extract(&opts, modified).expect("must set up parser");
+
+    let expected_content = br#"int check(char *s) { return s[0] == '' ? printf("", 0) : 0; }"#;
+    assert_eq!(original.len(), 1);
+    assert_eq!(modified.len(), 1);
+    assert_eq!(
+        original[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(
+        modified[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(original[0].fingerprint(), modified[0].fingerprint());
+}
+
 #[test]
 fn smoke_test() {
     crate::tracing::setup();
@@ -415,3 +566,291 @@ fn smoke_test() {
     let extract = c99_tc3::Extractor::extract(&opts, content).expect("extract snippets");
     assert!(!extract.is_empty(), "must have extracted snippets");
 }
+
+#[test]
+fn macro_full_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+
+    // The include guard has no value, so it isn't extracted.
+    let content = include_str_lf!("testdata/c99_tc3/macros.h");
+    let opts = Options::new(Target::Macro, kind, transform);
+    let extract = c99_tc3::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = [35..54, 55..84, 85..175]
+        .into_iter()
+        .map(Location::from)
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn macro_signature_and_body_raw() {
+    crate::tracing::setup();
+
+    let transform = None;
+
+    let content = include_str_lf!("testdata/c99_tc3/macros.h");
+    let opts = Options::new(Target::Macro, Kind::Signature | Kind::Body, transform);
+    let extract = c99_tc3::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = [
+        (Kind::Signature, 35..50, "#define MAX_LEN"),
+        (Kind::Body, 51..54, "256"),
+        (Kind::Signature, 55..72, "#define SQUARE(x)"),
+        (Kind::Body, 73..84, "((x) * (x))"),
+        (Kind::Signature, 85..103, "#define SWAP(a, b)"),
+        (
+            Kind::Body,
+            104..175,
+            "do { \\\n    int t = (a); \\\n    (a) = (b); \\\n    (b) = t; \\\n  } while (0)",
+        ),
+    ]
+    .into_iter()
+    .map(|(kind, span, text)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), Location::from(span)),
+            text.as_bytes(),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn macro_body_code() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = Some(Transform::Code);
+    let span = Location::from(104..175);
+
+    let content = include_str_lf!("testdata/c99_tc3/macros.h");
+    let opts = Options::new(Target::Macro, kind, transform).disable_raw();
+    let extract = c99_tc3::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        b"do { \\ int t = (a); \\ (a) = (b); \\ (b) = t; \\ } while (0)",
+    );
+
+    assert_eq!(extract.last(), Some(&expected));
+}
+
+#[test]
+fn lines_hello_world() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/c99_tc3/hello_world.c");
+    let opts = Options::new(Target::Function, Kinds::full(), Transforms::none());
+    let extract = c99_tc3::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let lines = extract
+        .iter()
+        .map(|snippet| {
+            (
+                snippet.metadata().kind(),
+                snippet.lines().map(|lines| lines.to_string()),
+            )
+        })
+        .collect_vec();
+    let expected = vec![
+        (Kind::Signature, Some(String::from("4:1-4:10"))),
+        (Kind::Body, Some(String::from("4:12-7:1"))),
+        (Kind::Full, Some(String::from("4:1-7:1"))),
+    ];
+
+    assert_eq!(lines, expected);
+}
+
+const SMALL_AND_LARGE: &str = r#"int get(void) { return x; }
+
+int sum(int a, int b) {
+  // add them
+  return a + b;
+}
+"#;
+
+#[test]
+fn min_bytes_skips_small_functions() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none()).with_min_bytes(28);
+    let extract = c99_tc3::Extractor::extract(&opts, SMALL_AND_LARGE).expect("must set up parser");
+
+    let span = Location::from(29..84);
+    let expected = vec![Snippet::from(
+        Metadata::new(Kind::Full, Method::Raw, span),
+        span.extract_from(SMALL_AND_LARGE.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn min_tokens_skips_small_functions() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none()).with_min_tokens(16);
+    let extract = c99_tc3::Extractor::extract(&opts, SMALL_AND_LARGE).expect("must set up parser");
+
+    let span = Location::from(29..84);
+    let expected = vec![Snippet::from(
+        Metadata::new(Kind::Full, Method::Raw, span),
+        span.extract_from(SMALL_AND_LARGE.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn min_tokens_does_not_count_comments() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none()).with_min_tokens(17);
+    let extract = c99_tc3::Extractor::extract(&opts, SMALL_AND_LARGE).expect("must set up parser");
+
+    assert_eq!(extract, Vec::new());
+}
+
+#[test]
+fn min_size_applies_per_kind() {
+    crate::tracing::setup();
+
+    // The signature of `sum` is 21 bytes, while its body and full snippets are larger;
+    // all snippets for `get` are smaller.
+    let opts = Options::new(Target::Function, Kinds::full(), Transforms::full()).with_min_bytes(28);
+    let extract = c99_tc3::Extractor::extract(&opts, SMALL_AND_LARGE).expect("must set up parser");
+
+    let kinds = extract
+        .iter()
+        .map(|snippet| snippet.metadata().kind())
+        .dedup()
+        .collect_vec();
+    assert_eq!(kinds, vec![Kind::Body, Kind::Full]);
+}
+
+#[test]
+fn names() {
+    crate::tracing::setup();
+
+    let content = r#"#define SQUARE(x) ((x) * (x))
+
+static char *(copy)(const char *s) {
+  return strdup(s);
+}
+
+int main() {}
+"#;
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect_vec();
+    assert_eq!(names, vec![Some("SQUARE"), Some("copy"), Some("main")]);
+}
+
+const DOCUMENTED: &str = r#"/* Copyright (c) Example Authors. */
+
+int x; // not a doc comment
+/**
+ * Adds two numbers.
+ */
+// Overflow is undefined.
+int sum(int a, int b) {
+  return a + b;
+}
+"#;
+
+#[test]
+fn leading_comments_full_raw() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kinds::full(), Transforms::none())
+        .with_leading_comments()
+        .with_content();
+    let extract = c99_tc3::Extractor::extract(&opts, DOCUMENTED).expect("must set up parser");
+
+    let full = extract
+        .iter()
+        .find(|snippet| snippet.metadata().kind() == Kind::Full)
+        .expect("must extract full snippet");
+    let span = Location::from(66..162);
+    assert_eq!(full.metadata().location(), span);
+    assert_eq!(
+        full.content().expect("content is retained").as_bytes(),
+        b"/**\n * Adds two numbers.\n */\n// Overflow is undefined.\nint sum(int a, int b) {\n  return a + b;\n}",
+    );
+
+    // Other kinds never include leading comments.
+    let signature = extract
+        .iter()
+        .find(|snippet| snippet.metadata().kind() == Kind::Signature)
+        .expect("must extract signature snippet");
+    assert_eq!(
+        signature.content().expect("content is retained").as_bytes(),
+        b"int sum(int a, int b)"
+    );
+}
+
+#[test]
+fn leading_comments_full_code_normalized() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transform::Code)
+        .disable_raw()
+        .with_leading_comments();
+    let extract = c99_tc3::Extractor::extract(&opts, DOCUMENTED).expect("must set up parser");
+
+    // Normalizing code removes the leading comments;
+    // the space that separated them from the function is collapsed like any other space.
+    let span = Location::from(66..162);
+    let expected = vec![Snippet::from(
+        Metadata::new(Kind::Full, Method::Normalized(Transform::Code), span),
+        b" int sum(int a, int b) { return a + b; }",
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn scope_nested_functions() {
+    crate::tracing::setup();
+
+    // GCC's nested functions are a compiler extension, but they do parse.
+    let content = r#"int outer(int x) {
+  int inner(int y) { return y * 2; }
+  return inner(x);
+}
+"#;
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), meta.depth(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("outer"), 0, String::new()),
+        (Some("inner"), 1, String::from("function outer")),
+    ];
+
+    assert_eq!(scopes, expected);
+}
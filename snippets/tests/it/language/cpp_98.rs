@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use snippets::{
     language::cpp_98, Extractor, Kind, Kinds, Location, Metadata, Options, Snippet, Target,
     Targets, Transform, Transforms,
@@ -64,6 +65,42 @@ fn functions_in_namespaces_full_comment_normalized() {
     assert_eq!(extract, expected);
 }
 
+#[test]
+fn functions_in_namespaces_full_space_crlf_lf() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Space);
+    let span_lf = Location::from(99..206);
+    let span_crlf = Location::from(107..218);
+
+    // This test runs on both Windows and other platforms, so it normalizes
+    // to \n regardless of the actual example file and then expands that back to \r\n.
+    //
+    // On non-Windows the first replace will just effectively do nothing.
+    let content_lf =
+        include_str!("testdata/cpp_98/bare_function_in_namespace.cc").replace("\r\n", "\n");
+    let content_crlf = content_lf.replace('\n', "\r\n");
+
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract_lf = cpp_98::Extractor::extract(&opts, &content_lf).expect("extract snippets");
+    let extract_crlf = cpp_98::Extractor::extract(&opts, &content_crlf).expect("extract snippets");
+
+    let expected_content =
+        br#"int main(/* A comment */) { std::cout << "Hello World!"; return 0; // another comment }"#;
+    let expected_lf = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span_lf),
+        expected_content,
+    )];
+    let expected_crlf = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span_crlf),
+        expected_content,
+    )];
+
+    assert_eq!(extract_lf, expected_lf);
+    assert_eq!(extract_crlf, expected_crlf);
+}
+
 #[test]
 fn function_in_namespace_signature() {
     crate::tracing::setup();
@@ -256,3 +293,383 @@ fn function_in_class_signature_code() {
 
     assert_eq!(extract, expected);
 }
+
+#[test]
+fn class_full_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let point_span = Location::from(57..97);
+    let shape_span = Location::from(102..239);
+
+    let content = include_str_lf!("testdata/cpp_98/classes.cc");
+    let opts = Options::new(Target::Class, kind, transform);
+
+    // The forward declaration has no body, so it isn't extracted.
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), point_span),
+            point_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), shape_span),
+            shape_span.extract_from(content.as_bytes()),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn class_signature_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let point_span = Location::from(57..69);
+    let shape_span = Location::from(102..128);
+
+    let content = include_str_lf!("testdata/cpp_98/classes.cc");
+    let opts = Options::new(Target::Class, kind, transform);
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), point_span),
+            b"struct Point",
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), shape_span),
+            b"class Shape : public Named",
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn class_body_code() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = Some(Transform::Code);
+    let point_span = Location::from(70..97);
+    let shape_span = Location::from(131..239);
+
+    let content = include_str_lf!("testdata/cpp_98/classes.cc");
+    let opts = Options::new(Target::Class, kind, transform).disable_raw();
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), point_span),
+            b"{ int x; int y; }",
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), shape_span),
+            b"{ public: virtual ~Shape() {} virtual double area() const = 0; }",
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn members_full_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+
+    // Constructors, an operator, a function template, and out-of-class definitions.
+    let spans = [
+        Location::from(96..136),
+        Location::from(141..188),
+        Location::from(194..278),
+        Location::from(313..387),
+        Location::from(391..424),
+        Location::from(428..496),
+    ];
+
+    let content = include_str_lf!("testdata/cpp_98/members.cc");
+    let opts = Options::new(Target::Function, kind, transform);
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn members_signature_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+
+    let content = include_str_lf!("testdata/cpp_98/members.cc");
+    let opts = Options::new(Target::Function, kind, transform);
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = [
+        (96..133, "explicit Box(T value) : value_(value)"),
+        (141..185, "Box(const Box& other) : value_(other.value_)"),
+        (194..233, "bool operator==(const Box& other) const"),
+        (313..355, "template <typename T>\n  T max_of(T a, T b)"),
+        (391..421, "Counter::Counter() : count_(0)"),
+        (428..458, "Counter& Counter::operator++()"),
+    ]
+    .into_iter()
+    .map(|(span, text)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), Location::from(span)),
+            text.as_bytes(),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn class_template_raw() {
+    crate::tracing::setup();
+
+    let transform = None;
+    let signature_span = Location::from(46..79);
+    let body_span = Location::from(80..308);
+    let full_span = Location::from(46..309);
+
+    let content = include_str_lf!("testdata/cpp_98/members.cc");
+    let opts = Options::new(Target::Class, Kinds::default(), transform);
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(Kind::Signature, transform.into(), signature_span),
+            b"template <typename T>\n  class Box",
+        ),
+        Snippet::from(
+            Metadata::new(Kind::Body, transform.into(), body_span),
+            body_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(Kind::Full, transform.into(), full_span),
+            full_span.extract_from(content.as_bytes()),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn macro_full_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let span = Location::from(20..49);
+
+    let content = "#include <iostream>\n#define SQUARE(x) ((x) * (x))\n";
+    let opts = Options::new(Target::Macro, kind, transform);
+    let extract = cpp_98::Extractor::extract(&opts, content).expect("extract snippets");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        b"#define SQUARE(x) ((x) * (x))",
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn names_members() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/cpp_98/members.cc");
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = cpp_98::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect::<Vec<_>>();
+    let expected = vec![
+        Some("Box"),
+        Some("Box"),
+        Some("Box"),
+        Some("operator=="),
+        Some("max_of"),
+        Some("Counter::Counter"),
+        Some("Counter::operator++"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn names_declarators() {
+    crate::tracing::setup();
+
+    let content = r#"struct Flag {
+  operator bool() const { return set; }
+  bool set;
+};
+Flag::operator int() const { return set; }
+int& counter() { static int n; return n; }
+struct { int x; } point;
+#define VERSION "1.0"
+"#;
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = cpp_98::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect::<Vec<_>>();
+    let expected = vec![
+        Some("Flag"),
+        Some("operator bool"),
+        Some("Flag::operator int"),
+        Some("counter"),
+        None,
+        Some("VERSION"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn templates_signature_raw() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+
+    let content = include_str_lf!("testdata/cpp_98/templates.cc");
+    let opts = Options::new(Target::Function, kind, transform);
+
+    let extract = cpp_98::Extractor::extract(&opts, &content).expect("extract snippets");
+    let expected = [
+        (65..185, "template <typename T, typename Compare = std::less<T> >\n  const T& pick(const T& a, const T& b, Compare cmp = Compare())"),
+        (225..307, "template <>\n  const int& pick<int>(const int& a, const int& b, std::less<int> cmp)"),
+        (343..420, "void sort(int* begin, int* end, bool descending = false, int (*key)(int) = 0)"),
+        (509..598, "template <typename U>\n    Range(const Range<U>& other) : lo_(other.lo()), hi_(other.hi())"),
+        (607..647, "bool operator<(const Range& other) const"),
+        (681..718, "bool operator()(const T& value) const"),
+        (814..860, "template <typename T>\n  T Range<T>::lo() const"),
+        (886..976, "template <typename T>\n  std::ostream& operator<<(std::ostream& out, const Range<T>& range)"),
+    ]
+    .into_iter()
+    .map(|(span, text)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), Location::from(span)),
+            text.as_bytes(),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn names_templates() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/cpp_98/templates.cc");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = cpp_98::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect::<Vec<_>>();
+    let expected = vec![
+        Some("pick"),
+        Some("pick<int>"),
+        Some("sort"),
+        Some("Range"),
+        Some("operator<"),
+        Some("operator()"),
+        Some("Range<T>::lo"),
+        Some("operator<<"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn scope_nested() {
+    crate::tracing::setup();
+
+    let content = r#"namespace net {
+namespace {
+int helper() { return 1; }
+}
+
+class Socket {
+ public:
+  int send(int n) {
+    auto make = []() {
+      struct Local { int f() { return 0; } };
+      return Local();
+    };
+    return make().f() + n;
+  }
+};
+
+template <typename T>
+struct Buffer {
+  T get() { return value; }
+  T value;
+};
+}
+
+int main() { return 0; }
+"#;
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = cpp_98::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), meta.depth(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("helper"), 2, "namespace net > anonymous namespace"),
+        (Some("Socket"), 1, "namespace net"),
+        (Some("send"), 2, "namespace net > class Socket"),
+        (
+            Some("Local"),
+            4,
+            "namespace net > class Socket > function send > anonymous function",
+        ),
+        (
+            Some("f"),
+            5,
+            "namespace net > class Socket > function send > anonymous function > class Local",
+        ),
+        (Some("Buffer"), 1, "namespace net"),
+        (Some("get"), 2, "namespace net > class Buffer"),
+        (Some("main"), 0, ""),
+    ]
+    .into_iter()
+    .map(|(name, depth, scope)| (name, depth, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
@@ -0,0 +1,521 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::java, Extractor, Kind, Kinds, Location, Metadata, Method, Options, Snippet, Target,
+    Targets, Transform, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_hello_world() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let span = Location::from(46..135);
+
+    let content = include_str_lf!("testdata/java/HelloWorld.java");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = java::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, Method::from(transform), span),
+        span.extract_from(content.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_raw_hello_world_crlf_lf() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let span_lf = Location::from(46..135);
+    let span_crlf = Location::from(49..140);
+
+    // This test runs on both Windows and other platforms, so it normalizes
+    // to \n regardless of the actual example file and then expands that back to \r\n.
+    //
+    // On non-Windows the first replace will just effectively do nothing.
+    let content_lf = include_str!("testdata/java/HelloWorld.java").replace("\r\n", "\n");
+    let content_crlf = content_lf.replace('\n', "\r\n");
+
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract_lf = java::Extractor::extract(&opts, &content_lf).unwrap();
+    let extract_crlf = java::Extractor::extract(&opts, &content_crlf).unwrap();
+
+    // Even though the fingerprints themselves are normalized, they'll still be at different byte offsets.
+    let expected_lf = vec![Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_lf),
+        span_lf.extract_from(content_lf.as_bytes()),
+    )];
+    let expected_crlf = vec![Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_crlf),
+        span_crlf.extract_from(content_crlf.as_bytes()),
+    )];
+
+    assert_eq!(extract_lf.clone(), expected_lf);
+    assert_eq!(extract_crlf.clone(), expected_crlf);
+
+    let fingerprints_lf = extract_lf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    let fingerprints_crlf = extract_crlf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    assert_eq!(fingerprints_lf, fingerprints_crlf);
+}
+
+#[test]
+fn signature_raw_hello_world() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let span = Location::from(46..84);
+
+    let content = include_str_lf!("testdata/java/HelloWorld.java");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = java::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        span.extract_from(content.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_hello_world() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    let span = Location::from(85..135);
+
+    let content = include_str_lf!("testdata/java/HelloWorld.java");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = java::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        span.extract_from(content.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_space_hello_world() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Space);
+    let span = Location::from(46..135);
+
+    let content = include_str_lf!("testdata/java/HelloWorld.java");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = java::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        br#"public static void main(String[] args) { System.out.println("hello world"); }"#,
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_raw_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let constructor_span = Location::from(146..259);
+    let method_span = Location::from(265..587);
+    let interface_method_span = Location::from(613..638);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Function, kind, transform);
+    let extract = java::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), constructor_span),
+            constructor_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), method_span),
+            method_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), interface_method_span),
+            interface_method_span.extract_from(content.as_bytes()),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_raw_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let constructor_span = Location::from(146..177);
+    let method_span = Location::from(265..377);
+    let interface_method_span = Location::from(613..638);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Function, kind, transform);
+    let extract = java::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), constructor_span),
+            constructor_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), method_span),
+            method_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), interface_method_span),
+            interface_method_span.extract_from(content.as_bytes()),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    let constructor_span = Location::from(178..259);
+    let method_span = Location::from(378..587);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Function, kind, transform);
+    let extract = java::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    // The interface method has no body, so it produces no body snippet.
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), constructor_span),
+            constructor_span.extract_from(content.as_bytes()),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), method_span),
+            method_span.extract_from(content.as_bytes()),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_comment_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Comment);
+    let constructor_span = Location::from(146..259);
+    let method_span = Location::from(265..587);
+    let interface_method_span = Location::from(613..638);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = java::Extractor::extract(&opts, content).expect("extract snippets");
+
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), constructor_span),
+            r#"public Greeter(String greeting) {
+        
+        this.greeting = greeting;
+    }"#
+            .as_bytes(),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), method_span),
+            r#"@Override
+    @SuppressWarnings({"unchecked", "rawtypes"})
+    public String greet(List names)  {
+        StringBuilder out = new StringBuilder();
+        for (Object name : names) {
+            out.append(greeting).append(", ").append(name); 
+        }
+        return out.toString();
+    }"#
+            .as_bytes(),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), interface_method_span),
+            r#"String greet(List names);"#.as_bytes(),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_code_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = Some(Transform::Code);
+    let constructor_span = Location::from(146..177);
+    let method_span = Location::from(265..377);
+    let interface_method_span = Location::from(613..638);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = java::Extractor::extract(&opts, content).expect("extract snippets");
+
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), constructor_span),
+            r#"public Greeter(String greeting)"#.as_bytes(),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), method_span),
+            r#"@Override @SuppressWarnings({"unchecked", "rawtypes"}) public String greet(List names) "#
+                .as_bytes(),
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), interface_method_span),
+            r#"String greet(List names);"#.as_bytes(),
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_literal_changed_constants() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Literal);
+
+    let original = r#"class A { long f() { return g("a", 'b', 1L, 0x2, 0b11, 4.5f) + """
+        block
+        """.length(); } }"#;
+    let modified = r#"class A { long f() { return g("c", 'd', 7L, 0x8, 0b1, 9.5f) + """
+        changed
+        """.length(); } }"#;
+
+    let opts = Options::new(Target::Function, kind, transform)
+        .disable_raw()
+        .with_content();
+    let original = java::Extractor::extract(&opts, original).expect("extract snippets");
+    let modified = java::Extractor::extract(&opts, modified).expect("extract snippets");
+
+    let expected_content = br#"long f() { return g("", '', 0, 0, 0, 0) + "".length(); }"#;
+    assert_eq!(original.len(), 1);
+    assert_eq!(modified.len(), 1);
+    assert_eq!(
+        original[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(original[0].fingerprint(), modified[0].fingerprint());
+}
+
+#[test]
+fn class_signature_raw_greeter() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let class_span = Location::from(66..103);
+    let interface_span = Location::from(591..606);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Class, kind, transform);
+    let extract = java::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = vec![
+        Snippet::from(
+            Metadata::new(kind, transform.into(), class_span),
+            b"public class Greeter implements Named",
+        ),
+        Snippet::from(
+            Metadata::new(kind, transform.into(), interface_span),
+            b"interface Named",
+        ),
+    ];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn class_full_and_body_raw_greeter() {
+    crate::tracing::setup();
+
+    let transform = None;
+    let class_full_span = Location::from(66..589);
+    let class_body_span = Location::from(104..589);
+    let interface_full_span = Location::from(591..640);
+    let interface_body_span = Location::from(607..640);
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Target::Class, Kind::Full | Kind::Body, transform);
+    let extract = java::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let expected = [
+        (Kind::Body, class_body_span),
+        (Kind::Full, class_full_span),
+        (Kind::Body, interface_body_span),
+        (Kind::Full, interface_full_span),
+    ]
+    .into_iter()
+    .map(|(kind, span)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), span),
+            span.extract_from(content.as_bytes()),
+        )
+    })
+    .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/java/smoke_test.java");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = java::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
+
+#[test]
+fn names_greeter() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/java/Greeter.java");
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = java::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect_vec();
+    let expected = vec![
+        Some("Greeter"),
+        Some("Greeter"),
+        Some("greet"),
+        Some("Named"),
+        Some("greet"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn leading_comments_javadoc() {
+    crate::tracing::setup();
+
+    let content = r#"/** A greeter. */
+@Deprecated
+class Greeter {
+  int count; // not a doc comment
+  /**
+   * Greets the world.
+   */
+  @Override
+  public void greet() {}
+}
+"#;
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none())
+        .with_leading_comments()
+        .with_content();
+    let extract = java::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let snippets = extract
+        .iter()
+        .map(|snippet| {
+            let text =
+                String::from_utf8_lossy(snippet.content().expect("content is retained").as_bytes())
+                    .into_owned();
+            (snippet.metadata().name(), text)
+        })
+        .collect_vec();
+    let expected = vec![
+        (Some("Greeter"), content.trim_end().to_string()),
+        (
+            Some("greet"),
+            "/**\n   * Greets the world.\n   */\n  @Override\n  public void greet() {}".to_string(),
+        ),
+    ];
+
+    assert_eq!(snippets, expected);
+}
+
+#[test]
+fn scope_nested() {
+    crate::tracing::setup();
+
+    let content = r#"class Outer {
+  void run() {
+    Runnable task = new Runnable() {
+      public void run() {}
+    };
+    Supplier<Object> make = () -> {
+      class Local {}
+      return new Local();
+    };
+  }
+
+  static class Inner {
+    int size() { return 0; }
+  }
+}
+"#;
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = java::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("Outer"), ""),
+        (Some("run"), "class Outer"),
+        (Some("run"), "class Outer > function run > anonymous class"),
+        (
+            Some("Local"),
+            "class Outer > function run > anonymous function",
+        ),
+        (Some("Inner"), "class Outer"),
+        (Some("size"), "class Outer > class Inner"),
+    ]
+    .into_iter()
+    .map(|(name, scope)| (name, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
@@ -0,0 +1,307 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::javascript, Extractor, Kind, Kinds, Location, Metadata, Method, Options, Snippet,
+    Target, Targets, Transform, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [
+        Location::from(32..91),
+        Location::from(99..125),
+        Location::from(149..205),
+        Location::from(209..304),
+    ];
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = javascript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, Method::from(transform), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_raw_functions_crlf_lf() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let span_lf = Location::from(32..91);
+    let span_crlf = Location::from(34..96);
+
+    // This test runs on both Windows and other platforms, so it normalizes
+    // to \n regardless of the actual example file and then expands that back to \r\n.
+    //
+    // On non-Windows the first replace will just effectively do nothing.
+    let content_lf = include_str!("testdata/javascript/functions.js").replace("\r\n", "\n");
+    let content_crlf = content_lf.replace('\n', "\r\n");
+
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract_lf = javascript::Extractor::extract(&opts, &content_lf).unwrap();
+    let extract_crlf = javascript::Extractor::extract(&opts, &content_crlf).unwrap();
+
+    // Even though the fingerprints themselves are normalized, they'll still be at different byte offsets.
+    let expected_lf = Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_lf),
+        span_lf.extract_from(content_lf.as_bytes()),
+    );
+    let expected_crlf = Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_crlf),
+        span_crlf.extract_from(content_crlf.as_bytes()),
+    );
+
+    assert_eq!(extract_lf.first(), Some(&expected_lf));
+    assert_eq!(extract_crlf.first(), Some(&expected_crlf));
+
+    let fingerprints_lf = extract_lf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    let fingerprints_crlf = extract_crlf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    assert_eq!(fingerprints_lf, fingerprints_crlf);
+}
+
+#[test]
+fn signature_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let spans = [
+        Location::from(32..50),
+        Location::from(99..119),
+        Location::from(149..173),
+        Location::from(209..241),
+    ];
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = javascript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    let spans = [
+        Location::from(51..91),
+        Location::from(120..125),
+        Location::from(174..205),
+        Location::from(242..304),
+    ];
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = javascript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_comment_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Comment);
+    let add_span = Location::from(32..91);
+    let apply_span = Location::from(209..304);
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = javascript::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let add = Snippet::from(
+        Metadata::new(kind, transform.into(), add_span),
+        r#"function add(a, b) {
+  
+  return a + b;
+}"#
+        .as_bytes(),
+    );
+    let apply = Snippet::from(
+        Metadata::new(kind, transform.into(), apply_span),
+        r#"apply(op, value)  {
+    this.total = op(this.total, value);
+    return this;
+  }"#
+        .as_bytes(),
+    );
+
+    assert_eq!(extract.first(), Some(&add));
+    assert_eq!(extract.last(), Some(&apply));
+}
+
+#[test]
+fn signature_code_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = Some(Transform::Code);
+    let apply_span = Location::from(209..241);
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = javascript::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let expected = Snippet::from(
+        Metadata::new(kind, transform.into(), apply_span),
+        r#"apply(op, value) "#.as_bytes(),
+    );
+
+    assert_eq!(extract.last(), Some(&expected));
+}
+
+#[test]
+fn full_literal_changed_constants() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Literal);
+
+    let original = r#"const greet = (name) => `hello ${name}` + '!'.repeat(3);"#;
+    let modified = r#"const greet = (name) => `hi ${name}` + "?".repeat(1.5);"#;
+
+    let opts = Options::new(Target::Function, kind, transform)
+        .disable_raw()
+        .with_content();
+    let original = javascript::Extractor::extract(&opts, original).expect("extract snippets");
+    let modified = javascript::Extractor::extract(&opts, modified).expect("extract snippets");
+
+    let expected_content = br#"greet = (name) => "" + "".repeat(0)"#;
+    assert_eq!(original.len(), 1);
+    assert_eq!(modified.len(), 1);
+    assert_eq!(
+        original[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(original[0].fingerprint(), modified[0].fingerprint());
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/javascript/smoke_test.js");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = javascript::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
+
+#[test]
+fn names_functions() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/javascript/functions.js");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = javascript::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect_vec();
+    let expected = vec![
+        Some("add"),
+        Some("multiply"),
+        Some("constructor"),
+        Some("apply"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn scope_nested() {
+    crate::tracing::setup();
+
+    let content = r#"class Widget {
+  render(items) {
+    const draw = () => { return 1; };
+    items.forEach(function (item) {
+      const format = (x) => x;
+    });
+  }
+}
+
+function outer() {
+  function inner() {}
+}
+"#;
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = javascript::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("render"), "class Widget"),
+        (Some("draw"), "class Widget > function render"),
+        (
+            Some("format"),
+            "class Widget > function render > anonymous function",
+        ),
+        (Some("outer"), ""),
+        (Some("inner"), "function outer"),
+    ]
+    .into_iter()
+    .map(|(name, scope)| (name, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
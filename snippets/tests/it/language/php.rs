@@ -0,0 +1,335 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::php, Extractor, Kind, Kinds, Location, Metadata, Method, Options, Snippet, Target,
+    Targets, Transform, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [
+        Location::from(38..105),
+        Location::from(151..233),
+        Location::from(239..386),
+        Location::from(412..442),
+    ];
+
+    let content = include_str_lf!("testdata/php/functions.php");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = php::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, Method::from(transform), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let spans = [
+        Location::from(38..58),
+        Location::from(151..192),
+        Location::from(239..306),
+        Location::from(412..442),
+    ];
+
+    let content = include_str_lf!("testdata/php/functions.php");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = php::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    // The interface method has no body, so it doesn't produce a body snippet.
+    let spans = [
+        Location::from(59..105),
+        Location::from(193..233),
+        Location::from(307..386),
+    ];
+
+    let content = include_str_lf!("testdata/php/functions.php");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = php::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_comment_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Comment);
+    let add_span = Location::from(38..105);
+    let apply_span = Location::from(239..386);
+
+    let content = include_str_lf!("testdata/php/functions.php");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = php::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let add = Snippet::from(
+        Metadata::new(kind, transform.into(), add_span),
+        r#"function add($a, $b) {
+    
+    return $a + $b;
+}"#
+        .as_bytes(),
+    );
+    let apply = Snippet::from(
+        Metadata::new(kind, transform.into(), apply_span),
+        r#"public function apply(callable $op, $value): static  {
+        $this->total = $op($this->total, $value);
+        return $this;
+    }"#
+        .as_bytes(),
+    );
+
+    assert_eq!(extract.first(), Some(&add));
+    assert_eq!(extract.get(2), Some(&apply));
+}
+
+#[test]
+fn full_raw_mixed_html() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [Location::from(96..171), Location::from(244..324)];
+
+    let content = include_str_lf!("testdata/php/template.php");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = php::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, Method::from(transform), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_code_mixed_html() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Code);
+
+    let content = include_str_lf!("testdata/php/template.php");
+    let opts = Options::new(Target::Function, kind, transform)
+        .disable_raw()
+        .with_content();
+    let extract = php::Extractor::extract(&opts, content).expect("must set up parser");
+
+    // The HTML output by a function is part of its body.
+    let contents = extract
+        .iter()
+        .map(|snippet| snippet.content().expect("content is retained").as_bytes())
+        .collect_vec();
+    let expected: Vec<&[u8]> = vec![
+        br#"function item($name) { ?> <li><?= htmlspecialchars($name) ?></li> <?php }"#,
+        br#"function footer(string $text): string { return "<footer>{$text}</footer>"; }"#,
+    ];
+
+    assert_eq!(contents, expected);
+}
+
+#[test]
+fn full_literal_changed_constants() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Literal);
+
+    let original = r#"<?php function greet(string $name): string { return "hello {$name}" . str_repeat('!', 3); }"#;
+    let modified = r#"<?php function greet(string $name): string { return "hi {$name}" . str_repeat("?", 1.5); }"#;
+
+    let opts = Options::new(Target::Function, kind, transform)
+        .disable_raw()
+        .with_content();
+    let original = php::Extractor::extract(&opts, original).expect("extract snippets");
+    let modified = php::Extractor::extract(&opts, modified).expect("extract snippets");
+
+    // Type names such as `string` are not literals, even though the grammar uses the same kind for both.
+    let expected_content =
+        br#"function greet(string $name): string { return "" . str_repeat("", 0); }"#;
+    assert_eq!(original.len(), 1);
+    assert_eq!(modified.len(), 1);
+    assert_eq!(
+        original[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(original[0].fingerprint(), modified[0].fingerprint());
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/php/smoke_test.php");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = php::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
+
+#[test]
+fn names_functions() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/php/functions.php");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = php::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect_vec();
+    let expected = vec![
+        Some("add"),
+        Some("__construct"),
+        Some("apply"),
+        Some("area"),
+    ];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn scope_nested() {
+    crate::tracing::setup();
+
+    let content = r#"<?php
+namespace App\Models;
+
+class User {
+    public function save() {
+        $hook = function () {
+            $format = new class {
+                public function format($value) { return $value; }
+            };
+        };
+    }
+}
+
+function helper() {}
+"#;
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = php::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("save"), r"namespace App\Models > class User"),
+        (
+            Some("format"),
+            r"namespace App\Models > class User > function save > anonymous function > anonymous class",
+        ),
+        (Some("helper"), r"namespace App\Models"),
+    ]
+    .into_iter()
+    .map(|(name, scope)| (name, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
+
+#[test]
+fn scope_braced_namespaces() {
+    crate::tracing::setup();
+
+    let content = r#"<?php
+namespace Greetings {
+    trait Greets {
+        public function greet() {}
+    }
+}
+
+namespace {
+    function main() {}
+}
+"#;
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = php::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), scope)
+        })
+        .collect_vec();
+
+    // The global namespace is not a scope.
+    let expected = vec![
+        (Some("greet"), "namespace Greetings > class Greets"),
+        (Some("main"), ""),
+    ]
+    .into_iter()
+    .map(|(name, scope)| (name, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
@@ -0,0 +1,242 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::swift, Extractor, Kind, Kinds, Location, Metadata, Method, Options, Snippet, Target,
+    Targets, Transform, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [
+        Location::from(32..111),
+        Location::from(170..225),
+        Location::from(231..346),
+        Location::from(371..392),
+    ];
+
+    let content = include_str_lf!("testdata/swift/functions.swift");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = swift::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, Method::from(transform), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+    let spans = [
+        Location::from(32..67),
+        Location::from(170..188),
+        Location::from(231..305),
+        Location::from(371..392),
+    ];
+
+    let content = include_str_lf!("testdata/swift/functions.swift");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = swift::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    // The protocol requirement has no body, so it doesn't produce a body snippet.
+    let spans = [
+        Location::from(68..111),
+        Location::from(189..225),
+        Location::from(306..346),
+    ];
+
+    let content = include_str_lf!("testdata/swift/functions.swift");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = swift::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_comment_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Comment);
+    let add_span = Location::from(32..111);
+    let apply_span = Location::from(231..346);
+
+    let content = include_str_lf!("testdata/swift/functions.swift");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = swift::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let add = Snippet::from(
+        Metadata::new(kind, transform.into(), add_span),
+        r#"func add(_ a: Int, _ b: Int) -> Int {
+    
+    return a + b
+}"#
+        .as_bytes(),
+    );
+    let apply = Snippet::from(
+        Metadata::new(kind, transform.into(), apply_span),
+        r#"mutating func apply(_ op: (Int, Int) -> Int, _ value: Int)  {
+        total = op(total, value)
+    }"#
+        .as_bytes(),
+    );
+
+    assert_eq!(extract.first(), Some(&add));
+    assert_eq!(extract.get(2), Some(&apply));
+}
+
+#[test]
+fn full_literal_changed_constants() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = Some(Transform::Literal);
+
+    let original = r#"func greet(_ name: String) -> String { "hello \(name)" + String(repeating: "!", count: 3) }"#;
+    let modified = r#"func greet(_ name: String) -> String { "hi \(name)" + String(repeating: """
+?
+""", count: 0x1F) }"#;
+
+    let opts = Options::new(Target::Function, kind, transform)
+        .disable_raw()
+        .with_content();
+    let original = swift::Extractor::extract(&opts, original).expect("extract snippets");
+    let modified = swift::Extractor::extract(&opts, modified).expect("extract snippets");
+
+    let expected_content =
+        br#"func greet(_ name: String) -> String { "" + String(repeating: "", count: 0) }"#;
+    assert_eq!(original.len(), 1);
+    assert_eq!(modified.len(), 1);
+    assert_eq!(
+        original[0]
+            .content()
+            .expect("content is retained")
+            .as_bytes(),
+        expected_content
+    );
+    assert_eq!(original[0].fingerprint(), modified[0].fingerprint());
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/swift/smoke_test.swift");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = swift::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
+
+#[test]
+fn names_functions() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("testdata/swift/functions.swift");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = swift::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let names = extract
+        .iter()
+        .map(|snippet| snippet.metadata().name())
+        .collect_vec();
+    let expected = vec![Some("add"), Some("init"), Some("apply"), Some("area")];
+
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn scope_nested() {
+    crate::tracing::setup();
+
+    let content = r#"final class Store {
+    init?(path: String) {
+        let load = { (name: String) in
+            func decode(_ value: String) -> String { value }
+        }
+    }
+}
+
+extension Array {
+    func chunked(by size: Int) -> [[Element]] { [] }
+}
+
+protocol Loader {
+    init(path: String)
+}
+"#;
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = swift::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let scopes = extract
+        .iter()
+        .map(|snippet| {
+            let meta = snippet.metadata();
+            let scope = meta.scope().iter().join(" > ");
+            (meta.name(), scope)
+        })
+        .collect_vec();
+
+    let expected = vec![
+        (Some("init"), "class Store"),
+        (
+            Some("decode"),
+            "class Store > function init > anonymous function",
+        ),
+        (Some("chunked"), "class Array"),
+        (Some("init"), "class Loader"),
+    ]
+    .into_iter()
+    .map(|(name, scope)| (name, String::from(scope)))
+    .collect_vec();
+
+    assert_eq!(scopes, expected);
+}
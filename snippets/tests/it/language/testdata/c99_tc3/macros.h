@@ -0,0 +1,12 @@
+#ifndef MACROS_H
+#define MACROS_H
+
+#define MAX_LEN 256
+#define SQUARE(x) ((x) * (x))
+#define SWAP(a, b) do { \
+    int t = (a); \
+    (a) = (b); \
+    (b) = t; \
+  } while (0)
+
+#endif
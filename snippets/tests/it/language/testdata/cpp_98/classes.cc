@@ -0,0 +1,20 @@
+#include <string>
+
+class Forward;
+
+namespace shapes {
+
+  struct Point {
+    int x;
+    int y;
+  };
+
+  class Shape : public Named
+  {
+  public:
+    virtual ~Shape() {}
+    // Every shape has an area.
+    virtual double area() const = 0;
+  };
+
+}
@@ -0,0 +1,31 @@
+#include "counter.h"
+
+namespace counting {
+
+  template <typename T>
+  class Box {
+  public:
+    explicit Box(T value) : value_(value) {}
+    Box(const Box& other) : value_(other.value_) {}
+
+    bool operator==(const Box& other) const {
+      return value_ == other.value_;
+    }
+
+  private:
+    T value_;
+  };
+
+  template <typename T>
+  T max_of(T a, T b) {
+    return a > b ? a : b;
+  }
+
+  Counter::Counter() : count_(0) {}
+
+  Counter& Counter::operator++() {
+    ++count_;
+    return *this;
+  }
+
+}
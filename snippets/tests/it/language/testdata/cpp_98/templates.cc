@@ -0,0 +1,46 @@
+#include <functional>
+#include <ostream>
+
+namespace sorting {
+
+  template <typename T, typename Compare = std::less<T> >
+  const T& pick(const T& a, const T& b, Compare cmp = Compare()) {
+    return cmp(a, b) ? a : b;
+  }
+
+  template <>
+  const int& pick<int>(const int& a, const int& b, std::less<int> cmp) {
+    return a < b ? a : b;
+  }
+
+  void sort(int* begin, int* end, bool descending = false, int (*key)(int) = 0) {
+    std::sort(begin, end);
+  }
+
+  template <typename T>
+  class Range {
+  public:
+    template <typename U>
+    Range(const Range<U>& other) : lo_(other.lo()), hi_(other.hi()) {}
+
+    bool operator<(const Range& other) const { return hi_ < other.lo_; }
+
+    bool operator()(const T& value) const { return lo_ <= value && value < hi_; }
+
+    T lo() const;
+
+  private:
+    T lo_, hi_;
+  };
+
+  template <typename T>
+  T Range<T>::lo() const {
+    return lo_;
+  }
+
+  template <typename T>
+  std::ostream& operator<<(std::ostream& out, const Range<T>& range) {
+    return out << range.lo();
+  }
+
+}
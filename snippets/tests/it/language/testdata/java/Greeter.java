@@ -0,0 +1,29 @@
+package hello;
+
+import java.util.List;
+
+/**
+ * Greets people.
+ */
+public class Greeter implements Named {
+    private final String greeting;
+
+    public Greeter(String greeting) {
+        // Keep the greeting for later.
+        this.greeting = greeting;
+    }
+
+    @Override
+    @SuppressWarnings({"unchecked", "rawtypes"})
+    public String greet(List names) /* all of them */ {
+        StringBuilder out = new StringBuilder();
+        for (Object name : names) {
+            out.append(greeting).append(", ").append(name); // one per name
+        }
+        return out.toString();
+    }
+}
+
+interface Named {
+    String greet(List names);
+}
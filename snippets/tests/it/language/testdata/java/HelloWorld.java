@@ -0,0 +1,7 @@
+package hello;
+
+public class HelloWorld {
+    public static void main(String[] args) {
+        System.out.println("hello world");
+    }
+}
@@ -0,0 +1,53 @@
+package smoke;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.function.Function;
+
+public abstract class SmokeTest<T extends Comparable<T>> {
+    private final List<T> items = new ArrayList<>();
+
+    protected SmokeTest() {
+        this(Collections.emptyList());
+    }
+
+    protected SmokeTest(List<T> initial) {
+        super();
+        items.addAll(initial);
+    }
+
+    public abstract String describe();
+
+    public <R> List<R> map(Function<? super T, ? extends R> f) throws IllegalStateException {
+        List<R> out = new ArrayList<>(items.size());
+        for (T item : items) {
+            out.add(f.apply(item));
+        }
+        return out;
+    }
+
+    public T max() {
+        if (items.isEmpty()) {
+            throw new IllegalStateException("no items");
+        }
+        return Collections.max(items);
+    }
+
+    public Runnable printer() {
+        return new Runnable() {
+            @Override
+            public void run() {
+                System.out.println(describe());
+            }
+        };
+    }
+
+    record Pair(String left, String right) {
+        Pair {
+            if (left == null) {
+                throw new IllegalArgumentException("left");
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// Functions in several forms.
+
+function add(a, b) {
+  // Plain addition.
+  return a + b;
+}
+
+const multiply = (a, b) => a * b;
+
+class Calculator {
+  constructor({ initial }) {
+    this.total = initial;
+  }
+
+  apply(op, value) /* chainable */ {
+    this.total = op(this.total, value);
+    return this;
+  }
+}
+
+[1, 2, 3].map((n) => n * 2);
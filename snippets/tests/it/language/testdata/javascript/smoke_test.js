@@ -0,0 +1,48 @@
+'use strict';
+
+const { EventEmitter } = require('events');
+
+function* counter(start = 0) {
+  let n = start;
+  while (true) {
+    yield n++;
+  }
+}
+
+async function fetchAll(urls) {
+  const results = await Promise.all(urls.map(async (url) => {
+    const res = await fetch(url);
+    return res.json();
+  }));
+  return results.filter(Boolean);
+}
+
+let handler;
+handler = (event) => {
+  console.log(`received ${event.type}`);
+};
+
+class Queue extends EventEmitter {
+  #items = [];
+
+  static create() {
+    return new Queue();
+  }
+
+  get size() {
+    return this.#items.length;
+  }
+
+  push(item) {
+    this.#items.push(item);
+    this.emit('push', item);
+  }
+
+  async *drain() {
+    while (this.#items.length) {
+      yield this.#items.shift();
+    }
+  }
+}
+
+module.exports = { counter, fetchAll, handler, Queue };
@@ -0,0 +1,26 @@
+<?php
+// Functions in several forms.
+
+function add($a, $b) {
+    // Plain addition.
+    return $a + $b;
+}
+
+class Calculator {
+    private $total;
+
+    public function __construct(int $initial) {
+        $this->total = $initial;
+    }
+
+    public function apply(callable $op, $value): static /* chainable */ {
+        $this->total = $op($this->total, $value);
+        return $this;
+    }
+}
+
+interface Shape {
+    public function area(): float;
+}
+
+array_map(fn ($n) => $n * 2, [1, 2, 3]);
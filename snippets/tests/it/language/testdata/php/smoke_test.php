@@ -0,0 +1,71 @@
+<?php
+
+declare(strict_types=1);
+
+namespace App\Http;
+
+use App\Models\User;
+
+/**
+ * Handles requests for users.
+ */
+#[Controller(prefix: '/users', options: ['cache' => true])]
+abstract class UserController extends Controller
+{
+    public function __construct(private readonly UserRepository $users)
+    {
+    }
+
+    #[Route('/{id}')]
+    public function show(int $id): ?User
+    {
+        $user = $this->users->find($id);
+        if ($user === null) {
+            throw new NotFoundException("User {$id} not found");
+        }
+        return $user;
+    }
+
+    public static function format(User $user): string
+    {
+        $label = fn (string $value): string => strtoupper($value);
+        return sprintf('%s <%s>', $label($user->name), $user->email);
+    }
+
+    abstract protected function authorize(User $user): bool;
+}
+
+trait Timestamps
+{
+    public function touch(): void
+    {
+        $this->updatedAt = new \DateTimeImmutable();
+    }
+}
+
+enum Status: string
+{
+    case Active = 'active';
+    case Banned = 'banned';
+
+    public function label(): string
+    {
+        return match ($this) {
+            Status::Active => 'Active',
+            Status::Banned => 'Banned',
+        };
+    }
+}
+
+function helper(array $items, int $limit = 10): array
+{
+    $handler = new class {
+        public function handle($item)
+        {
+            return $item * 1.5;
+        }
+    };
+    return array_slice(array_map([$handler, 'handle'], $items), 0, $limit);
+}
+?>
+<p>Trailing HTML is ignored.</p>
@@ -0,0 +1,17 @@
+<!DOCTYPE html>
+<html>
+<head><title><?= htmlspecialchars($title) ?></title></head>
+<body>
+<?php function item($name) { ?>
+  <li><?= htmlspecialchars($name) ?></li>
+<?php } ?>
+<ul>
+<?php foreach ($names as $name) { item($name); } ?>
+</ul>
+<?php
+function footer(string $text): string {
+    return "<footer>{$text}</footer>";
+}
+?>
+</body>
+</html>
@@ -0,0 +1,24 @@
+// Functions in several forms.
+
+func add(_ a: Int, _ b: Int) -> Int {
+    // Plain addition.
+    return a + b
+}
+
+struct Calculator {
+    private(set) var total: Int
+
+    init(initial: Int) {
+        self.total = initial
+    }
+
+    mutating func apply(_ op: (Int, Int) -> Int, _ value: Int) /* chainable */ {
+        total = op(total, value)
+    }
+}
+
+protocol Shape {
+    func area() -> Double
+}
+
+let doubled = [1, 2, 3].map { $0 * 2 }
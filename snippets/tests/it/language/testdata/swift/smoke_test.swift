@@ -0,0 +1,77 @@
+import Foundation
+
+/// A user of the service.
+public final class User: Codable {
+    public let id: Int
+    public var name: String
+
+    public init(id: Int, name: String) {
+        self.id = id
+        self.name = name
+    }
+
+    public convenience init?(json: [String: Any]) {
+        guard let id = json["id"] as? Int, let name = json["name"] as? String else {
+            return nil
+        }
+        self.init(id: id, name: name)
+    }
+
+    deinit {
+        print("releasing \(name)")
+    }
+
+    @discardableResult
+    public func rename(to name: String, notify: @escaping (String) -> Void = { _ in }) -> Self {
+        self.name = name
+        notify(name)
+        return self
+    }
+}
+
+enum Status: String {
+    case active = "active"
+    case banned = "banned"
+
+    var label: String {
+        switch self {
+        case .active: return "Active"
+        case .banned: return "Banned"
+        }
+    }
+
+    static func parse(_ raw: String) throws -> Status {
+        guard let status = Status(rawValue: raw) else {
+            throw URLError(.badURL)
+        }
+        return status
+    }
+}
+
+extension Array where Element == User {
+    func names() -> [String] {
+        map { user in
+            func format(_ value: String) -> String { value.uppercased() }
+            return format(user.name)
+        }
+    }
+}
+
+actor Counter {
+    private var value = 0x10
+
+    func increment(by amount: Double = 1.5) async -> Int {
+        value += Int(amount)
+        return value
+    }
+}
+
+protocol Repository {
+    init(path: String)
+    func find(id: Int) async throws -> User?
+}
+
+func main() {
+    let users = [User(id: 1, name: "Ada")]
+    print(users.names())
+}
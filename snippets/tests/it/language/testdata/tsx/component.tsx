@@ -0,0 +1,10 @@
+import React from 'react';
+
+type Props = { name: string };
+
+export function Greeting({ name }: Props): JSX.Element {
+  // Render the greeting.
+  return <h1 className="greeting">Hello, {name}!</h1>;
+}
+
+export const Farewell = ({ name }: Props) => <p>Goodbye, {name}.</p>;
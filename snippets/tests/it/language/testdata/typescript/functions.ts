@@ -0,0 +1,21 @@
+// Functions with type annotations.
+
+export function identity<T>(value: T): T {
+  return value;
+}
+
+const area = (shape: { width: number; height: number }): number =>
+  shape.width * shape.height;
+
+function parse(input: string): number;
+function parse(input: string | number): number {
+  return Number(input);
+}
+
+abstract class Repository<K, V> {
+  protected abstract load(key: K): V | undefined;
+
+  public get(key: K, fallback: V): V /* never undefined */ {
+    return this.load(key) ?? fallback;
+  }
+}
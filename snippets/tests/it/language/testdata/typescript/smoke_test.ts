@@ -0,0 +1,48 @@
+import { readFile } from 'fs/promises';
+
+export interface Config {
+  name: string;
+  retries?: number;
+}
+
+export enum Level {
+  Debug,
+  Info,
+}
+
+type Handler<T> = (event: T) => Promise<void>;
+
+export async function loadConfig(path: string): Promise<Config> {
+  const raw = await readFile(path, 'utf8');
+  return JSON.parse(raw) as Config;
+}
+
+export const withRetries = <T>(fn: () => Promise<T>, retries = 3): Promise<T> => {
+  return fn().catch((err: unknown) => {
+    if (retries <= 0) {
+      throw err;
+    }
+    return withRetries(fn, retries - 1);
+  });
+};
+
+export class Logger {
+  private static instance?: Logger;
+
+  constructor(private readonly level: Level = Level.Info) {}
+
+  static get(): Logger {
+    return (Logger.instance ??= new Logger());
+  }
+
+  log<K extends keyof Config>(key: K, value: Config[K]): void {
+    if (this.level <= Level.Info) {
+      console.log(`${String(key)}=${String(value)}`);
+    }
+  }
+}
+
+let onEvent: Handler<string>;
+onEvent = async (event: string): Promise<void> => {
+  Logger.get().log('name', event);
+};
@@ -0,0 +1,74 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::tsx, Extractor, Kind, Kinds, Location, Metadata, Options, Snippet, Target, Targets,
+    Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_component() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [Location::from(67..199), Location::from(214..269)];
+
+    let content = include_str_lf!("testdata/tsx/component.tsx");
+    let opts = Options::new(Target::Function, kind, transform);
+    let extract = tsx::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, transform.into(), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn signature_raw_component() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+
+    let content = include_str_lf!("testdata/tsx/component.tsx");
+    let opts = Options::new(Target::Function, kind, transform);
+    let extract = tsx::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = [
+        (67..114, "function Greeting({ name }: Props): JSX.Element"),
+        (214..245, "Farewell = ({ name }: Props) =>"),
+    ]
+    .into_iter()
+    .map(|(span, text)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), Location::from(span)),
+            text.as_bytes(),
+        )
+    })
+    .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/tsx/component.tsx");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = tsx::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
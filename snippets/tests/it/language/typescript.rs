@@ -0,0 +1,173 @@
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use snippets::{
+    language::typescript, Extractor, Kind, Kinds, Location, Metadata, Method, Options, Snippet,
+    Target, Targets, Transform, Transforms,
+};
+
+use crate::include_str_lf;
+
+#[test]
+fn full_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let spans = [
+        Location::from(44..97),
+        Location::from(105..194),
+        Location::from(236..310),
+        Location::from(399..500),
+    ];
+
+    let content = include_str_lf!("testdata/typescript/functions.ts");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = typescript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    // Neither the overload signature nor the abstract method have a body, so they aren't extracted.
+    let expected = spans
+        .into_iter()
+        .map(|span| {
+            Snippet::from(
+                Metadata::new(kind, Method::from(transform), span),
+                span.extract_from(content.as_bytes()),
+            )
+        })
+        .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn full_raw_functions_crlf_lf() {
+    crate::tracing::setup();
+
+    let kind = Kind::Full;
+    let transform = None;
+    let span_lf = Location::from(44..97);
+    let span_crlf = Location::from(46..101);
+
+    // This test runs on both Windows and other platforms, so it normalizes
+    // to \n regardless of the actual example file and then expands that back to \r\n.
+    //
+    // On non-Windows the first replace will just effectively do nothing.
+    let content_lf = include_str!("testdata/typescript/functions.ts").replace("\r\n", "\n");
+    let content_crlf = content_lf.replace('\n', "\r\n");
+
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract_lf = typescript::Extractor::extract(&opts, &content_lf).unwrap();
+    let extract_crlf = typescript::Extractor::extract(&opts, &content_crlf).unwrap();
+
+    // Even though the fingerprints themselves are normalized, they'll still be at different byte offsets.
+    let expected_lf = Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_lf),
+        span_lf.extract_from(content_lf.as_bytes()),
+    );
+    let expected_crlf = Snippet::from(
+        Metadata::new(kind, Method::from(transform), span_crlf),
+        span_crlf.extract_from(content_crlf.as_bytes()),
+    );
+
+    assert_eq!(extract_lf.first(), Some(&expected_lf));
+    assert_eq!(extract_crlf.first(), Some(&expected_crlf));
+
+    let fingerprints_lf = extract_lf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    let fingerprints_crlf = extract_crlf
+        .into_iter()
+        .map(|snippet| snippet.fingerprint().clone())
+        .collect_vec();
+    assert_eq!(fingerprints_lf, fingerprints_crlf);
+}
+
+#[test]
+fn signature_raw_functions() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = None;
+
+    let content = include_str_lf!("testdata/typescript/functions.ts");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = typescript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    // Signatures include type parameters and annotations.
+    let expected = [
+        (44..77, "function identity<T>(value: T): T"),
+        (
+            105..165,
+            "area = (shape: { width: number; height: number }): number =>",
+        ),
+        (236..282, "function parse(input: string | number): number"),
+        (
+            399..455,
+            "public get(key: K, fallback: V): V /* never undefined */",
+        ),
+    ]
+    .into_iter()
+    .map(|(span, text)| {
+        Snippet::from(
+            Metadata::new(kind, transform.into(), Location::from(span)),
+            text.as_bytes(),
+        )
+    })
+    .collect_vec();
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn body_raw_arrow_function() {
+    crate::tracing::setup();
+
+    let kind = Kind::Body;
+    let transform = None;
+    let span = Location::from(168..194);
+
+    let content = include_str_lf!("testdata/typescript/functions.ts");
+    let opts = Options::new(Target::Function, kind, Transforms::from(transform));
+    let extract = typescript::Extractor::extract(&opts, &content).expect("must set up parser");
+
+    let expected = Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        b"shape.width * shape.height",
+    );
+
+    assert_eq!(extract.get(1), Some(&expected));
+}
+
+#[test]
+fn signature_code_method() {
+    crate::tracing::setup();
+
+    let kind = Kind::Signature;
+    let transform = Some(Transform::Code);
+    let span = Location::from(399..455);
+
+    let content = include_str_lf!("testdata/typescript/functions.ts");
+    let opts = Options::new(Target::Function, kind, transform).disable_raw();
+    let extract = typescript::Extractor::extract(&opts, content).expect("must set up parser");
+
+    let expected = Snippet::from(
+        Metadata::new(kind, transform.into(), span),
+        b"public get(key: K, fallback: V): V ",
+    );
+
+    assert_eq!(extract.last(), Some(&expected));
+}
+
+#[test]
+fn smoke_test() {
+    crate::tracing::setup();
+
+    let target = Targets::default();
+    let kind = Kinds::default();
+    let transform = Transforms::default();
+
+    let content = include_str_lf!("testdata/typescript/smoke_test.ts");
+    let opts = Options::new(target, kind, transform).disable_raw();
+    let extract = typescript::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(!extract.is_empty(), "must have extracted snippets");
+}
@@ -0,0 +1,61 @@
+use snippets::{
+    language::{c99_tc3, cpp_98, java, javascript, php, swift, tsx, typescript},
+    Extractor, FallibleIterator, Kinds, LazyExtractor, Options, Targets, Transforms,
+};
+
+use crate::include_str_lf;
+
+/// Assert that extracting lazily produces the same snippets, in the same order, as extracting eagerly.
+fn assert_lazy_matches<E: LazyExtractor>(content: &str) {
+    let opts = Options::new(Targets::default(), Kinds::default(), Transforms::default());
+    let eager = E::extract(&opts, content).expect("extract snippets");
+
+    let parsed = E::parse(content.as_bytes()).expect("parse content");
+    let lazy = E::extract_lazy(&parsed, &opts)
+        .collect::<Vec<_>>()
+        .expect("extract snippets lazily");
+
+    assert!(!eager.is_empty(), "must have extracted snippets");
+    assert_eq!(lazy, eager);
+}
+
+#[test]
+fn lazy_matches_eager() {
+    crate::tracing::setup();
+
+    assert_lazy_matches::<c99_tc3::Extractor>(&include_str_lf!(
+        "language/testdata/c99_tc3/smoke_comp.c"
+    ));
+    assert_lazy_matches::<cpp_98::Extractor>(&include_str_lf!(
+        "language/testdata/cpp_98/smoke_test.cc"
+    ));
+    assert_lazy_matches::<java::Extractor>(&include_str_lf!(
+        "language/testdata/java/smoke_test.java"
+    ));
+    assert_lazy_matches::<javascript::Extractor>(&include_str_lf!(
+        "language/testdata/javascript/smoke_test.js"
+    ));
+    assert_lazy_matches::<php::Extractor>(&include_str_lf!("language/testdata/php/smoke_test.php"));
+    assert_lazy_matches::<swift::Extractor>(&include_str_lf!(
+        "language/testdata/swift/smoke_test.swift"
+    ));
+    assert_lazy_matches::<typescript::Extractor>(&include_str_lf!(
+        "language/testdata/typescript/smoke_test.ts"
+    ));
+    assert_lazy_matches::<tsx::Extractor>(&include_str_lf!("language/testdata/tsx/component.tsx"));
+}
+
+#[test]
+fn lazy_yields_on_demand() {
+    crate::tracing::setup();
+
+    let content = include_str_lf!("language/testdata/c99_tc3/smoke_comp.c");
+    let opts = Options::new(Targets::default(), Kinds::default(), Transforms::default());
+    let eager = c99_tc3::Extractor::extract(&opts, &content).expect("extract snippets");
+
+    let parsed = c99_tc3::Extractor::parse(content.as_bytes()).expect("parse content");
+    let mut lazy = c99_tc3::Extractor::extract_lazy(&parsed, &opts);
+    let first = lazy.next().expect("extract snippet");
+
+    assert_eq!(first.as_ref(), eager.first());
+}
@@ -12,8 +12,18 @@
 //! setting. For details, see [filtering events with environment variables].
 //!
 /// [filtering events with environment variables]: https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/index.html#filtering-events-with-environment-variables
+mod dynamic;
+mod encoding;
+mod fingerprint;
+mod guards;
 pub mod language;
+mod lazy;
+mod matching;
+mod preprocess;
+mod query;
 mod tracing;
+mod walk;
+mod winnow;
 
 /// Include the contents of the file at the provided path, normalizing `\r\n` to `\n`.
 #[macro_export]
@@ -0,0 +1,166 @@
+use snippets::{
+    language::c99_tc3,
+    matching::{self, Corpus},
+    DynSnippet, Extractor, Kind, Kinds, Method, Options, Target, Transform,
+};
+
+const UPSTREAM: &str = r#"int add(int a, int b) {
+  return a + b;
+}
+
+int sub(int a, int b) {
+  return a - b;
+}
+"#;
+
+/// Copies `add` verbatim, reformats the signature of `sub`, and adds `mul`.
+const PROJECT: &str = r#"int mul(int a, int b) {
+  return a * b;
+}
+
+int add(int a, int b) {
+  return a + b;
+}
+
+int sub(int  a,  int  b) {
+  return a - b;
+}
+"#;
+
+fn extract(opts: &Options, content: &str) -> Vec<DynSnippet> {
+    c99_tc3::Extractor::extract(opts, content)
+        .expect("extract snippets")
+        .into_iter()
+        .map(DynSnippet::from)
+        .collect()
+}
+
+fn tagged<'a>(source: &'a str, snippets: &[DynSnippet]) -> Vec<(&'a str, DynSnippet)> {
+    snippets
+        .iter()
+        .cloned()
+        .map(|snippet| (source, snippet))
+        .collect()
+}
+
+#[test]
+fn grouped_by_specificity() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kinds::full(), Transform::Space);
+    let known = tagged("upstream.c", &extract(&opts, UPSTREAM))
+        .into_iter()
+        .collect::<Corpus<_>>();
+    let project = tagged("project.c", &extract(&opts, PROJECT));
+
+    let matches = matching::find(project, &known);
+    let groups = matches
+        .groups()
+        .map(|(kind, method, group)| {
+            let names = group
+                .iter()
+                .map(|found| {
+                    found
+                        .project()
+                        .snippet()
+                        .metadata()
+                        .name()
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+            (kind, method, names)
+        })
+        .collect::<Vec<_>>();
+
+    // Everything else matched for `add` and `sub` (such as their bodies) is within these matches.
+    assert_eq!(
+        groups,
+        vec![
+            (Kind::Full, Method::Raw, vec!["add"]),
+            (
+                Kind::Full,
+                Method::Normalized(Transform::Space),
+                vec!["sub"]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn overlaps_resolved_per_source() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kinds::full(), Transform::Space);
+    let known = tagged("upstream.c", &extract(&opts, UPSTREAM))
+        .into_iter()
+        .collect::<Corpus<_>>();
+
+    let extracted = extract(&opts, PROJECT);
+    let project = tagged("a.c", &extracted)
+        .into_iter()
+        .chain(tagged("b.c", &extracted));
+
+    let matches = matching::find(project, &known);
+    let found = matches
+        .iter()
+        .map(|found| {
+            let meta = found.project().snippet().metadata();
+            (*found.project().source(), meta.name().unwrap_or_default())
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        vec![
+            ("a.c", "add"),
+            ("b.c", "add"),
+            ("a.c", "sub"),
+            ("b.c", "sub"),
+        ]
+    );
+}
+
+#[test]
+fn reports_every_known_source() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kind::Full, Transform::Space);
+    let upstream = extract(&opts, UPSTREAM);
+    let known = tagged("v1/upstream.c", &upstream)
+        .into_iter()
+        .chain(tagged("v2/upstream.c", &upstream))
+        .collect::<Corpus<_>>();
+    assert_eq!(known.len(), upstream.len() * 2);
+
+    let project = tagged("project.c", &extract(&opts, PROJECT));
+    let matches = matching::find(project, &known);
+    let add = matches
+        .iter()
+        .find(|found| found.project().snippet().metadata().name() == Some("add"))
+        .expect("must match add");
+
+    let sources = add
+        .known()
+        .iter()
+        .map(|entry| *entry.source())
+        .collect::<Vec<_>>();
+    assert_eq!(sources, vec!["v1/upstream.c", "v2/upstream.c"]);
+    assert!(add
+        .known()
+        .iter()
+        .all(|entry| entry.snippet().metadata().name() == Some("add")));
+}
+
+#[test]
+fn no_matches() {
+    crate::tracing::setup();
+
+    let opts = Options::new(Target::Function, Kinds::full(), Transform::Space);
+    let known = Corpus::<&str>::new();
+    let project = tagged("project.c", &extract(&opts, PROJECT));
+
+    let matches = matching::find(project, &known);
+    assert!(known.is_empty());
+    assert!(matches.is_empty());
+    assert_eq!(matches.groups().count(), 0);
+}
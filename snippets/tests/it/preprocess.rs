@@ -0,0 +1,294 @@
+use snippets::{
+    language::{c99_tc3, cpp_98},
+    preprocess::Preprocessor,
+    DynSnippet, Error, Kind, Options, Skipped, Target, Targets, Transforms,
+};
+
+/// Each function's signature differs per platform.
+const PLATFORMS: &str = r#"#include <stdio.h>
+
+#if defined(_WIN32) && !defined(USE_POSIX)
+int read_config(HANDLE file) {
+#elif PLATFORM_VERSION >= 2
+int read_config(int fd, int flags) {
+#else
+int read_config(int fd) {
+#endif
+  return BUFFER_SIZE;
+}
+
+#ifdef _WIN32
+static void log_line(const wchar_t *line) {
+#else
+static void log_line(const char *line) {
+#endif
+  puts(line);
+}
+"#;
+
+fn names(snippets: Vec<DynSnippet>) -> Vec<(Option<String>, String)> {
+    snippets
+        .into_iter()
+        .map(|snippet| {
+            let content = snippet.content().expect("content is retained");
+            let name = snippet.metadata().name().map(String::from);
+            (name, content.as_utf8().into_owned())
+        })
+        .collect()
+}
+
+fn signatures(preprocessor: &Preprocessor, content: &str) -> Vec<String> {
+    let opts = Options::new(Target::Function, Kind::Signature, Transforms::none()).with_content();
+    preprocessor
+        .extract::<c99_tc3::Extractor>(&opts, content)
+        .expect("extract snippets")
+        .into_iter()
+        .map(|snippet| {
+            let content = snippet.content().expect("content is retained");
+            content.as_utf8().into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn default_branches() {
+    crate::tracing::setup();
+
+    assert_eq!(
+        signatures(&Preprocessor::new(), PLATFORMS),
+        vec![
+            "int read_config(int fd)",
+            "static void log_line(const char *line)",
+        ]
+    );
+}
+
+#[test]
+fn defines_select_branches() {
+    crate::tracing::setup();
+
+    let windows = Preprocessor::new().define("_WIN32", "1");
+    assert_eq!(
+        signatures(&windows, PLATFORMS),
+        vec![
+            "int read_config(HANDLE file)",
+            "static void log_line(const wchar_t *line)",
+        ]
+    );
+
+    let posix = [
+        ("_WIN32", "1"),
+        ("USE_POSIX", ""),
+        ("PLATFORM_VERSION", "3"),
+    ]
+    .into_iter()
+    .collect::<Preprocessor>();
+    assert!(posix.is_defined("USE_POSIX"));
+    assert_eq!(
+        signatures(&posix, PLATFORMS),
+        vec![
+            "int read_config(int fd, int flags)",
+            "static void log_line(const wchar_t *line)",
+        ]
+    );
+}
+
+#[test]
+fn conditional_expressions() {
+    crate::tracing::setup();
+
+    let content = r#"#define LEVEL 3
+#define ENABLED (LEVEL > 2)
+#define CHECK(x) (x)
+#if ENABLED && !defined DISABLED
+level_enabled;
+#endif
+#if (LEVEL << 2) == 0x0C ? 1 : 0
+shift_and_ternary;
+#endif
+#if LEVEL % 2 - 1 || 017 != 15UL
+arithmetic_false;
+#else
+arithmetic_else;
+#endif
+#ifndef UNDEFINED
+#  if 0
+nested_false;
+#  elif UNDEFINED_IDENT == 0
+nested_elif;
+#  endif
+#endif
+#if CHECK(1)
+function_like_unsupported;
+#endif
+#if 1 / 0
+division_by_zero;
+#endif
+"#;
+
+    let preprocessed = Preprocessor::new().preprocess(content.as_bytes());
+    let kept = String::from_utf8(preprocessed)
+        .expect("preprocessed content is utf8")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        kept,
+        vec![
+            "level_enabled;",
+            "shift_and_ternary;",
+            "arithmetic_else;",
+            "nested_elif;"
+        ]
+    );
+}
+
+#[test]
+fn expands_object_like_macros() {
+    crate::tracing::setup();
+
+    let content = r#"#define SIZE (BASE * 2)
+#define BASE 16
+#define errno errno
+int buffer[SIZE]; /* SIZE */ char *name = "SIZE"; int e = errno;
+#undef SIZE
+int other[SIZE]; // SIZE
+"#;
+
+    let preprocessed = Preprocessor::new().preprocess(content.as_bytes());
+    let preprocessed = String::from_utf8(preprocessed).expect("preprocessed content is utf8");
+    assert_eq!(
+        preprocessed.lines().collect::<Vec<_>>(),
+        vec![
+            "#define SIZE (BASE * 2)",
+            "#define BASE 16",
+            "#define errno errno",
+            r#"int buffer[(16 * 2)]; /* SIZE */ char *name = "SIZE"; int e = errno;"#,
+            "#undef SIZE",
+            "int other[SIZE]; // SIZE",
+        ]
+    );
+}
+
+#[test]
+fn preserves_locations() {
+    crate::tracing::setup();
+
+    let content =
+        "#ifdef DEBUG\nint debug(void) {\n#else\nint debug(int level) {\n#endif\n  return 0;\n}\n";
+    let preprocessed = Preprocessor::new().preprocess(content.as_bytes());
+    assert_eq!(preprocessed.len(), content.len());
+    assert_eq!(preprocessed.iter().filter(|&&b| b == b'\n').count(), 7);
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = Preprocessor::new()
+        .extract::<c99_tc3::Extractor>(&opts, content)
+        .expect("extract snippets");
+    assert_eq!(extract.len(), 1);
+
+    let location = extract[0].metadata().location();
+    let written = location.extract_from(content.as_bytes());
+    assert_eq!(written, b"int debug(int level) {\n#endif\n  return 0;\n}");
+    assert_eq!(
+        extract[0].lines().expect("lines are set").to_string(),
+        "4:1-7:1"
+    );
+}
+
+#[test]
+fn macros_still_extracted() {
+    crate::tracing::setup();
+
+    let content = "#ifdef LEGACY\n#define LIMIT 10\n#else\n#define LIMIT 20\n#endif\n";
+    let opts = Options::new(Target::Macro, Kind::Full, Transforms::none()).with_content();
+    let extract = Preprocessor::new()
+        .extract::<c99_tc3::Extractor>(&opts, content)
+        .expect("extract snippets")
+        .into_iter()
+        .map(DynSnippet::from)
+        .collect();
+
+    assert_eq!(
+        names(extract),
+        vec![(
+            Some(String::from("LIMIT")),
+            String::from("#define LIMIT 20")
+        )]
+    );
+}
+
+#[test]
+fn reports_preprocessed_strategy() {
+    crate::tracing::setup();
+
+    let content = "#if __cplusplus >= 201103L\nauto sum(int a, int b) -> int {\n#else\nint sum(int a, int b) {\n#endif\n  return a + b;\n}\n";
+    let opts = Options::new(Targets::full(), Kind::Full, Transforms::none());
+    let extract = Preprocessor::new()
+        .define("__cplusplus", "201703L")
+        .extract::<cpp_98::Extractor>(&opts, content)
+        .expect("extract snippets");
+
+    assert_eq!(extract.len(), 1);
+    assert_eq!(extract[0].metadata().name(), Some("sum"));
+    assert_eq!(
+        extract[0].clone().into_dyn().language(),
+        "cpp_98/preprocessed"
+    );
+}
+
+#[test]
+fn exponential_expansion_is_bounded() {
+    crate::tracing::setup();
+
+    // Each macro expands to two copies of the next, so `A0` expands to 2^40 copies of `x`.
+    let mut content = (0..40)
+        .map(|i| format!("#define A{i} A{next} A{next}\n", next = i + 1))
+        .collect::<String>();
+    content.push_str("#define A40 x\nint A0;\n#if A0\n#endif\n");
+
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none())
+        .with_max_file_bytes(content.len() * 2);
+    let err = Preprocessor::new()
+        .extract::<c99_tc3::Extractor>(&opts, &content)
+        .expect_err("expansion is too large");
+    assert!(
+        matches!(err, Error::Skipped(Skipped::TooLarge { max_bytes, .. }) if max_bytes == content.len() * 2),
+        "expected expansion to be too large, got: {err:?}"
+    );
+
+    // The same applies to macros expanded in a conditional expression.
+    let content = content.replace("int A0;", "");
+    let err = Preprocessor::new()
+        .extract::<c99_tc3::Extractor>(&opts, &content)
+        .expect_err("expansion is too large");
+    assert!(matches!(err, Error::Skipped(Skipped::TooLarge { .. })));
+}
+
+#[test]
+fn deep_nesting_is_bounded() {
+    crate::tracing::setup();
+
+    let depth = 100_000;
+    let parens = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    let nots = format!("{}0", "!".repeat(depth));
+    let chain = (0..depth)
+        .map(|i| format!("#define M{i} M{}\n", i + 1))
+        .collect::<String>();
+    let content = format!(
+        "#if {parens}\nparens;\n#endif\n#if {nots}\nnots;\n#endif\n{chain}#if M0 == 0\nchain;\n#endif\nint x = M0;\n#if (((1)))\nshallow;\n#endif\n"
+    );
+
+    let preprocessed = Preprocessor::new().preprocess(content.as_bytes());
+    let kept = String::from_utf8(preprocessed)
+        .expect("preprocessed content is utf8")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    assert_eq!(kept, vec!["int x = M256;", "shallow;"]);
+}
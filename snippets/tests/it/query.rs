@@ -0,0 +1,147 @@
+use snippets::{
+    language::{c99_tc3, javascript},
+    query::QueryExtractor,
+    Error, FallibleIterator, Kind, Kinds, LazyExtractor, Location, Metadata, Method, Options,
+    Snippet, Target, Transform, Transforms,
+};
+
+const TESTS: &str = r#"#include "check.h"
+
+void test_add(void) {
+  check(add(1, 2) == 3);
+}
+
+int add(int a, int b) {
+  return a + b;
+}
+"#;
+
+/// Matches functions whose names start with `test_`, capturing the function as `test`.
+const TEST_FUNCTIONS: &str = r#"
+(function_definition
+  declarator: (function_declarator
+    declarator: (identifier) @_name)
+  (#match? @_name "^test_")) @test
+"#;
+
+#[test]
+fn query_captures() {
+    crate::tracing::setup();
+
+    let query = c99_tc3::Extractor::query(TEST_FUNCTIONS).expect("compile query");
+    let parsed = c99_tc3::Extractor::parse(TESTS.as_bytes()).expect("parse content");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let extract = query
+        .extract(&parsed, &opts)
+        .collect::<Vec<_>>()
+        .expect("extract snippets");
+
+    let span = Location::from(20..68);
+    let expected = vec![Snippet::from(
+        Metadata::new(Kind::Full, Method::Raw, span),
+        span.extract_from(TESTS.as_bytes()),
+    )];
+
+    assert_eq!(extract, expected);
+    assert_eq!(extract[0].metadata().name(), Some("test"));
+    assert_eq!(query.capture_names(), ["_name", "test"]);
+}
+
+#[test]
+fn query_applies_methods() {
+    crate::tracing::setup();
+
+    let query = c99_tc3::Extractor::query(TEST_FUNCTIONS).expect("compile query");
+    let parsed = c99_tc3::Extractor::parse(TESTS.as_bytes()).expect("parse content");
+    let opts = Options::new(Target::Function, Kind::Full, Transform::Space).disable_raw();
+    let extract = query
+        .extract(&parsed, &opts)
+        .collect::<Vec<_>>()
+        .expect("extract snippets");
+
+    let expected = vec![Snippet::from(
+        Metadata::new(
+            Kind::Full,
+            Method::Normalized(Transform::Space),
+            Location::from(20..68),
+        ),
+        b"void test_add(void) { check(add(1, 2) == 3); }",
+    )];
+
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn query_only_extracts_full() {
+    crate::tracing::setup();
+
+    let query = c99_tc3::Extractor::query(TEST_FUNCTIONS).expect("compile query");
+    let parsed = c99_tc3::Extractor::parse(TESTS.as_bytes()).expect("parse content");
+
+    let opts = Options::new(Target::Function, Kinds::full(), Transforms::none());
+    let kinds = query
+        .extract(&parsed, &opts)
+        .map(|snippet| Ok(snippet.metadata().kind()))
+        .collect::<Vec<_>>()
+        .expect("extract snippets");
+    assert_eq!(kinds, vec![Kind::Full]);
+
+    let opts = Options::new(Target::Function, Kind::Signature, Transforms::none());
+    let count = query
+        .extract(&parsed, &opts)
+        .count()
+        .expect("extract snippets");
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn query_multiple_captures() {
+    crate::tracing::setup();
+
+    let content = r#"const sql = "SELECT * FROM users";
+const greeting = "hello";
+db.query("DELETE FROM users");
+"#;
+    let query = javascript::Extractor::query(
+        r#"((string) @sql (#match? @sql "^.(SELECT|DELETE) "))
+           (call_expression function: (member_expression property: (property_identifier) @call))"#,
+    )
+    .expect("compile query");
+    let parsed = javascript::Extractor::parse(content.as_bytes()).expect("parse content");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none()).with_content();
+    let extract = query
+        .extract(&parsed, &opts)
+        .map(|snippet| {
+            Ok((
+                snippet.metadata().name().map(String::from),
+                snippet
+                    .content()
+                    .expect("content is retained")
+                    .as_utf8()
+                    .into_owned(),
+            ))
+        })
+        .collect::<Vec<_>>()
+        .expect("extract snippets");
+
+    let expected = vec![
+        (
+            Some(String::from("sql")),
+            String::from(r#""SELECT * FROM users""#),
+        ),
+        (Some(String::from("call")), String::from("query")),
+        (
+            Some(String::from("sql")),
+            String::from(r#""DELETE FROM users""#),
+        ),
+    ];
+    assert_eq!(extract, expected);
+}
+
+#[test]
+fn query_invalid() {
+    crate::tracing::setup();
+
+    let result = c99_tc3::Extractor::query("(function_definition");
+    assert!(matches!(result, Err(Error::Query(_))));
+}
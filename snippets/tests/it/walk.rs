@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+use snippets::{walk, Kind, Options, Skipped, Target, Transforms};
+
+#[test]
+fn extract_dir_routes_by_extension() {
+    crate::tracing::setup();
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/it/language/testdata");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let walked = walk::extract_dir(&root, &opts).expect("walk testdata");
+
+    let paths = walked
+        .iter()
+        .map(|walked| walked.path())
+        .collect::<Vec<_>>();
+    for expected in [
+        "c99_tc3/hello_world.c",
+        "c99_tc3/macros.h",
+        "cpp_98/simple_class.cc",
+        "java/Greeter.java",
+        "javascript/functions.js",
+        "php/functions.php",
+        "swift/functions.swift",
+        "typescript/functions.ts",
+        "tsx/component.tsx",
+    ] {
+        assert!(
+            paths.contains(&&PathBuf::from(expected)),
+            "{expected} must be walked, walked: {paths:?}"
+        );
+    }
+
+    let mut sorted = paths.clone();
+    sorted.sort();
+    assert_eq!(paths, sorted, "files must be sorted by path");
+
+    let hello_world = walked
+        .iter()
+        .find(|walked| walked.path() == Path::new("c99_tc3/hello_world.c"))
+        .expect("must have walked hello world");
+    assert!(matches!(hello_world.snippets(), walk::Extracted::C99Tc3(_)));
+    assert_eq!(hello_world.snippets().len(), 1);
+}
+
+#[test]
+fn walker_skips_unsupported_files() {
+    crate::tracing::setup();
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches");
+    let opts = Options::new(Target::Function, Kind::Full, Transforms::none());
+    let walked = walk::Walker::new(opts)
+        .walk(&root, |walked| -> Result<(), snippets::Error> {
+            panic!("unexpected file walked: {:?}", walked.path())
+        })
+        .expect("walk benches");
+
+    assert_eq!(walked, 0);
+}
+
+#[test]
+fn walker_reports_skipped_files() {
+    crate::tracing::setup();
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/it/language/testdata");
+    let opts =
+        Options::new(Target::Function, Kind::Full, Transforms::none()).with_max_file_bytes(1);
+    let walked = walk::extract_dir(&root, &opts).expect("walk testdata");
+
+    let hello_world = walked
+        .iter()
+        .find(|walked| walked.path() == Path::new("c99_tc3/hello_world.c"))
+        .expect("must have walked hello world");
+    assert!(matches!(
+        hello_world.snippets(),
+        walk::Extracted::Skipped(Skipped::TooLarge { max_bytes: 1, .. })
+    ));
+    assert!(hello_world.snippets().is_empty());
+
+    let extracted = walk::Walker::new(opts)
+        .walk(&root, |walked| -> Result<(), snippets::Error> {
+            assert!(matches!(walked.snippets(), walk::Extracted::Skipped(_)));
+            Ok(())
+        })
+        .expect("walk testdata");
+    assert_eq!(extracted, 0, "skipped files are not counted");
+}
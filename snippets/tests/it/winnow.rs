@@ -0,0 +1,135 @@
+use std::{collections::HashSet, num::NonZeroUsize};
+
+use snippets::{
+    language::{c99_tc3, java},
+    Extractor, Kind, Kinds, Method, Options, Snippet, Target, Transforms, Winnowing,
+};
+
+fn winnowing(k: usize, window: usize) -> Winnowing {
+    Winnowing::new(
+        NonZeroUsize::new(k).expect("k must be nonzero"),
+        NonZeroUsize::new(window).expect("window must be nonzero"),
+    )
+}
+
+fn winnowed_only(kind: Kind, winnowing: Winnowing) -> Options {
+    Options::new(Target::Function, kind, Transforms::none())
+        .disable_raw()
+        .with_winnowing(winnowing)
+        .with_content()
+}
+
+fn fingerprints<L>(snippets: &[Snippet<L>]) -> HashSet<String> {
+    snippets
+        .iter()
+        .map(|snippet| snippet.fingerprint().to_string())
+        .collect()
+}
+
+#[test]
+fn winnowed_kgrams() {
+    crate::tracing::setup();
+
+    let content = "int add(int a, int b) {\n  return a + b; // sum\n}\n";
+    let opts = winnowed_only(Kind::Body, winnowing(3, 2));
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("extract snippets");
+
+    assert!(!extract.is_empty(), "must have extracted snippets");
+    for snippet in &extract {
+        let meta = snippet.metadata();
+        assert_eq!(meta.kind(), Kind::Body);
+        assert_eq!(meta.method(), Method::Winnowed);
+        assert_eq!(meta.name(), Some("add"));
+
+        // Each snippet is three tokens, not counting comments, located where they were written.
+        let content_tokens = snippet.content().expect("content is retained").as_utf8();
+        let tokens = content_tokens.split(' ').collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 3, "k-gram: {content_tokens}");
+        assert!(!content_tokens.contains("//"), "k-gram: {content_tokens}");
+
+        let written = meta.location().extract_from(content.as_bytes());
+        let written = String::from_utf8_lossy(written);
+        assert!(
+            written.starts_with(tokens[0]),
+            "{written} vs {content_tokens}"
+        );
+        assert!(
+            written.ends_with(tokens[2]),
+            "{written} vs {content_tokens}"
+        );
+    }
+
+    // Every window of two k-grams has a selected k-gram, so selected k-grams are never more than two apart.
+    // The body has 8 tokens (`{ return a + b ; }`), so 6 k-grams.
+    assert!((3..=6).contains(&extract.len()), "{extract:?}");
+}
+
+#[test]
+fn winnowed_partial_match() {
+    crate::tracing::setup();
+
+    let original = r#"
+int checksum(const unsigned char *data, int len) {
+  int sum = 0;
+  for (int i = 0; i < len; i++) {
+    sum = (sum + data[i]) % 65521;
+  }
+  return sum;
+}
+"#;
+    // The loop is copied with different formatting and comments into a larger function.
+    let copied = r#"
+long process(const unsigned char *data, int len, long seed) {
+  long state = seed;
+  int sum = 0;
+  for (int i = 0; i < len; i++)
+  {
+    // keep it small
+    sum = (sum + data[i]) % 65521;
+  }
+  state ^= sum;
+  return state * 31;
+}
+"#;
+    let unrelated = "void greet(void) {\n  printf(\"hello, world\\n\");\n  fflush(stdout);\n}\n";
+
+    let opts = winnowed_only(Kind::Full, winnowing(5, 4));
+    let original = c99_tc3::Extractor::extract(&opts, original).expect("extract snippets");
+    let copied = c99_tc3::Extractor::extract(&opts, copied).expect("extract snippets");
+    let unrelated = c99_tc3::Extractor::extract(&opts, unrelated).expect("extract snippets");
+
+    let original = fingerprints(&original);
+    assert!(!original.is_disjoint(&fingerprints(&copied)));
+    assert!(original.is_disjoint(&fingerprints(&unrelated)));
+}
+
+#[test]
+fn winnowed_too_few_tokens() {
+    crate::tracing::setup();
+
+    let content = "int zero() { return 0; }";
+    let opts = winnowed_only(Kind::Full, winnowing(20, 4));
+    let extract = c99_tc3::Extractor::extract(&opts, content).expect("extract snippets");
+
+    assert_eq!(extract, Vec::new());
+}
+
+#[test]
+fn winnowed_not_default() {
+    crate::tracing::setup();
+
+    let content = "class Main { void run() { System.out.println(\"running\"); } }";
+    let opts = Options::new(Target::Function, Kinds::full(), Transforms::full());
+    let extract = java::Extractor::extract(&opts, content).expect("extract snippets");
+
+    assert!(!extract.is_empty(), "must have extracted snippets");
+    assert!(extract
+        .iter()
+        .all(|snippet| snippet.metadata().method() != Method::Winnowed));
+
+    let opts = opts.with_winnowing(Winnowing::default());
+    let extract = java::Extractor::extract(&opts, content).expect("extract snippets");
+    assert!(extract
+        .iter()
+        .any(|snippet| snippet.metadata().method() == Method::Winnowed));
+}
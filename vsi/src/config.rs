@@ -118,6 +118,21 @@ pub struct Scan {
     #[getset(get = "pub")]
     #[builder(default)]
     exclude_paths: Vec<PathBuf>,
+
+    /// Disable the fingerprint cache, even if a cache file is provided,
+    /// fingerprinting every file even if it is unchanged since the last scan.
+    #[clap(long)]
+    #[getset(get_copy = "pub")]
+    #[builder(default = false)]
+    no_cache: bool,
+
+    /// The file in which fingerprints are cached between scans.
+    ///
+    /// The cache is disabled unless a file is provided.
+    /// Fingerprints read from the cache are uploaded as-is, so the file should only be writable by the current user.
+    #[clap(long, env = "FOSSA_VSI_CACHE")]
+    #[builder(default, setter(strip_option, into))]
+    cache_file: Option<PathBuf>,
 }
 
 impl Scan {
@@ -126,4 +141,12 @@ impl Scan {
         let dir = self.dir.canonicalize().context("canonicalize target dir")?;
         Ok(Self { dir, ..self })
     }
+
+    /// The file in which fingerprints are cached between scans, or `None` if the cache is disabled.
+    pub fn cache(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+        self.cache_file.clone()
+    }
 }
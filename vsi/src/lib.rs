@@ -58,7 +58,10 @@ pub async fn run(
     info!("created scan: {id}");
 
     info!("scanning artifacts");
-    let opts = scan::Options::builder().root(scan.dir()).build();
+    let opts = scan::Options::builder()
+        .root(scan.dir())
+        .cache(scan.cache())
+        .build();
     let artifact_count = scan::artifacts(&client, &id, opts)
         .await
         .context("scan artifacts")?;
@@ -142,7 +142,10 @@ async fn main_partial(CmdPartial { scan, api, scan_id }: CmdPartial) -> Result<(
     debug!("running in partial mode");
 
     let client = Fossa::new(&api, &scan).context("create client")?;
-    let opts = scan::Options::builder().root(scan.dir()).build();
+    let opts = scan::Options::builder()
+        .root(scan.dir())
+        .cache(scan.cache())
+        .build();
     let id = scan::Id::from(scan_id);
 
     debug!("scanning partial artifacts into scan {id} with options: {opts:?}");
@@ -45,6 +45,11 @@ pub struct Options {
     #[builder(setter(into))]
     #[getset(get)]
     root: PathBuf,
+
+    /// The file in which fingerprints are cached between scans; if `None`, fingerprints are not cached.
+    #[builder(default)]
+    #[getset(get)]
+    cache: Option<PathBuf>,
 }
 
 /// An identifier indicating a specific scan. This is an opaque string.
@@ -1,19 +1,17 @@
 use std::{
-    io::BufReader,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 
-use cancel::Token;
+use archive::CancelToken;
 use defer_lite::defer;
-use fingerprint::fingerprint_stream;
-use log::{debug, info};
+use fingerprint::{cache::Cache, walk::Walker, KindSet};
+use log::{debug, info, warn};
 use num_format::{Locale, ToFormattedString};
-use rayon::prelude::*;
-use stable_eyre::{
-    eyre::{bail, eyre},
-    Result,
-};
+use stable_eyre::{eyre::bail, Result};
 use tokio::{sync::mpsc::Sender, task};
 
 const REPORT_TIMEOUT: Duration = Duration::from_secs(1);
@@ -25,7 +23,7 @@ use super::{Artifact, Options};
 /// Walking operations are run in parallel and rely on cooperative cancellation.
 /// This type ensures that walkers attached to `Context` are requested to cancel when `Context` is dropped.
 pub struct Context {
-    token: Arc<Token>,
+    token: CancelToken,
 }
 
 impl Drop for Context {
@@ -39,7 +37,7 @@ impl Context {
     /// Create a new walk context.
     pub fn new() -> Self {
         Self {
-            token: Arc::new(Token::new()),
+            token: CancelToken::new(),
         }
     }
 
@@ -68,56 +66,75 @@ impl Context {
 }
 
 /// The worker for `fs`, since directory walking and fingerprinting are currently synchronous operations.
-fn fs_worker(token: Arc<Token>, out: Sender<Artifact>, opts: Options) -> Result<usize> {
+fn fs_worker(token: CancelToken, out: Sender<Artifact>, opts: Options) -> Result<usize> {
     debug!("enter fs worker");
     defer! { debug!("exiting fs worker"); }
 
-    let mut produced = 0;
-    let mut last_report = Instant::now();
+    let reported = AtomicUsize::new(0);
+    let last_report = Mutex::new(Instant::now());
 
     use stable_eyre::eyre::Context;
 
-    archive::expand::walk(opts.root().clone().into(), Default::default())
-        // Collect and report in the iterator before it becomes parallel; iteration here is serial.
-        // Iterators are lazy so this still benefits from parallel operations.
-        .inspect(|_| {
-            produced += 1;
+    // The VSI backend only accepts the legacy fingerprint kinds.
+    let kinds = KindSet::legacy();
+
+    // Fingerprints of files that are unchanged since the last scan are reused from the cache.
+    let cache = opts
+        .cache()
+        .as_ref()
+        .map(|path| Cache::open_with(kinds, path));
+
+    // Share the token with the walker so that it stops expanding archives promptly once cancelled.
+    let walk_opts = archive::Options::builder().cancel(token.clone()).build();
+    let mut walker = Walker::new().archive_options(walk_opts).kinds(kinds);
+    if let Some(cache) = &cache {
+        walker = walker.cache(cache);
+    }
+
+    // Files are fingerprinted in parallel, so this is called concurrently.
+    let produced = walker.walk(opts.root(), |walked| -> Result<()> {
+        if token.is_cancelled() {
+            debug!("received cancellation signal, bailing");
+            bail!("cancellation requested");
+        }
+
+        // Archives that fail to expand are still fingerprinted, but their contents are not scanned.
+        for warning in walked.warnings() {
+            warn!("did not expand {:?}: {warning:?}", walked.path());
+        }
+
+        let discovered = reported.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Ok(mut last_report) = last_report.try_lock() {
             let now = Instant::now();
-            if now.duration_since(last_report) >= REPORT_TIMEOUT {
+            if now.duration_since(*last_report) >= REPORT_TIMEOUT {
                 info!(
                     "discovered {} fingerprints...",
-                    produced.to_formatted_string(&Locale::en)
+                    discovered.to_formatted_string(&Locale::en)
                 );
-                last_report = now;
-            }
-        })
-        // Rayon magic: turn this iterator into a parallel iterator, then generate each artifact in parallel.
-        .par_bridge()
-        .try_for_each(|entry| -> Result<()> {
-            let mut entry = entry?;
-            if token.check_cancel().is_err() {
-                debug!("received cancellation signal, bailing");
-                bail!("cancellation requested");
+                *last_report = now;
             }
+        }
 
-            // Fingerprint the file.
-            // Reading an [`Entry`] requires using [`Entry::open`], since its paths are tightly controlled.
-            // This prevents us from using `fingerprint` with a standard `Path`.
-            let mut file = BufReader::new(entry.open()?);
-            let combined = fingerprint_stream(&mut file)
-                .wrap_err_with(|| eyre!("fingerprint {:?}", entry.path()))?;
-
-            // Generate and send the artifact.
-            let artifact = Artifact(entry.into_path(), combined);
-            debug!("generated artifact: {artifact}");
-            out.blocking_send(artifact).context("send entry")?;
+        // Generate and send the artifact.
+        let (path, combined) = walked.into_parts();
+        let artifact = Artifact(path, combined);
+        debug!("generated artifact: {artifact}");
+        out.blocking_send(artifact).context("send entry")?;
 
-            Ok(())
-        })?;
+        Ok(())
+    })?;
 
     info!(
         "discovered {} fingerprints total",
         produced.to_formatted_string(&Locale::en)
     );
+
+    // Failing to save the cache only means the next scan is slower, so it doesn't fail the scan.
+    if let Some(cache) = cache {
+        if let Err(err) = cache.save() {
+            warn!("save fingerprint cache: {err:?}");
+        }
+    }
+
     Ok(produced)
 }
@@ -46,8 +46,12 @@ async fn dry_run_fingerprint() -> Result<()> {
     let expected_raw = include_str!("testdata/facebook-folly-Version.cpp");
     let expected_stripped = include_str!("testdata/facebook-folly-Version.cpp.stripped");
 
-    let (fp_raw, processed_raw) = processed.raw().to_owned();
-    let Some((fp_stripped, processed_stripped)) = processed.comment_stripped().to_owned() else {
+    let (fp_raw, processed_raw) = processed.raw().to_owned().into_parts();
+    let Some((fp_stripped, processed_stripped)) = processed
+        .comment_stripped()
+        .to_owned()
+        .map(|stripped| stripped.into_parts())
+    else {
         panic!("must have comment stripped")
     };
 